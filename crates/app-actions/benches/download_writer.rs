@@ -0,0 +1,79 @@
+//! Compares writing a download through [`DownloadFileWriter`] with writing every chunk
+//! straight to the file and hashing the file afterwards, the way the generic downloader used to.
+
+use std::path::Path;
+
+use app_actions::{blocklist::sha256_file, downloaders::DownloadFileWriter};
+use app_helpers::temp_dir::TempDir;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use tokio::{fs::File, io::AsyncWriteExt, runtime::Runtime};
+
+/// About what a chunk of a response body from the network is
+const CHUNK_SIZE: usize = 16 * 1024;
+
+const FILE_SIZES: &[usize] = &[8 * 1024 * 1024, 64 * 1024 * 1024];
+
+async fn write_buffered(path: &Path, chunks: &[Vec<u8>]) -> Option<String> {
+    let file = File::create(path).await.expect("Failed to create file");
+    let mut writer = DownloadFileWriter::new(file, true);
+
+    for chunk in chunks {
+        writer
+            .write_chunk(chunk)
+            .await
+            .expect("Failed to write chunk");
+    }
+
+    writer.finish().await.expect("Failed to finish file")
+}
+
+async fn write_then_hash(path: &Path, chunks: &[Vec<u8>]) -> Option<String> {
+    let mut file = File::create(path).await.expect("Failed to create file");
+
+    for chunk in chunks {
+        file.write_all(chunk).await.expect("Failed to write chunk");
+    }
+    file.flush().await.expect("Failed to flush file");
+    drop(file);
+
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .expect("Failed to join hashing task")
+        .ok()
+}
+
+fn bench_download_writer(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to start runtime");
+    let temp_dir = TempDir::absolute(std::env::temp_dir().join("downloader-hub-bench-writer"))
+        .expect("Failed to create temp dir");
+    let path = temp_dir.path().join("download.bin");
+
+    let mut group = c.benchmark_group("download_writer");
+    group.sample_size(10);
+
+    for size in FILE_SIZES {
+        #[allow(clippy::cast_possible_truncation)]
+        let chunks = (0..size / CHUNK_SIZE)
+            .map(|i| vec![i as u8; CHUNK_SIZE])
+            .collect::<Vec<_>>();
+
+        group.throughput(Throughput::Bytes(*size as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("buffered_streaming_hash", size),
+            &chunks,
+            |b, chunks| b.to_async(&runtime).iter(|| write_buffered(&path, chunks)),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("unbuffered_hash_after", size),
+            &chunks,
+            |b, chunks| b.to_async(&runtime).iter(|| write_then_hash(&path, chunks)),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_download_writer);
+criterion_main!(benches);
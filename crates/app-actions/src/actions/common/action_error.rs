@@ -1,3 +1,4 @@
+use app_errors::AppError;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -14,3 +15,12 @@ impl ActionError {
         matches!(self, Self::FailedAction(_))
     }
 }
+
+impl From<ActionError> for AppError {
+    fn from(err: ActionError) -> Self {
+        match err {
+            ActionError::FailedAction(e) => Self::Other(e.to_string()),
+            ActionError::JoinError(e) => e.into(),
+        }
+    }
+}
@@ -0,0 +1,355 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::temp_dir::TempDir;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{Action, ActionError, ActionRequest, ActionResult};
+use crate::common::request::Client;
+
+const DEFAULT_LANGUAGE: &str = "en";
+const TRANSLATE_BATCH_SIZE: usize = 50;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DownloadSubtitles;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DownloadSubtitlesOptions {
+    /// The URL of the video to get the subtitles for
+    pub url: Option<String>,
+    /// The language of the subtitles. Defaults to `en`
+    pub lang: Option<String>,
+    /// Language to translate from if there are no subtitles in `lang`. Defaults to `en`
+    pub source_lang: Option<String>,
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Action for DownloadSubtitles {
+    fn description(&self) -> &'static str {
+        "Download subtitles for a video URL as .srt files. If there are none in the requested \
+         language, they can be machine-translated from another one."
+    }
+
+    async fn can_run_for(&self, req: &ActionRequest) -> bool {
+        req.options::<DownloadSubtitlesOptions>()
+            .and_then(|x| x.url)
+            .is_some_and(|x| Url::parse(&x).is_ok())
+    }
+
+    /// The request file path is not used as the subtitles are downloaded from the `url` option.
+    ///
+    /// Options:
+    /// - `url`: The URL of the video.
+    /// - `lang`: The subtitle language. Defaults to `en`.
+    /// - `source-lang`: The language to translate from if `lang` is not available. Defaults to `en`.
+    async fn run(&self, request: &ActionRequest) -> Result<ActionResult, ActionError> {
+        let opts = request
+            .options::<DownloadSubtitlesOptions>()
+            .unwrap_or_default();
+
+        trace!(?opts, "Running download subtitles action");
+
+        let url = opts
+            .url
+            .as_deref()
+            .and_then(|x| Url::parse(x).ok())
+            .ok_or(DownloadSubtitlesError::NoUrl)?;
+        let lang = opts.lang.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+        let source_lang = opts.source_lang.as_deref().unwrap_or(DEFAULT_LANGUAGE);
+
+        let temp_dir = TempDir::in_tmp_with_prefix("downloader-hub.subtitles.")
+            .map_err(DownloadSubtitlesError::TempDir)?;
+
+        let subtitles = download_subtitles(&url, lang, &temp_dir).await?;
+
+        let (subtitles, translate_from) =
+            if subtitles.is_empty() && source_lang != lang && can_translate() {
+                debug!(
+                    ?lang,
+                    ?source_lang,
+                    "No subtitles found, trying to translate"
+                );
+
+                (
+                    download_subtitles(&url, source_lang, &temp_dir).await?,
+                    Some(source_lang),
+                )
+            } else {
+                (subtitles, None)
+            };
+
+        debug!(?subtitles, "Downloaded subtitles");
+
+        if subtitles.is_empty() {
+            return Err(DownloadSubtitlesError::NotFound(lang.to_string()).into());
+        }
+
+        let mut output_paths = vec![];
+        for subtitle in subtitles {
+            let Some(file_name) = subtitle.file_name() else {
+                continue;
+            };
+
+            let output_path = match translate_from {
+                Some(source_lang) => {
+                    let contents = fs::read_to_string(&subtitle)
+                        .await
+                        .map_err(DownloadSubtitlesError::Read)?;
+
+                    let translated = translate_srt(&contents, source_lang, lang).await?;
+
+                    let output_path =
+                        request
+                            .output_dir
+                            .join(translated_file_name(&subtitle, source_lang, lang));
+
+                    fs::write(&output_path, translated)
+                        .await
+                        .map_err(DownloadSubtitlesError::Write)?;
+
+                    output_path
+                }
+                None => {
+                    let output_path = request.output_dir.join(file_name);
+
+                    fs::copy(&subtitle, &output_path)
+                        .await
+                        .map_err(DownloadSubtitlesError::Write)?;
+
+                    output_path
+                }
+            };
+
+            output_paths.push(output_path);
+        }
+
+        Ok(ActionResult::paths(request, output_paths))
+    }
+}
+
+fn can_translate() -> bool {
+    Config::global().endpoint.translation_api_base_url.is_some()
+}
+
+async fn download_subtitles(
+    url: &Url,
+    lang: &str,
+    temp_dir: &TempDir,
+) -> Result<Vec<PathBuf>, DownloadSubtitlesError> {
+    let output_dir = temp_dir.path().join(lang);
+
+    fs::create_dir_all(&output_dir)
+        .await
+        .map_err(DownloadSubtitlesError::TempDir)?;
+
+    let mut cmd = Command::new(Config::global().dependency_paths.yt_dlp_path());
+    let cmd = cmd
+        .arg("--skip-download")
+        .arg("--write-subs")
+        .arg("--write-auto-subs")
+        .args(["--sub-langs", &format!("{lang},{lang}-.*")])
+        .args(["--convert-subs", "srt"])
+        .args(["--paths", &output_dir.to_string_lossy()])
+        .args(["--output", "%(title).100B [%(id)s].%(ext)s"])
+        .arg("--no-playlist")
+        .arg(url.as_str())
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running yt-dlp command");
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(DownloadSubtitlesError::YtDlpRun)?;
+
+    trace!(?output, "Command output");
+
+    if !output.status.success() {
+        return Err(DownloadSubtitlesError::YtDlpExited(
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    let mut iter = fs::read_dir(&output_dir)
+        .await
+        .map_err(DownloadSubtitlesError::TempDir)?;
+
+    let mut subtitles = vec![];
+    while let Ok(Some(entry)) = iter.next_entry().await {
+        let path = entry.path();
+
+        if path.extension().and_then(OsStr::to_str) == Some("srt") {
+            subtitles.push(path);
+        }
+    }
+    subtitles.sort();
+
+    Ok(subtitles)
+}
+
+/// `video [id].en.srt` -> `video [id].en-de.srt`
+fn translated_file_name(path: &Path, source_lang: &str, lang: &str) -> String {
+    let file_name = path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or("subtitles.srt");
+
+    let stem = file_name.strip_suffix(".srt").unwrap_or(file_name);
+    let stem = stem
+        .strip_suffix(&format!(".{source_lang}"))
+        .unwrap_or(stem);
+
+    format!("{stem}.{source_lang}-{lang}.srt")
+}
+
+#[derive(Debug)]
+struct SrtCue {
+    header: Vec<String>,
+    text: String,
+}
+
+/// Splits an SRT file into cues, keeping the index and timing lines as-is
+fn parse_srt(contents: &str) -> Vec<SrtCue> {
+    contents
+        .replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|x| !x.trim().is_empty())
+        .map(|block| {
+            let lines = block.trim_matches('\n').lines().collect::<Vec<_>>();
+            let timing_idx = lines.iter().position(|x| x.contains("-->"));
+            let split_at = timing_idx.map_or(0, |x| x + 1);
+
+            SrtCue {
+                header: lines[..split_at].iter().map(ToString::to_string).collect(),
+                text: lines[split_at..].join("\n"),
+            }
+        })
+        .collect()
+}
+
+async fn translate_srt(
+    contents: &str,
+    source_lang: &str,
+    lang: &str,
+) -> Result<String, DownloadSubtitlesError> {
+    let mut cues = parse_srt(contents);
+
+    for chunk in cues.chunks_mut(TRANSLATE_BATCH_SIZE) {
+        let texts = chunk.iter().map(|x| x.text.clone()).collect::<Vec<_>>();
+
+        let translated = translate(&texts, source_lang, lang).await?;
+
+        // The cues that would be left over can't be matched to the translations
+        if translated.len() != texts.len() {
+            return Err(DownloadSubtitlesError::Translate(format!(
+                "Got {got} translations for {sent} subtitles",
+                got = translated.len(),
+                sent = texts.len(),
+            )));
+        }
+
+        for (cue, text) in chunk.iter_mut().zip(translated) {
+            cue.text = text;
+        }
+    }
+
+    Ok(cues
+        .into_iter()
+        .map(|x| {
+            let mut block = x.header.join("\n");
+            block.push('\n');
+            block.push_str(&x.text);
+            block
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+        + "\n")
+}
+
+async fn translate(
+    texts: &[String],
+    source_lang: &str,
+    lang: &str,
+) -> Result<Vec<String>, DownloadSubtitlesError> {
+    #[derive(Debug, Serialize)]
+    struct TranslateRequest<'a> {
+        q: &'a [String],
+        source: &'a str,
+        target: &'a str,
+        format: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        api_key: Option<&'a str>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct TranslateResponse {
+        translated_text: Vec<String>,
+    }
+
+    let endpoint = &Config::global().endpoint;
+
+    let url = endpoint
+        .translation_api_url("translate")
+        .ok_or(DownloadSubtitlesError::NoTranslationApi)?;
+
+    trace!(?url, texts = ?texts.len(), "Translating subtitles");
+
+    let resp = Client::base()
+        .map_err(DownloadSubtitlesError::Translate)?
+        .post(url)
+        .json(&TranslateRequest {
+            q: texts,
+            source: source_lang,
+            target: lang,
+            format: "text",
+            api_key: endpoint.translation_api_key.as_deref(),
+        })
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| DownloadSubtitlesError::Translate(e.to_string()))?
+        .json::<TranslateResponse>()
+        .await
+        .map_err(|e| DownloadSubtitlesError::Translate(e.to_string()))?;
+
+    Ok(resp.translated_text)
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadSubtitlesError {
+    #[error("No URL given. Please specify the video URL (eg. <code>url=https://...</code>).")]
+    NoUrl,
+    #[error("No subtitles found for language {0:?}")]
+    NotFound(String),
+    #[error("Translation API URL not set")]
+    NoTranslationApi,
+    #[error("Error while translating subtitles: {0}")]
+    Translate(String),
+    #[error("Error while creating temp dir: {0:?}")]
+    TempDir(std::io::Error),
+    #[error("Error while reading subtitles: {0:?}")]
+    Read(std::io::Error),
+    #[error("Error while writing subtitles: {0:?}")]
+    Write(std::io::Error),
+    #[error("Error while running yt-dlp: {0}")]
+    YtDlpRun(std::io::Error),
+    #[error("yt-dlp exited with error code {0:?}: {1}")]
+    YtDlpExited(Option<i32>, String),
+}
+
+impl From<DownloadSubtitlesError> for ActionError {
+    fn from(val: DownloadSubtitlesError) -> Self {
+        Self::FailedAction(val.into())
+    }
+}
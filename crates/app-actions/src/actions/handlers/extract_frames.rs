@@ -0,0 +1,317 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::{
+    file_type::{infer_file_type, mime},
+    temp_dir::TempDir,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace};
+
+use super::{Action, ActionError, ActionRequest, ActionResult};
+
+const DEFAULT_ZIP_OVER: usize = 10;
+const MAX_FRAMES: usize = 1000;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ExtractFrames;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExtractFramesOptions {
+    /// Extract one frame every `interval` seconds
+    pub interval: Option<f64>,
+    /// Extract frames at the given timestamps.
+    /// Timestamps are separated by commas and can be
+    /// either seconds (eg. `12.5`) or `[hh:]mm:ss[.ms]` (eg. `1:02:03.5`)
+    pub timestamps: Option<FrameTimestamps>,
+    /// Zip the frames into a single archive if there are more than this many
+    pub zip_over: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum FrameTimestamps {
+    Single(f64),
+    List(Vec<f64>),
+    Text(String),
+}
+impl FrameTimestamps {
+    fn to_seconds(&self) -> Result<Vec<f64>, ExtractFramesError> {
+        match self {
+            Self::Single(x) => Ok(vec![*x]),
+            Self::List(x) => Ok(x.clone()),
+            Self::Text(x) => x
+                .split(',')
+                .map(str::trim)
+                .filter(|x| !x.is_empty())
+                .map(|x| {
+                    parse_timestamp(x).ok_or_else(|| ExtractFramesError::InvalidTimestamp(x.into()))
+                })
+                .collect(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Action for ExtractFrames {
+    fn description(&self) -> &'static str {
+        "Extract frames from a video as PNGs, either at an interval or at specific timestamps."
+    }
+
+    async fn can_run_for(&self, req: &ActionRequest) -> bool {
+        let file_mime = {
+            let file_path = req.file_path.clone();
+            tokio::task::spawn_blocking(move || infer_file_type(&file_path)).await
+        };
+
+        let file_mime = match file_mime {
+            Ok(Ok(x)) => x,
+            _ => return false,
+        };
+
+        matches!(file_mime.type_(), mime::VIDEO)
+    }
+
+    /// Options:
+    /// - `interval`: Extract one frame every `interval` seconds.
+    /// - `timestamps`: Comma separated list of timestamps to extract frames at.
+    /// - `zip-over`: Zip the frames if there are more than this many. Defaults to 10.
+    async fn run(&self, request: &ActionRequest) -> Result<ActionResult, ActionError> {
+        let opts = request
+            .options::<ExtractFramesOptions>()
+            .unwrap_or_default();
+
+        trace!(?opts, "Running extract frames action");
+
+        let temp_dir = TempDir::in_tmp_with_prefix("downloader-hub.extract-frames.")
+            .map_err(ExtractFramesError::TempDirCreate)?;
+
+        let frames = match (&opts.timestamps, opts.interval) {
+            (Some(timestamps), _) => {
+                extract_frames_at(
+                    &request.file_path,
+                    temp_dir.path(),
+                    &timestamps.to_seconds()?,
+                )
+                .await?
+            }
+            (None, Some(interval)) if interval > 0.0 => {
+                extract_frames_every(&request.file_path, temp_dir.path(), interval).await?
+            }
+            _ => {
+                return Err(ExtractFramesError::NoSelection(self.name()).into());
+            }
+        };
+
+        debug!(frames = ?frames.len(), "Extracted frames");
+
+        if frames.is_empty() {
+            return Ok(ActionResult::paths(request, Vec::<PathBuf>::new()));
+        }
+
+        let zip_over = opts.zip_over.unwrap_or(DEFAULT_ZIP_OVER);
+
+        if frames.len() > zip_over {
+            let zip_path = request.output_dir.join(format!(
+                "{}.frames.zip",
+                request
+                    .file_path
+                    .file_stem()
+                    .and_then(OsStr::to_str)
+                    .unwrap_or("video")
+            ));
+
+            trace!(?zip_path, "Zipping frames");
+
+            zip_files(frames, zip_path.clone()).await?;
+
+            return Ok(ActionResult::path(request, zip_path));
+        }
+
+        let mut output_paths = vec![];
+        for frame in frames {
+            let Some(file_name) = frame.file_name() else {
+                continue;
+            };
+
+            let output_path = request.output_dir.join(file_name);
+
+            fs::copy(&frame, &output_path)
+                .await
+                .map_err(ExtractFramesError::Copy)?;
+
+            output_paths.push(output_path);
+        }
+
+        Ok(ActionResult::paths(request, output_paths))
+    }
+}
+
+async fn extract_frames_every(
+    file_path: &Path,
+    output_dir: &Path,
+    interval: f64,
+) -> Result<Vec<PathBuf>, ExtractFramesError> {
+    let file_stem = file_stem(file_path);
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let cmd = cmd
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-vf", &format!("fps=1/{interval}")])
+        .args(["-frames:v", &MAX_FRAMES.to_string()])
+        .arg(output_dir.join(format!("{file_stem}.frame.%05d.png")));
+
+    run_ffmpeg(cmd).await?;
+
+    list_frames(output_dir).await
+}
+
+async fn extract_frames_at(
+    file_path: &Path,
+    output_dir: &Path,
+    timestamps: &[f64],
+) -> Result<Vec<PathBuf>, ExtractFramesError> {
+    let file_stem = file_stem(file_path);
+
+    for (i, timestamp) in timestamps.iter().take(MAX_FRAMES).enumerate() {
+        let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+        let cmd = cmd
+            .arg("-y")
+            .arg("-hide_banner")
+            .args(["-loglevel", "error"])
+            .args(["-ss", &timestamp.to_string()])
+            .arg("-i")
+            .arg(file_path)
+            .args(["-frames:v", "1"])
+            .arg(output_dir.join(format!("{file_stem}.frame.{:05}.png", i + 1)));
+
+        run_ffmpeg(cmd).await?;
+    }
+
+    list_frames(output_dir).await
+}
+
+async fn run_ffmpeg(cmd: &mut Command) -> Result<(), ExtractFramesError> {
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(ExtractFramesError::FfmpegRun)?;
+
+    trace!(?output, "Command output");
+
+    if !output.status.success() {
+        return Err(ExtractFramesError::FfmpegExited(
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn list_frames(dir: &Path) -> Result<Vec<PathBuf>, ExtractFramesError> {
+    let mut iter = fs::read_dir(dir)
+        .await
+        .map_err(ExtractFramesError::TempDirRead)?;
+
+    let mut frames = vec![];
+    while let Ok(Some(entry)) = iter.next_entry().await {
+        let path = entry.path();
+
+        if path.extension().and_then(OsStr::to_str) == Some("png") {
+            frames.push(path);
+        }
+    }
+    frames.sort();
+
+    Ok(frames)
+}
+
+async fn zip_files(files: Vec<PathBuf>, zip_path: PathBuf) -> Result<(), ExtractFramesError> {
+    tokio::task::spawn_blocking(move || -> Result<(), ExtractFramesError> {
+        let zip_file = std::fs::File::create(&zip_path).map_err(ExtractFramesError::Zip)?;
+        let mut zip = zip::ZipWriter::new(zip_file);
+        // PNGs are already compressed so there's no point in doing it again
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Stored);
+
+        for file in files {
+            let Some(file_name) = file.file_name().and_then(OsStr::to_str) else {
+                continue;
+            };
+
+            zip.start_file(file_name, options)
+                .map_err(|e| ExtractFramesError::Zip(e.into()))?;
+
+            let mut f = std::fs::File::open(&file).map_err(ExtractFramesError::Zip)?;
+            std::io::copy(&mut f, &mut zip).map_err(ExtractFramesError::Zip)?;
+        }
+
+        zip.finish()
+            .map_err(|e| ExtractFramesError::Zip(e.into()))?;
+
+        Ok(())
+    })
+    .await
+    .map_err(ExtractFramesError::Join)?
+}
+
+fn file_stem(file_path: &Path) -> String {
+    file_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or("video")
+        .to_string()
+}
+
+fn parse_timestamp(s: &str) -> Option<f64> {
+    s.split(':')
+        .map(|x| x.trim().parse::<f64>().ok())
+        .try_fold(0.0, |acc: f64, x| Some(acc.mul_add(60.0, x?)))
+        .filter(|x| *x >= 0.0)
+}
+
+#[derive(Debug, Error)]
+pub enum ExtractFramesError {
+    #[error(
+        "No frames selected. Please specify either an interval or timestamps (eg. <code>/act \
+         {0} interval=5</code> or <code>/act {0} timestamps=1,2:30,5.5</code>)."
+    )]
+    NoSelection(&'static str),
+    #[error("Invalid timestamp: {0:?}")]
+    InvalidTimestamp(String),
+    #[error("Error while creating temp dir: {0:?}")]
+    TempDirCreate(std::io::Error),
+    #[error("Error while reading temp dir: {0:?}")]
+    TempDirRead(std::io::Error),
+    #[error("Error while copying frame: {0:?}")]
+    Copy(std::io::Error),
+    #[error("Error while zipping frames: {0:?}")]
+    Zip(std::io::Error),
+    #[error("Error while running ffmpeg: {0}")]
+    FfmpegRun(std::io::Error),
+    #[error("ffmpeg exited with error code {0:?}: {1}")]
+    FfmpegExited(Option<i32>, String),
+    #[error(transparent)]
+    Join(tokio::task::JoinError),
+}
+
+impl From<ExtractFramesError> for ActionError {
+    fn from(val: ExtractFramesError) -> Self {
+        Self::FailedAction(val.into())
+    }
+}
@@ -1,14 +1,20 @@
 pub mod compact_media;
+pub mod download_subtitles;
+pub mod extract_frames;
 pub mod file_rename_to_id;
 pub mod ocr_image;
 pub mod remove_background;
+pub mod split_chapters;
 pub mod split_scenes;
 
-use std::sync::Arc;
+use std::{
+    sync::{Arc, PoisonError, RwLock},
+    time::Duration,
+};
 
 use futures::{stream::FuturesUnordered, StreamExt};
 use once_cell::sync::Lazy;
-use tracing::trace;
+use tracing::{debug, trace};
 
 use super::{Action, ActionError, ActionRequest, ActionResult};
 
@@ -16,34 +22,75 @@ pub type ActionEntry = Arc<dyn Action>;
 
 pub static ALL_ACTIONS: Lazy<Vec<ActionEntry>> = Lazy::new(all_actions);
 
-pub static AVAILABLE_ACTIONS: Lazy<Vec<ActionEntry>> = Lazy::new(available_actions);
+/// The actions that passed their `can_run` check the last time they were checked
+static AVAILABLE_ACTIONS: Lazy<RwLock<Vec<ActionEntry>>> = Lazy::new(Default::default);
 
 fn all_actions() -> Vec<ActionEntry> {
     vec![
         Arc::new(file_rename_to_id::RenameToId),
         Arc::new(split_scenes::SplitScenes),
+        Arc::new(split_chapters::SplitChapters),
         Arc::new(compact_media::CompactMedia),
+        Arc::new(extract_frames::ExtractFrames),
         Arc::new(ocr_image::OcrImage),
+        Arc::new(download_subtitles::DownloadSubtitles),
         Arc::new(remove_background::RemoveBackground),
     ]
 }
 
+/// The actions that can run, as of the last check.
+///
+/// Empty until [`init_available_actions`] is called.
 #[must_use]
-fn available_actions() -> Vec<ActionEntry> {
-    futures::executor::block_on(async move {
-        all_actions()
-            .into_iter()
-            .map(|x| async move {
-                trace!(?x, "Checking if action can run");
-                let can_run = x.can_run().await;
-                trace!(?x, can_run, "Checked if action can run");
-                (x, can_run)
-            })
-            .collect::<FuturesUnordered<_>>()
-            .collect::<Vec<_>>()
-            .await
-    })
-    .into_iter()
-    .filter_map(|(x, y)| if y { Some(x) } else { None })
-    .collect()
+pub fn available_actions() -> Vec<ActionEntry> {
+    AVAILABLE_ACTIONS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+/// Checks which actions can run and keeps rechecking them
+/// every `refresh_interval` in the background,
+/// so tools that get installed or go away later are picked up.
+pub async fn init_available_actions(refresh_interval: Duration) {
+    refresh_available_actions().await;
+
+    tokio::task::spawn(async move {
+        let mut interval = tokio::time::interval(refresh_interval);
+        // The first tick completes immediately and the actions were just checked
+        interval.tick().await;
+
+        loop {
+            interval.tick().await;
+            refresh_available_actions().await;
+        }
+    });
+}
+
+/// Checks which actions can run and caches the result
+pub async fn refresh_available_actions() -> Vec<ActionEntry> {
+    let available = ALL_ACTIONS
+        .iter()
+        .map(|x| async move {
+            trace!(?x, "Checking if action can run");
+            let can_run = x.can_run().await;
+            trace!(?x, can_run, "Checked if action can run");
+            (x, can_run)
+        })
+        .collect::<FuturesUnordered<_>>()
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .filter_map(|(x, y)| if y { Some(x.clone()) } else { None })
+        .collect::<Vec<_>>();
+
+    let names = available.iter().map(|x| x.name()).collect::<Vec<_>>();
+    debug!(actions = ?names, "Refreshed available actions");
+
+    AVAILABLE_ACTIONS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone_from(&available);
+
+    available
 }
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::{collections::HashSet, fmt::Write};
 
 use app_config::Config;
 use app_helpers::file_type::{infer_file_type, mime};
@@ -102,7 +102,7 @@ impl Action for OcrImage {
 
             let mut text = "Available OCR engines:\n".to_string();
             for handler in handlers {
-                text.push_str(&format!("  - {}\n", handler));
+                let _ = writeln!(text, "  - {}", handler);
             }
             text = text.trim().to_string();
 
@@ -0,0 +1,265 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::{
+    ffprobe::{self, FfProbeResult},
+    file_name::sanitize_file_name,
+    file_type::{infer_file_type, mime},
+};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::process::Command;
+use tracing::{debug, trace};
+
+use super::{Action, ActionError, ActionRequest, ActionResult};
+
+const MAX_CHAPTER_NAME_LENGTH: usize = 64;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SplitChapters;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct SplitChaptersOptions {
+    /// Text to parse chapter timestamps from (eg. the video description)
+    pub description: Option<String>,
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Action for SplitChapters {
+    fn description(&self) -> &'static str {
+        "Split a video or audio file into separate files for each chapter."
+    }
+
+    async fn can_run_for(&self, req: &ActionRequest) -> bool {
+        let file_mime = {
+            let file_path = req.file_path.clone();
+            tokio::task::spawn_blocking(move || infer_file_type(&file_path)).await
+        };
+
+        let file_mime = match file_mime {
+            Ok(Ok(x)) => x,
+            _ => return false,
+        };
+
+        matches!(file_mime.type_(), mime::VIDEO | mime::AUDIO)
+    }
+
+    /// Options:
+    /// - `description`: Text containing chapter timestamps (eg. `00:00 Intro`).
+    ///   Used if the file doesn't contain any chapter metadata.
+    async fn run(&self, request: &ActionRequest) -> Result<ActionResult, ActionError> {
+        let opts = request
+            .options::<SplitChaptersOptions>()
+            .unwrap_or_default();
+
+        trace!(?opts, "Running split chapters action");
+
+        let media_info = ffprobe::ffprobe_async(&request.file_path)
+            .await
+            .map_err(SplitChaptersError::FfProbe)?;
+
+        let chapters = get_chapters(&media_info, opts.description.as_deref());
+
+        debug!(?chapters, "Got chapters");
+
+        if chapters.len() < 2 {
+            return Err(SplitChaptersError::NoChapters.into());
+        }
+
+        let mut paths = vec![];
+        for (i, chapter) in chapters.iter().enumerate() {
+            let output_path =
+                chapter_file_path(&request.file_path, &request.output_dir, i, chapter);
+
+            split_chapter(&request.file_path, &output_path, chapter).await?;
+
+            paths.push(output_path);
+        }
+
+        Ok(ActionResult::paths(request, paths))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChapterInfo {
+    pub title: String,
+    pub start: f64,
+    pub end: Option<f64>,
+}
+
+fn get_chapters(media_info: &FfProbeResult, description: Option<&str>) -> Vec<ChapterInfo> {
+    let from_metadata = media_info
+        .chapters
+        .iter()
+        .filter_map(|x| {
+            Some(ChapterInfo {
+                title: x.title().unwrap_or_default().to_string(),
+                start: x.start_seconds()?,
+                end: x.end_seconds(),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    if !from_metadata.is_empty() {
+        return from_metadata;
+    }
+
+    let description = description.map(ToString::to_string).or_else(|| {
+        let tags = media_info.format.tags.as_ref()?;
+
+        ["description", "DESCRIPTION", "comment", "COMMENT"]
+            .iter()
+            .find_map(|x| tags.extra.get(*x).and_then(|x| x.as_str()))
+            .map(ToString::to_string)
+    });
+
+    let Some(description) = description else {
+        return vec![];
+    };
+
+    let duration = media_info.format.get_duration().map(|x| x.as_secs_f64());
+
+    parse_chapters_from_text(&description, duration)
+}
+
+static TIMESTAMP_LINE_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^\s*[\[(]?(?P<timestamp>(?:\d{1,2}:)?\d{1,2}:\d{2})[\])]?\s*(?:[-–—:|.]\s*)?(?P<title>.*)$",
+    )
+    .expect("Invalid regex")
+});
+
+/// Parses chapters from lines like `00:00 Intro` or `1:02:03 - Outro`.
+#[must_use]
+pub fn parse_chapters_from_text(text: &str, duration: Option<f64>) -> Vec<ChapterInfo> {
+    let mut chapters = text
+        .lines()
+        .filter_map(|line| {
+            let captures = TIMESTAMP_LINE_MATCH.captures(line)?;
+            let start = captures
+                .name("timestamp")?
+                .as_str()
+                .split(':')
+                .map(|x| x.parse::<f64>().ok())
+                .try_fold(0.0, |acc: f64, x| Some(acc.mul_add(60.0, x?)))?;
+            let title = captures
+                .name("title")
+                .map(|x| x.as_str().trim().to_string())
+                .unwrap_or_default();
+
+            Some(ChapterInfo {
+                title,
+                start,
+                end: None,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    chapters.sort_by(|a, b| a.start.total_cmp(&b.start));
+    chapters.dedup_by(|a, b| (a.start - b.start).abs() < f64::EPSILON);
+
+    let starts = chapters.iter().map(|x| x.start).skip(1).collect::<Vec<_>>();
+    for (chapter, end) in chapters
+        .iter_mut()
+        .zip(starts.into_iter().map(Some).chain([duration]))
+    {
+        chapter.end = end;
+    }
+
+    chapters
+}
+
+fn chapter_file_path(
+    file_path: &Path,
+    output_dir: &Path,
+    index: usize,
+    chapter: &ChapterInfo,
+) -> PathBuf {
+    let file_stem = file_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or("chapter");
+
+    let chapter_name = sanitize_file_name(&chapter.title, MAX_CHAPTER_NAME_LENGTH);
+
+    let mut file_name = format!("{file_stem}.{:02}", index + 1);
+    if !chapter_name.is_empty() {
+        file_name.push('.');
+        file_name.push_str(&chapter_name);
+    }
+    if let Some(ext) = file_path.extension().and_then(OsStr::to_str) {
+        file_name.push('.');
+        file_name.push_str(ext);
+    }
+
+    output_dir.join(file_name)
+}
+
+async fn split_chapter(
+    file_path: &Path,
+    output_path: &Path,
+    chapter: &ChapterInfo,
+) -> Result<(), SplitChaptersError> {
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let mut cmd = cmd
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .args(["-ss", &chapter.start.to_string()])
+        .arg("-i")
+        .arg(file_path);
+
+    if let Some(end) = chapter.end {
+        cmd = cmd.args(["-t", &(end - chapter.start).max(0.0).to_string()]);
+    }
+
+    let cmd = cmd
+        .args(["-map", "0"])
+        .args(["-c", "copy"])
+        .args(["-map_chapters", "-1"])
+        .arg(output_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(SplitChaptersError::FfmpegRun)?;
+
+    trace!(?output, "Command output");
+
+    if !output.status.success() {
+        return Err(SplitChaptersError::FfmpegExited(
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum SplitChaptersError {
+    #[error(transparent)]
+    FfProbe(ffprobe::FfProbeError),
+    #[error(
+        "No chapters found. The file has no chapter metadata and no timestamps were found in the \
+         description."
+    )]
+    NoChapters,
+    #[error("Error while running ffmpeg: {0}")]
+    FfmpegRun(std::io::Error),
+    #[error("ffmpeg exited with error code {0:?}: {1}")]
+    FfmpegExited(Option<i32>, String),
+}
+
+impl From<SplitChaptersError> for ActionError {
+    fn from(val: SplitChaptersError) -> Self {
+        Self::FailedAction(val.into())
+    }
+}
@@ -8,7 +8,7 @@ pub use common::{
     action_request::{ActionOptions, ActionRequest},
     action_result::{ActionResult, ActionResultData},
 };
-pub use handlers::AVAILABLE_ACTIONS;
+pub use handlers::{available_actions, init_available_actions};
 
 #[async_trait::async_trait]
 #[typetag::serde(tag = "$action")]
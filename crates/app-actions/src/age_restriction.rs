@@ -0,0 +1,17 @@
+pub use app_config::common::AgeRestrictedPolicy;
+use app_config::Config;
+
+/// Key of the downloader option that says whether age-restricted content
+/// may be downloaded for the request.
+///
+/// If it's not set, the global [`AgeRestrictedPolicy`] is applied as if the requester isn't the owner.
+pub const ALLOW_AGE_RESTRICTED_OPTION: &str = "allow-age-restricted";
+
+/// Whether the global [`AgeRestrictedPolicy`] lets the requester download age-restricted content
+#[must_use]
+pub fn allows_age_restricted(is_owner: bool) -> bool {
+    Config::global()
+        .download
+        .age_restricted_policy
+        .allows(is_owner)
+}
@@ -0,0 +1,313 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_errors::{AppError, UserInputError};
+use image::imageops::FilterType;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{debug, trace, warn};
+
+/// Errors of downloads that were deleted because they matched the blocklist start with this
+pub const BLOCKED_ERROR_PREFIX: &str = "Blocked:";
+
+/// Side of the grayscale image the perceptual hash is calculated from
+const PHASH_IMAGE_SIZE: u32 = 32;
+/// Side of the block of lowest frequencies that make up the perceptual hash
+const PHASH_HASH_SIZE: usize = 8;
+
+#[must_use]
+pub const fn is_blocked_error(error: &AppError) -> bool {
+    matches!(error, AppError::UserInput(UserInputError::Blocked(_)))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum BlocklistEntry {
+    Sha256(String),
+    PerceptualHash(u64),
+}
+impl BlocklistEntry {
+    fn parse(line: &str) -> Option<Self> {
+        let (kind, hash) = line.split_once(':')?;
+        let hash = hash.trim();
+
+        match kind.trim().to_lowercase().as_str() {
+            "sha256" => Some(Self::Sha256(hash.to_lowercase())),
+            "phash" => u64::from_str_radix(hash, 16).ok().map(Self::PerceptualHash),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for BlocklistEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sha256(x) => write!(f, "sha256:{x}"),
+            Self::PerceptualHash(x) => write!(f, "phash:{x:016x}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockedFile {
+    pub path: PathBuf,
+    /// The blocklist entry the file matched
+    pub matched: String,
+}
+impl fmt::Display for BlockedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{BLOCKED_ERROR_PREFIX} the downloaded file matched a blocklist entry ({matched}) and \
+             was deleted",
+            matched = self.matched,
+        )
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AuditEvent<'a> {
+    time: String,
+    event: &'static str,
+    url: &'a str,
+    #[serde(flatten)]
+    file: &'a BlockedFile,
+}
+
+/// Deletes the file if it matches the configured blocklist
+/// and records the event to the audit log.
+///
+/// The SHA-256 is calculated from the file if it isn't passed in.
+///
+/// Returns `None` if the file isn't blocked.
+pub async fn enforce_blocklist(
+    file_path: &Path,
+    source_url: &str,
+    known_sha256: Option<&str>,
+) -> Result<Option<BlockedFile>, BlocklistError> {
+    let Some(matched) = find_match(file_path, known_sha256).await? else {
+        return Ok(None);
+    };
+
+    let blocked = BlockedFile {
+        path: file_path.to_path_buf(),
+        matched: matched.to_string(),
+    };
+
+    warn!(?blocked, url = ?source_url, "Downloaded file matched the blocklist, deleting it");
+
+    fs::remove_file(file_path)
+        .await
+        .map_err(BlocklistError::Delete)?;
+
+    if let Err(e) = record_audit_event(&blocked, source_url).await {
+        warn!(?e, "Failed to record blocklist audit event");
+    }
+
+    Ok(Some(blocked))
+}
+
+/// Whether any of the files with the given SHA-256 hashes might be on the blocklist,
+/// eg. before sending files again that were uploaded before the blocklist changed.
+///
+/// Perceptual hashes can't be checked without the files,
+/// so every file might be blocked if the blocklist has any.
+pub async fn may_block_sha256<S>(hashes: &[S]) -> Result<bool, BlocklistError>
+where
+    S: AsRef<str> + Sync,
+{
+    let entries = read_entries().await?;
+
+    let blocked = entries.iter().any(|x| match x {
+        BlocklistEntry::Sha256(x) => hashes.iter().any(|h| h.as_ref().eq_ignore_ascii_case(x)),
+        BlocklistEntry::PerceptualHash(_) => true,
+    });
+
+    Ok(blocked)
+}
+
+async fn find_match(
+    file_path: &Path,
+    known_sha256: Option<&str>,
+) -> Result<Option<BlocklistEntry>, BlocklistError> {
+    let entries = read_entries().await?;
+
+    if entries.is_empty() {
+        return Ok(None);
+    }
+
+    trace!(
+        count = entries.len(),
+        ?file_path,
+        "Checking file against blocklist"
+    );
+
+    let wants_phash = entries
+        .iter()
+        .any(|x| matches!(x, BlocklistEntry::PerceptualHash(_)));
+
+    let path = file_path.to_path_buf();
+    let known_sha256 = known_sha256.map(str::to_lowercase);
+    let (sha256, phash) = tokio::task::spawn_blocking(move || {
+        let sha256 = match known_sha256 {
+            Some(x) => x,
+            None => sha256_file(&path)?,
+        };
+        // Files that aren't images just don't have a perceptual hash
+        let phash = wants_phash.then(|| perceptual_hash(&path).ok()).flatten();
+
+        Ok::<_, std::io::Error>((sha256, phash))
+    })
+    .await?
+    .map_err(BlocklistError::Read)?;
+
+    debug!(?sha256, phash = ?phash.map(|x| format!("{x:016x}")), "Hashed file");
+
+    let max_distance = Config::global().blocklist.phash_max_distance();
+
+    let matched = entries.into_iter().find(|x| match x {
+        BlocklistEntry::Sha256(x) => *x == sha256,
+        BlocklistEntry::PerceptualHash(x) => {
+            phash.is_some_and(|phash| (phash ^ x).count_ones() <= max_distance)
+        }
+    });
+
+    Ok(matched)
+}
+
+async fn read_entries() -> Result<Vec<BlocklistEntry>, BlocklistError> {
+    let Some(blocklist_file) = &Config::global().blocklist.blocklist_file else {
+        return Ok(vec![]);
+    };
+
+    let contents = fs::read_to_string(blocklist_file)
+        .await
+        .map_err(BlocklistError::ReadList)?;
+
+    let entries = contents
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty() && !x.starts_with('#'))
+        .filter_map(|x| {
+            let entry = BlocklistEntry::parse(x);
+
+            if entry.is_none() {
+                warn!(line = ?x, "Invalid blocklist entry");
+            }
+
+            entry
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+async fn record_audit_event(blocked: &BlockedFile, source_url: &str) -> Result<(), BlocklistError> {
+    let Some(audit_log) = &Config::global().blocklist.blocklist_audit_log else {
+        return Ok(());
+    };
+
+    let event = AuditEvent {
+        time: chrono::Utc::now().to_rfc3339(),
+        event: "blocked-file-deleted",
+        url: source_url,
+        file: blocked,
+    };
+
+    let mut line = serde_json::to_string(&event).map_err(BlocklistError::Serialize)?;
+    line.push('\n');
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(audit_log)
+        .await
+        .map_err(BlocklistError::AuditLog)?;
+
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(BlocklistError::AuditLog)
+}
+
+/// Lowercase hex SHA-256 of the file contents
+pub fn sha256_file(file_path: &Path) -> std::io::Result<String> {
+    let input = std::fs::File::open(file_path)?;
+    let mut reader = std::io::BufReader::new(input);
+    let mut hasher = Sha256::new();
+
+    std::io::copy(&mut reader, &mut hasher)?;
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// DCT-based perceptual hash of an image.
+///
+/// Bits are set for the lowest frequencies that are above their median,
+/// so resized or recompressed copies of an image end up only a few bits apart.
+fn perceptual_hash(file_path: &Path) -> Result<u64, image::ImageError> {
+    let img = image::open(file_path)?
+        .resize_exact(PHASH_IMAGE_SIZE, PHASH_IMAGE_SIZE, FilterType::Triangle)
+        .to_luma8();
+
+    let pixels = img.pixels().map(|x| f64::from(x.0[0])).collect::<Vec<_>>();
+
+    let mut coefficients = Vec::with_capacity(PHASH_HASH_SIZE * PHASH_HASH_SIZE);
+    for u in 0..PHASH_HASH_SIZE {
+        for v in 0..PHASH_HASH_SIZE {
+            coefficients.push(dct_coefficient(&pixels, u, v));
+        }
+    }
+
+    // The first coefficient is the average brightness, which would skew the median
+    let mut sorted = coefficients[1..].to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let median = sorted[sorted.len() / 2];
+
+    let hash = coefficients
+        .iter()
+        .enumerate()
+        .filter(|(_, x)| **x > median)
+        .fold(0_u64, |hash, (i, _)| hash | (1 << i));
+
+    Ok(hash)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn dct_coefficient(pixels: &[f64], u: usize, v: usize) -> f64 {
+    use std::f64::consts::PI;
+
+    let size = PHASH_IMAGE_SIZE as usize;
+    let n = f64::from(PHASH_IMAGE_SIZE);
+    let mut sum = 0.0;
+
+    for y in 0..size {
+        for x in 0..size {
+            sum += pixels[y * size + x]
+                * (2.0_f64.mul_add(x as f64, 1.0) * u as f64 * PI / (2.0 * n)).cos()
+                * (2.0_f64.mul_add(y as f64, 1.0) * v as f64 * PI / (2.0 * n)).cos();
+        }
+    }
+
+    sum
+}
+
+#[derive(Debug, Error)]
+pub enum BlocklistError {
+    #[error("Failed to read blocklist file: {0:?}")]
+    ReadList(std::io::Error),
+    #[error("Failed to read file: {0:?}")]
+    Read(std::io::Error),
+    #[error("Failed to delete blocked file: {0:?}")]
+    Delete(std::io::Error),
+    #[error("Failed to write to audit log: {0:?}")]
+    AuditLog(std::io::Error),
+    #[error("Failed to serialize audit event: {0:?}")]
+    Serialize(serde_json::Error),
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
@@ -0,0 +1,131 @@
+use std::str::FromStr;
+
+use app_config::Config;
+use app_errors::AppError;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+use crate::{
+    downloaders::DownloaderOptions,
+    extractors::{self, ExtractedInfo},
+};
+
+/// Key of the downloader option with the [`CollectionOptions`]
+/// for URLs of playlists, channels or profiles
+pub const COLLECTION_OPTION: &str = "collection";
+
+/// How many items of a collection are downloaded if it's not set
+const DEFAULT_MAX_ITEMS: usize = 50;
+
+/// The most items of a collection that are downloaded if the config doesn't say
+const DEFAULT_MAX_COLLECTION_ITEMS: usize = 200;
+
+/// Which items of a playlist, channel or profile are downloaded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CollectionOptions {
+    /// Defaults to 50, and can't be more than the configured `max_collection_items`
+    #[serde(default)]
+    pub max_items: Option<usize>,
+    /// Only items published on or after this day.
+    /// Items without a date are kept, since it's not known whether they're in the range.
+    #[serde(default)]
+    pub date_after: Option<NaiveDate>,
+    /// Only items published on or before this day
+    #[serde(default)]
+    pub date_before: Option<NaiveDate>,
+    #[serde(default)]
+    pub order: CollectionOrder,
+}
+impl CollectionOptions {
+    #[must_use]
+    pub fn from_options(options: &DownloaderOptions) -> Option<Self> {
+        serde_json::from_value(options.get(COLLECTION_OPTION)?.clone()).ok()
+    }
+
+    /// Never more than the configured `max_collection_items`
+    #[must_use]
+    pub fn max_items(&self) -> usize {
+        let limit = Config::global()
+            .download
+            .max_collection_items
+            .unwrap_or(DEFAULT_MAX_COLLECTION_ITEMS);
+
+        self.max_items.unwrap_or(DEFAULT_MAX_ITEMS).min(limit)
+    }
+
+    /// Keeps only the wanted items of the collection, in the wanted order
+    pub fn apply(&self, info: &mut ExtractedInfo) {
+        info.urls.retain(|x| {
+            x.upload_date.is_none_or(|date| {
+                self.date_after.is_none_or(|after| date >= after)
+                    && self.date_before.is_none_or(|before| date <= before)
+            })
+        });
+
+        // Items without a date end up last
+        match self.order {
+            CollectionOrder::Source => {}
+            CollectionOrder::Newest => info.urls.sort_by_key(|x| std::cmp::Reverse(x.upload_date)),
+            CollectionOrder::Oldest => info
+                .urls
+                .sort_by_key(|x| (x.upload_date.is_none(), x.upload_date)),
+        }
+
+        info.urls.truncate(self.max_items());
+    }
+}
+impl From<CollectionOptions> for serde_json::Value {
+    fn from(value: CollectionOptions) -> Self {
+        serde_json::to_value(value).expect("Failed to serialize collection options")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CollectionOrder {
+    /// The order the site lists them in
+    #[default]
+    Source,
+    Newest,
+    Oldest,
+}
+impl FromStr for CollectionOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "source" => Ok(Self::Source),
+            "newest" => Ok(Self::Newest),
+            "oldest" => Ok(Self::Oldest),
+            s => Err(format!(
+                "Invalid collection order: {s:?}. Expected `source`, `newest` or `oldest`"
+            )),
+        }
+    }
+}
+
+/// The URLs of the wanted items if the URL is of a playlist, channel or profile,
+/// so they can be downloaded as requests of their own.
+///
+/// `None` if the URL isn't of a collection.
+pub async fn expand_collection(
+    url: &Url,
+    options: &CollectionOptions,
+) -> Result<Option<Vec<String>>, AppError> {
+    let mut info = extractors::extract_info(&url.into()).await?;
+
+    if !info.is_collection() {
+        return Ok(None);
+    }
+
+    options.apply(&mut info);
+
+    debug!(count = info.urls.len(), "Expanded collection");
+
+    Ok(Some(
+        info.urls.iter().map(|x| x.url.url().to_string()).collect(),
+    ))
+}
@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static LINE_BREAK_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)<br\s*/?>").expect("Invalid regex"));
+static PARAGRAPH_BREAK_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)</p>\s*<p[^>]*>").expect("Invalid regex"));
+static TAG_MATCH: Lazy<Regex> = Lazy::new(|| Regex::new(r"<[^>]+>").expect("Invalid regex"));
+
+/// Get the `<meta>` tags of a HTML document as a map of
+/// `property`/`name` -> `content`.
+///
+/// Only the first occurrence of each key is kept.
+pub fn meta_tags(html: &str) -> Result<HashMap<String, String>, String> {
+    let dom = tl::parse(html, tl::ParserOptions::default())
+        .map_err(|e| format!("Failed to parse html: {:?}", e))?;
+    let parser = dom.parser();
+
+    let mut tags = HashMap::new();
+
+    let Some(meta_tags) = dom.query_selector("meta") else {
+        return Ok(tags);
+    };
+
+    for tag in meta_tags
+        .filter_map(|x| x.get(parser))
+        .filter_map(|x| x.as_tag())
+    {
+        let attrs = tag.attributes();
+
+        let key = attrs
+            .get("property")
+            .flatten()
+            .or_else(|| attrs.get("name").flatten())
+            .map(|x| x.as_utf8_str().to_string());
+
+        let content = attrs
+            .get("content")
+            .flatten()
+            .map(|x| decode_html_entities(&x.as_utf8_str()));
+
+        if let (Some(key), Some(content)) = (key, content) {
+            tags.entry(key).or_insert(content);
+        }
+    }
+
+    Ok(tags)
+}
+
+/// Get the URL of the JSON oEmbed endpoint a HTML document links to with
+/// `<link rel="alternate" type="application/json+oembed" href="...">`
+pub fn oembed_url(html: &str) -> Result<Option<String>, String> {
+    let dom = tl::parse(html, tl::ParserOptions::default())
+        .map_err(|e| format!("Failed to parse html: {:?}", e))?;
+    let parser = dom.parser();
+
+    let Some(link_tags) = dom.query_selector("link") else {
+        return Ok(None);
+    };
+
+    let url = link_tags
+        .filter_map(|x| x.get(parser))
+        .filter_map(|x| x.as_tag())
+        .find_map(|tag| {
+            let attrs = tag.attributes();
+
+            let is_oembed = attrs.get("type").flatten().is_some_and(|x| {
+                x.as_utf8_str()
+                    .eq_ignore_ascii_case("application/json+oembed")
+            });
+
+            if !is_oembed {
+                return None;
+            }
+
+            attrs
+                .get("href")
+                .flatten()
+                .map(|x| decode_html_entities(&x.as_utf8_str()))
+        });
+
+    Ok(url)
+}
+
+/// Turn a HTML snippet (eg. the content of a post) into plain text,
+/// keeping the line and paragraph breaks
+#[must_use]
+pub fn html_to_text(html: &str) -> String {
+    let text = PARAGRAPH_BREAK_MATCH.replace_all(html, "\n\n");
+    let text = LINE_BREAK_MATCH.replace_all(&text, "\n");
+    let text = TAG_MATCH.replace_all(&text, "");
+
+    decode_html_entities(text.trim())
+}
+
+fn decode_html_entities(s: &str) -> String {
+    s.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
@@ -1,2 +1,4 @@
+pub mod html;
+pub mod quality;
 pub mod request;
 pub mod url;
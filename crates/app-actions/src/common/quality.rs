@@ -0,0 +1,29 @@
+use app_config::Config;
+
+/// Picks the rendition that best matches the configured maximum video height.
+///
+/// Takes the tallest rendition that fits the limit, or the smallest one
+/// if none of them fit. Without a limit the tallest rendition is used.
+pub fn pick_by_height<T, I>(renditions: I) -> Option<T>
+where
+    I: IntoIterator<Item = (u32, T)>,
+{
+    let mut renditions = renditions.into_iter().collect::<Vec<_>>();
+    renditions.sort_by_key(|(height, _)| *height);
+
+    let max_height = Config::global().download.max_video_height;
+
+    let idx = match max_height {
+        Some(max_height) => renditions
+            .iter()
+            .rposition(|(height, _)| *height <= max_height)
+            .unwrap_or(0),
+        None => renditions.len().checked_sub(1)?,
+    };
+
+    if idx >= renditions.len() {
+        return None;
+    }
+
+    Some(renditions.swap_remove(idx).1)
+}
@@ -0,0 +1,190 @@
+use std::{
+    fmt::Write,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use app_config::Config;
+use http::HeaderValue;
+use once_cell::sync::Lazy;
+use reqwest::cookie::{CookieStore, Jar};
+use tracing::{debug, warn};
+use url::Url;
+
+/// The cookies from the cookies file and the domain cookies in the config
+static CONFIGURED_COOKIES: Lazy<Vec<NetscapeCookie>> = Lazy::new(load_configured_cookies);
+
+/// A new jar with the configured cookies for each client, if there are any.
+///
+/// Cookies set by responses are only kept for the client that got them,
+/// so they never end up in the requests made for someone else.
+pub fn cookie_jar() -> Option<Arc<Jar>> {
+    if !Config::global().cookies.is_configured() {
+        return None;
+    }
+
+    let jar = Jar::default();
+
+    for cookie in CONFIGURED_COOKIES.iter() {
+        if let Some(url) = cookie.url() {
+            jar.add_cookie_str(&cookie.to_set_cookie(), &url);
+        }
+    }
+
+    Some(Arc::new(jar))
+}
+
+/// The `Cookie` header with the configured cookies for the URL, for tools that only take headers
+pub fn cookie_header(url: &Url) -> Option<HeaderValue> {
+    cookie_jar()?.cookies(url)
+}
+
+/// Lines of a Netscape `cookies.txt` file (eg. for `yt-dlp --cookies`) with the configured cookies
+pub fn configured_cookie_lines() -> Vec<String> {
+    CONFIGURED_COOKIES
+        .iter()
+        .map(NetscapeCookie::to_line)
+        .collect()
+}
+
+/// A single line of a Netscape `cookies.txt` file
+#[derive(Debug, Clone)]
+struct NetscapeCookie {
+    domain: String,
+    include_subdomains: bool,
+    path: String,
+    secure: bool,
+    http_only: bool,
+    /// Unix timestamp, `0` for session cookies
+    expires: u64,
+    name: String,
+    value: String,
+}
+impl NetscapeCookie {
+    fn parse_line(line: &str) -> Option<Self> {
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        let (line, http_only) = match line.strip_prefix("#HttpOnly_") {
+            Some(x) => (x, true),
+            None if line.starts_with('#') || line.trim().is_empty() => return None,
+            None => (line, false),
+        };
+
+        let mut fields = line.split('\t');
+        let domain = fields.next()?.to_string();
+        let include_subdomains = fields.next()?.eq_ignore_ascii_case("TRUE");
+        let path = fields.next()?.to_string();
+        let secure = fields.next()?.eq_ignore_ascii_case("TRUE");
+        let expires = fields.next()?.parse().unwrap_or_default();
+        let name = fields.next()?.to_string();
+        let value = fields.next().unwrap_or_default().to_string();
+
+        Some(Self {
+            domain,
+            include_subdomains,
+            path,
+            secure,
+            http_only,
+            expires,
+            name,
+            value,
+        })
+    }
+
+    const fn is_expired(&self, now: u64) -> bool {
+        self.expires != 0 && self.expires < now
+    }
+
+    fn host(&self) -> &str {
+        self.domain.trim_start_matches('.')
+    }
+
+    fn url(&self) -> Option<Url> {
+        let scheme = if self.secure { "https" } else { "http" };
+
+        Url::parse(&format!(
+            "{scheme}://{host}{path}",
+            host = self.host(),
+            path = self.path
+        ))
+        .ok()
+    }
+
+    fn to_set_cookie(&self) -> String {
+        let mut cookie = format!("{}={}; Path={}", self.name, self.value, self.path);
+
+        if self.include_subdomains {
+            let _ = write!(cookie, "; Domain={}", self.host());
+        }
+        if self.secure {
+            cookie += "; Secure";
+        }
+        if self.http_only {
+            cookie += "; HttpOnly";
+        }
+
+        cookie
+    }
+
+    fn to_line(&self) -> String {
+        let bool_str = |x: bool| if x { "TRUE" } else { "FALSE" };
+
+        format!(
+            "{prefix}{domain}\t{include_subdomains}\t{path}\t{secure}\t{expires}\t{name}\t{value}",
+            prefix = if self.http_only { "#HttpOnly_" } else { "" },
+            domain = self.domain,
+            include_subdomains = bool_str(self.include_subdomains),
+            path = self.path,
+            secure = bool_str(self.secure),
+            expires = self.expires,
+            name = self.name,
+            value = self.value,
+        )
+    }
+}
+
+fn load_configured_cookies() -> Vec<NetscapeCookie> {
+    let config = &Config::global().cookies;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default();
+
+    let mut cookies = vec![];
+
+    if let Some(path) = config.cookies_file.as_ref() {
+        match std::fs::read_to_string(path) {
+            Ok(contents) => {
+                cookies.extend(
+                    contents
+                        .lines()
+                        .filter_map(NetscapeCookie::parse_line)
+                        .filter(|x| !x.is_expired(now)),
+                );
+            }
+            Err(e) => warn!(?e, ?path, "Failed to read cookies file"),
+        }
+    }
+
+    for domain_cookie in &config.domain_cookies {
+        cookies.extend(
+            domain_cookie
+                .cookies
+                .iter()
+                .map(|(name, value)| NetscapeCookie {
+                    domain: format!(".{}", domain_cookie.domain),
+                    include_subdomains: true,
+                    path: "/".to_string(),
+                    secure: false,
+                    http_only: false,
+                    expires: 0,
+                    name: name.clone(),
+                    value: value.clone(),
+                }),
+        );
+    }
+
+    debug!(count = cookies.len(), "Loaded configured cookies");
+
+    cookies
+}
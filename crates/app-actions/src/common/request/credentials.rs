@@ -0,0 +1,87 @@
+use app_config::{credentials::Credentials, Config};
+use app_helpers::{domain::host_matches, encoding::to_base64_standard};
+use http::{header, HeaderMap, HeaderName, HeaderValue};
+use tracing::warn;
+use url::Url;
+
+/// The credentials configured for the URL's domain, if there are any
+#[must_use]
+pub fn credentials_for(url: &Url) -> Option<&'static Credentials> {
+    Config::global()
+        .credentials
+        .domain_credentials
+        .iter()
+        .find(|x| host_matches(&x.domain, url))
+        .map(|x| &x.credentials)
+}
+
+/// Adds the credentials configured for the URL's domain to the headers.
+///
+/// Headers that are already set are kept, so the ones given with the request win.
+pub fn add_credentials(url: &Url, headers: &mut HeaderMap) {
+    let Some(credentials) = credentials_for(url) else {
+        return;
+    };
+
+    let Some((name, value)) = credentials_header(credentials) else {
+        warn!(host = ?url.host_str(), "Configured credentials can't be sent as a header");
+        return;
+    };
+
+    if !headers.contains_key(&name) {
+        headers.insert(name, value);
+    }
+}
+
+/// The name of the header [`add_credentials`] adds for the URL, if it adds any
+#[must_use]
+pub fn credentials_header_name(url: &Url) -> Option<HeaderName> {
+    credentials_for(url)
+        .and_then(credentials_header)
+        .map(|(name, _)| name)
+}
+
+/// Hides the secrets of the credentials configured for the URL's domain, eg. in logged commands
+#[must_use]
+pub fn redact_credentials(text: &str, url: &Url) -> String {
+    let Some(credentials) = credentials_for(url) else {
+        return text.to_string();
+    };
+
+    let secrets = match credentials {
+        Credentials::Basic { username, password } => vec![
+            to_base64_standard(format!("{username}:{password}")),
+            password.clone(),
+        ],
+        Credentials::Bearer(token) => vec![token.clone()],
+        Credentials::Header { value, .. } => vec![value.clone()],
+    };
+
+    secrets
+        .iter()
+        .filter(|x| !x.is_empty())
+        .fold(text.to_string(), |text, secret| {
+            text.replace(secret.as_str(), "<redacted>")
+        })
+}
+
+fn credentials_header(credentials: &Credentials) -> Option<(HeaderName, HeaderValue)> {
+    let (name, value) = match credentials {
+        Credentials::Basic { username, password } => (
+            header::AUTHORIZATION,
+            format!(
+                "Basic {}",
+                to_base64_standard(format!("{username}:{password}"))
+            ),
+        ),
+        Credentials::Bearer(token) => (header::AUTHORIZATION, format!("Bearer {token}")),
+        Credentials::Header { name, value } => {
+            (HeaderName::try_from(name.as_str()).ok()?, value.clone())
+        }
+    };
+
+    let mut value = HeaderValue::try_from(value).ok()?;
+    value.set_sensitive(true);
+
+    Some((name, value))
+}
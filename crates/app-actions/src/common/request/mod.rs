@@ -1,7 +1,22 @@
+mod cookies;
+mod credentials;
+mod quota;
+
 use std::time::Duration;
 
+use app_config::Config;
+use app_helpers::domain::host_matches;
+use http::{header, Method, StatusCode};
+use reqwest::{redirect::Policy, Proxy, Request, Response};
 pub use reqwest::{Client as RequestClient, ClientBuilder as RequestClientBuilder, RequestBuilder};
+use url::Url;
 
+use self::cookies::cookie_jar;
+pub use self::{
+    cookies::{configured_cookie_lines, cookie_header},
+    credentials::{add_credentials, credentials_for, credentials_header_name, redact_credentials},
+    quota::{record_quota, send_with_quota, wait_for_quota, wait_for_quota_reset},
+};
 use super::url::UrlWithMeta;
 
 pub const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like \
@@ -9,6 +24,9 @@ pub const USER_AGENT: &str = "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36
 
 const DEFAULT_TIMEOUT_SECS: u64 = 30;
 
+/// Same as the default redirect policy of reqwest
+const MAX_REDIRECTS: usize = 10;
+
 pub struct Client;
 
 impl Client {
@@ -18,10 +36,21 @@ impl Client {
             .map_err(|e| format!("Failed to create client: {:?}", e))
     }
 
-    pub fn base_with_url(url: &UrlWithMeta) -> Result<RequestBuilder, String> {
-        let mut builder = Self::base()?.request(url.method().clone(), url.url().as_str());
+    /// The request for the URL with its headers and the configured credentials.
+    ///
+    /// The client doesn't follow redirects, so the request should be sent with [`send_following_redirects`].
+    pub fn base_with_url(
+        url: &UrlWithMeta,
+        downloader: &'static str,
+    ) -> Result<RequestBuilder, String> {
+        let mut builder = Self::without_redirects(Some(downloader))
+            .map_err(|e| format!("Failed to create client: {:?}", e))?
+            .request(url.method().clone(), url.url().as_str());
 
-        for (k, v) in url.headers() {
+        let mut headers = url.headers().clone();
+        add_credentials(url.url(), &mut headers);
+
+        for (k, v) in &headers {
             builder = builder.header(k, v);
         }
 
@@ -29,8 +58,129 @@ impl Client {
     }
 
     pub fn builder() -> RequestClientBuilder {
-        RequestClient::builder()
+        Self::builder_with_proxy(None)
+    }
+
+    /// Same as [`Client::base`], but requests also go through the proxy set for the downloader
+    pub fn for_downloader(downloader: &'static str) -> Result<RequestClient, String> {
+        Self::builder_with_proxy(Some(downloader))
+            .build()
+            .map_err(|e| format!("Failed to create client: {:?}", e))
+    }
+
+    /// Client that leaves following redirects to [`send_following_redirects`]
+    pub fn without_redirects(
+        downloader: Option<&'static str>,
+    ) -> Result<RequestClient, reqwest::Error> {
+        Self::builder_with_proxy(downloader)
+            .redirect(Policy::none())
+            .build()
+    }
+
+    fn builder_with_proxy(downloader: Option<&'static str>) -> RequestClientBuilder {
+        let mut builder = RequestClient::builder()
             .user_agent(USER_AGENT)
-            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS))
+            .timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+
+        if let Some(jar) = cookie_jar() {
+            builder = builder.cookie_provider(jar);
+        }
+
+        // Setting any proxy turns off the ones from the environment
+        if !Config::global().proxy.is_configured() {
+            return builder;
+        }
+
+        builder.proxy(Proxy::custom(move |url| proxy_for(downloader, url)))
+    }
+}
+
+/// The proxy to send the request for `url` through.
+///
+/// Domain proxies come first, then the downloader's, then the global one.
+#[must_use]
+pub fn proxy_for(downloader: Option<&str>, url: &Url) -> Option<Url> {
+    let config = &Config::global().proxy;
+
+    config
+        .domain_proxies
+        .iter()
+        .find(|x| host_matches(&x.key, url))
+        .map(|x| &x.proxy)
+        .or_else(|| {
+            downloader.map_or(config.proxy.as_ref(), |downloader| {
+                config.downloader_proxy(downloader)
+            })
+        })
+        .cloned()
+}
+
+/// Sends the request and follows its redirects,
+/// leaving out the credentials once they lead to another host.
+///
+/// Reqwest only does that for the `Authorization` and `Cookie` headers,
+/// but credentials can also be configured as any other header.
+/// The client shouldn't follow redirects by itself, eg. one from [`Client::without_redirects`].
+///
+/// The last redirect is returned as is if there are too many of them.
+pub async fn send_following_redirects(
+    client: &RequestClient,
+    mut request: Request,
+) -> Result<Response, reqwest::Error> {
+    let first_url = request.url().clone();
+    let credentials_header = credentials_header_name(&first_url);
+
+    let mut redirects = 0;
+    loop {
+        // Requests with a streamed body can't be sent again
+        let next_request = request.try_clone();
+        let response = client.execute(request).await?;
+
+        let is_redirect = matches!(
+            response.status(),
+            StatusCode::MOVED_PERMANENTLY
+                | StatusCode::FOUND
+                | StatusCode::SEE_OTHER
+                | StatusCode::TEMPORARY_REDIRECT
+                | StatusCode::PERMANENT_REDIRECT
+        );
+        let location = response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| response.url().join(x).ok())
+            .filter(|_| is_redirect && redirects < MAX_REDIRECTS);
+
+        let (Some(location), Some(mut next_request)) = (location, next_request) else {
+            return Ok(response);
+        };
+        redirects += 1;
+
+        // Browsers turn these into `GET`s, and so does reqwest
+        if matches!(
+            response.status(),
+            StatusCode::MOVED_PERMANENTLY | StatusCode::FOUND | StatusCode::SEE_OTHER
+        ) && *next_request.method() != Method::HEAD
+        {
+            *next_request.method_mut() = Method::GET;
+            *next_request.body_mut() = None;
+            next_request.headers_mut().remove(header::CONTENT_TYPE);
+            next_request.headers_mut().remove(header::CONTENT_LENGTH);
+        }
+
+        let same_host = location.host_str() == first_url.host_str()
+            && location.port_or_known_default() == first_url.port_or_known_default();
+        if !same_host {
+            let headers = next_request.headers_mut();
+            headers.remove(header::AUTHORIZATION);
+            headers.remove(header::COOKIE);
+            headers.remove(header::PROXY_AUTHORIZATION);
+            if let Some(name) = &credentials_header {
+                headers.remove(name);
+            }
+        }
+
+        *next_request.url_mut() = location;
+        request = next_request;
     }
 }
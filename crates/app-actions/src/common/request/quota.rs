@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use chrono::DateTime;
+use http::{header, HeaderMap, StatusCode};
+use once_cell::sync::Lazy;
+use reqwest::{RequestBuilder, Response};
+use thiserror::Error;
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{add_credentials, credentials_for, send_following_redirects, Client};
+use crate::downloaders::helpers::domain_limit;
+
+/// Waiting longer than this for the quota to reset is failing instead,
+/// since whoever asked for the download is probably gone by then
+const MAX_QUOTA_WAIT: Duration = Duration::from_mins(5);
+
+/// Used when the site says to stop, but not for how long
+const DEFAULT_BLOCK: Duration = Duration::from_mins(1);
+
+/// How many requests are left, by host.
+/// Shared by every request so concurrent tasks don't use up the quota between them.
+static QUOTAS: Lazy<Mutex<HashMap<String, HostQuota>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Header pairs of how many requests are left and when that resets, as the sites send them
+const QUOTA_HEADERS: &[(&str, &str)] = &[
+    // Reddit, Mastodon and most others
+    ("x-ratelimit-remaining", "x-ratelimit-reset"),
+    // Imgur has separate quotas for the app and the user
+    ("x-ratelimit-clientremaining", "x-ratelimit-clientreset"),
+    ("x-ratelimit-userremaining", "x-ratelimit-userreset"),
+];
+
+#[derive(Debug, Error)]
+pub enum QuotaError {
+    #[error("Rate limit for {host} is used up, it resets in {reset_in:?}")]
+    Exhausted { host: String, reset_in: Duration },
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+}
+
+#[derive(Debug, Default)]
+struct HostQuota {
+    /// Requests left until `reset_at`
+    remaining: Option<f64>,
+    reset_at: Option<Instant>,
+    /// Set by `429 Too Many Requests` and `Retry-After`
+    blocked_until: Option<Instant>,
+}
+impl HostQuota {
+    /// How long to wait before the next request can be sent
+    fn wait_time(&mut self, now: Instant) -> Option<Duration> {
+        if self.reset_at.is_some_and(|x| x <= now) {
+            self.remaining = None;
+            self.reset_at = None;
+        }
+        if self.blocked_until.is_some_and(|x| x <= now) {
+            self.blocked_until = None;
+        }
+
+        if let Some(blocked_until) = self.blocked_until {
+            return Some(blocked_until - now);
+        }
+
+        match (self.remaining, self.reset_at) {
+            (Some(remaining), Some(reset_at)) if remaining < 1.0 => Some(reset_at - now),
+            _ => None,
+        }
+    }
+}
+
+/// Sends the request once the host's quota and the domain's rate limit allow it
+/// and remembers the quota the response reports.
+///
+/// The credentials configured for the host are added to it.
+pub async fn send_with_quota(request: RequestBuilder) -> Result<Response, QuotaError> {
+    let (client, request) = request.build_split();
+    let mut request = request?;
+
+    let url = request.url().clone();
+    add_credentials(&url, request.headers_mut());
+
+    wait_for_quota(request.url()).await?;
+    domain_limit::wait_for_rate_limit(&url).await;
+
+    // The client would send the credentials along to wherever the request is redirected
+    let response = if credentials_for(&url).is_some() {
+        send_following_redirects(&Client::without_redirects(None)?, request).await?
+    } else {
+        client.execute(request).await?
+    };
+    record_quota(response.url(), response.status(), response.headers());
+
+    Ok(response)
+}
+
+/// Waits until the host's quota has room for another request and takes it up
+pub async fn wait_for_quota(url: &Url) -> Result<(), QuotaError> {
+    wait(url, true).await
+}
+
+/// Waits until the host's quota has room for another request, without taking it up.
+/// For when the request itself is sent by something that waits for the quota on its own.
+pub async fn wait_for_quota_reset(url: &Url) -> Result<(), QuotaError> {
+    wait(url, false).await
+}
+
+#[allow(clippy::significant_drop_tightening)]
+async fn wait(url: &Url, take: bool) -> Result<(), QuotaError> {
+    let Some(host) = url.host_str() else {
+        return Ok(());
+    };
+
+    loop {
+        let wait = {
+            let mut quotas = QUOTAS.lock().expect("Quota lock poisoned");
+            let Some(quota) = quotas.get_mut(host) else {
+                return Ok(());
+            };
+
+            match quota.wait_time(Instant::now()) {
+                Some(wait) => wait,
+                None => {
+                    // Taken up now, so the requests waiting with this one don't all go at once
+                    if let Some(remaining) = quota.remaining.as_mut().filter(|_| take) {
+                        *remaining -= 1.0;
+                    }
+
+                    return Ok(());
+                }
+            }
+        };
+
+        if wait > MAX_QUOTA_WAIT {
+            return Err(QuotaError::Exhausted {
+                host: host.to_string(),
+                reset_in: wait,
+            });
+        }
+
+        debug!(?host, ?wait, "Waiting for rate limit quota");
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Remembers the quota the host reports in the response headers
+#[allow(clippy::significant_drop_tightening)]
+pub fn record_quota(url: &Url, status: StatusCode, headers: &HeaderMap) {
+    let Some(host) = url.host_str() else {
+        return;
+    };
+
+    let now = Instant::now();
+
+    // The lowest of the quotas the host reports is the one that runs out first
+    let reported = QUOTA_HEADERS
+        .iter()
+        .filter_map(|(remaining, reset)| {
+            let remaining = header_str(headers, remaining)?.parse::<f64>().ok()?;
+            let reset_at = header_str(headers, reset).and_then(|x| parse_reset(x, now));
+
+            Some((remaining, reset_at))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0));
+
+    let blocked_for = (status == StatusCode::TOO_MANY_REQUESTS).then(|| {
+        header_str(headers, header::RETRY_AFTER.as_str())
+            .and_then(|x| parse_reset(x, now))
+            .or_else(|| reported.and_then(|x| x.1))
+            .map_or(DEFAULT_BLOCK, |x| x.saturating_duration_since(now))
+    });
+
+    if reported.is_none() && blocked_for.is_none() {
+        return;
+    }
+
+    let mut quotas = QUOTAS.lock().expect("Quota lock poisoned");
+    let quota = quotas.entry(host.to_string()).or_default();
+
+    if let Some((remaining, reset_at)) = reported {
+        quota.remaining = Some(remaining);
+        quota.reset_at = reset_at;
+    }
+
+    if let Some(blocked_for) = blocked_for {
+        debug!(?host, ?blocked_for, "Rate limited by host");
+        quota.blocked_until = Some(now + blocked_for);
+    }
+
+    trace!(?host, ?quota, "Recorded rate limit quota");
+}
+
+fn header_str<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get(name)
+        .and_then(|x| x.to_str().ok())
+        .map(str::trim)
+}
+
+/// Sites say when the quota resets in different ways:
+/// seconds until then (Reddit, `Retry-After`), a UNIX timestamp (Imgur),
+/// an ISO 8601 date (Mastodon) or an HTTP date (`Retry-After`)
+fn parse_reset(value: &str, now: Instant) -> Option<Instant> {
+    /// Anything bigger is a timestamp, not a number of seconds
+    const MIN_TIMESTAMP: f64 = 1_000_000_000.0;
+
+    let unix_now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+
+    let reset_in = if let Ok(x) = value.parse::<f64>() {
+        if x >= MIN_TIMESTAMP {
+            Duration::try_from_secs_f64(x)
+                .ok()?
+                .saturating_sub(unix_now)
+        } else {
+            Duration::try_from_secs_f64(x).ok()?
+        }
+    } else {
+        let at = DateTime::parse_from_rfc3339(value)
+            .or_else(|_| DateTime::parse_from_rfc2822(value))
+            .ok()?;
+        let at = Duration::from_secs(u64::try_from(at.timestamp()).ok()?);
+
+        at.saturating_sub(unix_now)
+    };
+
+    Some(now + reset_in)
+}
@@ -41,6 +41,13 @@ impl UrlWithMeta {
         self
     }
 
+    /// Same headers and method, different URL
+    #[must_use]
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+
     #[must_use]
     pub fn with_method<T>(mut self, method: T) -> Self
     where
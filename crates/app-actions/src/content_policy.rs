@@ -0,0 +1,111 @@
+use std::{ffi::OsStr, path::Path};
+
+pub use app_config::common::ContentType;
+use app_config::Config;
+use app_errors::{AppError, UserInputError};
+use tracing::{debug, warn};
+
+use crate::{
+    downloaders::{DownloadRequest, DownloadResult, DownloaderOptions, DownloaderReturn},
+    format_choice::SCREENSHOT_OPTION,
+};
+
+/// Key of the downloader option with the only [`ContentType`]s that may be downloaded for the request.
+///
+/// If it's not set, the global `allowed_content_types` config is used.
+pub const ALLOWED_CONTENT_TYPES_OPTION: &str = "allowed-content-types";
+
+/// Key of the downloader option extractors set when the site says what kind of media the URL is
+pub const CONTENT_TYPE_OPTION: &str = "content-type";
+
+/// The only content types that may be downloaded, or `None` if anything may be
+#[must_use]
+pub fn allowed_content_types(options: &DownloaderOptions) -> Option<Vec<ContentType>> {
+    let allowed = options
+        .get(ALLOWED_CONTENT_TYPES_OPTION)
+        .and_then(|x| serde_json::from_value::<Vec<ContentType>>(x.clone()).ok())
+        .unwrap_or_else(|| Config::global().download.allowed_content_types.clone());
+
+    if allowed.is_empty() {
+        None
+    } else {
+        Some(allowed)
+    }
+}
+
+/// What the extractor says the URL is, or what its extension does if it doesn't
+#[must_use]
+pub fn reported_content_type(request: &DownloadRequest) -> Option<ContentType> {
+    if let Some(content_type) = request.downloader_option::<ContentType>(CONTENT_TYPE_OPTION) {
+        return Some(content_type);
+    }
+
+    if request
+        .downloader_option::<bool>(SCREENSHOT_OPTION)
+        .unwrap_or_default()
+    {
+        return Some(ContentType::Image);
+    }
+
+    Path::new(request.url.url().path())
+        .extension()
+        .and_then(OsStr::to_str)
+        .and_then(ContentType::from_extension)
+}
+
+/// Refuses the download before it starts if the extractor already says it's not allowed
+pub(crate) fn check_reported(
+    request: &DownloadRequest,
+    allowed: &[ContentType],
+) -> Result<(), AppError> {
+    match reported_content_type(request) {
+        Some(content_type) if !allowed.contains(&content_type) => {
+            debug!(url = ?request.url.url().as_str(), ?content_type, "Refusing content type");
+
+            Err(refusal(content_type, allowed))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Deletes the downloaded file if it turns out not to be allowed,
+/// for what the extractor couldn't tell before it was downloaded
+pub(crate) async fn check_downloaded(
+    result: DownloadResult,
+    allowed: &[ContentType],
+) -> DownloaderReturn {
+    let path = result.path.clone();
+    let content_type = tokio::task::spawn_blocking(move || {
+        app_helpers::file_type::infer_file_type(&path).map_or(ContentType::Other, |x| {
+            ContentType::from_mime_type(x.essence_str())
+        })
+    })
+    .await?;
+
+    if allowed.contains(&content_type) {
+        return Ok(result);
+    }
+
+    debug!(path = ?result.path, ?content_type, "Refusing downloaded content type");
+
+    if let Err(e) = tokio::fs::remove_file(&result.path).await {
+        warn!(?e, path = ?result.path, "Failed to remove refused file");
+    }
+
+    Err(refusal(content_type, allowed))
+}
+
+/// `Only images and videos can be downloaded here, not audio`
+fn refusal(content_type: ContentType, allowed: &[ContentType]) -> AppError {
+    let allowed = allowed
+        .iter()
+        .map(|x| x.plural())
+        .collect::<Vec<_>>()
+        .join(" and ");
+
+    UserInputError::NotAllowed(format!(
+        "Only {allowed} can be downloaded here, not {}",
+        content_type.plural()
+    ))
+    .into()
+}
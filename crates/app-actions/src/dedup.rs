@@ -0,0 +1,178 @@
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use once_cell::sync::Lazy;
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+use tracing::{debug, trace};
+
+use crate::blocklist::sha256_file;
+
+/// Where the CLI keeps the hashes of the files it downloaded
+const LOCAL_INDEX_FILE_NAME: &str = "dedup-index.tsv";
+
+/// Keeps the appends from different downloads from interleaving
+static INDEX_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// Whether identical files downloaded from different URLs should only be stored once
+#[must_use]
+pub fn is_enabled() -> bool {
+    Config::global().download.dedup_downloads
+}
+
+/// Replaces the file at `path` with a hard link to `existing`,
+/// a file with the same content that was downloaded before.
+///
+/// The existing file could have been changed since it was indexed,
+/// so it's hashed again with `hash_file` and has to still have the file's `hash`.
+///
+/// Returns `false` if the existing file is gone, is the same file already
+/// or doesn't match anymore, in which case the file is kept as it is.
+pub async fn link_to_existing<F, Fut>(
+    path: &Path,
+    existing: &Path,
+    hash: &str,
+    hash_file: F,
+) -> Result<bool, DedupError>
+where
+    F: FnOnce(PathBuf) -> Fut + Send,
+    Fut: Future<Output = Option<String>> + Send,
+{
+    let (Ok(existing_meta), Ok(meta)) = (fs::metadata(existing).await, fs::metadata(path).await)
+    else {
+        return Ok(false);
+    };
+
+    if existing_meta.len() != meta.len() || is_same_file(path, existing).await {
+        return Ok(false);
+    }
+
+    let existing_hash = hash_file(existing.to_path_buf()).await;
+    if existing_hash.as_deref() != Some(hash) {
+        debug!(
+            ?existing,
+            ?existing_hash,
+            ?hash,
+            "Existing file doesn't match anymore"
+        );
+        return Ok(false);
+    }
+
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{file_name}.dedup"));
+
+    debug!(?path, ?existing, "Replacing duplicate with hard link");
+
+    let _ = fs::remove_file(&temp_path).await;
+    fs::hard_link(existing, &temp_path)
+        .await
+        .map_err(DedupError::Link)?;
+
+    if let Err(e) = fs::rename(&temp_path, path).await {
+        let _ = fs::remove_file(&temp_path).await;
+
+        return Err(DedupError::Link(e));
+    }
+
+    Ok(true)
+}
+
+/// Hard links the file to the same one downloaded before, using the index in the cache directory.
+/// Files that weren't seen before are added to the index.
+///
+/// Returns the file it was linked to, if it was.
+pub async fn dedup_with_local_index(path: &Path) -> Result<Option<PathBuf>, DedupError> {
+    let hash = file_sha256(path).await?;
+    let index_file = Config::cache_dir().join(LOCAL_INDEX_FILE_NAME);
+
+    let _lock = INDEX_LOCK.lock().await;
+
+    let contents = match fs::read_to_string(&index_file).await {
+        Ok(x) => x,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(DedupError::Index(e)),
+    };
+
+    // The last entry wins, since older files might have been moved or deleted since
+    let existing = contents
+        .lines()
+        .rev()
+        .filter_map(|x| x.split_once('\t'))
+        .find(|(x, _)| *x == hash)
+        .map(|(_, x)| PathBuf::from(x));
+
+    trace!(?hash, ?existing, "Looked up file in dedup index");
+
+    if let Some(existing) = existing {
+        let hash_file = |x: PathBuf| async move { file_sha256(&x).await.ok() };
+
+        if link_to_existing(path, &existing, &hash, hash_file).await? {
+            return Ok(Some(existing));
+        }
+    }
+
+    let path = fs::canonicalize(path).await.map_err(DedupError::Index)?;
+
+    if let Some(parent) = index_file.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(DedupError::Index)?;
+    }
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&index_file)
+        .await
+        .map_err(DedupError::Index)?;
+
+    file.write_all(format!("{hash}\t{}\n", path.to_string_lossy()).as_bytes())
+        .await
+        .map_err(DedupError::Index)?;
+
+    Ok(None)
+}
+
+async fn file_sha256(path: &Path) -> Result<String, DedupError> {
+    let path = path.to_path_buf();
+
+    tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| DedupError::Hash(std::io::Error::other(e)))?
+        .map_err(DedupError::Hash)
+}
+
+async fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a).await, fs::canonicalize(b).await) {
+        (Ok(a), Ok(b)) => a == b || same_inode(&a, &b).await,
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+async fn same_inode(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (fs::metadata(a).await, fs::metadata(b).await) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+async fn same_inode(_a: &Path, _b: &Path) -> bool {
+    false
+}
+
+#[derive(Debug, Error)]
+pub enum DedupError {
+    #[error("Failed to hash file: {0:?}")]
+    Hash(std::io::Error),
+    #[error("Failed to link to the existing file: {0:?}")]
+    Link(std::io::Error),
+    #[error("Failed to use dedup index: {0:?}")]
+    Index(std::io::Error),
+}
@@ -0,0 +1,107 @@
+use app_config::Config;
+use once_cell::sync::Lazy;
+use thiserror::Error;
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+use tracing::{debug, trace};
+use url::Url;
+
+/// Query parameters that only track where the link was shared from
+const TRACKING_PARAMS: &[&str] = &[
+    "fbclid", "gclid", "igsh", "igshid", "si", "feature", "ref", "ref_src", "ref_url",
+];
+
+/// Keeps the appends from different downloads from interleaving
+static ARCHIVE_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
+
+/// How the URL is stored in the archive.
+///
+/// Tracking parameters, the scheme, the fragment and `www.`/`m.` are dropped,
+/// and the remaining query parameters are sorted,
+/// so different links to the same thing end up the same.
+#[must_use]
+pub fn archive_key(url: &Url) -> String {
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let host = host
+        .strip_prefix("www.")
+        .or_else(|| host.strip_prefix("m."))
+        .unwrap_or(&host);
+
+    // Short links are stored as the full ones
+    if host == "youtu.be" {
+        let id = url.path().trim_matches('/');
+
+        return format!("youtube.com/watch?v={id}");
+    }
+
+    let mut query = url
+        .query_pairs()
+        .filter(|(k, _)| {
+            let k = k.to_lowercase();
+
+            !k.starts_with("utm_") && !TRACKING_PARAMS.contains(&k.as_str())
+        })
+        .map(|(k, v)| format!("{k}={v}"))
+        .collect::<Vec<_>>();
+    query.sort();
+
+    let mut key = format!("{host}{path}", path = url.path().trim_end_matches('/'));
+    if !query.is_empty() {
+        key.push('?');
+        key.push_str(&query.join("&"));
+    }
+
+    key
+}
+
+/// Whether the URL is in the configured download archive
+pub async fn is_archived(url: &Url) -> Result<bool, DownloadArchiveError> {
+    let Some(archive_file) = &Config::global().download.download_archive else {
+        return Ok(false);
+    };
+
+    let contents = match fs::read_to_string(archive_file).await {
+        Ok(x) => x,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(DownloadArchiveError::Read(e)),
+    };
+
+    let key = archive_key(url);
+    trace!(?key, "Checking download archive");
+
+    Ok(contents.lines().map(str::trim).any(|x| x == key))
+}
+
+/// Adds the URL to the configured download archive, if it isn't there already
+pub async fn add_to_archive(url: &Url) -> Result<(), DownloadArchiveError> {
+    let Some(archive_file) = &Config::global().download.download_archive else {
+        return Ok(());
+    };
+
+    let _lock = ARCHIVE_LOCK.lock().await;
+
+    if is_archived(url).await? {
+        return Ok(());
+    }
+
+    let key = archive_key(url);
+    debug!(?key, "Adding URL to download archive");
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(archive_file)
+        .await
+        .map_err(DownloadArchiveError::Write)?;
+
+    file.write_all(format!("{key}\n").as_bytes())
+        .await
+        .map_err(DownloadArchiveError::Write)
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadArchiveError {
+    #[error("Failed to read download archive: {0:?}")]
+    Read(std::io::Error),
+    #[error("Failed to write to download archive: {0:?}")]
+    Write(std::io::Error),
+}
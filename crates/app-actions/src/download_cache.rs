@@ -0,0 +1,225 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::id::time_thread_id;
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use tokio::fs;
+use tracing::{debug, trace};
+use url::Url;
+
+use crate::{
+    blocklist::sha256_file,
+    download_archive::archive_key,
+    downloaders::{DownloadRequest, DownloadResult, DownloaderOptions, MediaMetadata},
+    resume::RESUME_KEY_OPTION,
+};
+
+/// The files downloaded from a link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CacheEntry {
+    url: String,
+    files: Vec<CachedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedFile {
+    sha256: String,
+    /// Name of the file when it was downloaded
+    file_name: String,
+    #[serde(default)]
+    metadata: Option<MediaMetadata>,
+}
+
+/// Copies the files cached for the URL into the download directory.
+///
+/// Returns `None` if nothing is cached for the URL with these options.
+pub async fn get_cached(
+    url: &Url,
+    options: &DownloaderOptions,
+    download_dir: &Path,
+) -> Result<Option<Vec<DownloadResult>>, DownloadCacheError> {
+    let Some(cache_dir) = &Config::global().download.download_cache_dir else {
+        return Ok(None);
+    };
+
+    let entry_path = entry_path(cache_dir, url, options);
+    let entry = match fs::read(&entry_path).await {
+        Ok(x) => x,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(DownloadCacheError::Read(e)),
+    };
+    let entry = serde_json::from_slice::<CacheEntry>(&entry).map_err(DownloadCacheError::Parse)?;
+
+    trace!(?entry, "Found download cache entry");
+
+    // Entries with missing files are treated as if they weren't there
+    for file in &entry.files {
+        if !fs::try_exists(file_path(cache_dir, file))
+            .await
+            .unwrap_or(false)
+        {
+            debug!(?file, "Cached file is missing");
+            return Ok(None);
+        }
+    }
+
+    let mut results = vec![];
+    for file in &entry.files {
+        let mut dest = download_dir.join(&file.file_name);
+        if fs::try_exists(&dest).await.unwrap_or(false) {
+            dest = download_dir.join(format!("{}.{}", time_thread_id(), file.file_name));
+        }
+
+        // Copied, not linked, since the fixers can change the file in place
+        fs::copy(file_path(cache_dir, file), &dest)
+            .await
+            .map_err(DownloadCacheError::Read)?;
+
+        results.push(DownloadResult {
+            request: DownloadRequest::from_url(url.as_str(), download_dir)
+                .with_downloader_options(options.clone()),
+            path: dest,
+            sha256: Some(file.sha256.clone()),
+            metadata: file.metadata.clone(),
+            downloader_chain: vec![],
+            response_headers: HeaderMap::new(),
+        });
+    }
+
+    debug!(count = results.len(), url = ?url.as_str(), "Served download from cache");
+
+    Ok(Some(results))
+}
+
+/// Adds a copy of the downloaded files to the cache
+pub async fn add_to_cache(
+    url: &Url,
+    options: &DownloaderOptions,
+    results: &[DownloadResult],
+) -> Result<(), DownloadCacheError> {
+    let Some(cache_dir) = &Config::global().download.download_cache_dir else {
+        return Ok(());
+    };
+
+    fs::create_dir_all(cache_dir.join("files"))
+        .await
+        .map_err(DownloadCacheError::Write)?;
+    fs::create_dir_all(cache_dir.join("urls"))
+        .await
+        .map_err(DownloadCacheError::Write)?;
+
+    let mut files = vec![];
+    for result in results {
+        let sha256 = match &result.sha256 {
+            Some(x) => x.to_lowercase(),
+            None => {
+                let path = result.path.clone();
+                tokio::task::spawn_blocking(move || sha256_file(&path))
+                    .await?
+                    .map_err(DownloadCacheError::Read)?
+            }
+        };
+
+        let file = CachedFile {
+            sha256,
+            file_name: result
+                .path
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            metadata: result.metadata.clone(),
+        };
+
+        let cached_path = file_path(cache_dir, &file);
+        if !fs::try_exists(&cached_path).await.unwrap_or(false) {
+            write_atomically(&cached_path, |temp| fs::copy(&result.path, temp)).await?;
+        }
+
+        files.push(file);
+    }
+
+    let entry = CacheEntry {
+        url: url.to_string(),
+        files,
+    };
+    let entry = serde_json::to_vec(&entry).map_err(DownloadCacheError::Serialize)?;
+    let entry_path = entry_path(cache_dir, url, options);
+
+    write_atomically(&entry_path, |temp| fs::write(temp, entry)).await?;
+
+    debug!(url = ?url.as_str(), "Added download to cache");
+
+    Ok(())
+}
+
+/// Writes the file next to `path` and moves it into place,
+/// so the other processes using the cache never see half written files
+async fn write_atomically<F, Fut, T>(path: &Path, write: F) -> Result<(), DownloadCacheError>
+where
+    F: FnOnce(PathBuf) -> Fut + Send,
+    Fut: Future<Output = std::io::Result<T>> + Send,
+{
+    let temp = path.with_extension(format!("{}.{}.tmp", std::process::id(), time_thread_id()));
+
+    if let Err(e) = write(temp.clone()).await {
+        let _ = fs::remove_file(&temp).await;
+        return Err(DownloadCacheError::Write(e));
+    }
+
+    fs::rename(&temp, path)
+        .await
+        .map_err(DownloadCacheError::Write)
+}
+
+/// The options are part of the key since they change what gets downloaded.
+/// The resume key doesn't, and it's different for every request.
+fn entry_path(cache_dir: &Path, url: &Url, options: &DownloaderOptions) -> PathBuf {
+    let options = options
+        .iter()
+        .filter(|(k, _)| k.as_str() != RESUME_KEY_OPTION)
+        .collect::<BTreeMap<_, _>>();
+    let options = serde_json::to_string(&options).unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(archive_key(url));
+    hasher.update("\n");
+    hasher.update(options);
+
+    cache_dir
+        .join("urls")
+        .join(format!("{:x}.json", hasher.finalize()))
+}
+
+fn file_path(cache_dir: &Path, file: &CachedFile) -> PathBuf {
+    let extension = Path::new(&file.file_name)
+        .extension()
+        .map(|x| format!(".{}", x.to_string_lossy()))
+        .unwrap_or_default();
+
+    cache_dir
+        .join("files")
+        .join(format!("{}{}", file.sha256, extension))
+}
+
+#[derive(Debug, Error)]
+pub enum DownloadCacheError {
+    #[error("Failed to read from download cache: {0:?}")]
+    Read(std::io::Error),
+    #[error("Failed to write to download cache: {0:?}")]
+    Write(std::io::Error),
+    #[error("Invalid download cache entry: {0:?}")]
+    Parse(serde_json::Error),
+    #[error("Failed to serialize download cache entry: {0:?}")]
+    Serialize(serde_json::Error),
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
@@ -4,10 +4,11 @@ use std::{
 };
 
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use url::Url;
 
 use crate::{
     common::url::UrlWithMeta,
-    downloaders::DownloaderEntry,
+    downloaders::{DownloaderEntry, ProgressReporter},
     extractors::{ExtractedInfo, ExtractedUrlInfo},
 };
 
@@ -16,9 +17,18 @@ pub type DownloaderOptions = HashMap<String, serde_json::Value>;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadRequest {
     pub url: UrlWithMeta,
+    /// Tried in order if the URL itself can't be downloaded
+    #[serde(default)]
+    pub mirrors: Vec<UrlWithMeta>,
     pub download_dir: PathBuf,
     pub preferred_downloader: Option<DownloaderEntry>,
     pub downloader_options: DownloaderOptions,
+    /// The page the URL was found on, if it came from an extractor
+    #[serde(default)]
+    pub source_url: Option<Url>,
+    /// Where the downloader reports how much of the file it got so far
+    #[serde(skip)]
+    pub progress: Option<ProgressReporter>,
 }
 impl DownloadRequest {
     #[must_use]
@@ -28,9 +38,12 @@ impl DownloadRequest {
     {
         Self {
             url: url.into(),
+            mirrors: vec![],
             download_dir: download_dir.to_path_buf(),
             preferred_downloader: None,
             downloader_options: HashMap::new(),
+            source_url: None,
+            progress: None,
         }
     }
 
@@ -38,9 +51,12 @@ impl DownloadRequest {
     pub fn from_extracted_url(info: &ExtractedUrlInfo, download_dir: &Path) -> Self {
         Self {
             url: info.url.clone(),
+            mirrors: info.mirrors.clone(),
             download_dir: download_dir.to_path_buf(),
             preferred_downloader: info.preferred_downloader.clone(),
             downloader_options: info.downloader_options.clone(),
+            source_url: None,
+            progress: None,
         }
     }
 
@@ -48,7 +64,10 @@ impl DownloadRequest {
     pub fn from_extracted_info(info: &ExtractedInfo, download_dir: &Path) -> Vec<Self> {
         info.urls
             .iter()
-            .map(|x| Self::from_extracted_url(x, download_dir))
+            .map(|x| Self {
+                source_url: Some(info.request.url.clone()),
+                ..Self::from_extracted_url(x, download_dir)
+            })
             .collect()
     }
 }
@@ -83,6 +102,12 @@ impl DownloadRequest {
         self
     }
 
+    #[must_use]
+    pub fn with_progress(mut self, progress: Option<ProgressReporter>) -> Self {
+        self.progress = progress;
+        self
+    }
+
     #[must_use]
     pub fn downloader_option_raw(&self, key: &str) -> Option<&serde_json::Value> {
         self.downloader_options.get(key)
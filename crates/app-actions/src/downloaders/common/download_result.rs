@@ -1,11 +1,53 @@
 use std::path::PathBuf;
 
+use http::HeaderMap;
 use serde::{Deserialize, Serialize};
 
-use super::download_request::DownloadRequest;
+use super::{download_request::DownloadRequest, media_metadata::MediaMetadata};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DownloadResult {
     pub request: DownloadRequest,
     pub path: PathBuf,
+    /// Hex-encoded SHA-256 of the file, if the downloader calculated it while writing the file
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// What the site says about the media, if the downloader found out
+    #[serde(default)]
+    pub metadata: Option<MediaMetadata>,
+    /// The downloaders that were tried for the file, in order, ending with the one that got it
+    #[serde(default)]
+    pub downloader_chain: Vec<DownloaderAttempt>,
+    /// Headers of the response the file was downloaded from, if the downloader saw them
+    #[serde(with = "http_serde::header_map", default)]
+    pub response_headers: HeaderMap,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloaderAttempt {
+    pub downloader: String,
+    /// Why the downloader failed, if it did
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+impl DownloaderAttempt {
+    #[must_use]
+    pub fn succeeded(downloader: &str) -> Self {
+        Self {
+            downloader: downloader.to_string(),
+            error: None,
+        }
+    }
+
+    #[must_use]
+    pub fn failed<E>(downloader: &str, error: &E) -> Self
+    where
+        E: ToString + ?Sized,
+    {
+        Self {
+            downloader: downloader.to_string(),
+            error: Some(error.to_string()),
+        }
+    }
 }
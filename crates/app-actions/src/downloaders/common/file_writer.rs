@@ -0,0 +1,46 @@
+use sha2::{Digest, Sha256};
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+};
+
+/// How much of the response is collected in memory before it's written to disk.
+/// Chunks from the network are only a few KiB, so writing each one directly
+/// ends up being mostly syscalls.
+pub const WRITE_BUFFER_SIZE: usize = 256 * 1024;
+
+/// Writes a download to disk through a buffer,
+/// hashing it in the same pass so the file doesn't have to be read again afterwards
+#[derive(Debug)]
+pub struct DownloadFileWriter {
+    file: BufWriter<File>,
+    hasher: Option<Sha256>,
+}
+impl DownloadFileWriter {
+    /// Files that are only partly written here (eg. resumed downloads)
+    /// are missing the start, so they shouldn't be hashed
+    #[must_use]
+    pub fn new(file: File, hash: bool) -> Self {
+        Self {
+            file: BufWriter::with_capacity(WRITE_BUFFER_SIZE, file),
+            hasher: hash.then(Sha256::new),
+        }
+    }
+
+    pub async fn write_chunk(&mut self, chunk: &[u8]) -> std::io::Result<()> {
+        if let Some(hasher) = &mut self.hasher {
+            hasher.update(chunk);
+        }
+
+        self.file.write_all(chunk).await
+    }
+
+    /// Writes out what's left in the buffer.
+    ///
+    /// Returns the hex-encoded SHA-256 of what was written, if the file is hashed.
+    pub async fn finish(mut self) -> std::io::Result<Option<String>> {
+        self.file.flush().await?;
+
+        Ok(self.hasher.map(|x| format!("{:x}", x.finalize())))
+    }
+}
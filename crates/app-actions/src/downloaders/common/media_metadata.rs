@@ -0,0 +1,109 @@
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// What the site says about the downloaded media
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaMetadata {
+    /// The ID of the media on the site
+    pub id: Option<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub uploader: Option<String>,
+    pub uploader_url: Option<String>,
+    pub channel: Option<String>,
+    pub upload_date: Option<NaiveDate>,
+    /// When it was uploaded, if the site says so more precisely than the date
+    pub uploaded_at: Option<DateTime<Utc>>,
+    /// In seconds
+    pub duration: Option<f64>,
+    pub view_count: Option<u64>,
+    pub like_count: Option<u64>,
+    pub comment_count: Option<u64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub categories: Vec<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub thumbnail: Option<String>,
+    /// The page the media is on
+    pub webpage_url: Option<String>,
+    /// Name of the yt-dlp extractor that found the media
+    pub extractor: Option<String>,
+    /// How old the site says viewers have to be, eg. `18` for age-restricted videos
+    pub age_limit: Option<u32>,
+}
+impl MediaMetadata {
+    /// Whether the site only shows the media to adults
+    #[must_use]
+    pub fn is_age_restricted(&self) -> bool {
+        self.age_limit.is_some_and(|x| x >= 18)
+    }
+
+    /// Reads the `.info.json` file yt-dlp writes next to the download with `--write-info-json`
+    pub async fn from_yt_dlp_info_file(path: &Path) -> Result<Self, std::io::Error> {
+        let data = tokio::fs::read(path).await?;
+
+        serde_json::from_slice::<YtDlpInfo>(&data)
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+}
+
+/// The parts of yt-dlp's info JSON that are kept
+#[derive(Debug, Deserialize)]
+struct YtDlpInfo {
+    id: Option<String>,
+    title: Option<String>,
+    description: Option<String>,
+    uploader: Option<String>,
+    uploader_url: Option<String>,
+    channel: Option<String>,
+    /// `YYYYMMDD`
+    upload_date: Option<String>,
+    timestamp: Option<i64>,
+    duration: Option<f64>,
+    view_count: Option<u64>,
+    like_count: Option<u64>,
+    comment_count: Option<u64>,
+    tags: Option<Vec<String>>,
+    categories: Option<Vec<String>>,
+    width: Option<u32>,
+    height: Option<u32>,
+    thumbnail: Option<String>,
+    webpage_url: Option<String>,
+    extractor_key: Option<String>,
+    age_limit: Option<u32>,
+}
+
+impl From<YtDlpInfo> for MediaMetadata {
+    fn from(info: YtDlpInfo) -> Self {
+        Self {
+            id: info.id,
+            title: info.title,
+            description: info.description,
+            uploader: info.uploader,
+            uploader_url: info.uploader_url,
+            channel: info.channel,
+            upload_date: info
+                .upload_date
+                .and_then(|x| NaiveDate::parse_from_str(&x, "%Y%m%d").ok()),
+            uploaded_at: info.timestamp.and_then(|x| DateTime::from_timestamp(x, 0)),
+            duration: info.duration,
+            view_count: info.view_count,
+            like_count: info.like_count,
+            comment_count: info.comment_count,
+            tags: info.tags.unwrap_or_default(),
+            categories: info.categories.unwrap_or_default(),
+            width: info.width,
+            height: info.height,
+            thumbnail: info.thumbnail,
+            webpage_url: info.webpage_url,
+            extractor: info.extractor_key,
+            age_limit: info.age_limit,
+        }
+    }
+}
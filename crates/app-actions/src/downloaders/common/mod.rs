@@ -1,2 +1,5 @@
 pub mod download_request;
 pub mod download_result;
+pub mod file_writer;
+pub mod media_metadata;
+pub mod progress;
@@ -0,0 +1,334 @@
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+
+use app_helpers::bytes::format_bytes;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::extractors::ExtractedInfo;
+
+/// How far back samples are kept when calculating the download speed
+const SPEED_WINDOW: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileProgress {
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub done: bool,
+}
+
+/// What the extractor found, known before anything is downloaded
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FoundMedia {
+    /// The URL the media was found at
+    pub source_url: Option<String>,
+    pub title: Option<String>,
+    pub duration: Option<Duration>,
+    pub estimated_size: Option<u64>,
+}
+impl FoundMedia {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.title.is_none() && self.duration.is_none() && self.estimated_size.is_none()
+    }
+}
+impl From<&ExtractedInfo> for FoundMedia {
+    fn from(info: &ExtractedInfo) -> Self {
+        Self {
+            source_url: Some(info.request.url.to_string()),
+            title: info.title().map(ToString::to_string),
+            duration: info.duration(),
+            estimated_size: info.estimated_size(),
+        }
+    }
+}
+impl fmt::Display for FoundMedia {
+    /// `Found: Some video (3m 12s, ~40.0 MiB)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Found: {}", self.title.as_deref().unwrap_or("untitled"))?;
+
+        let details = [
+            self.duration.map(format_duration),
+            self.estimated_size.map(|x| format!("~{}", format_bytes(x))),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+
+        if !details.is_empty() {
+            write!(f, " ({})", details.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Progress of all the downloads started for one request
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DownloadProgress {
+    files: Vec<FileProgress>,
+    found: Vec<FoundMedia>,
+}
+impl DownloadProgress {
+    #[must_use]
+    pub fn files(&self) -> &[FileProgress] {
+        &self.files
+    }
+
+    /// What was found for each of the URLs that finished extracting
+    #[must_use]
+    pub fn found(&self) -> &[FoundMedia] {
+        &self.found
+    }
+
+    #[must_use]
+    pub fn downloaded_bytes(&self) -> u64 {
+        self.files.iter().map(|x| x.downloaded_bytes).sum()
+    }
+
+    /// `None` until the size of every file is known
+    #[must_use]
+    pub fn total_bytes(&self) -> Option<u64> {
+        if self.files.is_empty() {
+            return None;
+        }
+
+        self.files.iter().map(|x| x.total_bytes).sum()
+    }
+}
+
+/// Hands out a [`ProgressReporter`] to every download and
+/// lets anyone subscribe to the combined [`DownloadProgress`].
+///
+/// Subscribers are notified that the downloads are over
+/// once every clone of the tracker is dropped.
+#[derive(Debug, Clone)]
+pub struct ProgressTracker {
+    sender: Arc<watch::Sender<DownloadProgress>>,
+}
+impl ProgressTracker {
+    #[must_use]
+    pub fn new() -> Self {
+        let (sender, _) = watch::channel(DownloadProgress::default());
+
+        Self {
+            sender: Arc::new(sender),
+        }
+    }
+
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<DownloadProgress> {
+        self.sender.subscribe()
+    }
+
+    /// Lets subscribers know what's about to be downloaded
+    pub fn found(&self, media: FoundMedia) {
+        if media.is_empty() {
+            return;
+        }
+
+        self.sender.send_modify(|x| x.found.push(media));
+    }
+
+    #[must_use]
+    pub fn reporter(&self) -> ProgressReporter {
+        let mut index = 0;
+
+        self.sender.send_modify(|x| {
+            index = x.files.len();
+            x.files.push(FileProgress::default());
+        });
+
+        ProgressReporter {
+            index,
+            sender: Arc::downgrade(&self.sender),
+        }
+    }
+}
+impl Default for ProgressTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reports the progress of a single download.
+///
+/// Doesn't keep the tracker alive, so results holding on
+/// to their request don't keep subscribers waiting.
+#[derive(Debug, Clone)]
+pub struct ProgressReporter {
+    index: usize,
+    sender: Weak<watch::Sender<DownloadProgress>>,
+}
+impl ProgressReporter {
+    pub fn update(&self, downloaded_bytes: u64, total_bytes: Option<u64>) {
+        self.modify(|x| {
+            x.downloaded_bytes = downloaded_bytes;
+            x.total_bytes = total_bytes.or(x.total_bytes);
+        });
+    }
+
+    pub fn add(&self, bytes: u64) {
+        self.modify(|x| x.downloaded_bytes += bytes);
+    }
+
+    pub fn finish(&self) {
+        self.modify(|x| {
+            x.total_bytes = Some(x.downloaded_bytes);
+            x.done = true;
+        });
+    }
+
+    fn modify<F>(&self, f: F)
+    where
+        F: FnOnce(&mut FileProgress),
+    {
+        let Some(sender) = self.sender.upgrade() else {
+            return;
+        };
+
+        sender.send_modify(|x| {
+            if let Some(file) = x.files.get_mut(self.index) {
+                f(file);
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProgressEstimate {
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub bytes_per_second: Option<f64>,
+    pub eta_seconds: Option<u64>,
+}
+impl ProgressEstimate {
+    #[must_use]
+    pub fn eta(&self) -> Option<Duration> {
+        self.eta_seconds.map(Duration::from_secs)
+    }
+
+    /// `None` until the total size is known
+    #[must_use]
+    pub fn percent(&self) -> Option<f64> {
+        let total_bytes = self.total_bytes.filter(|x| *x > 0)?;
+
+        #[allow(clippy::cast_precision_loss)]
+        let percent = self.downloaded_bytes as f64 / total_bytes as f64 * 100.0;
+
+        Some(percent.min(100.0))
+    }
+}
+impl fmt::Display for ProgressEstimate {
+    /// `12.3 MiB of 40.0 MiB (30%) at 2.1 MiB/s, about 13s left`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", format_bytes(self.downloaded_bytes))?;
+
+        if let (Some(total_bytes), Some(percent)) = (self.total_bytes, self.percent()) {
+            write!(f, " of {} ({percent:.0}%)", format_bytes(total_bytes))?;
+        }
+
+        if let Some(bytes_per_second) = self.bytes_per_second {
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let bytes_per_second = bytes_per_second as u64;
+
+            write!(f, " at {}/s", format_bytes(bytes_per_second))?;
+        }
+
+        if let Some(eta) = self.eta() {
+            write!(f, ", about {} left", format_duration(eta))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Calculates a rolling download speed and the time remaining
+/// from the progress updates it's fed
+#[derive(Debug, Clone, Default)]
+pub struct ProgressEstimator {
+    samples: VecDeque<(Instant, u64)>,
+}
+impl ProgressEstimator {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, progress: &DownloadProgress) -> ProgressEstimate {
+        let now = Instant::now();
+        let downloaded_bytes = progress.downloaded_bytes();
+        let total_bytes = progress.total_bytes();
+
+        // Downloads restarting (eg. yt-dlp moving on to the audio stream) would skew the speed
+        if self
+            .samples
+            .back()
+            .is_some_and(|(_, x)| *x > downloaded_bytes)
+        {
+            self.samples.clear();
+        }
+
+        self.samples.push_back((now, downloaded_bytes));
+
+        while self.samples.len() > 2
+            && self
+                .samples
+                .front()
+                .is_some_and(|(t, _)| now.duration_since(*t) > SPEED_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+
+        let bytes_per_second = match (self.samples.front(), self.samples.back()) {
+            (Some((start, start_bytes)), Some((end, end_bytes))) => {
+                let elapsed = end.duration_since(*start).as_secs_f64();
+
+                #[allow(clippy::cast_precision_loss)]
+                let speed = (end_bytes - start_bytes) as f64 / elapsed;
+
+                Some(speed).filter(|_| elapsed > 0.0)
+            }
+            _ => None,
+        };
+
+        let eta_seconds = match (total_bytes, bytes_per_second) {
+            (Some(total_bytes), Some(speed)) if speed > 0.0 => {
+                let remaining = total_bytes.saturating_sub(downloaded_bytes);
+
+                #[allow(
+                    clippy::cast_precision_loss,
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss
+                )]
+                let eta = (remaining as f64 / speed).ceil() as u64;
+
+                Some(eta)
+            }
+            _ => None,
+        };
+
+        ProgressEstimate {
+            downloaded_bytes,
+            total_bytes,
+            bytes_per_second,
+            eta_seconds,
+        }
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match secs {
+        0..=59 => format!("{secs}s"),
+        60..=3599 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::{
+    file_name::file_name_with_suffix, id::time_id, temp_dir::TempDir, temp_file::TempFile,
+};
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, trace, warn};
+
+use super::{DownloadRequest, DownloadResult, Downloader, DownloaderReturn};
+use crate::{
+    common::request::{configured_cookie_lines, proxy_for, USER_AGENT},
+    downloaders::helpers::{
+        size_limit::{check_size, max_download_size},
+        throttle::Throttle,
+    },
+};
+
+/// Downloads galleries (eg. image board posts or pixiv works) with gallery-dl.
+///
+/// It's last in line, so it's only used when an extractor prefers it
+/// or it's in the downloader chain for the domain.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GalleryDl;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Downloader for GalleryDl {
+    fn description(&self) -> &'static str {
+        "Downloads all the images of galleries and image board posts using gallery-dl."
+    }
+
+    fn can_run(&self) -> bool {
+        Config::global()
+            .dependency_paths
+            .gallery_dl_path()
+            .is_some()
+    }
+
+    async fn can_download(&self, request: &DownloadRequest) -> bool {
+        self.can_run() && matches!(request.url.url().scheme(), "http" | "https")
+    }
+
+    async fn download(&self, req: &DownloadRequest) -> DownloaderReturn {
+        self.download_many(req)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::other("gallery-dl finished but no files were downloaded."))
+    }
+
+    async fn download_all(&self, req: &DownloadRequest) -> Vec<DownloaderReturn> {
+        match self.download_many(req).await {
+            Ok(x) => x.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        }
+    }
+}
+
+impl GalleryDl {
+    /// All the files of the gallery, in the order gallery-dl downloaded them
+    #[allow(clippy::too_many_lines)]
+    pub async fn download_many(
+        &self,
+        request: &DownloadRequest,
+    ) -> Result<Vec<DownloadResult>, AppError> {
+        let gallery_dl = Config::global()
+            .dependency_paths
+            .gallery_dl_path()
+            .ok_or_else(|| {
+                ExternalToolError::unavailable(
+                    "gallery-dl",
+                    "Please make sure it is installed and added to the PATH environment variable",
+                )
+            })?;
+        trace!("`gallery-dl' binary: {:?}", &gallery_dl);
+
+        let temp_dir = TempDir::in_tmp_with_prefix("downloader-hub_gallery-dl-")
+            .map_err(|e| format!("Failed to create temporary directory for gallery-dl: {e:?}"))?;
+        let url = request.url.url();
+
+        let mut cmd = Command::new(gallery_dl);
+        let mut cmd = cmd
+            .arg("--config-ignore")
+            .arg("--no-part")
+            .arg("--no-mtime")
+            .args(["--user-agent", USER_AGENT])
+            // Straight into the directory, without the per-site subdirectories
+            .arg("--directory")
+            .arg(temp_dir.path());
+
+        if let Some(proxy) = proxy_for(Some(self.name()), url) {
+            cmd = cmd.args(["--proxy", proxy.as_str()]);
+        }
+
+        if let Some(limit_rate) = Throttle::lowest_rate() {
+            cmd = cmd.args(["--limit-rate", &limit_rate.to_string()]);
+        }
+
+        if let Some(max_size) = max_download_size() {
+            cmd = cmd.args(["--filesize-max", &max_size.to_string()]);
+        }
+
+        // Kept until gallery-dl is done with it
+        let cookie_file = cookie_file()?;
+        if let Some(cookie_file) = &cookie_file {
+            cmd = cmd.arg("--cookies").arg(cookie_file.path());
+        }
+
+        let cmd = cmd
+            .arg(url.as_str())
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+
+        debug!(?cmd, "Running gallery-dl command");
+
+        let output = cmd
+            .output()
+            .await
+            .map_err(|e| ExternalToolError::unavailable("gallery-dl", format!("{e:?}")))?;
+
+        trace!(?output, "gallery-dl output");
+
+        let file_paths =
+            downloaded_files(&String::from_utf8_lossy(&output.stdout), temp_dir.path());
+
+        if file_paths.is_empty() {
+            return Err(ExternalToolError::failed(
+                "gallery-dl",
+                format!(
+                    "Nothing was downloaded: {stderr}",
+                    stderr = String::from_utf8_lossy(&output.stderr).trim(),
+                ),
+            )
+            .into());
+        }
+
+        // The files that were downloaded are still worth keeping
+        if !output.status.success() {
+            warn!(
+                status = ?output.status,
+                stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+                "gallery-dl only downloaded some files"
+            );
+        }
+
+        // All of them are checked first so nothing is left in the download directory
+        for file_path in &file_paths {
+            let size = tokio::fs::metadata(file_path)
+                .await
+                .map(|x| x.len())
+                .unwrap_or_default();
+            check_size(size)?;
+        }
+
+        let id = time_id();
+        let mut results = Vec::<DownloadResult>::new();
+        for file_path in file_paths {
+            let file_name =
+                file_name_with_suffix(Path::new(file_path.file_name().unwrap_or_default()), &id);
+            let final_file_path = request.download_dir().join(file_name);
+
+            if let Err(e) = tokio::fs::copy(&file_path, &final_file_path).await {
+                for result in &results {
+                    let _ = tokio::fs::remove_file(&result.path).await;
+                }
+                let _ = tokio::fs::remove_file(&final_file_path).await;
+
+                return Err(format!(
+                    "Failed to copy file from {} to {}: {e:?}",
+                    file_path.display(),
+                    final_file_path.display()
+                )
+                .into());
+            }
+
+            results.push(DownloadResult {
+                request: request.clone(),
+                path: final_file_path,
+                sha256: None,
+                metadata: None,
+                downloader_chain: vec![],
+                response_headers: HeaderMap::new(),
+            });
+        }
+
+        if let Some(progress) = &request.progress {
+            progress.finish();
+        }
+
+        Ok(results)
+    }
+}
+
+/// gallery-dl prints the path of every file it downloads on its own line.
+/// Skipped files are printed with a `# ` in front, but the directory always starts out empty.
+fn downloaded_files(stdout: &str, dir: &Path) -> Vec<PathBuf> {
+    stdout
+        .lines()
+        .map(str::trim)
+        .map(PathBuf::from)
+        .filter(|x| x.starts_with(dir) && x.is_file())
+        .collect()
+}
+
+/// The configured cookies as a Netscape `cookies.txt` file, if there are any
+fn cookie_file() -> Result<Option<TempFile>, String> {
+    let cookie_lines = configured_cookie_lines();
+    if cookie_lines.is_empty() {
+        return Ok(None);
+    }
+
+    let mut cookie_file = TempFile::with_prefix("gallery-dl-cookies-")
+        .map_err(|e| format!("Failed to create temporary file for gallery-dl cookies: {e:?}"))?;
+
+    cookie_file
+        .file_mut()
+        .write_all(
+            format!(
+                "# Netscape HTTP Cookie File\n{cookie_lines}\n",
+                cookie_lines = cookie_lines.join("\n")
+            )
+            .as_bytes(),
+        )
+        .map_err(|e| format!("Failed to write cookies to file: {e:?}"))?;
+
+    Ok(Some(cookie_file))
+}
@@ -1,23 +1,55 @@
-use std::{ffi::OsString, path::PathBuf, string::ToString};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    string::ToString,
+    sync::Mutex,
+    time::Duration,
+};
 
 use app_config::timeframe::Timeframe;
-use app_helpers::id::time_id;
-use http::header;
+use app_errors::{AppError, NetworkError};
+use app_helpers::{
+    file_name::{file_name_with_suffix, sanitize_file_name},
+    id::time_id,
+};
+use http::{header, HeaderMap, Method, StatusCode};
 use mime2ext::mime2ext;
+use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use tokio::{fs::File, io::AsyncWriteExt};
-use tracing::{debug, info, trace};
+use sha2::{Digest, Sha256};
+use tokio::fs::{File, OpenOptions};
+use tracing::{debug, info, trace, warn};
 use unicode_segmentation::UnicodeSegmentation;
 use url::Url;
 
 use super::{DownloadRequest, DownloadResult, Downloader, DownloaderReturn};
 use crate::{
-    common::request::Client,
-    downloaders::{helpers::headers::content_disposition, DownloaderOptions},
+    common::request::{record_quota, send_following_redirects, wait_for_quota, Client},
+    downloaders::{
+        helpers::{
+            aria2c::{control_file_path, download_segmented, segmented_download_tool},
+            headers::content_disposition,
+            impersonate::{download_impersonated, impersonation_tool},
+            size_limit::{check_size, SizeLimit},
+            throttle::Throttle,
+        },
+        DownloadFileWriter, DownloaderOptions,
+    },
+    fixers::handlers::tag_audio::{tag_downloaded_file, AudioTags, AUDIO_TAGS_OPTION},
+    format_choice::{extract_audio, FormatChoice},
 };
 
 pub const MAX_FILENAME_LENGTH: usize = 120;
 
+/// Part files that weren't touched for this long are removed,
+/// the download they're from was probably given up on
+const PART_FILE_TTL: Duration = Duration::from_hours(2 * 24);
+
+/// Part files that are being downloaded to,
+/// so two downloads of the same URL don't write to the same one
+static PART_FILES_IN_USE: Lazy<Mutex<HashSet<PathBuf>>> = Lazy::new(|| Mutex::new(HashSet::new()));
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Generic;
 
@@ -33,7 +65,21 @@ impl Downloader for Generic {
     }
 
     async fn download(&self, request: &DownloadRequest) -> DownloaderReturn {
-        self.download_one(request).await
+        let mut result = self.download_one(request).await?;
+
+        if FormatChoice::from_options(&request.downloader_options) == Some(FormatChoice::AudioOnly)
+        {
+            result.path = extract_audio(&result.path).await?;
+            result.sha256 = None;
+        }
+
+        if let Some(tags) = request.downloader_option::<AudioTags>(AUDIO_TAGS_OPTION) {
+            result.path = tag_downloaded_file(tags, result.path).await;
+            // Tagging rewrites the file
+            result.sha256 = None;
+        }
+
+        Ok(result)
     }
 }
 
@@ -41,6 +87,10 @@ impl Downloader for Generic {
 #[serde(rename_all = "kebab-case")]
 pub struct GenericDownloaderOptions {
     timeout: Option<Timeframe>,
+    /// The name to save the file as instead of the one the server sends.
+    /// The unique ID is put before the extension (`<stem>.<id>.<ext>`),
+    /// so files sort by this name.
+    file_name: Option<String>,
 }
 impl GenericDownloaderOptions {
     #[must_use]
@@ -56,6 +106,15 @@ impl GenericDownloaderOptions {
         self.timeout = timeout.map(Into::into);
         self
     }
+
+    #[must_use]
+    pub fn with_file_name<T>(mut self, file_name: Option<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        self.file_name = file_name.map(Into::into);
+        self
+    }
 }
 impl From<GenericDownloaderOptions> for DownloaderOptions {
     fn from(val: GenericDownloaderOptions) -> Self {
@@ -77,85 +136,562 @@ impl Generic {
     pub async fn download_one(
         &self,
         request_info: &DownloadRequest,
-    ) -> Result<DownloadResult, String> {
+    ) -> Result<DownloadResult, AppError> {
         let url = &request_info.url;
-        let options = request_info.downloader_options::<GenericDownloaderOptions>();
+        let options = request_info
+            .downloader_options::<GenericDownloaderOptions>()
+            .unwrap_or_default();
 
         info!(?url, dir = ?request_info.download_dir(), "Downloading with generic downloader");
 
-        let mut res = Client::base_with_url(url)?.headers(url.headers().clone());
-
-        if let Some(timeout) = options.and_then(|x| x.timeout) {
-            res = res.timeout(timeout.into());
+        if let Some(curl_path) = impersonation_tool(url.url()) {
+            return download_with_impersonation(&curl_path, request_info, &options).await;
         }
 
-        let mut res = res
-            .send()
+        remove_stale_part_files(request_info.download_dir()).await;
+
+        let part_file_lock = PartFileLock::acquire(request_info);
+        let part_file_path = part_file_lock.path.clone();
+        // Only resumed if the server can tell whether the file changed since
+        let validator = read_validator(&part_file_path).await;
+        let part_file_len = tokio::fs::metadata(&part_file_path)
             .await
-            .map_err(|e| format!("Failed to send request: {:?}", e))?
-            .error_for_status()
-            .map_err(|e| format!("Failed to get response: {:?}", e))?;
-
-        let mime_type = res.headers().get(header::CONTENT_TYPE).map(|x| x.to_str());
-        debug!(?mime_type, "Got mime type");
-        let mime_type = match mime_type {
-            Some(Ok(mime_type)) => mime_type,
-            _ => "",
+            .map(|x| x.len())
+            .ok()
+            .filter(|x| *x > 0 && *url.method() == Method::GET);
+        let resume = part_file_len.zip(validator.as_deref());
+
+        let mut res = send_request(request_info, &options, resume).await?;
+
+        // Probably the whole file or something that changed since, so it's downloaded again
+        if res.status() == StatusCode::RANGE_NOT_SATISFIABLE {
+            debug!(
+                ?part_file_path,
+                "Server refused to resume download, starting over"
+            );
+            res = send_request(request_info, &options, None).await?;
+        }
+
+        let res = res.error_for_status().map_err(NetworkError::from)?;
+
+        // Servers that don't support ranges, or have a different file by now,
+        // just send the whole file again
+        let resume_from = resume
+            .map(|(len, _)| len)
+            .filter(|x| content_range_start(&res) == Some(*x));
+        if let Some(resume_from) = resume_from {
+            info!(?part_file_path, resume_from, "Resuming download");
+        } else {
+            save_validator(&part_file_path, res.headers()).await;
+        }
+
+        // Checked before anything is written, when the server says how big the file is
+        if let Some(content_length) = res.content_length() {
+            check_size(content_length + resume_from.unwrap_or_default())?;
+        }
+
+        let file_path = file_path_for(request_info, &options, res.headers());
+
+        // Big files go a lot faster over multiple connections
+        if let Some(aria2c_path) = segmented_download_tool(
+            url.method(),
+            res.content_length()
+                .map(|x| x + resume_from.unwrap_or_default()),
+            res.headers(),
+        ) {
+            let response_headers = res.headers().clone();
+            let final_url = res.url().clone();
+            drop(res);
+
+            let result = download_with_aria2c(
+                &aria2c_path,
+                request_info,
+                &final_url,
+                &options,
+                PartFile {
+                    path: part_file_path.clone(),
+                    resume_from,
+                },
+                file_path,
+                response_headers,
+            )
+            .await;
+
+            return finish_part_file(&part_file_path, result).await;
+        }
+
+        let part_file = PartFile {
+            path: part_file_path.clone(),
+            resume_from,
         };
 
-        let extension = mime2ext(mime_type).map_or("unknown".to_string(), |x| (*x).to_string());
+        let result = write_response_to_file(res, request_info, part_file, file_path).await;
 
-        debug!(?extension, "Got extension");
+        finish_part_file(&part_file_path, result).await
+    }
+}
 
-        let id = time_id();
-        let mut file_name = OsString::from(&id);
+/// Same as the usual download, but the server sees a browser instead of reqwest.
+/// Can't be resumed, and the progress is only reported once it's done.
+async fn download_with_impersonation(
+    curl_path: &Path,
+    request_info: &DownloadRequest,
+    options: &GenericDownloaderOptions,
+) -> Result<DownloadResult, AppError> {
+    let url = request_info.url.url();
+    let part_file_lock = PartFileLock::acquire(request_info);
+    let part_file_path = &part_file_lock.path;
 
-        let taken_filename_len = id.len() + 1 + extension.len();
+    wait_for_quota(url)
+        .await
+        .map_err(|e| AppError::other(e.to_string()))?;
 
-        let req_file_name = res
-            .headers()
-            .get(header::CONTENT_DISPOSITION)
-            .and_then(|x| content_disposition::ContentDisposition::from_raw(x).ok())
-            .and_then(|x| {
-                debug!(?x, "Got content disposition");
-                x.get_filename_ext()
-                    .and_then(content_disposition::ExtendedValue::try_decode)
-                    .or_else(|| x.get_filename().map(ToString::to_string))
-            })
-            .or_else(|| {
-                let url = url.url();
-                debug!(?url, "Using url as filename");
-                url_to_filename(url, taken_filename_len).map(|x| x + ".bin")
-            })
-            .unwrap_or_else(|| "unknown.bin".to_string());
+    let headers = download_impersonated(
+        curl_path,
+        request_info,
+        Generic.name(),
+        options.timeout.map(Into::into),
+        part_file_path,
+    )
+    .await?;
+    record_quota(url, StatusCode::OK, &headers);
 
-        trace!(?req_file_name, "Got file name from request");
+    let size = tokio::fs::metadata(part_file_path)
+        .await
+        .map_err(|e| format!("Failed to read downloaded file: {:?}", e))?
+        .len();
+    if let Err(e) = check_size(size) {
+        let _ = tokio::fs::remove_file(part_file_path).await;
+        return Err(e);
+    }
 
-        file_name.push(".");
-        file_name.push(req_file_name);
+    let file_path = file_path_for(request_info, options, &headers);
+    tokio::fs::rename(part_file_path, &file_path)
+        .await
+        .map_err(|e| format!("Failed to move downloaded file: {:?}", e))?;
+
+    if let Some(progress) = &request_info.progress {
+        progress.update(size, Some(size));
+        progress.finish();
+    }
+
+    Ok(DownloadResult {
+        request: request_info.clone(),
+        path: file_path,
+        sha256: None,
+        metadata: None,
+        downloader_chain: vec![],
+        response_headers: headers,
+    })
+}
+
+/// Same as the usual download, but over multiple connections at once.
+/// The progress is only reported once it's done.
+async fn download_with_aria2c(
+    aria2c_path: &Path,
+    request_info: &DownloadRequest,
+    url: &Url,
+    options: &GenericDownloaderOptions,
+    part_file: PartFile,
+    file_path: PathBuf,
+    response_headers: HeaderMap,
+) -> Result<DownloadResult, AppError> {
+    debug!(?file_path, ?part_file, "Downloading with aria2c");
+
+    // aria2c would continue from whatever is left over otherwise
+    if part_file.resume_from.is_none() {
+        let _ = tokio::fs::remove_file(&part_file.path).await;
+        let _ = tokio::fs::remove_file(control_file_path(&part_file.path)).await;
+    }
+
+    download_segmented(
+        aria2c_path,
+        request_info,
+        url,
+        Generic.name(),
+        options.timeout.map(Into::into),
+        &part_file.path,
+    )
+    .await?;
+
+    let size = tokio::fs::metadata(&part_file.path)
+        .await
+        .map_err(|e| format!("Failed to read downloaded file: {:?}", e))?
+        .len();
+    if let Err(e) = check_size(size) {
+        let _ = tokio::fs::remove_file(&part_file.path).await;
+        return Err(e);
+    }
+
+    tokio::fs::rename(&part_file.path, &file_path)
+        .await
+        .map_err(|e| format!("Failed to move downloaded file: {:?}", e))?;
+
+    if let Some(progress) = &request_info.progress {
+        progress.update(size, Some(size));
+        progress.finish();
+    }
+
+    Ok(DownloadResult {
+        request: request_info.clone(),
+        path: file_path,
+        sha256: None,
+        metadata: None,
+        downloader_chain: vec![],
+        response_headers,
+    })
+}
+
+/// Where the file is saved to, named after what the server or the options say it's called
+fn file_path_for(
+    request_info: &DownloadRequest,
+    options: &GenericDownloaderOptions,
+    headers: &HeaderMap,
+) -> PathBuf {
+    let url = &request_info.url;
+
+    let mime_type = headers.get(header::CONTENT_TYPE).map(|x| x.to_str());
+    debug!(?mime_type, "Got mime type");
+    let mime_type = match mime_type {
+        Some(Ok(mime_type)) => mime_type,
+        _ => "",
+    };
+
+    let extension = mime2ext(mime_type).map_or_else(|| "unknown".to_string(), |x| (*x).to_string());
+
+    debug!(?extension, "Got extension");
+
+    let id = time_id();
+
+    let wanted_file_name = options
+        .file_name
+        .as_ref()
+        .map(|x| sanitize_file_name(x, MAX_FILENAME_LENGTH - 1 - id.len()))
+        .filter(|x| !x.is_empty());
+
+    if let Some(wanted_file_name) = wanted_file_name {
+        let wanted_file_name = match Path::new(&wanted_file_name).extension() {
+            Some(_) => PathBuf::from(wanted_file_name),
+            None => PathBuf::from(format!("{wanted_file_name}.{extension}")),
+        };
+        let file_name = file_name_with_suffix(&wanted_file_name, &id);
+
+        return request_info.download_dir().join(file_name);
+    }
+
+    let mut file_name = OsString::from(&id);
+
+    let taken_filename_len = id.len() + 1 + extension.len();
+
+    let req_file_name = headers
+        .get(header::CONTENT_DISPOSITION)
+        .and_then(|x| content_disposition::ContentDisposition::from_raw(x).ok())
+        .and_then(|x| {
+            debug!(?x, "Got content disposition");
+            x.get_filename_ext()
+                .and_then(content_disposition::ExtendedValue::try_decode)
+                .or_else(|| x.get_filename().map(ToString::to_string))
+        })
+        .or_else(|| {
+            let url = url.url();
+            debug!(?url, "Using url as filename");
+            url_to_filename(url, taken_filename_len).map(|x| x + ".bin")
+        })
+        .unwrap_or_else(|| "unknown.bin".to_string());
+
+    trace!(?req_file_name, "Got file name from request");
+
+    file_name.push(".");
+    file_name.push(req_file_name);
+
+    request_info.download_dir().join(file_name)
+}
 
-        let file_path = request_info.download_dir().join(file_name);
-        debug!(?file_path, "Writing to file");
-        let mut out_file = File::create(&file_path)
+/// Where the file is downloaded to before it's complete.
+///
+/// Named after the URL so the download can be resumed
+/// if it's retried after failing or being interrupted.
+fn part_file_path(request_info: &DownloadRequest) -> PathBuf {
+    let url_hash = Sha256::digest(request_info.url.url().as_str().as_bytes());
+    let url_hash = format!("{url_hash:x}");
+
+    request_info
+        .download_dir()
+        .join(format!(".{}.part", &url_hash[..16]))
+}
+
+/// Holds the part file of the URL while it's downloaded to.
+///
+/// If another download of the same URL already has it,
+/// this one gets its own part file and starts from scratch.
+struct PartFileLock {
+    path: PathBuf,
+}
+impl PartFileLock {
+    fn acquire(request_info: &DownloadRequest) -> Self {
+        let path = part_file_path(request_info);
+        let mut in_use = PART_FILES_IN_USE.lock().expect("Part file lock poisoned");
+
+        let path = if in_use.insert(path.clone()) {
+            path
+        } else {
+            let path = path.with_extension(format!("{}.part", time_id()));
+            debug!(?path, "Part file is in use, downloading to another one");
+            in_use.insert(path.clone());
+            path
+        };
+        drop(in_use);
+
+        Self { path }
+    }
+}
+impl Drop for PartFileLock {
+    fn drop(&mut self) {
+        if let Ok(mut in_use) = PART_FILES_IN_USE.lock() {
+            in_use.remove(&self.path);
+        }
+    }
+}
+
+/// Where the `ETag` or `Last-Modified` of the part file's response is kept,
+/// so the server only continues it with the same file
+fn validator_path(part_file_path: &Path) -> PathBuf {
+    let mut path = part_file_path.as_os_str().to_owned();
+    path.push(".validator");
+
+    PathBuf::from(path)
+}
+
+async fn read_validator(part_file_path: &Path) -> Option<String> {
+    tokio::fs::read_to_string(validator_path(part_file_path))
+        .await
+        .ok()
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+}
+
+/// Weak `ETag`s can't be used in `If-Range`, so those fall back to `Last-Modified`
+async fn save_validator(part_file_path: &Path, headers: &HeaderMap) {
+    let validator = headers
+        .get(header::ETAG)
+        .and_then(|x| x.to_str().ok())
+        .filter(|x| !x.starts_with("W/"))
+        .or_else(|| {
+            headers
+                .get(header::LAST_MODIFIED)
+                .and_then(|x| x.to_str().ok())
+        });
+
+    let path = validator_path(part_file_path);
+    let res = match validator {
+        Some(validator) => tokio::fs::write(&path, validator).await,
+        None => match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            x => x,
+        },
+    };
+
+    if let Err(e) = res {
+        warn!(?e, ?path, "Failed to save part file validator");
+    }
+}
+
+/// Removes what's left over next to the part file once the download is done.
+/// Failed downloads keep it all, so they can be resumed.
+async fn finish_part_file(
+    part_file_path: &Path,
+    result: Result<DownloadResult, AppError>,
+) -> Result<DownloadResult, AppError> {
+    if result.is_ok() {
+        let _ = tokio::fs::remove_file(validator_path(part_file_path)).await;
+        let _ = tokio::fs::remove_file(control_file_path(part_file_path)).await;
+    }
+
+    result
+}
+
+/// Removes the part files in the directory that haven't been written to for a while,
+/// along with their validators and aria2c control files
+async fn remove_stale_part_files(dir: &Path) {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_part_file = path.file_name().and_then(|x| x.to_str()).is_some_and(|x| {
+            x.starts_with('.')
+                && [".part", ".part.validator", ".part.aria2"]
+                    .iter()
+                    .any(|suffix| x.ends_with(suffix))
+        });
+        if !is_part_file {
+            continue;
+        }
+
+        let is_stale = entry
+            .metadata()
             .await
-            .map_err(|e| format!("Failed to create file: {:?}", e))?;
+            .and_then(|x| x.modified())
+            .ok()
+            .and_then(|x| x.elapsed().ok())
+            .is_some_and(|x| x > PART_FILE_TTL);
+        let in_use = PART_FILES_IN_USE.lock().is_ok_and(|in_use| {
+            let path = path.to_string_lossy();
+            in_use
+                .iter()
+                .any(|x| path.starts_with(x.to_string_lossy().as_ref()))
+        });
+        if !is_stale || in_use {
+            continue;
+        }
+
+        debug!(?path, "Removing stale part file");
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            warn!(?e, ?path, "Failed to remove stale part file");
+        }
+    }
+}
+
+/// Sends the request for the file.
+///
+/// If resuming, only the rest of the file after the given length is asked for,
+/// and only if it's still the same file the validator is of.
+async fn send_request(
+    request_info: &DownloadRequest,
+    options: &GenericDownloaderOptions,
+    resume: Option<(u64, &str)>,
+) -> Result<reqwest::Response, AppError> {
+    let url = &request_info.url;
+    let mut req = Client::base_with_url(url, Generic.name())?.headers(url.headers().clone());
+
+    if let Some(timeout) = options.timeout {
+        req = req.timeout(timeout.into());
+    }
+
+    if let Some((resume_from, validator)) = resume {
+        req = req
+            .header(header::RANGE, format!("bytes={resume_from}-"))
+            .header(header::IF_RANGE, validator);
+    }
+
+    wait_for_quota(url.url())
+        .await
+        .map_err(|e| AppError::other(e.to_string()))?;
+
+    let (client, req) = req.build_split();
+    let req = req.map_err(NetworkError::from)?;
+    let res = send_following_redirects(&client, req)
+        .await
+        .map_err(NetworkError::from)?;
+    record_quota(res.url(), res.status(), res.headers());
+
+    if res.status().is_redirection() {
+        return Err(AppError::other(format!(
+            "Too many redirects for {:?}",
+            url.url().as_str()
+        )));
+    }
+
+    Ok(res)
+}
+
+/// The first byte of a `206 Partial Content` response, from `Content-Range: bytes <start>-<end>/<size>`
+fn content_range_start(res: &reqwest::Response) -> Option<u64> {
+    if res.status() != StatusCode::PARTIAL_CONTENT {
+        return None;
+    }
+
+    res.headers()
+        .get(header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes ")?
+        .split_once('-')?
+        .0
+        .trim()
+        .parse()
+        .ok()
+}
+
+#[derive(Debug)]
+struct PartFile {
+    path: PathBuf,
+    /// How much of the file was already downloaded, if the download is resumed
+    resume_from: Option<u64>,
+}
 
-        while let Some(chunk) = res
-            .chunk()
+async fn write_response_to_file(
+    mut res: reqwest::Response,
+    request_info: &DownloadRequest,
+    part_file: PartFile,
+    file_path: PathBuf,
+) -> Result<DownloadResult, AppError> {
+    debug!(?file_path, ?part_file, "Writing to file");
+    let response_headers = res.headers().clone();
+    let out_file = match part_file.resume_from {
+        Some(_) => OpenOptions::new().append(true).open(&part_file.path).await,
+        None => File::create(&part_file.path).await,
+    }
+    .map_err(|e| format!("Failed to create file: {:?}", e))?;
+
+    let already_downloaded = part_file.resume_from.unwrap_or_default();
+
+    let progress = request_info.progress.as_ref();
+    if let Some(progress) = progress {
+        progress.update(
+            already_downloaded,
+            res.content_length().map(|x| x + already_downloaded),
+        );
+    }
+
+    // Resumed downloads are missing the start, so they get hashed later
+    let mut out_file = DownloadFileWriter::new(out_file, part_file.resume_from.is_none());
+
+    let throttle = Throttle::for_download();
+    // The server doesn't always say how big the file is, or tell the truth about it
+    let mut size_limit = SizeLimit::starting_from(already_downloaded);
+
+    while let Some(chunk) = res.chunk().await.map_err(NetworkError::from)? {
+        if let Err(e) = size_limit.add(chunk.len() as u64) {
+            drop(out_file);
+            let _ = tokio::fs::remove_file(&part_file.path).await;
+            return Err(e);
+        }
+
+        throttle.wait_for(chunk.len() as u64).await;
+
+        out_file
+            .write_chunk(&chunk)
             .await
-            .map_err(|e| format!("Failed to get chunk: {:?}", e))?
-        {
-            out_file
-                .write_all(&chunk)
-                .await
-                .map_err(|e| format!("Failed to write chunk: {:?}", e))?;
+            .map_err(|e| format!("Failed to write chunk: {:?}", e))?;
+
+        if let Some(progress) = progress {
+            progress.add(chunk.len() as u64);
         }
+    }
 
-        Ok(DownloadResult {
-            request: request_info.clone(),
-            path: file_path,
-        })
+    let sha256 = out_file
+        .finish()
+        .await
+        .map_err(|e| format!("Failed to write file: {:?}", e))?;
+
+    tokio::fs::rename(&part_file.path, &file_path)
+        .await
+        .map_err(|e| format!("Failed to move downloaded file: {:?}", e))?;
+
+    if let Some(progress) = progress {
+        progress.finish();
     }
+
+    Ok(DownloadResult {
+        request: request_info.clone(),
+        path: file_path,
+        sha256,
+        metadata: None,
+        downloader_chain: vec![],
+        response_headers,
+    })
 }
 
 fn url_to_filename(url: &Url, taken_filename_len: usize) -> Option<String> {
@@ -0,0 +1,407 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::{
+    file_name::{file_name_with_suffix, sanitize_file_name},
+    id::time_id,
+    temp_dir::TempDir,
+};
+use futures::StreamExt;
+use http::HeaderMap;
+use serde::{Deserialize, Serialize};
+use tokio::{
+    fs::File,
+    io::{AsyncWriteExt, BufWriter},
+    process::Command,
+};
+use tracing::{debug, info, trace};
+use url::Url;
+
+use super::{generic::MAX_FILENAME_LENGTH, DownloadRequest, DownloadResult, Downloader};
+use crate::{
+    common::{
+        request::{Client, RequestClient, USER_AGENT},
+        url::UrlHeaders,
+    },
+    downloaders::{
+        helpers::{
+            m3u8::{MediaPlaylist, Playlist},
+            size_limit::{check_size, max_download_size, SizeLimit},
+            throttle::Throttle,
+        },
+        DownloaderOptions, DownloaderReturn,
+    },
+    format_choice::FormatChoice,
+};
+
+/// How many segments are downloaded at the same time
+const SEGMENT_CONCURRENCY: usize = 8;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Hls;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Downloader for Hls {
+    fn description(&self) -> &'static str {
+        "Downloads HLS (m3u8) streams in the best available quality and remuxes the segments \
+         into a single file using ffmpeg."
+    }
+
+    async fn can_download(&self, req: &DownloadRequest) -> bool {
+        let url = req.url.url();
+
+        matches!(url.scheme(), "http" | "https")
+            && Path::new(url.path())
+                .extension()
+                .is_some_and(|x| x.eq_ignore_ascii_case("m3u8"))
+    }
+
+    async fn download(&self, request: &DownloadRequest) -> DownloaderReturn {
+        self.download_one(request).await
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HlsDownloaderOptions {
+    /// The name to save the file as, without the extension.
+    /// The unique ID is put before the extension (`<stem>.<id>.<ext>`).
+    file_name: Option<String>,
+    /// Only keep the audio and save it as an m4a file
+    #[serde(default)]
+    audio_only: bool,
+}
+impl HlsDownloaderOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_file_name<T>(mut self, file_name: Option<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        self.file_name = file_name.map(Into::into);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_audio_only(mut self, audio_only: bool) -> Self {
+        self.audio_only = audio_only;
+        self
+    }
+}
+impl From<HlsDownloaderOptions> for DownloaderOptions {
+    fn from(val: HlsDownloaderOptions) -> Self {
+        let val = serde_json::to_value(val)
+            .ok()
+            .and_then(|x| x.as_object().cloned())
+            .expect("Failed to serialize options");
+
+        val.into_iter().collect()
+    }
+}
+
+impl Hls {
+    #[must_use]
+    pub fn options() -> HlsDownloaderOptions {
+        HlsDownloaderOptions::new()
+    }
+
+    #[allow(clippy::too_many_lines)]
+    pub async fn download_one(
+        &self,
+        request: &DownloadRequest,
+    ) -> Result<DownloadResult, AppError> {
+        let url = &request.url;
+        let options = request
+            .downloader_options::<HlsDownloaderOptions>()
+            .unwrap_or_default();
+        let choice = FormatChoice::from_options(&request.downloader_options);
+        let audio_only = options.audio_only || choice == Some(FormatChoice::AudioOnly);
+        let max_height = match choice {
+            Some(FormatChoice::MaxHeight(height)) => Some(height),
+            _ => None,
+        };
+
+        info!(?url, dir = ?request.download_dir(), "Downloading with HLS downloader");
+
+        let id = time_id();
+        let extension = if audio_only { "m4a" } else { "mp4" };
+        let file_name = options
+            .file_name
+            .map(|x| sanitize_file_name(&x, MAX_FILENAME_LENGTH - 1 - id.len() - extension.len()))
+            .filter(|x| !x.is_empty())
+            .map_or_else(
+                || PathBuf::from(format!("{id}.{extension}")),
+                |x| file_name_with_suffix(&PathBuf::from(format!("{x}.{extension}")), &id),
+            );
+        let file_path = request.download_dir().join(file_name);
+
+        let temp_dir = TempDir::in_tmp_with_prefix("downloader-hub_hls-")
+            .map_err(|e| format!("Failed to create temporary directory for HLS: {e:?}"))?;
+
+        if let Some(progress) = &request.progress {
+            progress.update(0, None);
+        }
+
+        let streams = download_streams(request, audio_only, max_height, temp_dir.path()).await?;
+
+        let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+        let cmd = {
+            let mut cmd = cmd
+                .arg("-y")
+                .arg("-hide_banner")
+                .args(["-loglevel", "error"]);
+
+            cmd = match &streams {
+                Some(streams) => streams
+                    .iter()
+                    .fold(cmd, |cmd, stream| cmd.arg("-i").arg(stream)),
+                None => {
+                    debug!("Segments can't be joined, letting ffmpeg download the stream");
+
+                    let headers = url.headers().iter().fold(String::new(), |mut acc, (k, v)| {
+                        let _ = write!(acc, "{k}: {}\r\n", v.to_str().unwrap_or_default());
+                        acc
+                    });
+
+                    cmd = cmd.args(["-user_agent", USER_AGENT]);
+
+                    if !headers.is_empty() {
+                        cmd = cmd.arg("-headers").arg(headers);
+                    }
+
+                    // ffmpeg stops once the file gets this big, so going over the limit can be noticed
+                    if let Some(max_size) = max_download_size() {
+                        cmd = cmd.args(["-fs", &(max_size + 1).to_string()]);
+                    }
+
+                    cmd.arg("-i").arg(url.url().as_str())
+                }
+            };
+
+            // The separate audio track is always the last input
+            cmd = match streams.as_ref().map_or(1, Vec::len) {
+                _ if audio_only => cmd.args(["-map", "0:a", "-vn"]),
+                1 => cmd.args(["-map", "0:v?", "-map", "0:a?"]),
+                _ => cmd.args(["-map", "0:v?", "-map", "1:a?"]),
+            };
+
+            cmd.args(["-c", "copy", "-movflags", "+faststart"])
+                .arg(&file_path)
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+        };
+
+        debug!(?cmd, "Running ffmpeg command");
+
+        let output = cmd
+            .output()
+            .await
+            .map_err(|e| ExternalToolError::unavailable("ffmpeg", format!("{e:?}")))?;
+
+        trace!(?output, "ffmpeg output");
+
+        if !output.status.success() || !file_path.exists() {
+            let _ = tokio::fs::remove_file(&file_path).await;
+
+            return Err(ExternalToolError::failed(
+                "ffmpeg",
+                format!(
+                    "Failed to remux the stream: {stderr}",
+                    stderr = String::from_utf8_lossy(&output.stderr).trim(),
+                ),
+            )
+            .into());
+        }
+
+        let size = tokio::fs::metadata(&file_path)
+            .await
+            .map(|x| x.len())
+            .unwrap_or_default();
+
+        if let Err(e) = check_size(size) {
+            let _ = tokio::fs::remove_file(&file_path).await;
+
+            return Err(e);
+        }
+
+        if let Some(progress) = &request.progress {
+            progress.update(size, Some(size));
+            progress.finish();
+        }
+
+        Ok(DownloadResult {
+            request: request.clone(),
+            path: file_path,
+            sha256: None,
+            metadata: None,
+            downloader_chain: vec![],
+            response_headers: HeaderMap::new(),
+        })
+    }
+}
+
+/// Downloads the streams of the best variant of the playlist (that's at most `max_height` tall),
+/// each into a single file. The video stream comes first and the separate audio track (if any) last.
+///
+/// Returns `None` if the segments can't just be joined (eg. they're encrypted),
+/// in which case ffmpeg has to download the stream itself.
+async fn download_streams(
+    request: &DownloadRequest,
+    audio_only: bool,
+    max_height: Option<u32>,
+    dir: &Path,
+) -> Result<Option<Vec<PathBuf>>, AppError> {
+    let client = Client::for_downloader(Hls.name())?;
+    let headers = request.url.headers();
+
+    let playlists = match fetch_playlist(&client, headers, request.url.url()).await? {
+        Playlist::Media(playlist) => vec![playlist],
+        Playlist::Master(master) => {
+            let variant = max_height
+                .map_or_else(|| master.best_variant(), |x| master.best_variant_up_to(x))
+                .ok_or_else(|| "Playlist has no variants".to_string())?;
+            let audio = master.audio_rendition(variant);
+
+            debug!(?variant, ?audio, "Picked HLS variant");
+
+            let urls = match audio {
+                Some(audio) if audio_only => vec![&audio.url],
+                Some(audio) => vec![&variant.url, &audio.url],
+                None => vec![&variant.url],
+            };
+
+            let mut playlists = vec![];
+            for url in urls {
+                match fetch_playlist(&client, headers, url).await? {
+                    Playlist::Media(playlist) => playlists.push(playlist),
+                    Playlist::Master(_) => {
+                        return Err(AppError::other(format!(
+                            "Variant playlist {url} is not a media playlist"
+                        )));
+                    }
+                }
+            }
+            playlists
+        }
+    };
+
+    if !playlists.iter().all(MediaPlaylist::can_be_joined) {
+        return Ok(None);
+    }
+
+    // The video and audio streams end up in the same file, so they count towards the same limit
+    let mut size_limit = SizeLimit::for_download();
+    let mut streams = vec![];
+    for (i, playlist) in playlists.iter().enumerate() {
+        let path = dir.join(format!("{i}.{ext}", ext = playlist.segment_extension()));
+
+        download_segments(&client, request, playlist, &path, &mut size_limit).await?;
+
+        streams.push(path);
+    }
+
+    Ok(Some(streams))
+}
+
+async fn fetch_playlist(
+    client: &RequestClient,
+    headers: &UrlHeaders,
+    url: &Url,
+) -> Result<Playlist, String> {
+    let res = client
+        .get(url.as_str())
+        .headers(headers.clone())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request for playlist: {e:?}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get playlist: {e:?}"))?;
+
+    // Segments are relative to where the playlist ended up after redirects
+    let base_url = res.url().clone();
+
+    let text = res
+        .text()
+        .await
+        .map_err(|e| format!("Failed to get text from playlist: {e:?}"))?;
+
+    Playlist::parse(&text, &base_url)
+}
+
+/// Downloads the segments concurrently and writes them to the file in order
+async fn download_segments(
+    client: &RequestClient,
+    request: &DownloadRequest,
+    playlist: &MediaPlaylist,
+    file_path: &Path,
+    size_limit: &mut SizeLimit,
+) -> Result<(), AppError> {
+    trace!(
+        count = playlist.segments.len(),
+        ?file_path,
+        "Downloading HLS segments"
+    );
+
+    let out_file = File::create(file_path)
+        .await
+        .map_err(|e| format!("Failed to create file: {e:?}"))?;
+    let mut out_file = BufWriter::new(out_file);
+
+    let throttle = Throttle::for_download();
+    let headers = request.url.headers();
+    // Owned, since futures borrowing from the iterator aren't `Send` for the download future
+    let urls = playlist
+        .init_segment
+        .iter()
+        .chain(&playlist.segments)
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut segments = futures::stream::iter(urls)
+        .map(|url| async move {
+            client
+                .get(url.as_str())
+                .headers(headers.clone())
+                .send()
+                .await
+                .map_err(|e| format!("Failed to send request for segment {url}: {e:?}"))?
+                .error_for_status()
+                .map_err(|e| format!("Failed to get segment {url}: {e:?}"))?
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to get segment {url}: {e:?}"))
+        })
+        .buffered(SEGMENT_CONCURRENCY);
+
+    while let Some(segment) = segments.next().await {
+        let segment = segment?;
+
+        size_limit.add(segment.len() as u64)?;
+        throttle.wait_for(segment.len() as u64).await;
+
+        out_file
+            .write_all(&segment)
+            .await
+            .map_err(|e| format!("Failed to write segment: {e:?}"))?;
+
+        if let Some(progress) = &request.progress {
+            progress.add(segment.len() as u64);
+        }
+    }
+
+    out_file
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to write file: {e:?}"))?;
+
+    Ok(())
+}
@@ -0,0 +1,600 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Output, Stdio},
+    time::{Duration, Instant},
+};
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::{id::time_id, temp_dir::TempDir, temp_file::TempFile};
+use http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, info, trace, warn};
+use url::Url;
+
+use super::{DownloadRequest, DownloadResult, Downloader, DownloaderReturn};
+use crate::{
+    common::request::{
+        add_credentials, configured_cookie_lines, cookie_header, proxy_for, redact_credentials,
+        send_following_redirects, Client, USER_AGENT,
+    },
+    downloaders::helpers::size_limit::{max_download_size, too_large_error},
+    resume,
+};
+
+/// Set to `true` to record the stream for as long as it's live
+/// instead of only downloading what's there
+pub const RECORD_LIVE_OPTION: &str = "record-live";
+
+/// How often the recording is saved if it's not configured
+const DEFAULT_CHECKPOINT_INTERVAL: Duration = Duration::from_mins(5);
+
+/// The recording is split into chunks of about the checkpoint interval,
+/// so the finished ones can be saved while the rest is being recorded
+const CHUNK_PREFIX: &str = "chunk-";
+
+const CHUNK_LIST_FILE_NAME: &str = "chunks.txt";
+
+/// The longest ffmpeg waits between tries to reconnect to the stream
+const RECONNECT_DELAY_MAX: Duration = Duration::from_secs(30);
+
+/// How many times ffmpeg is started again while the stream is still live before giving up
+const MAX_RESTARTS: u32 = 5;
+
+const RESTART_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LiveStream;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Downloader for LiveStream {
+    fn description(&self) -> &'static str {
+        "Records live HLS and DASH streams until they end using ffmpeg, saving what was recorded \
+         every few minutes. Only used for downloads that ask for the stream to be recorded."
+    }
+
+    async fn can_download(&self, req: &DownloadRequest) -> bool {
+        req.downloader_option::<bool>(RECORD_LIVE_OPTION)
+            .unwrap_or_default()
+            && matches!(req.url.url().scheme(), "http" | "https")
+    }
+
+    async fn download(&self, request: &DownloadRequest) -> DownloaderReturn {
+        self.record(request).await
+    }
+}
+
+impl LiveStream {
+    /// Records the stream until it ends or the configured duration is up.
+    ///
+    /// If the download can be resumed, the recorded chunks are kept between tries,
+    /// so a crash only loses the chunk that was being recorded.
+    pub async fn record(&self, request: &DownloadRequest) -> Result<DownloadResult, AppError> {
+        let config = &Config::global().download;
+        let checkpoint_interval = config
+            .live_recording_checkpoint_interval
+            .map_or(DEFAULT_CHECKPOINT_INTERVAL, Into::into)
+            .max(Duration::from_secs(1));
+
+        let resume_dir = resume::resume_dir(request);
+        let mut recording_dir = resume_dir
+            .as_ref()
+            .map_or_else(
+                || TempDir::in_tmp_with_prefix("downloader-hub_live-"),
+                TempDir::absolute,
+            )
+            .map_err(|e| format!("Failed to create directory for the recording: {e:?}"))?;
+        let file_identifier = if resume_dir.is_some() {
+            recording_dir.no_delete_on_drop();
+            resume::file_identifier(recording_dir.path())
+        } else {
+            time_id()
+        };
+        let file_path = request
+            .download_dir()
+            .join(format!("{file_identifier}.live.mp4"));
+
+        let recorded = chunks(recording_dir.path()).await;
+        // The chunks from the tries before count towards the duration
+        let max_duration = config.live_recording_max_duration.map(|x| {
+            let recorded = u32::try_from(recorded.len()).unwrap_or(u32::MAX);
+
+            Duration::from(x).saturating_sub(checkpoint_interval.saturating_mul(recorded))
+        });
+
+        info!(
+            url = ?request.url.url().as_str(),
+            ?file_path,
+            ?max_duration,
+            recorded_chunks = recorded.len(),
+            "Recording live stream"
+        );
+
+        if max_duration != Some(Duration::ZERO) {
+            record_chunks(
+                request,
+                recording_dir.path(),
+                &file_path,
+                checkpoint_interval,
+                max_duration,
+            )
+            .await?;
+        }
+
+        let chunks = chunks(recording_dir.path()).await;
+        if chunks.is_empty() {
+            return Err(AppError::other("Nothing was recorded from the live stream"));
+        }
+
+        save_chunks(&chunks, recording_dir.path(), &file_path).await?;
+
+        let size = tokio::fs::metadata(&file_path)
+            .await
+            .map(|x| x.len())
+            .unwrap_or_default();
+
+        if let Some(progress) = &request.progress {
+            progress.update(size, Some(size));
+            progress.finish();
+        }
+
+        if resume_dir.is_some() {
+            if let Err(e) = tokio::fs::remove_dir_all(recording_dir.path()).await {
+                warn!(?e, dir = ?recording_dir.path(), "Failed to remove recording directory");
+            }
+        }
+
+        Ok(DownloadResult {
+            request: request.clone(),
+            path: file_path,
+            sha256: None,
+            metadata: None,
+            downloader_chain: vec![],
+            response_headers: HeaderMap::new(),
+        })
+    }
+}
+
+/// Records the stream into chunks in `dir` with ffmpeg,
+/// saving the finished ones to `file_path` every `checkpoint_interval`.
+///
+/// ffmpeg reconnects by itself when the connection drops. If it still stops with an error
+/// while the stream is live, it's started again to record the rest into the next chunks.
+/// The recording fails once that happens too often,
+/// so a partial recording isn't saved as if it were the whole stream.
+async fn record_chunks(
+    request: &DownloadRequest,
+    dir: &Path,
+    file_path: &Path,
+    checkpoint_interval: Duration,
+    max_duration: Option<Duration>,
+) -> Result<(), AppError> {
+    let started_at = Instant::now();
+    let mut restarts = 0;
+
+    loop {
+        let remaining = max_duration.map(|x| x.saturating_sub(started_at.elapsed()));
+        if remaining == Some(Duration::ZERO) {
+            return Ok(());
+        }
+
+        let (stream_url, headers) = stream_url(request).await?;
+        let first_chunk = chunks(dir).await.len();
+
+        let output = record_once(
+            request,
+            &stream_url,
+            &headers,
+            dir,
+            file_path,
+            first_chunk,
+            checkpoint_interval,
+            remaining,
+        )
+        .await?;
+
+        trace!(?output, "ffmpeg output");
+
+        if output.status.success() {
+            return Ok(());
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        warn!(status = ?output.status, %stderr, "ffmpeg stopped recording with an error");
+
+        // The stream ending looks like an error to ffmpeg sometimes
+        if !is_still_live(request).await {
+            debug!("Live stream ended");
+
+            if chunks(dir).await.is_empty() {
+                return Err(ExternalToolError::failed(
+                    "ffmpeg",
+                    format!("Failed to record the stream: {stderr}"),
+                )
+                .into());
+            }
+
+            return Ok(());
+        }
+
+        restarts += 1;
+        if restarts > MAX_RESTARTS {
+            return Err(ExternalToolError::failed(
+                "ffmpeg",
+                format!("Recording stopped while the stream was still live: {stderr}"),
+            )
+            .into());
+        }
+
+        info!(restarts, "Live stream is still going, recording the rest");
+        tokio::time::sleep(RESTART_DELAY).await;
+    }
+}
+
+/// Runs ffmpeg until the stream ends, it fails or the duration is up
+#[allow(clippy::too_many_arguments)]
+async fn record_once(
+    request: &DownloadRequest,
+    stream_url: &Url,
+    headers: &HeaderMap,
+    dir: &Path,
+    file_path: &Path,
+    first_chunk: usize,
+    checkpoint_interval: Duration,
+    max_duration: Option<Duration>,
+) -> Result<Output, AppError> {
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    cmd.arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .args(["-user_agent", USER_AGENT])
+        .args(["-reconnect", "1", "-reconnect_streamed", "1"])
+        .args([
+            "-reconnect_delay_max",
+            &RECONNECT_DELAY_MAX.as_secs().to_string(),
+        ]);
+
+    if !headers.is_empty() {
+        cmd.arg("-headers").arg(header_lines(headers));
+    }
+
+    match proxy_for(Some(LiveStream.name()), stream_url) {
+        Some(proxy) if proxy.scheme() == "http" => {
+            cmd.args(["-http_proxy", proxy.as_str()]);
+        }
+        Some(proxy) => {
+            warn!(scheme = ?proxy.scheme(), "ffmpeg only supports HTTP proxies, recording without one");
+        }
+        None => {}
+    }
+
+    cmd.arg("-i")
+        .arg(stream_url.as_str())
+        .args(["-map", "0:v?", "-map", "0:a?", "-c", "copy"]);
+
+    if let Some(max_duration) = max_duration {
+        cmd.args(["-t", &max_duration.as_secs().max(1).to_string()]);
+    }
+
+    cmd.args(["-f", "segment", "-segment_format", "mpegts"])
+        .args(["-reset_timestamps", "1"])
+        .args(["-segment_time", &checkpoint_interval.as_secs().to_string()])
+        .args(["-segment_start_number", &first_chunk.to_string()])
+        .arg(dir.join(format!("{CHUNK_PREFIX}%05d.ts")))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    debug!(
+        cmd = %redact_credentials(&format!("{cmd:?}"), stream_url),
+        "Running ffmpeg to record live stream"
+    );
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| ExternalToolError::unavailable("ffmpeg", format!("{e:?}")))?;
+    let output = child.wait_with_output();
+    tokio::pin!(output);
+
+    let output = loop {
+        tokio::select! {
+            output = &mut output => break output,
+            () = tokio::time::sleep(checkpoint_interval) => {
+                save_checkpoint(request, dir, file_path).await?;
+            }
+        }
+    }
+    .map_err(|e| ExternalToolError::failed("ffmpeg", format!("{e:?}")))?;
+
+    Ok(output)
+}
+
+/// Whether the stream is still there and hasn't ended, checked after ffmpeg stopped with an error.
+///
+/// Ended HLS playlists get an `#EXT-X-ENDLIST` tag and ended DASH manifests become `static`.
+/// If the manifest can't be fetched for some other reason than it being gone,
+/// the stream is taken to still be live, so ffmpeg gets another go at it.
+async fn is_still_live(request: &DownloadRequest) -> bool {
+    let Ok((stream_url, headers)) = stream_url(request).await else {
+        return false;
+    };
+
+    let Ok(client) = Client::without_redirects(Some(LiveStream.name())) else {
+        return true;
+    };
+    let Ok(req) = client.get(stream_url.as_str()).headers(headers).build() else {
+        return true;
+    };
+
+    let res = match send_following_redirects(&client, req).await {
+        Ok(res) => res,
+        Err(e) => {
+            debug!(?e, "Failed to check if the live stream is still going");
+            return true;
+        }
+    };
+
+    if matches!(
+        res.status(),
+        StatusCode::NOT_FOUND | StatusCode::GONE | StatusCode::FORBIDDEN
+    ) {
+        return false;
+    }
+
+    let Ok(manifest) = res.text().await else {
+        return true;
+    };
+
+    !manifest.contains("#EXT-X-ENDLIST") && !manifest.contains(r#"type="static""#)
+}
+
+/// The headers as ffmpeg's `-headers` takes them
+fn header_lines(headers: &HeaderMap) -> String {
+    use std::fmt::Write;
+
+    headers.iter().fold(String::new(), |mut acc, (k, v)| {
+        let _ = write!(acc, "{k}: {}\r\n", v.to_str().unwrap_or_default());
+        acc
+    })
+}
+
+/// Saves the chunks that are done to `file_path`, so they're there even if the recording crashes
+async fn save_checkpoint(
+    request: &DownloadRequest,
+    dir: &Path,
+    file_path: &Path,
+) -> Result<(), AppError> {
+    let mut chunks = chunks(dir).await;
+
+    let mut recorded_size = 0;
+    for chunk in &chunks {
+        recorded_size += tokio::fs::metadata(chunk)
+            .await
+            .map(|x| x.len())
+            .unwrap_or_default();
+    }
+
+    if let Some(progress) = &request.progress {
+        progress.update(recorded_size, None);
+    }
+
+    // Trying again would only hit the limit again, so the chunks aren't kept
+    if let Some(max_size) = max_download_size().filter(|x| recorded_size > *x) {
+        let _ = tokio::fs::remove_dir_all(dir).await;
+
+        return Err(too_large_error(max_size));
+    }
+
+    // The last one is still being recorded
+    chunks.pop();
+    if chunks.is_empty() {
+        return Ok(());
+    }
+
+    debug!(
+        count = chunks.len(),
+        ?file_path,
+        "Saving live stream checkpoint"
+    );
+
+    // The recording goes on even if a checkpoint fails, the next one might work
+    if let Err(e) = save_chunks(&chunks, dir, file_path).await {
+        warn!(?e, "Failed to save live stream checkpoint");
+    }
+
+    Ok(())
+}
+
+/// The recorded chunks in `dir`, in order
+async fn chunks(dir: &Path) -> Vec<PathBuf> {
+    let Ok(mut entries) = tokio::fs::read_dir(dir).await else {
+        return vec![];
+    };
+
+    let mut chunks = vec![];
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let path = entry.path();
+        let is_chunk = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| x.starts_with(CHUNK_PREFIX))
+            && path
+                .extension()
+                .is_some_and(|x| x.eq_ignore_ascii_case("ts"));
+
+        if is_chunk {
+            chunks.push(path);
+        }
+    }
+
+    // The chunk numbers are zero-padded, so they sort by name
+    chunks.sort();
+
+    chunks
+}
+
+/// Joins the chunks into a single playable file at `file_path`.
+///
+/// The file is replaced only once it's complete, so the last checkpoint is kept if this fails.
+async fn save_chunks(chunks: &[PathBuf], dir: &Path, file_path: &Path) -> Result<(), AppError> {
+    let list_path = dir.join(CHUNK_LIST_FILE_NAME);
+    let list = chunks
+        .iter()
+        .map(|x| format!("file '{}'", x.to_string_lossy().replace('\'', r"'\''")))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    tokio::fs::write(&list_path, list)
+        .await
+        .map_err(|e| format!("Failed to write the list of recorded chunks: {e:?}"))?;
+
+    let mut part_file_name = file_path.as_os_str().to_owned();
+    part_file_name.push(".part");
+    let part_file_path = PathBuf::from(part_file_name);
+
+    let output = Command::new(Config::global().dependency_paths.ffmpeg_path())
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .args(["-f", "concat", "-safe", "0"])
+        .arg("-i")
+        .arg(&list_path)
+        .args(["-c", "copy", "-movflags", "+faststart", "-f", "mp4"])
+        .arg(&part_file_path)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable("ffmpeg", format!("{e:?}")))?;
+
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(&part_file_path).await;
+
+        return Err(ExternalToolError::failed(
+            "ffmpeg",
+            format!(
+                "Failed to join the recorded chunks: {stderr}",
+                stderr = String::from_utf8_lossy(&output.stderr).trim(),
+            ),
+        )
+        .into());
+    }
+
+    tokio::fs::rename(&part_file_path, file_path)
+        .await
+        .map_err(|e| format!("Failed to move recording: {e:?}"))?;
+
+    Ok(())
+}
+
+/// The manifest of the stream and the headers to request it with,
+/// including the configured credentials and cookies for its host.
+///
+/// yt-dlp finds it if the URL is of the page the stream is on.
+async fn stream_url(request: &DownloadRequest) -> Result<(Url, HeaderMap), AppError> {
+    let url = request.url.url();
+
+    let (stream_url, mut headers) = if is_manifest_url(url) {
+        (url.clone(), request.url.headers().clone())
+    } else {
+        find_stream(url).await?
+    };
+
+    add_credentials(&stream_url, &mut headers);
+    if !headers.contains_key(header::COOKIE) {
+        if let Some(cookies) = cookie_header(&stream_url) {
+            headers.insert(header::COOKIE, cookies);
+        }
+    }
+
+    Ok((stream_url, headers))
+}
+
+#[derive(Debug, Deserialize)]
+struct YtDlpStream {
+    url: Url,
+    #[serde(default)]
+    http_headers: HashMap<String, String>,
+}
+
+/// Asks yt-dlp for the manifest of the stream on the page and the headers it needs
+async fn find_stream(url: &Url) -> Result<(Url, HeaderMap), AppError> {
+    let mut cmd = Command::new(Config::global().dependency_paths.yt_dlp_path());
+    cmd.args(["--no-config", "--no-playlist", "--no-warnings"])
+        .args(["--format", "best", "--dump-json"]);
+
+    if let Some(proxy) = proxy_for(Some(LiveStream.name()), url) {
+        cmd.args(["--proxy", proxy.as_str()]);
+    }
+
+    let cookie_lines = configured_cookie_lines();
+    let _cookie_file = if cookie_lines.is_empty() {
+        None
+    } else {
+        let mut cookie_file = TempFile::with_prefix("cookie-headers-")
+            .map_err(|e| format!("Failed to create temporary file for yt-dlp cookies: {e:?}"))?;
+
+        cookie_file
+            .file_mut()
+            .write_all(
+                format!(
+                    "# Netscape HTTP Cookie File\n{cookie_lines}\n",
+                    cookie_lines = cookie_lines.join("\n")
+                )
+                .as_bytes(),
+            )
+            .map_err(|e| format!("Failed to write cookies to file: {e:?}"))?;
+
+        cmd.arg("--cookies").arg(cookie_file.path());
+
+        Some(cookie_file)
+    };
+
+    let output = cmd
+        .arg(url.as_str())
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable("yt-dlp", format!("{e:?}")))?;
+
+    if !output.status.success() {
+        return Err(ExternalToolError::failed(
+            "yt-dlp",
+            format!(
+                "Failed to find the live stream: {stderr}",
+                stderr = String::from_utf8_lossy(&output.stderr).trim(),
+            ),
+        )
+        .into());
+    }
+
+    let stream = serde_json::from_slice::<YtDlpStream>(&output.stdout)
+        .map_err(|e| AppError::other(format!("yt-dlp didn't find a live stream: {e}")))?;
+
+    debug!(url = ?stream.url.as_str(), "Found live stream");
+
+    let headers = stream
+        .http_headers
+        .iter()
+        .filter_map(|(k, v)| {
+            Some((
+                HeaderName::from_bytes(k.as_bytes()).ok()?,
+                HeaderValue::from_str(v).ok()?,
+            ))
+        })
+        .collect();
+
+    Ok((stream.url, headers))
+}
+
+fn is_manifest_url(url: &Url) -> bool {
+    Path::new(url.path())
+        .extension()
+        .and_then(|x| x.to_str())
+        .is_some_and(|x| x.eq_ignore_ascii_case("m3u8") || x.eq_ignore_ascii_case("mpd"))
+}
@@ -1,9 +1,13 @@
+pub mod gallery_dl;
 pub mod generic;
+pub mod hls;
+pub mod live;
 pub mod music;
 pub mod yt_dlp;
 
 use std::sync::Arc;
 
+use app_config::Config;
 use once_cell::sync::Lazy;
 
 pub use super::{
@@ -19,9 +23,14 @@ pub static AVAILABLE_DOWNLOADERS: Lazy<Vec<DownloaderEntry>> = Lazy::new(availab
 
 fn all_downloaders() -> Vec<DownloaderEntry> {
     vec![
+        // Only takes the downloads that ask for it, so it goes before yt-dlp takes them all
+        Arc::new(live::LiveStream),
+        // Only takes `.m3u8` URLs, which it downloads faster than yt-dlp
+        Arc::new(hls::Hls),
         Arc::new(yt_dlp::YtDlp),
         Arc::new(generic::Generic),
         Arc::new(music::Music),
+        Arc::new(gallery_dl::GalleryDl),
     ]
 }
 
@@ -29,6 +38,7 @@ fn all_downloaders() -> Vec<DownloaderEntry> {
 fn available_downloaders() -> Vec<DownloaderEntry> {
     all_downloaders()
         .into_iter()
+        .filter(|x| !Config::global().handlers.is_downloader_disabled(x.name()))
         .filter(|x| x.can_run())
         .collect()
 }
@@ -1,15 +1,30 @@
 pub mod spotifydown;
 pub mod yams;
 
-use std::path::{Path, PathBuf};
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
 
+use app_config::Config;
+use app_errors::AppError;
+use app_helpers::file_name::sanitize_file_name;
+use http::HeaderMap;
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
-use tracing::warn;
+use tokio::fs;
+use tracing::{debug, trace, warn};
 use url::Url;
 
 use super::{DownloadRequest, Downloader, DownloaderReturn};
-use crate::downloaders::DownloadResult;
+use crate::{
+    downloaders::{DownloadResult, DownloaderOptions},
+    fixers::handlers::tag_audio::{tag_downloaded_file, AudioTags, AUDIO_TAGS_OPTION},
+    thumbnail::embed_thumbnail,
+};
+
+const MAX_LIBRARY_NAME_LENGTH: usize = 100;
+const ALBUM_PLAYLIST_FILE_NAME: &str = "album.m3u";
 
 static HANDLERS: Lazy<Vec<DownloadHandler>> = Lazy::new(|| {
     vec![
@@ -46,10 +61,32 @@ impl Downloader for Music {
 
             match handler.download(req.download_dir(), song_url).await {
                 Ok(path) => {
+                    let options = req
+                        .downloader_options::<MusicDownloaderOptions>()
+                        .unwrap_or_default();
+                    let tags = req.downloader_option::<AudioTags>(AUDIO_TAGS_OPTION);
+
+                    let embed_cover = options
+                        .embed_cover
+                        .or_else(|| embed_thumbnail(&req.downloader_options))
+                        .unwrap_or(true);
+
+                    let path = tag_song(tags.clone(), embed_cover, path).await;
+
+                    let path = if options.library_layout {
+                        move_to_library(req.download_dir(), tags.unwrap_or_default(), path).await
+                    } else {
+                        path
+                    };
+
                     return Ok(DownloadResult {
                         path,
                         request: req.clone(),
-                    })
+                        sha256: None,
+                        metadata: None,
+                        downloader_chain: vec![],
+                        response_headers: HeaderMap::new(),
+                    });
                 }
                 Err(e) => {
                     warn!(?e, "Failed to download song");
@@ -57,11 +94,173 @@ impl Downloader for Music {
             }
         }
 
-        Err("No handler succeeded for song".to_string())
+        Err(AppError::other("No handler succeeded for song"))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MusicDownloaderOptions {
+    /// Place the song in an `artist/album/track` folder structure
+    /// and add it to the album's `album.m3u` playlist
+    #[serde(default)]
+    library_layout: bool,
+    /// Embed the cover art into the song.
+    /// Defaults to the `embed-thumbnail` option, or `true` if that isn't set either
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed_cover: Option<bool>,
+}
+impl MusicDownloaderOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The options set with `--music-library-layout` and `--music-embed-cover`
+    #[must_use]
+    pub fn from_config() -> Self {
+        let config = &Config::global().download;
+
+        Self {
+            library_layout: config.music_library_layout,
+            embed_cover: config.music_embed_cover,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_library_layout(mut self, library_layout: bool) -> Self {
+        self.library_layout = library_layout;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_embed_cover(mut self, embed_cover: bool) -> Self {
+        self.embed_cover = Some(embed_cover);
+        self
+    }
+}
+impl From<MusicDownloaderOptions> for DownloaderOptions {
+    fn from(val: MusicDownloaderOptions) -> Self {
+        let val = serde_json::to_value(val)
+            .ok()
+            .and_then(|x| x.as_object().cloned())
+            .expect("Failed to serialize options");
+
+        val.into_iter().collect()
+    }
+}
+
+/// Writes the song metadata found by the extractor into the file.
+/// Tagging is best-effort, so the untagged file is kept if it fails.
+async fn tag_song(tags: Option<AudioTags>, embed_cover: bool, path: PathBuf) -> PathBuf {
+    let Some(mut tags) = tags else {
+        return path;
+    };
+
+    if !embed_cover {
+        tags.cover_url = None;
+        tags.cover_path = None;
+    }
+
+    tag_downloaded_file(tags, path).await
+}
+
+/// Moves the song into `<download dir>/<artist>/<album>/<track>.<ext>`
+/// and appends it to the album playlist.
+/// If anything fails the song is left where it was downloaded.
+async fn move_to_library(download_dir: &Path, tags: AudioTags, path: PathBuf) -> PathBuf {
+    let library_name = |name: Option<String>, fallback: &str| {
+        let name = name
+            .map(|x| sanitize_file_name(&x, MAX_LIBRARY_NAME_LENGTH))
+            .unwrap_or_default();
+
+        if name.is_empty() {
+            fallback.to_string()
+        } else {
+            name
+        }
+    };
+
+    let file_stem = path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or("track")
+        .to_string();
+
+    let artist = library_name(tags.album_artist.or(tags.artist), "Unknown Artist");
+    let album = library_name(tags.album, "Unknown Album");
+    let title = library_name(tags.title, &file_stem);
+
+    let album_dir = download_dir.join(artist).join(album);
+
+    let mut file_name = match tags.track_number {
+        Some(track_number) => format!("{track_number:02} - {title}"),
+        None => title,
+    };
+    if let Some(ext) = path.extension().and_then(OsStr::to_str) {
+        file_name.push('.');
+        file_name.push_str(ext);
+    }
+    let new_path = album_dir.join(&file_name);
+
+    debug!(?path, ?new_path, "Moving song to library layout");
+
+    if let Err(e) = fs::create_dir_all(&album_dir).await {
+        warn!(?e, ?album_dir, "Failed to create album directory");
+        return path;
+    }
+
+    if let Err(e) = fs::rename(&path, &new_path).await {
+        warn!(
+            ?e,
+            ?path,
+            ?new_path,
+            "Failed to move song to album directory"
+        );
+        return path;
     }
+
+    if let Err(e) = add_to_album_playlist(&album_dir, &file_name).await {
+        warn!(?e, ?album_dir, "Failed to update album playlist");
+    }
+
+    new_path
+}
+
+async fn add_to_album_playlist(album_dir: &Path, file_name: &str) -> std::io::Result<()> {
+    let playlist_path = album_dir.join(ALBUM_PLAYLIST_FILE_NAME);
+
+    let existing = match fs::read_to_string(&playlist_path).await {
+        Ok(x) => x,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    if existing.lines().any(|x| x == file_name) {
+        trace!(?playlist_path, "Song already in album playlist");
+        return Ok(());
+    }
+
+    let mut playlist = if existing.is_empty() {
+        "#EXTM3U\n".to_string()
+    } else {
+        existing
+    };
+    if !playlist.ends_with('\n') {
+        playlist.push('\n');
+    }
+    playlist.push_str(file_name);
+    playlist.push('\n');
+
+    fs::write(&playlist_path, playlist).await
 }
 
 impl Music {
+    #[must_use]
+    pub fn options() -> MusicDownloaderOptions {
+        MusicDownloaderOptions::default()
+    }
+
     pub fn supports(song_url: &Url) -> bool {
         HANDLERS.iter().any(|handler| handler.supports(song_url))
     }
@@ -1,24 +1,172 @@
 use std::{
     io::Write,
     ops::Sub,
-    path::PathBuf,
-    process,
+    path::{Path, PathBuf},
+    process::{self, Stdio},
+    str::FromStr,
     time::{Duration, SystemTime},
 };
 
-use app_config::Config;
+use app_config::{credentials::Credentials, Config};
+use app_errors::{AppError, ExternalToolError};
 use app_helpers::{id::time_id, temp_dir::TempDir, temp_file::TempFile};
-use http::header;
+use http::{header, HeaderMap};
 use serde::{Deserialize, Serialize};
-use tokio::process::Command;
-use tracing::{debug, trace};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader},
+    process::Command,
+};
+use tracing::{debug, trace, warn};
+use url::Url;
 
 use super::{generic, DownloadRequest, DownloadResult, Downloader, DownloaderReturn};
-use crate::common::request::USER_AGENT;
+use crate::{
+    common::request::{
+        configured_cookie_lines, credentials_for, proxy_for, redact_credentials, USER_AGENT,
+    },
+    downloaders::{
+        helpers::{
+            size_limit::{check_size, max_download_size, too_large_error},
+            throttle::Throttle,
+        },
+        DownloaderOptions, MediaMetadata, ProgressReporter,
+    },
+    format_choice::FormatChoice,
+    media_policy::{resolve_media_policy, MediaPolicy, MEDIA_POLICY_OPTION},
+    resume,
+    thumbnail::embed_thumbnail,
+};
+
+/// Marks the progress lines yt-dlp prints so they can be told apart from the rest of the output
+const PROGRESS_LINE_PREFIX: &str = "downloader-hub-progress:";
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct YtDlp;
 
+/// Arguments added to the yt-dlp command for the request, after the configured ones
+pub const YT_DLP_EXTRA_ARGS_OPTION: &str = "yt-dlp-extra-args";
+
+/// Also get the subtitles of the video, see [`SubtitleMode`]
+pub const YT_DLP_SUBTITLES_OPTION: &str = "yt-dlp-subtitles";
+
+/// Languages of the subtitles to get, eg. `["en", "de"]`
+pub const YT_DLP_SUBTITLE_LANGS_OPTION: &str = "yt-dlp-subtitle-langs";
+
+/// Two-letter code of the country yt-dlp pretends to be in, eg. `US`.
+/// Takes precedence over the configured one.
+pub const YT_DLP_GEO_BYPASS_COUNTRY_OPTION: &str = "yt-dlp-geo-bypass-country";
+
+/// Subtitles are in this language if none are asked for
+const DEFAULT_SUBTITLE_LANG: &str = "en";
+
+/// What's done with the subtitles of the video
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SubtitleMode {
+    /// Saved as `.srt` files next to the video
+    Sidecar,
+    /// Muxed into the video file itself
+    Embed,
+}
+impl FromStr for SubtitleMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "sidecar" | "srt" => Ok(Self::Sidecar),
+            "embed" | "mux" => Ok(Self::Embed),
+            s => Err(format!(
+                "Invalid subtitle mode: {s:?}. Expected `sidecar` or `embed`"
+            )),
+        }
+    }
+}
+impl From<SubtitleMode> for serde_json::Value {
+    fn from(value: SubtitleMode) -> Self {
+        serde_json::to_value(value).expect("Failed to serialize subtitle mode")
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct YtDlpOptions {
+    /// Use yt-dlp's own downloader instead of handing the stream off to ffmpeg.
+    /// Needed for sites that only keep the stream session alive
+    /// while yt-dlp sends heartbeats (eg. Niconico).
+    #[serde(default)]
+    native_downloader: bool,
+    /// Split the video into one file per chapter, named after the chapters.
+    /// Videos without chapters are kept whole.
+    #[serde(default)]
+    split_chapters: bool,
+    /// Passed to yt-dlp as-is, eg. `--live-from-start`
+    #[serde(default, rename = "yt-dlp-extra-args")]
+    extra_args: Vec<String>,
+    #[serde(default, rename = "yt-dlp-subtitles")]
+    subtitles: Option<SubtitleMode>,
+    /// Defaults to English if empty, `all` gets all of them
+    #[serde(default, rename = "yt-dlp-subtitle-langs")]
+    subtitle_langs: Vec<String>,
+    #[serde(default, rename = "yt-dlp-geo-bypass-country")]
+    geo_bypass_country: Option<String>,
+    /// Proxy only used to get past the region check, eg. `socks5://localhost:1080`.
+    /// Takes precedence over the configured one.
+    #[serde(default, rename = "yt-dlp-geo-verification-proxy")]
+    geo_verification_proxy: Option<Url>,
+}
+impl YtDlpOptions {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub const fn with_native_downloader(mut self, native_downloader: bool) -> Self {
+        self.native_downloader = native_downloader;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_split_chapters(mut self, split_chapters: bool) -> Self {
+        self.split_chapters = split_chapters;
+        self
+    }
+
+    #[must_use]
+    pub fn with_extra_args(mut self, extra_args: Vec<String>) -> Self {
+        self.extra_args = extra_args;
+        self
+    }
+
+    #[must_use]
+    pub fn with_geo_bypass(
+        mut self,
+        country: Option<String>,
+        verification_proxy: Option<Url>,
+    ) -> Self {
+        self.geo_bypass_country = country;
+        self.geo_verification_proxy = verification_proxy;
+        self
+    }
+
+    #[must_use]
+    pub fn with_subtitles(mut self, mode: Option<SubtitleMode>, langs: Vec<String>) -> Self {
+        self.subtitles = mode;
+        self.subtitle_langs = langs;
+        self
+    }
+}
+impl From<YtDlpOptions> for DownloaderOptions {
+    fn from(val: YtDlpOptions) -> Self {
+        let val = serde_json::to_value(val)
+            .ok()
+            .and_then(|x| x.as_object().cloned())
+            .expect("Failed to serialize options");
+
+        val.into_iter().collect()
+    }
+}
+
 #[async_trait::async_trait]
 #[typetag::serde]
 impl Downloader for YtDlp {
@@ -33,46 +181,116 @@ impl Downloader for YtDlp {
     async fn download(&self, req: &DownloadRequest) -> DownloaderReturn {
         self.download_one(req).await
     }
+
+    async fn download_all(&self, req: &DownloadRequest) -> Vec<DownloaderReturn> {
+        match self.download_many(req).await {
+            Ok(x) => x.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        }
+    }
 }
 
 impl YtDlp {
+    /// Only returns the first file if the video is split into chapters
+    /// or comes with subtitle files. Use [`YtDlp::download_many`] to get all of them.
+    pub async fn download_one(
+        &self,
+        request: &DownloadRequest,
+    ) -> Result<DownloadResult, AppError> {
+        self.download_many(request)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| AppError::other("yt-dlp finished but file does not exist."))
+    }
+
     #[allow(clippy::too_many_lines)]
-    pub async fn download_one(&self, request: &DownloadRequest) -> Result<DownloadResult, String> {
+    pub async fn download_many(
+        &self,
+        request: &DownloadRequest,
+    ) -> Result<Vec<DownloadResult>, AppError> {
         let yt_dlp = Config::global().dependency_paths.yt_dlp_path();
         trace!("`yt-dlp' binary: {:?}", &yt_dlp);
-        let temp_dir = TempDir::in_tmp_with_prefix("downloader-hub_yt-dlp-")
+        let resume_dir = resume::resume_dir(request);
+        let resuming = resume_dir.is_some();
+        let mut temp_dir = resume_dir
+            .as_ref()
+            .map_or_else(
+                || TempDir::in_tmp_with_prefix("downloader-hub_yt-dlp-"),
+                TempDir::absolute,
+            )
             .map_err(|e| format!("Failed to create temporary directory for yt-dlp: {e:?}"))?;
-        let output_template = get_output_template(temp_dir.path());
+        let file_identifier = if resuming {
+            debug!(dir = ?temp_dir.path(), "Downloading to resumable directory");
+            // Kept if the download fails, so the next try can continue from the partial files
+            temp_dir.no_delete_on_drop();
+            resume::file_identifier(temp_dir.path())
+        } else {
+            time_id()
+        };
+        let output_template = get_output_template(temp_dir.path(), &file_identifier);
+        let chapter_output_template =
+            get_chapter_output_template(temp_dir.path(), &file_identifier);
+        let options = request
+            .downloader_options::<YtDlpOptions>()
+            .unwrap_or_default();
 
         let parsed_url = request.url.url();
         let host_str = parsed_url.host_str().unwrap_or_default();
         let in_a_year = SystemTime::now()
-            .checked_add(Duration::from_secs(60 * 60 * 24 * 365))
+            .checked_add(Duration::from_hours(24 * 365))
             .unwrap_or_else(SystemTime::now)
             .duration_since(SystemTime::UNIX_EPOCH)
             .unwrap_or_else(|_| Duration::from_secs(0))
             .as_secs();
 
-        let cookie_values = request
-            .url
-            .headers()
-            .get_all(header::COOKIE)
-            .into_iter()
-            .flat_map(|x| x.to_str())
-            .flat_map(|x| {
-                x.split("; ")
-                    .map(|x| x.splitn(2, '=').collect::<Vec<&str>>())
-                    .filter(|x| x.len() == 2)
-                    .map(|x| (x[0].trim(), x[1].trim()))
-                    .map(|(k, v)| {
-                        format!(
-                            "{host}\tFALSE\t/\tTRUE\t{expires}\t{k}\t{v}",
-                            host = host_str,
-                            expires = in_a_year,
-                        )
-                    })
-            })
-            .collect::<Vec<String>>();
+        // yt-dlp sends the headers it's given to every host the download leads to,
+        // so the credentials are given in ways it only uses for this host instead
+        let mut download_url = parsed_url.clone();
+        let mut credential_cookies = None;
+        match credentials_for(parsed_url) {
+            Some(Credentials::Basic { username, password }) => {
+                // Turned into the `Authorization` header of only the requests to this URL
+                let _ = download_url.set_username(username);
+                let _ = download_url.set_password(Some(password));
+            }
+            Some(Credentials::Header { name, value }) if name.eq_ignore_ascii_case("cookie") => {
+                credential_cookies = Some(value.as_str());
+            }
+            Some(_) => {
+                warn!(
+                    host = ?host_str,
+                    "yt-dlp can't limit the configured credentials to the host, leaving them out"
+                );
+            }
+            None => {}
+        }
+        // Headers come last so they override the configured cookies
+        let mut cookie_values = configured_cookie_lines();
+        cookie_values.extend(
+            request
+                .url
+                .headers()
+                .get_all(header::COOKIE)
+                .into_iter()
+                .flat_map(|x| x.to_str())
+                .chain(credential_cookies)
+                .flat_map(|x| {
+                    x.split("; ")
+                        .map(|x| x.splitn(2, '=').collect::<Vec<&str>>())
+                        .filter(|x| x.len() == 2)
+                        .map(|x| (x[0].trim(), x[1].trim()))
+                        .map(|(k, v)| {
+                            format!(
+                                "{host}\tFALSE\t/\tTRUE\t{expires}\t{k}\t{v}",
+                                host = host_str,
+                                expires = in_a_year,
+                            )
+                        })
+                }),
+        );
+
+        let headers = request.url.headers();
 
         debug!("template: {:?}", &output_template);
         let mut cmd = Command::new(yt_dlp);
@@ -80,14 +298,112 @@ impl YtDlp {
             let mut cmd = cmd
                 .arg("--no-check-certificate")
                 .args(["--socket-timeout", "120"])
-                .arg("--no-part")
+                // The `.part` files are what's continued from when resuming
+                .args(if resuming {
+                    ["--part", "--continue"].as_slice()
+                } else {
+                    ["--no-part"].as_slice()
+                })
                 .arg("--no-mtime")
                 .arg("--no-embed-metadata")
+                .arg("--write-info-json")
                 .arg("--no-config")
                 .arg("--no-playlist");
 
+            if options.native_downloader {
+                cmd = cmd.args(["--downloader", "native"]);
+            }
+
+            if let Some(proxy) = proxy_for(Some(Self.name()), parsed_url) {
+                cmd = cmd.args(["--proxy", proxy.as_str()]);
+            }
+
+            let download_config = &Config::global().download;
+            if let Some(country) = options
+                .geo_bypass_country
+                .as_ref()
+                .or(download_config.yt_dlp_geo_bypass_country.as_ref())
+            {
+                cmd = cmd.args(["--geo-bypass-country", country]);
+            }
+            if let Some(proxy) = options
+                .geo_verification_proxy
+                .as_ref()
+                .or(download_config.yt_dlp_geo_verification_proxy.as_ref())
+            {
+                cmd = cmd.args(["--geo-verification-proxy", proxy.as_str()]);
+            }
+
+            if let Some(limit_rate) = Throttle::lowest_rate() {
+                cmd = cmd.args(["--limit-rate", &limit_rate.to_string()]);
+            }
+
+            if let Some(max_size) = max_download_size() {
+                cmd = cmd.args(["--max-filesize", &max_size.to_string()]);
+            }
+
+            if options.split_chapters {
+                let chapter_output_template = chapter_output_template
+                    .to_str()
+                    .ok_or_else(|| "Failed to convert path to string".to_string())?;
+
+                cmd = cmd
+                    .arg("--split-chapters")
+                    .args(["--output", &format!("chapter:{chapter_output_template}")]);
+            }
+
+            if let Some(mode) = options.subtitles {
+                let langs = if options.subtitle_langs.is_empty() {
+                    DEFAULT_SUBTITLE_LANG.to_string()
+                } else {
+                    options.subtitle_langs.join(",")
+                };
+
+                cmd = cmd.arg("--write-subs").args(["--sub-langs", &langs]);
+                cmd = match mode {
+                    SubtitleMode::Sidecar => cmd.args(["--convert-subs", "srt"]),
+                    // yt-dlp converts them to what the container supports
+                    SubtitleMode::Embed => cmd.arg("--embed-subs"),
+                };
+            }
+
+            if embed_thumbnail(&request.downloader_options).unwrap_or_default() {
+                // WebM can't have a cover, so those files are put into containers that can
+                cmd = cmd
+                    .arg("--embed-thumbnail")
+                    .args(["--convert-thumbnails", "jpg"])
+                    .args(["--merge-output-format", "mp4/mkv"])
+                    .args(["--remux-video", "webm>mkv"]);
+            }
+
+            let choice = FormatChoice::from_options(&request.downloader_options);
+            let mut policy =
+                resolve_media_policy(request.downloader_option::<MediaPolicy>(MEDIA_POLICY_OPTION));
+            match choice {
+                Some(FormatChoice::MaxHeight(height)) => policy.max_video_height = Some(height),
+                Some(FormatChoice::Best) => policy.max_video_height = None,
+                _ => {}
+            }
+
+            if choice == Some(FormatChoice::AudioOnly) {
+                cmd = cmd.args(["--format", "bestaudio/best"]);
+            }
+
+            let format_sort = format_sort(&policy);
+            if !format_sort.is_empty() {
+                debug!(?format_sort, "Sorting formats");
+
+                cmd = cmd.args(["--format-sort", &format_sort.join(",")]);
+            }
+
+            if request.progress.is_some() {
+                cmd = cmd
+                    .args(["--progress", "--newline"])
+                    .args(["--progress-template", &progress_template()]);
+            }
+
             if !cookie_values.is_empty() {
-                debug!("Adding cookie headers: {:?}", &cookie_values);
+                debug!(count = cookie_values.len(), "Adding cookies");
 
                 let mut cookie_file = TempFile::with_prefix("cookie-headers-").map_err(|e| {
                     format!("Failed to create temporary file for yt-dlp cookie headers: {e:?}")
@@ -113,9 +429,7 @@ impl YtDlp {
                     generic::MAX_FILENAME_LENGTH.sub(5).to_string().as_str(),
                 ])
                 .args(
-                    request
-                        .url
-                        .headers()
+                    headers
                         .iter()
                         .filter(|x| x.0 != header::COOKIE)
                         .flat_map(|(k, v)| {
@@ -133,13 +447,22 @@ impl YtDlp {
                 ])
                 .args(["--user-agent", USER_AGENT])
                 .args(["--no-simulate", "--print", "after_move:filepath"])
+                // Last, so they take precedence over the ones above
+                .args(&Config::global().download.yt_dlp_extra_args)
+                .args(&options.extra_args)
                 // .arg("--verbose")
-                .arg(request.url.url().as_str());
+                .arg(download_url.as_str());
 
             cmd
         };
-        debug!("Running cmd: {:?}", &cmd);
-        let cmd_output = cmd.output().await;
+        debug!(
+            "Running cmd: {}",
+            redact_credentials(
+                &format!("{cmd:?}").replace(download_url.as_str(), parsed_url.as_str()),
+                parsed_url,
+            )
+        );
+        let cmd_output = output_with_progress(cmd, request.progress.as_ref()).await;
         trace!("Cmd output: {:?}", &cmd_output);
         let new_file_path = match cmd_output {
             Ok(process::Output {
@@ -149,10 +472,19 @@ impl YtDlp {
             }) if status.success() => {
                 let output = String::from_utf8(stdout)
                     .map_err(|e| format!("Failed to convert output to UTF-8: {e:?}"))?;
-                let output_path = PathBuf::from(output.trim());
+                let output = output.trim();
+
+                // yt-dlp skips files over `--max-filesize` without failing
+                if output.is_empty() {
+                    if let Some(max_size) = max_download_size() {
+                        return Err(too_large_error(max_size));
+                    }
+                }
+
+                let output_path = PathBuf::from(output);
 
                 if !output_path.exists() {
-                    return Err("yt-dlp finished but file does not exist.".to_string());
+                    return Err(AppError::other("yt-dlp finished but file does not exist."));
                 }
 
                 debug!("yt-dlp successful download to file: {:?}", output_path);
@@ -163,37 +495,309 @@ impl YtDlp {
                 stderr,
                 status: _,
             }) if is_image_error(stderr.clone()) => {
-                return generic::Generic.download(request).await
+                return generic::Generic.download(request).await.map(|x| vec![x])
             }
             _ => {
-                return Err(format!("yt-dlp failed downloading meme: {cmd_output:?}"));
+                return Err(ExternalToolError::failed(
+                    "yt-dlp",
+                    format!("Failed downloading meme: {cmd_output:?}"),
+                )
+                .into());
             }
         };
 
         if !new_file_path.exists() {
-            return Err("yt-dlp finished but file does not exist.".to_string());
+            return Err(AppError::other("yt-dlp finished but file does not exist."));
         }
 
-        let final_file_path = request
-            .download_dir()
-            .join(new_file_path.file_name().unwrap_or_default());
+        // The size yt-dlp checks is only an estimate, and merged formats aren't checked together
+        if let Ok(metadata) = tokio::fs::metadata(&new_file_path).await {
+            check_size(metadata.len())?;
+        }
 
-        std::fs::copy(&new_file_path, &final_file_path).map_err(|e| {
-            format!("Failed to copy file from {new_file_path:?} to {final_file_path:?}: {e:?}")
-        })?;
+        // Missing subtitles aren't an error, not every video has them
+        let subtitle_paths = if options.subtitles == Some(SubtitleMode::Sidecar) {
+            subtitle_files(temp_dir.path(), &new_file_path)
+        } else {
+            vec![]
+        };
+
+        let mut file_paths = vec![];
+        if options.split_chapters {
+            file_paths = chapter_files(temp_dir.path(), &file_identifier, &new_file_path)?;
+
+            debug!(?file_paths, "Got chapter files");
+        }
+        // The video didn't have any chapters
+        if file_paths.is_empty() {
+            file_paths.push(new_file_path);
+        }
+
+        let metadata = read_info_file(temp_dir.path(), &file_identifier).await;
+
+        let mut results = vec![];
+        for new_file_path in file_paths {
+            let final_file_path = request
+                .download_dir()
+                .join(new_file_path.file_name().unwrap_or_default());
 
-        Ok(DownloadResult {
-            request: request.clone(),
-            path: final_file_path,
+            std::fs::copy(&new_file_path, &final_file_path).map_err(|e| {
+                format!(
+                    "Failed to copy file from {} to {}: {e:?}",
+                    new_file_path.display(),
+                    final_file_path.display()
+                )
+            })?;
+
+            results.push(DownloadResult {
+                request: request.clone(),
+                path: final_file_path,
+                sha256: None,
+                metadata: metadata.clone(),
+                downloader_chain: vec![],
+                response_headers: HeaderMap::new(),
+            });
+        }
+
+        // Returned with the video, so they get to whoever asked for them
+        for subtitle_path in subtitle_paths {
+            let final_file_path = request
+                .download_dir()
+                .join(subtitle_path.file_name().unwrap_or_default());
+
+            if let Err(e) = tokio::fs::copy(&subtitle_path, &final_file_path).await {
+                warn!(?e, ?subtitle_path, "Failed to copy subtitles");
+                continue;
+            }
+
+            results.push(DownloadResult {
+                request: request.clone(),
+                path: final_file_path,
+                sha256: None,
+                metadata: None,
+                downloader_chain: vec![],
+                response_headers: HeaderMap::new(),
+            });
+        }
+
+        if let Some(progress) = &request.progress {
+            progress.finish();
+        }
+
+        if resuming {
+            if let Err(e) = tokio::fs::remove_dir_all(temp_dir.path()).await {
+                warn!(?e, dir = ?temp_dir.path(), "Failed to remove resumable download directory");
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// The files yt-dlp split the video into, in chapter order
+fn chapter_files(
+    dir: &Path,
+    file_identifier: &str,
+    video_path: &Path,
+) -> Result<Vec<PathBuf>, String> {
+    let prefix = format!("{file_identifier}.chapter.");
+
+    let mut paths = std::fs::read_dir(dir)
+        .map_err(|e| format!("Failed to read yt-dlp output directory: {e:?}"))?
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .filter(|x| x != video_path)
+        .filter(|x| {
+            x.file_name()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| x.starts_with(&prefix))
         })
+        .collect::<Vec<_>>();
+
+    // Chapter numbers are zero-padded, so they sort by name
+    paths.sort();
+
+    Ok(paths)
+}
+
+/// The `.srt` files yt-dlp wrote for the video, named `<video name>.<language>.srt`
+fn subtitle_files(dir: &Path, video_path: &Path) -> Vec<PathBuf> {
+    let Some(video_stem) = video_path.file_stem().and_then(|x| x.to_str()) else {
+        return vec![];
+    };
+    let prefix = format!("{video_stem}.");
+
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return vec![];
+    };
+
+    let mut paths = entries
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .filter(|x| {
+            x.file_name()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| x.starts_with(&prefix))
+                && x.extension().is_some_and(|x| x.eq_ignore_ascii_case("srt"))
+        })
+        .collect::<Vec<_>>();
+
+    paths.sort();
+
+    paths
+}
+
+/// The metadata from the `.info.json` file yt-dlp wrote for the video
+async fn read_info_file(dir: &Path, file_identifier: &str) -> Option<MediaMetadata> {
+    let prefix = format!("{file_identifier}.");
+
+    let path = std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .find(|x| {
+            x.file_name()
+                .and_then(|x| x.to_str())
+                .is_some_and(|x| x.starts_with(&prefix) && x.ends_with(".info.json"))
+        })?;
+
+    match MediaMetadata::from_yt_dlp_info_file(&path).await {
+        Ok(x) => Some(x),
+        Err(e) => {
+            warn!(?e, ?path, "Failed to read yt-dlp info file");
+            None
+        }
     }
 }
 
-fn get_output_template<S: Into<PathBuf>>(download_dir: S) -> PathBuf {
-    let file_identifier = time_id();
+/// Like [`Command::output`], but reports the progress lines yt-dlp prints
+/// and leaves them out of the returned output
+async fn output_with_progress(
+    cmd: &mut Command,
+    progress: Option<&ProgressReporter>,
+) -> std::io::Result<process::Output> {
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout = child.stdout.take();
+    let stderr = child.stderr.take();
+
+    let (stdout, stderr, status) = tokio::try_join!(
+        read_output(stdout, progress),
+        read_output(stderr, progress),
+        child.wait(),
+    )?;
+
+    Ok(process::Output {
+        status,
+        stdout,
+        stderr,
+    })
+}
+
+async fn read_output<R>(
+    reader: Option<R>,
+    progress: Option<&ProgressReporter>,
+) -> std::io::Result<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let Some(reader) = reader else {
+        return Ok(vec![]);
+    };
+
+    let mut reader = BufReader::new(reader);
+    let mut output = vec![];
+    let mut line = vec![];
+    // Bytes of the formats that were already downloaded (eg. the video before the audio)
+    let mut finished_bytes = 0;
+    let mut last = (0, None);
+
+    loop {
+        line.clear();
+
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            break;
+        }
+
+        let Some((downloaded, total)) = parse_progress_line(&line) else {
+            output.extend_from_slice(&line);
+            continue;
+        };
+
+        if downloaded < last.0 {
+            finished_bytes += last.1.unwrap_or(last.0);
+        }
+        last = (downloaded, total);
+
+        if let Some(progress) = progress {
+            progress.update(
+                finished_bytes + downloaded,
+                total.map(|x| finished_bytes + x),
+            );
+        }
+    }
+
+    Ok(output)
+}
+
+fn progress_template() -> String {
+    format!(
+        "download:{prefix}{downloaded}:{total}",
+        prefix = PROGRESS_LINE_PREFIX,
+        downloaded = "%(progress.downloaded_bytes)s",
+        total = "%(progress.total_bytes,progress.total_bytes_estimate)s",
+    )
+}
+
+/// `downloader-hub-progress:<downloaded bytes>:<total bytes>`, where the total may be `NA`
+fn parse_progress_line(line: &[u8]) -> Option<(u64, Option<u64>)> {
+    let line = std::str::from_utf8(line).ok()?.trim();
+    let line = line.strip_prefix(PROGRESS_LINE_PREFIX)?;
+    let (downloaded, total) = line.split_once(':')?;
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let parse_bytes = |x: &str| x.parse::<f64>().ok().map(|x| x as u64);
+
+    Some((
+        parse_bytes(downloaded).unwrap_or_default(),
+        parse_bytes(total),
+    ))
+}
+
+fn get_output_template(download_dir: &Path, file_identifier: &str) -> PathBuf {
     let file_name = format!("{file_identifier}.%(id).64s.%(ext)s");
 
-    download_dir.into().join(file_name)
+    download_dir.join(file_name)
+}
+
+/// `<id>.chapter.<number>.<chapter title>.<ext>`
+fn get_chapter_output_template(download_dir: &Path, file_identifier: &str) -> PathBuf {
+    let file_name =
+        format!("{file_identifier}.chapter.%(section_number)03d.%(section_title).64s.%(ext)s");
+
+    download_dir.join(file_name)
+}
+
+/// Format sort fields for yt-dlp so it picks the formats matching the policy.
+///
+/// Unlike a format filter, sorting still picks something if no format matches.
+fn format_sort(policy: &MediaPolicy) -> Vec<String> {
+    let mut fields = vec![];
+
+    if let Some(max_height) = policy.max_video_height {
+        fields.push(format!("res:{max_height}"));
+    }
+
+    if policy.prefers_h264() {
+        fields.push("vcodec:h264".to_string());
+        fields.push("acodec:aac".to_string());
+    }
+
+    fields
 }
 
 fn is_image_error(output: Vec<u8>) -> bool {
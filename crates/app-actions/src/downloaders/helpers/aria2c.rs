@@ -0,0 +1,194 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Once,
+    time::Duration,
+};
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::temp_file::TempFile;
+use http::{header, HeaderMap, Method};
+use tokio::process::Command;
+use tracing::{debug, trace, warn};
+use url::Url;
+
+use super::throttle::Throttle;
+use crate::{
+    common::request::{
+        add_credentials, configured_cookie_lines, proxy_for, redact_credentials, USER_AGENT,
+    },
+    downloaders::DownloadRequest,
+};
+
+const TOOL_NAME: &str = "aria2c";
+
+/// How many connections a file is downloaded over
+const CONNECTIONS: usize = 8;
+
+/// It's not going to get installed while running, so once is enough
+static NOT_INSTALLED_WARNING: Once = Once::new();
+
+/// aria2c to download the file with instead, if it's big enough to be worth splitting up
+/// and the server lets it be downloaded in parts
+pub fn segmented_download_tool(
+    method: &Method,
+    content_length: Option<u64>,
+    headers: &HeaderMap,
+) -> Option<PathBuf> {
+    let config = Config::global();
+
+    let min_size = config.download.aria2c_min_size?.bytes();
+    if content_length.unwrap_or_default() < min_size || *method != Method::GET {
+        return None;
+    }
+
+    // Resumed downloads are already a part of the file
+    let accepts_ranges = headers.contains_key(header::CONTENT_RANGE)
+        || headers
+            .get(header::ACCEPT_RANGES)
+            .and_then(|x| x.to_str().ok())
+            .is_some_and(|x| x.eq_ignore_ascii_case("bytes"));
+    if !accepts_ranges {
+        return None;
+    }
+
+    let path = config.dependency_paths.aria2c_path();
+    if path.is_none() {
+        NOT_INSTALLED_WARNING.call_once(|| {
+            warn!("aria2c is not installed, downloading over a single connection");
+        });
+    }
+
+    path
+}
+
+/// Downloads the URL into `out_path` over multiple connections with aria2c.
+///
+/// `url` is where the request ended up after its redirects, so aria2c doesn't follow them
+/// with the headers meant for the host that was asked for.
+///
+/// Whatever is already in `out_path` is kept and only the rest is downloaded.
+pub async fn download_segmented(
+    aria2c_path: &Path,
+    request: &DownloadRequest,
+    url: &Url,
+    downloader: &str,
+    timeout: Option<Duration>,
+    out_path: &Path,
+) -> Result<(), AppError> {
+    let (Some(out_dir), Some(out_file_name)) = (out_path.parent(), out_path.file_name()) else {
+        return Err(AppError::other(format!(
+            "Invalid output path: {}",
+            out_path.display()
+        )));
+    };
+
+    let mut cmd = Command::new(aria2c_path);
+    cmd.args(["--console-log-level=error", "--summary-interval=0"])
+        .args(["--download-result=hide", "--file-allocation=none"])
+        .args(["--continue=true", "--auto-file-renaming=false"])
+        .arg(format!("--split={CONNECTIONS}"))
+        .arg(format!("--max-connection-per-server={CONNECTIONS}"))
+        .arg("--min-split-size=1M")
+        .arg(format!("--user-agent={USER_AGENT}"))
+        .arg("--dir")
+        .arg(out_dir)
+        .arg("--out")
+        .arg(out_file_name);
+
+    if let Some(timeout) = timeout {
+        cmd.arg(format!("--timeout={}", timeout.as_secs().max(1)));
+    }
+
+    if let Some(proxy) = proxy_for(Some(downloader), url) {
+        cmd.arg(format!("--all-proxy={proxy}"));
+    }
+
+    if let Some(limit_rate) = Throttle::lowest_rate() {
+        cmd.arg(format!("--max-overall-download-limit={limit_rate}"));
+    }
+
+    let mut headers = request.url.headers().clone();
+    if url.host_str() != request.url.url().host_str() {
+        headers.remove(header::AUTHORIZATION);
+        headers.remove(header::COOKIE);
+        headers.remove(header::PROXY_AUTHORIZATION);
+    }
+    // The ones for the host it ended up on
+    add_credentials(url, &mut headers);
+
+    for (k, v) in &headers {
+        cmd.arg(format!("--header={k}: {}", v.to_str().unwrap_or_default()));
+    }
+
+    // Kept until aria2c is done with it
+    let cookie_file = {
+        let lines = configured_cookie_lines();
+
+        if lines.is_empty() {
+            None
+        } else {
+            let mut cookie_file = TempFile::with_prefix("cookie-headers-").map_err(|e| {
+                format!("Failed to create temporary file for aria2c cookies: {e:?}")
+            })?;
+
+            cookie_file
+                .file_mut()
+                .write_all(
+                    format!("# Netscape HTTP Cookie File\n{}\n", lines.join("\n")).as_bytes(),
+                )
+                .map_err(|e| format!("Failed to write cookies to file: {e:?}"))?;
+
+            Some(cookie_file)
+        }
+    };
+    if let Some(cookie_file) = &cookie_file {
+        cmd.arg("--load-cookies").arg(cookie_file.path());
+    }
+
+    cmd.arg(url.as_str());
+
+    debug!(
+        cmd = %redact_credentials(&format!("{cmd:?}"), url),
+        "Downloading with aria2c"
+    );
+
+    let output = cmd
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable(TOOL_NAME, e.to_string()))?;
+
+    trace!(?output, "aria2c output");
+
+    if !output.status.success() {
+        // aria2c prints its errors to stdout
+        let errors = format!(
+            "{stdout} {stderr}",
+            stdout = String::from_utf8_lossy(&output.stdout).trim(),
+            stderr = String::from_utf8_lossy(&output.stderr).trim(),
+        );
+
+        return Err(ExternalToolError::failed(
+            TOOL_NAME,
+            format!("Failed downloading {:?}: {}", url.as_str(), errors.trim()),
+        )
+        .into());
+    }
+
+    let _ = tokio::fs::remove_file(control_file_path(out_path)).await;
+
+    Ok(())
+}
+
+/// Where aria2c keeps track of the parts that are done, to continue an unfinished download
+#[must_use]
+pub fn control_file_path(out_path: &Path) -> PathBuf {
+    let mut path = out_path.as_os_str().to_owned();
+    path.push(".aria2");
+
+    PathBuf::from(path)
+}
@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+use app_config::Config;
+use once_cell::sync::Lazy;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tracing::trace;
+use url::Url;
+
+/// How many downloads can run at once, shared by every request
+static GLOBAL: Lazy<Option<Arc<Semaphore>>> = Lazy::new(|| {
+    Config::global()
+        .download
+        .max_concurrent_downloads
+        .filter(|x| *x > 0)
+        .map(|x| Arc::new(Semaphore::new(x)))
+});
+
+/// How many live streams can be recorded at once, apart from the other downloads
+static LIVE_RECORDINGS: Lazy<Option<Arc<Semaphore>>> = Lazy::new(|| {
+    Config::global()
+        .download
+        .max_concurrent_live_recordings
+        .filter(|x| *x > 0)
+        .map(|x| Arc::new(Semaphore::new(x)))
+});
+
+/// How many downloads can run at once from each host, created as the hosts come up
+/// and dropped once nothing is downloading from them
+static PER_HOST: Lazy<Mutex<HashMap<String, Arc<Semaphore>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Held for as long as the download runs
+#[derive(Debug)]
+pub struct ConcurrencyPermit {
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+/// Waits until fewer than the configured number of downloads are running,
+/// both overall and from the URL's host
+pub async fn acquire(url: &Url) -> ConcurrencyPermit {
+    let mut permits = vec![];
+
+    // The host's permit is taken first, so downloads waiting on a busy host
+    // don't hold up the ones from other hosts
+    if let Some(semaphore) = host_semaphore(url) {
+        trace!(host = ?url.host_str(), "Waiting for host download slot");
+        if let Ok(permit) = semaphore.acquire_owned().await {
+            permits.push(permit);
+        }
+    }
+
+    if let Some(semaphore) = GLOBAL.as_ref() {
+        trace!("Waiting for download slot");
+        if let Ok(permit) = semaphore.clone().acquire_owned().await {
+            permits.push(permit);
+        }
+    }
+
+    ConcurrencyPermit { _permits: permits }
+}
+
+/// Waits until fewer than the configured number of live streams are being recorded
+pub async fn acquire_live_recording() -> ConcurrencyPermit {
+    let mut permits = vec![];
+
+    if let Some(semaphore) = LIVE_RECORDINGS.as_ref() {
+        trace!("Waiting for live recording slot");
+        if let Ok(permit) = semaphore.clone().acquire_owned().await {
+            permits.push(permit);
+        }
+    }
+
+    ConcurrencyPermit { _permits: permits }
+}
+
+fn host_semaphore(url: &Url) -> Option<Arc<Semaphore>> {
+    let limit = Config::global()
+        .download
+        .max_concurrent_downloads_per_host
+        .filter(|x| *x > 0)?;
+    let host = url.host_str()?;
+
+    // The map only has the semaphores that are held or waited on, not every host ever seen
+    let mut semaphores = PER_HOST.lock().unwrap_or_else(PoisonError::into_inner);
+    semaphores.retain(|_, x| Arc::strong_count(x) > 1);
+
+    Some(
+        semaphores
+            .entry(host.to_string())
+            .or_insert_with(|| Arc::new(Semaphore::new(limit)))
+            .clone(),
+    )
+}
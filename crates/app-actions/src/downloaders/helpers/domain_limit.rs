@@ -0,0 +1,134 @@
+use std::{
+    collections::VecDeque,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use app_config::{
+    domain_limit::{DomainLimit, DomainLimitKind},
+    Config,
+};
+use app_helpers::domain::domain_matches;
+use once_cell::sync::Lazy;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use tracing::debug;
+use url::Url;
+
+use crate::downloaders::DownloadRequest;
+
+/// Shared by every download so the limits hold across concurrent tasks
+static LIMITERS: Lazy<Vec<DomainLimiter>> = Lazy::new(|| {
+    Config::global()
+        .download
+        .domain_limits
+        .iter()
+        .cloned()
+        .map(DomainLimiter::new)
+        .collect()
+});
+
+/// Held for as long as the download runs
+#[derive(Debug)]
+pub struct DomainPermit {
+    _permits: Vec<OwnedSemaphorePermit>,
+}
+
+/// Waits until the limits of the domain let another download start.
+///
+/// A limit applies if it matches the URL or the page the URL was found on,
+/// so the files on a site's CDN count towards the site's limit.
+pub async fn acquire(request: &DownloadRequest) -> DomainPermit {
+    let urls = std::iter::once(request.url.url())
+        .chain(request.source_url.as_ref())
+        .collect::<Vec<_>>();
+
+    let mut permits = vec![];
+
+    for limiter in LIMITERS.iter().filter(|x| x.matches_any(&urls)) {
+        debug!(limit = %limiter.limit, "Waiting for domain limit");
+
+        match &limiter.kind {
+            Limiter::Concurrent(semaphore) => {
+                if let Ok(permit) = semaphore.clone().acquire_owned().await {
+                    permits.push(permit);
+                }
+            }
+            Limiter::Rate(window) => window.wait().await,
+        }
+    }
+
+    DomainPermit { _permits: permits }
+}
+
+/// Waits until the rate limits of the URL's domain let another request go out,
+/// for the requests the extractors and the like send to the site.
+///
+/// Only the rate limits apply, the concurrent ones are for the downloads.
+pub async fn wait_for_rate_limit(url: &Url) {
+    for limiter in LIMITERS.iter().filter(|x| x.matches_any(&[url])) {
+        if let Limiter::Rate(window) = &limiter.kind {
+            debug!(limit = %limiter.limit, "Waiting for domain rate limit");
+            window.wait().await;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DomainLimiter {
+    limit: DomainLimit,
+    kind: Limiter,
+}
+impl DomainLimiter {
+    fn new(limit: DomainLimit) -> Self {
+        let kind = match limit.limit {
+            DomainLimitKind::Concurrent(count) => {
+                Limiter::Concurrent(Arc::new(Semaphore::new(count)))
+            }
+            DomainLimitKind::Rate { count, per } => Limiter::Rate(Window {
+                count,
+                per,
+                started: Mutex::new(VecDeque::new()),
+            }),
+        };
+
+        Self { limit, kind }
+    }
+
+    fn matches_any(&self, urls: &[&Url]) -> bool {
+        urls.iter().any(|x| domain_matches(&self.limit.domain, x))
+    }
+}
+
+#[derive(Debug)]
+enum Limiter {
+    Concurrent(Arc<Semaphore>),
+    Rate(Window),
+}
+
+/// Lets at most `count` downloads start in any `per` long window
+#[derive(Debug)]
+struct Window {
+    count: usize,
+    per: Duration,
+    /// When the downloads in the current window were started
+    started: Mutex<VecDeque<Instant>>,
+}
+impl Window {
+    async fn wait(&self) {
+        // Held while waiting so the downloads start in the order they came in
+        let mut started = self.started.lock().await;
+
+        while started.front().is_some_and(|x| x.elapsed() >= self.per) {
+            started.pop_front();
+        }
+
+        if started.len() >= self.count {
+            if let Some(oldest) = started.front() {
+                tokio::time::sleep(self.per.saturating_sub(oldest.elapsed())).await;
+            }
+            started.pop_front();
+        }
+
+        started.push_back(Instant::now());
+    }
+}
@@ -170,14 +170,14 @@ impl DispositionParam {
     /// Returns `true` if the parameter is [`Name`](DispositionParam::Name).
     #[inline]
     #[must_use]
-    pub fn is_name(&self) -> bool {
+    pub const fn is_name(&self) -> bool {
         self.as_name().is_some()
     }
 
     /// Returns `true` if the parameter is [`Filename`](DispositionParam::Filename).
     #[inline]
     #[must_use]
-    pub fn is_filename(&self) -> bool {
+    pub const fn is_filename(&self) -> bool {
         self.as_filename().is_some()
     }
 
@@ -205,7 +205,7 @@ impl DispositionParam {
     /// Returns the name if applicable.
     #[inline]
     #[must_use]
-    pub fn as_name(&self) -> Option<&str> {
+    pub const fn as_name(&self) -> Option<&str> {
         match self {
             Self::Name(name) => Some(name.as_str()),
             _ => None,
@@ -215,7 +215,7 @@ impl DispositionParam {
     /// Returns the filename if applicable.
     #[inline]
     #[must_use]
-    pub fn as_filename(&self) -> Option<&str> {
+    pub const fn as_filename(&self) -> Option<&str> {
         match self {
             Self::Filename(filename) => Some(filename.as_str()),
             _ => None,
@@ -308,13 +308,10 @@ impl ContentDisposition {
     /// Constructs a Content-Disposition header suitable for downloads.
     ///
     /// # Examples
-    /// ```
-    /// use actix_web::http::header::{ContentDisposition, TryIntoHeaderValue as _};
-    ///
+    /// ```ignore
     /// let cd = ContentDisposition::attachment("files.zip");
     ///
-    /// let cd_val = cd.try_into_value().unwrap();
-    /// assert_eq!(cd_val, "attachment; filename=\"files.zip\"");
+    /// assert_eq!(cd.to_string(), "attachment; filename=\"files.zip\"");
     /// ```
     pub fn attachment(filename: impl Into<String>) -> Self {
         Self {
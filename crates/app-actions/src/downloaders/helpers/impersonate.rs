@@ -0,0 +1,216 @@
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use app_config::{credentials::Credentials, Config};
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::{domain::host_matches, temp_file::TempFile};
+use http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use tokio::process::Command;
+use tracing::{debug, trace, warn};
+use url::Url;
+
+use super::{
+    size_limit::{max_download_size, too_large_error},
+    throttle::Throttle,
+};
+use crate::{
+    common::request::{configured_cookie_lines, credentials_for, proxy_for, redact_credentials},
+    downloaders::DownloadRequest,
+};
+
+const TOOL_NAME: &str = "curl-impersonate";
+
+/// The curl-impersonate wrapper to download the URL with,
+/// if its domain is set to be downloaded like a browser would
+pub fn impersonation_tool(url: &Url) -> Option<PathBuf> {
+    let config = Config::global();
+
+    if !config
+        .download
+        .impersonate_domains
+        .iter()
+        .any(|x| host_matches(x, url))
+    {
+        return None;
+    }
+
+    let path = config.dependency_paths.curl_impersonate_path();
+    if path.is_none() {
+        warn!(url = ?url.as_str(), "curl-impersonate is not installed, downloading without it");
+    }
+
+    path
+}
+
+/// Downloads the URL into `out_path` with curl-impersonate, so the server sees a browser.
+///
+/// Returns the headers of the final response, after following the redirects.
+#[allow(clippy::too_many_lines)]
+pub async fn download_impersonated(
+    curl_path: &Path,
+    request: &DownloadRequest,
+    downloader: &str,
+    timeout: Option<Duration>,
+    out_path: &Path,
+) -> Result<HeaderMap, AppError> {
+    let url = request.url.url();
+
+    let mut cmd = Command::new(curl_path);
+    cmd.args(["--silent", "--show-error", "--location", "--compressed"])
+        .args(["--request", request.url.method().as_str()])
+        .arg("--output")
+        .arg(out_path)
+        // Only written once the download is done, so it's the last thing in the output
+        .args(["--write-out", "%{http_code}\n%{header_json}"]);
+
+    if let Some(timeout) = timeout {
+        cmd.args(["--max-time", &timeout.as_secs().to_string()]);
+    }
+
+    if let Some(proxy) = proxy_for(Some(downloader), url) {
+        cmd.args(["--proxy", proxy.as_str()]);
+    }
+
+    if let Some(limit_rate) = Throttle::lowest_rate() {
+        cmd.args(["--limit-rate", &limit_rate.to_string()]);
+    }
+
+    if let Some(max_size) = max_download_size() {
+        cmd.args(["--max-filesize", &max_size.to_string()]);
+    }
+
+    for (k, v) in request.url.headers() {
+        cmd.arg("--header")
+            .arg(format!("{k}: {}", v.to_str().unwrap_or_default()));
+    }
+
+    // curl only sends these to the host it was given, not to the ones it's redirected to
+    #[allow(clippy::collapsible_match)]
+    match credentials_for(url) {
+        Some(Credentials::Basic { username, password }) => {
+            cmd.arg("--user").arg(format!("{username}:{password}"));
+        }
+        Some(Credentials::Bearer(token)) => {
+            cmd.arg("--oauth2-bearer").arg(token);
+        }
+        Some(Credentials::Header { name, value })
+            if name.eq_ignore_ascii_case("authorization")
+                || name.eq_ignore_ascii_case("cookie") =>
+        {
+            if !request.url.headers().contains_key(name.as_str()) {
+                cmd.arg("--header").arg(format!("{name}: {value}"));
+            }
+        }
+        Some(Credentials::Header { .. }) => {
+            warn!(
+                host = ?url.host_str(),
+                "curl-impersonate can't limit the configured credentials to the host, leaving them out"
+            );
+        }
+        None => {}
+    }
+
+    // Kept until curl is done with it
+    let cookie_file = {
+        let lines = configured_cookie_lines();
+
+        if lines.is_empty() {
+            None
+        } else {
+            let mut cookie_file = TempFile::with_prefix("cookie-headers-").map_err(|e| {
+                format!("Failed to create temporary file for curl-impersonate cookies: {e:?}")
+            })?;
+
+            cookie_file
+                .file_mut()
+                .write_all(
+                    format!("# Netscape HTTP Cookie File\n{}\n", lines.join("\n")).as_bytes(),
+                )
+                .map_err(|e| format!("Failed to write cookies to file: {e:?}"))?;
+
+            Some(cookie_file)
+        }
+    };
+    if let Some(cookie_file) = &cookie_file {
+        cmd.arg("--cookie").arg(cookie_file.path());
+    }
+
+    cmd.arg(url.as_str());
+
+    debug!(
+        cmd = %redact_credentials(&format!("{cmd:?}"), url),
+        "Downloading with curl-impersonate"
+    );
+
+    let output = cmd
+        .stdin(Stdio::null())
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable(TOOL_NAME, e.to_string()))?;
+
+    trace!(?output, "curl-impersonate output");
+
+    if !output.status.success() {
+        let _ = tokio::fs::remove_file(out_path).await;
+
+        // The exit code for going over `--max-filesize`
+        if output.status.code() == Some(63) {
+            if let Some(max_size) = max_download_size() {
+                return Err(too_large_error(max_size));
+            }
+        }
+
+        return Err(ExternalToolError::failed(
+            TOOL_NAME,
+            format!(
+                "Failed downloading {:?}: {}",
+                url.as_str(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            ),
+        )
+        .into());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let (status, headers) = stdout.split_once('\n').unwrap_or((&stdout, ""));
+
+    let status = status
+        .trim()
+        .parse::<u16>()
+        .ok()
+        .and_then(|x| StatusCode::from_u16(x).ok())
+        .ok_or_else(|| {
+            ExternalToolError::failed(TOOL_NAME, format!("Unexpected output: {stdout:?}"))
+        })?;
+
+    if !status.is_success() {
+        let _ = tokio::fs::remove_file(out_path).await;
+
+        return Err(ExternalToolError::failed(
+            TOOL_NAME,
+            format!("Server responded with {status} for {:?}", url.as_str()),
+        )
+        .into());
+    }
+
+    Ok(parse_header_json(headers))
+}
+
+/// `{"content-type": ["video/mp4"], ...}`, as curl writes `%{header_json}`
+fn parse_header_json(json: &str) -> HeaderMap {
+    let headers = serde_json::from_str::<HashMap<String, Vec<String>>>(json).unwrap_or_default();
+
+    headers
+        .into_iter()
+        .filter_map(|(k, v)| Some((HeaderName::try_from(k).ok()?, v)))
+        .flat_map(|(k, v)| {
+            v.into_iter()
+                .filter_map(move |x| Some((k.clone(), HeaderValue::try_from(x).ok()?)))
+        })
+        .collect()
+}
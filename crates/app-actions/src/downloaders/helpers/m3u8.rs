@@ -0,0 +1,214 @@
+use std::{collections::HashMap, path::Path};
+
+use url::Url;
+
+#[derive(Debug)]
+pub enum Playlist {
+    /// Lists the variants (qualities) of the stream
+    Master(MasterPlaylist),
+    /// Lists the segments of a single variant
+    Media(MediaPlaylist),
+}
+impl Playlist {
+    /// Relative URIs in the playlist are resolved against `base_url`
+    pub fn parse(text: &str, base_url: &Url) -> Result<Self, String> {
+        let mut lines = text
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .map(str::trim)
+            .filter(|x| !x.is_empty());
+
+        if lines.next() != Some("#EXTM3U") {
+            return Err("Not an M3U8 playlist".to_string());
+        }
+
+        let resolve = |uri: &str| {
+            base_url
+                .join(uri)
+                .map_err(|e| format!("Invalid URI in playlist {uri:?}: {e:?}"))
+        };
+
+        let mut variants = vec![];
+        let mut audio_renditions = vec![];
+        let mut media = MediaPlaylist::default();
+        let mut pending_variant = None;
+
+        for line in lines {
+            if let Some(attrs) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+                pending_variant = Some(parse_attributes(attrs));
+            } else if let Some(attrs) = line.strip_prefix("#EXT-X-MEDIA:") {
+                let attrs = parse_attributes(attrs);
+
+                if let (Some("AUDIO"), Some(uri)) =
+                    (attrs.get("TYPE").map(String::as_str), attrs.get("URI"))
+                {
+                    audio_renditions.push(Rendition {
+                        url: resolve(uri)?,
+                        group_id: attrs.get("GROUP-ID").cloned().unwrap_or_default(),
+                        is_default: attrs.get("DEFAULT").is_some_and(|x| x == "YES"),
+                    });
+                }
+            } else if let Some(attrs) = line.strip_prefix("#EXT-X-MAP:") {
+                let attrs = parse_attributes(attrs);
+
+                media.has_byte_ranges |= attrs.contains_key("BYTERANGE");
+                if let Some(uri) = attrs.get("URI") {
+                    media.init_segment = Some(resolve(uri)?);
+                }
+            } else if let Some(attrs) = line.strip_prefix("#EXT-X-KEY:") {
+                media.is_encrypted |= parse_attributes(attrs)
+                    .get("METHOD")
+                    .is_some_and(|x| x != "NONE");
+            } else if line.starts_with("#EXT-X-BYTERANGE") {
+                media.has_byte_ranges = true;
+            } else if line.starts_with('#') {
+                // Other tags don't change what gets downloaded
+            } else if let Some(attrs) = pending_variant.take() {
+                variants.push(Variant {
+                    url: resolve(line)?,
+                    bandwidth: attrs
+                        .get("BANDWIDTH")
+                        .and_then(|x| x.parse().ok())
+                        .unwrap_or_default(),
+                    resolution: attrs.get("RESOLUTION").and_then(|x| {
+                        let (width, height) = x.split_once('x')?;
+
+                        Some((width.parse().ok()?, height.parse().ok()?))
+                    }),
+                    audio_group: attrs.get("AUDIO").cloned(),
+                });
+            } else {
+                media.segments.push(resolve(line)?);
+            }
+        }
+
+        if !variants.is_empty() {
+            return Ok(Self::Master(MasterPlaylist {
+                variants,
+                audio_renditions,
+            }));
+        }
+
+        if media.segments.is_empty() {
+            return Err("Playlist has no segments".to_string());
+        }
+
+        Ok(Self::Media(media))
+    }
+}
+
+#[derive(Debug)]
+pub struct MasterPlaylist {
+    pub variants: Vec<Variant>,
+    /// Audio tracks that are served separately from the variants
+    pub audio_renditions: Vec<Rendition>,
+}
+impl MasterPlaylist {
+    /// The variant with the highest bitrate
+    #[must_use]
+    pub fn best_variant(&self) -> Option<&Variant> {
+        self.variants
+            .iter()
+            .max_by_key(|x| (x.bandwidth, x.resolution))
+    }
+
+    /// The variant with the highest bitrate that's at most `max_height` tall,
+    /// or the smallest one if none of them are
+    #[must_use]
+    pub fn best_variant_up_to(&self, max_height: u32) -> Option<&Variant> {
+        self.variants
+            .iter()
+            .filter(|x| x.resolution.is_none_or(|(_, height)| height <= max_height))
+            .max_by_key(|x| (x.bandwidth, x.resolution))
+            .or_else(|| {
+                self.variants
+                    .iter()
+                    .min_by_key(|x| (x.resolution, x.bandwidth))
+            })
+    }
+
+    /// The separate audio track of the variant, if it has one
+    #[must_use]
+    pub fn audio_rendition(&self, variant: &Variant) -> Option<&Rendition> {
+        let group_id = variant.audio_group.as_deref()?;
+        let mut group = self
+            .audio_renditions
+            .iter()
+            .filter(|x| x.group_id == group_id);
+
+        group
+            .clone()
+            .find(|x| x.is_default)
+            .or_else(|| group.next())
+    }
+}
+
+#[derive(Debug)]
+pub struct Variant {
+    pub url: Url,
+    /// Peak bitrate in bits per second
+    pub bandwidth: u64,
+    /// Width and height of the video
+    pub resolution: Option<(u32, u32)>,
+    pub audio_group: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Rendition {
+    pub url: Url,
+    pub group_id: String,
+    pub is_default: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct MediaPlaylist {
+    /// Segment with the headers of fragmented MP4 streams
+    pub init_segment: Option<Url>,
+    pub segments: Vec<Url>,
+    pub is_encrypted: bool,
+    pub has_byte_ranges: bool,
+}
+impl MediaPlaylist {
+    /// Whether the stream is just the segment files put one after another
+    #[must_use]
+    pub const fn can_be_joined(&self) -> bool {
+        !self.is_encrypted && !self.has_byte_ranges
+    }
+
+    /// Extension of the segment files, eg. `ts` or `aac`
+    #[must_use]
+    pub fn segment_extension(&self) -> String {
+        self.segments
+            .first()
+            .and_then(|x| {
+                Path::new(x.path())
+                    .extension()
+                    .and_then(|x| x.to_str())
+                    .map(str::to_lowercase)
+            })
+            .unwrap_or_else(|| "ts".to_string())
+    }
+}
+
+/// Parses `KEY=VALUE,KEY="quoted, value"` attribute lists
+fn parse_attributes(attrs: &str) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut rest = attrs.trim();
+
+    while let Some((key, value)) = rest.split_once('=') {
+        #[allow(clippy::option_if_let_else)]
+        let (value, next) = match value.strip_prefix('"') {
+            Some(quoted) => {
+                let (value, next) = quoted.split_once('"').unwrap_or((quoted, ""));
+
+                (value, next.split_once(',').map_or("", |x| x.1))
+            }
+            None => value.split_once(',').unwrap_or((value, "")),
+        };
+
+        result.insert(key.trim().to_uppercase(), value.trim().to_string());
+        rest = next;
+    }
+
+    result
+}
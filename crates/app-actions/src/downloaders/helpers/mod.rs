@@ -1 +1,9 @@
+pub mod aria2c;
+pub mod concurrency;
+pub mod domain_limit;
 pub mod headers;
+pub mod impersonate;
+pub mod m3u8;
+pub mod retry;
+pub mod size_limit;
+pub mod throttle;
@@ -0,0 +1,86 @@
+use app_config::{retry_policy::RetryPolicy, Config};
+use app_errors::AppError;
+use tracing::warn;
+
+use super::{concurrency, domain_limit};
+use crate::{
+    common::request::wait_for_quota_reset,
+    downloaders::{
+        handlers::live::LiveStream, DownloadRequest, Downloader, DownloaderEntry, DownloaderReturn,
+    },
+};
+
+/// Runs the download, trying again with a growing wait in between
+/// as long as the downloader's retry policy allows it.
+///
+/// The domain and concurrency limits are only held while a download is running, not while waiting.
+/// Live recordings only count towards their own limit.
+pub async fn download_with_retries(
+    downloader: &DownloaderEntry,
+    request: &DownloadRequest,
+) -> Vec<DownloaderReturn> {
+    let policy = Config::global()
+        .download
+        .retry_policy_for(downloader.name());
+
+    let mut attempt = 1;
+    loop {
+        // Recordings can run for hours, so they'd hold up every other download
+        let results = if downloader.name() == LiveStream.name() {
+            let _slot = concurrency::acquire_live_recording().await;
+
+            downloader.download_all(request).await
+        } else {
+            let _permit = domain_limit::acquire(request).await;
+            let _slot = concurrency::acquire(request.url.url()).await;
+
+            downloader.download_all(request).await
+        };
+
+        let Some(policy) = policy else {
+            return results;
+        };
+
+        if attempt >= policy.max_attempts || !should_retry(policy, &results) {
+            return results;
+        }
+
+        // Retrying while the site's rate limit is used up only gets the next attempt refused too
+        if let Err(e) = wait_for_quota_reset(request.url.url()).await {
+            warn!(
+                downloader = downloader.name(),
+                url = ?request.url.url().as_str(),
+                ?e,
+                "Not retrying download",
+            );
+            return results;
+        }
+
+        let wait = policy.backoff(attempt);
+        warn!(
+            downloader = downloader.name(),
+            url = ?request.url.url().as_str(),
+            attempt,
+            ?wait,
+            ?results,
+            "Download failed, trying again",
+        );
+        tokio::time::sleep(wait).await;
+
+        attempt += 1;
+    }
+}
+
+/// Only if nothing was downloaded and trying again could change that
+fn should_retry(policy: &RetryPolicy, results: &[DownloaderReturn]) -> bool {
+    !results.is_empty()
+        && results.iter().all(|x| match x {
+            // The statuses only decide about the HTTP errors, timeouts and the like are always retried
+            Err(AppError::Network(e)) => e
+                .status()
+                .and_then(|x| policy.retries_status(x))
+                .unwrap_or_else(|| e.is_retryable()),
+            Err(AppError::ExternalTool(e)) => e.is_retryable(),
+            Ok(_) | Err(_) => false,
+        })
+}
@@ -0,0 +1,62 @@
+use app_config::{byte_size::ByteSize, Config};
+use app_errors::{AppError, UserInputError};
+use app_helpers::bytes::format_bytes;
+
+/// The largest file a download can produce, if there's a limit
+#[must_use]
+pub fn max_download_size() -> Option<u64> {
+    Config::global()
+        .download
+        .max_download_size
+        .map(ByteSize::bytes)
+}
+
+/// Fails if a file of `size` bytes is over the download size limit
+pub fn check_size(size: u64) -> Result<(), AppError> {
+    match max_download_size() {
+        Some(max) if size > max => Err(too_large_error(max)),
+        _ => Ok(()),
+    }
+}
+
+/// Counts the bytes of a download as they come in,
+/// so it can be stopped once it gets over the limit
+#[derive(Debug)]
+pub struct SizeLimit {
+    max: Option<u64>,
+    downloaded: u64,
+}
+impl SizeLimit {
+    #[must_use]
+    pub fn for_download() -> Self {
+        Self::starting_from(0)
+    }
+
+    /// For downloads that already have `downloaded` bytes, eg. resumed ones
+    #[must_use]
+    pub fn starting_from(downloaded: u64) -> Self {
+        Self {
+            max: max_download_size(),
+            downloaded,
+        }
+    }
+
+    /// Adds `bytes` to the download and fails if it's now over the limit
+    pub fn add(&mut self, bytes: u64) -> Result<(), AppError> {
+        self.downloaded = self.downloaded.saturating_add(bytes);
+
+        match self.max {
+            Some(max) if self.downloaded > max => Err(too_large_error(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[must_use]
+pub fn too_large_error(max: u64) -> AppError {
+    UserInputError::TooLarge(format!(
+        "The file is larger than the {} download limit",
+        format_bytes(max)
+    ))
+    .into()
+}
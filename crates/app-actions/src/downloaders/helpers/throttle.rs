@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use app_config::{byte_rate::ByteRate, Config};
+use once_cell::sync::Lazy;
+use tokio::sync::Mutex;
+
+/// Shared by every download, so all of them combined stay under the limit
+static GLOBAL_LIMITER: Lazy<Option<RateLimiter>> =
+    Lazy::new(|| Config::global().download.limit_rate.map(RateLimiter::new));
+
+/// The bandwidth limits that apply to a single download
+#[derive(Debug)]
+pub struct Throttle {
+    per_download: Option<RateLimiter>,
+}
+impl Throttle {
+    #[must_use]
+    pub fn for_download() -> Self {
+        Self {
+            per_download: Config::global()
+                .download
+                .limit_rate_per_download
+                .map(RateLimiter::new),
+        }
+    }
+
+    /// Waits until `bytes` more can be transferred without going over the limits
+    pub async fn wait_for(&self, bytes: u64) {
+        if let Some(limiter) = GLOBAL_LIMITER.as_ref() {
+            limiter.acquire(bytes).await;
+        }
+
+        if let Some(limiter) = &self.per_download {
+            limiter.acquire(bytes).await;
+        }
+    }
+
+    /// The lowest of the limits, for programs that do the downloading themselves (eg. yt-dlp).
+    ///
+    /// They can't share the global limit with the other downloads,
+    /// so they're only kept from using more than all of it.
+    #[must_use]
+    pub fn lowest_rate() -> Option<ByteRate> {
+        let config = &Config::global().download;
+
+        [config.limit_rate, config.limit_rate_per_download]
+            .into_iter()
+            .flatten()
+            .min_by_key(|x| x.bytes_per_second())
+    }
+}
+
+/// A token bucket that lets through `rate` bytes per second on average,
+/// with bursts of up to a second's worth
+#[derive(Debug)]
+struct RateLimiter {
+    bytes_per_second: f64,
+    bucket: Mutex<Bucket>,
+}
+impl RateLimiter {
+    fn new(rate: ByteRate) -> Self {
+        #[allow(clippy::cast_precision_loss)]
+        let bytes_per_second = rate.bytes_per_second() as f64;
+
+        Self {
+            bytes_per_second,
+            bucket: Mutex::new(Bucket {
+                tokens: bytes_per_second,
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    #[allow(clippy::significant_drop_tightening)]
+    async fn acquire(&self, bytes: u64) {
+        // Held while waiting so the downloads take turns instead of racing for the tokens
+        let mut bucket = self.bucket.lock().await;
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = elapsed
+            .mul_add(self.bytes_per_second, bucket.tokens)
+            .min(self.bytes_per_second);
+        bucket.last_refill = now;
+
+        #[allow(clippy::cast_precision_loss)]
+        {
+            bucket.tokens -= bytes as f64;
+        }
+
+        // Going into debt lets chunks bigger than the bucket through,
+        // the wait is just as long as it takes to pay it back
+        if bucket.tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(
+                -bucket.tokens / self.bytes_per_second,
+            ))
+            .await;
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Bucket {
+    tokens: f64,
+    last_refill: Instant,
+}
@@ -2,16 +2,35 @@ use std::fmt::Debug;
 
 pub use common::{
     download_request::{DownloadRequest, DownloaderOptions},
-    download_result::DownloadResult,
+    download_result::{DownloadResult, DownloaderAttempt},
+    file_writer::DownloadFileWriter,
+    media_metadata::MediaMetadata,
+    progress::{
+        DownloadProgress, FileProgress, FoundMedia, ProgressEstimate, ProgressEstimator,
+        ProgressReporter, ProgressTracker,
+    },
 };
 pub use handlers::DownloaderEntry;
 
 mod common;
 pub mod handlers;
-mod helpers;
+pub(crate) mod helpers;
 
+use std::sync::Arc;
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError, UnsupportedSource};
+use app_helpers::domain::host_matches;
 pub use handlers::AVAILABLE_DOWNLOADERS;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+use url::Url;
+
+use crate::common::url::UrlWithMeta;
+
+/// Other URLs of the same content, eg. the image a page embeds.
+///
+/// They're tried in order with the generic downloader if downloading the URL itself fails.
+pub const FALLBACK_URLS_OPTION: &str = "fallback-urls";
 
 #[async_trait::async_trait]
 #[typetag::serde(tag = "$downloader")]
@@ -29,31 +48,122 @@ pub trait Downloader: Debug + Send + Sync {
     async fn can_download(&self, request: &DownloadRequest) -> bool;
 
     async fn download(&self, req: &DownloadRequest) -> DownloaderReturn;
+
+    /// Same as [`Downloader::download`], but for downloaders that can
+    /// turn one request into multiple files (eg. one per chapter)
+    async fn download_all(&self, req: &DownloadRequest) -> Vec<DownloaderReturn> {
+        vec![self.download(req).await]
+    }
 }
 
 pub type DownloaderReturn = Result<DownloadResult, DownloaderError>;
-pub type DownloaderError = String;
+pub type DownloaderError = AppError;
 
-pub async fn download_file(file: &DownloadRequest) -> DownloaderReturn {
+pub async fn download_file(file: &DownloadRequest) -> Vec<DownloaderReturn> {
     info!(?file, "Downloading file");
 
-    let new_file_paths = download_file_with(&AVAILABLE_DOWNLOADERS, file).await;
+    let mut new_file_paths = download_file_with(&AVAILABLE_DOWNLOADERS, file).await;
+
+    if host_failed(&new_file_paths) {
+        if let Some(mirror_file_paths) = download_mirrors(file).await {
+            new_file_paths = mirror_file_paths;
+        }
+    }
+
+    if new_file_paths.iter().all(Result::is_err) {
+        if let Some(fallback_file_paths) = download_fallback(file).await {
+            new_file_paths = fallback_file_paths;
+        }
+    }
 
     debug!("Downloaded files: {:?}", &new_file_paths);
 
     new_file_paths
 }
 
+/// Only when nothing was downloaded because the host failed,
+/// eg. it refused the request or timed out
+fn host_failed(results: &[DownloaderReturn]) -> bool {
+    !results.is_empty()
+        && results.iter().all(|x| {
+            matches!(
+                x,
+                Err(AppError::Network(_) | AppError::ExternalTool(ExternalToolError::Failed { .. }))
+            )
+        })
+}
+
+/// `None` if there are no mirrors or none of them could be downloaded
+async fn download_mirrors(file: &DownloadRequest) -> Option<Vec<DownloaderReturn>> {
+    for mirror in &file.mirrors {
+        debug!(url = ?mirror.url().as_str(), "Trying mirror");
+
+        let mut request = file.clone();
+        request.url = mirror.clone();
+        request.mirrors.clear();
+
+        let results = download_file_with(&AVAILABLE_DOWNLOADERS, &request).await;
+
+        if results.iter().any(Result::is_ok) {
+            return Some(results);
+        }
+
+        warn!(url = ?mirror.url().as_str(), ?results, "Failed to download mirror");
+    }
+
+    None
+}
+
+/// `None` if there are no fallback URLs or none of them could be downloaded
+async fn download_fallback(file: &DownloadRequest) -> Option<Vec<DownloaderReturn>> {
+    let fallback_urls = file.downloader_option::<Vec<String>>(FALLBACK_URLS_OPTION)?;
+
+    for url in fallback_urls {
+        if Url::parse(&url).is_err() {
+            warn!(?url, "Invalid fallback url");
+            continue;
+        }
+
+        debug!(?url, "Trying fallback url");
+
+        let mut request = file.clone();
+        request.url = UrlWithMeta::from_url(&url);
+        request.mirrors.clear();
+        request.downloader_options.remove(FALLBACK_URLS_OPTION);
+
+        let downloader: DownloaderEntry = if handlers::hls::Hls.can_download(&request).await {
+            Arc::new(handlers::hls::Hls)
+        } else {
+            Arc::new(handlers::generic::Generic)
+        };
+        request.preferred_downloader = Some(downloader);
+
+        let results = download_file_with(&AVAILABLE_DOWNLOADERS, &request).await;
+
+        if results.iter().any(Result::is_ok) {
+            return Some(results);
+        }
+
+        warn!(?url, ?results, "Failed to download fallback url");
+    }
+
+    None
+}
+
 pub async fn download_file_with(
     downloaders: &[DownloaderEntry],
     request: &DownloadRequest,
-) -> DownloaderReturn {
+) -> Vec<DownloaderReturn> {
     async fn find_downloader(
         downloaders: &[DownloaderEntry],
         request: &DownloadRequest,
     ) -> Option<DownloaderEntry> {
         if let Some(downloader) = &request.preferred_downloader {
-            if downloader.can_download(request).await {
+            let is_disabled = Config::global()
+                .handlers
+                .is_downloader_disabled(downloader.name());
+
+            if !is_disabled && downloader.can_download(request).await {
                 return Some(downloader.clone());
             }
         }
@@ -67,17 +177,134 @@ pub async fn download_file_with(
         None
     }
 
+    if let Some(chain) = chain_for(downloaders, request).await {
+        return download_with_chain(&chain, request).await;
+    }
+
     let downloader = find_downloader(downloaders, request).await;
 
     let downloader = match downloader {
         Some(d) => d,
         None => {
-            return Err(format!(
+            return vec![Err(UnsupportedSource::new(format!(
                 "Could not find a downloader that can handle {r:?}",
                 r = request,
-            ));
+            ))
+            .into())];
         }
     };
 
-    downloader.download(request).await
+    // The domain limits are only held while downloading,
+    // so they don't count the time spent extracting
+    let results = helpers::retry::download_with_retries(&downloader, request).await;
+
+    with_downloader_chain(results, vec![], downloader.name())
+}
+
+/// The downloaders of the chain configured for the URL's domain (or the domain of
+/// the page it was found on) that can download it.
+///
+/// The downloader the extractor asked for goes first, unless the chain overrides it.
+///
+/// `None` if there's no chain for the domain or none of its downloaders can download the URL.
+async fn chain_for(
+    downloaders: &[DownloaderEntry],
+    request: &DownloadRequest,
+) -> Option<Vec<DownloaderEntry>> {
+    let chain = Config::global()
+        .download
+        .downloader_chains
+        .iter()
+        .find(|x| {
+            host_matches(&x.domain, request.url.url())
+                || request
+                    .source_url
+                    .as_ref()
+                    .is_some_and(|source| host_matches(&x.domain, source))
+        })?;
+
+    let mut entries: Vec<DownloaderEntry> = vec![];
+
+    if let Some(preferred) = &request.preferred_downloader {
+        let is_disabled = Config::global()
+            .handlers
+            .is_downloader_disabled(preferred.name());
+
+        if !chain.overrides_preferred && !is_disabled && preferred.can_download(request).await {
+            entries.push(preferred.clone());
+        }
+    }
+
+    for name in &chain.downloaders {
+        let Some(downloader) = downloaders
+            .iter()
+            .find(|x| x.name().eq_ignore_ascii_case(name))
+        else {
+            warn!(?name, %chain, "Downloader in chain is unknown or disabled");
+            continue;
+        };
+
+        if entries.iter().any(|x| x.name() == downloader.name()) {
+            continue;
+        }
+
+        if downloader.can_download(request).await {
+            entries.push(downloader.clone());
+        }
+    }
+
+    let names = entries.iter().map(|x| x.name()).collect::<Vec<_>>();
+    debug!(%chain, downloaders = ?names, "Using downloader chain");
+
+    (!entries.is_empty()).then_some(entries)
+}
+
+/// Tries the downloaders one after the other until one of them doesn't fail because of the host
+async fn download_with_chain(
+    chain: &[DownloaderEntry],
+    request: &DownloadRequest,
+) -> Vec<DownloaderReturn> {
+    let mut attempts = vec![];
+    let mut results = vec![];
+
+    for downloader in chain {
+        results = helpers::retry::download_with_retries(downloader, request).await;
+
+        if !host_failed(&results) {
+            return with_downloader_chain(results, attempts, downloader.name());
+        }
+
+        warn!(
+            downloader = downloader.name(),
+            url = ?request.url.url().as_str(),
+            ?results,
+            "Downloader in chain failed, trying the next one",
+        );
+
+        if let Some(Err(e)) = results.first() {
+            attempts.push(DownloaderAttempt::failed(downloader.name(), e));
+        }
+    }
+
+    results
+}
+
+/// Records the downloaders that were tried on the downloaded files
+fn with_downloader_chain(
+    results: Vec<DownloaderReturn>,
+    failed_attempts: Vec<DownloaderAttempt>,
+    downloader: &str,
+) -> Vec<DownloaderReturn> {
+    let mut chain = failed_attempts;
+    chain.push(DownloaderAttempt::succeeded(downloader));
+
+    results
+        .into_iter()
+        .map(|x| {
+            x.map(|mut x| {
+                x.downloader_chain.clone_from(&chain);
+                x
+            })
+        })
+        .collect()
 }
@@ -1,13 +1,31 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
 use super::extract_info_request::ExtractInfoRequest;
 use crate::{
     common::url::UrlWithMeta,
     downloaders::{Downloader, DownloaderOptions},
+    format_choice::{format_choices, is_screenshot},
 };
 
+/// Meta key extractors set when the source marks the content as age-restricted or NSFW
+const AGE_RESTRICTED_META: &str = "age-restricted";
+/// Meta key for the title of the content, if the source has one
+const TITLE_META: &str = "title";
+/// Meta key for the text of the post, as plain text
+const TEXT_META: &str = "text";
+/// Meta key for the length of the content in seconds
+const DURATION_META: &str = "duration";
+/// Meta key for the combined size of the files in bytes, as reported by the source
+const ESTIMATED_SIZE_META: &str = "estimated-size";
+/// Meta key extractors set when the URLs are the items of a playlist, channel or profile
+/// that can each be downloaded on their own
+const COLLECTION_META: &str = "collection";
+/// Meta key with the extractor that found the info
+pub const EXTRACTOR_META: &str = "extractor";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractedInfo {
     pub request: ExtractInfoRequest,
@@ -90,6 +108,106 @@ impl ExtractedInfo {
         self
     }
 
+    #[must_use]
+    pub fn with_age_restricted(self, age_restricted: bool) -> Self {
+        self.with_meta(AGE_RESTRICTED_META, age_restricted)
+    }
+
+    #[must_use]
+    pub fn is_age_restricted(&self) -> bool {
+        self.meta
+            .get(AGE_RESTRICTED_META)
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or_default()
+    }
+
+    #[must_use]
+    pub fn with_title<T>(self, title: Option<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        match title.map(Into::into).filter(|x| !x.trim().is_empty()) {
+            Some(title) => self.with_meta(TITLE_META, title),
+            None => self,
+        }
+    }
+
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.meta
+            .get(TITLE_META)
+            .and_then(serde_json::Value::as_str)
+    }
+
+    #[must_use]
+    pub fn with_text<T>(self, text: Option<T>) -> Self
+    where
+        T: Into<String>,
+    {
+        match text.map(Into::into).filter(|x| !x.trim().is_empty()) {
+            Some(text) => self.with_meta(TEXT_META, text),
+            None => self,
+        }
+    }
+
+    #[must_use]
+    pub fn text(&self) -> Option<&str> {
+        self.meta.get(TEXT_META).and_then(serde_json::Value::as_str)
+    }
+
+    #[must_use]
+    pub fn with_duration(self, duration: Option<Duration>) -> Self {
+        match duration {
+            Some(duration) => self.with_meta(DURATION_META, duration.as_secs()),
+            None => self,
+        }
+    }
+
+    #[must_use]
+    pub fn duration(&self) -> Option<Duration> {
+        self.meta
+            .get(DURATION_META)
+            .and_then(serde_json::Value::as_u64)
+            .map(Duration::from_secs)
+    }
+
+    #[must_use]
+    pub fn with_estimated_size(self, size: Option<u64>) -> Self {
+        match size {
+            Some(size) => self.with_meta(ESTIMATED_SIZE_META, size),
+            None => self,
+        }
+    }
+
+    #[must_use]
+    pub fn estimated_size(&self) -> Option<u64> {
+        self.meta
+            .get(ESTIMATED_SIZE_META)
+            .and_then(serde_json::Value::as_u64)
+    }
+
+    #[must_use]
+    pub fn with_collection(self, collection: bool) -> Self {
+        self.with_meta(COLLECTION_META, collection)
+    }
+
+    #[must_use]
+    pub fn is_collection(&self) -> bool {
+        self.meta
+            .get(COLLECTION_META)
+            .and_then(serde_json::Value::as_bool)
+            .unwrap_or_default()
+    }
+
+    /// Name of the extractor that found the info
+    #[must_use]
+    pub fn extractor_name(&self) -> Option<&str> {
+        self.meta
+            .get(EXTRACTOR_META)
+            .and_then(|x| x.get("$extractor"))
+            .and_then(serde_json::Value::as_str)
+    }
+
     #[must_use]
     pub fn dedup_urls(mut self) -> Self {
         self.urls.dedup();
@@ -97,13 +215,82 @@ impl ExtractedInfo {
     }
 }
 
+/// What was found for a link, without the internals needed to download it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionSummary {
+    pub url: String,
+    pub extractor: Option<String>,
+    pub title: Option<String>,
+    /// Text of the post, if the link is to one
+    pub text: Option<String>,
+    pub duration_seconds: Option<u64>,
+    /// Combined size of the files in bytes, as reported by the source
+    pub estimated_size: Option<u64>,
+    pub age_restricted: bool,
+    /// What can be picked from when downloading, eg. `720p` or `audio`
+    pub formats: Vec<String>,
+    pub media: Vec<ExtractedMediaSummary>,
+}
+impl From<&ExtractedInfo> for ExtractionSummary {
+    fn from(info: &ExtractedInfo) -> Self {
+        Self {
+            url: info.request.url.to_string(),
+            extractor: info.extractor_name().map(ToString::to_string),
+            title: info.title().map(ToString::to_string),
+            text: info.text().map(ToString::to_string),
+            duration_seconds: info.duration().map(|x| x.as_secs()),
+            estimated_size: info.estimated_size(),
+            age_restricted: info.is_age_restricted(),
+            formats: format_choices(info)
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            media: info
+                .urls
+                .iter()
+                .map(|x| ExtractedMediaSummary {
+                    url: x.url.url().to_string(),
+                    mirrors: x.mirrors.iter().map(|x| x.url().to_string()).collect(),
+                    downloader: x
+                        .preferred_downloader
+                        .as_ref()
+                        .map(|x| x.name().to_string()),
+                    screenshot: is_screenshot(x),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractedMediaSummary {
+    /// The resolved URL of the media
+    pub url: String,
+    /// Other URLs serving the same file
+    pub mirrors: Vec<String>,
+    /// `None` if any downloader can be used
+    pub downloader: Option<String>,
+    /// A screenshot of the page itself rather than media from it
+    pub screenshot: bool,
+}
+
 pub type PreferredDownloader = Arc<dyn Downloader>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtractedUrlInfo {
     pub url: UrlWithMeta,
+    /// Other URLs serving the same file, eg. other CDNs.
+    ///
+    /// Tried in order if the URL itself can't be downloaded.
+    #[serde(default)]
+    pub mirrors: Vec<UrlWithMeta>,
     pub preferred_downloader: Option<PreferredDownloader>,
     pub downloader_options: DownloaderOptions,
+    /// When the item was published, if it's one of the items of a collection
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upload_date: Option<NaiveDate>,
 }
 impl ExtractedUrlInfo {
     #[must_use]
@@ -113,11 +300,23 @@ impl ExtractedUrlInfo {
     {
         Self {
             url: url.into(),
+            mirrors: vec![],
             preferred_downloader: None,
             downloader_options: HashMap::new(),
+            upload_date: None,
         }
     }
 
+    #[must_use]
+    pub fn with_mirrors<M, U>(mut self, mirrors: M) -> Self
+    where
+        M: IntoIterator<Item = U>,
+        U: Into<UrlWithMeta>,
+    {
+        self.mirrors = mirrors.into_iter().map(Into::into).collect();
+        self
+    }
+
     #[must_use]
     pub fn with_preferred_downloader<D>(mut self, downloader: Option<D>) -> Self
     where
@@ -151,6 +350,12 @@ impl ExtractedUrlInfo {
         self
     }
 
+    #[must_use]
+    pub const fn with_upload_date(mut self, upload_date: Option<NaiveDate>) -> Self {
+        self.upload_date = upload_date;
+        self
+    }
+
     #[must_use]
     pub fn downloader_option(&self, key: &str) -> Option<&serde_json::Value> {
         self.downloader_options.get(key)
@@ -6,7 +6,10 @@ use url::Url;
 
 use super::{node_info::NodeInfo, APHandler, HandleResult};
 use crate::{
-    common::request::Client,
+    common::{
+        html::html_to_text,
+        request::{send_with_quota, Client},
+    },
     extractors::{handlers::twitter::Twitter, ExtractedUrlInfo},
 };
 
@@ -47,13 +50,19 @@ impl APHandler for MastodonHandler {
 
         urls.push(Twitter.screenshot_tweet_url_info(url));
 
-        Ok(HandleResult::Handled(urls))
+        Ok(HandleResult::Handled {
+            urls,
+            text: Some(html_to_text(&toot_info.content)),
+        })
     }
 }
 
 #[derive(Debug, Deserialize)]
 struct TootInfo {
     url: Url,
+    /// HTML of the toot
+    #[serde(default)]
+    content: String,
     media_attachments: Vec<MediaAttachment>,
 }
 impl TootInfo {
@@ -69,9 +78,7 @@ impl TootInfo {
         };
         trace!(?api_url, ?id, "Getting toot info");
 
-        Client::base()?
-            .get(api_url.as_str())
-            .send()
+        send_with_quota(Client::base()?.get(api_url.as_str()))
             .await
             .map_err(|e| format!("Failed to get toot info: {:?}", e))?
             .json()
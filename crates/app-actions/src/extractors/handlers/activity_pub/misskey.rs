@@ -45,13 +45,18 @@ impl APHandler for MisskeyHandler {
 
         urls.push(Twitter.screenshot_tweet_url_info(url));
 
-        Ok(HandleResult::Handled(urls))
+        Ok(HandleResult::Handled {
+            urls,
+            text: post_info.text,
+        })
     }
 }
 
 #[derive(Debug, Deserialize)]
 struct PostInfo {
     files: Vec<PostFile>,
+    /// Misskey flavoured markdown
+    text: Option<String>,
 }
 impl PostInfo {
     #[tracing::instrument(skip(url))]
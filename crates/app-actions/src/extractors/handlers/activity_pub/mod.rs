@@ -1,3 +1,4 @@
+use app_errors::{AppError, UnsupportedSource};
 use node_info::{get_node_info, NodeInfo};
 use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
@@ -34,17 +35,16 @@ impl Extractor for ActivityPub {
             .any(|handler| handler.can_handle(&info, &url))
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
         let mut maybe_post_url = request.url.to_string();
         let mut seen_urls = vec![];
 
         'outer: while seen_urls.len() < 10 {
             debug!(url = ?maybe_post_url, "Handling URL");
             if seen_urls.contains(&maybe_post_url) {
-                return Err(format!(
-                    "URL loop detected ({}). Aborting.",
-                    seen_urls.join(" -> ")
-                ));
+                return Err(
+                    format!("URL loop detected ({}). Aborting.", seen_urls.join(" -> ")).into(),
+                );
             }
             seen_urls.push(maybe_post_url.clone());
 
@@ -69,9 +69,9 @@ impl Extractor for ActivityPub {
                 trace!(?result, "Got result");
 
                 match result {
-                    HandleResult::Handled(urls) => {
+                    HandleResult::Handled { urls, text } => {
                         trace!(?urls, "Got URLs");
-                        return Ok(ExtractedInfo::from_urls(request, urls));
+                        return Ok(ExtractedInfo::from_urls(request, urls).with_text(text));
                     }
                     HandleResult::Delegated { url } => {
                         debug!(?url, "Delegating to another handler");
@@ -81,13 +81,14 @@ impl Extractor for ActivityPub {
                 }
             }
 
-            return Err(format!(
+            return Err(UnsupportedSource::new(format!(
                 "No handler found for {:?} on {} version {}",
                 maybe_post_url, info.software.name, info.software.version
-            ));
+            ))
+            .into());
         }
 
-        Err(format!("No handler found for {:?}", maybe_post_url))
+        Err(UnsupportedSource::new(format!("No handler found for {:?}", maybe_post_url)).into())
     }
 }
 
@@ -102,8 +103,14 @@ trait APHandler: std::fmt::Debug + Send + Sync {
 
 #[derive(Debug)]
 enum HandleResult {
-    Handled(Vec<ExtractedUrlInfo>),
-    Delegated { url: String },
+    Handled {
+        urls: Vec<ExtractedUrlInfo>,
+        /// Text of the post, as plain text
+        text: Option<String>,
+    },
+    Delegated {
+        url: String,
+    },
 }
 
 fn handlers() -> Vec<Box<dyn APHandler>> {
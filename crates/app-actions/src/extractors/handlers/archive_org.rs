@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use app_errors::AppError;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client,
+    downloaders::handlers::generic::{Generic, GenericDownloaderOptions},
+    extractors::ExtractedUrlInfo,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ArchiveOrg;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for ArchiveOrg {
+    fn description(&self) -> &'static str {
+        "Gets the files of archive.org items. Downloads the originally uploaded files by default. \
+         Link to a file in the item to only get that one, or add `?format=<format>` to get all \
+         the files in that format (eg. `?format=VBR MP3`)."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        ItemLink::parse(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let link = ItemLink::parse(&request.url)
+            .ok_or_else(|| "Invalid archive.org item url".to_string())?;
+
+        debug!(?link, "Getting archive.org item metadata");
+
+        let item = get_item(&link.identifier).await?;
+
+        trace!(?item, "Got archive.org item");
+
+        let wanted_format = request
+            .url
+            .query_pairs()
+            .find(|(k, _)| k == "format")
+            .map(|(_, v)| v.to_string());
+
+        let files = item
+            .files
+            .iter()
+            .filter(|x| match (&link.file, &wanted_format) {
+                (Some(file), _) => x.name == *file,
+                (None, Some(format)) => x
+                    .format
+                    .as_deref()
+                    .is_some_and(|x| x.eq_ignore_ascii_case(format)),
+                (None, None) => x.is_original(),
+            })
+            .collect::<Vec<_>>();
+
+        debug!(count = files.len(), "Got archive.org files");
+
+        if files.is_empty() {
+            return Err(match (link.file, wanted_format) {
+                (Some(file), _) => format!("File {file:?} not found in archive.org item"),
+                (None, Some(format)) => {
+                    format!("No files in format {format:?} found in archive.org item")
+                }
+                (None, None) => "No original files found in archive.org item".to_string(),
+            }
+            .into());
+        }
+
+        // Missing sizes mean the total would be off, so it's not reported at all
+        let size = files
+            .iter()
+            .map(|x| x.size.as_deref().and_then(|x| x.parse::<u64>().ok()))
+            .sum::<Option<u64>>();
+        // Lengths are only known for audio and video, so only a single file's is useful
+        let duration = match files.as_slice() {
+            [file] => file
+                .length
+                .as_deref()
+                .and_then(|x| x.parse::<f64>().ok())
+                .filter(|x| x.is_finite() && *x >= 0.0)
+                .map(Duration::from_secs_f64),
+            _ => None,
+        };
+
+        let urls = files
+            .into_iter()
+            .map(|x| file_url_info(&link.identifier, x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ExtractedInfo::from_urls(request, urls)
+            .with_title(item.metadata.as_ref().and_then(ItemMetadata::title))
+            .with_duration(duration)
+            .with_estimated_size(size))
+    }
+}
+
+#[derive(Debug)]
+struct ItemLink {
+    identifier: String,
+    /// Path of the file inside the item
+    file: Option<String>,
+}
+impl ItemLink {
+    /// Parses `archive.org/details/<id>[/<file>]` and `archive.org/download/<id>[/<file>]` links
+    fn parse(url: &Url) -> Option<Self> {
+        if !matches!(url.host_str(), Some("archive.org" | "www.archive.org")) {
+            return None;
+        }
+
+        let segments = url
+            .path_segments()?
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                percent_encoding::percent_decode_str(x)
+                    .decode_utf8_lossy()
+                    .to_string()
+            })
+            .collect::<Vec<_>>();
+
+        match segments.as_slice() {
+            [kind, identifier, file @ ..] if kind == "details" || kind == "download" => {
+                Some(Self {
+                    identifier: identifier.clone(),
+                    file: Some(file.join("/")).filter(|x| !x.is_empty()),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn file_url_info(identifier: &str, file: &ItemFile) -> Result<ExtractedUrlInfo, String> {
+    let mut url = Url::parse("https://archive.org/download/").expect("Invalid URL");
+
+    url.path_segments_mut()
+        .map_err(|()| "Invalid archive.org download url".to_string())?
+        .pop_if_empty()
+        .push(identifier)
+        .extend(file.name.split('/'));
+
+    // Files can be in folders inside the item
+    let file_name = file.name.rsplit('/').next().map(ToString::to_string);
+
+    Ok(ExtractedUrlInfo::new(url.as_str())
+        .with_preferred_downloader(Some(Generic))
+        .with_downloader_options(GenericDownloaderOptions::new().with_file_name(file_name)))
+}
+
+#[derive(Debug, Deserialize)]
+struct Item {
+    metadata: Option<ItemMetadata>,
+    #[serde(default)]
+    files: Vec<ItemFile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemMetadata {
+    /// Usually a string, but items can have multiple titles
+    title: Option<serde_json::Value>,
+}
+impl ItemMetadata {
+    fn title(&self) -> Option<String> {
+        match self.title.as_ref()? {
+            serde_json::Value::Array(x) => x.first()?.as_str().map(ToString::to_string),
+            x => x.as_str().map(ToString::to_string),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemFile {
+    name: String,
+    /// `original` for the uploaded files, `derivative` for the ones archive.org generated
+    source: Option<String>,
+    format: Option<String>,
+    /// In bytes, sent as a string
+    size: Option<String>,
+    /// In seconds, sent as a string. Only set for audio and video.
+    length: Option<String>,
+}
+impl ItemFile {
+    fn is_original(&self) -> bool {
+        self.source.as_deref() == Some("original")
+            // Added by archive.org for every item
+            && !matches!(self.format.as_deref(), Some("Metadata" | "Archive BitTorrent"))
+    }
+}
+
+async fn get_item(identifier: &str) -> Result<Item, String> {
+    let mut api_url = Url::parse("https://archive.org/metadata/").expect("Invalid URL");
+    api_url
+        .path_segments_mut()
+        .map_err(|()| "Invalid archive.org metadata url".to_string())?
+        .pop_if_empty()
+        .push(identifier);
+
+    Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to archive.org: {e:?}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from archive.org: {e:?}"))?
+        .json::<Item>()
+        .await
+        .map_err(|e| format!("Failed to parse archive.org response: {e:?}"))
+}
@@ -0,0 +1,141 @@
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client,
+    downloaders::handlers::{generic::Generic, yt_dlp::YtDlp},
+    extractors::ExtractedUrlInfo,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ArtStation;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for ArtStation {
+    fn description(&self) -> &'static str {
+        "Gets all images and embedded videos from ArtStation projects."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::project_hash(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let hash = Self::project_hash(&request.url)
+            .ok_or_else(|| "Invalid ArtStation project url".to_string())?;
+
+        debug!(?hash, "Getting ArtStation project");
+
+        let project = get_project(&hash).await?;
+
+        trace!(?project, "Got ArtStation project");
+
+        let urls = project
+            .assets
+            .iter()
+            .filter_map(ProjectAsset::to_url_info)
+            .collect::<Vec<_>>();
+
+        if urls.is_empty() {
+            return Err("No downloadable assets found in ArtStation project"
+                .to_string()
+                .into());
+        }
+
+        Ok(ExtractedInfo::from_urls(request, urls))
+    }
+}
+
+static PROJECT_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/(?:artwork|projects)/(?P<hash>[a-zA-Z0-9]+)/?").expect("Invalid regex")
+});
+
+static IFRAME_SRC_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"src=["'](?P<src>[^"']+)["']"#).expect("Invalid regex"));
+
+impl ArtStation {
+    #[must_use]
+    pub fn is_artstation_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| x == "artstation.com" || x.ends_with(".artstation.com"))
+    }
+
+    fn project_hash(url: &Url) -> Option<String> {
+        if !Self::is_artstation_url(url) {
+            return None;
+        }
+
+        PROJECT_PATH_MATCH
+            .captures(url.path())
+            .and_then(|x| x.name("hash"))
+            .map(|x| x.as_str().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Project {
+    assets: Vec<ProjectAsset>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ProjectAsset {
+    asset_type: String,
+    #[serde(default)]
+    has_image: bool,
+    image_url: Option<String>,
+    player_embedded: Option<String>,
+}
+impl ProjectAsset {
+    fn to_url_info(&self) -> Option<ExtractedUrlInfo> {
+        match self.asset_type.as_str() {
+            "video" | "video_clip" => {
+                let embed = self.player_embedded.as_deref()?;
+                let src = IFRAME_SRC_MATCH.captures(embed)?.name("src")?.as_str();
+
+                Some(ExtractedUrlInfo::new(src).with_preferred_downloader(Some(YtDlp)))
+            }
+            _ if self.has_image => {
+                let image_url = self.image_url.as_deref()?;
+                let full_size_url = full_size_image_url(image_url);
+
+                // Not every image has a `4k` size, the `large` one is used for those
+                let mirrors = Some(image_url).filter(|x| *x != full_size_url);
+
+                Some(
+                    ExtractedUrlInfo::new(full_size_url.as_str())
+                        .with_mirrors(mirrors)
+                        .with_preferred_downloader(Some(Generic)),
+                )
+            }
+            _ => None,
+        }
+    }
+}
+
+/// `ArtStation` serves the `large` size by default, but the
+/// original upload is usually available under `4k`.
+/// Downloading it fails with a 404 if there isn't one.
+fn full_size_image_url(url: &str) -> String {
+    url.replacen("/large/", "/4k/", 1)
+}
+
+async fn get_project(hash: &str) -> Result<Project, String> {
+    let api_url = format!("https://www.artstation.com/projects/{hash}.json");
+
+    Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to ArtStation: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from ArtStation: {e}"))?
+        .json::<Project>()
+        .await
+        .map_err(|e| format!("Failed to parse ArtStation project: {e}"))
+}
@@ -1,3 +1,4 @@
+use app_errors::AppError;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -25,24 +26,36 @@ impl Extractor for Bsky {
         Self::is_post_url(&request.url)
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
-        let mut urls = match Self::get_bsky_media_urls(&request.url).await {
-            Ok(urls) => urls,
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let thread = match Self::get_post_thread(&request.url).await {
+            Ok(thread) => thread,
             Err(e) => {
-                return Err(format!("Failed to get bsky media urls: {e}"));
+                return Err(format!("Failed to get bsky media urls: {e}").into());
             }
         };
 
+        let mut urls = thread.get_media();
+
+        trace!(?urls, "Got media from post");
+
         urls.push(Twitter.screenshot_tweet_url_info(request.url.as_str()));
 
-        Ok(ExtractedInfo::from_urls(request, urls))
+        Ok(ExtractedInfo::from_urls(request, urls).with_text(thread.get_text()))
     }
 }
 
 impl Bsky {
-    #[tracing::instrument(skip(post_url), fields(post_url = %post_url.as_str()))]
     pub async fn get_bsky_media_urls(post_url: &Url) -> Result<Vec<ExtractedUrlInfo>, String> {
-        debug!("Getting bsky media urls for post url");
+        let media = Self::get_post_thread(post_url).await?.get_media();
+
+        trace!(?media, "Got media from post");
+
+        Ok(media)
+    }
+
+    #[tracing::instrument(skip(post_url), fields(post_url = %post_url.as_str()))]
+    async fn get_post_thread(post_url: &Url) -> Result<GetPostThreadResponse, String> {
+        debug!("Getting bsky post thread for post url");
 
         let Some(parts) = BSKY_PATH_MATCHER.captures(post_url.path()) else {
             return Err("Invalid bsky post url".to_string());
@@ -86,11 +99,7 @@ impl Bsky {
 
         trace!(?resp, "Got response from bsky api");
 
-        let media = resp.get_media();
-
-        trace!(?media, "Got media from post");
-
-        Ok(media)
+        Ok(resp)
     }
 }
 
@@ -126,6 +135,16 @@ impl GetPostThreadResponse {
             GetPostThreadResponseThread::ThreadViewPost { post } => post.get_media(),
         }
     }
+
+    pub fn get_text(&self) -> Option<String> {
+        match &self.thread {
+            GetPostThreadResponseThread::ThreadViewPost { post } => post
+                .record
+                .get("text")
+                .and_then(serde_json::Value::as_str)
+                .map(ToString::to_string),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -141,6 +160,9 @@ struct PostView {
     uri: String,
     cid: String,
     author: ProfileViewBasic,
+    /// The post as it was created, eg. with its text
+    #[serde(default)]
+    record: serde_json::Value,
     embed: Option<PostViewEmbed>,
     indexed_at: String,
 }
@@ -0,0 +1,185 @@
+use std::process::Stdio;
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::domain::host_matches;
+use chrono::{DateTime, NaiveDate};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::{proxy_for, USER_AGENT},
+    extractors::ExtractedUrlInfo,
+};
+
+const YOUTUBE_HOSTS: &[&str] = &["youtube.com", "www.youtube.com", "m.youtube.com"];
+
+/// Path prefixes of `YouTube` channels, which list their uploads
+const YOUTUBE_CHANNEL_PREFIXES: &[&str] = &["/@", "/channel/", "/c/", "/user/"];
+
+/// The most items that are listed, so huge channels don't take forever.
+/// Only matters when the oldest items are wanted, the newest ones are listed first.
+const MAX_LISTED_ITEMS: usize = 1000;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Collection;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Collection {
+    fn description(&self) -> &'static str {
+        "Lists the videos of YouTube playlists and channels and the tracks of SoundCloud sets, so \
+         they're downloaded one by one."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        collection_url(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let url = collection_url(&request.url).ok_or_else(|| {
+            AppError::other(format!(
+                "Not a playlist or channel URL: {url}",
+                url = request.url.as_str()
+            ))
+        })?;
+
+        let playlist = list_items(&url).await?;
+
+        debug!(
+            title = ?playlist.title,
+            count = playlist.entries.len(),
+            "Got collection items"
+        );
+
+        let urls = playlist
+            .entries
+            .iter()
+            .filter_map(|x| {
+                let url = Url::parse(x.webpage_url.as_deref().or(x.url.as_deref())?).ok()?;
+
+                Some(ExtractedUrlInfo::new(url.as_str()).with_upload_date(x.upload_date()))
+            })
+            .collect::<Vec<_>>();
+
+        Ok(ExtractedInfo::from_urls(request, urls)
+            .with_title(playlist.title)
+            .with_collection(true))
+    }
+}
+
+/// The URL to list the items of, if the URL is of a collection.
+///
+/// Channel home pages list their tabs instead of their videos, so they're pointed to the videos tab.
+fn collection_url(url: &Url) -> Option<Url> {
+    if YOUTUBE_HOSTS.iter().any(|x| host_matches(x, url)) {
+        let path = url.path().trim_end_matches('/');
+
+        if path == "/playlist" && url.query_pairs().any(|(k, _)| k == "list") {
+            return Some(url.clone());
+        }
+
+        let channel_path = YOUTUBE_CHANNEL_PREFIXES
+            .iter()
+            .find_map(|x| path.strip_prefix(x))?;
+        let mut segments = channel_path.split('/');
+        let _channel = segments.next().filter(|x| !x.is_empty())?;
+
+        return match segments.next() {
+            None => {
+                let mut url = url.clone();
+                url.set_path(&format!("{path}/videos"));
+                Some(url)
+            }
+            Some("videos" | "shorts" | "streams") => Some(url.clone()),
+            Some(_) => None,
+        };
+    }
+
+    if host_matches("soundcloud.com", url) || host_matches("m.soundcloud.com", url) {
+        let segments = url
+            .path_segments()?
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        if let [_user, "sets", _set] = segments.as_slice() {
+            return Some(url.clone());
+        }
+    }
+
+    None
+}
+
+async fn list_items(url: &Url) -> Result<Playlist, AppError> {
+    let mut cmd = Command::new(Config::global().dependency_paths.yt_dlp_path());
+    cmd.arg("--no-config")
+        .arg("--flat-playlist")
+        .arg("--dump-single-json")
+        .args(["--playlist-end", &MAX_LISTED_ITEMS.to_string()])
+        // Flat listings of YouTube tabs don't have dates otherwise
+        .args(["--extractor-args", "youtubetab:approximate_date"])
+        .args(["--user-agent", USER_AGENT]);
+
+    if let Some(proxy) = proxy_for(None, url) {
+        cmd.args(["--proxy", proxy.as_str()]);
+    }
+
+    cmd.arg(url.as_str())
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Listing collection items with yt-dlp");
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable("yt-dlp", format!("{e:?}")))?;
+
+    trace!(?output, "yt-dlp output");
+
+    if !output.status.success() {
+        return Err(ExternalToolError::failed(
+            "yt-dlp",
+            format!(
+                "Failed to list the items of {url}: {stderr}",
+                url = url.as_str(),
+                stderr = String::from_utf8_lossy(&output.stderr).trim(),
+            ),
+        )
+        .into());
+    }
+
+    serde_json::from_slice(&output.stdout)
+        .map_err(|e| AppError::other(format!("Failed to parse yt-dlp playlist: {e:?}")))
+}
+
+#[derive(Debug, Deserialize)]
+struct Playlist {
+    title: Option<String>,
+    #[serde(default)]
+    entries: Vec<PlaylistEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlaylistEntry {
+    url: Option<String>,
+    webpage_url: Option<String>,
+    /// `YYYYMMDD`
+    upload_date: Option<String>,
+    timestamp: Option<i64>,
+}
+impl PlaylistEntry {
+    fn upload_date(&self) -> Option<NaiveDate> {
+        self.upload_date
+            .as_deref()
+            .and_then(|x| NaiveDate::parse_from_str(x, "%Y%m%d").ok())
+            .or_else(|| {
+                self.timestamp
+                    .and_then(|x| DateTime::from_timestamp(x, 0))
+                    .map(|x| x.date_naive())
+            })
+    }
+}
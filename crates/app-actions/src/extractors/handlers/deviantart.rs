@@ -0,0 +1,200 @@
+use app_errors::{AppError, UnsupportedSource};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace, warn};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client,
+    downloaders::handlers::{generic::Generic, yt_dlp::YtDlp},
+    extractors::ExtractedUrlInfo,
+};
+
+static OEMBED_ENDPOINT: &str = "https://backend.deviantart.com/oembed";
+
+static EXTENDED_FETCH_ENDPOINT: &str =
+    "https://www.deviantart.com/_napi/shared_api/deviation/extended_fetch";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct DeviantArt;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for DeviantArt {
+    fn description(&self) -> &'static str {
+        "Gets the full resolution image from DeviantArt deviations instead of the preview."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::is_deviation_url(&request.url)
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let deviation = DeviationInfo::from_url(&request.url)
+            .ok_or_else(|| "Invalid DeviantArt deviation url".to_string())?;
+
+        debug!(?deviation, "Extracting deviation");
+
+        match get_download_url(&deviation).await {
+            Ok(Some(url)) => {
+                trace!(?url, "Got deviation download url");
+
+                return Ok(ExtractedInfo::from_url(
+                    request,
+                    ExtractedUrlInfo::new(url).with_preferred_downloader(Some(Generic)),
+                ));
+            }
+            Ok(None) => {
+                debug!("Deviation has no download available, falling back to oembed");
+            }
+            Err(e) => {
+                warn!(
+                    ?e,
+                    "Failed to get deviation download url, falling back to oembed"
+                );
+            }
+        }
+
+        let oembed = get_oembed(&request.url).await?;
+
+        trace!(?oembed, "Got deviation oembed");
+
+        let url_info = match oembed.kind.as_str() {
+            "photo" => oembed
+                .url
+                .map(|x| ExtractedUrlInfo::new(x).with_preferred_downloader(Some(Generic))),
+            "video" | "rich" => Some(
+                ExtractedUrlInfo::new(request.url.as_str()).with_preferred_downloader(Some(YtDlp)),
+            ),
+            _ => None,
+        }
+        .ok_or_else(|| {
+            UnsupportedSource::new(format!(
+                "Unsupported DeviantArt deviation type: {:?}",
+                oembed.kind
+            ))
+        })?;
+
+        Ok(ExtractedInfo::from_url(request, url_info))
+    }
+}
+
+static DEVIATION_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/(?:(?P<username>[^/]+)/)?art/(?:[^/]*-)?(?P<deviation_id>[0-9]+)/?$")
+        .expect("Invalid regex")
+});
+
+impl DeviantArt {
+    #[must_use]
+    pub fn is_deviation_url(url: &Url) -> bool {
+        DeviationInfo::from_url(url).is_some()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DeviationInfo {
+    username: String,
+    deviation_id: String,
+}
+impl DeviationInfo {
+    fn from_url(url: &Url) -> Option<Self> {
+        let host = url.host_str()?;
+        let captures = DEVIATION_PATH_MATCH.captures(url.path())?;
+        let deviation_id = captures.name("deviation_id")?.as_str().to_string();
+
+        let username = match host {
+            "deviantart.com" | "www.deviantart.com" => captures.name("username")?.as_str(),
+            _ => host.strip_suffix(".deviantart.com")?,
+        }
+        .to_string();
+
+        Some(Self {
+            username,
+            deviation_id,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OembedResponse {
+    #[serde(rename = "type")]
+    kind: String,
+    url: Option<String>,
+}
+
+async fn get_oembed(url: &Url) -> Result<OembedResponse, String> {
+    let api_url = {
+        let mut api_url = Url::parse(OEMBED_ENDPOINT).expect("Invalid URL");
+
+        api_url
+            .query_pairs_mut()
+            .extend_pairs([("url", url.as_str()), ("format", "json")]);
+
+        api_url
+    };
+
+    Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to DeviantArt: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from DeviantArt: {e}"))?
+        .json::<OembedResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse DeviantArt oembed response: {e}"))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExtendedFetchResponse {
+    deviation: ExtendedFetchDeviation,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExtendedFetchDeviation {
+    extended: Option<ExtendedFetchDeviationExtended>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExtendedFetchDeviationExtended {
+    download: Option<ExtendedFetchDownload>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExtendedFetchDownload {
+    url: String,
+}
+
+async fn get_download_url(deviation: &DeviationInfo) -> Result<Option<String>, String> {
+    let api_url = {
+        let mut api_url = Url::parse(EXTENDED_FETCH_ENDPOINT).expect("Invalid URL");
+
+        api_url.query_pairs_mut().extend_pairs([
+            ("deviationid", deviation.deviation_id.as_str()),
+            ("username", deviation.username.as_str()),
+            ("type", "art"),
+            ("include_session", "false"),
+        ]);
+
+        api_url
+    };
+
+    let resp = Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to DeviantArt: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from DeviantArt: {e}"))?
+        .json::<ExtendedFetchResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse DeviantArt deviation response: {e}"))?;
+
+    Ok(resp
+        .deviation
+        .extended
+        .and_then(|x| x.download)
+        .map(|x| x.url))
+}
@@ -0,0 +1,128 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use app_config::Config;
+use app_errors::AppError;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client, downloaders::handlers::generic::Generic, extractors::ExtractedUrlInfo,
+};
+
+static REFRESH_URLS_ENDPOINT: &str = "https://discord.com/api/v10/attachments/refresh-urls";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Discord;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Discord {
+    fn description(&self) -> &'static str {
+        "Refreshes expired Discord attachment links before downloading them. Requires a Discord \
+         bot token."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        bot_token().is_some() && Self::is_attachment_url(&request.url)
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let url = if Self::is_expired(&request.url) {
+            debug!(url = ?request.url.as_str(), "Discord attachment link expired, refreshing");
+
+            refresh_url(&request.url).await?
+        } else {
+            request.url.clone()
+        };
+
+        trace!(url = ?url.as_str(), "Got Discord attachment url");
+
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(url.as_str()).with_preferred_downloader(Some(Generic)),
+        ))
+    }
+}
+
+impl Discord {
+    #[must_use]
+    pub fn is_attachment_url(url: &Url) -> bool {
+        let is_discord_cdn = url
+            .host_str()
+            .is_some_and(|x| matches!(x, "cdn.discordapp.com" | "media.discordapp.net"));
+
+        is_discord_cdn && url.path().starts_with("/attachments/")
+    }
+
+    /// Signed links carry their expiry as a hex unix timestamp in the `ex` param.
+    /// Links without a signature are treated as expired since they no longer work.
+    fn is_expired(url: &Url) -> bool {
+        let expires_at = url
+            .query_pairs()
+            .find(|(k, _)| k == "ex")
+            .and_then(|(_, v)| u64::from_str_radix(&v, 16).ok());
+
+        let Some(expires_at) = expires_at else {
+            return true;
+        };
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or_default();
+
+        expires_at <= now
+    }
+}
+
+fn bot_token() -> Option<&'static str> {
+    Config::global()
+        .endpoint
+        .discord_bot_token
+        .as_deref()
+        .filter(|x| !x.is_empty())
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct RefreshUrlsRequest<'a> {
+    attachment_urls: [&'a str; 1],
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RefreshUrlsResponse {
+    refreshed_urls: Vec<RefreshedUrl>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RefreshedUrl {
+    refreshed: String,
+}
+
+async fn refresh_url(url: &Url) -> Result<Url, String> {
+    let token = bot_token().ok_or_else(|| "Discord bot token not set".to_string())?;
+
+    let resp = Client::base()?
+        .post(REFRESH_URLS_ENDPOINT)
+        .header("Authorization", format!("Bot {token}"))
+        .json(&RefreshUrlsRequest {
+            attachment_urls: [url.as_str()],
+        })
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Discord: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Discord: {e}"))?
+        .json::<RefreshUrlsResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse Discord response: {e}"))?;
+
+    trace!(?resp, "Got refreshed Discord urls");
+
+    resp.refreshed_urls
+        .into_iter()
+        .next()
+        .and_then(|x| Url::parse(&x.refreshed).ok())
+        .ok_or_else(|| "Discord did not return a refreshed url".to_string())
+}
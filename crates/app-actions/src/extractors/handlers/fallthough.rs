@@ -1,6 +1,35 @@
+use std::collections::HashMap;
+
+use app_errors::AppError;
+use http::header;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
 
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::{
+        html::{meta_tags, oembed_url},
+        request::Client,
+    },
+    downloaders::FALLBACK_URLS_OPTION,
+};
+
+/// Meta tags with video URLs, from most to least preferred
+static VIDEO_TAGS: &[&str] = &[
+    "twitter:player:stream",
+    "og:video:secure_url",
+    "og:video:url",
+    "og:video",
+];
+
+/// Meta tags with image URLs, from most to least preferred
+static IMAGE_TAGS: &[&str] = &[
+    "og:image:secure_url",
+    "og:image:url",
+    "og:image",
+    "twitter:image",
+];
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Fallthrough;
@@ -9,14 +38,154 @@ pub struct Fallthrough;
 #[typetag::serde]
 impl Extractor for Fallthrough {
     fn description(&self) -> &'static str {
-        "Fallthrough extractor. Does nothing, just forwards the URL."
+        "Fallthrough extractor. Forwards the URL as-is. If it's a page that can't be downloaded \
+         itself, the media from its OpenGraph/Twitter card tags or oEmbed endpoint is downloaded \
+         instead."
     }
 
     async fn can_handle(&self, _request: &ExtractInfoRequest) -> bool {
         true
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
-        Ok(ExtractedInfo::from_url(request, request.url.as_str()))
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let info = ExtractedInfo::from_url(request, request.url.as_str());
+
+        if !matches!(request.url.scheme(), "http" | "https") {
+            return Ok(info);
+        }
+
+        // The page might still be downloadable even if it can't be read here
+        let media_urls = match page_media_urls(request).await {
+            Ok(x) => x,
+            Err(e) => {
+                debug!(?e, "Failed to get media from page");
+                return Ok(info);
+            }
+        };
+
+        debug!(?media_urls, "Got page media urls");
+
+        if media_urls.is_empty() {
+            return Ok(info);
+        }
+
+        let media_urls = media_urls.iter().map(Url::as_str).collect::<Vec<_>>();
+
+        Ok(info.with_downloader_option(FALLBACK_URLS_OPTION, media_urls))
+    }
+}
+
+/// The media the page advertises, from most to least preferred
+async fn page_media_urls(request: &ExtractInfoRequest) -> Result<Vec<Url>, String> {
+    let res = request
+        .as_request_builder()?
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request for page: {e:?}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get page: {e:?}"))?;
+
+    let is_html = res
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.split(';').next())
+        .is_some_and(|x| {
+            matches!(
+                x.trim().to_lowercase().as_str(),
+                "text/html" | "application/xhtml+xml"
+            )
+        });
+
+    // Direct links to files and the like, the body isn't needed
+    if !is_html {
+        return Ok(vec![]);
+    }
+
+    let page_url = res.url().clone();
+    let html = res
+        .text()
+        .await
+        .map_err(|e| format!("Failed to get text from page: {e:?}"))?;
+
+    let (tags, oembed_url) = tokio::task::spawn_blocking(move || {
+        Ok::<_, String>((meta_tags(&html)?, oembed_url(&html)?))
+    })
+    .await
+    .map_err(|e| format!("Failed to parse page: {e:?}"))??;
+
+    trace!(?tags, ?oembed_url, "Got page meta tags");
+
+    let oembed_media = match oembed_url.and_then(|x| page_url.join(&x).ok()) {
+        Some(oembed_url) => get_oembed_media(&oembed_url).await.unwrap_or_else(|e| {
+            debug!(?e, "Failed to get oembed media");
+            None
+        }),
+        None => None,
+    };
+
+    let candidates = video_urls(&tags)
+        .into_iter()
+        .chain(oembed_media)
+        .chain(IMAGE_TAGS.iter().filter_map(|x| tags.get(*x)).cloned());
+
+    let mut media_urls = vec![];
+    for url in candidates {
+        let Ok(url) = page_url.join(&url) else {
+            continue;
+        };
+
+        if url != page_url && !media_urls.contains(&url) {
+            media_urls.push(url);
+        }
+    }
+
+    Ok(media_urls)
+}
+
+fn video_urls(tags: &HashMap<String, String>) -> Vec<String> {
+    // Players that are pages themselves can't be downloaded directly
+    let is_player_page = tags
+        .get("og:video:type")
+        .is_some_and(|x| x.to_lowercase().starts_with("text/html"));
+
+    if is_player_page {
+        return tags
+            .get("twitter:player:stream")
+            .cloned()
+            .into_iter()
+            .collect();
     }
+
+    VIDEO_TAGS
+        .iter()
+        .filter_map(|x| tags.get(*x))
+        .cloned()
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct OEmbed {
+    #[serde(rename = "type")]
+    kind: String,
+    url: Option<String>,
+}
+
+/// The URL of the image if the oEmbed is for a photo.
+/// Other kinds only have HTML to embed.
+async fn get_oembed_media(oembed_url: &Url) -> Result<Option<String>, String> {
+    let oembed = Client::base()?
+        .get(oembed_url.as_str())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request for oembed: {e:?}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get oembed: {e:?}"))?
+        .json::<OEmbed>()
+        .await
+        .map_err(|e| format!("Failed to parse oembed: {e:?}"))?;
+
+    trace!(?oembed, "Got oembed");
+
+    Ok(oembed.url.filter(|_| oembed.kind == "photo"))
 }
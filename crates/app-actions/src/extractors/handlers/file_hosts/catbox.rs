@@ -0,0 +1,65 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use tracing::trace;
+use url::Url;
+
+use super::{FileHostHandler, HostedFile};
+use crate::common::request::Client;
+
+static ALBUM_FILE_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"https://files\.catbox\.moe/[a-zA-Z0-9]+\.[a-zA-Z0-9]+").expect("Invalid regex")
+});
+
+#[derive(Debug)]
+pub struct CatboxHandler;
+
+#[async_trait::async_trait]
+impl FileHostHandler for CatboxHandler {
+    fn can_handle(&self, url: &Url) -> bool {
+        Self::is_file_url(url) || Self::is_album_url(url)
+    }
+
+    async fn handle(&self, url: &Url) -> Result<Vec<HostedFile>, String> {
+        // Catbox doesn't keep the uploaded file names, the file links are already direct
+        if Self::is_file_url(url) {
+            return Ok(vec![HostedFile::new(url.as_str(), None)]);
+        }
+
+        let html = Client::base()?
+            .get(url.as_str())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request to catbox: {e}"))?
+            .error_for_status()
+            .map_err(|e| format!("Failed to get response from catbox: {e}"))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to get text from catbox response: {e}"))?;
+
+        let mut urls = ALBUM_FILE_MATCH
+            .find_iter(&html)
+            .map(|x| x.as_str().to_string())
+            .collect::<Vec<_>>();
+        urls.dedup();
+
+        trace!(?urls, "Got catbox album files");
+
+        Ok(urls.into_iter().map(|x| HostedFile::new(x, None)).collect())
+    }
+}
+
+impl CatboxHandler {
+    /// `files.catbox.moe/<id>.<ext>` and `litter.catbox.moe/<id>.<ext>`
+    fn is_file_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| matches!(x, "files.catbox.moe" | "litter.catbox.moe"))
+            && url.path().len() > 1
+    }
+
+    /// `catbox.moe/c/<id>`
+    fn is_album_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| matches!(x, "catbox.moe" | "www.catbox.moe"))
+            && url.path().starts_with("/c/")
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{FileHostHandler, HostedFile};
+use crate::common::{request::Client, url::UrlWithMeta};
+
+static API_BASE: &str = "https://api.gofile.io";
+
+/// The website token the API wants is embedded in the site's scripts
+static WEBSITE_TOKEN_SCRIPT: &str = "https://gofile.io/dist/js/global.js";
+
+static WEBSITE_TOKEN_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r#"\.wt\s*=\s*["'](?P<token>[^"']+)["']"#).expect("Invalid regex"));
+
+#[derive(Debug)]
+pub struct GofileHandler;
+
+#[async_trait::async_trait]
+impl FileHostHandler for GofileHandler {
+    fn can_handle(&self, url: &Url) -> bool {
+        content_id(url).is_some()
+    }
+
+    async fn handle(&self, url: &Url) -> Result<Vec<HostedFile>, String> {
+        let content_id = content_id(url).ok_or_else(|| "Invalid gofile url".to_string())?;
+
+        debug!(?content_id, "Getting gofile content");
+
+        let account_token = create_guest_account().await?;
+        let website_token = get_website_token().await?;
+
+        let content = call_api::<Content>(
+            &format!("contents/{content_id}?wt={website_token}"),
+            &account_token,
+        )
+        .await?;
+
+        trace!(?content, "Got gofile content");
+
+        let files = match content {
+            Content {
+                link: Some(link),
+                name,
+                ..
+            } => vec![(link, name)],
+            Content { children, .. } => children
+                .into_values()
+                .filter(|x| x.content_type.as_deref() == Some("file"))
+                .filter_map(|x| Some((x.link?, x.name)))
+                .collect(),
+        };
+
+        // Downloads are only allowed for the account that fetched the content
+        Ok(files
+            .into_iter()
+            .filter_map(|(link, name)| {
+                let url = Url::parse(&link).ok()?;
+                let url = UrlWithMeta::from_url(url.as_str())
+                    .with_header("Cookie", &format!("accountToken={account_token}"));
+
+                Some(HostedFile::new(url, name))
+            })
+            .collect())
+    }
+}
+
+/// `gofile.io/d/<id>`
+fn content_id(url: &Url) -> Option<String> {
+    if !url
+        .host_str()
+        .is_some_and(|x| matches!(x, "gofile.io" | "www.gofile.io"))
+    {
+        return None;
+    }
+
+    url.path()
+        .strip_prefix("/d/")
+        .map(|x| x.trim_end_matches('/'))
+        .filter(|x| !x.is_empty() && !x.contains('/'))
+        .map(ToString::to_string)
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    status: String,
+    data: Option<T>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Account {
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(clippy::struct_field_names)]
+struct Content {
+    #[serde(rename = "type")]
+    content_type: Option<String>,
+    name: Option<String>,
+    link: Option<String>,
+    #[serde(default)]
+    children: HashMap<String, Self>,
+}
+
+async fn create_guest_account() -> Result<String, String> {
+    let resp = Client::base()?
+        .post(format!("{API_BASE}/accounts"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to gofile: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from gofile: {e}"))?
+        .json::<ApiResponse<Account>>()
+        .await
+        .map_err(|e| format!("Failed to parse gofile response: {e}"))?;
+
+    match resp {
+        ApiResponse {
+            data: Some(account),
+            ..
+        } => Ok(account.token),
+        ApiResponse { status, .. } => Err(format!("Failed to create gofile account: {status}")),
+    }
+}
+
+async fn get_website_token() -> Result<String, String> {
+    let script = Client::base()?
+        .get(WEBSITE_TOKEN_SCRIPT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to gofile: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from gofile: {e}"))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to get text from gofile response: {e}"))?;
+
+    WEBSITE_TOKEN_MATCH
+        .captures(&script)
+        .and_then(|x| x.name("token"))
+        .map(|x| x.as_str().to_string())
+        .ok_or_else(|| "Failed to find gofile website token".to_string())
+}
+
+async fn call_api<T>(path: &str, account_token: &str) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let resp = Client::base()?
+        .get(format!("{API_BASE}/{path}"))
+        .bearer_auth(account_token)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to gofile: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from gofile: {e}"))?
+        .json::<ApiResponse<T>>()
+        .await
+        .map_err(|e| format!("Failed to parse gofile response: {e}"))?;
+
+    match resp {
+        ApiResponse {
+            status,
+            data: Some(data),
+        } if status == "ok" => Ok(data),
+        ApiResponse { status, .. } => Err(format!("Gofile returned an error: {status}")),
+    }
+}
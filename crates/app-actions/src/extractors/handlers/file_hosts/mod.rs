@@ -0,0 +1,94 @@
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::url::UrlWithMeta,
+    downloaders::handlers::generic::{Generic, GenericDownloaderOptions},
+    extractors::ExtractedUrlInfo,
+};
+
+pub mod catbox;
+pub mod gofile;
+pub mod pixeldrain;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FileHosts;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for FileHosts {
+    fn description(&self) -> &'static str {
+        "Gets the direct file URLs and original file names from simple file hosts (gofile, \
+         catbox, pixeldrain)."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        HANDLERS.iter().any(|x| x.can_handle(&request.url))
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let handler = HANDLERS
+            .iter()
+            .find(|x| x.can_handle(&request.url))
+            .ok_or_else(|| format!("No file host handler found for {:?}", request.url.as_str()))?;
+
+        debug!(?handler, "Handling file host url");
+
+        let files = handler.handle(&request.url).await?;
+
+        trace!(?files, "Got file host files");
+
+        if files.is_empty() {
+            return Err("No files found".to_string().into());
+        }
+
+        Ok(ExtractedInfo::from_urls(
+            request,
+            files.into_iter().map(HostedFile::into_url_info),
+        ))
+    }
+}
+
+static HANDLERS: Lazy<Vec<Box<dyn FileHostHandler>>> = Lazy::new(handlers);
+
+#[async_trait::async_trait]
+trait FileHostHandler: std::fmt::Debug + Send + Sync {
+    fn can_handle(&self, url: &Url) -> bool;
+
+    async fn handle(&self, url: &Url) -> Result<Vec<HostedFile>, String>;
+}
+
+#[derive(Debug)]
+struct HostedFile {
+    url: UrlWithMeta,
+    name: Option<String>,
+}
+impl HostedFile {
+    fn new<U>(url: U, name: Option<String>) -> Self
+    where
+        U: Into<UrlWithMeta>,
+    {
+        Self {
+            url: url.into(),
+            name,
+        }
+    }
+
+    fn into_url_info(self) -> ExtractedUrlInfo {
+        ExtractedUrlInfo::new(self.url)
+            .with_preferred_downloader(Some(Generic))
+            .with_downloader_options(GenericDownloaderOptions::new().with_file_name(self.name))
+    }
+}
+
+fn handlers() -> Vec<Box<dyn FileHostHandler>> {
+    vec![
+        Box::new(gofile::GofileHandler),
+        Box::new(catbox::CatboxHandler),
+        Box::new(pixeldrain::PixeldrainHandler),
+    ]
+}
@@ -0,0 +1,89 @@
+use serde::Deserialize;
+use tracing::trace;
+use url::Url;
+
+use super::{FileHostHandler, HostedFile};
+use crate::common::request::Client;
+
+static API_BASE: &str = "https://pixeldrain.com/api";
+
+#[derive(Debug)]
+pub struct PixeldrainHandler;
+
+#[async_trait::async_trait]
+impl FileHostHandler for PixeldrainHandler {
+    fn can_handle(&self, url: &Url) -> bool {
+        PixeldrainUrl::parse(url).is_some()
+    }
+
+    async fn handle(&self, url: &Url) -> Result<Vec<HostedFile>, String> {
+        let files = match PixeldrainUrl::parse(url) {
+            Some(PixeldrainUrl::File(id)) => {
+                vec![get_api::<FileInfo>(&format!("file/{id}/info")).await?]
+            }
+            Some(PixeldrainUrl::List(id)) => {
+                get_api::<ListInfo>(&format!("list/{id}")).await?.files
+            }
+            None => return Err("Invalid pixeldrain url".to_string()),
+        };
+
+        trace!(?files, "Got pixeldrain files");
+
+        Ok(files
+            .into_iter()
+            .map(|x| HostedFile::new(format!("{API_BASE}/file/{}?download", x.id), Some(x.name)))
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+enum PixeldrainUrl {
+    File(String),
+    List(String),
+}
+impl PixeldrainUrl {
+    /// `pixeldrain.com/u/<id>` for files and `pixeldrain.com/l/<id>` for lists
+    fn parse(url: &Url) -> Option<Self> {
+        if !url
+            .host_str()
+            .is_some_and(|x| matches!(x, "pixeldrain.com" | "www.pixeldrain.com"))
+        {
+            return None;
+        }
+
+        let mut segments = url.path_segments()?.filter(|x| !x.is_empty());
+
+        match (segments.next()?, segments.next()?) {
+            ("u", id) => Some(Self::File(id.to_string())),
+            ("l", id) => Some(Self::List(id.to_string())),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FileInfo {
+    id: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListInfo {
+    files: Vec<FileInfo>,
+}
+
+async fn get_api<T>(path: &str) -> Result<T, String>
+where
+    T: serde::de::DeserializeOwned,
+{
+    Client::base()?
+        .get(format!("{API_BASE}/{path}"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to pixeldrain: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from pixeldrain: {e}"))?
+        .json::<T>()
+        .await
+        .map_err(|e| format!("Failed to parse pixeldrain response: {e}"))
+}
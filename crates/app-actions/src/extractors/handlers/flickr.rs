@@ -0,0 +1,266 @@
+use app_config::Config;
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client, downloaders::handlers::generic::Generic, extractors::ExtractedUrlInfo,
+};
+
+static FLICKR_API_ENDPOINT: &str = "https://api.flickr.com/services/rest/";
+
+const ALBUM_PAGE_SIZE: u32 = 500;
+const MAX_ALBUM_PAGES: u32 = 20;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Flickr;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Flickr {
+    fn description(&self) -> &'static str {
+        "Gets original-size images from Flickr photos and albums. Requires a Flickr API key."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        api_key().is_some() && FlickrUrl::parse(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let flickr_url = FlickrUrl::parse(&request.url)
+            .ok_or_else(|| "Invalid flickr photo or album url".to_string())?;
+
+        debug!(?flickr_url, "Extracting flickr media");
+
+        let urls = match flickr_url {
+            FlickrUrl::Photo { photo_id } => vec![get_photo_original_url(&photo_id).await?],
+            FlickrUrl::Album { album_id } => get_album_original_urls(&album_id).await?,
+        };
+
+        trace!(?urls, "Got flickr media urls");
+
+        let urls = urls
+            .into_iter()
+            .map(|x| ExtractedUrlInfo::new(x).with_preferred_downloader(Some(Generic)));
+
+        Ok(ExtractedInfo::from_urls(request, urls))
+    }
+}
+
+static PHOTO_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/photos/(?P<user>[^/]+)/(?P<photo_id>[0-9]+)/?").expect("Invalid regex")
+});
+
+static ALBUM_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/photos/(?P<user>[^/]+)/(?:albums|sets)/(?P<album_id>[0-9]+)/?")
+        .expect("Invalid regex")
+});
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum FlickrUrl {
+    Photo { photo_id: String },
+    Album { album_id: String },
+}
+impl FlickrUrl {
+    fn parse(url: &Url) -> Option<Self> {
+        if !Flickr::is_flickr_url(url) {
+            return None;
+        }
+
+        let path = url.path();
+
+        if let Some(album_id) = ALBUM_PATH_MATCH
+            .captures(path)
+            .and_then(|x| x.name("album_id"))
+        {
+            return Some(Self::Album {
+                album_id: album_id.as_str().to_string(),
+            });
+        }
+
+        PHOTO_PATH_MATCH
+            .captures(path)
+            .and_then(|x| x.name("photo_id"))
+            .map(|photo_id| Self::Photo {
+                photo_id: photo_id.as_str().to_string(),
+            })
+    }
+}
+
+impl Flickr {
+    #[must_use]
+    pub fn is_flickr_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| matches!(x, "flickr.com" | "www.flickr.com" | "m.flickr.com"))
+    }
+}
+
+fn api_key() -> Option<&'static str> {
+    Config::global()
+        .endpoint
+        .flickr_api_key
+        .as_deref()
+        .filter(|x| !x.is_empty())
+}
+
+async fn call_api<T>(method: &str, params: &[(&str, &str)]) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let api_key = api_key().ok_or_else(|| "Flickr API key not configured".to_string())?;
+
+    let api_url = {
+        let mut url = Url::parse(FLICKR_API_ENDPOINT).expect("Invalid URL");
+
+        url.query_pairs_mut()
+            .extend_pairs([
+                ("method", method),
+                ("api_key", api_key),
+                ("format", "json"),
+                ("nojsoncallback", "1"),
+            ])
+            .extend_pairs(params);
+
+        url
+    };
+
+    trace!(?method, ?params, "Calling flickr api");
+
+    let resp = Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to flickr: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from flickr: {e}"))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse flickr response: {e}"))?;
+
+    if resp.get("stat").and_then(|x| x.as_str()) != Some("ok") {
+        let message = resp
+            .get("message")
+            .and_then(|x| x.as_str())
+            .unwrap_or("Unknown error");
+
+        return Err(format!("Flickr API returned an error: {message}"));
+    }
+
+    serde_json::from_value(resp).map_err(|e| format!("Failed to deserialize flickr response: {e}"))
+}
+
+async fn get_photo_original_url(photo_id: &str) -> Result<String, String> {
+    let resp =
+        call_api::<GetSizesResponse>("flickr.photos.getSizes", &[("photo_id", photo_id)]).await?;
+
+    trace!(?resp, "Got flickr photo sizes");
+
+    let sizes = resp.sizes.size;
+
+    // Sizes are returned from smallest to largest, so the last one is
+    // the best we can get if the original isn't available.
+    sizes
+        .iter()
+        .find(|x| x.label == "Original")
+        .or_else(|| sizes.last())
+        .map(|x| x.source.clone())
+        .ok_or_else(|| format!("No sizes found for flickr photo {photo_id}"))
+}
+
+async fn get_album_original_urls(album_id: &str) -> Result<Vec<String>, String> {
+    let mut urls = vec![];
+
+    for page in 1..=MAX_ALBUM_PAGES {
+        let page_str = page.to_string();
+        let per_page = ALBUM_PAGE_SIZE.to_string();
+
+        let resp = call_api::<GetPhotosetPhotosResponse>(
+            "flickr.photosets.getPhotos",
+            &[
+                ("photoset_id", album_id),
+                ("extras", "url_o,url_k,url_h,url_b"),
+                ("per_page", per_page.as_str()),
+                ("page", page_str.as_str()),
+            ],
+        )
+        .await?;
+
+        trace!(?page, photos = ?resp.photoset.photo.len(), "Got flickr album page");
+
+        let pages = resp.photoset.pages();
+        for photo in resp.photoset.photo {
+            let url = match photo.best_url() {
+                Some(x) => x.to_string(),
+                None => get_photo_original_url(&photo.id).await?,
+            };
+
+            urls.push(url);
+        }
+
+        if page >= pages {
+            break;
+        }
+    }
+
+    Ok(urls)
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GetSizesResponse {
+    sizes: Sizes,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Sizes {
+    size: Vec<Size>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Size {
+    label: String,
+    source: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GetPhotosetPhotosResponse {
+    photoset: Photoset,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Photoset {
+    photo: Vec<PhotosetPhoto>,
+    // Flickr is inconsistent about returning this as a number or a string
+    pages: serde_json::Value,
+}
+impl Photoset {
+    fn pages(&self) -> u32 {
+        match &self.pages {
+            serde_json::Value::Number(x) => x.as_u64().and_then(|x| u32::try_from(x).ok()),
+            serde_json::Value::String(x) => x.parse().ok(),
+            _ => None,
+        }
+        .unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PhotosetPhoto {
+    id: String,
+    url_o: Option<String>,
+    url_k: Option<String>,
+    url_h: Option<String>,
+    url_b: Option<String>,
+}
+impl PhotosetPhoto {
+    fn best_url(&self) -> Option<&str> {
+        self.url_o
+            .as_deref()
+            .or(self.url_k.as_deref())
+            .or(self.url_h.as_deref())
+            .or(self.url_b.as_deref())
+    }
+}
@@ -0,0 +1,76 @@
+use app_config::Config;
+use app_errors::AppError;
+use app_helpers::domain::host_matches;
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{downloaders::handlers::gallery_dl::GalleryDl, extractors::ExtractedUrlInfo};
+
+/// Image boards and galleries that gallery-dl knows how to download from
+/// and none of the other extractors handle
+const GALLERY_HOSTS: &[&str] = &[
+    "danbooru.donmai.us",
+    "safebooru.donmai.us",
+    "gelbooru.com",
+    "safebooru.org",
+    "yande.re",
+    "konachan.com",
+    "konachan.net",
+    "e621.net",
+    "e926.net",
+    "zerochan.net",
+    "wallhaven.cc",
+    "boards.4chan.org",
+    "boards.4channel.org",
+    "archived.moe",
+    "desuarchive.org",
+    "pixiv.net",
+    "www.pixiv.net",
+    "nijie.info",
+    "sankakucomplex.com",
+    "*.sankakucomplex.com",
+    "newgrounds.com",
+    "*.newgrounds.com",
+    "furaffinity.net",
+    "www.furaffinity.net",
+    "inkbunny.net",
+    "imgbox.com",
+    "imgchest.com",
+    "kemono.su",
+    "mangadex.org",
+];
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Gallery;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Gallery {
+    fn description(&self) -> &'static str {
+        "Hands posts from image boards and gallery sites (eg. danbooru, pixiv, 4chan) off to \
+         gallery-dl. Requires gallery-dl."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Config::global()
+            .dependency_paths
+            .gallery_dl_path()
+            .is_some()
+            && Self::is_gallery_url(&request.url)
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(request.url.as_str()).with_preferred_downloader(Some(GalleryDl)),
+        ))
+    }
+}
+
+impl Gallery {
+    #[must_use]
+    pub fn is_gallery_url(url: &Url) -> bool {
+        GALLERY_HOSTS.iter().any(|x| host_matches(x, url))
+    }
+}
@@ -0,0 +1,63 @@
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{downloaders::handlers::generic::Generic, extractors::ExtractedUrlInfo};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Giphy;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Giphy {
+    fn description(&self) -> &'static str {
+        "Gets the MP4 version of GIFs from Giphy."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::gif_id(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let gif_id = Self::gif_id(&request.url).ok_or_else(|| "Invalid Giphy url".to_string())?;
+
+        debug!(?gif_id, "Got Giphy gif id");
+
+        let url = format!("https://media.giphy.com/media/{gif_id}/giphy.mp4");
+
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(url).with_preferred_downloader(Some(Generic)),
+        ))
+    }
+}
+
+static PAGE_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/(?:gifs|stickers|clips|embed)/(?:[^/]*-)?(?P<id>[a-zA-Z0-9]+)/?$")
+        .expect("Invalid regex")
+});
+
+static MEDIA_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/(?:media/(?:v1\.[^/]+/)?)?(?P<id>[a-zA-Z0-9]+)(?:/|\.)").expect("Invalid regex")
+});
+
+impl Giphy {
+    fn gif_id(url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+
+        let matcher = match host {
+            "giphy.com" | "www.giphy.com" => &PAGE_PATH_MATCH,
+            _ if host == "i.giphy.com" || host.ends_with(".giphy.com") => &MEDIA_PATH_MATCH,
+            _ => return None,
+        };
+
+        matcher
+            .captures(url.path())
+            .and_then(|x| x.name("id"))
+            .map(|x| x.as_str().to_string())
+    }
+}
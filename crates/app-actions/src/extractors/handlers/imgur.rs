@@ -1,10 +1,21 @@
 use std::string::ToString;
 
+use app_errors::AppError;
 use serde::{Deserialize, Serialize};
-use tracing::trace;
+use tracing::{debug, trace, warn};
 use url::Url;
 
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::{send_with_quota, Client},
+    downloaders::handlers::generic::{Generic, GenericDownloaderOptions},
+    extractors::ExtractedUrlInfo,
+};
+
+static POST_API_ENDPOINT: &str = "https://api.imgur.com/post/v1";
+
+/// The public client ID used by the Imgur website itself
+static WEB_CLIENT_ID: &str = "546c25a59c58ad7";
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Imgur;
@@ -20,7 +31,19 @@ impl Extractor for Imgur {
         Self::is_media_url(&request.url) || Self::is_post_url(&request.url)
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        if let Some(album_id) = Self::album_id(&request.url) {
+            debug!(?album_id, "Getting all imgur album items");
+
+            match get_album_data(&album_id).await {
+                Ok(album) => return Ok(album_info(request, &album_id, album)),
+                Err(e) => warn!(
+                    ?e,
+                    "Failed to get imgur album, falling back to the page data"
+                ),
+            }
+        }
+
         let post_data = get_post_data(request).await?;
 
         let media = post_data.media.into_iter().map(|x| x.url);
@@ -51,6 +74,36 @@ impl Imgur {
 
         host.is_some_and(|x| x == "imgur.com") || host.is_some_and(|x| x == "www.imgur.com")
     }
+
+    /// Gets the ID from `imgur.com/a/<id>` and `imgur.com/gallery/<title>-<id>` links
+    fn album_id(url: &Url) -> Option<String> {
+        if !Self::is_post_url(url) {
+            return None;
+        }
+
+        let mut segments = url.path_segments()?.filter(|x| !x.is_empty());
+
+        let id = match (segments.next()?, segments.next()?) {
+            ("a" | "gallery", slug) => slug.rsplit('-').next()?,
+            _ => return None,
+        };
+
+        Some(id.to_string())
+    }
+}
+
+/// One URL per album item. The items are numbered in the file names
+/// (`<album>.<number>.<ext>`), so the files keep the album order.
+fn album_info(request: &ExtractInfoRequest, album_id: &str, album: ImgurPostData) -> ExtractedInfo {
+    let urls = album.media.into_iter().enumerate().map(|(i, x)| {
+        let file_name = x.ext.map(|ext| format!("{album_id}.{:03}.{ext}", i + 1));
+
+        ExtractedUrlInfo::new(x.url)
+            .with_preferred_downloader(Some(Generic))
+            .with_downloader_options(GenericDownloaderOptions::new().with_file_name(file_name))
+    });
+
+    ExtractedInfo::from_urls(request, urls)
 }
 
 #[derive(Debug, Deserialize)]
@@ -61,12 +114,34 @@ struct ImgurPostData {
 #[derive(Debug, Deserialize)]
 struct ImgurPostMedia {
     url: String,
+    ext: Option<String>,
+}
+
+async fn get_album_data(album_id: &str) -> Result<ImgurPostData, String> {
+    let api_url = {
+        let mut api_url = Url::parse(&format!("{POST_API_ENDPOINT}/albums/{album_id}"))
+            .map_err(|e| format!("Invalid imgur album id: {e}"))?;
+
+        api_url
+            .query_pairs_mut()
+            .append_pair("client_id", WEB_CLIENT_ID)
+            .append_pair("include", "media");
+
+        api_url
+    };
+
+    send_with_quota(Client::base()?.get(api_url))
+        .await
+        .map_err(|e| format!("Failed to send request to imgur: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from imgur: {e}"))?
+        .json::<ImgurPostData>()
+        .await
+        .map_err(|e| format!("Failed to parse imgur album: {e}"))
 }
 
 async fn get_post_data(req: &ExtractInfoRequest) -> Result<ImgurPostData, String> {
-    let resp = req
-        .as_request_builder()?
-        .send()
+    let resp = send_with_quota(req.as_request_builder()?)
         .await
         .map_err(|e| format!("Failed to send request to imgur: {:?}", e))?
         .text()
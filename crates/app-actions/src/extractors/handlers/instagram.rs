@@ -1,5 +1,7 @@
 use std::result::Result;
 
+use app_config::Config;
+use app_errors::AppError;
 use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
@@ -7,7 +9,16 @@ use tracing::{debug, trace};
 use url::Url;
 
 use super::{ExtractInfoRequest, Extractor};
-use crate::{common::request::Client, extractors::ExtractedInfo};
+use crate::{
+    common::request::Client,
+    downloaders::handlers::generic::Generic,
+    extractors::{ExtractedInfo, ExtractedUrlInfo},
+};
+
+static PRIVATE_API_ENDPOINT: &str = "https://i.instagram.com/api/v1";
+
+/// The app ID the Instagram website uses for its API requests
+static WEB_APP_ID: &str = "936619743392459";
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Instagram;
@@ -16,14 +27,25 @@ pub struct Instagram;
 #[typetag::serde]
 impl Extractor for Instagram {
     fn description(&self) -> &'static str {
-        "Get images and videos from Instagram posts"
+        "Get images and videos from Instagram posts, stories and highlights"
     }
 
     async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
-        Self::is_post_url(&request.url)
+        Self::is_post_url(&request.url) || Self::is_story_url(&request.url)
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        if let Some(reel) = StoryReel::from_url(&request.url) {
+            let media_urls = get_story_media_urls(&reel).await?;
+
+            return Ok(ExtractedInfo::from_urls(
+                request,
+                media_urls
+                    .into_iter()
+                    .map(|x| ExtractedUrlInfo::new(x).with_preferred_downloader(Some(Generic))),
+            ));
+        }
+
         let media_urls = get_media_urls(request.url.as_str()).await?;
 
         Ok(ExtractedInfo::from_urls(request, media_urls))
@@ -35,10 +57,41 @@ static URL_MATCH: Lazy<Regex> = Lazy::new(|| {
         .expect("Invalid regex")
 });
 
+static STORY_URL_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(
+        r"^https?://(www\.)?instagram.com/stories/(highlights/(?P<highlight_id>\d+)|(?P<username>[^/?]+))",
+    )
+    .expect("Invalid regex")
+});
+
 impl Instagram {
     pub fn is_post_url(url: &Url) -> bool {
         URL_MATCH.is_match(url.as_str())
     }
+
+    pub fn is_story_url(url: &Url) -> bool {
+        STORY_URL_MATCH.is_match(url.as_str())
+    }
+}
+
+/// A reel is either the current stories of a user or one of their highlights
+#[derive(Debug)]
+enum StoryReel {
+    User(String),
+    Highlight(String),
+}
+impl StoryReel {
+    fn from_url(url: &Url) -> Option<Self> {
+        let captures = STORY_URL_MATCH.captures(url.as_str())?;
+
+        if let Some(highlight_id) = captures.name("highlight_id") {
+            return Some(Self::Highlight(highlight_id.as_str().to_string()));
+        }
+
+        captures
+            .name("username")
+            .map(|x| Self::User(x.as_str().to_string()))
+    }
 }
 
 #[derive(Deserialize)]
@@ -145,3 +198,131 @@ async fn get_api_response(post_id: &str) -> Result<InstagramXDTGraphMedia, Strin
         .and_then(|x| serde_json::from_value::<InstagramXDTGraphMedia>(x.clone()).ok())
         .ok_or_else(|| "Failed to parse media from response".to_string())
 }
+
+#[derive(Debug, Deserialize)]
+struct ReelsMediaResponse {
+    #[serde(default)]
+    reels_media: Vec<StoryReelMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoryReelMedia {
+    #[serde(default)]
+    items: Vec<StoryItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoryItem {
+    video_versions: Option<Vec<StoryMediaVersion>>,
+    image_versions2: Option<StoryImageVersions>,
+}
+impl StoryItem {
+    /// The biggest version of the video, or of the image for photo stories
+    fn media_url(&self) -> Option<String> {
+        let biggest = |versions: &[StoryMediaVersion]| {
+            versions
+                .iter()
+                .max_by_key(|x| x.width.unwrap_or_default())
+                .map(|x| x.url.clone())
+        };
+
+        self.video_versions
+            .as_deref()
+            .and_then(biggest)
+            .or_else(|| {
+                self.image_versions2
+                    .as_ref()
+                    .and_then(|x| biggest(&x.candidates))
+            })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StoryImageVersions {
+    candidates: Vec<StoryMediaVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct StoryMediaVersion {
+    url: String,
+    width: Option<u32>,
+}
+
+async fn get_story_media_urls(reel: &StoryReel) -> Result<Vec<String>, String> {
+    let session_id = Config::global()
+        .endpoint
+        .instagram_session_id
+        .as_deref()
+        .ok_or_else(|| {
+            "Instagram stories and highlights can only be downloaded with a session cookie set"
+                .to_string()
+        })?;
+
+    debug!(?reel, "Getting Instagram story reel");
+
+    let reel_id = match reel {
+        StoryReel::User(username) => get_user_id(session_id, username).await?,
+        StoryReel::Highlight(highlight_id) => format!("highlight:{highlight_id}"),
+    };
+
+    let resp = private_api_request(
+        session_id,
+        &format!("{PRIVATE_API_ENDPOINT}/feed/reels_media/"),
+        &[("reel_ids", &reel_id)],
+    )
+    .await?;
+
+    trace!(?resp, "Got Instagram reels media response");
+
+    let resp = serde_json::from_value::<ReelsMediaResponse>(resp)
+        .map_err(|e| format!("Failed to parse Instagram story reel: {e:?}"))?;
+
+    let urls = resp
+        .reels_media
+        .iter()
+        .flat_map(|x| &x.items)
+        .filter_map(StoryItem::media_url)
+        .collect::<Vec<_>>();
+
+    if urls.is_empty() {
+        return Err("No stories found. They might have expired.".to_string());
+    }
+
+    Ok(urls)
+}
+
+async fn get_user_id(session_id: &str, username: &str) -> Result<String, String> {
+    let resp = private_api_request(
+        session_id,
+        &format!("{PRIVATE_API_ENDPOINT}/users/web_profile_info/"),
+        &[("username", username)],
+    )
+    .await?;
+
+    resp.get("data")
+        .and_then(|x| x.get("user"))
+        .and_then(|x| x.get("id"))
+        .and_then(|x| x.as_str())
+        .map(ToString::to_string)
+        .ok_or_else(|| format!("Failed to find Instagram user {username:?}"))
+}
+
+async fn private_api_request(
+    session_id: &str,
+    url: &str,
+    query: &[(&str, &str)],
+) -> Result<serde_json::Value, String> {
+    Client::base()?
+        .get(url)
+        .query(query)
+        .header("X-IG-App-ID", WEB_APP_ID)
+        .header("Cookie", format!("sessionid={session_id}"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to instagram API: {e:?}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from instagram API: {e:?}"))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse response from instagram API: {e:?}"))
+}
@@ -0,0 +1,135 @@
+use std::{path::Path, time::Duration};
+
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client,
+    downloaders::handlers::{generic::Generic, yt_dlp::YtDlp},
+    extractors::ExtractedUrlInfo,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Kick;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Kick {
+    fn description(&self) -> &'static str {
+        "Gets the video source of Kick clips."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::clip_id(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let clip_id =
+            Self::clip_id(&request.url).ok_or_else(|| "Invalid Kick clip url".to_string())?;
+
+        debug!(?clip_id, "Getting Kick clip");
+
+        let clip = get_clip(&clip_id).await?;
+
+        trace!(?clip, "Got Kick clip");
+
+        let url_info = clip
+            .to_url_info()
+            .ok_or_else(|| "Kick clip has no video source".to_string())?;
+
+        Ok(ExtractedInfo::from_url(request, url_info)
+            .with_title(clip.title)
+            .with_duration(clip.duration.map(Duration::from_secs)))
+    }
+}
+
+static CLIP_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/[^/]+/clips/(?P<clip_id>clip_[a-zA-Z0-9]+)/?$").expect("Invalid regex")
+});
+
+impl Kick {
+    #[must_use]
+    pub fn is_kick_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| matches!(x, "kick.com" | "www.kick.com"))
+    }
+
+    /// Clips are linked either as `kick.com/<channel>/clips/<clip id>`
+    /// or as `kick.com/<channel>?clip=<clip id>`
+    fn clip_id(url: &Url) -> Option<String> {
+        if !Self::is_kick_url(url) {
+            return None;
+        }
+
+        if let Some((_, clip_id)) = url.query_pairs().find(|(k, _)| k == "clip") {
+            return Some(clip_id.to_string());
+        }
+
+        CLIP_PATH_MATCH
+            .captures(url.path())
+            .and_then(|x| x.name("clip_id"))
+            .map(|x| x.as_str().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ClipResponse {
+    clip: Clip,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[allow(clippy::struct_field_names)]
+struct Clip {
+    clip_url: Option<String>,
+    video_url: Option<String>,
+    title: Option<String>,
+    /// In seconds
+    duration: Option<u64>,
+}
+impl Clip {
+    fn to_url_info(&self) -> Option<ExtractedUrlInfo> {
+        let urls = [self.video_url.as_deref(), self.clip_url.as_deref()]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+
+        // Prefer a direct MP4 if there is one, otherwise
+        // let yt-dlp deal with the HLS playlist
+        if let Some(url) = urls.iter().find(|x| is_mp4_url(x)) {
+            return Some(ExtractedUrlInfo::new(*url).with_preferred_downloader(Some(Generic)));
+        }
+
+        urls.first()
+            .map(|x| ExtractedUrlInfo::new(*x).with_preferred_downloader(Some(YtDlp)))
+    }
+}
+
+fn is_mp4_url(url: &str) -> bool {
+    Url::parse(url).is_ok_and(|x| {
+        Path::new(x.path())
+            .extension()
+            .is_some_and(|x| x.eq_ignore_ascii_case("mp4"))
+    })
+}
+
+async fn get_clip(clip_id: &str) -> Result<Clip, String> {
+    let api_url = format!("https://kick.com/api/v2/clips/{clip_id}");
+
+    Client::base()?
+        .get(api_url)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Kick: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Kick: {e}"))?
+        .json::<ClipResponse>()
+        .await
+        .map(|x| x.clip)
+        .map_err(|e| format!("Failed to parse Kick clip: {e}"))
+}
@@ -1,16 +1,34 @@
 pub mod activity_pub;
+pub mod archive_org;
+pub mod artstation;
 pub mod bsky;
+pub mod collection;
+pub mod deviantart;
+pub mod discord;
 pub mod fallthough;
+pub mod file_hosts;
+pub mod flickr;
+pub mod gallery;
+pub mod giphy;
 pub mod imgur;
 pub mod instagram;
+pub mod kick;
 pub mod music;
+pub mod niconico;
+pub mod odysee;
+pub mod podcast;
 pub mod reddit;
+pub mod rumble;
+pub mod snapchat;
+pub mod tenor;
 pub mod tiktok;
 pub mod tumblr;
 pub mod twitter;
+pub mod weibo;
 
 use std::sync::Arc;
 
+use app_config::Config;
 use once_cell::sync::Lazy;
 
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
@@ -21,6 +39,13 @@ pub static AVAILABLE_EXTRACTORS: Lazy<Vec<ExtractorEntry>> = Lazy::new(available
 
 #[must_use]
 pub fn available_extractors() -> Vec<ExtractorEntry> {
+    all_extractors()
+        .into_iter()
+        .filter(|x| !Config::global().handlers.is_extractor_disabled(x.name()))
+        .collect()
+}
+
+fn all_extractors() -> Vec<ExtractorEntry> {
     vec![
         Arc::new(imgur::Imgur),
         Arc::new(instagram::Instagram),
@@ -28,8 +53,25 @@ pub fn available_extractors() -> Vec<ExtractorEntry> {
         Arc::new(tiktok::Tiktok),
         Arc::new(tumblr::Tumblr),
         Arc::new(twitter::Twitter),
+        Arc::new(collection::Collection),
         Arc::new(music::Music),
         Arc::new(bsky::Bsky),
+        Arc::new(flickr::Flickr),
+        Arc::new(deviantart::DeviantArt),
+        Arc::new(artstation::ArtStation),
+        Arc::new(giphy::Giphy),
+        Arc::new(tenor::Tenor),
+        Arc::new(kick::Kick),
+        Arc::new(rumble::Rumble),
+        Arc::new(odysee::Odysee),
+        Arc::new(niconico::Niconico),
+        Arc::new(weibo::Weibo),
+        Arc::new(snapchat::Snapchat),
+        Arc::new(discord::Discord),
+        Arc::new(archive_org::ArchiveOrg),
+        Arc::new(file_hosts::FileHosts),
+        Arc::new(podcast::Podcast),
+        Arc::new(gallery::Gallery),
         Arc::new(activity_pub::ActivityPub),
         Arc::new(fallthough::Fallthrough),
     ]
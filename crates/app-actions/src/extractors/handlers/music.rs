@@ -1,7 +1,16 @@
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
 
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
-use crate::downloaders::handlers::music::Music as MusicDownloader;
+use crate::{
+    common::request::Client,
+    downloaders::handlers::music::Music as MusicDownloader,
+    fixers::handlers::tag_audio::{AudioTags, AUDIO_TAGS_OPTION},
+};
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Music;
@@ -18,8 +27,126 @@ impl Extractor for Music {
         MusicDownloader::supports(&request.url)
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
-        Ok(ExtractedInfo::from_url(request, request.url.as_str())
-            .with_preferred_downloader(Some(MusicDownloader)))
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let info = ExtractedInfo::from_url(request, request.url.as_str())
+            .with_preferred_downloader(Some(MusicDownloader));
+
+        let tags = match get_audio_tags(&request.url).await {
+            Ok(Some(tags)) if !tags.is_empty() => tags,
+            Ok(_) => return Ok(info),
+            Err(e) => {
+                debug!(?e, "Failed to get song metadata");
+                return Ok(info);
+            }
+        };
+
+        trace!(?tags, "Got song metadata");
+
+        let tags = serde_json::to_value(tags)
+            .map_err(|e| format!("Failed to serialize song metadata: {e}"))?;
+
+        Ok(info.with_downloader_option(AUDIO_TAGS_OPTION, tags))
+    }
+}
+
+static DEEZER_TRACK_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:/[a-z]{2})?/track/(?P<track_id>[0-9]+)/?$").expect("Invalid regex")
+});
+
+/// Best-effort lookup of the song metadata so the downloaded file can be tagged
+async fn get_audio_tags(url: &Url) -> Result<Option<AudioTags>, String> {
+    match url.host_str() {
+        Some("deezer.com" | "www.deezer.com") => {
+            let Some(track_id) = DEEZER_TRACK_PATH_MATCH
+                .captures(url.path())
+                .and_then(|x| x.name("track_id"))
+            else {
+                return Ok(None);
+            };
+
+            get_deezer_tags(track_id.as_str()).await.map(Some)
+        }
+        Some("open.spotify.com") if url.path().contains("/track/") => {
+            get_spotify_tags(url).await.map(Some)
+        }
+        _ => Ok(None),
     }
 }
+
+#[derive(Debug, Deserialize)]
+struct DeezerTrack {
+    title: Option<String>,
+    track_position: Option<u32>,
+    release_date: Option<String>,
+    artist: Option<DeezerArtist>,
+    album: Option<DeezerAlbum>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeezerArtist {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeezerAlbum {
+    title: Option<String>,
+    cover_xl: Option<String>,
+}
+
+async fn get_deezer_tags(track_id: &str) -> Result<AudioTags, String> {
+    let track = Client::base()?
+        .get(format!("https://api.deezer.com/track/{track_id}"))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Deezer: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Deezer: {e}"))?
+        .json::<DeezerTrack>()
+        .await
+        .map_err(|e| format!("Failed to parse Deezer track: {e}"))?;
+
+    let artist = track.artist.map(|x| x.name);
+    let (album, cover_url) = track
+        .album
+        .map(|x| (x.title, x.cover_xl))
+        .unwrap_or_default();
+
+    Ok(AudioTags {
+        title: track.title,
+        album_artist: artist.clone(),
+        artist,
+        album,
+        track_number: track.track_position,
+        year: track
+            .release_date
+            .and_then(|x| x.split('-').next().and_then(|x| x.parse().ok())),
+        cover_url,
+        cover_path: None,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotifyOembed {
+    title: Option<String>,
+    thumbnail_url: Option<String>,
+}
+
+async fn get_spotify_tags(url: &Url) -> Result<AudioTags, String> {
+    let oembed = Client::base()?
+        .get("https://open.spotify.com/oembed")
+        .query(&[("url", url.as_str())])
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Spotify: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Spotify: {e}"))?
+        .json::<SpotifyOembed>()
+        .await
+        .map_err(|e| format!("Failed to parse Spotify oembed: {e}"))?;
+
+    Ok(AudioTags {
+        title: oembed.title,
+        cover_url: oembed.thumbnail_url,
+        ..Default::default()
+    })
+}
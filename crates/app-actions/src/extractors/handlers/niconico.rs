@@ -0,0 +1,77 @@
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    downloaders::handlers::yt_dlp::{YtDlp, YtDlpOptions},
+    extractors::ExtractedUrlInfo,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Niconico;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Niconico {
+    fn description(&self) -> &'static str {
+        "Downloads Niconico videos through yt-dlp while keeping the stream session alive."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::video_id(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let video_id =
+            Self::video_id(&request.url).ok_or_else(|| "Invalid Niconico url".to_string())?;
+
+        debug!(?video_id, "Got Niconico video id");
+
+        // The DMC/DMS stream sessions expire unless heartbeats are sent while downloading,
+        // which only yt-dlp's own downloader does. Handing the stream to ffmpeg ends in 403s.
+        let watch_url = format!("https://www.nicovideo.jp/watch/{video_id}");
+
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(watch_url)
+                .with_preferred_downloader(Some(YtDlp))
+                .with_downloader_options(YtDlpOptions::new().with_native_downloader(true)),
+        ))
+    }
+}
+
+static VIDEO_ID_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^/(?:watch/)?(?P<id>(?:sm|nm|so)\d+)/?$").expect("Invalid regex"));
+
+impl Niconico {
+    #[must_use]
+    pub fn is_niconico_url(url: &Url) -> bool {
+        url.host_str().is_some_and(|x| {
+            matches!(
+                x,
+                "nicovideo.jp"
+                    | "www.nicovideo.jp"
+                    | "sp.nicovideo.jp"
+                    | "embed.nicovideo.jp"
+                    | "nico.ms"
+            )
+        })
+    }
+
+    /// Gets the video ID from `nicovideo.jp/watch/sm123`, `embed.nicovideo.jp/watch/sm123`
+    /// and `nico.ms/sm123` links
+    fn video_id(url: &Url) -> Option<String> {
+        if !Self::is_niconico_url(url) {
+            return None;
+        }
+
+        VIDEO_ID_MATCH
+            .captures(url.path())
+            .and_then(|x| x.name("id"))
+            .map(|x| x.as_str().to_string())
+    }
+}
@@ -0,0 +1,224 @@
+use std::time::Duration;
+
+use app_errors::{AppError, UnsupportedSource};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::request::Client, downloaders::handlers::generic::Generic, extractors::ExtractedUrlInfo,
+};
+
+static LBRY_API_ENDPOINT: &str = "https://api.na-backend.odysee.com/api/v1/proxy";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Odysee;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Odysee {
+    fn description(&self) -> &'static str {
+        "Gets the source file of Odysee and LBRY videos."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::lbry_uri(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let lbry_uri =
+            Self::lbry_uri(&request.url).ok_or_else(|| "Invalid Odysee/LBRY url".to_string())?;
+
+        debug!(?lbry_uri, "Resolving LBRY claim");
+
+        let claim = resolve_claim(&lbry_uri).await?;
+
+        trace!(?claim, "Got LBRY claim");
+
+        if claim.value_type.as_deref() != Some("stream") {
+            return Err(UnsupportedSource::new(format!(
+                "Unsupported LBRY claim type: {:?}",
+                claim.value_type.unwrap_or_default()
+            ))
+            .into());
+        }
+
+        let stream = call_api::<GetResponse>(
+            "get",
+            serde_json::json!({ "uri": claim.canonical_url.as_deref().unwrap_or(&lbry_uri) }),
+        )
+        .await?;
+
+        trace!(?stream, "Got LBRY stream");
+
+        let size = claim
+            .value
+            .source
+            .and_then(|x| x.size)
+            .and_then(|x| x.parse().ok());
+        let duration = claim
+            .value
+            .video
+            .and_then(|x| x.duration)
+            .map(Duration::from_secs);
+
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(stream.streaming_url).with_preferred_downloader(Some(Generic)),
+        )
+        .with_title(claim.value.title)
+        .with_duration(duration)
+        .with_estimated_size(size))
+    }
+}
+
+impl Odysee {
+    #[must_use]
+    pub fn is_odysee_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| matches!(x, "odysee.com" | "www.odysee.com"))
+    }
+
+    /// Converts `odysee.com/@channel:c/video:a` and
+    /// `odysee.com/$/embed/video:a` into `lbry://@channel#c/video#a`.
+    /// `lbry://` URLs are used as-is.
+    fn lbry_uri(url: &Url) -> Option<String> {
+        if url.scheme() == "lbry" {
+            return Some(url.as_str().to_string());
+        }
+
+        if !Self::is_odysee_url(url) {
+            return None;
+        }
+
+        let segments = url
+            .path_segments()?
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        let segments = match segments.as_slice() {
+            ["$", "embed", rest @ ..] => rest,
+            ["$", ..] => return None,
+            rest => rest,
+        };
+
+        // Either a claim on its own or a claim in a channel
+        match segments {
+            [claim] if !claim.starts_with('@') => {}
+            [channel, _] if channel.starts_with('@') => {}
+            _ => return None,
+        }
+
+        let path = segments
+            .iter()
+            .map(|x| {
+                percent_encoding::percent_decode_str(x)
+                    .decode_utf8_lossy()
+                    .replace(':', "#")
+            })
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Some(format!("lbry://{path}"))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiResponse<T> {
+    result: Option<T>,
+    error: Option<ApiError>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ApiError {
+    message: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Claim {
+    canonical_url: Option<String>,
+    value_type: Option<String>,
+    #[serde(default)]
+    value: ClaimValue,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct ClaimValue {
+    title: Option<String>,
+    source: Option<ClaimSource>,
+    video: Option<ClaimVideo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ClaimSource {
+    /// In bytes, sent as a string
+    size: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ClaimVideo {
+    /// In seconds
+    duration: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GetResponse {
+    streaming_url: String,
+}
+
+async fn call_api<T>(method: &str, params: serde_json::Value) -> Result<T, String>
+where
+    T: DeserializeOwned,
+{
+    let api_url = {
+        let mut api_url = Url::parse(LBRY_API_ENDPOINT).expect("Invalid URL");
+
+        api_url.query_pairs_mut().append_pair("m", method);
+
+        api_url
+    };
+
+    trace!(?method, ?params, "Calling LBRY api");
+
+    let resp = Client::base()?
+        .post(api_url)
+        .json(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to LBRY API: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from LBRY API: {e}"))?
+        .json::<ApiResponse<T>>()
+        .await
+        .map_err(|e| format!("Failed to parse LBRY API response: {e}"))?;
+
+    if let Some(error) = resp.error {
+        return Err(format!("LBRY API returned an error: {}", error.message));
+    }
+
+    resp.result
+        .ok_or_else(|| "LBRY API returned an empty response".to_string())
+}
+
+async fn resolve_claim(lbry_uri: &str) -> Result<Claim, String> {
+    let mut resp = call_api::<serde_json::Map<String, serde_json::Value>>(
+        "resolve",
+        serde_json::json!({ "urls": [lbry_uri] }),
+    )
+    .await?;
+
+    let claim = resp
+        .remove(lbry_uri)
+        .ok_or_else(|| format!("LBRY claim {lbry_uri:?} not found"))?;
+
+    if let Some(error) = claim.get("error") {
+        return Err(format!("Failed to resolve LBRY claim: {error}"));
+    }
+
+    serde_json::from_value(claim).map_err(|e| format!("Failed to parse LBRY claim: {e}"))
+}
@@ -0,0 +1,299 @@
+use std::path::Path;
+
+use app_config::Config;
+use app_errors::AppError;
+use chrono::{DateTime, Datelike, FixedOffset};
+use http::{header, HeaderMap};
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    downloaders::handlers::generic::{Generic, GenericDownloaderOptions},
+    extractors::ExtractedUrlInfo,
+    fixers::handlers::tag_audio::{AudioTags, AUDIO_TAGS_OPTION},
+};
+
+/// Content types feeds are served with
+static FEED_CONTENT_TYPES: &[&str] = &[
+    "application/rss+xml",
+    "application/atom+xml",
+    "application/xml",
+    "text/xml",
+];
+
+/// Extensions of feed URLs, eg. `https://example.com/podcast.rss`
+static FEED_EXTENSIONS: &[&str] = &["rss", "xml", "atom"];
+
+/// Last path segments of feed URLs, eg. `https://example.com/show/feed`
+static FEED_PATH_SEGMENTS: &[&str] = &["feed", "rss", "atom"];
+
+/// Subdomains that only serve feeds, eg. `feeds.example.com`
+static FEED_SUBDOMAINS: &[&str] = &["feed", "feeds", "rss"];
+
+const ITUNES_NAMESPACE: &str = "http://www.itunes.com/dtds/podcast-1.0.dtd";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Podcast;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Podcast {
+    fn description(&self) -> &'static str {
+        "Downloads the most recent episodes of RSS and Atom podcast feeds and tags them with the \
+         episode metadata."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        looks_like_feed(&request.url)
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let resp = request
+            .as_request_builder()?
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request for podcast feed: {e:?}"))?
+            .error_for_status()
+            .map_err(|e| format!("Failed to get podcast feed: {e:?}"))?;
+
+        // Only the URL looked like a feed, so it's downloaded as-is
+        if !is_feed_content_type(resp.headers()) {
+            debug!(content_type = ?resp.headers().get(header::CONTENT_TYPE), "Not served as a feed");
+            return Ok(ExtractedInfo::from_url(request, request.url.as_str()));
+        }
+
+        let body = resp
+            .text()
+            .await
+            .map_err(|e| format!("Failed to get text from podcast feed: {e:?}"))?;
+
+        let feed = tokio::task::spawn_blocking(move || Feed::parse(&body))
+            .await
+            .map_err(|e| format!("Failed to parse podcast feed: {e:?}"))?;
+
+        trace!(?feed, "Got podcast feed");
+
+        // Just some XML file or a feed without any media, so it's downloaded as-is
+        let Some(mut feed) = feed.filter(|x| !x.episodes.is_empty()) else {
+            debug!("No episodes found in feed");
+            return Ok(ExtractedInfo::from_url(request, request.url.as_str()));
+        };
+
+        // Episodes without a date end up last
+        feed.episodes
+            .sort_by_key(|x| std::cmp::Reverse(x.published));
+        feed.episodes
+            .truncate(Config::global().download.podcast_episode_count());
+
+        debug!(count = feed.episodes.len(), "Downloading podcast episodes");
+
+        let urls = feed
+            .episodes
+            .iter()
+            .map(|x| episode_url_info(&feed, x))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ExtractedInfo::from_urls(request, urls))
+    }
+}
+
+fn looks_like_feed(url: &Url) -> bool {
+    if !matches!(url.scheme(), "http" | "https") {
+        return false;
+    }
+
+    let on_feed_subdomain = url
+        .host_str()
+        .and_then(|x| x.split('.').next())
+        .is_some_and(|x| FEED_SUBDOMAINS.contains(&x));
+    if on_feed_subdomain {
+        return true;
+    }
+
+    let Some(last_segment) = url
+        .path_segments()
+        .and_then(|mut x| x.rfind(|x| !x.is_empty()))
+        .map(str::to_lowercase)
+    else {
+        return false;
+    };
+
+    FEED_PATH_SEGMENTS.contains(&last_segment.as_str())
+        || Path::new(&last_segment)
+            .extension()
+            .and_then(|x| x.to_str())
+            .is_some_and(|x| FEED_EXTENSIONS.contains(&x))
+}
+
+fn is_feed_content_type(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|x| x.to_str().ok())
+        .and_then(|x| x.split(';').next())
+        .is_some_and(|x| FEED_CONTENT_TYPES.contains(&x.trim().to_lowercase().as_str()))
+}
+
+fn episode_url_info(feed: &Feed, episode: &Episode) -> Result<ExtractedUrlInfo, String> {
+    let tags = AudioTags {
+        title: episode.title.clone(),
+        artist: feed.author.clone(),
+        album: feed.title.clone(),
+        album_artist: feed.author.clone(),
+        track_number: episode.number,
+        year: episode.published.and_then(|x| u32::try_from(x.year()).ok()),
+        cover_url: episode.image_url.clone().or_else(|| feed.image_url.clone()),
+        cover_path: None,
+    };
+
+    let tags =
+        serde_json::to_value(tags).map_err(|e| format!("Failed to serialize episode tags: {e}"))?;
+
+    // The generic downloader only adds an extension to names that don't look like they have one
+    let file_name = episode.title.as_ref().map(|title| {
+        let extension = Url::parse(&episode.url).ok().and_then(|x| {
+            Path::new(x.path())
+                .extension()
+                .and_then(|x| x.to_str())
+                .map(ToString::to_string)
+        });
+
+        extension.map_or_else(|| title.clone(), |extension| format!("{title}.{extension}"))
+    });
+
+    Ok(ExtractedUrlInfo::new(episode.url.as_str())
+        .with_preferred_downloader(Some(Generic))
+        .with_downloader_options(GenericDownloaderOptions::new().with_file_name(file_name))
+        .with_downloader_option(AUDIO_TAGS_OPTION, tags))
+}
+
+#[derive(Debug, Default)]
+struct Feed {
+    title: Option<String>,
+    author: Option<String>,
+    image_url: Option<String>,
+    episodes: Vec<Episode>,
+}
+impl Feed {
+    /// `None` if it's not an RSS or Atom feed
+    fn parse(xml: &str) -> Option<Self> {
+        let document = Document::parse(xml)
+            .map_err(|e| debug!(?e, "Failed to parse feed XML"))
+            .ok()?;
+        let root = document.root_element();
+
+        // RSS feeds describe the podcast in the channel, Atom ones in the feed itself
+        let (channel, item_name) = match root.tag_name().name() {
+            "rss" => (child(root, "channel")?, "item"),
+            "feed" => (root, "entry"),
+            _ => return None,
+        };
+
+        let episodes = channel
+            .children()
+            .filter(|x| is_element(*x, item_name))
+            .filter_map(Episode::parse)
+            .collect();
+
+        let author = itunes_child(channel, "author").and_then(text).or_else(|| {
+            // Atom authors have the name in a child element
+            child(channel, "author").and_then(|x| child(x, "name").and_then(text))
+        });
+
+        let image_url = itunes_child(channel, "image")
+            .and_then(|x| x.attribute("href"))
+            .map(ToString::to_string)
+            .or_else(|| {
+                child(channel, "image")
+                    .and_then(|x| child(x, "url"))
+                    .and_then(text)
+            })
+            .or_else(|| child(channel, "logo").and_then(text));
+
+        Some(Self {
+            title: child(channel, "title").and_then(text),
+            author,
+            image_url,
+            episodes,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Episode {
+    url: String,
+    title: Option<String>,
+    number: Option<u32>,
+    image_url: Option<String>,
+    published: Option<DateTime<FixedOffset>>,
+}
+impl Episode {
+    /// `None` if the item has no media attached
+    fn parse(item: Node) -> Option<Self> {
+        let url = child(item, "enclosure")
+            .and_then(|x| x.attribute("url"))
+            .or_else(|| {
+                // Atom feeds link to the media instead
+                item.children()
+                    .filter(|x| is_element(*x, "link"))
+                    .find(|x| x.attribute("rel") == Some("enclosure"))
+                    .and_then(|x| x.attribute("href"))
+            })?
+            .trim()
+            .to_string();
+
+        let published = child(item, "pubDate")
+            .and_then(text)
+            .and_then(|x| DateTime::parse_from_rfc2822(&x).ok())
+            .or_else(|| {
+                child(item, "published")
+                    .or_else(|| child(item, "updated"))
+                    .and_then(text)
+                    .and_then(|x| DateTime::parse_from_rfc3339(&x).ok())
+            });
+
+        Some(Self {
+            url,
+            title: child(item, "title").and_then(text),
+            number: itunes_child(item, "episode")
+                .and_then(text)
+                .and_then(|x| x.parse().ok()),
+            image_url: itunes_child(item, "image")
+                .and_then(|x| x.attribute("href"))
+                .map(ToString::to_string),
+            published,
+        })
+    }
+}
+
+/// Elements of the feed itself, not the iTunes extensions with the same name
+fn is_element(node: Node, name: &str) -> bool {
+    node.is_element()
+        && node.tag_name().name() == name
+        && node.tag_name().namespace() != Some(ITUNES_NAMESPACE)
+}
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|x| is_element(*x, name))
+}
+
+fn itunes_child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|x| {
+        x.is_element()
+            && x.tag_name().name() == name
+            && x.tag_name().namespace() == Some(ITUNES_NAMESPACE)
+    })
+}
+
+/// All the text in the element, with CDATA sections and entities already taken care of
+fn text(node: Node) -> Option<String> {
+    let text = node
+        .descendants()
+        .filter_map(|x| x.text().filter(|_| x.is_text()))
+        .collect::<String>();
+    let text = text.trim();
+
+    (!text.is_empty()).then(|| text.to_string())
+}
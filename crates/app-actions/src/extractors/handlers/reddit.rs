@@ -1,7 +1,24 @@
+use std::collections::HashMap;
+
+use app_errors::{AppError, SourceGone};
+use http::StatusCode;
 use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
 
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
-use crate::downloaders::handlers::{generic::Generic, yt_dlp::YtDlp};
+use crate::{
+    common::request::{send_with_quota, Client},
+    downloaders::handlers::{
+        generic::{Generic, GenericDownloaderOptions},
+        yt_dlp::YtDlp,
+    },
+    extractors::ExtractedUrlInfo,
+};
+
+static IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "bmp", "tiff", "tif", "ico",
+];
 
 #[must_use]
 pub fn is_reddit_image_url(url: &str) -> bool {
@@ -15,32 +32,56 @@ pub struct Reddit;
 #[typetag::serde]
 impl Extractor for Reddit {
     fn description(&self) -> &'static str {
-        "Gets reddit media. Only works on media links (eg. https://i.redd.it/...)"
+        "Gets reddit media. Works on media links (eg. https://i.redd.it/...) and posts, \
+         including galleries and crossposts."
     }
 
     async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
-        Self::is_media_url(request.url.as_str())
+        Self::is_media_url(request.url.as_str()) || Self::is_post_url(&request.url)
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
-        let url = {
-            let mut x = request.url.clone();
-            let _ = x.set_host(Some("i.redd.it"));
-            x.set_query(None);
-            x
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        if Self::is_media_url(request.url.as_str()) {
+            return Ok(media_url_info(request));
+        }
+
+        let post_id = match Self::post_id(&request.url) {
+            Some(x) => x,
+            None => {
+                // Share links (`reddit.com/r/<sub>/s/<code>`) redirect to the post
+                let resolved_url = resolve_share_url(&request.url).await?;
+
+                Self::post_id(&resolved_url)
+                    .ok_or_else(|| format!("Failed to get reddit post ID from {resolved_url}"))?
+            }
         };
-        let file_ext = url.path().split('.').last().unwrap_or_default();
-        let info = {
-            let x = ExtractedInfo::from_url(request, url.as_str());
-            match file_ext {
-                "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "tiff" | "tif" | "ico" => {
-                    x.with_preferred_downloader(Some(Generic))
-                }
-                _ => x.with_preferred_downloader(Some(YtDlp)),
+
+        debug!(?post_id, "Getting reddit post");
+
+        let mut post = get_post(&post_id).await?;
+
+        trace!(?post, "Got reddit post");
+
+        // Crossposts only link to the original post, which has the media
+        let original = post
+            .crosspost_parent_list
+            .take()
+            .and_then(|x| x.into_iter().next());
+
+        let post = match original {
+            Some(original) => {
+                debug!(original_id = ?original.id, "Following crosspost to the original post");
+                original
             }
+            None => post,
         };
 
-        Ok(info)
+        let over_18 = post.over_18;
+        let title = post.title.clone();
+
+        Ok(post_info(request, post)
+            .with_age_restricted(over_18)
+            .with_title(title))
     }
 }
 
@@ -49,4 +90,273 @@ impl Reddit {
     pub fn is_media_url(url: &str) -> bool {
         url.starts_with("https://i.redd.it/") || url.starts_with("https://preview.redd.it/")
     }
+
+    #[must_use]
+    pub fn is_post_url(url: &Url) -> bool {
+        match url.host_str() {
+            Some("redd.it") => true,
+            Some(host) if is_reddit_host(host) => {
+                Self::post_id(url).is_some() || share_code(url).is_some()
+            }
+            _ => false,
+        }
+    }
+
+    /// Gets the ID from `redd.it/<id>`, `reddit.com/gallery/<id>`
+    /// and `reddit.com/[r/<sub>/|user/<user>/]comments/<id>/...` links
+    fn post_id(url: &Url) -> Option<String> {
+        let host = url.host_str()?;
+        let segments = url
+            .path_segments()?
+            .filter(|x| !x.is_empty())
+            .collect::<Vec<_>>();
+
+        let id = match segments.as_slice() {
+            [id] if host == "redd.it" => *id,
+            _ if !is_reddit_host(host) => return None,
+            ["gallery" | "comments", id, ..] | ["r" | "u" | "user", _, "comments", id, ..] => *id,
+            _ => return None,
+        };
+
+        Some(id.to_string())
+    }
+}
+
+fn is_reddit_host(host: &str) -> bool {
+    host == "reddit.com" || host.ends_with(".reddit.com")
+}
+
+fn share_code(url: &Url) -> Option<&str> {
+    let mut segments = url.path_segments()?.filter(|x| !x.is_empty());
+
+    match (segments.next()?, segments.next()?, segments.next()?) {
+        ("r" | "u" | "user", _, "s") => segments.next(),
+        _ => None,
+    }
+}
+
+fn media_url_info(request: &ExtractInfoRequest) -> ExtractedInfo {
+    let url = {
+        let mut x = request.url.clone();
+        let _ = x.set_host(Some("i.redd.it"));
+        x.set_query(None);
+        x
+    };
+    let file_ext = url.path().rsplit('.').next().unwrap_or_default();
+    let x = ExtractedInfo::from_url(request, url.as_str());
+
+    if IMAGE_EXTENSIONS.contains(&file_ext) {
+        x.with_preferred_downloader(Some(Generic))
+    } else {
+        x.with_preferred_downloader(Some(YtDlp))
+    }
+}
+
+async fn resolve_share_url(url: &Url) -> Result<Url, String> {
+    let resp = send_with_quota(Client::base()?.get(url.as_str()))
+        .await
+        .map_err(|e| format!("Failed to send request to reddit: {e:?}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from reddit: {e:?}"))?;
+
+    Ok(resp.url().clone())
+}
+
+fn post_info(request: &ExtractInfoRequest, post: RedditPost) -> ExtractedInfo {
+    if let Some(gallery) = &post.gallery_data {
+        let media_metadata = post.media_metadata.unwrap_or_default();
+
+        let urls = gallery
+            .items
+            .iter()
+            .filter_map(|x| media_metadata.get(&x.media_id).map(|m| (&x.media_id, m)))
+            .filter_map(|(media_id, m)| Some((m.download_url(media_id)?, m.mirror_urls())))
+            .enumerate()
+            .map(|(i, ((url, ext), mirrors))| {
+                let file_name = ext.map(|ext| format!("{}.{:03}.{ext}", post.id, i + 1));
+
+                ExtractedUrlInfo::new(url)
+                    .with_mirrors(mirrors)
+                    .with_preferred_downloader(Some(Generic))
+                    .with_downloader_options(
+                        GenericDownloaderOptions::new().with_file_name(file_name),
+                    )
+            })
+            .collect::<Vec<_>>();
+
+        debug!(count = urls.len(), "Got reddit gallery items");
+
+        if !urls.is_empty() {
+            return ExtractedInfo::from_urls(request, urls);
+        }
+    }
+
+    let linked_image = post
+        .url_overridden_by_dest
+        .as_deref()
+        .filter(|x| is_reddit_image_url(x))
+        .filter(|x| {
+            x.rsplit('.')
+                .next()
+                .is_some_and(|ext| IMAGE_EXTENSIONS.contains(&ext))
+        });
+
+    if let Some(image_url) = linked_image {
+        return ExtractedInfo::from_url(request, image_url)
+            .with_preferred_downloader(Some(Generic));
+    }
+
+    // Videos need their audio merged in, and yt-dlp knows how to handle links to other sites
+    let post_url = format!("https://www.reddit.com{}", post.permalink);
+
+    ExtractedInfo::from_url(request, &post_url).with_preferred_downloader(Some(YtDlp))
+}
+
+#[derive(Debug, Deserialize)]
+struct Listing {
+    data: ListingData,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingData {
+    children: Vec<ListingChild>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingChild {
+    data: RedditPost,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RedditPost {
+    id: String,
+    permalink: String,
+    title: Option<String>,
+    #[serde(default)]
+    over_18: bool,
+    url_overridden_by_dest: Option<String>,
+    gallery_data: Option<GalleryData>,
+    media_metadata: Option<HashMap<String, MediaMetadata>>,
+    crosspost_parent_list: Option<Vec<Self>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GalleryData {
+    items: Vec<GalleryItem>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GalleryItem {
+    media_id: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MediaMetadata {
+    status: Option<String>,
+    #[serde(rename = "e")]
+    kind: Option<String>,
+    /// Mime type, eg. `image/jpg`
+    #[serde(rename = "m")]
+    mime: Option<String>,
+    /// The source (biggest) version of the media
+    #[serde(rename = "s")]
+    source: Option<MediaSource>,
+}
+impl MediaMetadata {
+    /// The URL and the file extension (if known) of the media
+    fn download_url(&self, media_id: &str) -> Option<(String, Option<String>)> {
+        if self.status.as_deref().is_some_and(|x| x != "valid") {
+            return None;
+        }
+
+        let ext = self
+            .mime
+            .as_deref()
+            .and_then(|x| x.split('/').nth(1))
+            .map(ToString::to_string);
+
+        match self.kind.as_deref() {
+            // The original file instead of the resized preview
+            Some("Image") => {
+                let ext = ext?;
+
+                Some((format!("https://i.redd.it/{media_id}.{ext}"), Some(ext)))
+            }
+            Some("AnimatedImage") => {
+                let source = self.source.as_ref()?;
+
+                match (&source.mp4, &source.gif) {
+                    (Some(mp4), _) => Some((mp4.clone(), Some("mp4".to_string()))),
+                    (None, Some(gif)) => Some((gif.clone(), Some("gif".to_string()))),
+                    _ => None,
+                }
+            }
+            _ => self
+                .source
+                .as_ref()
+                .and_then(|x| x.url.clone())
+                .map(|x| (x, ext)),
+        }
+    }
+
+    /// Other places to get the same file from if the download URL fails,
+    /// eg. the full-size preview when `i.redd.it` refuses the request
+    fn mirror_urls(&self) -> Vec<String> {
+        match self.kind.as_deref() {
+            Some("Image") => self
+                .source
+                .as_ref()
+                .and_then(|x| x.url.clone())
+                .into_iter()
+                .collect(),
+            _ => vec![],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MediaSource {
+    #[serde(rename = "u")]
+    url: Option<String>,
+    gif: Option<String>,
+    mp4: Option<String>,
+}
+
+async fn get_post(post_id: &str) -> Result<RedditPost, AppError> {
+    let api_url = {
+        let mut api_url = Url::parse(&format!("https://www.reddit.com/comments/{post_id}.json"))
+            .map_err(|e| format!("Invalid reddit post ID {post_id:?}: {e:?}"))?;
+
+        // Otherwise the media URLs are HTML-escaped
+        api_url.query_pairs_mut().append_pair("raw_json", "1");
+
+        api_url
+    };
+
+    let res = send_with_quota(Client::base()?.get(api_url))
+        .await
+        .map_err(|e| format!("Failed to send request to reddit: {e:?}"))?;
+
+    // Reddit also refuses requests it thinks are from bots with a 403, so that one isn't counted
+    if matches!(res.status(), StatusCode::NOT_FOUND | StatusCode::GONE) {
+        return Err(SourceGone::new(format!(
+            "Reddit post {post_id} ({status})",
+            status = res.status()
+        ))
+        .into());
+    }
+
+    let listings = res
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from reddit: {e:?}"))?
+        .json::<Vec<Listing>>()
+        .await
+        .map_err(|e| format!("Failed to parse reddit response: {e:?}"))?;
+
+    listings
+        .into_iter()
+        .next()
+        .and_then(|x| x.data.children.into_iter().next())
+        .map(|x| x.data)
+        .ok_or_else(|| SourceGone::new(format!("Reddit post {post_id} not found")).into())
 }
@@ -0,0 +1,190 @@
+use std::{collections::HashMap, path::Path};
+
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::{quality::pick_by_height, request::Client},
+    downloaders::handlers::generic::Generic,
+    extractors::ExtractedUrlInfo,
+};
+
+static OEMBED_ENDPOINT: &str = "https://rumble.com/api/Media/oembed.json";
+
+static EMBED_ENDPOINT: &str = "https://rumble.com/embedJS/u3/";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Rumble;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Rumble {
+    fn description(&self) -> &'static str {
+        "Gets the direct MP4 of Rumble videos in the configured quality."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::is_rumble_url(&request.url)
+            && (EMBED_PATH_MATCH.is_match(request.url.path())
+                || Path::new(request.url.path())
+                    .extension()
+                    .is_some_and(|x| x.eq_ignore_ascii_case("html")))
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let embed_id = match embed_id_from_str(request.url.as_str()) {
+            Some(x) => x,
+            None => get_embed_id(&request.url).await?,
+        };
+
+        debug!(?embed_id, "Getting Rumble video");
+
+        let video = get_video(&embed_id).await?;
+
+        trace!(?video, "Got Rumble video");
+
+        let video_url = video
+            .mp4_url()
+            .ok_or_else(|| "No MP4 renditions found for Rumble video".to_string())?;
+
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(video_url).with_preferred_downloader(Some(Generic)),
+        ))
+    }
+}
+
+static EMBED_PATH_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^/embed/(?P<embed_id>v[a-z0-9]+)").expect("Invalid regex"));
+
+static EMBED_URL_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"rumble\.com/embed/(?P<embed_id>v[a-z0-9]+)").expect("Invalid regex"));
+
+impl Rumble {
+    #[must_use]
+    pub fn is_rumble_url(url: &Url) -> bool {
+        url.host_str()
+            .is_some_and(|x| matches!(x, "rumble.com" | "www.rumble.com"))
+    }
+}
+
+fn embed_id_from_str(s: &str) -> Option<String> {
+    EMBED_URL_MATCH
+        .captures(s)
+        .and_then(|x| x.name("embed_id"))
+        .map(|x| x.as_str().to_string())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct OembedResponse {
+    html: String,
+}
+
+/// Video pages use a different ID than the embed,
+/// so the embed ID has to be looked up through oembed
+async fn get_embed_id(url: &Url) -> Result<String, String> {
+    let api_url = {
+        let mut api_url = Url::parse(OEMBED_ENDPOINT).expect("Invalid URL");
+
+        api_url.query_pairs_mut().append_pair("url", url.as_str());
+
+        api_url
+    };
+
+    let oembed = Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Rumble: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Rumble: {e}"))?
+        .json::<OembedResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse Rumble oembed response: {e}"))?;
+
+    embed_id_from_str(&oembed.html).ok_or_else(|| "Rumble video embed not found".to_string())
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedVideo {
+    ua: Option<EmbedRenditions>,
+    u: Option<EmbedLegacyRenditions>,
+}
+impl EmbedVideo {
+    fn mp4_url(&self) -> Option<String> {
+        let renditions = self
+            .ua
+            .as_ref()
+            .map(|x| &x.mp4)
+            .into_iter()
+            .flatten()
+            .filter_map(|(height, rendition)| {
+                let height = rendition
+                    .meta
+                    .as_ref()
+                    .and_then(|x| x.h)
+                    .or_else(|| height.parse().ok())?;
+
+                Some((height, rendition.url.clone()))
+            });
+
+        pick_by_height(renditions).or_else(|| {
+            self.u
+                .as_ref()
+                .and_then(|x| x.mp4.as_ref())
+                .map(|x| x.url.clone())
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedRenditions {
+    #[serde(default)]
+    mp4: HashMap<String, EmbedRendition>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedRendition {
+    url: String,
+    meta: Option<EmbedRenditionMeta>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedRenditionMeta {
+    h: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EmbedLegacyRenditions {
+    mp4: Option<EmbedRendition>,
+}
+
+async fn get_video(embed_id: &str) -> Result<EmbedVideo, String> {
+    let api_url = {
+        let mut api_url = Url::parse(EMBED_ENDPOINT).expect("Invalid URL");
+
+        api_url.query_pairs_mut().extend_pairs([
+            ("request", "video"),
+            ("ver", "2"),
+            ("v", embed_id),
+        ]);
+
+        api_url
+    };
+
+    Client::base()?
+        .get(api_url)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Rumble: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Rumble: {e}"))?
+        .json::<EmbedVideo>()
+        .await
+        .map_err(|e| format!("Failed to parse Rumble video: {e}"))
+}
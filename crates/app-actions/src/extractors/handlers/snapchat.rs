@@ -0,0 +1,151 @@
+use app_errors::AppError;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::{html::meta_tags, request::Client},
+    downloaders::handlers::generic::Generic,
+    extractors::ExtractedUrlInfo,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Snapchat;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Snapchat {
+    fn description(&self) -> &'static str {
+        "Gets the videos and images of Snapchat Spotlight and Story share links."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::is_share_url(&request.url)
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        // Short links (`t.snapchat.com/...`) redirect to the full page
+        let html = Client::base()?
+            .get(request.url.as_str())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request to Snapchat: {e:?}"))?
+            .error_for_status()
+            .map_err(|e| format!("Failed to get response from Snapchat: {e:?}"))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to get text from Snapchat response: {e:?}"))?;
+
+        let media_urls = tokio::task::spawn_blocking(move || media_urls_from_page(&html))
+            .await
+            .map_err(|e| format!("Failed to parse Snapchat page: {e:?}"))??;
+
+        debug!(?media_urls, "Got Snapchat media urls");
+
+        if media_urls.is_empty() {
+            return Err("Failed to find media on Snapchat page".to_string().into());
+        }
+
+        Ok(ExtractedInfo::from_urls(
+            request,
+            media_urls
+                .into_iter()
+                .map(|x| ExtractedUrlInfo::new(x).with_preferred_downloader(Some(Generic))),
+        ))
+    }
+}
+
+impl Snapchat {
+    #[must_use]
+    pub fn is_share_url(url: &Url) -> bool {
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+
+        match host {
+            "t.snapchat.com" | "story.snapchat.com" => true,
+            "snapchat.com" | "www.snapchat.com" => {
+                let path = url.path();
+
+                path.starts_with("/spotlight/")
+                    || path.starts_with("/add/")
+                    || path.starts_with("/p/")
+                    || (path.starts_with("/@") && path.contains("/spotlight/"))
+            }
+            _ => false,
+        }
+    }
+}
+
+fn media_urls_from_page(html: &str) -> Result<Vec<String>, String> {
+    let page_props = next_data(html)?.and_then(|x| x.get("props")?.get("pageProps").cloned());
+
+    trace!(?page_props, "Got Snapchat page props");
+
+    let mut urls = page_props
+        .as_ref()
+        .map(media_urls_from_page_props)
+        .unwrap_or_default();
+
+    // Fall back to the OpenGraph tags if the page data changes shape
+    if urls.is_empty() {
+        let tags = meta_tags(html)?;
+
+        trace!(?tags, "Got Snapchat meta tags");
+
+        urls.extend(
+            ["og:video:secure_url", "og:video", "og:video:url"]
+                .iter()
+                .find_map(|x| tags.get(*x))
+                .cloned(),
+        );
+    }
+
+    urls.dedup();
+
+    Ok(urls)
+}
+
+/// The JSON data Next.js embeds into the page
+fn next_data(html: &str) -> Result<Option<serde_json::Value>, String> {
+    let dom = tl::parse(html, tl::ParserOptions::default())
+        .map_err(|e| format!("Failed to parse response body: {e:?}"))?;
+    let parser = dom.parser();
+
+    let Some(data_el) = dom
+        .get_element_by_id("__NEXT_DATA__")
+        .and_then(|x| x.get(parser))
+    else {
+        return Ok(None);
+    };
+
+    let data = serde_json::from_str(&data_el.inner_text(parser))
+        .map_err(|e| format!("Failed to parse Snapchat page data: {e:?}"))?;
+
+    Ok(Some(data))
+}
+
+fn media_urls_from_page_props(page_props: &serde_json::Value) -> Vec<String> {
+    // Spotlight videos
+    let spotlight = page_props
+        .get("videoMetadata")
+        .and_then(|x| x.get("contentUrl"))
+        .and_then(|x| x.as_str())
+        .map(ToString::to_string);
+
+    if let Some(spotlight) = spotlight {
+        return vec![spotlight];
+    }
+
+    // Stories, where every snap is a separate image or video
+    page_props
+        .get("story")
+        .and_then(|x| x.get("snapList"))
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|x| x.get("snapUrls")?.get("mediaUrl")?.as_str())
+        .map(ToString::to_string)
+        .collect()
+}
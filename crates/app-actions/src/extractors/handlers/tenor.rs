@@ -0,0 +1,74 @@
+use app_errors::AppError;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::{html::meta_tags, request::Client},
+    downloaders::handlers::generic::Generic,
+    extractors::ExtractedUrlInfo,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Tenor;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Tenor {
+    fn description(&self) -> &'static str {
+        "Gets the MP4 version of GIFs from Tenor."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::is_view_url(&request.url)
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let html = Client::base()?
+            .get(request.url.as_str())
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send request to Tenor: {e:?}"))?
+            .error_for_status()
+            .map_err(|e| format!("Failed to get response from Tenor: {e:?}"))?
+            .text()
+            .await
+            .map_err(|e| format!("Failed to get text from Tenor response: {e:?}"))?;
+
+        let tags = tokio::task::spawn_blocking(move || meta_tags(&html))
+            .await
+            .map_err(|e| format!("Failed to parse Tenor page: {e:?}"))??;
+
+        trace!(?tags, "Got Tenor meta tags");
+
+        // Prefer the video version as it's both smaller and better quality
+        let media_url = [
+            "og:video:secure_url",
+            "og:video",
+            "og:video:url",
+            "og:image",
+        ]
+        .iter()
+        .find_map(|x| tags.get(*x))
+        .ok_or_else(|| "Failed to find media on Tenor page".to_string())?;
+
+        debug!(?media_url, "Got Tenor media url");
+
+        Ok(ExtractedInfo::from_url(
+            request,
+            ExtractedUrlInfo::new(media_url).with_preferred_downloader(Some(Generic)),
+        ))
+    }
+}
+
+impl Tenor {
+    #[must_use]
+    pub fn is_view_url(url: &Url) -> bool {
+        let is_tenor = url
+            .host_str()
+            .is_some_and(|x| matches!(x, "tenor.com" | "www.tenor.com"));
+
+        is_tenor && url.path().contains("/view/")
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
 
+use app_errors::AppError;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, trace};
 use url::Url;
@@ -7,7 +8,8 @@ use url::Url;
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
 use crate::{
     common::{request::USER_AGENT, url::UrlWithMeta},
-    downloaders::handlers::generic::Generic,
+    downloaders::handlers::generic::{Generic, GenericDownloaderOptions},
+    extractors::ExtractedUrlInfo,
 };
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -17,19 +19,19 @@ pub struct Tiktok;
 #[typetag::serde]
 impl Extractor for Tiktok {
     fn description(&self) -> &'static str {
-        "Get videos from TikTok posts"
+        "Get videos from TikTok posts, and every image and the audio from photo mode posts"
     }
 
     async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
         Self::is_post_url(&request.url)
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
         let media_urls = get_media_download_urls(request)
             .await
             .map_err(|e| format!("Failed to get media download urls for tiktok post: {:?}", e))?;
 
-        Ok(ExtractedInfo::from_url(request, media_urls).with_preferred_downloader(Some(Generic)))
+        Ok(ExtractedInfo::from_urls(request, media_urls))
     }
 }
 
@@ -40,7 +42,9 @@ impl Tiktok {
     }
 }
 
-async fn get_media_download_urls(req: &ExtractInfoRequest) -> Result<UrlWithMeta, String> {
+async fn get_media_download_urls(
+    req: &ExtractInfoRequest,
+) -> Result<Vec<ExtractedUrlInfo>, String> {
     debug!("Getting media download urls for tiktok post");
 
     let resp = req
@@ -107,17 +111,85 @@ async fn get_media_download_urls(req: &ExtractInfoRequest) -> Result<UrlWithMeta
         .ok_or_else(|| "Failed to get video data from post data".to_string())?;
     trace!(?video_data, "Got video data from post data");
 
+    let with_headers = |url: &str| {
+        UrlWithMeta::from_url(url)
+            .with_header("User-Agent", &USER_AGENT)
+            .with_header("Referer", &req.url)
+            .with_header("Cookie", &format!("tt_chain_token={}", csrf_token))
+    };
+
+    if let Some(image_post) = video_data.get("imagePost") {
+        debug!("Post is a photo mode slideshow");
+
+        return Ok(photo_mode_urls(video_data, image_post)?
+            .into_iter()
+            .map(|(url, file_name)| {
+                ExtractedUrlInfo::new(with_headers(&url))
+                    .with_preferred_downloader(Some(Generic))
+                    .with_downloader_options(
+                        GenericDownloaderOptions::new().with_file_name(Some(file_name)),
+                    )
+            })
+            .collect());
+    }
+
     let video_url = video_data
         .get("video")
         .and_then(|x| x.get("playAddr"))
         .and_then(|x| x.as_str())
+        .filter(|x| !x.is_empty())
         .ok_or_else(|| "Failed to get video url from video data".to_string())?;
     trace!(?video_url, "Got video url from video data");
 
-    let download_info = UrlWithMeta::from_url(video_url)
-        .with_header("User-Agent", &USER_AGENT)
-        .with_header("Referer", &req.url)
-        .with_header("Cookie", &format!("tt_chain_token={}", csrf_token));
+    Ok(vec![
+        ExtractedUrlInfo::new(with_headers(video_url)).with_preferred_downloader(Some(Generic))
+    ])
+}
+
+/// Every slide of a photo mode post, followed by the background audio.
+///
+/// The files are named `<post id>_<slide number>` (and `<post id>_audio`)
+/// so they keep the slide order.
+fn photo_mode_urls(
+    video_data: &serde_json::Value,
+    image_post: &serde_json::Value,
+) -> Result<Vec<(String, String)>, String> {
+    let post_id = video_data
+        .get("id")
+        .and_then(|x| x.as_str())
+        .unwrap_or("tiktok");
+
+    let mut urls = image_post
+        .get("images")
+        .and_then(|x| x.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|x| {
+            x.get("imageURL")?
+                .get("urlList")?
+                .as_array()?
+                .first()?
+                .as_str()
+        })
+        .enumerate()
+        .map(|(i, url)| (url.to_string(), format!("{post_id}_{:03}", i + 1)))
+        .collect::<Vec<_>>();
+    trace!(?urls, "Got photo mode image urls");
+
+    if urls.is_empty() {
+        return Err("Failed to get images from photo mode post".to_string());
+    }
+
+    let audio_url = video_data
+        .get("music")
+        .and_then(|x| x.get("playUrl"))
+        .and_then(|x| x.as_str())
+        .filter(|x| !x.is_empty());
+    trace!(?audio_url, "Got photo mode audio url");
+
+    if let Some(audio_url) = audio_url {
+        urls.push((audio_url.to_string(), format!("{post_id}_audio")));
+    }
 
-    Ok(download_info)
+    Ok(urls)
 }
@@ -1,6 +1,7 @@
 use std::string::ToString;
 
 use app_config::{timeframe::Timeframe, Config};
+use app_errors::{AppError, SourceGone};
 use http::{header, HeaderMap};
 use once_cell::sync::Lazy;
 use regex::Regex;
@@ -11,7 +12,10 @@ use url::{form_urlencoded, Url};
 
 use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
 use crate::{
-    common::request::Client, downloaders::handlers::generic::Generic, extractors::ExtractedUrlInfo,
+    common::request::Client,
+    downloaders::handlers::{generic::Generic, hls::Hls},
+    extractors::ExtractedUrlInfo,
+    format_choice::{MediaVariant, SCREENSHOT_OPTION, VARIANTS_OPTION},
 };
 
 pub static URL_MATCH: Lazy<Regex> = Lazy::new(|| {
@@ -21,11 +25,22 @@ pub static URL_MATCH: Lazy<Regex> = Lazy::new(|| {
     .expect("Invalid regex")
 });
 
+pub static SPACE_URL_MATCH: Lazy<Regex> = Lazy::new(|| {
+    // https://x.com/i/spaces/1OdKrBnaEPXKX
+    Regex::new(r"^https?://(www\.)?(twitter|x)\.com/i/spaces/(?P<space_id>[0-9a-zA-Z]+)")
+        .expect("Invalid regex")
+});
+
 pub static MEDIA_URL_MATCH: Lazy<Regex> = Lazy::new(|| {
     // https://pbs.twimg.com/media/FqPFEWYWYBQ5iG3?format=png&name=small
     Regex::new(r"^https?://pbs\.twimg\.com/media/").expect("Invalid regex")
 });
 
+/// The size of the video is in the URL,
+/// eg. `https://video.twimg.com/ext_tw_video/1/pu/vid/avc1/1280x720/abc.mp4`
+static VIDEO_SIZE_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"/(?P<width>[0-9]+)x(?P<height>[0-9]+)/").expect("Invalid regex"));
+
 static DEFAULT_AUTHORIZATION: &str =
     "Bearer AAAAAAAAAAAAAAAAAAAAANRILgAAAAAAnNwIzUejRCOuH5E6I8xnZz4puTs%\
      3D1Zv7ttfk8LF81IUq16cHjhLTvJu4FA33AGWWjCpTnA";
@@ -33,6 +48,11 @@ static DEFAULT_AUTHORIZATION: &str =
 static TWEET_INFO_ENDPOINT: &str =
     "https://x.com/i/api/graphql/sCU6ckfHY0CyJ4HFjPhjtg/TweetResultByRestId";
 
+static AUDIO_SPACE_ENDPOINT: &str =
+    "https://x.com/i/api/graphql/HPEisOmj1epUNLCWTYhUWw/AudioSpaceById";
+
+static LIVE_VIDEO_STREAM_ENDPOINT: &str = "https://api.x.com/1.1/live_video_stream/status";
+
 static GUEST_TOKEN_ENDPOINT: &str = "https://api.x.com/1.1/guest/activate.json";
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -42,14 +62,18 @@ pub struct Twitter;
 #[typetag::serde]
 impl Extractor for Twitter {
     fn description(&self) -> &'static str {
-        "Downloads images and videos from Twitter posts"
+        "Downloads images and videos from Twitter posts and the audio of Spaces"
     }
 
     async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
-        Self::is_post_url(request.url.as_str())
+        Self::is_post_url(request.url.as_str()) || Self::is_space_url(request.url.as_str())
     }
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        if let Some(space_id) = get_space_id_from_url(request.url.as_str()) {
+            return Ok(get_space_info(request, &space_id).await?);
+        }
+
         debug!("Downloading tweet");
 
         let tweet_info = match get_tweet_info_from_url(request.url.as_str())? {
@@ -81,7 +105,9 @@ impl Extractor for Twitter {
             tweet_media.push(tweet_screenshot_url);
         }
 
-        Ok(ExtractedInfo::from_urls(request, tweet_media))
+        Ok(ExtractedInfo::from_urls(request, tweet_media)
+            .with_text(get_tweet_text(&tweet_data))
+            .with_age_restricted(is_tweet_sensitive(&tweet_data)))
     }
 }
 
@@ -104,12 +130,17 @@ impl Twitter {
         ExtractedUrlInfo::new(self.screenshot_tweet_url(url))
             .with_preferred_downloader(Some(Generic))
             .with_downloader_options(Generic::options().with_timeout(Some(Timeframe::Seconds(60))))
+            .with_downloader_option(SCREENSHOT_OPTION, true)
     }
 
     pub fn is_post_url(url: &str) -> bool {
         URL_MATCH.is_match(url)
     }
 
+    pub fn is_space_url(url: &str) -> bool {
+        SPACE_URL_MATCH.is_match(url)
+    }
+
     pub fn is_media_url(url: &str) -> bool {
         MEDIA_URL_MATCH.is_match(url)
     }
@@ -153,7 +184,7 @@ fn get_tweet_info_from_url(url: &str) -> Result<Option<TweetInfo>, String> {
 struct TweetData(serde_json::Value);
 
 #[tracing::instrument]
-async fn get_tweet_data(tweet_id: &str) -> Result<TweetData, String> {
+async fn get_tweet_data(tweet_id: &str) -> Result<TweetData, AppError> {
     let guest_auth = get_guest_auth().await?;
 
     let query_params = {
@@ -228,31 +259,214 @@ async fn get_tweet_data(tweet_id: &str) -> Result<TweetData, String> {
 
     trace!(?resp, "Got response");
 
-    resp.as_object()
+    let Some(tweet_result) = resp
+        .as_object()
         .and_then(|x| x.get("data"))
         .and_then(|x| x.as_object())
         .and_then(|x| x.get("tweetResult"))
         .and_then(|x| x.as_object())
-        .and_then(|x| x.get("result"))
-        .map(|x| TweetData(x.clone()))
+    else {
+        return Err(format!(
+            "Failed to get tweet data from response: {:?}",
+            resp.to_string()
+        )
+        .into());
+    };
+
+    // Deleted tweets come back without a result
+    let Some(result) = tweet_result.get("result") else {
+        return Err(SourceGone::new(format!("Tweet {tweet_id} was deleted")).into());
+    };
+
+    // Eg. `{"__typename": "TweetUnavailable", "reason": "Suspended"}`
+    if matches!(
+        result.get("__typename").and_then(|x| x.as_str()),
+        Some("TweetUnavailable" | "TweetTombstone")
+    ) {
+        let reason = result
+            .get("reason")
+            .and_then(|x| x.as_str())
+            .unwrap_or("Unavailable");
+
+        return Err(SourceGone::new(format!("Tweet {tweet_id} is unavailable ({reason})")).into());
+    }
+
+    Ok(TweetData(result.clone()))
+}
+
+fn get_space_id_from_url(url: &str) -> Option<String> {
+    SPACE_URL_MATCH
+        .captures(url)
+        .and_then(|x| x.name("space_id"))
+        .map(|x| x.as_str().to_string())
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SpaceMetadata {
+    media_key: String,
+    state: Option<String>,
+    title: Option<String>,
+    #[serde(default)]
+    is_space_available_for_replay: bool,
+}
+
+/// Resolves the replay (or live) playlist of the Space,
+/// which gets joined into a single audio file by ffmpeg
+#[tracing::instrument(skip(request))]
+async fn get_space_info(
+    request: &ExtractInfoRequest,
+    space_id: &str,
+) -> Result<ExtractedInfo, String> {
+    debug!("Downloading Twitter Space");
+
+    let guest_auth = get_guest_auth().await?;
+
+    let metadata = get_space_metadata(&guest_auth, space_id).await?;
+
+    trace!(?metadata, "Got Space metadata");
+
+    if metadata.state.as_deref() == Some("NotStarted") {
+        return Err("The Space hasn't started yet".to_string());
+    }
+
+    if metadata.state.as_deref() == Some("Ended") && !metadata.is_space_available_for_replay {
+        return Err("The Space has ended and its replay isn't available".to_string());
+    }
+
+    let playlist_url = get_space_playlist_url(&guest_auth, &metadata.media_key).await?;
+
+    debug!(?playlist_url, "Got Space playlist URL");
+
+    let file_name = metadata
+        .title
+        .filter(|x| !x.trim().is_empty())
+        .unwrap_or_else(|| space_id.to_string());
+
+    Ok(ExtractedInfo::from_urls(
+        request,
+        vec![ExtractedUrlInfo::new(playlist_url)
+            .with_preferred_downloader(Some(Hls))
+            .with_downloader_options(
+                Hls::options()
+                    .with_file_name(Some(file_name))
+                    .with_audio_only(true),
+            )],
+    ))
+}
+
+async fn get_space_metadata(
+    guest_auth: &GuestAuth,
+    space_id: &str,
+) -> Result<SpaceMetadata, String> {
+    let url = {
+        let graphql_variables = json!({
+            "id": space_id,
+            "isMetatagsQuery": true,
+            "withReplays": true,
+            "withListeners": true,
+        })
+        .to_string();
+        let graphql_features = json!({
+            "spaces_2022_h2_clipping": true,
+            "spaces_2022_h2_spaces_communities": true,
+            "verified_phone_label_enabled": false,
+            "responsive_web_graphql_skip_user_profile_image_extensions_enabled": false,
+            "responsive_web_graphql_timeline_navigation_enabled": true,
+            "responsive_web_graphql_exclude_directive_enabled": true,
+        })
+        .to_string();
+
+        let mut url = Url::parse(AUDIO_SPACE_ENDPOINT).expect("Invalid URL");
+        url.query_pairs_mut()
+            .append_pair("variables", &graphql_variables)
+            .append_pair("features", &graphql_features);
+
+        url
+    };
+
+    trace!(?url, "Space metadata URL");
+
+    let resp = Client::base()?
+        .get(url)
+        .headers(guest_auth.get_headers())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {:?}", e))?
+        .json::<serde_json::Value>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {:?}", e))?;
+
+    trace!(?resp, "Got response");
+
+    resp.get("data")
+        .and_then(|x| x.get("audioSpace"))
+        .and_then(|x| x.get("metadata"))
+        .and_then(|x| serde_json::from_value::<SpaceMetadata>(x.clone()).ok())
         .ok_or_else(|| {
             format!(
-                "Failed to get tweet data from response: {:?}",
+                "Failed to get Space metadata from response: {:?}",
                 resp.to_string()
             )
         })
 }
 
+async fn get_space_playlist_url(guest_auth: &GuestAuth, media_key: &str) -> Result<String, String> {
+    #[derive(Debug, Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct StreamSource {
+        location: Option<String>,
+        no_redirect_playback_url: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    struct StreamStatus {
+        source: StreamSource,
+    }
+
+    let resp = Client::base()?
+        .get(format!("{LIVE_VIDEO_STREAM_ENDPOINT}/{media_key}"))
+        .headers(guest_auth.get_headers())
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {:?}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get Space stream status: {:?}", e))?
+        .json::<StreamStatus>()
+        .await
+        .map_err(|e| format!("Failed to parse response: {:?}", e))?;
+
+    trace!(?resp, "Got Space stream status");
+
+    let playlist_url = resp
+        .source
+        .no_redirect_playback_url
+        .or(resp.source.location)
+        .ok_or_else(|| "Space stream has no playlist".to_string())?;
+
+    if playlist_url.contains("/live_video_stream/geoblocked/") {
+        return Err("The Space is not available in this region".to_string());
+    }
+
+    Ok(playlist_url)
+}
+
 #[derive(Debug)]
 pub enum TweetMedia {
-    Photo { url: String },
-    Video { url: String },
+    Photo {
+        url: String,
+    },
+    Video {
+        url: String,
+        /// All the qualities of the video, best one first
+        variants: Vec<MediaVariant>,
+    },
 }
 impl TweetMedia {
     #[must_use]
     pub fn as_url(&self) -> &str {
         match self {
-            Self::Photo { url } | Self::Video { url } => url,
+            Self::Photo { url } | Self::Video { url, .. } => url,
         }
     }
 }
@@ -264,10 +478,16 @@ impl std::fmt::Display for TweetMedia {
 
 impl From<TweetMedia> for ExtractedUrlInfo {
     fn from(val: TweetMedia) -> Self {
-        Self::new(val.as_url()).with_preferred_downloader(match val {
-            TweetMedia::Photo { .. } => Some(Generic),
-            TweetMedia::Video { .. } => None,
-        })
+        let info = Self::new(val.as_url());
+
+        match val {
+            TweetMedia::Photo { .. } => info.with_preferred_downloader(Some(Generic)),
+            // Left to yt-dlp
+            TweetMedia::Video { variants, .. } => info.with_downloader_option(
+                VARIANTS_OPTION,
+                serde_json::to_value(variants).unwrap_or_default(),
+            ),
+        }
     }
 }
 
@@ -310,9 +530,20 @@ fn get_tweet_media_urls(tweet_data: &TweetData) -> Option<Vec<TweetMedia>> {
                             .cmp(&lt.bitrate.unwrap_or_default())
                     });
 
-                    variants
-                        .first()
-                        .map(|x| TweetMedia::Video { url: x.url.clone() })
+                    let url = variants.first()?.url.clone();
+                    // The playlists don't have a bitrate and aren't worth picking on their own
+                    let variants = variants
+                        .into_iter()
+                        .filter(|x| x.bitrate.is_some())
+                        .map(|x| MediaVariant {
+                            height: VIDEO_SIZE_MATCH
+                                .captures(&x.url)
+                                .and_then(|c| c.name("height")?.as_str().parse().ok()),
+                            url: x.url,
+                        })
+                        .collect();
+
+                    Some(TweetMedia::Video { url, variants })
                 }
             }
         }
@@ -346,6 +577,39 @@ fn get_tweet_media_urls(tweet_data: &TweetData) -> Option<Vec<TweetMedia>> {
     tweet_media
 }
 
+/// Whether the tweet is marked as sensitive, by the author or by Twitter for its media
+fn is_tweet_sensitive(tweet_data: &TweetData) -> bool {
+    let possibly_sensitive = tweet_data
+        .0
+        .pointer("/legacy/possibly_sensitive")
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or_default();
+
+    let media_warning = tweet_data
+        .0
+        .pointer("/legacy/extended_entities/media")
+        .and_then(serde_json::Value::as_array)
+        .is_some_and(|media| {
+            media.iter().any(|x| {
+                x.pointer("/sensitive_media_warning/adult_content")
+                    .and_then(serde_json::Value::as_bool)
+                    .unwrap_or_default()
+            })
+        });
+
+    possibly_sensitive || media_warning
+}
+
+/// Long tweets only have the start of the text in the usual place
+fn get_tweet_text(tweet_data: &TweetData) -> Option<String> {
+    tweet_data
+        .0
+        .pointer("/note_tweet/note_tweet_results/result/text")
+        .or_else(|| tweet_data.0.pointer("/legacy/full_text"))
+        .and_then(serde_json::Value::as_str)
+        .map(ToString::to_string)
+}
+
 #[derive(Debug)]
 struct GuestAuth {
     guest_token: String,
@@ -0,0 +1,261 @@
+use app_errors::AppError;
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, trace};
+use url::Url;
+
+use super::{ExtractInfoRequest, ExtractedInfo, Extractor};
+use crate::{
+    common::{request::Client, url::UrlWithMeta},
+    downloaders::handlers::generic::Generic,
+    extractors::ExtractedUrlInfo,
+};
+
+static STATUS_API_ENDPOINT: &str = "https://m.weibo.cn/statuses/show";
+
+/// Media hosts reject requests without a Weibo referer
+static MEDIA_REFERER: &str = "https://weibo.com/";
+
+/// Video URL keys from the best to the worst quality
+static VIDEO_URL_KEYS: &[&str] = &["mp4_1080p_mp4", "mp4_720p_mp4", "mp4_hd_mp4", "mp4_ld_mp4"];
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Weibo;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Extractor for Weibo {
+    fn description(&self) -> &'static str {
+        "Gets original size images and the best quality video from Weibo posts."
+    }
+
+    async fn can_handle(&self, request: &ExtractInfoRequest) -> bool {
+        Self::status_id(&request.url).is_some()
+    }
+
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
+        let status_id =
+            Self::status_id(&request.url).ok_or_else(|| "Invalid Weibo url".to_string())?;
+
+        debug!(?status_id, "Getting Weibo status");
+
+        let status = get_status(&status_id).await?;
+
+        trace!(?status, "Got Weibo status");
+
+        // Reposts without their own media show the media of the original post
+        let status = match status.retweeted_status {
+            Some(retweeted) if !status.has_media() => *retweeted,
+            _ => status,
+        };
+
+        let urls = status.media_urls();
+
+        if urls.is_empty() {
+            return Err("No media found in Weibo post".to_string().into());
+        }
+
+        Ok(ExtractedInfo::from_urls(
+            request,
+            urls.into_iter().map(|x| {
+                ExtractedUrlInfo::new(
+                    UrlWithMeta::from_url(&x).with_header("Referer", &MEDIA_REFERER),
+                )
+                .with_preferred_downloader(Some(Generic))
+            }),
+        ))
+    }
+}
+
+static MOBILE_PATH_MATCH: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^/(?:status|detail)/(?P<id>[a-zA-Z0-9]+)/?$").expect("Invalid regex")
+});
+
+static DESKTOP_PATH_MATCH: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^/(?:detail|\d+)/(?P<id>[a-zA-Z0-9]+)/?$").expect("Invalid regex"));
+
+impl Weibo {
+    /// Gets the status ID (either the numeric or the base62 one) from
+    /// `m.weibo.cn/status/<id>`, `m.weibo.cn/detail/<id>`,
+    /// `weibo.com/<user-id>/<id>` and `weibo.com/detail/<id>` links
+    fn status_id(url: &Url) -> Option<String> {
+        let matcher = match url.host_str()? {
+            "m.weibo.cn" => &MOBILE_PATH_MATCH,
+            "weibo.com" | "www.weibo.com" => &DESKTOP_PATH_MATCH,
+            _ => return None,
+        };
+
+        matcher
+            .captures(url.path())
+            .and_then(|x| x.name("id"))
+            .map(|x| x.as_str().to_string())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct StatusResponse {
+    ok: i32,
+    data: Option<Status>,
+    msg: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[allow(clippy::struct_field_names)]
+struct Status {
+    #[serde(default)]
+    pics: Vec<StatusPic>,
+    page_info: Option<PageInfo>,
+    retweeted_status: Option<Box<Self>>,
+}
+impl Status {
+    fn has_media(&self) -> bool {
+        !self.pics.is_empty() || self.video_url().is_some()
+    }
+
+    fn media_urls(&self) -> Vec<String> {
+        let mut urls = self
+            .pics
+            .iter()
+            .filter_map(StatusPic::original_url)
+            .collect::<Vec<_>>();
+
+        if let Some(video_url) = self.video_url() {
+            urls.push(video_url);
+        }
+
+        urls
+    }
+
+    fn video_url(&self) -> Option<String> {
+        let page_info = self.page_info.as_ref()?;
+
+        if page_info.page_type.as_deref() != Some("video") {
+            return None;
+        }
+
+        let best_by_bitrate = page_info
+            .media_info
+            .as_ref()
+            .and_then(|x| x.playback_list.as_ref())
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.play_info.as_ref())
+            .max_by_key(|x| x.bitrate.unwrap_or_default())
+            .map(|x| x.url.clone());
+
+        let best_by_name = || {
+            page_info.urls.as_ref().and_then(|urls| {
+                VIDEO_URL_KEYS
+                    .iter()
+                    .find_map(|key| urls.get(*key))
+                    .and_then(|x| x.as_str())
+                    .map(ToString::to_string)
+            })
+        };
+
+        let stream_url = || {
+            page_info.media_info.as_ref().and_then(|x| {
+                x.stream_url_hd
+                    .clone()
+                    .or_else(|| x.stream_url.clone())
+                    .filter(|x| !x.is_empty())
+            })
+        };
+
+        best_by_bitrate
+            .or_else(best_by_name)
+            .or_else(stream_url)
+            .map(|x| x.replace("http://", "https://"))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct StatusPic {
+    url: Option<String>,
+    large: Option<StatusPicSize>,
+}
+impl StatusPic {
+    /// `https://wx1.sinaimg.cn/orj360/<name>.jpg` -> `https://wx1.sinaimg.cn/large/<name>.jpg`
+    fn original_url(&self) -> Option<String> {
+        let url = self
+            .large
+            .as_ref()
+            .map(|x| x.url.as_str())
+            .or(self.url.as_deref())?;
+
+        let mut url = Url::parse(url).ok()?;
+
+        let file_name = url.path_segments()?.next_back()?.to_string();
+        url.set_path(&format!("/large/{file_name}"));
+
+        Some(url.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct StatusPicSize {
+    url: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PageInfo {
+    #[serde(rename = "type")]
+    page_type: Option<String>,
+    urls: Option<serde_json::Map<String, serde_json::Value>>,
+    media_info: Option<MediaInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MediaInfo {
+    stream_url: Option<String>,
+    stream_url_hd: Option<String>,
+    playback_list: Option<Vec<Playback>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct Playback {
+    play_info: Option<PlayInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct PlayInfo {
+    url: String,
+    bitrate: Option<u64>,
+}
+
+async fn get_status(status_id: &str) -> Result<Status, String> {
+    let api_url = {
+        let mut api_url = Url::parse(STATUS_API_ENDPOINT).expect("Invalid URL");
+
+        api_url.query_pairs_mut().append_pair("id", status_id);
+
+        api_url
+    };
+
+    let resp = Client::base()?
+        .get(api_url)
+        .header("Referer", format!("https://m.weibo.cn/status/{status_id}"))
+        .header("MWeibo-Pwa", "1")
+        .header("X-Requested-With", "XMLHttpRequest")
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request to Weibo: {e}"))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to get response from Weibo: {e}"))?
+        .json::<StatusResponse>()
+        .await
+        .map_err(|e| format!("Failed to parse Weibo response: {e}"))?;
+
+    match resp {
+        StatusResponse {
+            ok: 1,
+            data: Some(data),
+            ..
+        } => Ok(data),
+        StatusResponse { msg, .. } => Err(format!(
+            "Weibo returned an error: {}",
+            msg.unwrap_or_else(|| "Unknown error".to_string())
+        )),
+    }
+}
@@ -1,6 +1,8 @@
+use app_errors::{AppError, UnsupportedSource};
+use common::extracted_info::EXTRACTOR_META;
 pub use common::{
     extract_info_request::ExtractInfoRequest,
-    extracted_info::{ExtractedInfo, ExtractedUrlInfo},
+    extracted_info::{ExtractedInfo, ExtractedMediaSummary, ExtractedUrlInfo, ExtractionSummary},
 };
 pub use handlers::AVAILABLE_EXTRACTORS;
 
@@ -18,15 +20,15 @@ pub trait Extractor {
 
     async fn can_handle(&self, request: &ExtractInfoRequest) -> bool;
 
-    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, String>;
+    async fn extract_info(&self, request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError>;
 }
 
-pub async fn extract_info(request: &ExtractInfoRequest) -> Result<ExtractedInfo, String> {
+pub async fn extract_info(request: &ExtractInfoRequest) -> Result<ExtractedInfo, AppError> {
     for extractor in AVAILABLE_EXTRACTORS.iter() {
         if extractor.can_handle(request).await {
             return extractor.extract_info(request).await.map(|x| {
                 x.with_meta(
-                    "extractor",
+                    EXTRACTOR_META,
                     serde_json::to_value(extractor).expect("Failed to serialize extractor"),
                 )
                 .dedup_urls()
@@ -34,5 +36,5 @@ pub async fn extract_info(request: &ExtractInfoRequest) -> Result<ExtractedInfo,
         }
     }
 
-    Err("No extractor found".to_string())
+    Err(UnsupportedSource::new("No extractor found").into())
 }
@@ -78,3 +78,9 @@ impl From<CmdOutputErr> for FixerError {
         Self::CommandError(err.into())
     }
 }
+
+impl From<CmdError> for FixerError {
+    fn from(err: CmdError) -> Self {
+        Self::CommandError(err.into())
+    }
+}
@@ -4,7 +4,7 @@ use std::{
 };
 
 use resolve_path::PathResolveExt;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
 use super::FixerError;
 
@@ -39,6 +39,26 @@ impl FixRequest {
         self
     }
 
+    #[must_use]
+    pub fn options<T>(&self) -> Option<T>
+    where
+        T: DeserializeOwned,
+    {
+        let val = serde_json::to_value(self.options.clone()).ok()?;
+
+        serde_json::from_value(val).ok()
+    }
+
+    #[must_use]
+    pub fn option<T>(&self, key: &str) -> Option<T>
+    where
+        T: DeserializeOwned,
+    {
+        let val = self.options.get(key)?.clone();
+
+        serde_json::from_value(val).ok()
+    }
+
     pub fn resolve_path(mut self) -> Result<Self, FixerError> {
         let p = self
             .file_path
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use app_errors::{AppError, ExternalToolError, UserInputError};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -18,6 +19,8 @@ pub enum FixerError {
     FileNotFound(PathBuf),
     #[error("{0:?} is not a file")]
     NotAFile(PathBuf),
+    #[error("File {0:?} is corrupt and could not be repaired: {1}")]
+    CorruptFile(PathBuf, String),
 }
 impl FixerError {
     pub fn failed_fix<T>(err: T) -> Self
@@ -27,8 +30,25 @@ impl FixerError {
         Self::FailedFix(err.into())
     }
 
+    /// Errors that mean the file shouldn't be passed on, so the remaining fixers are skipped
+    #[must_use]
+    pub const fn is_fatal(&self) -> bool {
+        matches!(self, Self::CorruptFile(..))
+    }
+
     #[must_use]
     pub const fn should_send_as_response(&self) -> bool {
-        matches!(self, Self::FailedFix(_))
+        matches!(self, Self::FailedFix(_) | Self::CorruptFile(..))
+    }
+}
+
+impl From<FixerError> for AppError {
+    fn from(err: FixerError) -> Self {
+        match err {
+            FixerError::CommandError(e) => ExternalToolError::failed("fixer", e.to_string()).into(),
+            FixerError::CorruptFile(..) => UserInputError::Invalid(err.to_string()).into(),
+            FixerError::JoinError(e) => e.into(),
+            e => Self::Other(e.to_string()),
+        }
     }
 }
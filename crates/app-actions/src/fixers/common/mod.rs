@@ -3,6 +3,8 @@ pub mod crop_filter;
 pub mod fix_request;
 pub mod fix_result;
 pub mod fixer_error;
+pub mod reencode;
+pub mod subtitles;
 
 pub use fix_request::FixRequest;
 pub use fix_result::FixResult;
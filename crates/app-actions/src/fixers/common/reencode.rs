@@ -0,0 +1,157 @@
+use std::path::{Path, PathBuf};
+
+use app_config::Config;
+use app_helpers::{
+    ffprobe::{FfProbeResult, Stream},
+    trash::move_to_trash,
+};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, warn};
+
+use super::{command::CmdError, subtitles::mp4_subtitle_args};
+
+/// Field orders of interlaced video, the rest are `progressive` or `unknown`
+const INTERLACED_FIELD_ORDERS: &[&str] = &["tt", "bb", "tb", "bt"];
+
+/// Cover art codecs mp4 files can have
+const MP4_ATTACHED_PIC_CODECS: &[&str] = &["mjpeg", "png"];
+
+#[must_use]
+pub fn is_interlaced(stream: &Stream) -> bool {
+    stream
+        .field_order
+        .as_deref()
+        .is_some_and(|x| INTERLACED_FIELD_ORDERS.contains(&x))
+}
+
+/// The video stream of the file, not counting cover art
+#[must_use]
+pub fn main_video_stream(media_info: &FfProbeResult) -> Option<&Stream> {
+    media_info
+        .streams
+        .iter()
+        .find(|s| s.codec_type.as_deref() == Some("video") && s.disposition.attached_pic == 0)
+}
+
+/// Re-encodes the video of the file into an mp4 in one pass.
+///
+/// ffmpeg turns the frames upright by itself while doing so, and the video is
+/// deinterlaced with the given filter (eg. `bwdif`) if there is one.
+/// The audio, text subtitles and cover art are kept.
+///
+/// The new file replaces the old one if that was an mp4 already,
+/// otherwise the old one is moved to the trash.
+pub async fn reencode_video(
+    file_path: &Path,
+    media_info: &FfProbeResult,
+    video_stream: &Stream,
+    deinterlace_filter: Option<&str>,
+) -> Result<PathBuf, ReencodeError> {
+    let audio_is_aac = media_info
+        .streams
+        .iter()
+        .filter(|s| s.codec_type.as_deref() == Some("audio"))
+        .all(|s| s.codec_name.as_deref() == Some("aac"));
+
+    let attached_pics = media_info
+        .streams
+        .iter()
+        .filter(|s| s.codec_type.as_deref() == Some("video") && s.disposition.attached_pic != 0)
+        .filter(|s| {
+            s.codec_name
+                .as_deref()
+                .is_some_and(|x| MP4_ATTACHED_PIC_CODECS.contains(&x))
+        })
+        .collect::<Vec<_>>();
+
+    let in_place = file_path
+        .extension()
+        .is_some_and(|x| x.eq_ignore_ascii_case("mp4"));
+    let new_path = if in_place {
+        file_path.with_extension("reencoded.mp4")
+    } else {
+        file_path.with_extension("mp4")
+    };
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    cmd.arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-map", &format!("0:{}", video_stream.index), "-map", "0:a?"])
+        .args(mp4_subtitle_args(media_info));
+
+    for (i, pic) in attached_pics.iter().enumerate() {
+        // The video being re-encoded is `v:0`
+        let out_index = i + 1;
+        cmd.args(["-map", &format!("0:{}", pic.index)])
+            .args([format!("-c:v:{out_index}").as_str(), "copy"])
+            .args([
+                format!("-disposition:v:{out_index}").as_str(),
+                "attached_pic",
+            ]);
+    }
+
+    if let Some(filter) = deinterlace_filter {
+        let video_filter = deinterlace_video_filter(filter, video_stream.rotation());
+        cmd.args(["-filter:v:0", &video_filter]);
+    }
+
+    // The `rotate` tag is reset for older versions that would copy it over
+    cmd.args(["-c:v:0", "libx264", "-preset", "slow", "-crf", "18"])
+        .args(["-pix_fmt:v:0", "yuv420p"])
+        .args(["-c:a", if audio_is_aac { "copy" } else { "aac" }])
+        .args(["-map_metadata", "0", "-metadata:s:v:0", "rotate=0"])
+        .args(["-movflags", "+faststart"])
+        .arg(&new_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        let _ = fs::remove_file(&new_path).await;
+        return Err(CmdError::Failed("Failed to re-encode video".into(), output.into()).into());
+    }
+
+    if in_place {
+        fs::rename(&new_path, file_path)
+            .await
+            .map_err(ReencodeError::Write)?;
+
+        return Ok(file_path.to_path_buf());
+    }
+
+    if let Err(e) = move_to_trash(file_path) {
+        warn!("Failed to move file {file_path:?} to trash: {e:?}");
+    }
+
+    Ok(new_path)
+}
+
+/// ffmpeg rotates the frames before any of the filters run, which would mix up
+/// the fields of sideways video, so they're turned back for the deinterlacing.
+/// One frame per frame, with the field order taken from the video.
+fn deinterlace_video_filter(filter: &str, rotation: i64) -> String {
+    let deinterlace = format!("{filter}=mode=send_frame:parity=auto:deint=all");
+
+    let (undo, redo) = match rotation {
+        90 => ("transpose=cclock", "transpose=clock"),
+        180 => ("hflip,vflip", "hflip,vflip"),
+        270 => ("transpose=clock", "transpose=cclock"),
+        _ => return deinterlace,
+    };
+
+    format!("{undo},{deinterlace},{redo}")
+}
+
+#[derive(Debug, Error)]
+pub enum ReencodeError {
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("Failed to replace file: {0:?}")]
+    Write(std::io::Error),
+}
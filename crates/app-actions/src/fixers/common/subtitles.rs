@@ -0,0 +1,28 @@
+use app_helpers::ffprobe::FfProbeResult;
+
+/// Subtitle codecs that can be turned into `mov_text`, the only kind mp4 files can have.
+/// Bitmap subtitles (eg. from DVDs and Blu-rays) can't, so they're left out.
+const TEXT_SUBTITLE_CODECS: &[&str] =
+    &["subrip", "srt", "ass", "ssa", "webvtt", "mov_text", "text"];
+
+/// ffmpeg arguments that keep the text subtitles of the file in the mp4 it's turned into
+#[must_use]
+pub fn mp4_subtitle_args(media_info: &FfProbeResult) -> Vec<String> {
+    let maps = media_info
+        .streams
+        .iter()
+        .filter(|s| s.codec_type.as_deref() == Some("subtitle"))
+        .filter(|s| {
+            s.codec_name
+                .as_deref()
+                .is_some_and(|x| TEXT_SUBTITLE_CODECS.contains(&x))
+        })
+        .flat_map(|s| ["-map".to_string(), format!("0:{}", s.index)])
+        .collect::<Vec<_>>();
+
+    if maps.is_empty() {
+        return maps;
+    }
+
+    [maps, vec!["-c:s".to_string(), "mov_text".to_string()]].concat()
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+
+use app_config::Config;
+use app_helpers::ffprobe::{self, Stream};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace};
+
+use crate::fixers::{
+    common::{
+        command::CmdError,
+        reencode::{main_video_stream, reencode_video, ReencodeError},
+        FixRequest, FixResult, FixerError,
+    },
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AutoRotate;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for AutoRotate {
+    fn description(&self) -> &'static str {
+        "Rotates videos that are only shown upright because of their rotation metadata (eg. phone \
+         videos), so they don't end up sideways when the metadata is lost."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let Ok(media_info) = ffprobe::ffprobe_async(&request.file_path).await else {
+            return false;
+        };
+
+        if media_info.format.format_name == "image2" {
+            return false;
+        }
+
+        media_info
+            .streams
+            .iter()
+            .any(|s| s.codec_type.as_deref() == Some("video"))
+    }
+
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        rotate_video(&request.file_path)
+            .await
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+async fn rotate_video(file_path: &Path) -> Result<PathBuf, AutoRotateError> {
+    let media_info = ffprobe::ffprobe_async(file_path).await?;
+
+    let Some(video_stream) = main_video_stream(&media_info) else {
+        trace!(?file_path, "File has no video stream");
+        return Ok(file_path.to_path_buf());
+    };
+
+    let rotation = video_stream.rotation();
+    if rotation == 0 {
+        if has_rotation_flag(video_stream) {
+            debug!(
+                ?file_path,
+                "Video is upright, only clearing its rotation metadata"
+            );
+            return clear_rotation_flag(file_path, video_stream).await;
+        }
+
+        trace!(?file_path, "Video is already upright");
+        return Ok(file_path.to_path_buf());
+    }
+
+    debug!(?file_path, ?rotation, "Rotating video");
+
+    // ffmpeg rotates the frames by itself when re-encoding and leaves out the display matrix.
+    // Interlaced video is left to `Deinterlace`, which runs first and rotates it as well.
+    let new_path = reencode_video(file_path, &media_info, video_stream, None).await?;
+
+    Ok(new_path)
+}
+
+/// Rotation metadata that doesn't turn the video (eg. `rotate=360`),
+/// which tools that only look at the flag could still get wrong
+fn has_rotation_flag(stream: &Stream) -> bool {
+    let has_tag = stream
+        .tags
+        .as_ref()
+        .and_then(|x| x.rotate.as_deref())
+        .is_some_and(|x| x.trim() != "0");
+
+    has_tag
+        || stream
+            .side_data_list
+            .iter()
+            .any(|x| x.rotation.is_some_and(|x| x.abs() >= 1.0))
+}
+
+/// Only the metadata is rewritten, the streams are copied over as they are
+async fn clear_rotation_flag(
+    file_path: &Path,
+    stream: &Stream,
+) -> Result<PathBuf, AutoRotateError> {
+    let extension = file_path
+        .extension()
+        .and_then(|x| x.to_str())
+        .unwrap_or("mp4");
+    let new_path = file_path.with_extension(format!("unrotated.{extension}"));
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    cmd.arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-map", "0", "-c", "copy", "-map_metadata", "0"])
+        .args([format!("-metadata:s:{}", stream.index).as_str(), "rotate=0"])
+        .arg(&new_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        let _ = fs::remove_file(&new_path).await;
+        return Err(
+            CmdError::Failed("Failed to clear rotation metadata".into(), output.into()).into(),
+        );
+    }
+
+    fs::rename(&new_path, file_path)
+        .await
+        .map_err(AutoRotateError::Write)?;
+
+    Ok(file_path.to_path_buf())
+}
+
+#[derive(Debug, Error)]
+pub enum AutoRotateError {
+    #[error(transparent)]
+    FfProbe(#[from] ffprobe::FfProbeError),
+    #[error(transparent)]
+    Reencode(#[from] ReencodeError),
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("Failed to replace file: {0:?}")]
+    Write(std::io::Error),
+}
+
+impl From<AutoRotateError> for FixerError {
+    fn from(val: AutoRotateError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
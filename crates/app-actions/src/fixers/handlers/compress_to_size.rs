@@ -0,0 +1,366 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use app_config::{byte_size::ByteSize, Config};
+use app_helpers::{
+    ffprobe::{self, FfProbeResult},
+    temp_dir::TempDir,
+    trash::move_to_trash,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace, warn};
+
+use crate::fixers::{
+    common::{command::CmdError, FixRequest, FixResult, FixerError},
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+/// Key of the fixer option with the most bytes the file may have,
+/// which takes precedence over the configured size
+pub const COMPRESS_TO_SIZE_OPTION: &str = "target-size";
+
+/// Parts of the size the streams get on each try.
+/// The rest is left for the container and the encoder missing the bitrate.
+const SIZE_MARGINS: &[f64] = &[0.95, 0.85];
+
+/// Videos can't be watched below this bitrate, so they're not compressed that far
+const MIN_VIDEO_BITRATE: u64 = 100_000;
+const MIN_AUDIO_BITRATE: u64 = 32_000;
+const MAX_AUDIO_BITRATE: u64 = 320_000;
+
+/// The tallest the video may be with at least this bitrate,
+/// so low bitrates don't get spread over too many pixels
+const HEIGHT_FOR_BITRATE: &[(u64, u32)] = &[
+    (4_000_000, 1080),
+    (2_000_000, 720),
+    (1_000_000, 540),
+    (600_000, 480),
+    (300_000, 360),
+    (0, 240),
+];
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct CompressToSize;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for CompressToSize {
+    fn description(&self) -> &'static str {
+        "Re-encodes videos and audio that are too big (eg. for Telegram's upload limit) to fit, \
+         lowering the resolution if the bitrate would be too low for it."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let Some(target_size) = target_size(request) else {
+            return false;
+        };
+
+        let is_too_big = fs::metadata(&request.file_path)
+            .await
+            .is_ok_and(|x| x.len() > target_size);
+        if !is_too_big {
+            return false;
+        }
+
+        let Ok(media_info) = ffprobe::ffprobe_async(&request.file_path).await else {
+            return false;
+        };
+
+        // Images and the like can't be compressed to a bitrate
+        if media_info.format.format_name == "image2" {
+            return false;
+        }
+
+        media_info
+            .format
+            .get_duration()
+            .is_some_and(|x| !x.is_zero())
+    }
+
+    /// Options:
+    /// - `target-size`: The most bytes the file may have. Defaults to `--compress-to-size`.
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        let Some(target_size) = target_size(request) else {
+            return Ok(FixResult::new(request.clone(), request.file_path.clone()));
+        };
+
+        compress_file(&request.file_path, target_size)
+            .await
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+fn target_size(request: &FixRequest) -> Option<u64> {
+    request
+        .option::<u64>(COMPRESS_TO_SIZE_OPTION)
+        .or_else(|| {
+            Config::global()
+                .handlers
+                .compress_to_size
+                .map(ByteSize::bytes)
+        })
+        .filter(|x| *x > 0)
+}
+
+async fn compress_file(file_path: &Path, target_size: u64) -> Result<PathBuf, CompressError> {
+    let media_info = ffprobe::ffprobe_async(file_path).await?;
+
+    let duration = media_info
+        .format
+        .get_duration()
+        .filter(|x| !x.is_zero())
+        .ok_or(CompressError::NoDuration)?;
+
+    let has_video = media_info
+        .streams
+        .iter()
+        .any(|s| s.codec_type.as_deref() == Some("video") && s.disposition.attached_pic == 0);
+    let has_audio = media_info
+        .streams
+        .iter()
+        .any(|s| s.codec_type.as_deref() == Some("audio"));
+
+    let extension = match (has_video, has_audio) {
+        (true, _) => "mp4",
+        (false, true) => "mp3",
+        (false, false) => return Err(CompressError::NoMediaStream),
+    };
+    let new_path = match file_path.with_extension(extension) {
+        x if x == file_path => file_path.with_extension(format!("compressed.{extension}")),
+        x => x,
+    };
+
+    for margin in SIZE_MARGINS {
+        let bitrate = total_bitrate(target_size, *margin, duration);
+
+        debug!(
+            ?file_path,
+            ?target_size,
+            ?bitrate,
+            "Compressing file to fit size"
+        );
+
+        let encoded = if has_video {
+            encode_video(file_path, &new_path, &media_info, bitrate, has_audio).await
+        } else {
+            encode_audio(file_path, &new_path, bitrate).await
+        };
+        if let Err(e) = encoded {
+            let _ = fs::remove_file(&new_path).await;
+            return Err(e);
+        }
+
+        let size = fs::metadata(&new_path)
+            .await
+            .map_err(CompressError::Metadata)?
+            .len();
+
+        trace!(?size, ?target_size, "Compressed file");
+
+        if size <= target_size {
+            if let Err(e) = move_to_trash(file_path) {
+                warn!("Failed to move file {file_path:?} to trash: {e:?}");
+            }
+
+            return Ok(new_path);
+        }
+
+        debug!(?size, ?target_size, "Compressed file is still too big");
+    }
+
+    if let Err(e) = fs::remove_file(&new_path).await {
+        warn!(?e, ?new_path, "Failed to remove compressed file");
+    }
+
+    Err(CompressError::TooBig(target_size))
+}
+
+/// Bits per second that the streams together may take up
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn total_bitrate(target_size: u64, margin: f64, duration: Duration) -> u64 {
+    ((target_size * 8) as f64 * margin / duration.as_secs_f64()) as u64
+}
+
+const fn audio_bitrate_for(total_bitrate: u64) -> u64 {
+    match total_bitrate {
+        x if x >= 1_000_000 => 128_000,
+        x if x >= 400_000 => 96_000,
+        _ => 64_000,
+    }
+}
+
+fn height_for(video_bitrate: u64) -> u32 {
+    HEIGHT_FOR_BITRATE
+        .iter()
+        .find(|(min_bitrate, _)| video_bitrate >= *min_bitrate)
+        .map_or(240, |(_, height)| *height)
+}
+
+/// Two passes, so the bitrate is spent where the video needs it
+/// while the whole file still ends up at the wanted size
+async fn encode_video(
+    from_path: &Path,
+    to_path: &Path,
+    media_info: &FfProbeResult,
+    total_bitrate: u64,
+    has_audio: bool,
+) -> Result<(), CompressError> {
+    let audio_bitrate = if has_audio {
+        audio_bitrate_for(total_bitrate)
+    } else {
+        0
+    };
+    let video_bitrate = total_bitrate.saturating_sub(audio_bitrate);
+
+    if video_bitrate < MIN_VIDEO_BITRATE {
+        return Err(CompressError::BitrateTooLow(video_bitrate));
+    }
+
+    let video_stream = media_info
+        .streams
+        .iter()
+        .find(|s| s.codec_type.as_deref() == Some("video") && s.disposition.attached_pic == 0)
+        .ok_or(CompressError::NoMediaStream)?;
+    let source_height = video_stream
+        .height
+        .and_then(|x| u32::try_from(x).ok())
+        .unwrap_or(u32::MAX);
+    // x264 only takes even dimensions
+    let height = height_for(video_bitrate).min(source_height) / 2 * 2;
+
+    trace!(
+        ?video_bitrate,
+        ?audio_bitrate,
+        ?height,
+        "Picked video settings"
+    );
+
+    let pass_dir =
+        TempDir::in_tmp_with_prefix("downloader-hub_compress-").map_err(CompressError::TempDir)?;
+    let pass_log = pass_dir.path().join("pass");
+
+    let video_args = [
+        "-map".to_string(),
+        format!("0:{}", video_stream.index),
+        "-vf".to_string(),
+        format!("scale=-2:{height}"),
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "medium".to_string(),
+        "-b:v".to_string(),
+        video_bitrate.to_string(),
+        "-pix_fmt".to_string(),
+        "yuv420p".to_string(),
+    ];
+
+    for pass in ["1", "2"] {
+        let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+        cmd.arg("-y")
+            .arg("-hide_banner")
+            .args(["-loglevel", "error"])
+            .arg("-i")
+            .arg(from_path)
+            .args(&video_args)
+            .args(["-pass", pass])
+            .arg("-passlogfile")
+            .arg(&pass_log);
+
+        if pass == "1" {
+            cmd.args(["-an", "-f", "null", "-"]);
+        } else {
+            if has_audio {
+                cmd.args(["-map", "0:a:0", "-c:a", "aac", "-ac", "2"])
+                    .args(["-b:a", &audio_bitrate.to_string()]);
+            }
+
+            cmd.args(["-map_metadata", "0", "-movflags", "+faststart"])
+                .arg(to_path);
+        }
+
+        cmd.kill_on_drop(true);
+
+        debug!(?cmd, "Running ffmpeg command");
+
+        let output = cmd.output().await.map_err(CmdError::Run)?;
+
+        if !output.status.success() {
+            return Err(CmdError::Failed(
+                format!("Failed to compress video (pass {pass})"),
+                output.into(),
+            )
+            .into());
+        }
+    }
+
+    Ok(())
+}
+
+async fn encode_audio(
+    from_path: &Path,
+    to_path: &Path,
+    total_bitrate: u64,
+) -> Result<(), CompressError> {
+    let audio_bitrate = total_bitrate.min(MAX_AUDIO_BITRATE);
+
+    if audio_bitrate < MIN_AUDIO_BITRATE {
+        return Err(CompressError::BitrateTooLow(audio_bitrate));
+    }
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    cmd.arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(from_path)
+        .args(["-map", "0:a:0", "-c:a", "libmp3lame"])
+        .args(["-b:a", &audio_bitrate.to_string()])
+        .args(["-map_metadata", "0"])
+        .arg(to_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() {
+        return Err(CmdError::Failed("Failed to compress audio".into(), output.into()).into());
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Error)]
+pub enum CompressError {
+    #[error(transparent)]
+    FfProbe(#[from] ffprobe::FfProbeError),
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("File has no duration to spread the size over")]
+    NoDuration,
+    #[error("File has no video or audio")]
+    NoMediaStream,
+    #[error("File would need a bitrate of {0} bits/s to fit, which is too low")]
+    BitrateTooLow(u64),
+    #[error("Failed to compress file to fit in {0} bytes")]
+    TooBig(u64),
+    #[error("Failed to create temporary directory: {0:?}")]
+    TempDir(std::io::Error),
+    #[error("Failed to get file size: {0:?}")]
+    Metadata(std::io::Error),
+}
+
+impl From<CompressError> for FixerError {
+    fn from(val: CompressError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
@@ -0,0 +1,118 @@
+use std::path::{Path, PathBuf};
+
+use app_helpers::ffprobe;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, trace};
+
+use crate::fixers::{
+    common::{
+        reencode::{is_interlaced, main_video_stream, reencode_video, ReencodeError},
+        FixRequest, FixResult, FixerError,
+    },
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Deinterlace;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeinterlaceFilter {
+    /// Sharper than `yadif`, but not in very old ffmpeg versions
+    #[default]
+    Bwdif,
+    Yadif,
+}
+impl DeinterlaceFilter {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Bwdif => "bwdif",
+            Self::Yadif => "yadif",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct DeinterlaceOptions {
+    /// The ffmpeg filter to deinterlace with. Defaults to `bwdif`
+    pub filter: Option<DeinterlaceFilter>,
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for Deinterlace {
+    fn description(&self) -> &'static str {
+        "Deinterlaces interlaced videos (eg. TV rips and old uploads), so they don't show combing \
+         lines."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let Ok(media_info) = ffprobe::ffprobe_async(&request.file_path).await else {
+            return false;
+        };
+
+        if media_info.format.format_name == "image2" {
+            return false;
+        }
+
+        media_info
+            .streams
+            .iter()
+            .any(|s| s.codec_type.as_deref() == Some("video"))
+    }
+
+    /// Options:
+    /// - `filter`: `bwdif` or `yadif`. Defaults to `bwdif`.
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        let opts = request.options::<DeinterlaceOptions>().unwrap_or_default();
+
+        deinterlace_video(&request.file_path, &opts)
+            .await
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+async fn deinterlace_video(
+    file_path: &Path,
+    opts: &DeinterlaceOptions,
+) -> Result<PathBuf, DeinterlaceError> {
+    let media_info = ffprobe::ffprobe_async(file_path).await?;
+
+    let Some(video_stream) = main_video_stream(&media_info) else {
+        trace!(?file_path, "File has no video stream");
+        return Ok(file_path.to_path_buf());
+    };
+
+    if !is_interlaced(video_stream) {
+        trace!(?file_path, field_order = ?video_stream.field_order, "Video isn't interlaced");
+        return Ok(file_path.to_path_buf());
+    }
+
+    let filter = opts.filter.unwrap_or_default();
+
+    debug!(?file_path, field_order = ?video_stream.field_order, ?filter, "Deinterlacing video");
+
+    // Rotated video gets turned upright in the same pass,
+    // so `AutoRotate` doesn't have to re-encode it again
+    let new_path =
+        reencode_video(file_path, &media_info, video_stream, Some(filter.name())).await?;
+
+    Ok(new_path)
+}
+
+#[derive(Debug, Error)]
+pub enum DeinterlaceError {
+    #[error(transparent)]
+    FfProbe(#[from] ffprobe::FfProbeError),
+    #[error(transparent)]
+    Reencode(#[from] ReencodeError),
+}
+
+impl From<DeinterlaceError> for FixerError {
+    fn from(val: DeinterlaceError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
@@ -0,0 +1,229 @@
+use std::{
+    ffi::OsStr,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::{
+    ffprobe::{self, FfProbeResult},
+    trash::move_to_trash,
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace, warn};
+
+use crate::fixers::{
+    common::{command::CmdError, subtitles::mp4_subtitle_args, FixRequest, FixResult, FixerError},
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+/// Containers that get remuxed into mp4 if their codecs allow it
+const REMUXABLE_FORMATS: &[&str] = &["matroska", "webm", "flv", "mpegts", "avi"];
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Faststart;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for Faststart {
+    fn description(&self) -> &'static str {
+        "Makes videos stream instantly by moving the mp4 index to the front of the file and \
+         remuxing other containers into mp4 when the codecs allow it, without re-encoding."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let Ok(media_info) = ffprobe::ffprobe_async(&request.file_path).await else {
+            return false;
+        };
+
+        media_info
+            .streams
+            .iter()
+            .any(|s| s.codec_type.as_deref() == Some("video"))
+    }
+
+    /// Options:
+    ///
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        make_streamable(&request.file_path)
+            .await
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+async fn make_streamable(file_path: &Path) -> Result<PathBuf, FaststartError> {
+    let media_info = ffprobe::ffprobe_async(file_path).await?;
+
+    let is_mp4 = media_info.format.format_name.split(',').any(|x| x == "mp4")
+        && path_has_extension(file_path, "mp4");
+
+    if is_mp4 {
+        let path = file_path.to_path_buf();
+        let index_at_end = tokio::task::spawn_blocking(move || moov_after_mdat(&path))
+            .await?
+            .map_err(FaststartError::Read)?;
+
+        if !index_at_end {
+            trace!(?file_path, "File is already streamable");
+            return Ok(file_path.to_path_buf());
+        }
+
+        debug!(?file_path, "Moving mp4 index to the front");
+
+        return remux_into_mp4(file_path, &media_info).await;
+    }
+
+    let is_remuxable = media_info
+        .format
+        .format_name
+        .split(',')
+        .any(|x| REMUXABLE_FORMATS.contains(&x));
+
+    if is_remuxable && has_mp4_friendly_codecs(&media_info) {
+        debug!(?file_path, "Remuxing into mp4");
+
+        return remux_into_mp4(file_path, &media_info).await;
+    }
+
+    trace!(
+        ?file_path,
+        "File can't be made streamable without re-encoding"
+    );
+
+    Ok(file_path.to_path_buf())
+}
+
+/// Only codecs that the media formats fixer keeps as-is,
+/// so the file doesn't get re-encoded right after anyway
+fn has_mp4_friendly_codecs(media_info: &FfProbeResult) -> bool {
+    media_info.streams.iter().all(|s| {
+        let codec = s.codec_name.as_deref().unwrap_or_default();
+
+        match s.codec_type.as_deref() {
+            Some("video") => codec == "h264",
+            Some("audio") => codec == "aac",
+            _ => true,
+        }
+    })
+}
+
+/// Walks the top-level mp4 boxes to check whether the `moov` box (the index)
+/// comes after the `mdat` box (the media data).
+/// Players have to download the whole file before playing it in that case.
+fn moov_after_mdat(file_path: &Path) -> std::io::Result<bool> {
+    let mut file = File::open(file_path)?;
+    let file_len = file.metadata()?.len();
+    let mut pos = 0;
+
+    while pos + 8 <= file_len {
+        file.seek(SeekFrom::Start(pos))?;
+
+        let mut header = [0; 8];
+        file.read_exact(&mut header)?;
+
+        match &header[4..8] {
+            b"moov" => return Ok(false),
+            b"mdat" => return Ok(true),
+            _ => {}
+        }
+
+        let size = match u32::from_be_bytes([header[0], header[1], header[2], header[3]]) {
+            // The box extends to the end of the file
+            0 => return Ok(false),
+            // 64-bit size right after the header
+            1 => {
+                let mut large_size = [0; 8];
+                file.read_exact(&mut large_size)?;
+                u64::from_be_bytes(large_size)
+            }
+            x => u64::from(x),
+        };
+
+        if size < 8 {
+            break;
+        }
+
+        pos += size;
+    }
+
+    Ok(false)
+}
+
+async fn remux_into_mp4(
+    file_path: &Path,
+    media_info: &FfProbeResult,
+) -> Result<PathBuf, FaststartError> {
+    let in_place = path_has_extension(file_path, "mp4");
+    let new_path = if in_place {
+        file_path.with_extension("faststart.mp4")
+    } else {
+        file_path.with_extension("mp4")
+    };
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let cmd = cmd
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        // `V` leaves out cover art, which would end up as a second video track
+        .args(["-map", "0:V", "-map", "0:a?", "-c", "copy"])
+        .args(mp4_subtitle_args(media_info))
+        .args(["-map_metadata", "0", "-movflags", "+faststart"])
+        .arg(&new_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        let _ = fs::remove_file(&new_path).await;
+        return Err(CmdError::Failed("Failed to remux into mp4".into(), output.into()).into());
+    }
+
+    if in_place {
+        fs::rename(&new_path, file_path)
+            .await
+            .map_err(FaststartError::Write)?;
+
+        return Ok(file_path.to_path_buf());
+    }
+
+    if let Err(e) = move_to_trash(file_path) {
+        warn!("Failed to move file {file_path:?} to trash: {e:?}");
+    }
+
+    Ok(new_path)
+}
+
+fn path_has_extension(path: &Path, wanted_extension: &str) -> bool {
+    path.extension()
+        .and_then(OsStr::to_str)
+        .is_some_and(|x| x.eq_ignore_ascii_case(wanted_extension))
+}
+
+#[derive(Debug, Error)]
+pub enum FaststartError {
+    #[error(transparent)]
+    FfProbe(#[from] ffprobe::FfProbeError),
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("Failed to read file: {0:?}")]
+    Read(std::io::Error),
+    #[error("Failed to replace file: {0:?}")]
+    Write(std::io::Error),
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
+}
+
+impl From<FaststartError> for FixerError {
+    fn from(val: FaststartError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
@@ -0,0 +1,207 @@
+use std::path::{Path, PathBuf};
+
+use app_config::Config;
+use app_helpers::{ffprobe, trash::move_to_trash};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace, warn};
+
+use crate::fixers::{
+    common::{command::CmdError, FixRequest, FixResult, FixerError},
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+/// GIFs smaller than this aren't worth converting
+const DEFAULT_MIN_SIZE: u64 = 1024 * 1024;
+/// The video has to be at most this fraction of the GIF's size to replace it
+const DEFAULT_SIZE_RATIO: f64 = 0.75;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct GifToVideo;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GifVideoFormat {
+    #[default]
+    Mp4,
+    Webm,
+}
+impl GifVideoFormat {
+    const fn extension(self) -> &'static str {
+        match self {
+            Self::Mp4 => "mp4",
+            Self::Webm => "webm",
+        }
+    }
+
+    const fn codec_args(self) -> &'static [&'static str] {
+        match self {
+            Self::Mp4 => &[
+                "-c:v",
+                "libx264",
+                "-preset",
+                "slow",
+                "-pix_fmt",
+                "yuv420p",
+                "-movflags",
+                "+faststart",
+            ],
+            Self::Webm => &[
+                "-c:v",
+                "libvpx-vp9",
+                "-b:v",
+                "0",
+                "-crf",
+                "32",
+                "-pix_fmt",
+                "yuv420p",
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct GifToVideoOptions {
+    /// Only GIFs of at least this many bytes are converted. Defaults to 1 MiB
+    pub min_size: Option<u64>,
+    /// The video replaces the GIF only if it's at most this fraction of the GIF's size.
+    /// Defaults to `0.75`
+    pub size_ratio: Option<f64>,
+    /// The format to convert into. Defaults to `mp4`
+    pub format: Option<GifVideoFormat>,
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for GifToVideo {
+    fn description(&self) -> &'static str {
+        "Converts large GIFs into videos if that makes them significantly smaller."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let opts = request.options::<GifToVideoOptions>().unwrap_or_default();
+        let min_size = opts.min_size.unwrap_or(DEFAULT_MIN_SIZE);
+
+        let Ok(metadata) = fs::metadata(&request.file_path).await else {
+            return false;
+        };
+
+        if metadata.len() < min_size {
+            return false;
+        }
+
+        let Ok(media_info) = ffprobe::ffprobe_async(&request.file_path).await else {
+            return false;
+        };
+
+        media_info
+            .streams
+            .iter()
+            .any(|s| s.codec_name.as_deref() == Some("gif"))
+    }
+
+    /// Options:
+    /// - `min-size`: Minimum GIF size in bytes to try converting. Defaults to 1 MiB.
+    /// - `size-ratio`: Maximum size of the video compared to the GIF to keep it. Defaults to `0.75`.
+    /// - `format`: `mp4` or `webm`. Defaults to `mp4`.
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        let opts = request.options::<GifToVideoOptions>().unwrap_or_default();
+
+        convert_gif(&request.file_path, &opts)
+            .await
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+async fn convert_gif(
+    file_path: &Path,
+    opts: &GifToVideoOptions,
+) -> Result<PathBuf, GifToVideoError> {
+    let format = opts.format.unwrap_or_default();
+    let size_ratio = opts.size_ratio.unwrap_or(DEFAULT_SIZE_RATIO);
+
+    let video_path = match file_path.with_extension(format.extension()) {
+        x if x == file_path => file_path.with_extension(format!("video.{}", format.extension())),
+        x => x,
+    };
+
+    debug!(?file_path, ?video_path, "Converting GIF into video");
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let cmd = cmd
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-vf", "scale=trunc(iw/2)*2:trunc(ih/2)*2"])
+        .args(format.codec_args())
+        .args(["-map_metadata", "-1"])
+        .arg("-an")
+        .arg(&video_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() {
+        let _ = fs::remove_file(&video_path).await;
+        return Err(
+            CmdError::Failed("Failed to convert GIF into video".into(), output.into()).into(),
+        );
+    }
+
+    let gif_size = fs::metadata(file_path)
+        .await
+        .map_err(GifToVideoError::Metadata)?
+        .len();
+    let video_size = fs::metadata(&video_path)
+        .await
+        .map_err(GifToVideoError::Metadata)?
+        .len();
+
+    trace!(?gif_size, ?video_size, "Converted GIF into video");
+
+    #[allow(clippy::cast_precision_loss)]
+    let keep_video = (video_size as f64) <= (gif_size as f64) * size_ratio;
+
+    if !keep_video {
+        debug!(
+            ?gif_size,
+            ?video_size,
+            "Video isn't small enough, keeping the GIF"
+        );
+
+        if let Err(e) = fs::remove_file(&video_path).await {
+            warn!(?e, ?video_path, "Failed to remove converted video");
+        }
+
+        return Ok(file_path.to_path_buf());
+    }
+
+    debug!(?gif_size, ?video_size, "Replacing GIF with video");
+
+    if let Err(e) = move_to_trash(file_path) {
+        warn!("Failed to move file {file_path:?} to trash: {e:?}");
+    }
+
+    Ok(video_path)
+}
+
+#[derive(Debug, Error)]
+pub enum GifToVideoError {
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("Failed to get file size: {0:?}")]
+    Metadata(std::io::Error),
+}
+
+impl From<GifToVideoError> for FixerError {
+    fn from(val: GifToVideoError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
@@ -0,0 +1,130 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use app_config::Config;
+use app_helpers::{file_type, trash::move_to_trash};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::process::Command;
+use tracing::{debug, trace, warn};
+
+use crate::fixers::{
+    common::{command::CmdError, FixRequest, FixResult, FixerError},
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+const JPEG_QUALITY: u8 = 92;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct HeifToJpeg;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for HeifToJpeg {
+    fn can_run(&self) -> bool {
+        Config::global()
+            .dependency_paths
+            .imagemagick_path()
+            .is_some()
+    }
+
+    fn description(&self) -> &'static str {
+        "Converts HEIC/HEIF/AVIF images into JPEG (or PNG if they're transparent), so they can be \
+         shown everywhere."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let path = request.file_path.clone();
+        tokio::task::spawn_blocking(move || file_type::is_heif_image(&path).ok())
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        convert_image(&request.file_path)
+            .await
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+async fn convert_image(file_path: &Path) -> Result<PathBuf, HeifToJpegError> {
+    let imagemagick_path = Config::global()
+        .dependency_paths
+        .imagemagick_path()
+        .ok_or(HeifToJpegError::ImagemagickNotFound)?;
+
+    // Only the primary image, HEIF files can also hold thumbnails and depth maps
+    let input = {
+        let mut input = OsString::from(file_path);
+        input.push("[0]");
+        input
+    };
+
+    let is_opaque = {
+        let mut cmd = Command::new(&imagemagick_path);
+        cmd.arg(&input)
+            .args(["-format", "%[opaque]"])
+            .arg("info:-")
+            .stdin(Stdio::null())
+            .kill_on_drop(true);
+
+        trace!(?cmd, "Checking image transparency");
+
+        let output = cmd.output().await.map_err(CmdError::Run)?;
+        if !output.status.success() {
+            return Err(CmdError::Failed("Failed to read image".into(), output.into()).into());
+        }
+
+        !String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .eq_ignore_ascii_case("false")
+    };
+
+    let extension = if is_opaque { "jpg" } else { "png" };
+    let new_path = match file_path.with_extension(extension) {
+        // Misnamed files shouldn't get overwritten and then trashed
+        x if x == file_path => file_path.with_extension(format!("converted.{extension}")),
+        x => x,
+    };
+
+    let mut cmd = Command::new(imagemagick_path);
+    cmd.arg(&input).arg("-auto-orient");
+    if is_opaque {
+        cmd.args(["-quality", &JPEG_QUALITY.to_string()]);
+    }
+    cmd.arg(&new_path).stdin(Stdio::null()).kill_on_drop(true);
+
+    debug!(?cmd, "Running command to convert image");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        return Err(CmdError::Failed("Failed to convert image".into(), output.into()).into());
+    }
+
+    if let Err(e) = move_to_trash(file_path) {
+        warn!(file = ?file_path, ?e, "Failed to move file to trash");
+    }
+
+    Ok(new_path)
+}
+
+#[derive(Debug, Error)]
+pub enum HeifToJpegError {
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("Imagemagick not found")]
+    ImagemagickNotFound,
+}
+
+impl From<HeifToJpegError> for FixerError {
+    fn from(val: HeifToJpegError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
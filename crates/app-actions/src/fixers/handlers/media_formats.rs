@@ -4,6 +4,7 @@ use anyhow::anyhow;
 use app_config::Config;
 use app_helpers::{
     ffprobe::{self, FfProbeResult, Stream},
+    file_type,
     id::time_thread_id,
     temp_dir::TempDir,
     trash::move_to_trash,
@@ -13,11 +14,14 @@ use image::ColorType;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tokio::{fs, process::Command};
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 
-use crate::fixers::{
-    common::{FixRequest, FixResult, FixerError},
-    Fixer, FixerReturn, IntoFixerReturn,
+use crate::{
+    fixers::{
+        common::{FixRequest, FixResult, FixerError},
+        Fixer, FixerReturn, IntoFixerReturn,
+    },
+    media_policy::{resolve_media_policy, MediaPolicy, MEDIA_POLICY_OPTION},
 };
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -31,7 +35,8 @@ impl Fixer for MediaFormats {
     }
 
     /// Options:
-    ///
+    /// - `media-policy`: Overrides the global media policy.
+    ///   Videos taller than the allowed height get scaled down.
     async fn run(&self, request: &FixRequest) -> FixerReturn {
         convert_into_preferred_formats(request.clone()).await
     }
@@ -41,7 +46,9 @@ async fn convert_into_preferred_formats(request: FixRequest) -> FixerReturn {
     let file_path = request.file_path.clone();
     debug!("Checking if {file_path:?} has unwanted formats");
 
-    check_and_fix_file(&file_path)
+    let policy = resolve_media_policy(request.option::<MediaPolicy>(MEDIA_POLICY_OPTION));
+
+    check_and_fix_file(&file_path, &policy)
         .await
         .map(|p| {
             debug!("File {file_path:?} done being converted");
@@ -50,7 +57,31 @@ async fn convert_into_preferred_formats(request: FixRequest) -> FixerReturn {
         .map_err(FixerError::failed_fix)
 }
 
-async fn check_and_fix_file(file_path: &Path) -> Result<PathBuf, MediaFormatsError> {
+async fn check_and_fix_file(
+    file_path: &Path,
+    policy: &MediaPolicy,
+) -> Result<PathBuf, MediaFormatsError> {
+    let image_kind = {
+        let path = file_path.to_path_buf();
+        tokio::task::spawn_blocking(move || ImageKind::of(&path)).await?
+    };
+
+    match image_kind {
+        ImageKind::Animated => {
+            return convert_animated_image(file_path)
+                .await
+                .map_err(MediaFormatsError::CodecFix);
+        }
+        ImageKind::HeifStill => {
+            trace!(
+                ?file_path,
+                "Leaving still HEIF image to be converted into an image instead of a video"
+            );
+            return Ok(file_path.to_path_buf());
+        }
+        ImageKind::Other => {}
+    }
+
     let file_format_info = ffprobe::ffprobe_async(file_path).await?;
 
     trace!(
@@ -87,6 +118,30 @@ async fn check_and_fix_file(file_path: &Path) -> Result<PathBuf, MediaFormatsErr
         file_stream_codec = file_stream_codec
     );
 
+    if is_video_codec(file_stream_codec) {
+        if let Some(max_height) = policy.max_video_height {
+            let height = file_media_stream
+                .height
+                .and_then(|x| u32::try_from(x).ok())
+                .unwrap_or_default();
+
+            if policy.exceeds_height(height) {
+                debug!(
+                    ?height,
+                    ?max_height,
+                    "Video is taller than allowed, scaling down"
+                );
+
+                return transcode_media_into(
+                    file_path,
+                    &TranscodeInfo::mp4().with_max_height(max_height),
+                )
+                .await
+                .map_err(MediaFormatsError::CodecFix);
+            }
+        }
+    }
+
     let handler = CODEC_HANDLERS
         .iter()
         .find(|h| (h.can_handle)(file_stream_codec, &file_media_stream));
@@ -105,12 +160,105 @@ async fn check_and_fix_file(file_path: &Path) -> Result<PathBuf, MediaFormatsErr
     ))
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImageKind {
+    /// Animated WebP or AVIF, which won't play in most places
+    Animated,
+    /// HEIC/HEIF/AVIF still images, which ffmpeg would turn into single frame videos
+    HeifStill,
+    Other,
+}
+impl ImageKind {
+    fn of(file_path: &Path) -> Self {
+        if file_type::is_animated_image(file_path).unwrap_or_default() {
+            Self::Animated
+        } else if file_type::is_heif_image(file_path).unwrap_or_default() {
+            Self::HeifStill
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Converts the animation into an mp4.
+///
+/// Older ffmpeg versions can't decode animated `WebP` images,
+/// so Imagemagick turns those into a GIF instead.
+async fn convert_animated_image(file_path: &Path) -> anyhow::Result<PathBuf> {
+    let mp4_error = if ffmpeg_decodes_animation(file_path).await {
+        trace!("Converting animated image {file_path:?} into mp4");
+
+        match transcode_media_into(
+            file_path,
+            &TranscodeInfo::mp4().with_additional_args(["-pix_fmt", "yuv420p"]),
+        )
+        .await
+        {
+            Ok(x) => return Ok(x),
+            Err(e) => e,
+        }
+    } else {
+        anyhow!("ffmpeg can't decode the frames of {file_path:?}")
+    };
+
+    let Some(imagemagick_path) = Config::global().dependency_paths.imagemagick_path() else {
+        return Err(mp4_error);
+    };
+
+    warn!(
+        ?mp4_error,
+        "Failed to convert animated image {file_path:?} into mp4, converting into gif instead"
+    );
+
+    let new_file_path = file_path.with_extension("gif");
+
+    let mut cmd = Command::new(imagemagick_path);
+    let cmd = cmd
+        .arg(file_path)
+        .arg("-coalesce")
+        .arg(&new_file_path)
+        .kill_on_drop(true);
+    debug!("Running `imagemagick' command: {cmd:?}");
+
+    let output = cmd.output().await?;
+    if !output.status.success() || !new_file_path.exists() {
+        return Err(anyhow!(
+            "Failed transforming {file_path:?} into gif: {stderr}",
+            stderr = String::from_utf8_lossy(&output.stderr).trim(),
+        ));
+    }
+
+    if let Err(e) = move_to_trash(file_path) {
+        debug!("Failed to delete {path:?}: {e:?}", path = file_path);
+    }
+
+    Ok(new_file_path)
+}
+
+/// ffmpeg reads a single frame (or none) of animations it can't decode
+async fn ffmpeg_decodes_animation(file_path: &Path) -> bool {
+    let config = ffprobe::FfprobeConfig::builder().count_frames(true).build();
+
+    ffprobe::ffprobe_config_async(config, file_path)
+        .await
+        .ok()
+        .and_then(|x| {
+            get_stream_of_type(&x, "video")?
+                .nb_read_frames
+                .as_deref()?
+                .parse::<u64>()
+                .ok()
+        })
+        .is_some_and(|frames| frames > 1)
+}
+
 #[derive(Debug, Clone, PartialEq, Default)]
 struct TranscodeInfo {
     extension: &'static str,
     video_codec: Option<&'static str>,
     audio_codec: Option<&'static str>,
     additional_args: Vec<&'static str>,
+    max_height: Option<u32>,
 }
 
 impl TranscodeInfo {
@@ -131,6 +279,11 @@ impl TranscodeInfo {
         self
     }
 
+    const fn with_max_height(mut self, max_height: u32) -> Self {
+        self.max_height = Some(max_height);
+        self
+    }
+
     fn with_additional_args<I>(mut self, args: I) -> Self
     where
         I: IntoIterator<Item = &'static str>,
@@ -139,6 +292,13 @@ impl TranscodeInfo {
         self
     }
 
+    fn video_filter(&self) -> String {
+        self.max_height.map_or_else(
+            || "scale=ceil(iw/2)*2:ceil(ih/2)*2".to_string(),
+            |max_height| format!("scale=-2:{max_height}"),
+        )
+    }
+
     // Default codecs
 
     fn mp3() -> Self {
@@ -149,7 +309,7 @@ impl TranscodeInfo {
         Self::new("mp4")
             .with_video_codec("libx264")
             .with_audio_codec("aac")
-            .with_additional_args(["-map_metadata", "-1"])
+            .with_additional_args(["-map_metadata", "-1", "-movflags", "+faststart"])
     }
 
     fn jpg() -> Self {
@@ -215,7 +375,7 @@ async fn transcode_media_into(
         .arg("-i")
         .arg(&cache_from_path)
         .args(["-max_muxing_queue_size", "1024"])
-        .args(["-vf", "scale=ceil(iw/2)*2:ceil(ih/2)*2"])
+        .args(["-vf", &to_format.video_filter()])
         .args(["-b:a", "256k"])
         .args(["-preset", "slow"]);
 
@@ -313,6 +473,10 @@ fn file_name_with_suffix_extension(path: &Path, suffix: &str) -> Option<PathBuf>
         .and_then(|p| p.file_name().map(PathBuf::from))
 }
 
+fn is_video_codec(codec: &str) -> bool {
+    matches!(codec, "h264" | "mpeg4" | "vp8" | "vp9" | "av1" | "hevc")
+}
+
 fn get_stream_of_type<'a>(
     file_format_info: &'a FfProbeResult,
     stream_type: &'a str,
@@ -323,7 +487,7 @@ fn get_stream_of_type<'a>(
         .find(|s| s.codec_type.as_deref().is_some_and(|x| x == stream_type))
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone)]
 struct CodecHandler {
     pub can_handle: fn(&str, &Stream) -> bool,
     pub handle: fn(FfProbeResult, Stream) -> BoxFuture<'static, anyhow::Result<PathBuf>>,
@@ -368,7 +532,7 @@ const CODEC_HANDLERS: &[CodecHandler] = &[
                     .is_some_and(|vcodec| vcodec == "h264");
 
                 let audio_codec_ok =
-                    get_stream_of_type(&file_format_info, "audio").map_or(true, |audio_stream| {
+                    get_stream_of_type(&file_format_info, "audio").is_none_or(|audio_stream| {
                         audio_stream
                             .codec_name
                             .as_ref()
@@ -464,6 +628,8 @@ const CODEC_HANDLERS: &[CodecHandler] = &[
 pub enum MediaFormatsError {
     #[error(transparent)]
     FfProbeError(#[from] ffprobe::FfProbeError),
+    #[error(transparent)]
+    Join(#[from] tokio::task::JoinError),
     #[error("Failed to get media stream of {0:?}")]
     NoMediaStream(PathBuf),
     #[error("Failed to get codec of {0:?}")]
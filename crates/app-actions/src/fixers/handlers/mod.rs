@@ -1,11 +1,21 @@
+pub mod auto_rotate;
+pub mod compress_to_size;
 pub mod crop_image;
 pub mod crop_video_bars;
+pub mod deinterlace;
+pub mod faststart;
 pub mod file_extensions;
 pub mod file_name;
+pub mod gif_to_video;
+pub mod heif_to_jpeg;
 pub mod media_formats;
+pub mod repair_media;
+pub mod strip_metadata;
+pub mod tag_audio;
 
 use std::sync::Arc;
 
+use app_config::Config;
 use once_cell::sync::Lazy;
 
 use crate::fixers::Fixer;
@@ -20,14 +30,27 @@ fn all_fixers() -> Vec<FixerInstance> {
     vec![
         Arc::new(file_extensions::FileExtension),
         Arc::new(file_name::FileName),
+        Arc::new(repair_media::RepairMedia),
+        Arc::new(deinterlace::Deinterlace),
+        Arc::new(auto_rotate::AutoRotate),
+        Arc::new(faststart::Faststart),
         Arc::new(media_formats::MediaFormats),
+        Arc::new(gif_to_video::GifToVideo),
+        Arc::new(heif_to_jpeg::HeifToJpeg),
         Arc::new(crop_video_bars::CropVideoBars),
         Arc::new(crop_image::CropImage),
+        Arc::new(strip_metadata::StripMetadata),
+        Arc::new(tag_audio::TagAudio),
+        Arc::new(compress_to_size::CompressToSize),
     ]
 }
 
 fn available_fixers() -> Vec<FixerInstance> {
-    all_fixers().into_iter().filter(|f| f.can_run()).collect()
+    all_fixers()
+        .into_iter()
+        .filter(|f| !Config::global().handlers.is_fixer_disabled(f.name()))
+        .filter(|f| f.can_run())
+        .collect()
 }
 
 fn enabled_fixers() -> Vec<FixerInstance> {
@@ -0,0 +1,211 @@
+use std::{
+    ffi::OsStr,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::{ffprobe, trash::move_to_trash};
+use serde::{Deserialize, Serialize};
+use tokio::{fs, process::Command};
+use tracing::{debug, trace, warn};
+
+use crate::fixers::{
+    common::{command::CmdError, FixRequest, FixResult, FixerError},
+    Fixer, FixerReturn,
+};
+
+/// Maximum number of ffmpeg error lines kept in the error message
+const MAX_REPORTED_ERRORS: usize = 5;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RepairMedia;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct RepairMediaOptions {
+    /// Decode every frame instead of only reading the packets.
+    /// Finds broken frames as well, but takes about as long as playing the file.
+    #[serde(default)]
+    pub full_decode: bool,
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for RepairMedia {
+    fn description(&self) -> &'static str {
+        "Checks that media files decode cleanly and tries to repair truncated or misflagged ones \
+         by remuxing them into a fresh container."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        let Ok(media_info) = ffprobe::ffprobe_async(&request.file_path).await else {
+            return false;
+        };
+
+        if media_info.format.format_name == "image2" {
+            return false;
+        }
+
+        media_info.streams.iter().any(|s| {
+            s.codec_type
+                .as_deref()
+                .is_some_and(|x| matches!(x, "video" | "audio"))
+        })
+    }
+
+    /// Options:
+    /// - `full-decode`: decode every frame instead of only reading the packets.
+    ///   Defaults to `false`.
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        let file_path = &request.file_path;
+        let full_decode = request
+            .options::<RepairMediaOptions>()
+            .unwrap_or_default()
+            .full_decode;
+
+        let errors = decode_errors(file_path, full_decode).await?;
+
+        if errors.is_empty() {
+            trace!(?file_path, "File decodes cleanly");
+            return Ok(FixResult::new(request.clone(), file_path.clone()));
+        }
+
+        debug!(
+            ?file_path,
+            ?errors,
+            "File has decode errors, trying to repair"
+        );
+
+        let repaired_path = match remux(file_path).await {
+            Ok(x) => x,
+            Err(e) => {
+                warn!(?e, ?file_path, "Failed to remux file");
+                return Err(corrupt_file(file_path, &errors));
+            }
+        };
+
+        let repaired_errors = decode_errors(&repaired_path, full_decode).await?;
+
+        if !repaired_errors.is_empty() {
+            debug!(?repaired_errors, "Repaired file still has decode errors");
+
+            if let Err(e) = move_to_trash(&repaired_path) {
+                warn!("Failed to move file {repaired_path:?} to trash: {e:?}");
+            }
+
+            return Err(corrupt_file(file_path, &repaired_errors));
+        }
+
+        debug!(?file_path, ?repaired_path, "Repaired file");
+
+        if let Err(e) = move_to_trash(file_path) {
+            warn!("Failed to move file {file_path:?} to trash: {e:?}");
+        }
+
+        // The repaired file takes the place of the original
+        if let Err(e) = fs::rename(&repaired_path, file_path).await {
+            warn!(?e, ?repaired_path, "Failed to rename repaired file");
+            return Ok(FixResult::new(request.clone(), repaired_path));
+        }
+
+        Ok(FixResult::new(request.clone(), file_path.clone()))
+    }
+}
+
+/// Reads the whole file without writing anything and returns the errors ffmpeg ran into.
+///
+/// Only the packets are read unless `full_decode` is set, which is enough to find
+/// truncated files and broken containers without the time decoding every frame takes.
+async fn decode_errors(file_path: &Path, full_decode: bool) -> Result<Vec<String>, CmdError> {
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let mut cmd = cmd
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-map", "0:v?", "-map", "0:a?"]);
+
+    if !full_decode {
+        cmd = cmd.args(["-c", "copy"]);
+    }
+
+    let cmd = cmd.args(["-f", "null", "-"]).kill_on_drop(true);
+
+    debug!(?cmd, "Running decode check");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    let mut errors = String::from_utf8_lossy(&output.stderr)
+        .lines()
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() && !output.status.success() {
+        errors.push(format!(
+            "ffmpeg exited with status {:?}",
+            output.status.code()
+        ));
+    }
+
+    Ok(errors)
+}
+
+/// Copies the streams into a fresh container, which fixes broken indexes,
+/// wrong durations and `moov` atoms at the end of the file
+async fn remux(file_path: &Path) -> Result<PathBuf, CmdError> {
+    let extension = file_path
+        .extension()
+        .and_then(OsStr::to_str)
+        .unwrap_or("mp4");
+    let new_path = file_path.with_extension(format!("repaired.{extension}"));
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let cmd = cmd
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .args(["-err_detect", "ignore_err"])
+        .args(["-fflags", "+genpts+discardcorrupt"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-map", "0", "-c", "copy"])
+        .args(["-map_metadata", "0", "-movflags", "+faststart"])
+        .arg(&new_path)
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Remuxing file");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        let _ = fs::remove_file(&new_path).await;
+        return Err(CmdError::Failed(
+            "Failed to remux file".into(),
+            output.into(),
+        ));
+    }
+
+    Ok(new_path)
+}
+
+fn corrupt_file(file_path: &Path, errors: &[String]) -> FixerError {
+    let mut message = errors
+        .iter()
+        .take(MAX_REPORTED_ERRORS)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("; ");
+
+    if errors.len() > MAX_REPORTED_ERRORS {
+        let _ = write!(
+            message,
+            " (and {} more)",
+            errors.len() - MAX_REPORTED_ERRORS
+        );
+    }
+
+    FixerError::CorruptFile(file_path.to_path_buf(), message)
+}
@@ -0,0 +1,280 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use app_config::Config;
+use app_helpers::{ffprobe, file_type};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{fs, process::Command};
+use tracing::{debug, trace};
+
+use crate::fixers::{
+    common::{command::CmdError, FixRequest, FixResult, FixerError},
+    Fixer, FixerReturn, IntoFixerReturn,
+};
+
+/// The only tag that can be kept for images
+const ORIENTATION_TAG: &str = "orientation";
+
+/// The streams that are copied, in the order they end up in
+const MAPPED_STREAM_TYPES: &[&str] = &["video", "audio", "subtitle"];
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct StripMetadata;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for StripMetadata {
+    fn description(&self) -> &'static str {
+        "Removes EXIF, GPS and container metadata (eg. camera, location or encoder tags) from \
+         images and videos, so they can be shared without giving anything away."
+    }
+
+    fn enabled_by_default(&self) -> bool {
+        false
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        match media_kind(&request.file_path).await {
+            Some(MediaKind::Image) => Config::global()
+                .dependency_paths
+                .imagemagick_path()
+                .is_some(),
+            Some(MediaKind::Media) => true,
+            None => false,
+        }
+    }
+
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        debug!(path = ?request.file_path, "Stripping metadata");
+
+        let result = match media_kind(&request.file_path).await {
+            Some(MediaKind::Image) => strip_image(&request.file_path).await,
+            Some(MediaKind::Media) => strip_media(&request.file_path).await,
+            None => Ok(request.file_path.clone()),
+        };
+
+        result
+            .map(|x| FixResult::new(request.clone(), x))
+            .into_fixer_return()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MediaKind {
+    Image,
+    /// Anything with audio or video streams
+    Media,
+}
+
+async fn media_kind(file_path: &Path) -> Option<MediaKind> {
+    let path = file_path.to_path_buf();
+    let is_image = tokio::task::spawn_blocking(move || file_type::infer_file_type(&path).ok())
+        .await
+        .ok()
+        .flatten()
+        .is_some_and(|x| x.type_() == file_type::mime::IMAGE);
+
+    if is_image {
+        return Some(MediaKind::Image);
+    }
+
+    let media_info = ffprobe::ffprobe_async(file_path).await.ok()?;
+
+    media_info
+        .streams
+        .iter()
+        .any(|s| matches!(s.codec_type.as_deref(), Some("video" | "audio")))
+        .then_some(MediaKind::Media)
+}
+
+fn stripped_path(file_path: &Path) -> PathBuf {
+    let mut file_name = file_path.file_stem().unwrap_or_default().to_os_string();
+    file_name.push(".stripped");
+    if let Some(extension) = file_path.extension() {
+        file_name.push(".");
+        file_name.push(extension);
+    }
+
+    file_path.with_file_name(file_name)
+}
+
+/// Imagemagick can't keep single EXIF tags, so the orientation is kept
+/// by rotating the image to match it before everything is stripped
+async fn strip_image(file_path: &Path) -> Result<PathBuf, StripMetadataError> {
+    let new_path = stripped_path(file_path);
+
+    let mut cmd = Command::new(
+        Config::global()
+            .dependency_paths
+            .imagemagick_path()
+            .ok_or(StripMetadataError::ImagemagickNotFound)?,
+    );
+    cmd.arg(file_path);
+    if Config::global()
+        .handlers
+        .strip_metadata_keeps_tag(ORIENTATION_TAG)
+    {
+        cmd.arg("-auto-orient");
+    }
+    cmd.arg("-strip").arg(&new_path).kill_on_drop(true);
+
+    debug!(?cmd, "Running imagemagick command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        return Err(
+            CmdError::Failed("Failed to strip image metadata".into(), output.into()).into(),
+        );
+    }
+
+    replace_file(&new_path, file_path).await
+}
+
+/// Copies the audio, video and subtitle streams without re-encoding.
+/// Data streams are dropped, since that's where cameras put things like GPS tracks.
+async fn strip_media(file_path: &Path) -> Result<PathBuf, StripMetadataError> {
+    let new_path = stripped_path(file_path);
+    let kept_tags = kept_tags(file_path).await?;
+
+    trace!(?kept_tags, "Keeping metadata tags");
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    cmd.arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(file_path)
+        .args(["-map", "0:v?", "-map", "0:a?", "-map", "0:s?", "-c", "copy"])
+        .args(["-map_metadata", "-1"])
+        .args(["-map_metadata:s:v", "-1", "-map_metadata:s:a", "-1"])
+        .args(["-map_metadata:s:s", "-1", "-map_chapters", "-1"])
+        // Leaves out the encoder tag
+        .args(["-fflags", "+bitexact"]);
+
+    for (k, v) in &kept_tags.format {
+        cmd.arg("-metadata").arg(format!("{k}={v}"));
+    }
+    for (i, tags) in &kept_tags.streams {
+        for (k, v) in tags {
+            cmd.arg(format!("-metadata:s:{i}")).arg(format!("{k}={v}"));
+        }
+    }
+
+    cmd.arg(&new_path).kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() || !new_path.exists() {
+        return Err(
+            CmdError::Failed("Failed to strip media metadata".into(), output.into()).into(),
+        );
+    }
+
+    replace_file(&new_path, file_path).await
+}
+
+async fn replace_file(new_path: &Path, file_path: &Path) -> Result<PathBuf, StripMetadataError> {
+    fs::rename(new_path, file_path)
+        .await
+        .map_err(StripMetadataError::Write)?;
+
+    Ok(file_path.to_path_buf())
+}
+
+#[derive(Debug, Default)]
+struct KeptTags {
+    format: BTreeMap<String, String>,
+    /// By index of the stream in the output
+    streams: Vec<(usize, BTreeMap<String, String>)>,
+}
+
+/// The allowed tags of the file and its streams.
+///
+/// The parsed ffprobe output only has some known tags, so the tags are listed separately.
+async fn kept_tags(file_path: &Path) -> Result<KeptTags, StripMetadataError> {
+    let handlers = &Config::global().handlers;
+    if handlers.strip_metadata_keep_tags.is_empty() {
+        return Ok(KeptTags::default());
+    }
+
+    let mut cmd = Command::new(Config::global().dependency_paths.ffprobe_path());
+    cmd.args(["-v", "error"])
+        .args(["-show_entries", "format_tags:stream=codec_type:stream_tags"])
+        .args(["-of", "json"])
+        .arg(file_path)
+        .kill_on_drop(true);
+
+    let output = cmd.output().await.map_err(CmdError::Run)?;
+
+    if !output.status.success() {
+        return Err(CmdError::Failed("Failed to list metadata tags".into(), output.into()).into());
+    }
+
+    let probe = serde_json::from_slice::<TagsProbe>(&output.stdout)
+        .map_err(StripMetadataError::ParseTags)?;
+
+    let keep = |tags: &BTreeMap<String, String>| {
+        tags.iter()
+            .filter(|(k, _)| handlers.strip_metadata_keeps_tag(k))
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect::<BTreeMap<_, _>>()
+    };
+
+    // The output streams are in the order they're mapped in: video, audio and then subtitles
+    let streams = MAPPED_STREAM_TYPES
+        .iter()
+        .flat_map(|kind| {
+            probe
+                .streams
+                .iter()
+                .filter(move |x| x.codec_type.as_deref() == Some(*kind))
+        })
+        .enumerate()
+        .map(|(i, x)| (i, keep(&x.tags)))
+        .filter(|(_, tags)| !tags.is_empty())
+        .collect();
+
+    Ok(KeptTags {
+        format: keep(&probe.format.tags),
+        streams,
+    })
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TagsProbe {
+    #[serde(default)]
+    format: TaggedEntry,
+    #[serde(default)]
+    streams: Vec<TaggedEntry>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct TaggedEntry {
+    codec_type: Option<String>,
+    #[serde(default)]
+    tags: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Error)]
+pub enum StripMetadataError {
+    #[error(transparent)]
+    Command(#[from] CmdError),
+    #[error("Imagemagick not found")]
+    ImagemagickNotFound,
+    #[error("Failed to parse metadata tags: {0:?}")]
+    ParseTags(serde_json::Error),
+    #[error("Failed to replace file: {0:?}")]
+    Write(std::io::Error),
+}
+
+impl From<StripMetadataError> for FixerError {
+    fn from(val: StripMetadataError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
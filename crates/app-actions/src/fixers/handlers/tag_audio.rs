@@ -0,0 +1,244 @@
+use std::path::{Path, PathBuf};
+
+use app_helpers::file_type::{infer_file_type, mime};
+use lofty::{
+    config::WriteOptions,
+    file::TaggedFileExt,
+    picture::{Picture, PictureType},
+    tag::{Accessor, ItemKey, Tag, TagExt},
+};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, trace, warn};
+
+use crate::{
+    common::request::Client,
+    fixers::{
+        common::{FixRequest, FixResult, FixerError},
+        Fixer, FixerReturn, IntoFixerReturn,
+    },
+};
+
+pub const AUDIO_TAGS_OPTION: &str = "audio-tags";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct TagAudio;
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub year: Option<u32>,
+    /// URL of the image to embed as the front cover
+    pub cover_url: Option<String>,
+    /// Path of a local image to embed as the front cover
+    pub cover_path: Option<PathBuf>,
+}
+impl AudioTags {
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.artist.is_none()
+            && self.album.is_none()
+            && self.album_artist.is_none()
+            && self.track_number.is_none()
+            && self.year.is_none()
+            && self.cover_url.is_none()
+            && self.cover_path.is_none()
+    }
+
+    /// Fills in any missing fields with the values from `other`
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            title: self.title.or(other.title),
+            artist: self.artist.or(other.artist),
+            album: self.album.or(other.album),
+            album_artist: self.album_artist.or(other.album_artist),
+            track_number: self.track_number.or(other.track_number),
+            year: self.year.or(other.year),
+            cover_url: self.cover_url.or(other.cover_url),
+            cover_path: self.cover_path.or(other.cover_path),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl Fixer for TagAudio {
+    fn description(&self) -> &'static str {
+        "Writes artist, title, album, and cover art metadata into audio files."
+    }
+
+    async fn can_run_for(&self, request: &FixRequest) -> bool {
+        if request
+            .option::<AudioTags>(AUDIO_TAGS_OPTION)
+            .is_none_or(|x| x.is_empty())
+        {
+            return false;
+        }
+
+        let path = request.file_path.clone();
+        tokio::task::spawn_blocking(move || infer_file_type(&path).ok())
+            .await
+            .ok()
+            .flatten()
+            .is_some_and(|x| x.type_() == mime::AUDIO)
+    }
+
+    /// Options:
+    /// - `audio-tags`: The tags to write into the file. See [`AudioTags`].
+    async fn run(&self, request: &FixRequest) -> FixerReturn {
+        let tags = request
+            .option::<AudioTags>(AUDIO_TAGS_OPTION)
+            .unwrap_or_default();
+
+        debug!(path = ?request.file_path, ?tags, "Tagging audio file");
+
+        // The rest of the tags are still worth writing without the cover
+        let cover = match get_cover(&tags).await {
+            Ok(x) => x,
+            Err(e) => {
+                warn!(?e, "Failed to get cover, tagging without it");
+                None
+            }
+        };
+
+        let file_path = request.file_path.clone();
+        tokio::task::spawn_blocking(move || write_tags(&file_path, &tags, cover))
+            .await?
+            .map(|()| FixResult::new(request.clone(), request.file_path.clone()))
+            .into_fixer_return()
+    }
+}
+
+/// Writes the tags into a freshly downloaded file.
+/// Tagging is best-effort, so the untagged file is kept if it fails.
+pub async fn tag_downloaded_file(tags: AudioTags, path: PathBuf) -> PathBuf {
+    let Ok(tags) = serde_json::to_value(tags) else {
+        return path;
+    };
+
+    let fix_request = FixRequest::new(&path).with_option(AUDIO_TAGS_OPTION, tags);
+
+    if !TagAudio.can_run_for(&fix_request).await {
+        return path;
+    }
+
+    match TagAudio.run(&fix_request).await {
+        Ok(x) => x.file_path,
+        Err(e) => {
+            warn!(?e, ?path, "Failed to tag audio file");
+            path
+        }
+    }
+}
+
+async fn get_cover(tags: &AudioTags) -> Result<Option<Vec<u8>>, TagAudioError> {
+    if let Some(cover_path) = &tags.cover_path {
+        trace!(?cover_path, "Reading cover from file");
+
+        return tokio::fs::read(cover_path)
+            .await
+            .map(Some)
+            .map_err(TagAudioError::CoverRead);
+    }
+
+    let Some(cover_url) = &tags.cover_url else {
+        return Ok(None);
+    };
+
+    trace!(?cover_url, "Downloading cover");
+
+    let bytes = Client::base()
+        .map_err(TagAudioError::CoverDownload)?
+        .get(cover_url)
+        .send()
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(|e| TagAudioError::CoverDownload(e.to_string()))?
+        .bytes()
+        .await
+        .map_err(|e| TagAudioError::CoverDownload(e.to_string()))?;
+
+    Ok(Some(bytes.to_vec()))
+}
+
+fn write_tags(
+    file_path: &Path,
+    tags: &AudioTags,
+    cover: Option<Vec<u8>>,
+) -> Result<(), TagAudioError> {
+    let mut tagged_file = lofty::read_from_path(file_path).map_err(TagAudioError::Read)?;
+
+    if tagged_file.primary_tag().is_none() {
+        let tag_type = tagged_file.primary_tag_type();
+        tagged_file.insert_tag(Tag::new(tag_type));
+    }
+
+    let tag = tagged_file
+        .primary_tag_mut()
+        .ok_or(TagAudioError::Unsupported)?;
+
+    if let Some(title) = &tags.title {
+        tag.set_title(title.clone());
+    }
+    if let Some(artist) = &tags.artist {
+        tag.set_artist(artist.clone());
+    }
+    if let Some(album) = &tags.album {
+        tag.set_album(album.clone());
+    }
+    if let Some(album_artist) = &tags.album_artist {
+        tag.insert_text(ItemKey::AlbumArtist, album_artist.clone());
+    }
+    if let Some(track_number) = tags.track_number {
+        tag.set_track(track_number);
+    }
+    if let Some(year) = tags.year {
+        tag.set_year(year);
+    }
+
+    if let Some(cover) = cover {
+        match Picture::from_reader(&mut cover.as_slice()) {
+            Ok(mut picture) => {
+                picture.set_pic_type(PictureType::CoverFront);
+
+                tag.remove_picture_type(PictureType::CoverFront);
+                tag.push_picture(picture);
+            }
+            Err(e) => {
+                warn!(?e, "Invalid cover image, tagging without it");
+            }
+        }
+    }
+
+    trace!(?file_path, "Saving audio tags");
+
+    tag.save_to_path(file_path, WriteOptions::default())
+        .map_err(TagAudioError::Write)
+}
+
+#[derive(Debug, Error)]
+pub enum TagAudioError {
+    #[error("Unable to read audio file: {0:?}")]
+    Read(lofty::error::LoftyError),
+    #[error("Audio file format does not support tags")]
+    Unsupported,
+    #[error("Unable to read cover image: {0:?}")]
+    CoverRead(std::io::Error),
+    #[error("Unable to download cover image: {0}")]
+    CoverDownload(String),
+    #[error("Unable to write audio tags: {0:?}")]
+    Write(lofty::error::LoftyError),
+}
+
+impl From<TagAudioError> for FixerError {
+    fn from(val: TagAudioError) -> Self {
+        Self::FailedFix(val.into())
+    }
+}
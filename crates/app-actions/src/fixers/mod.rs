@@ -70,6 +70,10 @@ pub async fn fix_file_with(fixers: Vec<FixerInstance>, request: FixRequest) -> F
 
         let result = match fixer.run(&req).await {
             Ok(x) => x,
+            Err(e) if e.is_fatal() => {
+                warn!("Fixer {fixer:?} failed fatally on {req:?}: {e:?}");
+                return Err(e);
+            }
             Err(e) => {
                 warn!("Failed to run fixer {fixer:?} on {req:?}: {e:?}");
                 continue;
@@ -0,0 +1,282 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    process::Stdio,
+    str::FromStr,
+};
+
+use app_config::Config;
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::file_name::file_name_with_suffix;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, trace};
+use url::Url;
+
+use crate::{
+    downloaders::{
+        handlers::{hls::Hls, yt_dlp::YtDlp},
+        Downloader, DownloaderOptions,
+    },
+    extractors::{ExtractedInfo, ExtractedUrlInfo},
+};
+
+/// Key of the downloader option with the [`FormatChoice`] the user picked
+pub const FORMAT_CHOICE_OPTION: &str = "format-choice";
+
+/// Key of the downloader option extractors set on screenshots of the page itself
+pub const SCREENSHOT_OPTION: &str = "screenshot";
+
+/// Key of the downloader option extractors set when the same media is available
+/// at multiple URLs, as a list of [`MediaVariant`]s, best one first
+pub const VARIANTS_OPTION: &str = "variants";
+
+/// Video heights offered when the downloader can pick the quality
+const OFFERED_HEIGHTS: &[u32] = &[720, 1080];
+
+/// What to download when the source has multiple variants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FormatChoice {
+    /// The best available video, ignoring the configured height limit
+    Best,
+    /// The best video that's at most this tall
+    MaxHeight(u32),
+    AudioOnly,
+    /// Only the screenshots of the post, without its media
+    ScreenshotOnly,
+}
+impl FormatChoice {
+    #[must_use]
+    pub fn from_options(options: &DownloaderOptions) -> Option<Self> {
+        serde_json::from_value(options.get(FORMAT_CHOICE_OPTION)?.clone()).ok()
+    }
+
+    #[must_use]
+    pub fn label(&self) -> String {
+        match self {
+            Self::Best => "Best".to_string(),
+            Self::MaxHeight(height) => format!("{height}p"),
+            Self::AudioOnly => "Audio only".to_string(),
+            Self::ScreenshotOnly => "Screenshot only".to_string(),
+        }
+    }
+}
+impl fmt::Display for FormatChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Best => write!(f, "best"),
+            Self::MaxHeight(height) => write!(f, "{height}p"),
+            Self::AudioOnly => write!(f, "audio"),
+            Self::ScreenshotOnly => write!(f, "screenshot"),
+        }
+    }
+}
+impl FromStr for FormatChoice {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "best" => Ok(Self::Best),
+            "audio" | "audio-only" => Ok(Self::AudioOnly),
+            "screenshot" => Ok(Self::ScreenshotOnly),
+            s => s
+                .strip_suffix('p')
+                .and_then(|x| x.parse().ok())
+                .map(Self::MaxHeight)
+                .ok_or_else(|| format!("Unknown format choice: {s:?}")),
+        }
+    }
+}
+impl TryFrom<String> for FormatChoice {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+impl From<FormatChoice> for String {
+    fn from(value: FormatChoice) -> Self {
+        value.to_string()
+    }
+}
+impl From<FormatChoice> for serde_json::Value {
+    fn from(value: FormatChoice) -> Self {
+        value.to_string().into()
+    }
+}
+
+/// One of the URLs the same media is available at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MediaVariant {
+    pub url: String,
+    /// Height of the video, if it's known
+    pub height: Option<u32>,
+}
+
+/// Swaps the URLs that have [`MediaVariant`]s for the variant that fits the choice,
+/// so the downloader that ends up with them (eg. the generic one) gets the right file.
+///
+/// The extractor's pick is kept if nothing was chosen.
+pub fn apply_format_choice(info: &mut ExtractedInfo, choice: Option<FormatChoice>) {
+    let Some(choice) = choice else {
+        return;
+    };
+
+    if choice == FormatChoice::ScreenshotOnly {
+        info.urls.retain(is_screenshot);
+        // A single image of the post is what's wanted
+        info.urls.truncate(1);
+        return;
+    }
+
+    for url in &mut info.urls {
+        let Some(variants) = url
+            .downloader_option(VARIANTS_OPTION)
+            .and_then(|x| serde_json::from_value::<Vec<MediaVariant>>(x.clone()).ok())
+        else {
+            continue;
+        };
+
+        let Some(variant_url) =
+            pick_variant(&variants, choice).and_then(|x| Url::parse(&x.url).ok())
+        else {
+            continue;
+        };
+
+        trace!(url = ?variant_url.as_str(), ?choice, "Picked media variant");
+
+        url.url = url.url.clone().with_url(variant_url);
+        // The mirrors serve the variant that was there before
+        url.mirrors.clear();
+    }
+}
+
+fn pick_variant(variants: &[MediaVariant], choice: FormatChoice) -> Option<&MediaVariant> {
+    match choice {
+        FormatChoice::Best | FormatChoice::ScreenshotOnly => variants.first(),
+        // The audio is the same in all of them, so the smallest one is enough.
+        // The downloader strips the video from it with `extract_audio`.
+        FormatChoice::AudioOnly => variants.iter().min_by_key(|x| x.height.unwrap_or(u32::MAX)),
+        FormatChoice::MaxHeight(max_height) => variants
+            .iter()
+            .filter(|x| x.height.is_none_or(|h| h <= max_height))
+            .max_by_key(|x| x.height.unwrap_or_default())
+            .or_else(|| variants.iter().min_by_key(|x| x.height.unwrap_or(u32::MAX))),
+    }
+}
+
+/// Keeps only the audio of the downloaded file, for downloaders that
+/// can only get the whole video when [`FormatChoice::AudioOnly`] was picked.
+///
+/// The audio is copied as-is if it fits in an `m4a` file and re-encoded otherwise.
+/// The original file is removed once the audio is out.
+pub async fn extract_audio(file_path: &Path) -> Result<PathBuf, AppError> {
+    let mut audio_path = file_path.with_extension("m4a");
+    if audio_path == file_path {
+        audio_path = file_name_with_suffix(file_path, "audio");
+    }
+
+    let copied = run_audio_extraction(file_path, &audio_path, &["-c:a", "copy"]).await;
+    let result = match copied {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            debug!(?e, "Couldn't copy the audio stream, re-encoding it");
+            run_audio_extraction(file_path, &audio_path, &["-c:a", "aac", "-b:a", "192k"]).await
+        }
+    };
+
+    if let Err(e) = result {
+        let _ = tokio::fs::remove_file(&audio_path).await;
+        return Err(e);
+    }
+
+    let _ = tokio::fs::remove_file(file_path).await;
+
+    Ok(audio_path)
+}
+
+async fn run_audio_extraction(
+    input: &Path,
+    output: &Path,
+    codec_args: &[&str],
+) -> Result<(), AppError> {
+    let mut cmd = Command::new(Config::global().dependency_paths.ffmpeg_path());
+    let cmd = cmd
+        .arg("-y")
+        .arg("-hide_banner")
+        .args(["-loglevel", "error"])
+        .arg("-i")
+        .arg(input)
+        .args(["-map", "0:a:0", "-vn", "-sn", "-dn"])
+        .args(codec_args)
+        .args(["-movflags", "+faststart"])
+        .arg(output)
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+
+    debug!(?cmd, "Running ffmpeg command");
+
+    let output_info = cmd
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable("ffmpeg", format!("{e:?}")))?;
+
+    trace!(?output_info, "ffmpeg output");
+
+    if !output_info.status.success() || !output.exists() {
+        return Err(ExternalToolError::failed(
+            "ffmpeg",
+            format!(
+                "Failed to extract the audio: {stderr}",
+                stderr = String::from_utf8_lossy(&output_info.stderr).trim(),
+            ),
+        )
+        .into());
+    }
+
+    Ok(())
+}
+
+/// The choices that make a difference for the extracted media.
+///
+/// Empty if there's nothing to choose from.
+#[must_use]
+pub fn format_choices(info: &ExtractedInfo) -> Vec<FormatChoice> {
+    let mut choices = vec![];
+
+    if info.urls.iter().any(can_pick_quality) {
+        choices.push(FormatChoice::Best);
+        choices.extend(OFFERED_HEIGHTS.iter().copied().map(FormatChoice::MaxHeight));
+        choices.push(FormatChoice::AudioOnly);
+    }
+
+    // Only a choice if there's something else to leave out
+    if info.urls.iter().any(is_screenshot) && !info.urls.iter().all(is_screenshot) {
+        choices.push(FormatChoice::ScreenshotOnly);
+    }
+
+    choices
+}
+
+#[must_use]
+pub fn is_screenshot(url: &ExtractedUrlInfo) -> bool {
+    url.downloader_option(SCREENSHOT_OPTION)
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or_default()
+}
+
+/// URLs without a preferred downloader end up with yt-dlp
+fn can_pick_quality(url: &ExtractedUrlInfo) -> bool {
+    if is_screenshot(url) {
+        return false;
+    }
+
+    if url.downloader_option(VARIANTS_OPTION).is_some() {
+        return true;
+    }
+
+    url.preferred_downloader
+        .as_ref()
+        .is_none_or(|x| [YtDlp.name(), Hls.name()].contains(&x.name()))
+}
@@ -1,50 +1,342 @@
 use std::path::Path;
 
-use futures::{stream::FuturesUnordered, StreamExt};
-use tracing::debug;
+use app_errors::{AppError, UnsupportedSource, UserInputError};
+use futures::future::join_all;
+use tracing::{debug, error, warn};
+
+use crate::{
+    collection::CollectionOptions,
+    downloaders::{DownloaderOptions, FoundMedia, ProgressTracker},
+    format_choice::FormatChoice,
+};
 
 pub mod actions;
+pub mod age_restriction;
+pub mod blocklist;
+pub mod collection;
 pub(crate) mod common;
+pub mod content_policy;
+pub mod dedup;
+pub mod download_archive;
+pub mod download_cache;
 pub mod downloaders;
 pub mod extractors;
 pub mod fixers;
+pub mod format_choice;
+pub mod media_policy;
+pub mod partial_retry;
+pub mod playlist;
+pub mod post_archive;
+pub mod resume;
+pub mod sidecar;
+pub mod source_check;
+pub mod startup;
+pub mod thumbnail;
 
 #[tracing::instrument]
 pub async fn download_file<R>(request: R, download_dir: &Path) -> Vec<downloaders::DownloaderReturn>
+where
+    R: Into<extractors::ExtractInfoRequest> + Send + Sync + std::fmt::Debug,
+{
+    download_file_with_options(request, download_dir, DownloaderOptions::new()).await
+}
+
+/// Same as [`download_file`], but adds the `options` to every download request.
+///
+/// Options set by the extractor take precedence.
+#[tracing::instrument]
+pub async fn download_file_with_options<R>(
+    request: R,
+    download_dir: &Path,
+    options: DownloaderOptions,
+) -> Vec<downloaders::DownloaderReturn>
+where
+    R: Into<extractors::ExtractInfoRequest> + Send + Sync + std::fmt::Debug,
+{
+    download_file_with_progress(request, download_dir, options, None).await
+}
+
+/// Same as [`download_file_with_options`], but every download reports
+/// how far along it is to the `progress` tracker.
+#[tracing::instrument]
+pub async fn download_file_with_progress<R>(
+    request: R,
+    download_dir: &Path,
+    options: DownloaderOptions,
+    progress: Option<&ProgressTracker>,
+) -> Vec<downloaders::DownloaderReturn>
 where
     R: Into<extractors::ExtractInfoRequest> + Send + Sync + std::fmt::Debug,
 {
     let request = request.into();
 
+    // Retrying the files that failed last time, the rest of them are already downloaded
+    let is_retry = partial_retry::skipped_urls(&options).is_some();
+
+    match download_archive::is_archived(&request.url).await {
+        Ok(false) => {}
+        Ok(true) if is_retry => {}
+        Ok(true) => {
+            debug!(url = ?request.url.as_str(), "URL is in the download archive, skipping");
+
+            return vec![Err(UserInputError::AlreadyDownloaded(
+                request.url.to_string(),
+            )
+            .into())];
+        }
+        // Downloading it again is better than not downloading it at all
+        Err(e) => warn!(?e, "Failed to check download archive"),
+    }
+
+    match download_cache::get_cached(&request.url, &options, download_dir).await {
+        Ok(None) => {}
+        Ok(Some(results)) => {
+            // The blocklist could have changed since the files were cached
+            return join_all(results.into_iter().map(enforce_blocklist)).await;
+        }
+        Err(e) => warn!(?e, "Failed to check download cache"),
+    }
+
     debug!(?request, "Extracting info");
 
-    let info = match extractors::extract_info(&request).await {
+    let mut info = match extractors::extract_info(&request).await {
         Ok(x) => x,
-        Err(e) => {
-            return vec![Err(format!(
+        // The kind of error is kept so it's still known whether retrying would help
+        Err(AppError::Other(e)) => {
+            return vec![Err(AppError::Other(format!(
                 "Failed to extract info from {request:?}: <u>{e}</u>"
-            ))];
+            )))];
+        }
+        Err(e) => {
+            debug!(?e, "Failed to extract info");
+            return vec![Err(e)];
         }
     };
 
     debug!(?info, "Extracted info");
 
-    let download_requests = downloaders::DownloadRequest::from_extracted_info(&info, download_dir);
+    if let Some(progress) = progress {
+        progress.found(FoundMedia::from(&info));
+    }
 
-    debug!(?download_requests, "Download requests");
+    if let Err(e) = check_age_restriction(&info, &options) {
+        return vec![Err(e)];
+    }
+
+    if let Err(e) = check_estimated_size(&info) {
+        return vec![Err(e)];
+    }
+
+    // Whole playlists and channels can be a lot of files, so they're only downloaded when asked for
+    if info.is_collection() {
+        let Some(collection) = CollectionOptions::from_options(&options) else {
+            return vec![Err(UserInputError::NotAllowed(format!(
+                "{url} is a playlist, channel or profile, which is only downloaded when the \
+                 collection option is set",
+                url = request.url,
+            ))
+            .into())];
+        };
 
-    let download_results = download_requests
+        collection.apply(&mut info);
+    }
+
+    let format_choice = FormatChoice::from_options(&options);
+    format_choice::apply_format_choice(&mut info, format_choice);
+
+    if format_choice == Some(FormatChoice::ScreenshotOnly) && info.urls.is_empty() {
+        return vec![Err(UnsupportedSource::new(format!(
+            "Screenshots aren't available for {url}",
+            url = request.url,
+        ))
+        .into())];
+    }
+
+    let download_results = download_extracted(&info, download_dir, &options, progress).await;
+
+    // Partial results aren't cached so the failed parts are tried again next time
+    let downloaded = download_results
+        .iter()
+        .filter_map(|x| x.as_ref().ok())
+        .cloned()
+        .collect::<Vec<_>>();
+    if !downloaded.is_empty() && downloaded.len() == download_results.len() {
+        if let Err(e) = download_cache::add_to_cache(&request.url, &options, &downloaded).await {
+            warn!(?e, "Failed to add download to cache");
+        }
+    }
+
+    // The media itself wasn't downloaded, so it's still wanted later
+    let only_screenshot = format_choice == Some(FormatChoice::ScreenshotOnly);
+    if !only_screenshot && download_results.iter().any(Result::is_ok) {
+        if let Err(e) = download_archive::add_to_archive(&request.url).await {
+            warn!(?e, "Failed to add URL to download archive");
+        }
+    }
+
+    download_results
+}
+
+/// Only extracts the info, for when the metadata or the direct links are enough
+#[tracing::instrument]
+pub async fn extract_only<R>(
+    request: R,
+    options: &DownloaderOptions,
+) -> Result<extractors::ExtractionSummary, AppError>
+where
+    R: Into<extractors::ExtractInfoRequest> + Send + Sync + std::fmt::Debug,
+{
+    let info = extractors::extract_info(&request.into()).await?;
+
+    debug!(?info, "Extracted info");
+
+    check_age_restriction(&info, options)?;
+
+    Ok(extractors::ExtractionSummary::from(&info))
+}
+
+/// Downloads everything the extractor found, with the `options` added to every download request
+pub(crate) async fn download_extracted(
+    info: &extractors::ExtractedInfo,
+    download_dir: &Path,
+    options: &DownloaderOptions,
+    progress: Option<&ProgressTracker>,
+) -> Vec<downloaders::DownloaderReturn> {
+    let skipped = partial_retry::skipped_urls(options).unwrap_or_default();
+
+    let download_requests = downloaders::DownloadRequest::from_extracted_info(info, download_dir)
         .into_iter()
-        .map(|x| async move { downloaders::download_file(&x).await })
-        .collect::<FuturesUnordered<_>>()
-        .collect::<Vec<_>>()
-        .await;
+        .filter(|x| !partial_retry::is_skipped(x, &skipped))
+        .map(|mut x| {
+            for (k, v) in options {
+                x.downloader_options
+                    .entry(k.clone())
+                    .or_insert_with(|| v.clone());
+            }
+            x.with_progress(progress.map(ProgressTracker::reporter))
+        })
+        .collect::<Vec<_>>();
+
+    debug!(?download_requests, "Download requests");
+
+    let source_url = info.request.url.as_str();
+    let extractor = info.extractor_name();
+    let allowed_content_types = content_policy::allowed_content_types(options);
+    let allowed_content_types = allowed_content_types.as_deref();
+
+    // Results are kept in the order the extractor returned them
+    // so they can be used to build playlists and the like
+    let download_results = join_all(download_requests.into_iter().map(|x| async move {
+        if let Some(allowed) = allowed_content_types {
+            if let Err(e) = content_policy::check_reported(&x, allowed) {
+                return vec![Err(e)];
+            }
+        }
+
+        join_all(
+            downloaders::download_file(&x)
+                .await
+                .into_iter()
+                .map(|result| async move {
+                    let mut result = enforce_blocklist(result?).await?;
+                    result = check_downloaded_age_restriction(result, options).await?;
+                    if let Some(allowed) = allowed_content_types {
+                        result = content_policy::check_downloaded(result, allowed).await?;
+                    }
+                    sidecar::write_sidecar_if_wanted(&result, source_url, extractor).await;
+
+                    Ok(result)
+                }),
+        )
+        .await
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect::<Vec<_>>();
 
     debug!(?download_results, "Download results");
 
     download_results
 }
 
+pub(crate) fn check_age_restriction(
+    info: &extractors::ExtractedInfo,
+    options: &DownloaderOptions,
+) -> Result<(), AppError> {
+    if !info.is_age_restricted() || allows_age_restricted(options) {
+        return Ok(());
+    }
+
+    debug!("Refusing to download age-restricted content");
+
+    Err(age_restricted_refusal())
+}
+
+/// Deletes the downloaded file if the site turns out to only show it to adults,
+/// for the sites the extractors can't tell about before it's downloaded (eg. `YouTube` through `yt-dlp`)
+async fn check_downloaded_age_restriction(
+    result: downloaders::DownloadResult,
+    options: &DownloaderOptions,
+) -> downloaders::DownloaderReturn {
+    let is_age_restricted = result
+        .metadata
+        .as_ref()
+        .is_some_and(downloaders::MediaMetadata::is_age_restricted);
+
+    if !is_age_restricted || allows_age_restricted(options) {
+        return Ok(result);
+    }
+
+    debug!(path = ?result.path, "Refusing downloaded age-restricted content");
+
+    if let Err(e) = tokio::fs::remove_file(&result.path).await {
+        warn!(?e, path = ?result.path, "Failed to remove refused file");
+    }
+
+    Err(age_restricted_refusal())
+}
+
+fn allows_age_restricted(options: &DownloaderOptions) -> bool {
+    options
+        .get(age_restriction::ALLOW_AGE_RESTRICTED_OPTION)
+        .and_then(serde_json::Value::as_bool)
+        .unwrap_or_else(|| age_restriction::allows_age_restricted(false))
+}
+
+fn age_restricted_refusal() -> AppError {
+    UserInputError::NotAllowed(
+        "Refusing to download age-restricted content on this instance".to_string(),
+    )
+    .into()
+}
+
+/// Refuses downloads the site already says are over the size limit,
+/// before anything is downloaded
+pub(crate) fn check_estimated_size(info: &extractors::ExtractedInfo) -> Result<(), AppError> {
+    info.estimated_size()
+        .map_or(Ok(()), downloaders::helpers::size_limit::check_size)
+}
+
+async fn enforce_blocklist(result: downloaders::DownloadResult) -> downloaders::DownloaderReturn {
+    let source_url = result.request.url.url().as_str();
+
+    match blocklist::enforce_blocklist(&result.path, source_url, result.sha256.as_deref()).await {
+        Ok(None) => Ok(result),
+        Ok(Some(blocked)) => Err(UserInputError::Blocked(blocked.to_string()).into()),
+        // Files that can't be checked aren't kept, in case they're on the list
+        Err(e) => {
+            error!(?e, path = ?result.path, "Failed to check file against blocklist");
+
+            let _ = tokio::fs::remove_file(&result.path).await;
+
+            Err(AppError::other(format!(
+                "Failed to check file against blocklist: {e}"
+            )))
+        }
+    }
+}
+
 #[tracing::instrument]
 pub async fn fix_file<R>(request: R) -> fixers::FixerReturn
 where
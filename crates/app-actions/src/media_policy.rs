@@ -0,0 +1,13 @@
+pub use app_config::common::MediaPolicy;
+use app_config::Config;
+
+/// Key of the downloader/fixer option used to override the global [`MediaPolicy`]
+pub const MEDIA_POLICY_OPTION: &str = "media-policy";
+
+/// The given policy with the unset fields taken from the global config
+#[must_use]
+pub fn resolve_media_policy(policy: Option<MediaPolicy>) -> MediaPolicy {
+    policy
+        .unwrap_or_default()
+        .or(Config::global().download.media_policy())
+}
@@ -0,0 +1,26 @@
+use std::collections::HashSet;
+
+use crate::downloaders::{DownloadRequest, DownloaderOptions};
+
+/// Key of the downloader option with the URLs of the files that were already downloaded,
+/// for when only the files that failed last time should be downloaded again
+pub const SKIP_URLS_OPTION: &str = "skip-urls";
+
+/// The URLs of the files that were already downloaded, if only the rest should be downloaded
+pub(crate) fn skipped_urls(options: &DownloaderOptions) -> Option<HashSet<String>> {
+    let urls = options.get(SKIP_URLS_OPTION)?.as_array()?;
+
+    Some(
+        urls.iter()
+            .filter_map(serde_json::Value::as_str)
+            .map(ToString::to_string)
+            .collect(),
+    )
+}
+
+/// Whether the file was already downloaded, from its URL or one of its mirrors
+pub(crate) fn is_skipped(request: &DownloadRequest, skipped: &HashSet<String>) -> bool {
+    std::iter::once(&request.url)
+        .chain(&request.mirrors)
+        .any(|x| skipped.contains(x.url().as_str()))
+}
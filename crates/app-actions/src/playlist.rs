@@ -0,0 +1,123 @@
+use std::{
+    ffi::OsStr,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+pub use app_config::common::PlaylistFormat;
+use app_helpers::file_name::sanitize_file_name;
+use tokio::fs;
+use tracing::debug;
+
+const MAX_PLAYLIST_NAME_LENGTH: usize = 100;
+
+/// Writes a playlist with the `files` in the given order to `<dir>/<name>.<ext>`.
+///
+/// Files inside `dir` are referenced by their relative path
+/// so the directory can be moved around without breaking the playlist.
+pub async fn write_playlist(
+    format: PlaylistFormat,
+    dir: &Path,
+    name: &str,
+    files: &[PathBuf],
+) -> std::io::Result<PathBuf> {
+    let name = match sanitize_file_name(name, MAX_PLAYLIST_NAME_LENGTH) {
+        x if x.is_empty() => "playlist".to_string(),
+        x => x,
+    };
+    let playlist_path = dir.join(format!("{name}.{}", format.extension()));
+
+    let entries = files
+        .iter()
+        .map(|x| PlaylistEntry::new(dir, x))
+        .collect::<Vec<_>>();
+
+    let contents = match format {
+        PlaylistFormat::M3u8 => to_m3u8(&entries),
+        PlaylistFormat::Cue => to_cue(&name, &entries),
+    };
+
+    debug!(?playlist_path, files = ?files.len(), "Writing playlist");
+
+    fs::write(&playlist_path, contents).await?;
+
+    Ok(playlist_path)
+}
+
+#[derive(Debug)]
+struct PlaylistEntry {
+    path: String,
+    title: String,
+    extension: String,
+}
+impl PlaylistEntry {
+    fn new(dir: &Path, file: &Path) -> Self {
+        let path = file
+            .strip_prefix(dir)
+            .unwrap_or(file)
+            .to_string_lossy()
+            .to_string();
+
+        let title = file
+            .file_stem()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_string();
+
+        let extension = file
+            .extension()
+            .and_then(OsStr::to_str)
+            .unwrap_or_default()
+            .to_lowercase();
+
+        Self {
+            path,
+            title,
+            extension,
+        }
+    }
+
+    /// File type as understood by cue sheet readers
+    fn cue_file_type(&self) -> &'static str {
+        match self.extension.as_str() {
+            "mp3" => "MP3",
+            "aif" | "aiff" => "AIFF",
+            _ => "WAVE",
+        }
+    }
+}
+
+fn to_m3u8(entries: &[PlaylistEntry]) -> String {
+    let mut out = "#EXTM3U\n".to_string();
+
+    for entry in entries {
+        let _ = writeln!(out, "#EXTINF:-1,{}", entry.title);
+        let _ = writeln!(out, "{}", entry.path);
+    }
+
+    out
+}
+
+fn to_cue(name: &str, entries: &[PlaylistEntry]) -> String {
+    let mut out = String::new();
+
+    let _ = writeln!(out, "TITLE {}", cue_quote(name));
+
+    for (i, entry) in entries.iter().enumerate() {
+        let _ = writeln!(
+            out,
+            "FILE {} {}",
+            cue_quote(&entry.path),
+            entry.cue_file_type()
+        );
+        let _ = writeln!(out, "  TRACK {:02} AUDIO", i + 1);
+        let _ = writeln!(out, "    TITLE {}", cue_quote(&entry.title));
+        let _ = writeln!(out, "    INDEX 01 00:00:00");
+    }
+
+    out
+}
+
+fn cue_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "'"))
+}
@@ -0,0 +1,219 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use app_errors::AppError;
+use app_helpers::file_name::sanitize_file_name;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::fs;
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::{
+    check_age_restriction, check_estimated_size, download_extracted,
+    downloaders::DownloaderOptions,
+    extractors::{self, ExtractInfoRequest, ExtractionSummary},
+    format_choice::FORMAT_CHOICE_OPTION,
+};
+
+const MAX_ARCHIVE_NAME_LENGTH: usize = 100;
+/// The post text, readable without any tools
+const TEXT_FILE_NAME: &str = "post.md";
+/// Everything known about the post, for tools
+const META_FILE_NAME: &str = "post.json";
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PostArchiveMeta {
+    archived_at: DateTime<Utc>,
+    #[serde(flatten)]
+    summary: ExtractionSummary,
+    /// Everything the extractor found out about the post, as-is
+    extractor_meta: HashMap<String, serde_json::Value>,
+    /// The files in the archive, in the order they were found
+    files: Vec<String>,
+    /// Why some of the media is missing from the archive
+    errors: Vec<String>,
+}
+
+/// Downloads everything in the post (the media and the screenshot) to `<output_dir>/<name>/`,
+/// next to the post text as `post.md` and its metadata as `post.json`.
+///
+/// Returns the directory of the archive.
+#[tracing::instrument]
+pub async fn archive_post<R>(
+    request: R,
+    output_dir: &Path,
+    mut options: DownloaderOptions,
+) -> Result<PathBuf, AppError>
+where
+    R: Into<ExtractInfoRequest> + Send + Sync + std::fmt::Debug,
+{
+    let request = request.into();
+
+    let info = extractors::extract_info(&request).await?;
+
+    debug!(?info, "Extracted info");
+
+    check_age_restriction(&info, &options)?;
+    check_estimated_size(&info)?;
+
+    // The whole post is kept, no matter what would be picked for a download
+    options.remove(FORMAT_CHOICE_OPTION);
+
+    let archive_dir = output_dir.join(archive_name(&request.url));
+    fs::create_dir_all(&archive_dir).await?;
+
+    let results = download_extracted(&info, &archive_dir, &options, None).await;
+
+    let mut files = vec![];
+    let mut errors = vec![];
+    for result in results {
+        match result {
+            Ok(x) => files.push(
+                x.path
+                    .strip_prefix(&archive_dir)
+                    .unwrap_or(&x.path)
+                    .to_string_lossy()
+                    .to_string(),
+            ),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    // Only the text is still worth keeping, but not nothing at all
+    if files.is_empty() && info.text().is_none() {
+        let _ = fs::remove_dir_all(&archive_dir).await;
+
+        return Err(errors
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| AppError::other("Nothing was found to archive")));
+    }
+
+    let meta = PostArchiveMeta {
+        archived_at: Utc::now(),
+        summary: ExtractionSummary::from(&info),
+        extractor_meta: info.meta.clone(),
+        files,
+        errors: errors.iter().map(ToString::to_string).collect(),
+    };
+
+    debug!(?archive_dir, files = ?meta.files, "Writing post archive");
+
+    fs::write(archive_dir.join(TEXT_FILE_NAME), to_markdown(&meta)).await?;
+    fs::write(
+        archive_dir.join(META_FILE_NAME),
+        serde_json::to_vec_pretty(&meta).map_err(|e| AppError::other(e.to_string()))?,
+    )
+    .await?;
+
+    Ok(archive_dir)
+}
+
+/// Packs the archive directory into `<name>.zip` next to it and removes the directory
+pub async fn zip_post_archive(archive_dir: &Path) -> Result<PathBuf, AppError> {
+    let name = archive_dir
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or("post");
+    let zip_path = archive_dir.with_file_name(format!("{name}.zip"));
+
+    let mut files = vec![];
+    let mut entries = fs::read_dir(archive_dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_file() {
+            files.push(entry.path());
+        }
+    }
+    files.sort();
+
+    debug!(?zip_path, ?files, "Zipping post archive");
+
+    let zip_path = tokio::task::spawn_blocking(move || -> Result<PathBuf, AppError> {
+        let zip_file = std::fs::File::create(&zip_path)?;
+        let mut zip = zip::ZipWriter::new(zip_file);
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated);
+
+        for file in files {
+            let Some(file_name) = file.file_name().and_then(OsStr::to_str) else {
+                continue;
+            };
+
+            zip.start_file(file_name, options)
+                .map_err(|e| AppError::other(format!("Failed to zip post archive: {e}")))?;
+
+            let mut f = std::fs::File::open(&file)?;
+            std::io::copy(&mut f, &mut zip)?;
+        }
+
+        zip.finish()
+            .map_err(|e| AppError::other(format!("Failed to zip post archive: {e}")))?;
+
+        Ok(zip_path)
+    })
+    .await
+    .map_err(|e| AppError::other(e.to_string()))??;
+
+    if let Err(e) = fs::remove_dir_all(archive_dir).await {
+        warn!(?e, ?archive_dir, "Failed to remove post archive directory");
+    }
+
+    Ok(zip_path)
+}
+
+/// Eg. `x.com_user_status_123` for `https://x.com/user/status/123`
+fn archive_name(url: &Url) -> String {
+    let name = format!(
+        "{}{}",
+        url.host_str().unwrap_or_default(),
+        url.path().trim_end_matches('/')
+    );
+
+    match sanitize_file_name(&name, MAX_ARCHIVE_NAME_LENGTH) {
+        x if x.is_empty() => "post".to_string(),
+        x => x,
+    }
+}
+
+fn to_markdown(meta: &PostArchiveMeta) -> String {
+    let summary = &meta.summary;
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "# {}\n",
+        summary.title.as_deref().unwrap_or(summary.url.as_str())
+    );
+
+    if let Some(text) = &summary.text {
+        let _ = writeln!(out, "{}\n", text.trim());
+    }
+
+    let _ = writeln!(out, "- Source: <{}>", summary.url);
+    let _ = writeln!(out, "- Archived: {}", meta.archived_at.to_rfc3339());
+    if let Some(extractor) = &summary.extractor {
+        let _ = writeln!(out, "- Extractor: {extractor}");
+    }
+
+    if !meta.files.is_empty() {
+        let _ = writeln!(out, "\n## Files\n");
+        for file in &meta.files {
+            let _ = writeln!(out, "- [{file}](<{file}>)");
+        }
+    }
+
+    if !meta.errors.is_empty() {
+        let _ = writeln!(out, "\n## Missing\n");
+        for error in &meta.errors {
+            let _ = writeln!(out, "- {error}");
+        }
+    }
+
+    out
+}
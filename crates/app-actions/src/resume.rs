@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use app_config::Config;
+use app_helpers::id::time_id;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::downloaders::DownloadRequest;
+
+/// Key of the downloader option that's the same every time the same download is tried.
+///
+/// Eg. the ID of the request, so a download that was cut off (eg. by a restart)
+/// can pick up where it left off instead of starting over.
+pub const RESUME_KEY_OPTION: &str = "resume-key";
+
+/// Prefix of the directories in the cache directory with the partial downloads
+const RESUME_DIR_PREFIX: &str = "resume-";
+
+const IDENTIFIER_FILE_NAME: &str = "identifier";
+
+/// Where the download keeps its partial files between tries, if it can be resumed.
+///
+/// It's in the cache directory since the temporary directory might not survive a restart.
+/// Only removed once the download is done, or by pruning the cache.
+#[must_use]
+pub fn resume_dir(request: &DownloadRequest) -> Option<PathBuf> {
+    let key = request.downloader_option::<String>(RESUME_KEY_OPTION)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update("\n");
+    hasher.update(request.url.url().as_str());
+
+    Some(Config::cache_dir().join(format!("{RESUME_DIR_PREFIX}{:x}", hasher.finalize())))
+}
+
+/// What the files in the resume directory are named with.
+///
+/// Picked the first time and kept in the directory, so the next try uses the same names
+/// and the downloaded files still get unique names.
+#[must_use]
+pub fn file_identifier(resume_dir: &Path) -> String {
+    let path = resume_dir.join(IDENTIFIER_FILE_NAME);
+
+    if let Ok(id) = std::fs::read_to_string(&path) {
+        let id = id.trim();
+        if !id.is_empty() {
+            return id.to_string();
+        }
+    }
+
+    let id = time_id();
+    if let Err(e) = std::fs::write(&path, &id) {
+        warn!(
+            ?e,
+            ?path,
+            "Failed to save the identifier of the resumable download"
+        );
+    }
+
+    id
+}
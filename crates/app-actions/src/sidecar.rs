@@ -0,0 +1,105 @@
+use std::path::{Path, PathBuf};
+
+use app_config::Config;
+use chrono::{DateTime, Utc};
+use http::{header, HeaderMap};
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::downloaders::{DownloadResult, DownloaderAttempt, MediaMetadata};
+
+/// Key of the downloader option that says whether to write a sidecar next to the downloaded files.
+///
+/// If it's not set, the `write_sidecars` config decides.
+pub const WRITE_SIDECAR_OPTION: &str = "write-sidecar";
+
+/// Added to the name of the downloaded file
+const SIDECAR_SUFFIX: &str = ".info.json";
+
+/// Not written to the sidecar, since they're secrets
+const SECRET_HEADERS: &[header::HeaderName] = &[
+    header::AUTHORIZATION,
+    header::COOKIE,
+    header::PROXY_AUTHORIZATION,
+    header::SET_COOKIE,
+];
+
+/// Where a downloaded file came from, written next to it
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Sidecar<'a> {
+    /// The URL that was asked to be downloaded
+    source_url: &'a str,
+    /// The URL the file itself was downloaded from
+    download_url: &'a str,
+    extractor: Option<&'a str>,
+    downloader_chain: &'a [DownloaderAttempt],
+    downloaded_at: DateTime<Utc>,
+    #[serde(with = "http_serde::header_map")]
+    request_headers: HeaderMap,
+    #[serde(with = "http_serde::header_map")]
+    response_headers: HeaderMap,
+    sha256: Option<&'a str>,
+    metadata: Option<&'a MediaMetadata>,
+}
+
+/// `<file>.info.json`
+#[must_use]
+pub fn sidecar_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(SIDECAR_SUFFIX);
+
+    path.into()
+}
+
+/// Writes the sidecar next to the downloaded file, if the request asks for it.
+///
+/// Failing to write it doesn't fail the download, the file is still there.
+pub async fn write_sidecar_if_wanted(
+    result: &DownloadResult,
+    source_url: &str,
+    extractor: Option<&str>,
+) {
+    let wanted = result
+        .request
+        .downloader_option::<bool>(WRITE_SIDECAR_OPTION)
+        .unwrap_or_else(|| Config::global().download.write_sidecars);
+    if !wanted {
+        return;
+    }
+
+    let sidecar = Sidecar {
+        source_url,
+        download_url: result.request.url.url().as_str(),
+        extractor,
+        downloader_chain: &result.downloader_chain,
+        downloaded_at: Utc::now(),
+        request_headers: without_secrets(result.request.url.headers()),
+        response_headers: without_secrets(&result.response_headers),
+        sha256: result.sha256.as_deref(),
+        metadata: result.metadata.as_ref(),
+    };
+
+    let path = sidecar_path(&result.path);
+    debug!(?path, "Writing sidecar");
+
+    let res = match serde_json::to_vec_pretty(&sidecar) {
+        Ok(data) => tokio::fs::write(&path, data)
+            .await
+            .map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    if let Err(e) = res {
+        warn!(?e, ?path, "Failed to write sidecar");
+    }
+}
+
+fn without_secrets(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in SECRET_HEADERS {
+        headers.remove(name);
+    }
+
+    headers
+}
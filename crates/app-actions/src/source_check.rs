@@ -0,0 +1,58 @@
+use app_errors::{AppError, NetworkError};
+use tracing::debug;
+use url::Url;
+
+use crate::{common::request::Client, extractors};
+
+/// Whether the content a URL points to is still there
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceStatus {
+    Live,
+    /// Deleted, suspended or taken down
+    Dead,
+}
+
+/// Checks whether the content at `url` is still available.
+///
+/// Only statuses that clearly mean the content is gone (eg. 404 or 410) count as dead.
+/// Returns an error if it can't be told, eg. because the site is down or rate limiting.
+#[tracing::instrument]
+pub async fn check_source(url: &Url) -> Result<SourceStatus, AppError> {
+    match extractors::extract_info(&url.into()).await {
+        Ok(_) => {}
+        Err(e) if is_gone(&e) => return Ok(SourceStatus::Dead),
+        Err(e) => return Err(e),
+    }
+
+    // Some extractors pass the URL through without looking at it, so the page is checked too
+    let res = Client::base()?
+        .get(url.as_str())
+        .send()
+        .await
+        .map_err(NetworkError::from)?;
+
+    debug!(status = ?res.status(), "Checked source page");
+
+    match res.error_for_status() {
+        Ok(_) => Ok(SourceStatus::Live),
+        Err(e) => {
+            let e = AppError::from(NetworkError::from(e));
+
+            if is_gone(&e) {
+                Ok(SourceStatus::Dead)
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Extractors report deleted and suspended content themselves,
+/// other sites only say so with the status of the page
+const fn is_gone(error: &AppError) -> bool {
+    match error {
+        AppError::SourceGone(_) => true,
+        AppError::Network(e) => matches!(e.status(), Some(404 | 410 | 451)),
+        _ => false,
+    }
+}
@@ -0,0 +1,181 @@
+use std::{fmt, path::Path, process::Stdio, time::Duration};
+
+use app_config::Config;
+use app_errors::AppError;
+use app_helpers::temp_dir::TempDir;
+use futures::future::join_all;
+use tokio::process::Command;
+use tracing::{debug, info};
+use url::Url;
+
+use crate::{
+    downloaders::{DownloaderOptions, AVAILABLE_DOWNLOADERS},
+    extractors::{self, AVAILABLE_EXTRACTORS},
+    fixers::ENABLED_FIXERS,
+};
+
+/// Downloaded by the self-test if no URL is configured
+const DEFAULT_SELF_TEST_URL: &str = "https://www.wikipedia.org/static/favicon/wikipedia.ico";
+
+/// How long a program gets to print its version before it's skipped
+const VERSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// What the app can do with how it's set up, to be logged when it starts
+#[derive(Debug, Clone)]
+pub struct Capabilities {
+    pub extractors: Vec<&'static str>,
+    pub downloaders: Vec<&'static str>,
+    pub fixers: Vec<&'static str>,
+    /// Every program with its version, or `None` if it wasn't found
+    pub tools: Vec<(&'static str, Option<String>)>,
+    /// Where the downloaded files are kept
+    pub storage: String,
+    /// Where the tasks wait to be run
+    pub queue: String,
+}
+impl Capabilities {
+    #[must_use]
+    pub async fn detect<S, Q>(storage: S, queue: Q) -> Self
+    where
+        S: Into<String> + Send,
+        Q: Into<String> + Send,
+    {
+        let tools = join_all(
+            Config::global()
+                .dependency_paths
+                .programs()
+                .into_iter()
+                .map(|(name, path)| async move {
+                    let version = match path {
+                        Some(path) => Some(
+                            tool_version(name, path)
+                                .await
+                                .unwrap_or_else(|| "unknown version".to_string()),
+                        ),
+                        None => None,
+                    };
+
+                    (name, version)
+                }),
+        )
+        .await;
+
+        Self {
+            extractors: AVAILABLE_EXTRACTORS.iter().map(|x| x.name()).collect(),
+            downloaders: AVAILABLE_DOWNLOADERS.iter().map(|x| x.name()).collect(),
+            fixers: ENABLED_FIXERS.iter().map(|x| x.name()).collect(),
+            tools,
+            storage: storage.into(),
+            queue: queue.into(),
+        }
+    }
+}
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tools = self
+            .tools
+            .iter()
+            .map(|(name, version)| {
+                version.as_ref().map_or_else(
+                    || format!("{name} (not found)"),
+                    |version| format!("{name} ({version})"),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        writeln!(f, "Extractors: {}", self.extractors.join(", "))?;
+        writeln!(f, "Downloaders: {}", self.downloaders.join(", "))?;
+        writeln!(f, "Fixers: {}", self.fixers.join(", "))?;
+        writeln!(f, "Tools: {}", tools.join(", "))?;
+        writeln!(f, "Storage: {}", self.storage)?;
+        write!(f, "Queue: {}", self.queue)
+    }
+}
+
+/// The first line the program prints about its version
+async fn tool_version(name: &str, path: &Path) -> Option<String> {
+    let version_arg = match name {
+        "ffmpeg" | "ffprobe" | "imagemagick" => "-version",
+        "rclone" => "version",
+        _ => "--version",
+    };
+
+    let output = tokio::time::timeout(
+        VERSION_TIMEOUT,
+        Command::new(path)
+            .arg(version_arg)
+            .stdin(Stdio::null())
+            .kill_on_drop(true)
+            .output(),
+    )
+    .await
+    .ok()?
+    .ok()?;
+
+    let version = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(str::trim)
+        .find(|x| !x.is_empty())?
+        .to_string();
+
+    Some(version)
+}
+
+/// Downloads the configured test file if `--self-test` is set,
+/// so a broken setup (eg. missing tools or a bad proxy) fails right away
+pub async fn self_test_if_enabled() -> Result<(), AppError> {
+    let config = &Config::global().run;
+    if !config.self_test {
+        return Ok(());
+    }
+
+    let url = config.self_test_url.clone().unwrap_or_else(|| {
+        Url::parse(DEFAULT_SELF_TEST_URL).expect("Invalid default self-test URL")
+    });
+
+    self_test(&url).await
+}
+
+/// Downloads the URL into a temporary directory and checks that something was downloaded.
+///
+/// Skips the download archive and cache, since those would answer without downloading anything.
+pub async fn self_test(url: &Url) -> Result<(), AppError> {
+    info!(url = ?url.as_str(), "Running self-test");
+
+    let download_dir = TempDir::in_tmp_with_prefix("downloader-hub_self-test-")
+        .map_err(|e| format!("Failed to create temporary directory for self-test: {e:?}"))?;
+
+    let info = extractors::extract_info(&url.into()).await?;
+    let results =
+        crate::download_extracted(&info, download_dir.path(), &DownloaderOptions::new(), None)
+            .await;
+
+    debug!(?results, "Self-test results");
+
+    let mut first_error = None;
+    for result in results {
+        match result {
+            Ok(result) => {
+                let size = tokio::fs::metadata(&result.path)
+                    .await
+                    .map(|x| x.len())
+                    .unwrap_or_default();
+
+                if size > 0 {
+                    info!(size, "Self-test passed");
+                    return Ok(());
+                }
+            }
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(first_error.unwrap_or_else(|| {
+        AppError::other(format!(
+            "Self-test didn't download anything from {url}",
+            url = url.as_str()
+        ))
+    }))
+}
@@ -0,0 +1,15 @@
+use crate::downloaders::DownloaderOptions;
+
+/// Key of the downloader option that says whether the thumbnail or cover art of the source
+/// is embedded into the downloaded file, eg. as the MP4 cover or the ID3 front cover.
+///
+/// Used by the yt-dlp and music downloaders.
+/// If it's not set, yt-dlp doesn't embed thumbnails and the music downloader embeds covers.
+pub const EMBED_THUMBNAIL_OPTION: &str = "embed-thumbnail";
+
+#[must_use]
+pub fn embed_thumbnail(options: &DownloaderOptions) -> Option<bool> {
+    options
+        .get(EMBED_THUMBNAIL_OPTION)
+        .and_then(serde_json::Value::as_bool)
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+use crate::byte_size::parse_bytes;
+
+/// A transfer rate in bytes per second, eg. `2M` or `500K`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ByteRate(u64);
+
+impl ByteRate {
+    #[must_use]
+    pub const fn from_bytes_per_second(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    #[must_use]
+    pub const fn bytes_per_second(self) -> u64 {
+        self.0
+    }
+
+    /// Units are powers of 1024, the same as yt-dlp's `--limit-rate`.
+    /// Eg. 500K, 2M, 1.5MiB, 100KB/s, 4096
+    pub fn parse_str(arg: &str) -> Result<Self, ByteRateParseError> {
+        let arg = arg.trim();
+        let arg = arg
+            .strip_suffix("/s")
+            .or_else(|| arg.strip_suffix("/S"))
+            .unwrap_or(arg);
+
+        parse_bytes(arg, "rate")
+            .map(Self)
+            .map_err(ByteRateParseError)
+    }
+}
+
+impl TryFrom<String> for ByteRate {
+    type Error = ByteRateParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<ByteRate> for String {
+    fn from(val: ByteRate) -> Self {
+        val.0.to_string()
+    }
+}
+
+impl std::fmt::Display for ByteRate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteRateParseError(String);
+impl std::fmt::Display for ByteRateParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for ByteRateParseError {}
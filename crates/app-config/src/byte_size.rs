@@ -0,0 +1,102 @@
+use serde::{Deserialize, Serialize};
+
+/// A size in bytes, eg. `2G` or `500M`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ByteSize(u64);
+
+impl ByteSize {
+    #[must_use]
+    pub const fn from_bytes(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    #[must_use]
+    pub const fn bytes(self) -> u64 {
+        self.0
+    }
+
+    /// Units are powers of 1024, the same as yt-dlp's `--max-filesize`.
+    /// Eg. 500K, 2G, 1.5GiB, 100MB, 4096
+    pub fn parse_str(arg: &str) -> Result<Self, ByteSizeParseError> {
+        parse_bytes(arg, "size")
+            .map(Self)
+            .map_err(ByteSizeParseError)
+    }
+}
+
+/// Parses a number of bytes with an optional unit that's a power of 1024.
+/// `kind` is what the number is in the error messages, eg. `size`.
+pub(crate) fn parse_bytes(arg: &str, kind: &str) -> Result<u64, String> {
+    let arg = arg.trim().to_lowercase();
+
+    let num = arg
+        .chars()
+        .take_while(|x| x.is_ascii_digit() || *x == '.')
+        .collect::<String>();
+
+    if num.is_empty() {
+        return Err(format!("invalid {kind} (no number found): {arg}"));
+    }
+
+    let unit = arg.chars().skip(num.len()).collect::<String>();
+    let unit = unit.trim();
+    let unit = unit
+        .strip_suffix("ib")
+        .or_else(|| unit.strip_suffix('b'))
+        .unwrap_or(unit);
+
+    let num = num
+        .parse::<f64>()
+        .map_err(|_| format!("invalid {kind} (invalid number): {arg}"))?;
+
+    let multiplier: u64 = match unit {
+        "" => 1,
+        "k" => 1024,
+        "m" => 1024 * 1024,
+        "g" => 1024 * 1024 * 1024,
+        _ => return Err(format!("invalid {kind} (invalid unit): {arg}")),
+    };
+
+    #[allow(
+        clippy::cast_precision_loss,
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss
+    )]
+    let bytes = (num * multiplier as f64).round() as u64;
+
+    if bytes == 0 {
+        return Err(format!("invalid {kind} (must be more than 0): {arg}"));
+    }
+
+    Ok(bytes)
+}
+
+impl TryFrom<String> for ByteSize {
+    type Error = ByteSizeParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<ByteSize> for String {
+    fn from(val: ByteSize) -> Self {
+        val.0.to_string()
+    }
+}
+
+impl std::fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteSizeParseError(String);
+impl std::fmt::Display for ByteSizeParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for ByteSizeParseError {}
@@ -1,12 +1,12 @@
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser};
 use serde::{Deserialize, Serialize};
 
-use crate::{common, conditional};
+use crate::{common, conditional, config_file::ConfigFile};
 
 /// A hub for downloading media from various platforms,
 /// process the results and aggregate them in one place.
 #[derive(Debug, Clone, Serialize, Deserialize, Parser)]
-#[clap(disable_help_flag = true)]
+#[clap(disable_help_flag = true, subcommand_negates_reqs = true)]
 pub struct CliArgs {
     /// Print help
     #[clap(action = ArgAction::Help, long)]
@@ -24,6 +24,47 @@ pub struct CliArgs {
     #[command(flatten)]
     pub task: common::TaskConfig,
 
+    #[command(flatten)]
+    pub temp: common::TempConfig,
+
+    #[command(flatten)]
+    pub download: common::DownloadConfig,
+
+    #[command(flatten)]
+    pub handlers: common::HandlerConfig,
+
+    #[command(flatten)]
+    pub domain_filter: common::DomainFilterConfig,
+
+    #[command(flatten)]
+    pub blocklist: common::BlocklistConfig,
+
+    #[command(flatten)]
+    pub proxy: common::ProxyConfig,
+
+    #[command(flatten)]
+    pub cookies: common::CookieConfig,
+
+    #[command(flatten)]
+    pub credentials: common::CredentialsConfig,
+
     #[command(flatten)]
     pub conditional: conditional::ConditionalConfig,
 }
+
+impl CliArgs {
+    /// Parses the arguments, with the values in the config file as the defaults
+    #[must_use]
+    pub fn parse_with_config_file() -> Self {
+        let mut command = Self::command();
+
+        match ConfigFile::load() {
+            Ok(file) => command = file.apply_defaults(command),
+            Err(e) => eprintln!("Ignoring the config file: {e}"),
+        }
+
+        let mut matches = command.get_matches();
+
+        Self::from_arg_matches_mut(&mut matches).unwrap_or_else(|e| e.exit())
+    }
+}
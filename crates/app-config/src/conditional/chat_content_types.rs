@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::common::ContentType;
+
+/// The only kinds of content a chat can download, eg. `-1001234=image|video`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ChatContentTypes {
+    /// The Telegram ID of the chat, negative for groups
+    pub chat_id: i64,
+    pub allowed: Vec<ContentType>,
+}
+
+impl ChatContentTypes {
+    /// `<chat id>=<content type>|<content type>...`, eg. `-1001234=image|video`
+    pub fn parse_str(arg: &str) -> Result<Self, ChatContentTypesParseError> {
+        let (chat_id, allowed) = arg.trim().split_once('=').ok_or_else(|| {
+            ChatContentTypesParseError(format!("invalid chat content types (missing `=`): {arg}"))
+        })?;
+
+        let chat_id = chat_id.trim().parse::<i64>().map_err(|_| {
+            ChatContentTypesParseError(format!(
+                "invalid chat content types (invalid chat id `{chat_id}`): {arg}"
+            ))
+        })?;
+
+        let allowed = allowed
+            .split('|')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                ContentType::from_str(x, true).map_err(|_| {
+                    ChatContentTypesParseError(format!(
+                        "invalid chat content types (unknown content type `{x}`): {arg}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if allowed.is_empty() {
+            return Err(ChatContentTypesParseError(format!(
+                "invalid chat content types (no content types): {arg}"
+            )));
+        }
+
+        Ok(Self { chat_id, allowed })
+    }
+}
+
+impl TryFrom<String> for ChatContentTypes {
+    type Error = ChatContentTypesParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<ChatContentTypes> for String {
+    fn from(val: ChatContentTypes) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for ChatContentTypes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let allowed = self
+            .allowed
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join("|");
+
+        write!(f, "{}={allowed}", self.chat_id)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ChatContentTypesParseError(String);
+impl std::fmt::Display for ChatContentTypesParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for ChatContentTypesParseError {}
@@ -1,16 +1,21 @@
 use std::path::PathBuf;
 
-use clap::{Args, ValueHint};
+use clap::{Args, Subcommand, ValueHint};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use crate::validators::{
-    directory::{validate_is_writable_directory, value_parser_parse_valid_directory},
-    file::{validate_is_files, value_parser_parse_valid_file},
+use crate::{
+    common::PlaylistFormat,
+    timeframe::Timeframe,
+    validators::{
+        directory::{validate_is_writable_directory, value_parser_parse_valid_directory},
+        file::{validate_is_files, value_parser_parse_valid_file},
+    },
 };
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, Args, Validate)]
 #[clap(next_help_heading = "Cli options")]
+#[allow(clippy::struct_excessive_bools)]
 pub struct CliConfig {
     #[clap(flatten)]
     #[validate(nested)]
@@ -34,6 +39,137 @@ pub struct CliConfig {
     /// The standard format is `<id>.<original_name>.<extension>`.
     #[clap(long, action = clap::ArgAction::SetTrue)]
     pub and_rename: bool,
+
+    /// Generate a playlist file for URLs that download multiple files
+    ///
+    /// The playlist is placed in the output directory and
+    /// lists the files in the order the site returned them.
+    #[clap(long, value_enum)]
+    pub playlist: Option<PlaylistFormat>,
+
+    /// Also download subtitles in the given language for the URLs
+    ///
+    /// Subtitles are machine-translated if they are not available
+    /// in the language and a translation API is configured.
+    #[clap(long, value_name = "LANG")]
+    pub subs: Option<String>,
+
+    /// Embed the thumbnail or cover art of the source into the downloaded files
+    ///
+    /// Only works for downloads done by yt-dlp and for songs.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub embed_thumbnail: bool,
+
+    /// Only download a screenshot of the posts, without their media
+    ///
+    /// Fails for URLs that can't be screenshotted.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub screenshot: bool,
+
+    /// Archive the whole post for each URL instead of just downloading its media
+    ///
+    /// Each post gets its own folder in the output directory with the media,
+    /// the screenshot, the post text as `post.md` and the metadata as `post.json`.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub archive_post: bool,
+
+    /// Only print what was found for the URLs as JSON, without downloading anything
+    ///
+    /// Has the metadata (eg. title and duration) and the direct links to the media.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub extract_only: bool,
+
+    /// Print a line of JSON for each downloaded file once it's fixed
+    ///
+    /// Has the URL it was downloaded from, where the file ended up,
+    /// what the site says about it (eg. title and uploader) if that's known,
+    /// and the downloaders that were tried for it.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub json: bool,
+
+    /// Don't show the download progress bar
+    ///
+    /// It's only shown when the output is a terminal anyway.
+    #[clap(long, action = clap::ArgAction::SetTrue)]
+    pub no_progress: bool,
+
+    /// Write what happens during the run to a file, as a line of JSON for each event
+    ///
+    /// Has when each URL starts and finishes extracting and downloading,
+    /// snapshots of the download progress, when each file starts and finishes fixing,
+    /// and the final results.
+    /// Meant for tools built on top of the CLI that want to follow along.
+    #[clap(long, value_name = "PATH", value_hint = ValueHint::FilePath)]
+    pub event_log: Option<PathBuf>,
+
+    #[clap(subcommand)]
+    #[serde(skip)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum CliCommand {
+    /// Manage the files left in the cache directory
+    #[clap(subcommand)]
+    Cache(CacheCommand),
+
+    /// Manage the defaults kept in the config file
+    ///
+    /// The options given on the command line or in the environment
+    /// take precedence over the ones in the file.
+    #[clap(subcommand)]
+    Config(ConfigCommand),
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum ConfigCommand {
+    /// Print the value of an option, or the whole file if no option is given
+    Get {
+        /// Eg. `output_directory` for `--output-directory`
+        key: Option<String>,
+    },
+
+    /// Set the default value of an option
+    ///
+    /// Eg. `config set output_directory ~/Downloads/memes`
+    Set {
+        /// Eg. `output_directory` for `--output-directory`
+        key: String,
+
+        /// Options that can be given multiple times take multiple values
+        #[clap(required = true)]
+        values: Vec<String>,
+    },
+
+    /// Remove an option from the config file
+    Unset {
+        /// Eg. `output_directory` for `--output-directory`
+        key: String,
+    },
+
+    /// Open the config file in `$VISUAL` or `$EDITOR`
+    Edit,
+
+    /// Print where the config file is
+    Path,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum CacheCommand {
+    /// Show how much space the cache directory takes up, by what the files are for
+    Stats,
+
+    /// Remove the files in the cache directory that weren't changed for a while
+    Prune {
+        /// Eg. 30d, 2 weeks, 12h
+        #[clap(long, value_parser = Timeframe::parse_str, default_value = "30d")]
+        older_than: Timeframe,
+    },
+
+    /// Remove everything in the cache directory
+    ///
+    /// Files that are in use by a running download are removed as well.
+    Clear,
 }
 
 #[derive(Debug, Clone, Default, Args, Serialize, Deserialize, Validate)]
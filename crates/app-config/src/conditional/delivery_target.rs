@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+
+/// A cloud storage destination the bot can deliver results to using rclone,
+/// eg. `drive=gdrive:memes` or `s3@1234+5678=s3:bucket/downloads`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DeliveryTarget {
+    /// What the users call the target in `/deliver <name>`
+    pub name: String,
+    /// The rclone remote path (eg. `s3:bucket/path` or `webdav:uploads`)
+    pub remote: String,
+    /// Telegram IDs of the users that can deliver to the target besides the owner
+    pub users: Vec<u64>,
+}
+
+impl DeliveryTarget {
+    /// `<name>=<remote>` for a target only the owner can use
+    /// or `<name>@<user id>+<user id>=<remote>` to also let the listed users use it.
+    pub fn parse_str(arg: &str) -> Result<Self, DeliveryTargetParseError> {
+        let (name, remote) = arg.trim().split_once('=').ok_or_else(|| {
+            DeliveryTargetParseError(format!("invalid delivery target (missing `=`): {arg}"))
+        })?;
+
+        let (name, users) = match name.split_once('@') {
+            Some((name, users)) => (name.trim(), Some(users)),
+            None => (name.trim(), None),
+        };
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(DeliveryTargetParseError(format!(
+                "invalid delivery target (name must be a single word): {arg}"
+            )));
+        }
+
+        let remote = remote.trim();
+        if !remote.contains(':') {
+            return Err(DeliveryTargetParseError(format!(
+                "invalid delivery target (remote must be an rclone path like `remote:path`): \
+                 {arg}"
+            )));
+        }
+
+        let users = users
+            .unwrap_or_default()
+            .split('+')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.parse::<u64>().map_err(|_| {
+                    DeliveryTargetParseError(format!(
+                        "invalid delivery target (invalid user id `{x}`): {arg}"
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            name: name.to_lowercase(),
+            remote: remote.to_string(),
+            users,
+        })
+    }
+
+    /// Joins the file name onto the remote path
+    #[must_use]
+    pub fn remote_path(&self, file_name: &str) -> String {
+        if self.remote.ends_with(':') || self.remote.ends_with('/') {
+            format!("{}{}", self.remote, file_name)
+        } else {
+            format!("{}/{}", self.remote, file_name)
+        }
+    }
+}
+
+impl TryFrom<String> for DeliveryTarget {
+    type Error = DeliveryTargetParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<DeliveryTarget> for String {
+    fn from(val: DeliveryTarget) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for DeliveryTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.users.is_empty() {
+            let users = self
+                .users
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join("+");
+            write!(f, "@{users}")?;
+        }
+        write!(f, "={}", self.remote)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeliveryTargetParseError(String);
+impl std::fmt::Display for DeliveryTargetParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for DeliveryTargetParseError {}
@@ -2,8 +2,12 @@ use clap::Args;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
+#[cfg(feature = "telegram-bot")]
+pub mod chat_content_types;
 #[cfg(feature = "cli")]
 pub mod cli;
+#[cfg(feature = "telegram-bot")]
+pub mod delivery_target;
 #[cfg(feature = "server")]
 pub mod server;
 #[cfg(feature = "telegram-bot")]
@@ -1,6 +1,6 @@
 use clap::{Args, ValueHint};
 use serde::{Deserialize, Serialize};
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
 use crate::validators::{
     str::value_parser_ensure_min_length,
@@ -20,6 +20,10 @@ pub struct ServerConfig {
     #[clap(flatten)]
     #[validate(nested)]
     pub app: AppConfig,
+
+    #[clap(flatten)]
+    #[validate(nested)]
+    pub storage: StorageConfig,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, Args, Validate)]
@@ -45,6 +49,22 @@ pub struct ServerRunConfig {
     #[arg(long, env = "DOWNLOADER_HUB_SIGNING_KEY", value_parser = value_parser_ensure_min_length(32))]
     #[validate(length(min = 32))]
     pub signing_key: String,
+
+    /// Size of the chunks (in bytes) that downloaded files are read in when serving them.
+    /// Bigger chunks mean fewer reads per file, at the cost of more memory per client.
+    /// Worth raising if serving large files takes up a lot of CPU.
+    ///
+    /// Defaults to 256 KiB.
+    #[arg(long, env = "DOWNLOADER_HUB_FILE_CHUNK_SIZE", value_parser = clap::value_parser!(u64).range(4096..))]
+    pub file_chunk_size: Option<u64>,
+}
+impl ServerRunConfig {
+    #[must_use]
+    pub fn file_chunk_size(&self) -> usize {
+        self.file_chunk_size
+            .and_then(|x| usize::try_from(x).ok())
+            .unwrap_or(256 * 1024)
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, Args, Validate)]
@@ -68,3 +88,27 @@ pub struct AppConfig {
     #[validate(custom(function = "validate_is_absolute_url"))]
     pub public_url: String,
 }
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Args, Validate)]
+#[clap(next_help_heading = "Storage options")]
+pub struct StorageConfig {
+    /// `rclone` remote to keep the downloaded files in instead of the local disk,
+    /// eg. `s3:bucket/downloads` for an S3 or `MinIO` bucket set up in the `rclone` config.
+    ///
+    /// Files are only on the local disk while they're downloaded and fixed,
+    /// and are streamed to the remote once they're done.
+    /// If not set, files are kept in the download folders of the clients.
+    #[arg(long, env = "DOWNLOADER_HUB_STORAGE_REMOTE", value_hint = ValueHint::Other)]
+    #[validate(custom(function = "validate_is_rclone_remote"))]
+    pub storage_remote: Option<String>,
+}
+
+fn validate_is_rclone_remote(remote: &str) -> Result<(), ValidationError> {
+    if remote.contains(':') {
+        Ok(())
+    } else {
+        Err(ValidationError::new(
+            "Storage remote must be an rclone path like `remote:bucket/path`",
+        ))
+    }
+}
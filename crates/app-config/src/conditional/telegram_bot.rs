@@ -1,14 +1,18 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
 
 use clap::{Args, ValueHint};
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-use crate::validators::directory::{
-    validate_is_writable_directory, value_parser_parse_valid_directory,
+use super::{chat_content_types::ChatContentTypes, delivery_target::DeliveryTarget};
+use crate::{
+    common::ContentType,
+    timeframe::Timeframe,
+    validators::directory::{validate_is_writable_directory, value_parser_parse_valid_directory},
 };
 
 pub const OFFICIAL_API_URL: &str = "https://api.telegram.org";
+const DEFAULT_FILE_ID_CACHE_TTL: Duration = Duration::from_hours(7 * 24);
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, Args, Validate)]
 #[clap(next_help_heading = "Telegram bot options")]
@@ -48,6 +52,52 @@ pub struct TelegramBotConfig {
     /// If left empty, a generic default text will be used.
     #[arg(long = "telegram-about", value_name = "ABOUT", env = "DOWNLOADER_HUB_TELEGRAM_ABOUT", value_hint = ValueHint::Other)]
     pub about: Option<String>,
+
+    /// Cloud storage the results can be delivered to with `/deliver <name>`, using rclone.
+    ///
+    /// Given as `<name>=<rclone remote>` for targets only the owner can use
+    /// or `<name>@<user id>+<user id>=<rclone remote>` to also let the listed users use them.
+    /// The remotes have to be set up in the rclone config, eg. `s3=s3:bucket/downloads`.
+    #[arg(long = "telegram-delivery-target", value_name = "TARGET", value_parser = DeliveryTarget::parse_str, env = "DOWNLOADER_HUB_TELEGRAM_DELIVERY_TARGETS", value_delimiter = ',', value_hint = ValueHint::Other)]
+    #[serde(default)]
+    pub delivery_targets: Vec<DeliveryTarget>,
+
+    /// URL of a downloader-hub instance to hand the downloads off to.
+    ///
+    /// If set, links are downloaded and fixed by the hub and the bot only fetches the results,
+    /// so a single hub can serve multiple bots.
+    /// If not set, everything is processed locally.
+    #[arg(long = "telegram-hub-url", value_name = "HUB_URL", env = "DOWNLOADER_HUB_TELEGRAM_HUB_URL", value_hint = ValueHint::Url, requires = "hub_client_key")]
+    #[validate(url)]
+    pub hub_url: Option<String>,
+
+    /// The client key the bot uses to authenticate with the hub.
+    #[arg(long = "telegram-hub-client-key", value_name = "CLIENT_KEY", env = "DOWNLOADER_HUB_TELEGRAM_HUB_CLIENT_KEY", value_hint = ValueHint::Other)]
+    pub hub_client_key: Option<String>,
+
+    /// File to remember the Telegram file IDs of the uploaded results in.
+    ///
+    /// Files Telegram already has are sent by their ID instead of being uploaded again,
+    /// and links whose results are all known aren't downloaded at all.
+    /// Will be created if it doesn't exist.
+    /// If not set, every result is uploaded.
+    #[arg(long = "telegram-file-id-cache", value_name = "FILE", env = "DOWNLOADER_HUB_TELEGRAM_FILE_ID_CACHE", value_hint = ValueHint::FilePath)]
+    pub file_id_cache: Option<PathBuf>,
+
+    /// How long the results of a link are sent from the file ID cache
+    /// before the link is downloaded again. Defaults to 7 days.
+    ///
+    /// Uses the same format as `--yt-dlp-update-interval`.
+    #[arg(long = "telegram-file-id-cache-ttl", value_name = "TIMEFRAME", value_parser = Timeframe::parse_str, env = "DOWNLOADER_HUB_TELEGRAM_FILE_ID_CACHE_TTL")]
+    pub file_id_cache_ttl: Option<Timeframe>,
+
+    /// The only kinds of content some chats can download, instead of `--allowed-content-type`.
+    ///
+    /// Given as `<chat id>=<content type>|<content type>...`, eg. `-1001234=image|video`
+    /// for a group that only wants images and videos.
+    #[arg(long = "telegram-chat-content-types", value_name = "CHAT_CONTENT_TYPES", value_parser = ChatContentTypes::parse_str, env = "DOWNLOADER_HUB_TELEGRAM_CHAT_CONTENT_TYPES", value_delimiter = ',', value_hint = ValueHint::Other)]
+    #[serde(default)]
+    pub chat_content_types: Vec<ChatContentTypes>,
 }
 impl TelegramBotConfig {
     #[must_use]
@@ -59,4 +109,34 @@ impl TelegramBotConfig {
     pub fn owner_link(&self) -> Option<String> {
         self.owner_id.map(|id| format!("tg://user?id={}", id))
     }
+
+    /// The hub URL and client key, if the downloads are handed off to a hub
+    #[must_use]
+    pub fn hub(&self) -> Option<(&str, &str)> {
+        Some((self.hub_url.as_deref()?, self.hub_client_key.as_deref()?))
+    }
+
+    #[must_use]
+    pub fn file_id_cache_ttl(&self) -> Duration {
+        self.file_id_cache_ttl
+            .map_or(DEFAULT_FILE_ID_CACHE_TTL, Duration::from)
+    }
+
+    /// The content types the chat is limited to, if it has its own
+    #[must_use]
+    pub fn content_types_for(&self, chat_id: i64) -> Option<&[ContentType]> {
+        self.chat_content_types
+            .iter()
+            .find(|x| x.chat_id == chat_id)
+            .map(|x| x.allowed.as_slice())
+    }
+
+    /// The delivery targets the user is allowed to use
+    pub fn delivery_targets_for(&self, user_id: u64) -> impl Iterator<Item = &DeliveryTarget> {
+        let is_owner = self.owner_id == Some(user_id);
+
+        self.delivery_targets
+            .iter()
+            .filter(move |x| is_owner || x.users.contains(&user_id))
+    }
 }
@@ -0,0 +1,214 @@
+use std::{fs, io, path::PathBuf};
+
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use directories::BaseDirs;
+use toml::{Table, Value};
+
+use crate::{cli::CliArgs, Config};
+
+pub const CONFIG_FILE_NAME: &str = "cli.toml";
+
+/// Defaults for the command line options of the CLI, kept in `cli.toml` in the config directory.
+///
+/// Eg. `output_directory = "~/Downloads/memes"`.
+/// The server and the bot don't read it, they're set up with options and the environment.
+///
+/// The keys are the names of the options (`output_directory` for `--output-directory`).
+/// Options given on the command line or in the environment take precedence over the file.
+#[derive(Debug, Clone, Default)]
+pub struct ConfigFile {
+    values: Table,
+}
+
+impl ConfigFile {
+    #[must_use]
+    pub fn path() -> Option<PathBuf> {
+        Config::config_dir().map(|x| x.join(CONFIG_FILE_NAME))
+    }
+
+    /// Empty if the file doesn't exist yet
+    pub fn load() -> Result<Self, ConfigFileError> {
+        let path = Self::path()
+            .ok_or_else(|| ConfigFileError("no config directory for this user".to_string()))?;
+
+        let contents = match fs::read_to_string(&path) {
+            Ok(x) => x,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => {
+                return Err(ConfigFileError(format!(
+                    "failed to read {}: {e}",
+                    path.display()
+                )))
+            }
+        };
+
+        let values = contents
+            .parse::<Table>()
+            .map_err(|e| ConfigFileError(format!("failed to parse {}: {e}", path.display())))?;
+
+        Ok(Self { values })
+    }
+
+    /// Writes the file, creating the config directory if needed.
+    ///
+    /// Returns the path of the file.
+    pub fn save(&self) -> Result<PathBuf, ConfigFileError> {
+        let path = Self::path()
+            .ok_or_else(|| ConfigFileError("no config directory for this user".to_string()))?;
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .map_err(|e| ConfigFileError(format!("failed to create {}: {e}", dir.display())))?;
+        }
+
+        fs::write(&path, self.to_string())
+            .map_err(|e| ConfigFileError(format!("failed to write {}: {e}", path.display())))?;
+
+        Ok(path)
+    }
+
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<Vec<String>> {
+        self.values.get(&option_id(key)).and_then(value_strings)
+    }
+
+    /// Sets the option to the values, if there is such an option and they're valid for it
+    pub fn set(&mut self, key: &str, values: Vec<String>) -> Result<(), ConfigFileError> {
+        let id = option_id(key);
+        check_values(&CliArgs::command(), &id, &values)?;
+
+        let value = match <[String; 1]>::try_from(values) {
+            Ok([x]) => Value::String(x),
+            Err(values) => Value::Array(values.into_iter().map(Value::String).collect()),
+        };
+
+        self.values.insert(id, value);
+
+        Ok(())
+    }
+
+    /// Whether the option was set
+    pub fn remove(&mut self, key: &str) -> bool {
+        self.values.remove(&option_id(key)).is_some()
+    }
+
+    /// Uses the values in the file as the defaults of the command's options.
+    ///
+    /// Options that the command doesn't have or that have invalid values are skipped,
+    /// so a broken file doesn't stop the program from starting (eg. to fix it).
+    #[must_use]
+    pub fn apply_defaults(&self, mut command: Command) -> Command {
+        for (key, value) in &self.values {
+            let id = option_id(key);
+
+            let Some(values) = value_strings(value) else {
+                eprintln!("Ignoring {key:?} in the config file: values must be strings, numbers or booleans");
+                continue;
+            };
+
+            if command.get_arguments().all(|x| x.get_id() != id.as_str()) {
+                continue;
+            }
+
+            if let Err(e) = check_values(&command, &id, &values) {
+                eprintln!("Ignoring {key:?} in the config file: {e}");
+                continue;
+            }
+
+            command = command.mut_arg(id, |arg| arg.default_values(values));
+        }
+
+        command
+    }
+}
+
+impl std::fmt::Display for ConfigFile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.values)
+    }
+}
+
+/// `output-directory` and `--output-directory` are accepted for `output_directory`
+fn option_id(key: &str) -> String {
+    key.trim().trim_start_matches("--").replace('-', "_")
+}
+
+fn value_strings(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(values) => values.iter().map(scalar_string).collect(),
+        x => scalar_string(x).map(|x| vec![x]),
+    }
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(x) => Some(expand_home(x)),
+        Value::Integer(x) => Some(x.to_string()),
+        Value::Float(x) => Some(x.to_string()),
+        Value::Boolean(x) => Some(x.to_string()),
+        Value::Datetime(x) => Some(x.to_string()),
+        Value::Array(_) | Value::Table(_) => None,
+    }
+}
+
+/// `~/Downloads` is in the home directory, same as the shell would have it
+fn expand_home(value: &str) -> String {
+    let Some(rest) = value.strip_prefix("~/") else {
+        return value.to_string();
+    };
+
+    BaseDirs::new().map_or_else(
+        || value.to_string(),
+        |x| x.home_dir().join(rest).to_string_lossy().to_string(),
+    )
+}
+
+fn find_option<'a>(command: &'a Command, id: &str) -> Result<&'a Arg, ConfigFileError> {
+    command
+        .get_arguments()
+        .filter(|x| !x.is_positional() && x.get_long().is_some())
+        .find(|x| x.get_id() == id)
+        .ok_or_else(|| ConfigFileError(format!("unknown option {id:?}")))
+}
+
+fn check_values(command: &Command, id: &str, values: &[String]) -> Result<(), ConfigFileError> {
+    let arg = find_option(command, id)?;
+
+    if values.is_empty() {
+        return Err(ConfigFileError(format!("no value given for {id:?}")));
+    }
+
+    if values.len() > 1 && !matches!(arg.get_action(), ArgAction::Append) {
+        return Err(ConfigFileError(format!("{id:?} only takes one value")));
+    }
+
+    // The option is parsed on its own, so the rest of the required options aren't needed
+    let long = arg.get_long().unwrap_or_default();
+    let check = Command::new(command.get_name().to_string())
+        .no_binary_name(true)
+        .arg(arg.clone().required(false));
+
+    for value in values {
+        check
+            .clone()
+            .try_get_matches_from([format!("--{long}={}", expand_home(value))])
+            .map_err(|e| {
+                // Only the first line, without the usage and help hints clap adds
+                let e = e.to_string();
+                let e = e.lines().next().unwrap_or_default();
+
+                ConfigFileError(e.trim_start_matches("error: ").to_string())
+            })?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigFileError(String);
+impl std::fmt::Display for ConfigFileError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for ConfigFileError {}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+
+/// Cookies to send with the requests to a domain and its subdomains,
+/// eg. `example.com=sessionid=abc123; csrftoken=def456`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DomainCookie {
+    pub domain: String,
+    /// `<name>=<value>` pairs, like in the `Cookie` header
+    pub cookies: Vec<(String, String)>,
+}
+
+impl DomainCookie {
+    /// `<domain>=<name>=<value>; <name>=<value>`
+    pub fn parse_str(arg: &str) -> Result<Self, DomainCookieParseError> {
+        let (domain, cookies) = arg.trim().split_once('=').ok_or_else(|| {
+            DomainCookieParseError(format!("invalid domain cookie (missing `=`): {arg}"))
+        })?;
+
+        let domain = domain
+            .trim()
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .to_lowercase();
+        if domain.is_empty() {
+            return Err(DomainCookieParseError(format!(
+                "invalid domain cookie (no domain): {arg}"
+            )));
+        }
+
+        let cookies = cookies
+            .split(';')
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+            .map(|x| {
+                x.split_once('=')
+                    .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                    .filter(|(k, _)| !k.is_empty())
+                    .ok_or_else(|| {
+                        DomainCookieParseError(format!(
+                            "invalid domain cookie (`{x}` is not `<name>=<value>`): {arg}"
+                        ))
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if cookies.is_empty() {
+            return Err(DomainCookieParseError(format!(
+                "invalid domain cookie (no cookies): {arg}"
+            )));
+        }
+
+        Ok(Self { domain, cookies })
+    }
+}
+
+impl TryFrom<String> for DomainCookie {
+    type Error = DomainCookieParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<DomainCookie> for String {
+    fn from(val: DomainCookie) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for DomainCookie {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cookies = self
+            .cookies
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        write!(f, "{}={}", self.domain, cookies)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DomainCookieParseError(String);
+impl std::fmt::Display for DomainCookieParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for DomainCookieParseError {}
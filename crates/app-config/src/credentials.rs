@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+
+/// How to authenticate with a site
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Credentials {
+    /// `basic:<username>:<password>`, sent as HTTP basic auth
+    Basic { username: String, password: String },
+    /// `bearer:<token>`, sent in the `Authorization` header, eg. for Mastodon access tokens
+    Bearer(String),
+    /// `header:<name>:<value>`, for API keys sent in a header of their own
+    Header { name: String, value: String },
+}
+
+impl std::fmt::Display for Credentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Basic { username, password } => write!(f, "basic:{username}:{password}"),
+            Self::Bearer(token) => write!(f, "bearer:{token}"),
+            Self::Header { name, value } => write!(f, "header:{name}:{value}"),
+        }
+    }
+}
+
+/// Credentials to use for the requests to a domain and its subdomains,
+/// eg. `mastodon.example=bearer:abc123` or `example.com=basic:user:hunter2`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DomainCredentials {
+    pub domain: String,
+    pub credentials: Credentials,
+}
+
+impl DomainCredentials {
+    /// `<domain>=basic:<username>:<password>`, `<domain>=bearer:<token>`
+    /// or `<domain>=header:<name>:<value>`
+    pub fn parse_str(arg: &str) -> Result<Self, DomainCredentialsParseError> {
+        let (domain, credentials) = arg.trim().split_once('=').ok_or_else(|| {
+            DomainCredentialsParseError(format!("invalid domain credentials (missing `=`): {arg}"))
+        })?;
+
+        let domain = domain
+            .trim()
+            .trim_start_matches("*.")
+            .trim_start_matches('.')
+            .to_lowercase();
+        if domain.is_empty() {
+            return Err(DomainCredentialsParseError(format!(
+                "invalid domain credentials (no domain): {arg}"
+            )));
+        }
+
+        let (kind, rest) = credentials.trim().split_once(':').ok_or_else(|| {
+            DomainCredentialsParseError(format!(
+                "invalid domain credentials (missing `<kind>:`): {arg}"
+            ))
+        })?;
+
+        // Passwords and header values can have `:` in them, so only the first one splits
+        let pair = |what: &str| {
+            rest.split_once(':')
+                .map(|(k, v)| (k.trim().to_string(), v.to_string()))
+                .filter(|(k, v)| !k.is_empty() && !v.is_empty())
+                .ok_or_else(|| {
+                    DomainCredentialsParseError(format!(
+                        "invalid domain credentials (expected `{kind}:{what}`): {arg}"
+                    ))
+                })
+        };
+
+        let credentials = match kind.trim().to_lowercase().as_str() {
+            "basic" => {
+                let (username, password) = pair("<username>:<password>")?;
+                Credentials::Basic { username, password }
+            }
+            "bearer" if !rest.trim().is_empty() => Credentials::Bearer(rest.trim().to_string()),
+            "bearer" => {
+                return Err(DomainCredentialsParseError(format!(
+                    "invalid domain credentials (no token): {arg}"
+                )))
+            }
+            "header" => {
+                let (name, value) = pair("<name>:<value>")?;
+                Credentials::Header { name, value }
+            }
+            _ => {
+                return Err(DomainCredentialsParseError(format!(
+                    "invalid domain credentials (kind must be `basic`, `bearer` or `header`): {arg}"
+                )))
+            }
+        };
+
+        Ok(Self {
+            domain,
+            credentials,
+        })
+    }
+}
+
+impl TryFrom<String> for DomainCredentials {
+    type Error = DomainCredentialsParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<DomainCredentials> for String {
+    fn from(val: DomainCredentials) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for DomainCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}={}", self.domain, self.credentials)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DomainCredentialsParseError(String);
+impl std::fmt::Display for DomainCredentialsParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for DomainCredentialsParseError {}
@@ -0,0 +1,117 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::timeframe::Timeframe;
+
+/// A limit on the downloads from the domains matching a pattern,
+/// eg. `*.tiktok.com=1` or `reddit.com=30/min`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DomainLimit {
+    /// Glob pattern for the domain (eg. `*.reddit.com`)
+    pub domain: String,
+    pub limit: DomainLimitKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainLimitKind {
+    /// At most this many downloads at the same time
+    Concurrent(usize),
+    /// At most `count` downloads started in any `per` long window
+    Rate { count: usize, per: Duration },
+}
+
+impl DomainLimit {
+    /// `<domain>=<count>` for concurrent downloads
+    /// or `<domain>=<count>/<timeframe>` for downloads per timeframe.
+    /// Eg. `*.tiktok.com=1`, `reddit.com=30/min`, `example.com=5/10s`
+    pub fn parse_str(arg: &str) -> Result<Self, DomainLimitParseError> {
+        let (domain, limit) = arg.trim().split_once('=').ok_or_else(|| {
+            DomainLimitParseError(format!("invalid domain limit (missing `=`): {arg}"))
+        })?;
+
+        let domain = domain.trim().to_lowercase();
+        if domain.is_empty() {
+            return Err(DomainLimitParseError(format!(
+                "invalid domain limit (no domain): {arg}"
+            )));
+        }
+
+        let (count, per) = match limit.split_once('/') {
+            Some((count, per)) => (count, Some(per.trim())),
+            None => (limit, None),
+        };
+
+        let count = count
+            .trim()
+            .parse::<usize>()
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| {
+                DomainLimitParseError(format!(
+                    "invalid domain limit (count must be a number more than 0): {arg}"
+                ))
+            })?;
+
+        let limit = match per {
+            None => DomainLimitKind::Concurrent(count),
+            Some(per) => {
+                // `30/min` is the same as `30/1min`
+                let per = if per.starts_with(|x: char| x.is_ascii_digit()) {
+                    per.to_string()
+                } else {
+                    format!("1{per}")
+                };
+
+                let per = Timeframe::parse_str(&per)
+                    .map(Duration::from)
+                    .map_err(|e| DomainLimitParseError(format!("invalid domain limit ({e})")))?;
+
+                if per.is_zero() {
+                    return Err(DomainLimitParseError(format!(
+                        "invalid domain limit (timeframe must be more than 0): {arg}"
+                    )));
+                }
+
+                DomainLimitKind::Rate { count, per }
+            }
+        };
+
+        Ok(Self { domain, limit })
+    }
+}
+
+impl TryFrom<String> for DomainLimit {
+    type Error = DomainLimitParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<DomainLimit> for String {
+    fn from(val: DomainLimit) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for DomainLimit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.limit {
+            DomainLimitKind::Concurrent(count) => write!(f, "{}={}", self.domain, count),
+            DomainLimitKind::Rate { count, per } => {
+                write!(f, "{}={}/{}ms", self.domain, count, per.as_millis())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DomainLimitParseError(String);
+impl std::fmt::Display for DomainLimitParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for DomainLimitParseError {}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+/// The downloaders to try one after the other for URLs on some domains,
+/// eg. `*.twitter.com=YtDlp|Generic`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct DownloaderChain {
+    /// Pattern of the domains the chain is for, eg. `*.twitter.com`
+    pub domain: String,
+    /// Names of the downloaders, in the order they're tried
+    pub downloaders: Vec<String>,
+    /// Whether the chain is used as-is instead of trying
+    /// the downloader the extractor asked for first
+    pub overrides_preferred: bool,
+}
+
+impl DownloaderChain {
+    /// `<domain>=<downloader>|<downloader>...`, eg. `*.twitter.com=YtDlp|Generic`
+    ///
+    /// A `!` before the downloaders, eg. `*.twitter.com=!YtDlp|Generic`,
+    /// makes the chain override the downloader the extractor asked for.
+    pub fn parse_str(arg: &str) -> Result<Self, DownloaderChainParseError> {
+        let (domain, downloaders) = arg.trim().split_once('=').ok_or_else(|| {
+            DownloaderChainParseError(format!("invalid downloader chain (missing `=`): {arg}"))
+        })?;
+
+        let domain = domain.trim().to_string();
+        if domain.is_empty() {
+            return Err(DownloaderChainParseError(format!(
+                "invalid downloader chain (no domain): {arg}"
+            )));
+        }
+
+        let (overrides_preferred, downloaders) = downloaders
+            .trim()
+            .strip_prefix('!')
+            .map_or((false, downloaders), |rest| (true, rest));
+
+        let downloaders = downloaders
+            .split('|')
+            .map(str::trim)
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        if downloaders.iter().any(String::is_empty) {
+            return Err(DownloaderChainParseError(format!(
+                "invalid downloader chain (empty downloader name): {arg}"
+            )));
+        }
+
+        Ok(Self {
+            domain,
+            downloaders,
+            overrides_preferred,
+        })
+    }
+}
+
+impl TryFrom<String> for DownloaderChain {
+    type Error = DownloaderChainParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<DownloaderChain> for String {
+    fn from(val: DownloaderChain) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for DownloaderChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let marker = if self.overrides_preferred { "!" } else { "" };
+
+        write!(
+            f,
+            "{}={}{}",
+            self.domain,
+            marker,
+            self.downloaders.join("|")
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DownloaderChainParseError(String);
+impl std::fmt::Display for DownloaderChainParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for DownloaderChainParseError {}
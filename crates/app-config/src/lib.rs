@@ -1,20 +1,39 @@
+pub mod byte_rate;
+pub mod byte_size;
+pub mod check;
 pub mod cli;
 pub mod common;
 pub mod conditional;
+pub mod config_file;
+pub mod cookie;
+pub mod credentials;
+pub mod domain_limit;
+pub mod downloader_chain;
+pub mod proxy;
+pub mod retry_policy;
 pub mod timeframe;
 pub mod validators;
 
-use std::{env, path::PathBuf};
+use std::{
+    env,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        LazyLock,
+    },
+};
 
 use clap::Parser;
 use cli::CliArgs;
 use common::DumpConfigType;
 use directories::ProjectDirs;
-use once_cell::sync::Lazy;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
 
-static CONFIG: Lazy<Config> = Lazy::new(Config::new);
+static CONFIG: LazyLock<Config> = LazyLock::new(Config::new);
+
+/// Whether the defaults in the config file are used, set by the programs that have one
+static USE_CONFIG_FILE: AtomicBool = AtomicBool::new(false);
 
 pub static APPLICATION_NAME: &str = "downloader-hub";
 pub static ORGANIZATION_NAME: &str = "allypost";
@@ -39,6 +58,37 @@ pub struct Config {
 
     #[validate(nested)]
     pub task: common::TaskConfig,
+
+    /// Where temporary files go and how much space they can take up
+    #[validate(nested)]
+    pub temp: common::TempConfig,
+
+    #[validate(nested)]
+    pub download: common::DownloadConfig,
+
+    /// Extractors, downloaders and fixers that are turned off
+    #[validate(nested)]
+    pub handlers: common::HandlerConfig,
+
+    /// Domains that URLs are accepted or refused from
+    #[validate(nested)]
+    pub domain_filter: common::DomainFilterConfig,
+
+    /// Hashes of files that are deleted as soon as they're downloaded
+    #[validate(nested)]
+    pub blocklist: common::BlocklistConfig,
+
+    /// Proxies that requests are sent through
+    #[validate(nested)]
+    pub proxy: common::ProxyConfig,
+
+    /// Cookies that are sent with the requests
+    #[validate(nested)]
+    pub cookies: common::CookieConfig,
+
+    /// Credentials that are used for the requests to some domains
+    #[validate(nested)]
+    pub credentials: common::CredentialsConfig,
 }
 impl Config {
     #[must_use]
@@ -47,6 +97,13 @@ impl Config {
         &CONFIG
     }
 
+    /// Takes the option defaults from the config file (see [`config_file::ConfigFile`]).
+    ///
+    /// Has to be called before the config is first used to have any effect.
+    pub fn use_config_file() {
+        USE_CONFIG_FILE.store(true, Ordering::Relaxed);
+    }
+
     #[must_use]
     #[inline]
     pub fn config_dir() -> Option<PathBuf> {
@@ -127,11 +184,16 @@ impl Config {
     }
 
     fn new() -> Self {
-        let args = CliArgs::parse();
+        let args = if USE_CONFIG_FILE.load(Ordering::Relaxed) {
+            CliArgs::parse_with_config_file()
+        } else {
+            CliArgs::parse()
+        };
 
         Self::default()
             .merge_with_cli(args)
             .resolve_paths()
+            .check_if_needed()
             .validate_self()
             .dump_if_needed()
     }
@@ -142,6 +204,14 @@ impl Config {
         self.endpoint = args.endpoint;
         self.conditional = args.conditional;
         self.task = args.task;
+        self.temp = args.temp;
+        self.download = args.download;
+        self.handlers = args.handlers;
+        self.domain_filter = args.domain_filter;
+        self.blocklist = args.blocklist;
+        self.proxy = args.proxy;
+        self.cookies = args.cookies;
+        self.credentials = args.credentials;
 
         self
     }
@@ -157,6 +227,20 @@ impl Config {
         self
     }
 
+    fn check_if_needed(self) -> Self {
+        if self.run.check_config {
+            let report = check::ConfigReport::for_config(&self);
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("Failed to serialize config report")
+            );
+            std::process::exit(i32::from(!report.valid));
+        }
+
+        self
+    }
+
     #[inline]
     fn validate_self(self) -> Self {
         Self::validate_config_and_exit(self)
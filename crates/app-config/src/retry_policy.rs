@@ -0,0 +1,178 @@
+use std::{ops::RangeInclusive, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::timeframe::Timeframe;
+
+/// Used when the policy doesn't say how long to wait
+const DEFAULT_BACKOFF_BASE: Duration = Duration::from_secs(1);
+/// Waiting longer than this is unlikely to help more than failing and trying again later
+const MAX_BACKOFF: Duration = Duration::from_mins(1);
+
+/// How many times a downloader tries a download before giving up,
+/// eg. `Generic=3`, `YtDlp=4/2s` or `*=3/500ms@429|503`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RetryPolicy {
+    /// Name of the downloader, or `*` for all of them
+    pub downloader: String,
+    /// Including the first one
+    pub max_attempts: u32,
+    /// How long to wait before the first retry, doubled for every one after it
+    pub backoff_base: Duration,
+    /// The status codes to retry on.
+    /// If empty, the usual transient errors (timeouts, 408, 429, 5xx) are retried.
+    pub retry_on_status: Vec<RangeInclusive<u16>>,
+}
+
+impl RetryPolicy {
+    /// `<downloader>=<max attempts>[/<backoff base>][@<status>|<status>...]`.
+    /// Statuses can be ranges, eg. `500-599`.
+    /// Eg. `Generic=3`, `YtDlp=4/2s`, `*=3/500ms@429|500-599`
+    pub fn parse_str(arg: &str) -> Result<Self, RetryPolicyParseError> {
+        let (downloader, policy) = arg.trim().split_once('=').ok_or_else(|| {
+            RetryPolicyParseError(format!("invalid retry policy (missing `=`): {arg}"))
+        })?;
+
+        let downloader = downloader.trim().to_string();
+        if downloader.is_empty() {
+            return Err(RetryPolicyParseError(format!(
+                "invalid retry policy (no downloader): {arg}"
+            )));
+        }
+
+        let (policy, statuses) = match policy.split_once('@') {
+            Some((policy, statuses)) => (policy, Some(statuses)),
+            None => (policy, None),
+        };
+
+        let (attempts, backoff) = match policy.split_once('/') {
+            Some((attempts, backoff)) => (attempts, Some(backoff)),
+            None => (policy, None),
+        };
+
+        let max_attempts = attempts
+            .trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|x| *x > 0)
+            .ok_or_else(|| {
+                RetryPolicyParseError(format!(
+                    "invalid retry policy (attempts must be a number more than 0): {arg}"
+                ))
+            })?;
+
+        let backoff_base = match backoff {
+            Some(backoff) => Timeframe::parse_str(backoff)
+                .map(Duration::from)
+                .map_err(|e| RetryPolicyParseError(format!("invalid retry policy ({e})")))?,
+            None => DEFAULT_BACKOFF_BASE,
+        };
+
+        let retry_on_status = statuses
+            .into_iter()
+            .flat_map(|x| x.split('|'))
+            .map(|x| parse_status_range(x.trim()))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                RetryPolicyParseError(format!(
+                    "invalid retry policy (statuses must be numbers or ranges like 500-599): {arg}"
+                ))
+            })?;
+
+        Ok(Self {
+            downloader,
+            max_attempts,
+            backoff_base,
+            retry_on_status,
+        })
+    }
+
+    #[must_use]
+    pub fn applies_to(&self, downloader: &str) -> bool {
+        self.downloader == "*" || self.downloader.eq_ignore_ascii_case(downloader)
+    }
+
+    /// `None` if the status codes aren't set and the usual transient errors should be retried
+    #[must_use]
+    pub fn retries_status(&self, status: u16) -> Option<bool> {
+        if self.retry_on_status.is_empty() {
+            return None;
+        }
+
+        Some(self.retry_on_status.iter().any(|x| x.contains(&status)))
+    }
+
+    /// How long to wait after the `attempt`th (starting at 1) attempt failed
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+
+        self.backoff_base.saturating_mul(factor).min(MAX_BACKOFF)
+    }
+}
+
+fn parse_status_range(s: &str) -> Option<RangeInclusive<u16>> {
+    match s.split_once('-') {
+        Some((from, to)) => {
+            let from = from.trim().parse().ok()?;
+            let to = to.trim().parse().ok()?;
+
+            (from <= to).then_some(from..=to)
+        }
+        None => s.parse().ok().map(|x| x..=x),
+    }
+}
+
+impl TryFrom<String> for RetryPolicy {
+    type Error = RetryPolicyParseError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        Self::parse_str(&value)
+    }
+}
+
+impl From<RetryPolicy> for String {
+    fn from(val: RetryPolicy) -> Self {
+        val.to_string()
+    }
+}
+
+impl std::fmt::Display for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}={}/{}ms",
+            self.downloader,
+            self.max_attempts,
+            self.backoff_base.as_millis()
+        )?;
+
+        if !self.retry_on_status.is_empty() {
+            let statuses = self
+                .retry_on_status
+                .iter()
+                .map(|x| {
+                    if x.start() == x.end() {
+                        x.start().to_string()
+                    } else {
+                        format!("{}-{}", x.start(), x.end())
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            write!(f, "@{}", statuses.join("|"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicyParseError(String);
+impl std::fmt::Display for RetryPolicyParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+impl std::error::Error for RetryPolicyParseError {}
@@ -11,3 +11,16 @@ pub fn value_parser_ensure_min_length(min_len: usize) -> impl clap::builder::Typ
         Ok(s.to_string())
     }
 }
+
+/// Two-letter ISO 3166-1 country codes, eg. `US` or `de`. Uppercased.
+pub fn parse_country_code(s: &str) -> Result<String, String> {
+    let s = s.trim();
+
+    if s.len() != 2 || !s.chars().all(|x| x.is_ascii_alphabetic()) {
+        return Err(format!(
+            "Invalid country code: {s:?}. Expected a two-letter code like `US`"
+        ));
+    }
+
+    Ok(s.to_uppercase())
+}
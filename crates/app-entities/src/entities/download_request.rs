@@ -27,6 +27,8 @@ pub struct Model {
     pub app_meta: Json,
     pub created_at: DateTimeWithTimeZone,
     pub updated_at: DateTimeWithTimeZone,
+    pub source_checked_at: Option<DateTimeWithTimeZone>,
+    pub source_dead_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
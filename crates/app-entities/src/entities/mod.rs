@@ -5,4 +5,5 @@ pub mod prelude;
 pub mod client;
 pub mod download_request;
 pub mod download_result;
+pub mod file_hash;
 pub mod sea_orm_active_enums;
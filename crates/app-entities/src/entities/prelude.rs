@@ -2,5 +2,5 @@
 
 pub use super::{
     client::Entity as Client, download_request::Entity as DownloadRequest,
-    download_result::Entity as DownloadResult,
+    download_result::Entity as DownloadResult, file_hash::Entity as FileHash,
 };
@@ -6,8 +6,13 @@ use serde::Serialize;
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Copy, Serialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "item_status")]
 pub enum ItemStatus {
+    #[sea_orm(string_value = "blocked")]
+    Blocked,
     #[sea_orm(string_value = "failed")]
     Failed,
+    /// Some of the files were downloaded, but not all of them
+    #[sea_orm(string_value = "partial")]
+    Partial,
     #[sea_orm(string_value = "pending")]
     Pending,
     #[sea_orm(string_value = "processing")]
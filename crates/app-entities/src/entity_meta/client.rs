@@ -47,4 +47,38 @@ impl client::Model {
 
         Ok(download_dir)
     }
+
+    /// Per-client overrides of the media policy (max resolution, preferred codecs)
+    /// stored under `mediaPolicy` in the app meta.
+    #[must_use]
+    pub fn media_policy(&self) -> Option<serde_json::Value> {
+        self.app_meta
+            .get("mediaPolicy")
+            .filter(|x| !x.is_null())
+            .cloned()
+    }
+
+    /// The only content types the client can download (eg. `["image"]`),
+    /// stored under `allowedContentTypes` in the app meta.
+    #[must_use]
+    pub fn allowed_content_types(&self) -> Option<serde_json::Value> {
+        self.app_meta
+            .get("allowedContentTypes")
+            .filter(|x| !x.is_null())
+            .cloned()
+    }
+
+    /// Whether the client belongs to the owner of the instance,
+    /// stored as `owner` in the app meta. The admin always is.
+    ///
+    /// Only the admin can set it, with `PATCH /v1/admin/clients/<api key>/settings`.
+    #[must_use]
+    pub fn is_owner(&self) -> bool {
+        ["owner", "admin"].iter().any(|key| {
+            self.app_meta
+                .get(key)
+                .and_then(serde_json::Value::as_bool)
+                .unwrap_or_default()
+        })
+    }
 }
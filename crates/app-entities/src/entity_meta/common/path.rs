@@ -6,6 +6,8 @@ use serde::{Deserialize, Serialize};
 #[serde(rename_all = "camelCase")]
 pub enum AppPath {
     LocalAbsolute(PathBuf),
+    /// A file in the storage remote, as an rclone path (eg. `s3:bucket/downloads/file.mp4`)
+    Remote(String),
     None,
 }
 
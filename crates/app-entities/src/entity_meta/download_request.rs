@@ -14,6 +14,14 @@ impl download_request::Model {
     pub fn meta(&self) -> Option<DownloadRequestMeta> {
         serde_json::from_value(self.meta.clone()).ok()
     }
+
+    /// Whether the source was still there when it was last checked.
+    /// `None` if it hasn't been checked yet.
+    #[must_use]
+    pub fn is_source_live(&self) -> Option<bool> {
+        self.source_checked_at
+            .map(|_| self.source_dead_at.is_none())
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
@@ -30,6 +38,7 @@ const fn default_get() -> http::Method {
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[allow(clippy::struct_excessive_bools)]
 pub struct DownloadRequestMeta {
     #[serde(default)]
     pub request: DownloadRequestMetaRequest,
@@ -37,6 +46,43 @@ pub struct DownloadRequestMeta {
     pub tags: Vec<String>,
     #[serde(default)]
     pub skip_fixing: bool,
+    /// What to download when there are multiple variants, eg. `best`, `1080p` or `audio-only`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Bundle the whole post (media, screenshot, text and metadata) into a single zip
+    #[serde(default)]
+    pub archive_post: bool,
+    /// Embed the thumbnail or cover art of the source into the downloaded file
+    #[serde(default)]
+    pub embed_thumbnail: bool,
+    /// Put songs in an `artist/album/track` folder structure with an album playlist.
+    /// Defaults to `--music-library-layout`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub library_layout: Option<bool>,
+    /// Embed the cover art into songs. Defaults to `--music-embed-cover`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embed_cover: Option<bool>,
+    /// Record the live stream until it ends instead of only downloading what's there
+    #[serde(default)]
+    pub record_live: bool,
+    /// Arguments to pass to yt-dlp, eg. `--live-from-start`.
+    /// Only allowed for the owner, since yt-dlp can be made to run commands with them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub yt_dlp_extra_args: Vec<String>,
+    /// Country to pretend to be in for region-locked videos, as a two-letter code, eg. `US`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub geo_bypass_country: Option<String>,
+    /// Also get the subtitles, either as `.srt` files next to the video (`sidecar`)
+    /// or muxed into it (`embed`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtitles: Option<String>,
+    /// Languages of the subtitles, eg. `en` or `de`, or `all`. Defaults to `en`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtitle_langs: Vec<String>,
+    /// If the URL is of a playlist, channel or profile,
+    /// its items are downloaded as requests of their own
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<DownloadRequestMetaCollection>,
     #[serde(default)]
     pub other: HashMap<String, serde_json::Value>,
 }
@@ -46,6 +92,23 @@ impl From<DownloadRequestMeta> for serde_json::Value {
     }
 }
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadRequestMetaCollection {
+    /// How many items are downloaded. Defaults to 50, and is capped by `--max-collection-items`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_items: Option<usize>,
+    /// Only items published on or after this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_after: Option<chrono::NaiveDate>,
+    /// Only items published on or before this day
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_before: Option<chrono::NaiveDate>,
+    /// `source` (the order the site lists them in), `newest` or `oldest`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order: Option<String>,
+}
+
 // pub type DownloadRequestMeta = serde_json::Map<String, serde_json::Value>;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -3,6 +3,15 @@ use serde::{Deserialize, Serialize};
 use super::common::path::AppPath;
 use crate::{download_result, sea_orm_active_enums::ItemStatus};
 
+/// Key in the meta of what the site says about the downloaded media.
+/// Kept next to the [`DownloadResultMeta`] instead of in it, since the result has both.
+pub const MEDIA_METADATA_KEY: &str = "mediaMetadata";
+/// Key in the meta of the downloaders that were tried for the file, in order
+pub const DOWNLOADER_CHAIN_KEY: &str = "downloaderChain";
+/// Key in the meta of the URL the file was downloaded from,
+/// so the files that are already there can be skipped when the request is retried
+pub const ITEM_URL_KEY: &str = "itemUrl";
+
 impl download_result::Model {
     #[must_use]
     pub fn path(&self) -> Option<AppPath> {
@@ -11,7 +20,31 @@ impl download_result::Model {
 
     #[must_use]
     pub fn meta(&self) -> Option<DownloadResultMeta> {
-        serde_json::from_value(self.meta.clone()).ok()
+        let mut meta = self.meta.clone();
+        if let Some(meta) = meta.as_object_mut() {
+            meta.remove(MEDIA_METADATA_KEY);
+            meta.remove(DOWNLOADER_CHAIN_KEY);
+            meta.remove(ITEM_URL_KEY);
+        }
+
+        serde_json::from_value(meta).ok()
+    }
+
+    #[must_use]
+    pub fn media_metadata(&self) -> Option<&serde_json::Value> {
+        self.meta.get(MEDIA_METADATA_KEY)
+    }
+
+    #[must_use]
+    pub fn downloader_chain(&self) -> Option<&serde_json::Value> {
+        self.meta.get(DOWNLOADER_CHAIN_KEY)
+    }
+
+    #[must_use]
+    pub fn item_url(&self) -> Option<&str> {
+        self.meta
+            .get(ITEM_URL_KEY)
+            .and_then(serde_json::Value::as_str)
     }
 }
 
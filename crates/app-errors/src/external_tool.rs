@@ -0,0 +1,39 @@
+use thiserror::Error;
+
+/// Errors of the programs downloads are handed off to, eg. `yt-dlp` or `ffmpeg`
+#[derive(Debug, Clone, Error)]
+pub enum ExternalToolError {
+    #[error("{tool} is not available: {message}")]
+    Unavailable { tool: String, message: String },
+    #[error("{tool} failed: {message}")]
+    Failed { tool: String, message: String },
+}
+impl ExternalToolError {
+    pub fn unavailable<T, M>(tool: T, message: M) -> Self
+    where
+        T: Into<String>,
+        M: Into<String>,
+    {
+        Self::Unavailable {
+            tool: tool.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn failed<T, M>(tool: T, message: M) -> Self
+    where
+        T: Into<String>,
+        M: Into<String>,
+    {
+        Self::Failed {
+            tool: tool.into(),
+            message: message.into(),
+        }
+    }
+
+    /// Missing tools won't show up on their own
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        matches!(self, Self::Failed { .. })
+    }
+}
@@ -0,0 +1,92 @@
+mod external_tool;
+mod network;
+mod source_gone;
+mod unsupported_source;
+mod user_input;
+
+pub use external_tool::ExternalToolError;
+pub use network::NetworkError;
+pub use source_gone::SourceGone;
+use thiserror::Error;
+pub use unsupported_source::UnsupportedSource;
+pub use user_input::UserInputError;
+
+/// Errors shared by everything that handles downloads.
+///
+/// The kind of error decides whether the work is retried
+/// and what the user gets told about it.
+#[derive(Debug, Clone, Error)]
+pub enum AppError {
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    ExternalTool(#[from] ExternalToolError),
+    #[error(transparent)]
+    UnsupportedSource(#[from] UnsupportedSource),
+    #[error(transparent)]
+    SourceGone(#[from] SourceGone),
+    #[error(transparent)]
+    UserInput(#[from] UserInputError),
+    /// Anything that doesn't fit the other kinds, eg. failing to write a file
+    #[error("{0}")]
+    Other(String),
+}
+impl AppError {
+    pub fn other<T>(message: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self::Other(message.into())
+    }
+
+    /// Whether trying again later could succeed
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::Network(e) => e.is_retryable(),
+            Self::ExternalTool(e) => e.is_retryable(),
+            Self::UnsupportedSource(_) | Self::SourceGone(_) | Self::UserInput(_) => false,
+            Self::Other(_) => true,
+        }
+    }
+
+    /// A message that can be shown to the user as-is.
+    ///
+    /// `None` if the error is internal and the user should only be told that something went wrong.
+    #[must_use]
+    pub fn user_message(&self) -> Option<String> {
+        match self {
+            Self::Network(e) => Some(e.user_message()),
+            Self::UnsupportedSource(e) => Some(e.to_string()),
+            Self::SourceGone(e) => Some(e.to_string()),
+            Self::UserInput(e) => Some(e.to_string()),
+            Self::ExternalTool(_) | Self::Other(_) => None,
+        }
+    }
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::Other(message)
+    }
+}
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        Self::Other(message.to_string())
+    }
+}
+impl From<reqwest::Error> for AppError {
+    fn from(err: reqwest::Error) -> Self {
+        Self::Network(err.into())
+    }
+}
+impl From<std::io::Error> for AppError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Other(format!("IO error: {err:?}"))
+    }
+}
+impl From<tokio::task::JoinError> for AppError {
+    fn from(err: tokio::task::JoinError) -> Self {
+        Self::Other(format!("Failed to join task: {err:?}"))
+    }
+}
@@ -0,0 +1,72 @@
+use thiserror::Error;
+
+#[derive(Debug, Clone, Error)]
+pub enum NetworkError {
+    #[error("Request timed out: {0}")]
+    Timeout(String),
+    #[error("Failed to connect: {0}")]
+    Connect(String),
+    #[error("Got status {status}: {message}")]
+    Status { status: u16, message: String },
+    #[error("Request failed: {0}")]
+    Request(String),
+}
+impl NetworkError {
+    #[must_use]
+    pub const fn status(&self) -> Option<u16> {
+        match self {
+            Self::Status { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Timeouts, connection issues, rate limits and server errors usually go away on their own
+    #[must_use]
+    pub const fn is_retryable(&self) -> bool {
+        match self {
+            Self::Timeout(_) | Self::Connect(_) | Self::Request(_) => true,
+            Self::Status { status, .. } => matches!(*status, 408 | 429 | 500..=599),
+        }
+    }
+
+    #[must_use]
+    pub fn user_message(&self) -> String {
+        match self {
+            Self::Timeout(_) => "The site took too long to respond".to_string(),
+            Self::Connect(_) => "Couldn't connect to the site".to_string(),
+            Self::Status {
+                status: 401 | 403, ..
+            } => "The site refused access to the content".to_string(),
+            Self::Status {
+                status: 404 | 410, ..
+            } => "The content doesn't exist anymore".to_string(),
+            Self::Status { status: 429, .. } => {
+                "The site is rate limiting requests, try again later".to_string()
+            }
+            Self::Status { status, .. } => format!("The site responded with an error ({status})"),
+            Self::Request(_) => "Failed to get the content from the site".to_string(),
+        }
+    }
+}
+
+impl From<reqwest::Error> for NetworkError {
+    fn from(err: reqwest::Error) -> Self {
+        let message = format!("{err:?}");
+
+        if err.is_timeout() {
+            return Self::Timeout(message);
+        }
+
+        if err.is_connect() {
+            return Self::Connect(message);
+        }
+
+        match err.status() {
+            Some(status) => Self::Status {
+                status: status.as_u16(),
+                message,
+            },
+            None => Self::Request(message),
+        }
+    }
+}
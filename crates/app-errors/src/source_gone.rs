@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+/// The content was deleted, suspended or taken down, so trying again won't bring it back
+#[derive(Debug, Clone, Error)]
+#[error("No longer available: {0}")]
+pub struct SourceGone(pub String);
+impl SourceGone {
+    pub fn new<T>(reason: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self(reason.into())
+    }
+}
@@ -0,0 +1,14 @@
+use thiserror::Error;
+
+/// Nothing knows how to get anything from the source
+#[derive(Debug, Clone, Error)]
+#[error("Unsupported source: {0}")]
+pub struct UnsupportedSource(pub String);
+impl UnsupportedSource {
+    pub fn new<T>(reason: T) -> Self
+    where
+        T: Into<String>,
+    {
+        Self(reason.into())
+    }
+}
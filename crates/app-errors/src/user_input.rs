@@ -0,0 +1,21 @@
+use thiserror::Error;
+
+/// The request itself is the problem, so it won't work no matter how often it's retried
+#[derive(Debug, Clone, Error)]
+pub enum UserInputError {
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    #[error("Not allowed: {0}")]
+    NotAllowed(String),
+    /// The downloaded file matched the blocklist
+    #[error("{0}")]
+    Blocked(String),
+    #[error("{0}")]
+    Invalid(String),
+    /// The file is over the download size limit
+    #[error("Too large: {0}")]
+    TooLarge(String),
+    /// The URL is in the download archive
+    #[error("Already downloaded: {0}")]
+    AlreadyDownloaded(String),
+}
@@ -0,0 +1,24 @@
+/// Eg. `512 B`, `1.5 MiB` or `2.0 GiB`
+#[must_use]
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = UNITS[0];
+
+    for next_unit in &UNITS[1..] {
+        if value < 1024.0 {
+            break;
+        }
+
+        value /= 1024.0;
+        unit = next_unit;
+    }
+
+    format!("{value:.1} {unit}")
+}
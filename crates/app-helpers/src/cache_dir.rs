@@ -0,0 +1,87 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use app_config::Config;
+
+/// The prefixes the temporary files and directories are named with, before their ID,
+/// and the names of the files that are kept around.
+/// Everything else is a plain temporary directory.
+const KNOWN_PREFIXES: &[&str] = &["transcode-", "cookie-headers-", "dedup-index.", "resume-"];
+
+/// A file or directory directly in the cache directory
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub path: PathBuf,
+    /// What the entry is for, from its name, eg. `transcode` or `temp`
+    pub kind: &'static str,
+    /// Including everything in it, for directories
+    pub size: u64,
+    /// When anything in it was last changed
+    pub modified: SystemTime,
+}
+
+/// Everything in [`Config::cache_dir`]
+pub fn cache_entries() -> io::Result<Vec<CacheEntry>> {
+    let cache_dir = Config::cache_dir();
+
+    if !cache_dir.exists() {
+        return Ok(vec![]);
+    }
+
+    fs::read_dir(cache_dir)?
+        .map(|entry| {
+            let path = entry?.path();
+            let (size, modified) = disk_usage(&path)?;
+
+            Ok(CacheEntry {
+                kind: entry_kind(&path),
+                path,
+                size,
+                modified,
+            })
+        })
+        .collect()
+}
+
+pub fn remove_cache_entry(entry: &CacheEntry) -> io::Result<()> {
+    if entry.path.is_dir() {
+        fs::remove_dir_all(&entry.path)
+    } else {
+        fs::remove_file(&entry.path)
+    }
+}
+
+/// The size of everything at the path and when it was last modified
+fn disk_usage(path: &Path) -> io::Result<(u64, SystemTime)> {
+    let meta = fs::symlink_metadata(path)?;
+    let mut modified = meta.modified()?;
+
+    if !meta.is_dir() {
+        return Ok((meta.len(), modified));
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        let (entry_size, entry_modified) = disk_usage(&entry?.path())?;
+
+        size += entry_size;
+        modified = modified.max(entry_modified);
+    }
+
+    Ok((size, modified))
+}
+
+fn entry_kind(path: &Path) -> &'static str {
+    let name = path
+        .file_name()
+        .map(|x| x.to_string_lossy())
+        .unwrap_or_default();
+
+    KNOWN_PREFIXES
+        .iter()
+        .find(|x| name.starts_with(*x))
+        .map_or("temp", |x| x.trim_end_matches(['-', '.']))
+}
@@ -1,4 +1,7 @@
-use std::{fs, path::PathBuf};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 use app_config::Config;
 
@@ -12,3 +15,19 @@ pub fn create_temp_dir() -> anyhow::Result<PathBuf> {
 
     Ok(temp_dir)
 }
+
+/// The size of the file, or everything in the directory
+pub fn dir_size(path: &Path) -> io::Result<u64> {
+    let meta = fs::symlink_metadata(path)?;
+
+    if !meta.is_dir() {
+        return Ok(meta.len());
+    }
+
+    let mut size = 0;
+    for entry in fs::read_dir(path)? {
+        size += dir_size(&entry?.path())?;
+    }
+
+    Ok(size)
+}
@@ -1,3 +1,4 @@
+use app_config::Config;
 use url::Url;
 
 pub struct DomainParser;
@@ -13,3 +14,89 @@ impl DomainParser {
         Self::get_domain(url).and_then(|x| x.root())
     }
 }
+
+#[derive(Debug, thiserror::Error)]
+pub enum DomainFilterError {
+    #[error("URL has no host")]
+    NoHost,
+
+    #[error("Downloading from {0} is blocked on this instance")]
+    Blocked(String),
+
+    #[error("Downloading from {0} is not allowed on this instance")]
+    NotAllowed(String),
+}
+
+/// Checks the URL's host against the configured domain allow and block lists
+pub fn check_domain_allowed(url: &Url) -> Result<(), DomainFilterError> {
+    let config = &Config::global().domain_filter;
+
+    if config.allowed_domains.is_empty() && config.blocked_domains.is_empty() {
+        return Ok(());
+    }
+
+    let host = url
+        .host_str()
+        .map(|x| x.trim_end_matches('.').to_lowercase())
+        .ok_or(DomainFilterError::NoHost)?;
+
+    let matches_any = |patterns: &[String]| patterns.iter().any(|x| glob_matches(x, &host));
+
+    if matches_any(&config.blocked_domains) {
+        return Err(DomainFilterError::Blocked(host));
+    }
+
+    if !config.allowed_domains.is_empty() && !matches_any(&config.allowed_domains) {
+        return Err(DomainFilterError::NotAllowed(host));
+    }
+
+    Ok(())
+}
+
+/// Whether the URL's host matches the glob `pattern` (eg. `*.example.com`)
+#[must_use]
+pub fn host_matches(pattern: &str, url: &Url) -> bool {
+    url.host_str()
+        .is_some_and(|host| glob_matches(pattern, host.trim_end_matches('.')))
+}
+
+/// Whether the glob `pattern` matches the URL's host or its registrable domain,
+/// so `example.com` also covers `www.example.com` and `cdn.example.com`
+#[must_use]
+pub fn domain_matches(pattern: &str, url: &Url) -> bool {
+    host_matches(pattern, url)
+        || DomainParser::get_domain_root(url).is_some_and(|root| glob_matches(pattern, root))
+}
+
+/// Case-insensitive glob match where `*` matches any number of characters and `?` exactly one
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    // Where the last `*` was and the text position it's currently matched up to
+    let mut backtrack = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    backtrack = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|x| *x == '*')
+}
@@ -15,6 +15,15 @@ where
     base64::engine::general_purpose::URL_SAFE.decode(data)
 }
 
+/// With the standard alphabet and padding, eg. for HTTP basic auth
+#[must_use]
+pub fn to_base64_standard<T>(data: T) -> String
+where
+    T: AsRef<[u8]>,
+{
+    base64::engine::general_purpose::STANDARD.encode(data)
+}
+
 #[must_use]
 pub fn to_base64<T>(data: T) -> String
 where
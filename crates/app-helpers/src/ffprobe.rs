@@ -40,7 +40,8 @@ where
         cmd.args(["-v", "quiet"])
             .args(["-print_format", "json=c=1"])
             .arg("-show_format")
-            .arg("-show_streams");
+            .arg("-show_streams")
+            .arg("-show_chapters");
 
         if config.count_frames {
             cmd.arg("-count_frames");
@@ -70,7 +71,8 @@ pub fn ffprobe_config(
         cmd.args(["-v", "quiet"])
             .args(["-print_format", "json=c=1"])
             .arg("-show_format")
-            .arg("-show_streams");
+            .arg("-show_streams")
+            .arg("-show_chapters");
 
         if config.count_frames {
             cmd.arg("-count_frames");
@@ -163,6 +165,8 @@ pub enum FfProbeError {
 pub struct FfProbeResult {
     pub streams: Vec<Stream>,
     pub format: Format,
+    #[serde(default)]
+    pub chapters: Vec<Chapter>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -219,11 +223,36 @@ pub struct Stream {
     #[serde(default)]
     pub side_data_list: Vec<SideData>,
 }
+impl Stream {
+    /// Clockwise degrees (`0`, `90`, `180` or `270`) the video has to be rotated by to be upright.
+    ///
+    /// Comes from the display matrix, or the `rotate` tag of older ffmpeg versions.
+    #[must_use]
+    #[allow(clippy::cast_possible_truncation)]
+    pub fn rotation(&self) -> i64 {
+        let degrees = self
+            .side_data_list
+            .iter()
+            .find_map(|x| x.rotation)
+            .map(|x| -x)
+            .or_else(|| {
+                self.tags
+                    .as_ref()
+                    .and_then(|x| x.rotate.as_deref())
+                    .and_then(|x| x.trim().parse::<f64>().ok())
+            })
+            .unwrap_or_default();
+
+        ((degrees / 90.0).round() as i64 * 90).rem_euclid(360)
+    }
+}
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 // Allowed to prevent having to break compatibility of float fields are added.
 #[allow(clippy::derive_partial_eq_without_eq)]
 pub struct SideData {
     pub side_data_type: String,
+    /// Counter-clockwise degrees of the display matrix
+    pub rotation: Option<f64>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -254,6 +283,49 @@ pub struct StreamTags {
     pub encoder: Option<String>,
     pub timecode: Option<String>,
     pub reel_name: Option<String>,
+    /// Clockwise degrees, only set by older ffmpeg versions
+    pub rotate: Option<String>,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub id: i64,
+    pub time_base: String,
+    pub start: i64,
+    pub start_time: String,
+    pub end: i64,
+    pub end_time: String,
+    pub tags: Option<ChapterTags>,
+}
+
+impl Chapter {
+    /// Get the chapter title, if any.
+    #[must_use]
+    pub fn title(&self) -> Option<&str> {
+        self.tags.as_ref().and_then(|x| x.title.as_deref())
+    }
+
+    /// Get the start time in seconds.
+    ///
+    /// Will return [`None`] if parsing fails.
+    #[must_use]
+    pub fn start_seconds(&self) -> Option<f64> {
+        self.start_time.parse().ok()
+    }
+
+    /// Get the end time in seconds.
+    ///
+    /// Will return [`None`] if parsing fails.
+    #[must_use]
+    pub fn end_seconds(&self) -> Option<f64> {
+        self.end_time.parse().ok()
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[allow(clippy::derive_partial_eq_without_eq)]
+pub struct ChapterTags {
+    pub title: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -18,3 +18,32 @@ pub fn file_name_with_suffix(file_path: &Path, suffix: &str) -> PathBuf {
 
     file_path.with_file_name(file_name)
 }
+
+/// Make a string safe to use as (a part of) a file name.
+///
+/// Replaces path separators and other problematic characters with `_`,
+/// collapses whitespace and truncates the result to `max_len` characters.
+#[must_use]
+pub fn sanitize_file_name(name: &str, max_len: usize) -> String {
+    let sanitized = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || matches!(c, '\\' | '/' | ':' | '*' | '?' | '"' | '<' | '>' | '|') {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+
+    sanitized
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_matches(|c: char| c == '.' || c.is_whitespace())
+        .chars()
+        .take(max_len)
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
@@ -1,4 +1,4 @@
-use std::{path::Path, str::FromStr};
+use std::{fs::File, io::Read, path::Path, str::FromStr};
 
 use file_format::FileFormat;
 use infer::get_from_path as infer_from_path;
@@ -6,6 +6,11 @@ pub use mime;
 use mime::Mime;
 use tree_magic_mini::from_filepath as magic_infer_from_filepath;
 
+/// HEIF brands of single images (HEIC from iPhones, AVIF from CDNs)
+const HEIF_IMAGE_BRANDS: &[&[u8; 4]] = &[b"heic", b"heix", b"heim", b"heis", b"mif1", b"avif"];
+/// HEIF brands of image sequences, which are closer to videos than images
+const HEIF_SEQUENCE_BRANDS: &[&[u8; 4]] = &[b"msf1", b"hevc", b"hevx", b"avis"];
+
 pub fn infer_file_type(file: &Path) -> anyhow::Result<Mime> {
     let file = file.to_path_buf();
     let mime_type = infer_from_path(&file)?
@@ -21,3 +26,74 @@ pub fn infer_file_type(file: &Path) -> anyhow::Result<Mime> {
     Mime::from_str(&mime_type)
         .map_err(|e| anyhow::anyhow!("Failed to parse mime type: {:?}, error: {:?}", mime_type, e))
 }
+
+/// Whether the file is a HEIC/HEIF/AVIF still image, which Telegram and most players can't show.
+///
+/// Checks the brands in the `ftyp` box, since not every HEIF flavour is recognized by
+/// [`infer_file_type`] (eg. AVIF files with the generic `mif1` brand).
+/// Image sequences (eg. animated AVIF) aren't counted.
+pub fn is_heif_image(file: &Path) -> std::io::Result<bool> {
+    let header = read_header(file)?;
+
+    let Some(brands) = heif_brands(&header) else {
+        return Ok(false);
+    };
+
+    let is_sequence = brands.iter().any(|x| HEIF_SEQUENCE_BRANDS.contains(x));
+    let is_image = brands.iter().any(|x| HEIF_IMAGE_BRANDS.contains(x));
+
+    Ok(is_image && !is_sequence)
+}
+
+/// Whether the file is an animated WebP or an AVIF/HEIF image sequence
+pub fn is_animated_image(file: &Path) -> std::io::Result<bool> {
+    const WEBP_ANIMATION_FLAG: u8 = 0x02;
+
+    let header = read_header(file)?;
+
+    // Animated WebPs start with an extended (`VP8X`) chunk that has the animation flag set
+    if header.len() > 20 && &header[0..4] == b"RIFF" && &header[8..12] == b"WEBP" {
+        return Ok(&header[12..16] == b"VP8X" && header[20] & WEBP_ANIMATION_FLAG != 0);
+    }
+
+    let is_sequence = heif_brands(&header)
+        .is_some_and(|brands| brands.iter().any(|x| HEIF_SEQUENCE_BRANDS.contains(x)));
+
+    Ok(is_sequence)
+}
+
+/// The first bytes of the file, or less if the file is shorter
+fn read_header(file: &Path) -> std::io::Result<Vec<u8>> {
+    const HEADER_SIZE: u64 = 64;
+
+    let mut header = Vec::new();
+    File::open(file)?
+        .take(HEADER_SIZE)
+        .read_to_end(&mut header)?;
+
+    Ok(header)
+}
+
+/// The major and compatible brands in the `ftyp` box, if the file starts with one
+fn heif_brands(header: &[u8]) -> Option<Vec<&[u8; 4]>> {
+    if header.len() < 16 || &header[4..8] != b"ftyp" {
+        return None;
+    }
+
+    let box_size = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let major_brand = header[8..12].try_into().ok()?;
+    // The minor version comes between the major brand and the compatible brands
+    let compatible_brands = header
+        .get(16..box_size.min(header.len()))
+        .unwrap_or_default();
+
+    let brands = std::iter::once(major_brand)
+        .chain(
+            compatible_brands
+                .chunks_exact(4)
+                .filter_map(|x| x.try_into().ok()),
+        )
+        .collect();
+
+    Some(brands)
+}
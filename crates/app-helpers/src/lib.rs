@@ -1,3 +1,5 @@
+pub mod bytes;
+pub mod cache_dir;
 pub mod dirs;
 pub mod domain;
 pub mod encoding;
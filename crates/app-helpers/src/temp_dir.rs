@@ -1,11 +1,24 @@
 use std::{
-    env,
     ffi::OsString,
+    future::Future,
     marker::Send,
     path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
 };
 
-use super::id::time_thread_id;
+use app_config::{byte_size::ByteSize, Config};
+use tracing::{trace, warn};
+
+use super::{bytes::format_bytes, dirs::dir_size, id::time_thread_id};
+
+/// How often the size of the temporary directories of a task is checked
+const SIZE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+tokio::task_local! {
+    /// The temporary directories made by the task running in [`with_task_size_limit`]
+    static TASK_TEMP_DIRS: Arc<Mutex<Vec<PathBuf>>>;
+}
 
 #[derive(Debug)]
 pub struct TempDir {
@@ -30,6 +43,12 @@ impl TempDir {
             ));
         }
 
+        let _ = TASK_TEMP_DIRS.try_with(|dirs| {
+            if let Ok(mut dirs) = dirs.lock() {
+                dirs.push(tmp_dir.clone());
+            }
+        });
+
         Ok(Self {
             path: tmp_dir,
             delete_on_drop: true,
@@ -40,7 +59,7 @@ impl TempDir {
     where
         T: Into<OsString>,
     {
-        let tmp_dir = env::temp_dir();
+        let tmp_dir = Config::global().temp.dir();
         let tmp_dir = tmp_dir.join(dir_name.into());
 
         Self::absolute(tmp_dir)
@@ -61,7 +80,7 @@ impl TempDir {
     }
 
     #[allow(dead_code)]
-    pub fn no_delete_on_drop(&mut self) -> &mut Self {
+    pub const fn no_delete_on_drop(&mut self) -> &mut Self {
         self.delete_on_drop = false;
         self
     }
@@ -74,3 +93,61 @@ impl Drop for TempDir {
         }
     }
 }
+
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("The temporary files took up more than the {} limit", format_bytes(*.max))]
+pub struct TempSizeExceeded {
+    pub max: u64,
+}
+
+/// Runs the task, stopping it once the temporary directories it made
+/// take up more than `--max-task-temp-size` together.
+///
+/// The directories are deleted when the task is stopped, since they're dropped with it.
+pub async fn with_task_size_limit<F>(task: F) -> Result<F::Output, TempSizeExceeded>
+where
+    F: Future,
+{
+    let Some(max) = Config::global()
+        .temp
+        .max_task_temp_size
+        .map(ByteSize::bytes)
+    else {
+        return Ok(task.await);
+    };
+
+    let dirs = Arc::new(Mutex::new(vec![]));
+
+    tokio::select! {
+        output = TASK_TEMP_DIRS.scope(dirs.clone(), task) => Ok(output),
+        () = wait_until_over(dirs, max) => Err(TempSizeExceeded { max }),
+    }
+}
+
+async fn wait_until_over(dirs: Arc<Mutex<Vec<PathBuf>>>, max: u64) {
+    loop {
+        tokio::time::sleep(SIZE_CHECK_INTERVAL).await;
+
+        let paths = dirs.lock().map(|x| x.clone()).unwrap_or_default();
+        if paths.is_empty() {
+            continue;
+        }
+
+        // Deleted directories just don't count anymore
+        let size = tokio::task::spawn_blocking(move || {
+            paths
+                .iter()
+                .map(|x| dir_size(x).unwrap_or_default())
+                .sum::<u64>()
+        })
+        .await
+        .unwrap_or_default();
+
+        if size > max {
+            warn!(size, max, "Task went over the temporary file size limit");
+            return;
+        }
+
+        trace!(size, "Checked size of task temporary files");
+    }
+}
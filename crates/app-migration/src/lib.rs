@@ -5,12 +5,22 @@ pub const DEBUG: bool = true;
 #[macro_use]
 pub mod common;
 mod m20220101_000001_create_table;
+mod m20261016_000001_add_blocked_item_status;
+mod m20261016_000002_add_source_liveness;
+mod m20261016_000003_create_file_hash;
+mod m20261016_000004_add_partial_item_status;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20220101_000001_create_table::Migration)]
+        vec![
+            Box::new(m20220101_000001_create_table::Migration),
+            Box::new(m20261016_000001_add_blocked_item_status::Migration),
+            Box::new(m20261016_000002_add_source_liveness::Migration),
+            Box::new(m20261016_000003_create_file_hash::Migration),
+            Box::new(m20261016_000004_add_partial_item_status::Migration),
+        ]
     }
 }
@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let stmt = r#"
+            ALTER TYPE "item_status" ADD VALUE IF NOT EXISTS 'blocked';
+        "#
+        .trim();
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Postgres can't drop values from an enum, so only the rows using it are reverted
+        for table in ["download_request", "download_result"] {
+            let stmt =
+                format!(r#"UPDATE "{table}" SET "status" = 'failed' WHERE "status" = 'blocked';"#);
+
+            debug_print!(stmt);
+
+            db.execute_unprepared(&stmt).await?;
+        }
+
+        Ok(())
+    }
+}
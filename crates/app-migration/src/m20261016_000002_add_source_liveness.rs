@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let stmt = r#"
+            ALTER TABLE "download_request"
+                ADD COLUMN IF NOT EXISTS "source_checked_at" TIMESTAMP WITH TIME ZONE NULL,
+                ADD COLUMN IF NOT EXISTS "source_dead_at" TIMESTAMP WITH TIME ZONE NULL;
+        "#
+        .trim();
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let stmt = r#"
+            ALTER TABLE "download_request"
+                DROP COLUMN IF EXISTS "source_checked_at",
+                DROP COLUMN IF EXISTS "source_dead_at";
+        "#
+        .trim();
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+}
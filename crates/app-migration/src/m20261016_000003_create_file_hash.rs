@@ -0,0 +1,38 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let stmt = r#"
+            CREATE TABLE IF NOT EXISTS "file_hash" (
+                "hash" TEXT PRIMARY KEY,
+                "path" TEXT NOT NULL,
+                "created_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT now()
+            );
+        "#
+        .trim();
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let stmt = r#"DROP TABLE IF EXISTS "file_hash";"#;
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,37 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        let stmt = r#"
+            ALTER TYPE "item_status" ADD VALUE IF NOT EXISTS 'partial';
+        "#
+        .trim();
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        let db = manager.get_connection();
+
+        // Postgres can't drop values from an enum, so only the rows using it are reverted.
+        // Only requests can be partial, and they did get some of the files.
+        let stmt =
+            r#"UPDATE "download_request" SET "status" = 'success' WHERE "status" = 'partial';"#;
+
+        debug_print!(stmt);
+
+        db.execute_unprepared(stmt).await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,89 @@
+mod metrics;
+mod retry;
+mod task;
+
+use deadqueue::unlimited::Queue;
+pub use metrics::{QueueMetrics, QueueMetricsSnapshot};
+pub use retry::{RetryError, RetryPolicy};
+pub use task::Task;
+use tracing::{debug, info, trace};
+
+/// An in-memory queue of tasks that get retried with a backoff if they fail
+pub struct TaskQueue<T> {
+    queue: Queue<Task<T>>,
+    metrics: QueueMetrics,
+    retry_policy: RetryPolicy,
+}
+impl<T> TaskQueue<T>
+where
+    T: Clone + Send + Sync + std::fmt::Debug + 'static,
+{
+    #[must_use]
+    pub fn new(retry_policy: RetryPolicy) -> Self {
+        Self {
+            queue: Queue::new(),
+            metrics: QueueMetrics::default(),
+            retry_policy,
+        }
+    }
+
+    pub fn push(&self, task: Task<T>) {
+        trace!(?task, "Pushing task to queue");
+        self.metrics.pushed();
+        self.queue.push(task);
+    }
+
+    pub async fn pop(&self) -> Task<T> {
+        self.queue.pop().await
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    #[must_use]
+    pub const fn metrics(&self) -> &QueueMetrics {
+        &self.metrics
+    }
+
+    pub fn completed(&self, task: &Task<T>) {
+        self.metrics.completed();
+
+        if let Ok(took) = task.time_since_added().to_std() {
+            info!("Task completed after {:?}", took);
+        }
+    }
+
+    /// For tasks that failed in a way that retrying won't fix
+    pub fn failed(&self, _task: &Task<T>) {
+        self.metrics.failed();
+    }
+
+    /// Queues the task again once its backoff delay passes.
+    ///
+    /// Fails if the task was already retried too many times.
+    pub fn retry(&'static self, task: &Task<T>) -> Result<(), RetryError> {
+        let Some(delay) = self.retry_policy.delay(task.retries()) else {
+            self.metrics.failed();
+            return Err(RetryError::TooManyRetries);
+        };
+
+        self.metrics.retried();
+
+        let task = task.retried();
+        debug!(?delay, retries = task.retries(), "Retrying task");
+
+        tokio::task::spawn(async move {
+            tokio::time::sleep(delay).await;
+            self.queue.push(task);
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use thiserror::Error;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// How many times a task is retried before it's given up on
+    pub max_retries: u32,
+    /// How long to wait before the first retry.
+    /// Doubled for every retry after that.
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+impl RetryPolicy {
+    #[must_use]
+    pub const fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_mins(5),
+        }
+    }
+
+    #[must_use]
+    pub const fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// How long to wait before retrying a task that was already retried `retries` times.
+    ///
+    /// `None` if it shouldn't be retried anymore.
+    #[must_use]
+    pub fn delay(&self, retries: u32) -> Option<Duration> {
+        if retries >= self.max_retries {
+            return None;
+        }
+
+        let delay = self
+            .base_delay
+            .checked_mul(2_u32.saturating_pow(retries))
+            .unwrap_or(self.max_delay);
+
+        Some(delay.min(self.max_delay))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum RetryError {
+    #[error("Too many retries, giving up")]
+    TooManyRetries,
+}
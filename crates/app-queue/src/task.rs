@@ -0,0 +1,55 @@
+#[derive(Clone, Debug)]
+pub struct Task<T> {
+    id: String,
+    data: T,
+    retries: u32,
+    added: chrono::DateTime<chrono::Utc>,
+}
+impl<T> Task<T> {
+    pub fn new(data: T) -> Self {
+        let mut id = ulid::Ulid::new().to_string();
+        id.make_ascii_lowercase();
+
+        Self {
+            id,
+            data,
+            retries: 0,
+            added: chrono::Utc::now(),
+        }
+    }
+
+    pub const fn id(&self) -> &String {
+        &self.id
+    }
+
+    pub const fn data(&self) -> &T {
+        &self.data
+    }
+
+    pub const fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    pub fn time_since_added(&self) -> chrono::Duration {
+        chrono::Utc::now().signed_duration_since(self.added)
+    }
+}
+impl<T: Clone> Task<T> {
+    #[must_use]
+    pub const fn with_inc_retries(mut self) -> Self {
+        self.retries += 1;
+        self
+    }
+
+    #[must_use]
+    pub fn retried(&self) -> Self {
+        self.clone().with_inc_retries()
+    }
+}
+impl<T> std::ops::Deref for Task<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.data
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use app_actions::downloaders::{DownloadProgress, ProgressEstimator};
+use serde_json::{json, Value};
+use tokio::sync::watch;
+use tracing::warn;
+
+/// How often a snapshot of the download progress is written at most
+const PROGRESS_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Writes the events of the run as newline-delimited JSON,
+/// eg. `{"event":"fixStarted","timestamp":1760000000000,"path":"..."}`.
+///
+/// Does nothing if no file was given.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    file: Option<Arc<Mutex<File>>>,
+}
+impl EventLog {
+    pub fn new(path: Option<&Path>) -> io::Result<Self> {
+        let file = match path {
+            Some(path) => Some(Arc::new(Mutex::new(File::create(path)?))),
+            None => None,
+        };
+
+        Ok(Self { file })
+    }
+
+    pub const fn is_enabled(&self) -> bool {
+        self.file.is_some()
+    }
+
+    /// Writes the event with the fields of `data` next to its name and when it happened
+    pub fn emit(&self, event: &str, data: Value) {
+        let Some(file) = &self.file else {
+            return;
+        };
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_millis());
+
+        let mut line = json!({
+            "event": event,
+            "timestamp": timestamp,
+        });
+        if let (Some(line), Value::Object(data)) = (line.as_object_mut(), data) {
+            line.extend(data);
+        }
+
+        let mut file = file.lock().expect("Event log lock poisoned");
+        // Flushed right away so whoever is reading the file sees the event as it happens
+        if let Err(e) = writeln!(file, "{line}").and_then(|()| file.flush()) {
+            warn!(?e, "Failed to write to event log");
+        }
+    }
+}
+
+/// Writes what the extractors found and snapshots of the download progress
+/// until every download is done
+pub async fn log_download_progress(
+    mut progress: watch::Receiver<DownloadProgress>,
+    event_log: EventLog,
+) {
+    let mut estimator = ProgressEstimator::new();
+    let mut found = 0;
+
+    while progress.changed().await.is_ok() {
+        let current = progress.borrow_and_update().clone();
+
+        for media in current.found().iter().skip(found) {
+            event_log.emit(
+                "extractionFinished",
+                json!({
+                    "url": media.source_url,
+                    "title": media.title,
+                    "durationSeconds": media.duration.map(|x| x.as_secs()),
+                    "estimatedSize": media.estimated_size,
+                }),
+            );
+        }
+        found = current.found().len();
+
+        event_log.emit(
+            "progress",
+            json!({
+                "estimate": estimator.update(&current),
+                "files": current.files(),
+            }),
+        );
+
+        tokio::time::sleep(PROGRESS_SNAPSHOT_INTERVAL).await;
+    }
+}
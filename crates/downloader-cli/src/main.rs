@@ -1,26 +1,87 @@
-use std::{collections::HashSet, fmt::Debug, path::PathBuf, result::Result};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    result::Result,
+    time::Duration,
+};
 
 use app_actions::{
     actions::{
-        handlers::{file_rename_to_id::RenameToId, split_scenes::SplitScenes},
+        handlers::{
+            download_subtitles::DownloadSubtitles, file_rename_to_id::RenameToId,
+            split_scenes::SplitScenes,
+        },
         Action, ActionRequest,
     },
-    download_file, fix_file,
+    age_restriction::{allows_age_restricted, ALLOW_AGE_RESTRICTED_OPTION},
+    dedup::{self, dedup_with_local_index},
+    download_file_with_progress,
+    downloaders::{
+        handlers::music::MusicDownloaderOptions, DownloadProgress, DownloadResult,
+        DownloaderOptions, ProgressEstimator, ProgressTracker,
+    },
+    extract_only, fix_file,
+    fixers::FixResult,
+    format_choice::{FormatChoice, FORMAT_CHOICE_OPTION},
+    playlist::write_playlist,
+    post_archive::archive_post,
+    startup::{self, Capabilities},
+    thumbnail::EMBED_THUMBNAIL_OPTION,
+};
+use app_config::{
+    conditional::cli::{CacheCommand, CliCommand, ConfigCommand},
+    config_file::ConfigFile,
+    Config,
+};
+use app_helpers::{
+    bytes::format_bytes,
+    cache_dir::{cache_entries, remove_cache_entry, CacheEntry},
+    domain::check_domain_allowed,
 };
-use app_config::Config;
 use futures::{stream::FuturesUnordered, StreamExt};
+use tokio::sync::watch;
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::{filter::LevelFilter, util::SubscriberInitExt};
 
+use crate::event_log::{log_download_progress, EventLog};
+
+mod event_log;
+
+/// How often the progress bar is redrawn
+const PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Width of the bar itself, without the text next to it
+const PROGRESS_BAR_WIDTH: usize = 30;
+
 #[tokio::main]
 #[allow(clippy::too_many_lines)]
 async fn main() {
     init_log();
 
+    Config::use_config_file();
     let config = Config::global();
 
     debug!(config = ?*config, "Running with config");
 
+    if let Some(command) = &config.cli().command {
+        run_command(command);
+        return;
+    }
+
+    let capabilities = Capabilities::detect(
+        config.cli().output_directory.display().to_string(),
+        "none, everything is downloaded right away",
+    )
+    .await;
+    info!("Starting with capabilities:\n{capabilities}");
+
+    if let Err(e) = startup::self_test_if_enabled().await {
+        error!("Self-test failed: {e}");
+        std::process::exit(1);
+    }
+
     let urls = get_explicit_urls();
     let mut urls = print_errors("urls", urls);
 
@@ -51,26 +112,173 @@ async fn main() {
         warn!("Failed to parse {x:?} as URL or file: {errs:?}");
     }
 
+    urls.retain(|url| match check_domain_allowed(url) {
+        Ok(()) => true,
+        Err(e) => {
+            error!("Refusing to download {url}: {e}");
+            false
+        }
+    });
+
     debug!(urls = ?urls, files = ?files, "Parsed urls and files");
 
     info!("Outputting to {:?}", cli_config.output_directory);
 
+    let event_log = EventLog::new(cli_config.event_log.as_deref()).unwrap_or_else(|e| {
+        error!("Failed to create event log {:?}: {e}", cli_config.event_log);
+        std::process::exit(1);
+    });
+    let event_log = &event_log;
+    event_log.emit(
+        "runStarted",
+        serde_json::json!({
+            "urls": urls,
+            "files": files,
+        }),
+    );
+
+    // Whoever runs the CLI is the owner of the instance
+    let mut download_options = DownloaderOptions::new();
+    download_options.insert(
+        ALLOW_AGE_RESTRICTED_OPTION.to_string(),
+        allows_age_restricted(true).into(),
+    );
+    if cli_config.embed_thumbnail {
+        download_options.insert(EMBED_THUMBNAIL_OPTION.to_string(), true.into());
+    }
+    download_options.extend(DownloaderOptions::from(
+        MusicDownloaderOptions::from_config(),
+    ));
+    if cli_config.screenshot {
+        download_options.insert(
+            FORMAT_CHOICE_OPTION.to_string(),
+            FormatChoice::ScreenshotOnly.into(),
+        );
+    }
+    let download_options = &download_options;
+
+    if cli_config.extract_only {
+        print_extracted_info(&urls, download_options).await;
+        return;
+    }
+
+    if cli_config.archive_post {
+        archive_posts(&urls, &cli_config.output_directory, download_options).await;
+        return;
+    }
+
+    if let Some(lang) = &cli_config.subs {
+        info!("Downloading subtitles for {} urls", urls.len());
+        for url in &urls {
+            let req = ActionRequest::new(
+                cli_config.output_directory.clone(),
+                cli_config.output_directory.clone(),
+            )
+            .with_option("url", url.as_str())
+            .with_option("lang", lang.as_str());
+
+            match DownloadSubtitles.run(&req).await {
+                Ok(res) => debug!(?res, "Downloaded subtitles"),
+                Err(e) => error!("Failed to download subtitles for {url:?}: {e}"),
+            }
+        }
+    }
+
     info!("Starting download");
+    let tracker = ProgressTracker::new();
+    let show_progress = (!cli_config.no_progress && std::io::stderr().is_terminal())
+        .then(|| tokio::spawn(show_download_progress(tracker.subscribe())));
+    let log_progress = event_log.is_enabled().then(|| {
+        tokio::spawn(log_download_progress(
+            tracker.subscribe(),
+            event_log.clone(),
+        ))
+    });
+    let progress = &tracker;
     let downloaded_urls = urls
         .into_iter()
         .map(|url| async move {
             let url_str = url.to_string();
-            download_file(url, &cli_config.output_directory)
-                .await
-                .into_iter()
-                .map(|x| x.map_err(|e| (url_str.clone(), e)))
-                .collect::<Vec<_>>()
+            event_log.emit("extractionStarted", serde_json::json!({ "url": url_str }));
+
+            let results = download_file_with_progress(
+                url,
+                &cli_config.output_directory,
+                download_options.clone(),
+                Some(progress),
+            )
+            .await
+            .into_iter()
+            .map(|x| x.map_err(|e| (url_str.clone(), e)))
+            .collect::<Vec<_>>();
+
+            if event_log.is_enabled() {
+                let (paths, errors) =
+                    results
+                        .iter()
+                        .fold((vec![], vec![]), |(mut paths, mut errors), x| {
+                            match x {
+                                Ok(x) => paths.push(&x.path),
+                                Err((_, e)) => errors.push(e.to_string()),
+                            }
+                            (paths, errors)
+                        });
+
+                event_log.emit(
+                    "downloadFinished",
+                    serde_json::json!({
+                        "url": url_str,
+                        "paths": paths,
+                        "errors": errors,
+                    }),
+                );
+            }
+
+            (url_str, results)
         })
         .collect::<FuturesUnordered<_>>()
         .collect::<Vec<_>>()
-        .await
+        .await;
+
+    // Lets the event log write the last of the progress before it stops
+    drop(tracker);
+    if let Some(log_progress) = log_progress {
+        let _ = log_progress.await;
+    }
+
+    if let Some(show_progress) = show_progress {
+        show_progress.abort();
+        let _ = show_progress.await;
+        clear_progress_line();
+    }
+
+    let playlist_groups = downloaded_urls
+        .iter()
+        .map(|(url, results)| {
+            let paths = results
+                .iter()
+                .filter_map(|x| x.as_ref().ok())
+                .map(|x| x.path.clone())
+                .collect::<Vec<_>>();
+
+            (url.clone(), paths)
+        })
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect::<Vec<_>>();
+
+    let downloaded_files = downloaded_urls
+        .iter()
+        .flat_map(|(url, results)| {
+            results
+                .iter()
+                .filter_map(|x| x.as_ref().ok())
+                .map(move |x| (url.clone(), x.clone()))
+        })
+        .collect::<Vec<_>>();
+
+    let downloaded_urls = downloaded_urls
         .into_iter()
-        .flatten()
+        .flat_map(|(_, results)| results)
         .collect::<Vec<_>>();
     debug!(urls = ?downloaded_urls, "Downloaded urls");
 
@@ -81,6 +289,11 @@ async fn main() {
         failed_downloaded.len()
     );
 
+    let downloaded_paths = downloaded
+        .iter()
+        .map(|x| x.path.clone())
+        .collect::<HashSet<_>>();
+
     let to_fix = downloaded
         .into_iter()
         .map(|x| x.path)
@@ -92,10 +305,22 @@ async fn main() {
     let fixed_files = to_fix
         .into_iter()
         .map(|x| async move {
-            fix_file(&x)
-                .await
-                .map(|n| (x.clone(), n))
-                .map_err(|e| (x, e))
+            event_log.emit("fixStarted", serde_json::json!({ "path": x }));
+
+            let result = fix_file(&x).await;
+
+            match &result {
+                Ok(n) => event_log.emit(
+                    "fixFinished",
+                    serde_json::json!({ "path": x, "newPath": n.file_path }),
+                ),
+                Err(e) => event_log.emit(
+                    "fixFailed",
+                    serde_json::json!({ "path": x, "error": e.to_string() }),
+                ),
+            }
+
+            result.map(|n| (x.clone(), n)).map_err(|e| (x, e))
         })
         .collect::<FuturesUnordered<_>>()
         .collect::<Vec<_>>()
@@ -108,6 +333,60 @@ async fn main() {
         failed_fixed.len()
     );
 
+    if dedup::is_enabled() {
+        for (old, new) in &fixed {
+            if !downloaded_paths.contains(old) {
+                continue;
+            }
+
+            match dedup_with_local_index(&new.file_path).await {
+                Ok(Some(existing)) => {
+                    info!(
+                        "{:?} is the same as {existing:?}, linked to it",
+                        new.file_path
+                    );
+                }
+                Ok(None) => {}
+                Err(e) => warn!("Failed to dedup {:?}: {e}", new.file_path),
+            }
+        }
+    }
+
+    if cli_config.json {
+        print_downloaded_files(&downloaded_files, &fixed);
+    }
+
+    if let Some(playlist_format) = cli_config.playlist {
+        let fixed_paths = fixed
+            .iter()
+            .map(|(old, new)| (old.clone(), new.file_path.clone()))
+            .collect::<HashMap<_, _>>();
+
+        for (url, paths) in &playlist_groups {
+            let paths = paths
+                .iter()
+                .map(|x| fixed_paths.get(x).unwrap_or(x).clone())
+                .collect::<Vec<_>>();
+
+            let playlist_name = url::Url::parse(url).map_or_else(
+                |_| url.clone(),
+                |x| format!("{}{}", x.host_str().unwrap_or_default(), x.path()),
+            );
+
+            match write_playlist(
+                playlist_format,
+                &cli_config.output_directory,
+                &playlist_name,
+                &paths,
+            )
+            .await
+            {
+                Ok(path) => info!("Wrote playlist for {url:?} to {path:?}"),
+                Err(e) => error!("Failed to write playlist for {url:?}: {e:?}"),
+            }
+        }
+    }
+
     if cli_config.and_rename {
         let files_set = {
             let mut new = HashSet::new();
@@ -144,10 +423,34 @@ async fn main() {
         if let Err(e) = SplitScenes.run(&req).await {
             error!("Failed to split {f:?}: {e}");
             failed_split.push((f.clone(), e));
-            continue;
         }
     }
 
+    if event_log.is_enabled() {
+        let download_failures = failed_downloaded
+            .iter()
+            .map(|(x, e)| failure_json("url", &serde_json::json!(x), e))
+            .collect::<Vec<_>>();
+        let fix_failures = failed_fixed
+            .iter()
+            .map(|(x, e)| failure_json("path", &serde_json::json!(x), e))
+            .collect::<Vec<_>>();
+        let split_failures = failed_split
+            .iter()
+            .map(|(x, e)| failure_json("path", &serde_json::json!(x), e))
+            .collect::<Vec<_>>();
+
+        event_log.emit(
+            "runFinished",
+            serde_json::json!({
+                "files": downloaded_files_json(&downloaded_files, &fixed),
+                "failedDownloads": download_failures,
+                "failedFixes": fix_failures,
+                "failedSplits": split_failures,
+            }),
+        );
+    }
+
     if !failed_downloaded.is_empty() || !failed_fixed.is_empty() || !failed_split.is_empty() {
         for (x, e) in failed_downloaded {
             error!("Failed to download {x:?}: {e}");
@@ -165,6 +468,313 @@ async fn main() {
     }
 }
 
+fn run_command(command: &CliCommand) {
+    match command {
+        CliCommand::Cache(command) => manage_cache(command),
+        CliCommand::Config(command) => manage_config(command),
+    }
+}
+
+/// Shows or cleans up what's left in the cache directory
+fn manage_cache(command: &CacheCommand) {
+    let entries = match cache_entries() {
+        Ok(entries) => entries,
+        Err(e) => {
+            error!(
+                "Failed to read cache directory {:?}: {e}",
+                Config::cache_dir()
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let to_remove = match command {
+        CacheCommand::Stats => {
+            print_cache_stats(&entries);
+            return;
+        }
+        CacheCommand::Prune { older_than } => {
+            let older_than = Duration::from(older_than);
+
+            entries
+                .into_iter()
+                .filter(|x| x.modified.elapsed().is_ok_and(|age| age > older_than))
+                .collect()
+        }
+        CacheCommand::Clear => entries,
+    };
+
+    let mut failed = false;
+    let mut removed = 0;
+    let mut freed = 0;
+    for entry in &to_remove {
+        match remove_cache_entry(entry) {
+            Ok(()) => {
+                removed += 1;
+                freed += entry.size;
+            }
+            Err(e) => {
+                error!("Failed to remove {:?}: {e}", entry.path);
+                failed = true;
+            }
+        }
+    }
+
+    println!("Removed {removed} entries, freed {}", format_bytes(freed));
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn print_cache_stats(entries: &[CacheEntry]) {
+    let mut kinds = HashMap::<&str, (usize, u64)>::new();
+    for entry in entries {
+        let (count, size) = kinds.entry(entry.kind).or_default();
+        *count += 1;
+        *size += entry.size;
+    }
+
+    let mut kinds = kinds.into_iter().collect::<Vec<_>>();
+    kinds.sort_by_key(|(_, (_, size))| std::cmp::Reverse(*size));
+
+    println!("{}", Config::cache_dir().display());
+    for (kind, (count, size)) in kinds {
+        println!("  {kind}: {} in {count} entries", format_bytes(size));
+    }
+    println!(
+        "Total: {}",
+        format_bytes(entries.iter().map(|x| x.size).sum())
+    );
+}
+
+/// Reads or changes the defaults kept in the config file
+fn manage_config(command: &ConfigCommand) {
+    match command {
+        ConfigCommand::Get { key: None } => print!("{}", load_config_file()),
+        ConfigCommand::Get { key: Some(key) } => {
+            let Some(values) = load_config_file().get(key) else {
+                error!("{key:?} is not set in the config file");
+                std::process::exit(1);
+            };
+
+            for value in values {
+                println!("{value}");
+            }
+        }
+        ConfigCommand::Set { key, values } => {
+            let mut file = load_config_file();
+
+            if let Err(e) = file.set(key, values.clone()) {
+                error!("Failed to set {key:?}: {e}");
+                std::process::exit(1);
+            }
+
+            save_config_file(&file);
+        }
+        ConfigCommand::Unset { key } => {
+            let mut file = load_config_file();
+
+            if !file.remove(key) {
+                warn!("{key:?} is not set in the config file");
+                return;
+            }
+
+            save_config_file(&file);
+        }
+        ConfigCommand::Edit => edit_config_file(),
+        ConfigCommand::Path => match ConfigFile::path() {
+            Some(path) => println!("{}", path.display()),
+            None => {
+                error!("There is no config directory for this user");
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+fn load_config_file() -> ConfigFile {
+    ConfigFile::load().unwrap_or_else(|e| {
+        error!("Failed to load the config file: {e}");
+        std::process::exit(1);
+    })
+}
+
+fn save_config_file(file: &ConfigFile) {
+    match file.save() {
+        Ok(path) => info!("Saved config to {path:?}"),
+        Err(e) => {
+            error!("Failed to save the config file: {e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Opens the config file in the user's editor and checks it's still valid after
+fn edit_config_file() {
+    let Some(path) = ConfigFile::path() else {
+        error!("There is no config directory for this user");
+        std::process::exit(1);
+    };
+
+    if !path.exists() {
+        save_config_file(&ConfigFile::default());
+    }
+
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .filter_map(|x| std::env::var(x).ok())
+        .find(|x| !x.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+
+    // Eg. `code --wait`
+    let mut editor_args = editor.split_whitespace();
+    let status = std::process::Command::new(editor_args.next().unwrap_or("vi"))
+        .args(editor_args)
+        .arg(&path)
+        .status();
+
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => {
+            error!("{editor:?} exited with {status}");
+            std::process::exit(1);
+        }
+        Err(e) => {
+            error!("Failed to run {editor:?}: {e}");
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(e) = ConfigFile::load() {
+        error!("The config file is no longer valid: {e}");
+        std::process::exit(1);
+    }
+}
+
+/// Prints each downloaded file as a line of JSON, with the path it has after fixing
+fn print_downloaded_files(downloaded: &[(String, DownloadResult)], fixed: &[(PathBuf, FixResult)]) {
+    for line in downloaded_files_json(downloaded, fixed) {
+        println!("{line}");
+    }
+}
+
+/// `{"<key>": <value>, "error": "..."}`
+fn failure_json(key: &str, value: &serde_json::Value, error: &impl ToString) -> serde_json::Value {
+    serde_json::json!({ key: value, "error": error.to_string() })
+}
+
+fn downloaded_files_json(
+    downloaded: &[(String, DownloadResult)],
+    fixed: &[(PathBuf, FixResult)],
+) -> Vec<serde_json::Value> {
+    let fixed_paths = fixed
+        .iter()
+        .map(|(old, new)| (old, &new.file_path))
+        .collect::<HashMap<_, _>>();
+
+    downloaded
+        .iter()
+        .map(|(url, result)| {
+            let path = fixed_paths
+                .get(&result.path)
+                .copied()
+                .unwrap_or(&result.path);
+
+            serde_json::json!({
+                "url": url,
+                "path": path,
+                "metadata": result.metadata,
+                "downloaderChain": result.downloader_chain,
+            })
+        })
+        .collect()
+}
+
+/// Prints what was found for each URL as a line of JSON
+async fn print_extracted_info(urls: &[url::Url], options: &DownloaderOptions) {
+    let mut failed = false;
+
+    for url in urls {
+        match extract_only(url, options).await {
+            Ok(summary) => match serde_json::to_string(&summary) {
+                Ok(json) => println!("{json}"),
+                Err(e) => {
+                    error!("Failed to serialize info for {url:?}: {e}");
+                    failed = true;
+                }
+            },
+            Err(e) => {
+                error!("Failed to extract info from {url:?}: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+async fn archive_posts(urls: &[url::Url], output_dir: &Path, options: &DownloaderOptions) {
+    let mut failed = false;
+
+    for url in urls {
+        match archive_post(url, output_dir, options.clone()).await {
+            Ok(dir) => info!("Archived {url} to {dir:?}"),
+            Err(e) => {
+                error!("Failed to archive {url:?}: {e}");
+                failed = true;
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Draws a progress bar for all the downloads on the last line of the terminal
+async fn show_download_progress(mut progress: watch::Receiver<DownloadProgress>) {
+    let mut estimator = ProgressEstimator::new();
+
+    while progress.changed().await.is_ok() {
+        let estimate = estimator.update(&progress.borrow_and_update());
+
+        let bar = estimate.percent().map_or_else(
+            || "-".repeat(PROGRESS_BAR_WIDTH),
+            |percent| {
+                #[allow(
+                    clippy::cast_precision_loss,
+                    clippy::cast_possible_truncation,
+                    clippy::cast_sign_loss
+                )]
+                let filled = (percent / 100.0 * PROGRESS_BAR_WIDTH as f64).round() as usize;
+
+                format!(
+                    "{}{}",
+                    "#".repeat(filled),
+                    "-".repeat(PROGRESS_BAR_WIDTH - filled)
+                )
+            },
+        );
+
+        {
+            let mut stderr = std::io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[2K[{bar}] {estimate}");
+            let _ = stderr.flush();
+        }
+
+        tokio::time::sleep(PROGRESS_UPDATE_INTERVAL).await;
+    }
+}
+
+fn clear_progress_line() {
+    let mut stderr = std::io::stderr().lock();
+    let _ = write!(stderr, "\r\x1b[2K");
+    let _ = stderr.flush();
+}
+
 fn split_vec_err<T: Debug, E: Debug>(v: Vec<Result<T, E>>) -> (Vec<T>, Vec<E>) {
     let (ok, err) = v.into_iter().partition::<Vec<_>, _>(Result::is_ok);
     (
@@ -176,10 +786,7 @@ fn split_vec_err<T: Debug, E: Debug>(v: Vec<Result<T, E>>) -> (Vec<T>, Vec<E>) {
 fn print_errors<T: Sized>(name: &str, maybe_errors: Vec<Result<T, String>>) -> Vec<T> {
     let errors = maybe_errors
         .iter()
-        .filter_map(|maybe_err| match maybe_err {
-            Ok(_) => None,
-            Err(err) => Some(err),
-        })
+        .filter_map(|maybe_err| maybe_err.as_ref().err())
         .collect::<Vec<_>>();
 
     if !errors.is_empty() {
@@ -224,7 +831,7 @@ fn get_explicit_split_files() -> Vec<Result<PathBuf, String>> {
         .map(|x| (x, parse_file(x)))
         .map(|(f, maybe_err)| match maybe_err {
             Ok(x) => Ok(x),
-            Err(err) => Err(format!("Failed to parse {f:?} as path: {err}")),
+            Err(err) => Err(format!("Failed to parse {} as path: {err}", f.display())),
         })
         .collect::<Vec<_>>()
 }
@@ -238,7 +845,7 @@ fn get_explicit_files() -> Vec<Result<PathBuf, String>> {
         .map(|x| (x, parse_file(x)))
         .map(|(f, maybe_err)| match maybe_err {
             Ok(x) => Ok(x),
-            Err(err) => Err(format!("Failed to parse {f:?} as path: {err}")),
+            Err(err) => Err(format!("Failed to parse {} as path: {err}", f.display())),
         })
         .collect::<Vec<_>>()
 }
@@ -0,0 +1,85 @@
+//! Compares serving a whole downloaded file with `ServeFile` and with the `io_uring` reader.
+//!
+//! Run with `cargo bench -p downloader-hub --features io-uring`.
+
+use std::path::Path;
+
+use app_helpers::temp_dir::TempDir;
+use axum::{body::Body, http::Request};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::{Stream, StreamExt};
+use tokio::runtime::Runtime;
+use tower_http::services::ServeFile;
+
+#[allow(dead_code)]
+#[path = "../src/server/app_response/uring_file.rs"]
+mod uring_file;
+
+const FILE_SIZE: usize = 256 * 1024 * 1024;
+
+const CHUNK_SIZES: &[usize] = &[64 * 1024, 256 * 1024, 1024 * 1024];
+
+async fn drain<S, E>(mut body: S) -> usize
+where
+    S: Stream<Item = Result<axum::body::Bytes, E>> + Unpin,
+    E: std::fmt::Debug,
+{
+    let mut read = 0;
+    while let Some(chunk) = body.next().await {
+        read += chunk.expect("Failed to read chunk").len();
+    }
+
+    read
+}
+
+async fn serve_file(path: &Path, chunk_size: usize) -> usize {
+    let response = ServeFile::new(path)
+        .with_buf_chunk_size(chunk_size)
+        .try_call(Request::new(Body::empty()))
+        .await
+        .expect("Failed to serve file");
+
+    drain(Body::new(response.into_body()).into_data_stream()).await
+}
+
+async fn serve_uring(path: &Path, chunk_size: usize) -> usize {
+    let body = uring_file::read_file(path.to_path_buf(), chunk_size)
+        .expect("Failed to start reading file");
+
+    drain(Box::pin(body)).await
+}
+
+fn bench_file_serving(c: &mut Criterion) {
+    let runtime = Runtime::new().expect("Failed to start runtime");
+    let temp_dir = TempDir::absolute(std::env::temp_dir().join("downloader-hub-bench-serving"))
+        .expect("Failed to create temp dir");
+    let path = temp_dir.path().join("result.bin");
+    std::fs::write(&path, vec![7_u8; FILE_SIZE]).expect("Failed to write file");
+
+    let mut group = c.benchmark_group("file_serving");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(FILE_SIZE as u64));
+
+    for chunk_size in CHUNK_SIZES {
+        group.bench_with_input(
+            BenchmarkId::new("serve_file", chunk_size),
+            chunk_size,
+            |b, chunk_size| {
+                b.to_async(&runtime).iter(|| serve_file(&path, *chunk_size));
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("io_uring", chunk_size),
+            chunk_size,
+            |b, chunk_size| {
+                b.to_async(&runtime)
+                    .iter(|| serve_uring(&path, *chunk_size));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_file_serving);
+criterion_main!(benches);
@@ -1,6 +1,7 @@
+use app_actions::startup::{self, Capabilities};
 use app_config::Config;
 use app_tasks::TaskRunner;
-use tracing::{debug, error};
+use tracing::{debug, error, info};
 
 use crate::{
     db::AppDb,
@@ -11,6 +12,7 @@ mod db;
 mod queue;
 mod server;
 mod service;
+mod source_recheck;
 
 #[tokio::main]
 async fn main() {
@@ -33,12 +35,26 @@ async fn main() {
 
     debug!(config = ?*Config::global(), "Running with config");
 
+    let storage = Config::global()
+        .server()
+        .storage
+        .storage_remote
+        .as_deref()
+        .map_or_else(|| "local disk".to_string(), |x| format!("rclone ({x})"));
+    let capabilities = Capabilities::detect(storage, "in memory, restored from the database").await;
+    info!("Starting with capabilities:\n{capabilities}");
+
+    startup::self_test_if_enabled()
+        .await
+        .expect("Self-test failed");
+
     AppDb::init().await.expect("Failed to initialize database");
 
     TaskQueue::init().await.expect("Failed to initialize queue");
 
     tokio::task::spawn(TaskQueueProcessor::run());
     tokio::task::spawn(TaskRunner::run());
+    tokio::task::spawn(source_recheck::run());
 
     server::run().await.expect("Failed to run server");
 }
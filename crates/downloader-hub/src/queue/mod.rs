@@ -1,17 +1,21 @@
-use deadqueue::unlimited::Queue;
+use app_queue::{RetryPolicy, TaskQueue as Queue};
 use once_cell::sync::Lazy;
 use tracing::{debug, info, trace};
 
 pub mod processor;
+pub mod progress;
 pub mod task;
 
 use crate::{
     db::AppDb,
-    queue::task::Task,
+    queue::task::{Task, TaskInfo},
     service::{download_request::DownloadRequestService, download_result::DownloadResultService},
 };
 
-pub static TASK_QUEUE: Lazy<Queue<Task>> = Lazy::new(Queue::new);
+const MAX_RETRIES: u32 = 5;
+
+pub static TASK_QUEUE: Lazy<Queue<TaskInfo>> =
+    Lazy::new(|| Queue::new(RetryPolicy::new(MAX_RETRIES)));
 
 pub struct TaskQueue;
 impl TaskQueue {
@@ -39,7 +43,9 @@ async fn add_download_requests_from_db() -> Result<(), anyhow::Error> {
 
     for request in &pending_requests {
         trace!(?request, "Enqueued pending download request");
-        TASK_QUEUE.push(Task::download_request(request.request_uid.clone()));
+        TASK_QUEUE.push(Task::new(TaskInfo::DownloadRequest(
+            request.request_uid.clone(),
+        )));
     }
 
     let pending_count = pending_requests.len();
@@ -70,7 +76,9 @@ async fn add_download_results_from_db() -> Result<(), anyhow::Error> {
     for result in &pending_results {
         trace!(?result, "Enqueued pending download result");
         if let Some(path) = result.path() {
-            TASK_QUEUE.push(Task::process_download_result(result.id, path));
+            TASK_QUEUE.push(Task::new(TaskInfo::ProcessDownloadResult((
+                result.id, path,
+            ))));
         }
     }
 
@@ -1,30 +1,90 @@
-use std::result::Result;
+use std::{path::Path, result::Result};
 
-use app_actions::download_file;
+use app_actions::{
+    age_restriction::{allows_age_restricted, ALLOW_AGE_RESTRICTED_OPTION},
+    blocklist::is_blocked_error,
+    collection::{expand_collection, CollectionOptions, CollectionOrder},
+    content_policy::ALLOWED_CONTENT_TYPES_OPTION,
+    download_file_with_progress,
+    downloaders::{
+        handlers::{
+            live::RECORD_LIVE_OPTION,
+            music::MusicDownloaderOptions,
+            yt_dlp::{
+                SubtitleMode, YT_DLP_EXTRA_ARGS_OPTION, YT_DLP_GEO_BYPASS_COUNTRY_OPTION,
+                YT_DLP_SUBTITLES_OPTION, YT_DLP_SUBTITLE_LANGS_OPTION,
+            },
+        },
+        DownloadRequest, DownloadResult, DownloaderOptions, DownloaderReturn,
+    },
+    format_choice::{FormatChoice, FORMAT_CHOICE_OPTION},
+    media_policy::MEDIA_POLICY_OPTION,
+    partial_retry::SKIP_URLS_OPTION,
+    post_archive::{archive_post, zip_post_archive},
+    resume::RESUME_KEY_OPTION,
+    thumbnail::EMBED_THUMBNAIL_OPTION,
+};
+use app_config::validators::str::parse_country_code;
 use app_entities::{
     download_request,
-    entity_meta::{common::path::AppPath, download_result::DownloadResultStatus},
+    entity_meta::{
+        common::path::AppPath,
+        download_request::{DownloadRequestMeta, DownloadRequestMetaCollection},
+        download_result::{DownloadResultMeta, DownloadResultStatus},
+    },
 };
-use app_helpers::ip::url_resolves_to_valid_ip;
+use app_errors::{AppError, UserInputError};
+use app_helpers::{
+    domain::check_domain_allowed, ip::url_resolves_to_valid_ip, temp_dir::with_task_size_limit,
+};
+use axum::http::HeaderMap;
 use sea_orm::{prelude::*, TransactionTrait};
 use tracing::{debug, error, info, warn};
+use url::Url;
 
-use super::HandlerError;
+use super::{
+    download_result::{dedup_file, store_file},
+    HandlerError,
+};
 use crate::{
     db::AppDb,
-    queue::{task::Task, TASK_QUEUE},
+    queue::{
+        progress::DownloadProgressRegistry,
+        task::{Task, TaskInfo},
+        TASK_QUEUE,
+    },
     service::{
-        download_request::{DownloadRequestService, DownloadRequestStatus},
+        download_request::{
+            CreateDownloadRequestPayload, DownloadRequestService, DownloadRequestStatus,
+        },
         download_result::{CreateDownloadResultPayload, DownloadResultService},
+        storage::StorageService,
     },
 };
 
 pub(super) async fn handle_download_request(uid: &str) -> Result<(), HandlerError> {
-    match download(uid).await {
+    let res = with_task_size_limit(download(uid))
+        .await
+        .unwrap_or_else(|e| Err(AppError::from(UserInputError::TooLarge(e.to_string())).into()));
+
+    let res = match res {
         Ok((request, paths)) => {
-            if let Err(e) = add_metadata(request.id, paths).await {
+            if let Err(e) = add_metadata(request.id, paths.clone()).await {
                 error!(?request, ?e, "Failed to add metadata");
             }
+
+            // Files that get fixed are stored once they're fixed
+            let request_meta = request.meta().unwrap_or_default();
+            if StorageService::is_remote()
+                && (request_meta.skip_fixing || request_meta.archive_post)
+            {
+                for path in &paths {
+                    if let AppPath::LocalAbsolute(path) = path {
+                        store_file(request.id, &request.request_uid, path).await;
+                    }
+                }
+            }
+
             Ok(())
         }
         Err(e) if e.is_fatal() => {
@@ -55,7 +115,12 @@ pub(super) async fn handle_download_request(uid: &str) -> Result<(), HandlerErro
 
             Err(e)
         }
-    }
+    };
+
+    // Only after the status is updated, so event listeners see the final status
+    DownloadProgressRegistry::untrack(uid).await;
+
+    res
 }
 
 #[tracing::instrument]
@@ -76,49 +141,178 @@ async fn download(uid: &str) -> Result<(download_request::Model, Vec<AppPath>),
         .resolve_download_folder()
         .map_err(|e| HandlerError::Fatal(e.to_string()))?;
     let download_url = request.url.clone();
-    let download_url =
-        url_resolves_to_valid_ip(&download_url).map_err(|e| HandlerError::Fatal(e.to_string()))?;
+    let download_url = url_resolves_to_valid_ip(&download_url)
+        .map_err(|e| AppError::from(UserInputError::NotAllowed(e.to_string())))?;
+    // The lists might have changed since the request was submitted
+    check_domain_allowed(&download_url)
+        .map_err(|e| AppError::from(UserInputError::NotAllowed(e.to_string())))?;
 
     let request_meta = request.meta().unwrap_or_default();
 
+    if let Some(collection) = &request_meta.collection {
+        if expand_collection_request(&request, &download_url, &request_meta, collection).await? {
+            DownloadRequestService::update_status(&db, uid, DownloadRequestStatus::Success).await?;
+
+            return Ok((request, vec![]));
+        }
+    }
+
     debug!(dir = ?download_dir, url = ?download_url.as_str(), "Staring download");
 
-    let results = download_file(&download_url, &download_dir).await;
+    let mut download_options = DownloaderOptions::new();
+    if let Some(media_policy) = client.media_policy() {
+        download_options.insert(MEDIA_POLICY_OPTION.to_string(), media_policy);
+    }
+    if let Some(allowed_content_types) = client.allowed_content_types() {
+        download_options.insert(
+            ALLOWED_CONTENT_TYPES_OPTION.to_string(),
+            allowed_content_types,
+        );
+    }
+    download_options.insert(
+        ALLOW_AGE_RESTRICTED_OPTION.to_string(),
+        allows_age_restricted(client.is_owner()).into(),
+    );
+    // Downloads cut off by a restart or a failed try continue from what they already have
+    download_options.insert(RESUME_KEY_OPTION.to_string(), uid.into());
+    if let Some(choice) = request_meta
+        .format
+        .as_deref()
+        .and_then(|x| x.parse::<FormatChoice>().ok())
+    {
+        download_options.insert(FORMAT_CHOICE_OPTION.to_string(), choice.into());
+    }
+    if request_meta.embed_thumbnail {
+        download_options.insert(EMBED_THUMBNAIL_OPTION.to_string(), true.into());
+    }
+    let mut music_options = MusicDownloaderOptions::from_config();
+    if let Some(library_layout) = request_meta.library_layout {
+        music_options = music_options.with_library_layout(library_layout);
+    }
+    if let Some(embed_cover) = request_meta.embed_cover {
+        music_options = music_options.with_embed_cover(embed_cover);
+    }
+    download_options.extend(DownloaderOptions::from(music_options));
+    if request_meta.record_live {
+        download_options.insert(RECORD_LIVE_OPTION.to_string(), true.into());
+    }
+    if let Some(country) = request_meta
+        .geo_bypass_country
+        .as_deref()
+        .and_then(|x| parse_country_code(x).ok())
+    {
+        download_options.insert(YT_DLP_GEO_BYPASS_COUNTRY_OPTION.to_string(), country.into());
+    }
+    if let Some(mode) = request_meta
+        .subtitles
+        .as_deref()
+        .and_then(|x| x.parse::<SubtitleMode>().ok())
+    {
+        download_options.insert(YT_DLP_SUBTITLES_OPTION.to_string(), mode.into());
+        download_options.insert(
+            YT_DLP_SUBTITLE_LANGS_OPTION.to_string(),
+            request_meta.subtitle_langs.clone().into(),
+        );
+    }
+    if !request_meta.yt_dlp_extra_args.is_empty() {
+        if !client.is_owner() {
+            return Err(AppError::from(UserInputError::NotAllowed(
+                "Only the owner can pass arguments to yt-dlp".to_string(),
+            ))
+            .into());
+        }
+
+        download_options.insert(
+            YT_DLP_EXTRA_ARGS_OPTION.to_string(),
+            request_meta.yt_dlp_extra_args.clone().into(),
+        );
+    }
+
+    // Only the files that failed are downloaded again when the request is retried
+    let already_downloaded = DownloadResultService::find_by_request_id(&db, request.id)
+        .await?
+        .iter()
+        .filter_map(|x| x.item_url().map(ToString::to_string))
+        .collect::<Vec<_>>();
+    if !already_downloaded.is_empty() {
+        debug!(
+            ?already_downloaded,
+            "Skipping the files that are already downloaded"
+        );
+        download_options.insert(
+            SKIP_URLS_OPTION.to_string(),
+            already_downloaded.clone().into(),
+        );
+    }
+
+    let progress = DownloadProgressRegistry::track(uid).await;
+    let results = if request_meta.archive_post {
+        vec![archive_post_zip(&download_url, &download_dir, download_options).await]
+    } else {
+        download_file_with_progress(
+            &download_url,
+            &download_dir,
+            download_options,
+            Some(&progress),
+        )
+        .await
+    };
 
     debug!(?results, "Download completed successfully");
 
+    // Blocked files are already deleted, but the request itself gets flagged as well
+    let blocked_reason = results
+        .iter()
+        .filter_map(|x| x.as_ref().err())
+        .find(|x| is_blocked_error(x))
+        .map(ToString::to_string);
+
+    let downloaded = already_downloaded.len() + results.iter().filter(|x| x.is_ok()).count();
+    let first_error = results
+        .iter()
+        .find_map(|x| x.as_ref().err())
+        .map(ToString::to_string);
+
     let results = app_helpers::futures::retry_fn(5, || {
         let results = results.clone();
+        let status = match (blocked_reason.clone(), first_error.clone()) {
+            (Some(reason), _) => DownloadRequestStatus::Blocked(reason),
+            (None, None) => DownloadRequestStatus::Success,
+            (None, Some(_)) if downloaded > 0 => DownloadRequestStatus::Partial,
+            (None, Some(e)) => DownloadRequestStatus::Failed(e),
+        };
 
         db.transaction_with_config::<_, _, DbErr>(
             |txn| {
                 let uid = uid.to_string();
                 Box::pin(async move {
-                    DownloadRequestService::update_status(
-                        txn,
-                        &uid,
-                        DownloadRequestStatus::Success,
-                    )
-                    .await?;
+                    DownloadRequestService::update_status(txn, &uid, status).await?;
 
                     DownloadResultService::create_many(
                         txn,
                         results.iter().map(|x| match x {
                             Ok(x) => CreateDownloadResultPayload {
                                 request_id: request.id,
-                                status: if request_meta.skip_fixing {
+                                // There's nothing to fix in the zip of an archived post
+                                status: if request_meta.skip_fixing || request_meta.archive_post {
                                     DownloadResultStatus::Success
                                 } else {
                                     DownloadResultStatus::Pending
                                 },
                                 path: Some(x.path.clone()),
                                 meta: None,
+                                media_metadata: x.metadata.clone(),
+                                downloader_chain: x.downloader_chain.clone(),
+                                item_url: Some(x.request.url.url().to_string()),
                             },
                             Err(e) => CreateDownloadResultPayload {
                                 request_id: request.id,
-                                status: DownloadResultStatus::Failed(e.clone()),
+                                status: DownloadResultStatus::Failed(e.to_string()),
                                 path: None,
-                                meta: None,
+                                meta: Some(DownloadResultMeta::Error(e.to_string())),
+                                media_metadata: None,
+                                downloader_chain: vec![],
+                                item_url: None,
                             },
                         }),
                     )
@@ -145,15 +339,99 @@ async fn download(uid: &str) -> Result<(download_request::Model, Vec<AppPath>),
         .map(AppPath::LocalAbsolute)
         .collect::<Vec<_>>();
 
-    if !request_meta.skip_fixing {
+    if !request_meta.skip_fixing && !request_meta.archive_post {
+        for item in &successful {
+            TASK_QUEUE.push(Task::new(TaskInfo::ProcessDownloadResult((
+                request.id,
+                item.clone(),
+            ))));
+        }
+    } else if !StorageService::is_remote() {
+        // Files that get fixed are deduplicated once they're fixed
         for item in &successful {
-            TASK_QUEUE.push(Task::process_download_result(request.id, item.clone()));
+            if let AppPath::LocalAbsolute(path) = item {
+                dedup_file(path).await;
+            }
         }
     }
 
     Ok((request, successful))
 }
 
+/// Adds a request for each of the wanted items if the URL is of a playlist, channel or profile,
+/// so they go through the queue like any other request.
+///
+/// Returns whether the URL was of one.
+async fn expand_collection_request(
+    request: &download_request::Model,
+    url: &Url,
+    meta: &DownloadRequestMeta,
+    collection: &DownloadRequestMetaCollection,
+) -> Result<bool, HandlerError> {
+    let order = collection
+        .order
+        .as_deref()
+        .map(str::parse::<CollectionOrder>)
+        .transpose()
+        .map_err(|e| AppError::from(UserInputError::Invalid(e)))?
+        .unwrap_or_default();
+    let options = CollectionOptions {
+        max_items: collection.max_items,
+        date_after: collection.date_after,
+        date_before: collection.date_before,
+        order,
+    };
+
+    let Some(item_urls) = expand_collection(url, &options).await? else {
+        return Ok(false);
+    };
+
+    info!(
+        count = item_urls.len(),
+        "Adding requests for the items of the collection"
+    );
+
+    // The items are downloaded like the collection would have been
+    let item_meta = DownloadRequestMeta {
+        collection: None,
+        ..meta.clone()
+    };
+    let payloads = item_urls
+        .into_iter()
+        .map(|url| CreateDownloadRequestPayload {
+            url,
+            client_id: request.client_id,
+            meta: Some(item_meta.clone()),
+            app_meta: request.app_meta(),
+        })
+        .collect::<Vec<_>>();
+
+    if !payloads.is_empty() {
+        DownloadRequestService::create_many(&AppDb::db(), payloads).await?;
+    }
+
+    Ok(true)
+}
+
+/// The whole post as a single zip in the download directory
+async fn archive_post_zip(
+    url: &Url,
+    download_dir: &Path,
+    options: DownloaderOptions,
+) -> DownloaderReturn {
+    let archive_dir = archive_post(url, download_dir, options).await?;
+    let path = zip_post_archive(&archive_dir).await?;
+
+    Ok(DownloadResult {
+        request: DownloadRequest::from_url(url.as_str(), download_dir),
+        path,
+        sha256: None,
+        metadata: None,
+        downloader_chain: vec![],
+        response_headers: HeaderMap::new(),
+    })
+}
+
 async fn add_metadata(request_id: i32, paths: Vec<AppPath>) -> Result<(), anyhow::Error> {
     debug!(request_id, ?paths, "Adding metadata");
     let db = AppDb::db();
@@ -1,13 +1,21 @@
-use app_actions::fix_file;
+use std::path::{Path, PathBuf};
+
+use app_actions::{dedup, fix_file, fixers::FixRequest, media_policy::MEDIA_POLICY_OPTION};
 use app_entities::entity_meta::{
     common::path::AppPath,
     download_result::{DownloadResultMeta, DownloadResultStatus},
 };
 use sea_orm::{DbErr, TransactionTrait};
-use tracing::{debug, error};
+use tracing::{debug, error, warn};
 
 use super::HandlerError;
-use crate::{db::AppDb, service::download_result::DownloadResultService};
+use crate::{
+    db::AppDb,
+    service::{
+        download_request::DownloadRequestService, download_result::DownloadResultService,
+        file::FileService, file_hash::FileHashService, storage::StorageService,
+    },
+};
 
 pub async fn handle_process_result(request_id: i32, path: AppPath) -> Result<(), HandlerError> {
     match fix(request_id, path.clone()).await {
@@ -69,7 +77,17 @@ async fn fix(request_id: i32, app_path: AppPath) -> Result<(), HandlerError> {
     )
     .await?;
 
-    let new_path = fix_file(&path).await;
+    let request = DownloadRequestService::find_by_id_with_client(&db, request_id).await?;
+
+    let mut fix_request = FixRequest::new(&path);
+    if let Some(media_policy) = request
+        .as_ref()
+        .and_then(|(_, client)| client.media_policy())
+    {
+        fix_request = fix_request.with_option(MEDIA_POLICY_OPTION, media_policy);
+    }
+
+    let new_path = fix_file(fix_request).await;
 
     match new_path {
         Err(e) => {
@@ -115,8 +133,76 @@ async fn fix(request_id: i32, app_path: AppPath) -> Result<(), HandlerError> {
                 .await
             })
             .await?;
+
+            match request {
+                Some((request, _)) if StorageService::is_remote() => {
+                    store_file(request_id, &request.request_uid, new_path).await;
+                }
+                _ => dedup_file(new_path).await,
+            }
         }
-    };
+    }
 
     Ok(())
 }
+
+/// Streams the finished file to the storage remote and removes it from the disk.
+///
+/// The file stays where it is if it can't be stored, so it can still be served from there.
+#[allow(clippy::similar_names)]
+pub(super) async fn store_file(request_id: i32, request_uid: &str, path: &Path) {
+    let res = async {
+        let stored = StorageService::store(path, request_uid).await?;
+
+        DownloadResultService::update_path(
+            &AppDb::db(),
+            request_id,
+            AppPath::LocalAbsolute(path.to_path_buf()),
+            stored,
+        )
+        .await?;
+
+        anyhow::Ok(())
+    }
+    .await;
+
+    match res {
+        Ok(()) => {
+            if let Err(e) = tokio::fs::remove_file(path).await {
+                warn!(?e, ?path, "Failed to remove stored file from disk");
+            }
+        }
+        Err(e) => warn!(?e, ?path, "Failed to move file to storage"),
+    }
+}
+
+/// Replaces the file with a hard link to the same one downloaded before, if there is one,
+/// so it's only stored once
+pub(super) async fn dedup_file(path: &Path) {
+    if !dedup::is_enabled() {
+        return;
+    }
+
+    let res = async {
+        let db = AppDb::db();
+        let hash = FileService::file_hash(path).await?;
+
+        if let Some(existing) = FileHashService::find_by_hash(&db, &hash).await? {
+            let hash_file = |x: PathBuf| async move { FileService::file_hash(&x).await.ok() };
+
+            if dedup::link_to_existing(path, Path::new(&existing.path), &hash, hash_file).await? {
+                debug!(?path, existing = ?existing.path, "Linked duplicate file");
+                return Ok(());
+            }
+        }
+
+        FileHashService::upsert(&db, hash, path.to_string_lossy().to_string()).await?;
+
+        anyhow::Ok(())
+    }
+    .await;
+
+    if let Err(e) = res {
+        warn!(?e, ?path, "Failed to dedup file");
+    }
+}
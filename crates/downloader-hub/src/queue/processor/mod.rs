@@ -1,5 +1,3 @@
-use std::string::ToString;
-
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
 
@@ -9,8 +7,6 @@ use crate::queue::{task::TaskInfo, TASK_QUEUE};
 mod download_request;
 mod download_result;
 
-const MAX_RETRIES: u32 = 5;
-
 #[derive(Debug, Error)]
 enum HandlerError {
     #[error("Join error: `{0}`")]
@@ -25,10 +21,16 @@ enum HandlerError {
     Fatal(String),
     #[error("Failed to fix: `{0}`")]
     FixFailed(#[from] app_actions::fixers::FixerError),
+    #[error(transparent)]
+    App(#[from] app_errors::AppError),
 }
 impl HandlerError {
     pub const fn is_fatal(&self) -> bool {
-        matches!(self, Self::Fatal(_))
+        match self {
+            Self::Fatal(_) => true,
+            Self::App(e) => !e.is_retryable(),
+            _ => false,
+        }
     }
 }
 
@@ -46,7 +48,7 @@ impl TaskQueueProcessor {
 
 #[tracing::instrument]
 async fn handle_task(task: &Task) {
-    let res = match task.info() {
+    let res = match task.data() {
         TaskInfo::DownloadRequest(uid) => download_request::handle_download_request(uid).await,
         TaskInfo::ProcessDownloadResult((request_id, path)) => {
             download_result::handle_process_result(*request_id, path.clone()).await
@@ -55,9 +57,7 @@ async fn handle_task(task: &Task) {
 
     let err = match res {
         Ok(()) => {
-            if let Ok(took) = task.time_since_added().to_std() {
-                info!("Task completed after {:?}", took);
-            }
+            TASK_QUEUE.completed(task);
             return;
         }
 
@@ -66,24 +66,18 @@ async fn handle_task(task: &Task) {
 
     warn!(?err, "Got error processing task");
 
-    if let Err(e) = should_retry(task, err) {
+    if let Err(e) = retry(task, err) {
         error!(?e, "Task will not be retried");
-        return;
     }
-
-    TASK_QUEUE.push(task.retried());
 }
 
-fn should_retry(task: &Task, err: HandlerError) -> Result<(), HandlerError> {
-    if task.retries() >= MAX_RETRIES {
-        return Err(HandlerError::Fatal(
-            "Too many retries, giving up".to_string(),
-        ));
-    }
-
+fn retry(task: &Task, err: HandlerError) -> Result<(), HandlerError> {
     if err.is_fatal() {
+        TASK_QUEUE.failed(task);
         return Err(err);
     }
 
-    Ok(())
+    TASK_QUEUE
+        .retry(task)
+        .map_err(|e| HandlerError::Fatal(e.to_string()))
 }
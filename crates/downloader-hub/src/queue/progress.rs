@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use app_actions::downloaders::{DownloadProgress, ProgressTracker};
+use once_cell::sync::Lazy;
+use tokio::sync::{watch, Mutex};
+
+/// Trackers of the download requests that are currently being downloaded, by request UID
+static DOWNLOADS_IN_PROGRESS: Lazy<Mutex<HashMap<String, ProgressTracker>>> =
+    Lazy::new(Default::default);
+
+pub struct DownloadProgressRegistry;
+impl DownloadProgressRegistry {
+    pub async fn track(uid: &str) -> ProgressTracker {
+        let tracker = ProgressTracker::new();
+
+        DOWNLOADS_IN_PROGRESS
+            .lock()
+            .await
+            .insert(uid.to_string(), tracker.clone());
+
+        tracker
+    }
+
+    pub async fn untrack(uid: &str) {
+        DOWNLOADS_IN_PROGRESS.lock().await.remove(uid);
+    }
+
+    /// `None` if the request isn't being downloaded right now
+    pub async fn subscribe(uid: &str) -> Option<watch::Receiver<DownloadProgress>> {
+        DOWNLOADS_IN_PROGRESS
+            .lock()
+            .await
+            .get(uid)
+            .map(ProgressTracker::subscribe)
+    }
+}
@@ -95,7 +95,7 @@ impl AuthorizationSchema {
                 Some(CurrentUser {
                     id: ADMIN_ID,
                     name: "admin".to_string(),
-                    api_key: key.to_string(),
+                    api_key: key.clone(),
                     app_meta: serde_json::json!({
                         "admin": true,
                     }),
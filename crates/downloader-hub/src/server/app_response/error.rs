@@ -48,6 +48,13 @@ impl From<String> for ApiError {
     }
 }
 
+impl From<app_errors::AppError> for ApiError {
+    fn from(err: app_errors::AppError) -> Self {
+        err.user_message()
+            .map_or_else(|| Self(err.into()), Into::into)
+    }
+}
+
 impl From<&str> for ApiError {
     fn from(err: &str) -> Self {
         Self(anyhow::anyhow!(err.to_string()).into())
@@ -9,6 +9,8 @@ use self::error::ApiError;
 
 pub mod error;
 pub mod range_responder;
+#[cfg(feature = "io-uring")]
+pub mod uring_file;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
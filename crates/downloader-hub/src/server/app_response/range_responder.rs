@@ -4,6 +4,7 @@ use std::{
     path::{Path, PathBuf},
 };
 
+use app_config::Config;
 use app_helpers::file_type::infer_file_type;
 use axum::{
     body::Body,
@@ -11,7 +12,7 @@ use axum::{
         header::{self, IntoHeaderName},
         HeaderMap, HeaderValue, Request, StatusCode,
     },
-    response::IntoResponse,
+    response::{IntoResponse, Response},
 };
 use chrono::{DateTime, Utc};
 use tower_http::services::ServeFile;
@@ -141,16 +142,13 @@ impl RangeResponder {
         Ok(())
     }
 
-    pub async fn into_response(mut self, request_headers: HeaderMap) -> axum::response::Response {
+    pub async fn into_response(mut self, request_headers: HeaderMap) -> Response {
         self.try_add_metadata().await;
         if let Err(e) = self.respond_to_cache_headers(&request_headers) {
             return e.into_response();
         }
 
-        let mut request = Request::new(Body::empty());
-        *request.headers_mut() = request_headers;
-
-        let response = ServeFile::new(&self.path).try_call(request).await;
+        let response = self.serve(request_headers).await;
         let mut response = match response {
             Ok(response) => response,
             Err(e) => {
@@ -171,6 +169,48 @@ impl RangeResponder {
             }
         }
 
-        response.into_response()
+        response
+    }
+
+    async fn serve(&self, request_headers: HeaderMap) -> std::io::Result<Response> {
+        let chunk_size = Config::global().server().run.file_chunk_size();
+
+        #[cfg(feature = "io-uring")]
+        if !request_headers.contains_key(header::RANGE) {
+            if let Some(response) = self.uring_response(chunk_size) {
+                return Ok(response);
+            }
+        }
+
+        let mut request = Request::new(Body::empty());
+        *request.headers_mut() = request_headers;
+
+        ServeFile::new(&self.path)
+            .with_buf_chunk_size(chunk_size)
+            .try_call(request)
+            .await
+            .map(IntoResponse::into_response)
+    }
+
+    /// Serves the whole file using `io_uring`.
+    /// Range requests are left to the regular file server.
+    #[cfg(feature = "io-uring")]
+    fn uring_response(&self, chunk_size: usize) -> Option<Response> {
+        let len = self.metadata.as_ref()?.len();
+
+        let body = match super::uring_file::read_file(self.path.clone(), chunk_size) {
+            Ok(x) => x,
+            Err(e) => {
+                warn!(?e, "Failed to start reading file with io_uring");
+                return None;
+            }
+        };
+
+        let mut response = Body::from_stream(body).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::CONTENT_LENGTH, HeaderValue::from(len));
+        headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+        Some(response)
     }
 }
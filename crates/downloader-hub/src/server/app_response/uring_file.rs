@@ -0,0 +1,111 @@
+use std::{path::PathBuf, sync::LazyLock};
+
+use axum::body::Bytes;
+use futures::Stream;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+/// How many read chunks can be waiting for the client before reading pauses
+const READ_AHEAD_CHUNKS: usize = 4;
+
+type ChunkSender = mpsc::Sender<std::io::Result<Bytes>>;
+
+#[derive(Debug)]
+struct ReadFile {
+    path: PathBuf,
+    chunk_size: usize,
+    chunks: ChunkSender,
+}
+
+/// `tokio-uring` needs its own runtime, so one thread runs it for all the files being read.
+/// `None` if the thread couldn't be started.
+static URING_READER: LazyLock<Option<mpsc::UnboundedSender<ReadFile>>> = LazyLock::new(|| {
+    let (tx, mut rx) = mpsc::unbounded_channel::<ReadFile>();
+
+    let started = std::thread::Builder::new()
+        .name("uring-file-reader".to_string())
+        .spawn(move || {
+            tokio_uring::start(async move {
+                while let Some(job) = rx.recv().await {
+                    tokio_uring::spawn(read_chunks(job));
+                }
+            });
+        });
+
+    match started {
+        Ok(_) => Some(tx),
+        Err(e) => {
+            warn!(?e, "Failed to start io_uring file reader");
+            None
+        }
+    }
+});
+
+/// Reads the file using `io_uring` in chunks of `chunk_size` bytes.
+///
+/// The file is read on the `io_uring` thread and the chunks are handed over
+/// to the server's runtime through a channel.
+pub fn read_file(
+    path: PathBuf,
+    chunk_size: usize,
+) -> std::io::Result<impl Stream<Item = std::io::Result<Bytes>> + Send + 'static> {
+    let reader = URING_READER
+        .as_ref()
+        .ok_or_else(|| std::io::Error::other("io_uring file reader isn't running"))?;
+
+    let (tx, rx) = mpsc::channel(READ_AHEAD_CHUNKS);
+
+    reader
+        .send(ReadFile {
+            path,
+            chunk_size,
+            chunks: tx,
+        })
+        .map_err(|_| std::io::Error::other("io_uring file reader stopped"))?;
+
+    Ok(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|x| (x, rx))
+    }))
+}
+
+/// Runs on the `io_uring` thread's own executor, so it doesn't have to be `Send`
+#[allow(clippy::future_not_send)]
+async fn read_chunks(job: ReadFile) {
+    let ReadFile {
+        path,
+        chunk_size,
+        chunks: tx,
+    } = job;
+
+    let file = match tokio_uring::fs::File::open(&path).await {
+        Ok(x) => x,
+        Err(e) => {
+            let _ = tx.send(Err(e)).await;
+            return;
+        }
+    };
+
+    let mut position = 0_u64;
+    loop {
+        let (res, buf) = file.read_at(Vec::with_capacity(chunk_size), position).await;
+
+        let chunk = match res {
+            Ok(0) => break,
+            Ok(read) => {
+                position += read as u64;
+                Ok(Bytes::from(buf))
+            }
+            Err(e) => Err(e),
+        };
+        let failed = chunk.is_err();
+
+        // The client went away
+        if tx.send(chunk).await.is_err() || failed {
+            break;
+        }
+    }
+
+    if let Err(e) = file.close().await {
+        warn!(?e, ?path, "Failed to close file");
+    }
+}
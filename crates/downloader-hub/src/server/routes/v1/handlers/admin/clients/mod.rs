@@ -2,7 +2,7 @@ use app_entities::entity_meta::client::ClientWithHidden;
 use axum::{
     extract::{Path, Query},
     http::StatusCode,
-    routing::get,
+    routing::{get, patch},
     Json, Router,
 };
 use axum_extra::extract::WithRejection;
@@ -15,13 +15,17 @@ use crate::{
         routes::v1::response::{V1Error, V1Response, V1Result},
         AppRouter,
     },
-    service::client::{ClientCreateError, ClientCreatePayload, ClientService},
+    service::client::{
+        ClientCreateError, ClientCreatePayload, ClientService, ClientSettingsPayload,
+        ClientUpdateError,
+    },
 };
 
 pub(super) fn router() -> AppRouter {
     Router::new()
         .route("/", get(list_clients).put(add_client))
         .route("/:api_key", get(get_client).delete(remove_client))
+        .route("/:api_key/settings", patch(update_client_settings))
 }
 
 async fn list_clients(
@@ -73,6 +77,30 @@ async fn get_client(Path(client_uid): Path<String>) -> V1Result<ClientWithHidden
     Ok(V1Response::success(res))
 }
 
+/// Changes the settings kept in the client's app meta, eg. `{ "owner": true }`
+async fn update_client_settings(
+    Path(client_uid): Path<String>,
+    WithRejection(Json(payload), _): WithRejection<Json<ClientSettingsPayload>, V1Error>,
+) -> V1Result<ClientWithHidden> {
+    let res = ClientService::update_settings(&AppDb::db(), &client_uid, payload).await;
+
+    let res = match res {
+        Ok(Some(res)) => res,
+        Ok(None) => return Err(V1Response::error(StatusCode::NOT_FOUND, "Client not found")),
+        Err(e @ ClientUpdateError::UnknownSetting(_)) => {
+            return Err(V1Response::error(StatusCode::BAD_REQUEST, e.to_string()))
+        }
+        Err(e) => {
+            return Err(V1Response::error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                e.to_string(),
+            ))
+        }
+    };
+
+    Ok(V1Response::success(res))
+}
+
 async fn remove_client(Path(client_uid): Path<String>) -> V1Result<bool> {
     ClientService::delete_by_api_key(&AppDb::db(), client_uid).await?;
     Ok(V1Response::success(true))
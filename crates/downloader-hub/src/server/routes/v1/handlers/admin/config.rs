@@ -0,0 +1,18 @@
+use app_config::{check::ConfigReport, Config};
+use axum::{routing::get, Router};
+
+use crate::server::{
+    routes::v1::response::{V1Response, V1Result},
+    AppRouter,
+};
+
+pub(super) fn router() -> AppRouter {
+    Router::new().route("/", get(get_config))
+}
+
+/// The config the hub is running with, with the secrets redacted
+async fn get_config() -> V1Result<ConfigReport> {
+    Ok(V1Response::success(ConfigReport::for_config(
+        Config::global(),
+    )))
+}
@@ -33,6 +33,7 @@ pub(super) fn router() -> AppRouter {
 async fn queue_info() -> V1Response {
     V1Response::success(json!({
         "length": TASK_QUEUE.len(),
+        "metrics": TASK_QUEUE.metrics().snapshot(),
     }))
 }
 
@@ -57,6 +58,8 @@ struct DownloadRequestInfoQuery {
 #[serde(rename_all = "camelCase")]
 struct DownloadRequestInfoResponse {
     request: DownloadRequestWithHidden,
+    /// Whether the source was still there when it was last checked, if it was
+    source_live: Option<bool>,
     results: Vec<WithDownloadUrl<download_result::Model>>,
 }
 async fn request_info(
@@ -113,6 +116,7 @@ async fn request_info(
     };
 
     Ok(V1Response::success(DownloadRequestInfoResponse {
+        source_live: request.is_source_live(),
         request: request.into(),
         results,
     }))
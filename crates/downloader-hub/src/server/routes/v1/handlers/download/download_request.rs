@@ -1,23 +1,35 @@
+use std::time::Duration;
+
+use app_actions::{
+    collection::CollectionOrder, downloaders::ProgressEstimator, format_choice::FormatChoice,
+};
+use app_config::validators::str::parse_country_code;
 use app_entities::{
     download_request, download_result,
     entity_meta::download_request::{
         DownloadRequestAppMeta, DownloadRequestAppMetaInfo, DownloadRequestMeta,
     },
+    sea_orm_active_enums::ItemStatus,
 };
+use app_errors::{AppError, UserInputError};
+use app_helpers::domain::check_domain_allowed;
 use axum::{
     extract::{Path, Query},
     middleware,
-    routing::get,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::{get, post},
     Extension, Json, Router,
 };
 use axum_extra::extract::WithRejection;
-use futures::StreamExt;
+use futures::{stream, Stream, StreamExt};
 use sea_orm::prelude::*;
 use serde::{Deserialize, Serialize};
 use tower_http::request_id::RequestId;
+use url::Url;
 
 use crate::{
     db::AppDb,
+    queue::progress::DownloadProgressRegistry,
     server::{
         app_helpers::pagination::{Paginated, PaginationQuery},
         routes::v1::{
@@ -28,6 +40,7 @@ use crate::{
     },
     service::{
         download_request::{CreateDownloadRequestPayload, DownloadRequestService},
+        download_result::DownloadResultService,
         signature::{Signature, WithDownloadUrl},
     },
 };
@@ -36,6 +49,8 @@ pub(super) fn router() -> AppRouter {
     Router::new()
         .route("/", get(list_all).post(create_request))
         .route("/:uid", get(request_info))
+        .route("/:uid/events", get(request_events))
+        .route("/:uid/retry", post(retry_request))
         .route_layer(middleware::from_fn(require_auth_not_admin))
 }
 
@@ -62,6 +77,8 @@ struct DownloadRequestInfoQuery {
 #[serde(rename_all = "camelCase")]
 struct DownloadRequestInfoResponse {
     request: download_request::Model,
+    /// Whether the source was still there when it was last checked, if it was
+    source_live: Option<bool>,
     results: Vec<WithDownloadUrl<download_result::Model>>,
 }
 async fn request_info(
@@ -126,13 +143,100 @@ async fn request_info(
     };
 
     Ok(V1Response::success(DownloadRequestInfoResponse {
+        source_live: request.is_source_live(),
         request,
         results,
     }))
 }
 
+/// How often `progress` events are sent at most
+const PROGRESS_EVENT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sends `progress` events with the download speed and the time remaining
+/// while the request is being downloaded, and a `done` event with the status
+/// of the request once it isn't anymore
+async fn request_events(
+    Extension(user): Extension<CurrentUser>,
+    Path(uid): Path<String>,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, V1Error> {
+    DownloadRequestService::find_by_uid_and_client_id(&AppDb::db(), &uid, user.id)
+        .await?
+        .ok_or_else(V1Response::not_found)?;
+
+    let progress = DownloadProgressRegistry::subscribe(&uid).await;
+
+    let progress_events = stream::unfold(
+        (progress, ProgressEstimator::new()),
+        |(progress, mut estimator)| async move {
+            let mut progress = progress?;
+
+            tokio::time::sleep(PROGRESS_EVENT_INTERVAL).await;
+            progress.changed().await.ok()?;
+
+            let estimate = estimator.update(&progress.borrow_and_update());
+            let event = Event::default().event("progress").json_data(estimate);
+
+            Some((event, (Some(progress), estimator)))
+        },
+    );
+
+    let done_event = stream::once(async move {
+        let request =
+            DownloadRequestService::find_by_uid_and_client_id(&AppDb::db(), &uid, user.id)
+                .await
+                .map_err(axum::Error::new)?;
+
+        Event::default()
+            .event("done")
+            .json_data(request.map(|x| x.status))
+    });
+
+    Ok(Sse::new(progress_events.chain(done_event)).keep_alive(KeepAlive::default()))
+}
+
+/// Downloads the files that failed again, for requests that failed or only got some of the files
+async fn retry_request(
+    Extension(user): Extension<CurrentUser>,
+    Path(uid): Path<String>,
+) -> V1Result<download_request::Model> {
+    let db = AppDb::db();
+
+    let request = DownloadRequestService::find_by_uid_and_client_id(&db, &uid, user.id)
+        .await?
+        .ok_or_else(V1Response::not_found)?;
+
+    if !matches!(request.status, ItemStatus::Failed | ItemStatus::Partial) {
+        return Err(AppError::from(UserInputError::Invalid(format!(
+            "Only failed or partial requests can be retried, this one is {:?}",
+            request.status
+        )))
+        .into());
+    }
+
+    // Results from before the URLs were kept can't be skipped, so they'd be downloaded twice
+    let results = DownloadResultService::find_by_request_id(&db, request.id).await?;
+    if results
+        .iter()
+        .any(|x| x.status != ItemStatus::Failed && x.item_url().is_none())
+    {
+        return Err(AppError::from(UserInputError::Invalid(
+            "The downloaded files of this request aren't known, so it can't be retried".to_string(),
+        ))
+        .into());
+    }
+
+    DownloadRequestService::retry_failed(&db, &request).await?;
+
+    let request = DownloadRequestService::find_by_uid(&db, &uid)
+        .await?
+        .ok_or_else(V1Response::not_found)?;
+
+    Ok(V1Response::success(request))
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", untagged)]
+#[allow(clippy::large_enum_variant)]
 enum RequestDownloadPayload {
     Url(RequestDownloadPayloadUrl),
     Urls(Vec<RequestDownloadPayloadUrl>),
@@ -155,6 +259,47 @@ async fn create_request(
         RequestDownloadPayload::Urls(urls) => urls,
     };
 
+    let refused = urls
+        .iter()
+        .filter_map(|x| Url::parse(&x.url).ok())
+        .filter_map(|x| check_domain_allowed(&x).err())
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>();
+
+    if !refused.is_empty() {
+        return Err(AppError::from(UserInputError::NotAllowed(refused.join("; "))).into());
+    }
+
+    let invalid_formats = urls
+        .iter()
+        .filter_map(|x| x.meta.as_ref()?.format.as_deref())
+        .filter_map(|x| x.parse::<FormatChoice>().err())
+        .collect::<Vec<_>>();
+
+    if !invalid_formats.is_empty() {
+        return Err(AppError::from(UserInputError::Invalid(invalid_formats.join("; "))).into());
+    }
+
+    let invalid_countries = urls
+        .iter()
+        .filter_map(|x| x.meta.as_ref()?.geo_bypass_country.as_deref())
+        .filter_map(|x| parse_country_code(x).err())
+        .collect::<Vec<_>>();
+
+    if !invalid_countries.is_empty() {
+        return Err(AppError::from(UserInputError::Invalid(invalid_countries.join("; "))).into());
+    }
+
+    let invalid_orders = urls
+        .iter()
+        .filter_map(|x| x.meta.as_ref()?.collection.as_ref()?.order.as_deref())
+        .filter_map(|x| x.parse::<CollectionOrder>().err())
+        .collect::<Vec<_>>();
+
+    if !invalid_orders.is_empty() {
+        return Err(AppError::from(UserInputError::Invalid(invalid_orders.join("; "))).into());
+    }
+
     let app_meta = Some(DownloadRequestAppMeta::Info(DownloadRequestAppMetaInfo {
         request_id: request_id
             .header_value()
@@ -1,17 +1,22 @@
+use app_config::Config;
 use app_entities::{
     download_result,
     entity_meta::{common::path::AppPath, download_result::DownloadResultMeta},
     sea_orm_active_enums::ItemStatus,
 };
+use app_errors::AppError;
 use axum::{
+    body::Body,
     extract::{OriginalUri, Path, Query},
-    http::{header, HeaderMap, StatusCode},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
     middleware,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::get,
     Router,
 };
+use futures::{future, stream, StreamExt};
 use serde::Deserialize;
+use tokio_util::io::ReaderStream;
 use tracing::{error, trace};
 
 use crate::{
@@ -27,6 +32,7 @@ use crate::{
     service::{
         download_result::DownloadResultService,
         signature::{Signature, WithDownloadUrl},
+        storage::StorageService,
     },
 };
 
@@ -99,6 +105,7 @@ async fn download_result(
             .into_response());
     }
 
+    #[allow(clippy::result_large_err)]
     let result_meta = result.meta().map(|x| match x {
         DownloadResultMeta::FileData(_) => Ok(x),
         DownloadResultMeta::Error(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, e).into_response()),
@@ -108,16 +115,23 @@ async fn download_result(
         return Err(e);
     }
 
+    let etag = match &result_meta {
+        Some(Ok(DownloadResultMeta::FileData(x))) => format!("{:?}", x.hash).parse().ok(),
+        _ => None,
+    };
+
     let file_path = {
         let path = match result.path() {
             Some(path) => path,
             None => return Err((StatusCode::NOT_FOUND).into_response()),
         };
 
-        #[allow(clippy::match_wildcard_for_single_variants)]
         let path = match path {
             AppPath::LocalAbsolute(path) => path,
-            _ => return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response()),
+            AppPath::Remote(remote_path) => {
+                return Ok(remote_file_response(&remote_path, etag).await)
+            }
+            AppPath::None => return Err((StatusCode::INTERNAL_SERVER_ERROR).into_response()),
         };
 
         if !path.exists() {
@@ -138,6 +152,8 @@ async fn download_result(
         )
         .add_header(
             header::CONTENT_DISPOSITION,
+            // `Debug` quotes and escapes the file name
+            #[allow(clippy::unnecessary_debug_formatting)]
             format!(
                 "inline; filename={:?}",
                 file_path.file_name().unwrap_or_default()
@@ -150,18 +166,66 @@ async fn download_result(
             "public".parse().expect("Invalid pragma header value"),
         );
 
-    if let Some(Ok(result_meta)) = result_meta {
-        match result_meta {
-            DownloadResultMeta::FileData(x) => {
-                if let Ok(etag) = format!("{:?}", x.hash).parse() {
-                    responder.add_header(header::ETAG, etag);
-                }
-            }
-            DownloadResultMeta::Error(_) => {}
-        };
+    if let Some(etag) = etag {
+        responder.add_header(header::ETAG, etag);
     }
 
     let resp = responder.into_response(headers).await;
 
     Ok(resp)
 }
+
+/// Streams the file from the storage remote as it's read.
+/// Ranges aren't supported there, so the whole file is always sent.
+///
+/// If rclone fails after the response has started, the body ends with an error
+/// so the client sees a broken transfer instead of a short file.
+async fn remote_file_response(remote_path: &str, etag: Option<HeaderValue>) -> Response {
+    let file = match StorageService::open(remote_path).await {
+        Ok(Some(x)) => x,
+        Ok(None) => {
+            trace!(?remote_path, "Stored file not found");
+
+            return (StatusCode::NOT_FOUND).into_response();
+        }
+        Err(e) => {
+            error!(?e, ?remote_path, "Failed to open stored file");
+
+            return (StatusCode::BAD_GATEWAY).into_response();
+        }
+    };
+
+    let file_name = remote_path.rsplit(['/', ':']).next().unwrap_or(remote_path);
+
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static("public, max-age=31536000, immutable"),
+    );
+    headers.insert(header::PRAGMA, HeaderValue::from_static("public"));
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("none"));
+    if let Ok(disposition) = format!("inline; filename={file_name:?}").parse() {
+        headers.insert(header::CONTENT_DISPOSITION, disposition);
+    }
+    if let Some(etag) = etag {
+        headers.insert(header::ETAG, etag);
+    }
+
+    (
+        headers,
+        Body::from_stream(
+            ReaderStream::with_capacity(
+                file.reader,
+                Config::global().server().run.file_chunk_size(),
+            )
+            .chain(stream::once(file.finished).filter_map(|x| {
+                future::ready(x.err().map(|e| {
+                    error!(?e, "Failed to stream stored file");
+
+                    Err(std::io::Error::other(AppError::from(e).to_string()))
+                }))
+            })),
+        ),
+    )
+        .into_response()
+}
@@ -0,0 +1,52 @@
+use app_actions::{
+    age_restriction::{allows_age_restricted, ALLOW_AGE_RESTRICTED_OPTION},
+    downloaders::DownloaderOptions,
+    extract_only,
+    extractors::ExtractionSummary,
+};
+use app_errors::{AppError, UserInputError};
+use app_helpers::{domain::check_domain_allowed, ip::url_resolves_to_valid_ip};
+use axum::{middleware, routing::post, Extension, Json, Router};
+use axum_extra::extract::WithRejection;
+use serde::Deserialize;
+
+use crate::server::{
+    routes::v1::{
+        middleware::auth::{require_auth_not_admin, CurrentUser},
+        response::{V1Error, V1Response, V1Result},
+    },
+    AppRouter,
+};
+
+pub(super) fn router() -> AppRouter {
+    Router::new()
+        .route("/", post(extract))
+        .route_layer(middleware::from_fn(require_auth_not_admin))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[serde(deny_unknown_fields)]
+struct ExtractPayload {
+    url: String,
+}
+/// Finds the metadata and the media URLs of the link without downloading anything
+async fn extract(
+    Extension(user): Extension<CurrentUser>,
+    WithRejection(Json(payload), _): WithRejection<Json<ExtractPayload>, V1Error>,
+) -> V1Result<ExtractionSummary> {
+    let url = url_resolves_to_valid_ip(&payload.url)
+        .map_err(|e| AppError::from(UserInputError::NotAllowed(e.to_string())))?;
+    check_domain_allowed(&url)
+        .map_err(|e| AppError::from(UserInputError::NotAllowed(e.to_string())))?;
+
+    let mut options = DownloaderOptions::new();
+    options.insert(
+        ALLOW_AGE_RESTRICTED_OPTION.to_string(),
+        allows_age_restricted(user.is_owner()).into(),
+    );
+
+    let summary = extract_only(&url, &options).await?;
+
+    Ok(V1Response::success(summary))
+}
@@ -5,10 +5,12 @@ use crate::server::AppRouter;
 mod admin;
 mod clients;
 mod download;
+mod extract;
 
 pub(super) fn router() -> AppRouter {
     Router::new()
         .nest("/clients", clients::router())
         .nest("/download", download::router())
+        .nest("/extract", extract::router())
         .nest("/admin", admin::router())
 }
@@ -1,3 +1,4 @@
+use app_errors::{AppError, UserInputError};
 use axum::{
     extract::rejection::{JsonRejection, QueryRejection},
     http::StatusCode,
@@ -117,6 +118,8 @@ pub enum V1Error {
     ErrorEmpty(StatusCode),
     #[error(transparent)]
     MultipartError(#[from] axum_extra::extract::multipart::MultipartError),
+    #[error(transparent)]
+    App(#[from] AppError),
 }
 impl V1Error {
     pub fn not_found() -> Self {
@@ -159,6 +162,25 @@ impl IntoResponse for V1Error {
             Self::MultipartError(err) => {
                 V1Response::<String>::Error(err.status(), err.body_text().into()).into_response()
             }
+            Self::App(err) => {
+                let status = match &err {
+                    AppError::UserInput(
+                        UserInputError::NotAllowed(_) | UserInputError::Blocked(_),
+                    ) => StatusCode::FORBIDDEN,
+                    AppError::UserInput(UserInputError::TooLarge(_)) => {
+                        StatusCode::PAYLOAD_TOO_LARGE
+                    }
+                    AppError::UserInput(_) => StatusCode::BAD_REQUEST,
+                    AppError::UnsupportedSource(_) => StatusCode::UNPROCESSABLE_ENTITY,
+                    AppError::SourceGone(_) => StatusCode::GONE,
+                    AppError::Network(_) => StatusCode::BAD_GATEWAY,
+                    AppError::ExternalTool(_) | AppError::Other(_) => {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+
+                V1Response::<String>::Error(status, err.into()).into_response()
+            }
         }
     }
 }
@@ -65,6 +65,48 @@ impl ClientService {
             .await
     }
 
+    /// Sets the settings in the client's app meta, removing the ones set to `null`
+    pub async fn update_settings<TDb, TValue>(
+        db: &TDb,
+        api_key: TValue,
+        payload: ClientSettingsPayload,
+    ) -> Result<Option<client::Model>, ClientUpdateError>
+    where
+        TDb: ConnectionTrait,
+        TValue: Into<Value> + Send + Sync,
+    {
+        if let Some(key) = payload
+            .0
+            .keys()
+            .find(|x| !CLIENT_SETTINGS_KEYS.contains(&x.as_str()))
+        {
+            return Err(ClientUpdateError::UnknownSetting(key.clone()));
+        }
+
+        let Some(client) = Self::get_by_api_key(db, api_key).await? else {
+            return Ok(None);
+        };
+
+        let mut app_meta = match &client.app_meta {
+            serde_json::Value::Object(x) => x.clone(),
+            _ => serde_json::Map::new(),
+        };
+        for (key, value) in payload.0 {
+            if value.is_null() {
+                app_meta.remove(&key);
+            } else {
+                app_meta.insert(key, value);
+            }
+        }
+
+        info!(client = ?client.name, ?app_meta, "Updating client settings");
+
+        let mut model: client::ActiveModel = client.into();
+        model.app_meta = Set(serde_json::Value::Object(app_meta));
+
+        Ok(Some(model.update(db).await?))
+    }
+
     pub async fn delete_by_api_key<TDb, TValue>(
         db: &TDb,
         api_key: TValue,
@@ -87,6 +129,22 @@ pub struct ClientCreatePayload {
     pub download_folder: String,
 }
 
+/// Settings of the client that are kept in its app meta
+pub const CLIENT_SETTINGS_KEYS: &[&str] = &["owner", "mediaPolicy", "allowedContentTypes"];
+
+/// Settings to change, by their key in the app meta (eg. `{ "owner": true }`).
+/// Settings set to `null` are removed.
+#[derive(Debug, Deserialize)]
+pub struct ClientSettingsPayload(pub serde_json::Map<String, serde_json::Value>);
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientUpdateError {
+    #[error("Unknown client setting {0:?}")]
+    UnknownSetting(String),
+    #[error(transparent)]
+    DbErr(#[from] DbErr),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ClientCreateError {
     #[error("Client with that name already exists")]
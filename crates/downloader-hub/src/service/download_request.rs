@@ -6,13 +6,18 @@ use app_entities::{
     sea_orm_active_enums::ItemStatus,
 };
 use sea_orm::{
-    prelude::*, sea_query::IntoCondition, AccessMode, IsolationLevel, QueryOrder, Set,
-    TransactionError, TransactionTrait, UpdateResult,
+    prelude::*,
+    sea_query::{Expr, IntoCondition},
+    AccessMode, Condition, IsolationLevel, QueryOrder, QuerySelect, Set, TransactionError,
+    TransactionTrait, UpdateResult,
 };
 
-use super::id::AppUidFor;
+use super::{download_result::DownloadResultService, id::AppUidFor};
 use crate::{
-    queue::{task::Task, TASK_QUEUE},
+    queue::{
+        task::{Task, TaskInfo},
+        TASK_QUEUE,
+    },
     server::app_helpers::pagination::{Paginated, PaginationQuery},
 };
 
@@ -65,22 +70,86 @@ impl DownloadRequestService {
         })?;
 
         for uid in uids {
-            TASK_QUEUE.push(Task::download_request(uid));
+            TASK_QUEUE.push(Task::new(TaskInfo::DownloadRequest(uid)));
         }
 
         Ok(requests)
     }
 
+    /// Including the ones that were being processed when the hub stopped,
+    /// so they're picked up again (and resumed if they can be)
     pub async fn find_pending<TDb>(db: &TDb) -> Result<Vec<download_request::Model>, DbErr>
     where
         TDb: ConnectionTrait,
     {
         download_request::Entity::find()
-            .filter(download_request::Column::Status.eq(ItemStatus::Pending))
+            .filter(
+                download_request::Column::Status
+                    .is_in([ItemStatus::Pending, ItemStatus::Processing]),
+            )
             .all(db)
             .await
     }
 
+    /// Successful archived posts whose source wasn't checked since `checked_before`,
+    /// in the order they were made, starting after the request with `after_id`
+    pub async fn find_due_source_checks<TDb>(
+        db: &TDb,
+        checked_before: DateTimeWithTimeZone,
+        after_id: i32,
+        limit: u64,
+    ) -> Result<Vec<download_request::Model>, DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        download_request::Entity::find()
+            .filter(download_request::Column::Status.eq(ItemStatus::Success))
+            .filter(Expr::cust(r#""meta" @> '{"archivePost": true}'"#))
+            .filter(
+                Condition::any()
+                    .add(download_request::Column::SourceCheckedAt.is_null())
+                    .add(download_request::Column::SourceCheckedAt.lt(checked_before)),
+            )
+            .filter(download_request::Column::Id.gt(after_id))
+            .order_by_asc(download_request::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+
+    /// Records whether the source of the request is still there.
+    ///
+    /// Sources that stay dead keep the time they were first noticed to be gone.
+    pub async fn update_source_status<TDb>(
+        db: &TDb,
+        request: &download_request::Model,
+        live: bool,
+    ) -> Result<UpdateResult, DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        let now: DateTimeWithTimeZone = chrono::Utc::now().into();
+
+        let model = {
+            let mut model = download_request::ActiveModel::new();
+
+            model.source_checked_at = Set(Some(now));
+            model.source_dead_at = Set(if live {
+                None
+            } else {
+                request.source_dead_at.or(Some(now))
+            });
+
+            model
+        };
+
+        download_request::Entity::update_many()
+            .set(model)
+            .filter(download_request::Column::Id.eq(request.id))
+            .exec(db)
+            .await
+    }
+
     pub async fn update_status<TDb, TValue>(
         db: &TDb,
         uid: TValue,
@@ -93,7 +162,9 @@ impl DownloadRequestService {
         let model = {
             let mut model = download_request::ActiveModel::new();
 
-            if let DownloadRequestStatus::Failed(err) = &status {
+            if let DownloadRequestStatus::Failed(err) | DownloadRequestStatus::Blocked(err) =
+                &status
+            {
                 model.app_meta = Set(DownloadRequestAppMeta::Error(err.clone()).into());
             }
             model.updated_at = Set(chrono::Utc::now().into());
@@ -109,6 +180,35 @@ impl DownloadRequestService {
             .await
     }
 
+    /// Downloads the files of the request that failed again.
+    /// The files that were downloaded are kept and skipped.
+    pub async fn retry_failed<TDb>(db: &TDb, request: &download_request::Model) -> Result<(), DbErr>
+    where
+        TDb: ConnectionTrait + TransactionTrait,
+    {
+        let request_id = request.id;
+        let uid = request.request_uid.clone();
+
+        db.transaction::<_, _, DbErr>(|tx| {
+            Box::pin(async move {
+                DownloadResultService::delete_failed(tx, request_id).await?;
+                Self::update_status(tx, uid, DownloadRequestStatus::Pending).await?;
+
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e| match e {
+            TransactionError::Transaction(e) | TransactionError::Connection(e) => e,
+        })?;
+
+        TASK_QUEUE.push(Task::new(TaskInfo::DownloadRequest(
+            request.request_uid.clone(),
+        )));
+
+        Ok(())
+    }
+
     pub async fn find_by_uid<TDb, TValue1>(
         db: &TDb,
         uid: TValue1,
@@ -171,6 +271,31 @@ impl DownloadRequestService {
         Ok(Some((request, client)))
     }
 
+    pub async fn find_by_id_with_client<TDb>(
+        db: &TDb,
+        id: i32,
+    ) -> Result<Option<(download_request::Model, app_entities::client::Model)>, DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        let res = download_request::Entity::find_by_id(id)
+            .find_also_related(app_entities::client::Entity)
+            .one(db)
+            .await?;
+
+        let (request, client) = match res {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        let client = match client {
+            Some(x) => x,
+            None => return Ok(None),
+        };
+
+        Ok(Some((request, client)))
+    }
+
     pub async fn find_all_paginated<TDb, TFilter>(
         db: &TDb,
         pagination_query: PaginationQuery,
@@ -194,7 +319,11 @@ impl DownloadRequestService {
 }
 
 pub enum DownloadRequestStatus {
+    /// A downloaded file matched the hash blocklist
+    Blocked(String),
     Failed(String),
+    /// Some of the files were downloaded, but not all of them
+    Partial,
     Pending,
     Processing,
     Success,
@@ -202,7 +331,9 @@ pub enum DownloadRequestStatus {
 impl From<DownloadRequestStatus> for ItemStatus {
     fn from(status: DownloadRequestStatus) -> Self {
         match status {
+            DownloadRequestStatus::Blocked(_) => Self::Blocked,
             DownloadRequestStatus::Failed(_) => Self::Failed,
+            DownloadRequestStatus::Partial => Self::Partial,
             DownloadRequestStatus::Pending => Self::Pending,
             DownloadRequestStatus::Processing => Self::Processing,
             DownloadRequestStatus::Success => Self::Success,
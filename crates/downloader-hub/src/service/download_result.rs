@@ -1,15 +1,19 @@
 use std::{convert::Into, path::PathBuf, time::Instant};
 
+use app_actions::downloaders::{DownloaderAttempt, MediaMetadata};
 use app_entities::{
     download_result,
     entity_meta::{
         common::path::AppPath,
-        download_result::{DownloadResultMeta, DownloadResultMetaFileData, DownloadResultStatus},
+        download_result::{
+            DownloadResultMeta, DownloadResultMetaFileData, DownloadResultStatus,
+            DOWNLOADER_CHAIN_KEY, ITEM_URL_KEY, MEDIA_METADATA_KEY,
+        },
     },
-    sea_orm_active_enums::ItemStatusEnum,
+    sea_orm_active_enums::{ItemStatus, ItemStatusEnum},
 };
 use app_migration::IntoColumnRef;
-use sea_orm::{prelude::*, InsertResult, Set, TryInsertResult, UpdateResult};
+use sea_orm::{prelude::*, DeleteResult, InsertResult, Set, TryInsertResult, UpdateResult};
 use tracing::{trace, warn};
 
 use crate::service::{file::FileService, id::AppUidFor};
@@ -200,6 +204,31 @@ impl DownloadResultService {
             .await
     }
 
+    pub async fn find_by_request_id<TDb>(
+        db: &TDb,
+        request_id: i32,
+    ) -> Result<Vec<download_result::Model>, DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        download_result::Entity::find()
+            .filter(download_result::Column::DownloadRequestId.eq(request_id))
+            .all(db)
+            .await
+    }
+
+    /// Removes the results of the files that failed, so they're downloaded again
+    pub async fn delete_failed<TDb>(db: &TDb, request_id: i32) -> Result<DeleteResult, DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        download_result::Entity::delete_many()
+            .filter(download_result::Column::DownloadRequestId.eq(request_id))
+            .filter(download_result::Column::Status.eq(ItemStatus::Failed))
+            .exec(db)
+            .await
+    }
+
     pub async fn find_pending_results<TDb>(db: &TDb) -> Result<Vec<download_result::Model>, DbErr>
     where
         TDb: ConnectionTrait,
@@ -218,6 +247,12 @@ pub struct CreateDownloadResultPayload {
     pub status: DownloadResultStatus,
     pub path: Option<PathBuf>,
     pub meta: Option<DownloadResultMeta>,
+    /// What the site says about the downloaded media
+    pub media_metadata: Option<MediaMetadata>,
+    /// The downloaders that were tried for the file, in order
+    pub downloader_chain: Vec<DownloaderAttempt>,
+    /// The URL the file was downloaded from
+    pub item_url: Option<String>,
 }
 impl CreateDownloadResultPayload {
     pub fn into_active_model(self) -> download_result::ActiveModel {
@@ -229,9 +264,29 @@ impl CreateDownloadResultPayload {
             ..Default::default()
         };
 
-        if let Some(meta) = self.meta {
-            model.meta = Set(meta.into());
+        let mut meta = self
+            .meta
+            .map_or_else(|| serde_json::json!({}), serde_json::Value::from);
+        if let Some(meta) = meta.as_object_mut() {
+            if let Some(media_metadata) = self.media_metadata {
+                meta.insert(
+                    MEDIA_METADATA_KEY.to_string(),
+                    serde_json::to_value(media_metadata).expect("Invalid media metadata"),
+                );
+            }
+
+            if !self.downloader_chain.is_empty() {
+                meta.insert(
+                    DOWNLOADER_CHAIN_KEY.to_string(),
+                    serde_json::to_value(self.downloader_chain).expect("Invalid downloader chain"),
+                );
+            }
+
+            if let Some(item_url) = self.item_url {
+                meta.insert(ITEM_URL_KEY.to_string(), item_url.into());
+            }
         }
+        model.meta = Set(meta);
 
         model
     }
@@ -0,0 +1,35 @@
+use app_entities::file_hash;
+use sea_orm::{prelude::*, sea_query::OnConflict, Set};
+
+pub struct FileHashService;
+impl FileHashService {
+    pub async fn find_by_hash<TDb>(db: &TDb, hash: &str) -> Result<Option<file_hash::Model>, DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        file_hash::Entity::find_by_id(hash).one(db).await
+    }
+
+    /// Points the hash at the file, instead of the one it pointed at before
+    pub async fn upsert<TDb>(db: &TDb, hash: String, path: String) -> Result<(), DbErr>
+    where
+        TDb: ConnectionTrait,
+    {
+        let model = file_hash::ActiveModel {
+            hash: Set(hash),
+            path: Set(path),
+            created_at: Set(chrono::Utc::now().into()),
+        };
+
+        file_hash::Entity::insert(model)
+            .on_conflict(
+                OnConflict::column(file_hash::Column::Hash)
+                    .update_columns([file_hash::Column::Path, file_hash::Column::CreatedAt])
+                    .to_owned(),
+            )
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -2,5 +2,7 @@ pub mod client;
 pub mod download_request;
 pub mod download_result;
 pub mod file;
+pub mod file_hash;
 pub mod id;
 pub mod signature;
+pub mod storage;
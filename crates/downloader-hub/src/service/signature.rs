@@ -66,7 +66,7 @@ impl Signature {
         let vals = vals.as_object().expect("Failed to convert to object");
         let vals = vals
             .iter()
-            .filter_map(|(k, v)| Some((k.to_string(), v.as_str()?.to_string())))
+            .filter_map(|(k, v)| Some((k.clone(), v.as_str()?.to_string())))
             .collect::<Vec<_>>();
 
         vals
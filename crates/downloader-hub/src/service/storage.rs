@@ -0,0 +1,197 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use app_config::Config;
+use app_entities::entity_meta::common::path::AppPath;
+use app_errors::{AppError, ExternalToolError};
+use futures::future::BoxFuture;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncWriteExt, BufReader},
+    process::{Child, ChildStdout, Command},
+};
+use tracing::debug;
+
+const TOOL_NAME: &str = "rclone";
+
+/// Where the finished downloads are kept, if not on the local disk.
+///
+/// S3, `MinIO` and the like are reached through an `rclone` remote.
+/// The downloaders and fixers work on files, so downloads land in local temp first
+/// and are streamed to the remote once they're done, never while they're downloading.
+pub struct StorageService {}
+impl StorageService {
+    /// The rclone remote the files are streamed to, if one is set
+    pub fn remote() -> Option<&'static str> {
+        Config::global().server().storage.storage_remote.as_deref()
+    }
+
+    pub fn is_remote() -> bool {
+        Self::remote().is_some()
+    }
+
+    /// Streams the local file to `<remote>/<dir_name>/<file name>`.
+    /// The local file is kept, it's up to the caller to remove it once the new path is saved.
+    ///
+    /// Returns the local path if there's no remote to put it in.
+    pub async fn store(path: &Path, dir_name: &str) -> Result<AppPath, AppError> {
+        let Some(remote) = Self::remote() else {
+            return Ok(AppPath::LocalAbsolute(path.to_path_buf()));
+        };
+
+        let file_name = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .ok_or_else(|| AppError::other(format!("Not a file: {}", path.display())))?;
+        let remote_path = remote_path(remote, &format!("{dir_name}/{file_name}"));
+
+        debug!(?path, ?remote_path, "Streaming file to storage");
+
+        let mut file = tokio::fs::File::open(path).await?;
+        Self::store_stream(&mut file, &remote_path).await?;
+
+        Ok(AppPath::Remote(remote_path))
+    }
+
+    /// Streams the bytes to the path on the remote as they're read, without touching the disk
+    pub async fn store_stream<R>(reader: &mut R, remote_path: &str) -> Result<(), AppError>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let mut child = Command::new(rclone_path()?)
+            .args(["rcat", remote_path])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ExternalToolError::unavailable(TOOL_NAME, e.to_string()))?;
+
+        let mut stdin = child.stdin.take().expect("rclone stdin is piped");
+        let copied = tokio::io::copy(reader, &mut stdin).await;
+        let _ = stdin.shutdown().await;
+        drop(stdin);
+
+        let output = child
+            .wait_with_output()
+            .await
+            .map_err(|e| ExternalToolError::failed(TOOL_NAME, e.to_string()))?;
+
+        if !output.status.success() {
+            return Err(ExternalToolError::failed(
+                TOOL_NAME,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+            .into());
+        }
+
+        copied.map_err(|e| AppError::other(format!("Failed to stream file to storage: {e}")))?;
+
+        Ok(())
+    }
+
+    /// Streams the file from the remote.
+    ///
+    /// Waits for the first bytes, or for rclone to exit if there are none,
+    /// so a missing file or a broken remote is an error instead of an empty file.
+    /// Returns `None` if the file isn't on the remote.
+    pub async fn open(remote_path: &str) -> Result<Option<RemoteFile>, AppError> {
+        let mut child = Command::new(rclone_path()?)
+            .args(["cat", remote_path])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| ExternalToolError::unavailable(TOOL_NAME, e.to_string()))?;
+
+        let stdout = child.stdout.take().expect("rclone stdout is piped");
+        let mut reader =
+            BufReader::with_capacity(Config::global().server().run.file_chunk_size(), stdout);
+
+        let has_data = reader
+            .fill_buf()
+            .await
+            .map(|x| !x.is_empty())
+            .map_err(|e| ExternalToolError::failed(TOOL_NAME, e.to_string()))?;
+
+        if has_data {
+            return Ok(Some(RemoteFile {
+                reader,
+                finished: Box::pin(wait_for_exit(child)),
+            }));
+        }
+
+        match wait_for_exit(child).await {
+            Ok(()) => Ok(Some(RemoteFile {
+                reader,
+                finished: Box::pin(async { Ok(()) }),
+            })),
+            Err(RcloneExit::NotFound) => Ok(None),
+            Err(RcloneExit::Failed(e)) => Err(e),
+        }
+    }
+}
+
+/// A file that's being streamed from the remote
+pub struct RemoteFile {
+    pub reader: BufReader<ChildStdout>,
+    /// Resolves once rclone is done, with an error if it failed part way through
+    pub finished: BoxFuture<'static, Result<(), RcloneExit>>,
+}
+
+#[derive(Debug)]
+pub enum RcloneExit {
+    NotFound,
+    Failed(AppError),
+}
+impl From<RcloneExit> for AppError {
+    fn from(val: RcloneExit) -> Self {
+        match val {
+            RcloneExit::NotFound => Self::other("File not found in storage"),
+            RcloneExit::Failed(e) => e,
+        }
+    }
+}
+
+async fn wait_for_exit(child: Child) -> Result<(), RcloneExit> {
+    let output = child.wait_with_output().await.map_err(|e| {
+        RcloneExit::Failed(ExternalToolError::failed(TOOL_NAME, e.to_string()).into())
+    })?;
+
+    match output.status.code() {
+        Some(0) => Ok(()),
+        // Directory and file not found
+        Some(3 | 4) => Err(RcloneExit::NotFound),
+        _ => Err(RcloneExit::Failed(
+            ExternalToolError::failed(
+                TOOL_NAME,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            )
+            .into(),
+        )),
+    }
+}
+
+/// Joins the path onto the remote
+fn remote_path(remote: &str, path: &str) -> String {
+    if remote.ends_with(':') || remote.ends_with('/') {
+        format!("{remote}{path}")
+    } else {
+        format!("{remote}/{path}")
+    }
+}
+
+fn rclone_path() -> Result<PathBuf, AppError> {
+    Config::global()
+        .dependency_paths
+        .rclone_path()
+        .ok_or_else(|| {
+            ExternalToolError::unavailable(
+                TOOL_NAME,
+                "Please make sure it is installed and added to the PATH environment variable",
+            )
+            .into()
+        })
+}
@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use app_actions::source_check::{check_source, SourceStatus};
+use app_config::Config;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use tracing::{debug, error, info, warn};
+use url::Url;
+
+use crate::{db::AppDb, service::download_request::DownloadRequestService};
+
+/// How many requests are loaded from the database at a time
+const BATCH_SIZE: u64 = 50;
+/// Keeps the checks from hammering the sites when there are a lot of archived posts
+const DELAY_BETWEEN_CHECKS: Duration = Duration::from_secs(2);
+
+/// Periodically checks whether the sources of archived posts are still up.
+///
+/// Does nothing if `--source-recheck-interval` isn't set.
+pub async fn run() {
+    let Some(interval) = Config::global().task.source_recheck_interval else {
+        return;
+    };
+    let interval: Duration = interval.into();
+
+    info!(?interval, "Starting source recheck task");
+
+    loop {
+        if let Err(e) = recheck_due_sources(interval).await {
+            error!(?e, "Failed to recheck sources");
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+#[tracing::instrument]
+async fn recheck_due_sources(interval: Duration) -> Result<(), sea_orm::DbErr> {
+    let db = AppDb::db();
+    let checked_before: DateTimeWithTimeZone = (chrono::Utc::now()
+        - chrono::Duration::from_std(interval).unwrap_or_else(|_| chrono::Duration::days(1)))
+    .into();
+
+    let mut after_id = 0;
+    loop {
+        let requests = DownloadRequestService::find_due_source_checks(
+            &db,
+            checked_before,
+            after_id,
+            BATCH_SIZE,
+        )
+        .await?;
+
+        let Some(last) = requests.last() else {
+            return Ok(());
+        };
+        after_id = last.id;
+
+        debug!(count = requests.len(), "Rechecking sources");
+
+        for request in requests {
+            let Ok(url) = Url::parse(&request.url) else {
+                warn!(url = ?request.url, "Invalid source URL");
+                continue;
+            };
+
+            match check_source(&url).await {
+                Ok(status) => {
+                    debug!(?url, ?status, "Checked source");
+
+                    DownloadRequestService::update_source_status(
+                        &db,
+                        &request,
+                        status == SourceStatus::Live,
+                    )
+                    .await?;
+                }
+                // Tried again next time
+                Err(e) => {
+                    debug!(?url, ?e, "Couldn't tell if source is still up");
+                }
+            }
+
+            tokio::time::sleep(DELAY_BETWEEN_CHECKS).await;
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::{collections::HashMap, future::Future, time::Duration};
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use teloxide::types::{ChatId, Message};
+use tracing::{debug, trace, Instrument};
+
+/// How long to wait for more parts of an album after the last one came in
+const ALBUM_PART_WAIT: Duration = Duration::from_secs(2);
+
+/// Telegram sends each photo or video of an album (eg. a forwarded channel post)
+/// as its own message, so they're collected here to be handled as one post
+/// The chat and the media group ID of an album
+type AlbumKey = (ChatId, String);
+
+static PENDING_ALBUMS: Lazy<Mutex<HashMap<AlbumKey, Vec<Message>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Adds the message to its album.
+///
+/// The first part starts a task that waits until no more parts come in
+/// and then calls `on_collected` with all of them in order.
+/// It runs apart from the message handlers, so they're free to add the other parts meanwhile.
+///
+/// Messages that aren't part of an album are passed to `on_collected` right away.
+#[allow(clippy::significant_drop_tightening)]
+pub fn collect_album<F, Fut>(msg: Message, on_collected: F)
+where
+    F: FnOnce(Vec<Message>) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let Some(group_id) = msg.media_group_id().map(ToString::to_string) else {
+        tokio::task::spawn(on_collected(vec![msg]).in_current_span());
+        return;
+    };
+    let key = (msg.chat.id, group_id);
+
+    let count = {
+        let mut albums = PENDING_ALBUMS.lock();
+        let parts = albums.entry(key.clone()).or_default();
+        parts.push(msg);
+        parts.len()
+    };
+    if count > 1 {
+        trace!(?key, count, "Added part to pending album");
+        return;
+    }
+
+    tokio::task::spawn(
+        async move {
+            let parts = wait_for_parts(key).await;
+            on_collected(parts).await;
+        }
+        .in_current_span(),
+    );
+}
+
+async fn wait_for_parts(key: AlbumKey) -> Vec<Message> {
+    let mut count = 1;
+    loop {
+        tokio::time::sleep(ALBUM_PART_WAIT).await;
+
+        let parts = {
+            let mut albums = PENDING_ALBUMS.lock();
+            let new_count = albums.get(&key).map_or(0, Vec::len);
+            if new_count == count {
+                albums.remove(&key)
+            } else {
+                count = new_count;
+                None
+            }
+        };
+
+        if let Some(mut parts) = parts {
+            parts.sort_by_key(|x| x.id.0);
+
+            debug!(?key, count = parts.len(), "Collected album");
+
+            return parts;
+        }
+    }
+}
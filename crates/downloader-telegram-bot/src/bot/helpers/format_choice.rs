@@ -0,0 +1,54 @@
+use app_actions::format_choice::FormatChoice;
+use once_cell::sync::Lazy;
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup, Message, UserId};
+
+use super::{
+    pending::{ClaimResult, PendingRequests},
+    status_message::StatusMessage,
+};
+
+/// Prefix of the callback data of the format choice buttons
+const CALLBACK_PREFIX: &str = "format:";
+
+static PENDING_CHOICES: Lazy<PendingRequests<PendingFormatChoice>> =
+    Lazy::new(PendingRequests::new);
+
+/// A download request that waits for the user to pick the format
+#[derive(Debug, Clone)]
+pub struct PendingFormatChoice {
+    pub message: Message,
+    pub status_message: StatusMessage,
+}
+
+/// Remembers the request and returns the keyboard to pick one of the `choices` with
+pub fn add_pending_choice(
+    message: Message,
+    status_message: StatusMessage,
+    choices: &[FormatChoice],
+) -> InlineKeyboardMarkup {
+    let id = PENDING_CHOICES.add(
+        message.from.as_ref().map(|x| x.id),
+        PendingFormatChoice {
+            message,
+            status_message,
+        },
+    );
+
+    let buttons = choices.iter().map(|choice| {
+        InlineKeyboardButton::callback(choice.label(), format!("{CALLBACK_PREFIX}{id}:{choice}"))
+    });
+
+    InlineKeyboardMarkup::new(buttons.map(|x| vec![x]))
+}
+
+/// Takes the pending request if `user` is the one that made it
+pub fn claim_pending_choice(id: &str, user: UserId) -> ClaimResult<PendingFormatChoice> {
+    PENDING_CHOICES.claim(id, user)
+}
+
+/// The ID of the pending request and the picked choice from the button's callback data
+pub fn parse_callback_data(data: &str) -> Option<(&str, FormatChoice)> {
+    let (id, choice) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+
+    Some((id, choice.parse().ok()?))
+}
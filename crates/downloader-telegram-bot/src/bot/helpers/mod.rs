@@ -1 +1,5 @@
+pub mod album;
+pub mod format_choice;
+pub mod pending;
 pub mod status_message;
+pub mod url_list;
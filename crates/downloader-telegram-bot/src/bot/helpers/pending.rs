@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use app_helpers::id::time_id;
+use parking_lot::Mutex;
+use teloxide::types::UserId;
+
+/// Requests that weren't answered in time are forgotten
+const PENDING_TTL: Duration = Duration::from_hours(1);
+
+/// Requests that wait for the user to press one of the buttons under the status message
+#[derive(Debug)]
+pub struct PendingRequests<T> {
+    requests: Mutex<HashMap<String, Pending<T>>>,
+}
+
+#[derive(Debug)]
+struct Pending<T> {
+    value: T,
+    requested_by: Option<UserId>,
+    added_at: Instant,
+}
+
+#[derive(Debug)]
+pub enum ClaimResult<T> {
+    Claimed(T),
+    /// Someone other than the requester pressed the button
+    NotRequester,
+    /// The request was already answered or it expired
+    Missing,
+}
+
+impl<T> PendingRequests<T> {
+    pub fn new() -> Self {
+        Self {
+            requests: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Remembers the request and returns the ID to put into the callback data
+    pub fn add(&self, requested_by: Option<UserId>, value: T) -> String {
+        let id = time_id();
+
+        let mut requests = self.requests.lock();
+        requests.retain(|_, x| x.added_at.elapsed() < PENDING_TTL);
+        requests.insert(
+            id.clone(),
+            Pending {
+                value,
+                requested_by,
+                added_at: Instant::now(),
+            },
+        );
+
+        id
+    }
+
+    /// Takes the pending request if `user` is the one that made it
+    pub fn claim(&self, id: &str, user: UserId) -> ClaimResult<T> {
+        let mut requests = self.requests.lock();
+
+        let Some(pending) = requests.get(id) else {
+            return ClaimResult::Missing;
+        };
+
+        if pending.requested_by.is_some_and(|x| x != user) {
+            return ClaimResult::NotRequester;
+        }
+
+        requests
+            .remove(id)
+            .map_or(ClaimResult::Missing, |x| ClaimResult::Claimed(x.value))
+    }
+}
+impl<T> Default for PendingRequests<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,7 +1,9 @@
 use teloxide::{
     payloads::{EditMessageTextSetters, SendMessageSetters},
     requests::Requester,
-    types::{ChatId, LinkPreviewOptions, Message, MessageId, ReplyParameters},
+    types::{
+        ChatId, InlineKeyboardMarkup, LinkPreviewOptions, Message, MessageId, ReplyParameters,
+    },
 };
 
 use crate::bot::TelegramBot;
@@ -87,6 +89,39 @@ impl StatusMessage {
         ))
     }
 
+    /// Same as [`Self::update_message`], but with buttons under the text.
+    /// Updating the message again removes them.
+    pub async fn update_message_with_keyboard(
+        &mut self,
+        text: &str,
+        keyboard: InlineKeyboardMarkup,
+    ) -> Result<(), teloxide::RequestError> {
+        if let Some(reply_id) = self.reply_msg_id {
+            let res = TelegramBot::instance()
+                .edit_message_text(self.chat_id, reply_id, text)
+                .reply_markup(keyboard.clone())
+                .await;
+
+            match res {
+                Err(teloxide::RequestError::Api(teloxide::ApiError::MessageToEditNotFound)) => {
+                    self.reply_msg_id = None;
+                }
+                res => return res.map(|_| ()),
+            }
+        }
+
+        let status_msg = TelegramBot::instance()
+            .send_message(self.chat_id, text)
+            .disable_notification(true)
+            .reply_parameters(ReplyParameters::new(self.msg_id).allow_sending_without_reply())
+            .reply_markup(keyboard)
+            .await?;
+
+        self.reply_msg_id = Some(status_msg.id);
+
+        Ok(())
+    }
+
     pub async fn delete_message(&self) -> Result<(), teloxide::RequestError> {
         if let Some(id) = self.reply_msg_id {
             TelegramBot::instance()
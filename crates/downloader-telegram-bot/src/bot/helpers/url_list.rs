@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use once_cell::sync::Lazy;
+use teloxide::types::{
+    Document, InlineKeyboardButton, InlineKeyboardMarkup, MediaKind, Message, MessageKind, UserId,
+};
+use url::Url;
+
+use super::{
+    pending::{ClaimResult, PendingRequests},
+    status_message::StatusMessage,
+};
+
+/// Prefix of the callback data of the confirmation buttons
+const CALLBACK_PREFIX: &str = "batch:";
+
+static PENDING_BATCHES: Lazy<PendingRequests<PendingBatch>> = Lazy::new(PendingRequests::new);
+
+/// The links of a list that wait for the user to confirm they should be downloaded
+#[derive(Debug, Clone)]
+pub struct PendingBatch {
+    pub message: Message,
+    pub urls: Vec<Url>,
+    pub status_message: StatusMessage,
+}
+
+/// The text file attached to the message or the one it replies to, if there is one.
+///
+/// Only used for `/batch`, other text files are downloaded like any other file.
+pub fn url_list_document(msg: &Message) -> Option<&Document> {
+    text_document(msg).or_else(|| msg.reply_to_message().and_then(text_document))
+}
+
+fn text_document(msg: &Message) -> Option<&Document> {
+    let MessageKind::Common(msg_data) = &msg.kind else {
+        return None;
+    };
+
+    let MediaKind::Document(x) = &msg_data.media_kind else {
+        return None;
+    };
+
+    let is_text_file = x
+        .document
+        .file_name
+        .as_deref()
+        .and_then(|x| Path::new(x).extension())
+        .is_some_and(|x| x.eq_ignore_ascii_case("txt"))
+        || x.document
+            .mime_type
+            .as_ref()
+            .is_some_and(|x| x.essence_str() == "text/plain");
+
+    is_text_file.then_some(&x.document)
+}
+
+/// One link per line. Empty lines, comments (`#`) and lines that aren't links are skipped.
+pub fn parse_url_list(text: &str) -> Vec<Url> {
+    let mut urls = vec![];
+
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Ok(url) = Url::parse(line) else {
+            continue;
+        };
+
+        if matches!(url.scheme(), "http" | "https") && !urls.contains(&url) {
+            urls.push(url);
+        }
+    }
+
+    urls
+}
+
+/// Remembers the links and returns the keyboard to confirm downloading them with
+pub fn add_pending_batch(
+    message: Message,
+    urls: Vec<Url>,
+    status_message: StatusMessage,
+) -> InlineKeyboardMarkup {
+    let id = PENDING_BATCHES.add(
+        message.from.as_ref().map(|x| x.id),
+        PendingBatch {
+            message,
+            urls,
+            status_message,
+        },
+    );
+
+    InlineKeyboardMarkup::new([[
+        InlineKeyboardButton::callback("Download all", format!("{CALLBACK_PREFIX}{id}:yes")),
+        InlineKeyboardButton::callback("Cancel", format!("{CALLBACK_PREFIX}{id}:no")),
+    ]])
+}
+
+/// Takes the pending links if `user` is the one that sent them
+pub fn claim_pending_batch(id: &str, user: UserId) -> ClaimResult<PendingBatch> {
+    PENDING_BATCHES.claim(id, user)
+}
+
+/// The ID of the pending links and whether downloading them was confirmed
+pub fn parse_callback_data(data: &str) -> Option<(&str, bool)> {
+    let (id, answer) = data.strip_prefix(CALLBACK_PREFIX)?.split_once(':')?;
+
+    match answer {
+        "yes" => Some((id, true)),
+        "no" => Some((id, false)),
+        _ => None,
+    }
+}
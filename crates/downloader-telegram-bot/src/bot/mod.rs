@@ -3,25 +3,32 @@ pub mod helpers;
 use std::{collections::HashMap, string::ToString};
 
 use app_actions::{
-    actions::{handlers::ActionEntry, ActionOptions, AVAILABLE_ACTIONS},
+    actions::{available_actions, handlers::ActionEntry, ActionOptions},
     downloaders::AVAILABLE_DOWNLOADERS,
     extractors::AVAILABLE_EXTRACTORS,
-    fixers::{handlers::FixerInstance, AVAILABLE_FIXERS},
+    fixers::{handlers::FixerInstance, AVAILABLE_FIXERS, ENABLED_FIXERS},
+    format_choice::FormatChoice,
 };
 use app_config::Config;
-use helpers::status_message::StatusMessage;
+use helpers::{
+    album,
+    format_choice::{self, claim_pending_choice},
+    pending::ClaimResult,
+    status_message::StatusMessage,
+    url_list::{self, claim_pending_batch, url_list_document},
+};
 use once_cell::sync::OnceCell;
 use teloxide::{
     adaptors::trace,
     prelude::*,
     requests::RequesterExt,
     types::{LinkPreviewOptions, ParseMode, ReplyParameters},
-    utils::command::BotCommands,
+    utils::command::{BotCommands, ParseError},
 };
-use tracing::{field, info, trace, Instrument, Span};
+use tracing::{field, info, trace, warn, Instrument, Span};
 use url::Url;
 
-use crate::queue::{Task, TaskQueue};
+use crate::queue::{TaskQueue, TaskRequest};
 
 pub type TeloxideBot =
     teloxide::adaptors::CacheMe<trace::Trace<teloxide::adaptors::DefaultParseMode<teloxide::Bot>>>;
@@ -72,7 +79,7 @@ enum BotCommand {
     #[command(description = "Responds with 'Pong!'")]
     Ping,
     #[command(
-        description = "Run the specified fixers on the message.",
+        description = "Run the specified fixers on the media you reply to (eg. something I sent before), or all of them with /fix all",
         parse_with = parse_fixers,
     )]
     Fix(Vec<FixerInstance>),
@@ -81,6 +88,43 @@ enum BotCommand {
         parse_with = parse_action,
     )]
     Act(ActionEntry, ActionOptions),
+    #[command(
+        description = "Download the linked media, picking the quality first, or eg. /download 1080p",
+        parse_with = parse_download,
+    )]
+    Download(Option<FormatChoice>),
+    #[command(
+        description = "Download subtitles for the linked video, eg. /subs de",
+        parse_with = parse_subs,
+    )]
+    Subs(String),
+    #[command(
+        description = "Show what was found for the link without downloading it, eg. /info <url>"
+    )]
+    // The link is read from the message, the argument only has to be accepted
+    #[allow(dead_code)]
+    Info(String),
+    #[command(description = "Only send a screenshot of the linked post, eg. /screenshot <url>")]
+    // The link is read from the message, the argument only has to be accepted
+    #[allow(dead_code)]
+    Screenshot(String),
+    #[command(
+        description = "Archive the whole post (media, screenshot, text and metadata) as a zip, eg. /archive <url>"
+    )]
+    // The link is read from the message, the argument only has to be accepted
+    #[allow(dead_code)]
+    Archive(String),
+    #[command(
+        description = "Download every link of a .txt file (one per line), sent with the command or replied to"
+    )]
+    // The file is read from the message, the argument only has to be accepted
+    #[allow(dead_code)]
+    Batch(String),
+    #[command(
+        description = "Upload the linked media to cloud storage instead, eg. /deliver drive",
+        parse_with = parse_deliver,
+    )]
+    Deliver(String),
 }
 
 struct CmdActParams(ActionEntry, ActionOptions);
@@ -90,8 +134,8 @@ fn parse_action(s: String) -> Result<CmdActParams, teloxide::utils::command::Par
     let (name, opts) = s.split_once(' ').unwrap_or((s.as_str(), ""));
     let name = name.trim();
 
-    AVAILABLE_ACTIONS
-        .iter()
+    available_actions()
+        .into_iter()
         .find(|x| x.name() == name)
         .map(|x| {
             let opts = opts
@@ -102,7 +146,7 @@ fn parse_action(s: String) -> Result<CmdActParams, teloxide::utils::command::Par
 
             trace!(?opts, "Parsed action options");
 
-            CmdActParams(x.clone(), opts)
+            CmdActParams(x, opts)
         })
         .ok_or_else(|| {
             teloxide::utils::command::ParseError::IncorrectFormat(
@@ -112,23 +156,85 @@ fn parse_action(s: String) -> Result<CmdActParams, teloxide::utils::command::Par
         })
 }
 
-struct CmdFixParams(Vec<FixerInstance>);
+struct CmdDownloadParams(Option<FormatChoice>);
+#[allow(clippy::needless_pass_by_value)]
+fn parse_download(s: String) -> Result<CmdDownloadParams, teloxide::utils::command::ParseError> {
+    let Some(format) = s
+        .split_whitespace()
+        .next()
+        .filter(|x| Url::parse(x).is_err())
+    else {
+        return Ok(CmdDownloadParams(None));
+    };
+
+    format
+        .parse::<FormatChoice>()
+        .map(|x| CmdDownloadParams(Some(x)))
+        .map_err(|_| {
+            teloxide::utils::command::ParseError::IncorrectFormat(
+                anyhow::anyhow!("Unknown format. Use eg. best, 1080p, 720p or audio-only.").into(),
+            )
+        })
+}
+
+struct CmdSubsParams(String);
+#[allow(clippy::unnecessary_wraps)]
+#[allow(clippy::needless_pass_by_value)]
+fn parse_subs(s: String) -> Result<CmdSubsParams, teloxide::utils::command::ParseError> {
+    let lang = s
+        .split_whitespace()
+        .next()
+        .filter(|x| Url::parse(x).is_err())
+        .unwrap_or("en");
+
+    Ok(CmdSubsParams(lang.to_string()))
+}
+
+struct CmdDeliverParams(String);
 #[allow(clippy::unnecessary_wraps)]
 #[allow(clippy::needless_pass_by_value)]
+fn parse_deliver(s: String) -> Result<CmdDeliverParams, teloxide::utils::command::ParseError> {
+    let target = s
+        .split_whitespace()
+        .next()
+        .filter(|x| Url::parse(x).is_err())
+        .unwrap_or_default();
+
+    Ok(CmdDeliverParams(target.to_lowercase()))
+}
+
+struct CmdFixParams(Vec<FixerInstance>);
+#[allow(clippy::needless_pass_by_value)]
 fn parse_fixers(s: String) -> Result<CmdFixParams, teloxide::utils::command::ParseError> {
     let name_to_instance = AVAILABLE_FIXERS
         .iter()
         .map(|x| (x.name(), x.clone()))
         .collect::<HashMap<_, _>>();
 
-    let res = s
+    let mut res = vec![];
+    for (name, _params) in s
         .split(' ')
         .map(str::trim)
         .filter(|x| !x.is_empty())
         .map(|x| x.split_once('=').unwrap_or((x, "")))
-        .filter_map(|(name, _params)| name_to_instance.get(name))
-        .cloned()
-        .collect();
+    {
+        // Eg. to apply a newly added fixer to something that was sent before
+        if name.eq_ignore_ascii_case("all") {
+            res.extend(ENABLED_FIXERS.iter().cloned());
+            continue;
+        }
+
+        let Some(fixer) = name_to_instance.get(name) else {
+            return Err(teloxide::utils::command::ParseError::IncorrectFormat(
+                anyhow::anyhow!(
+                    "Unknown fixer {name:?}. Use /list_fixers to see the available fixers."
+                )
+                .into(),
+            ));
+        };
+
+        res.push(fixer.clone());
+    }
 
     Ok(CmdFixParams(res))
 }
@@ -147,9 +253,14 @@ pub async fn run() -> anyhow::Result<()> {
     info!(api_url = ?TelegramBot::pure_instance().api_url().as_str(), id = ?me.id, user = ?me.username(), name = ?me.full_name(), "Bot started");
 
     Box::pin(
-        Dispatcher::builder(bot, Update::filter_message().endpoint(answer))
-            .build()
-            .dispatch(),
+        Dispatcher::builder(
+            bot,
+            dptree::entry()
+                .branch(Update::filter_message().endpoint(answer))
+                .branch(Update::filter_callback_query().endpoint(answer_callback)),
+        )
+        .build()
+        .dispatch(),
     )
     .await;
 
@@ -196,7 +307,20 @@ async fn answer(_bot: &TeloxideBot, msg: Message) -> ResponseResult<()> {
 
             match BotCommand::parse(&msg_text, bot_me.username()) {
                 Ok(c) => handle_command(msg, c).await,
-                Err(_) => handle_message(msg).await,
+                Err(ParseError::IncorrectFormat(e)) => {
+                    TelegramBot::instance()
+                        .send_message(msg.chat.id, teloxide::utils::html::escape(&e.to_string()))
+                        .reply_parameters(
+                            ReplyParameters::new(msg.id).allow_sending_without_reply(),
+                        )
+                        .await?;
+
+                    Ok(())
+                }
+                Err(_) => {
+                    handle_message(msg);
+                    Ok(())
+                }
             }
         }
         .instrument(Span::current()),
@@ -205,6 +329,98 @@ async fn answer(_bot: &TeloxideBot, msg: Message) -> ResponseResult<()> {
     Ok(())
 }
 
+#[tracing::instrument(name = "callback", skip(bot, query), fields(from = %query.from.id))]
+async fn answer_callback(bot: &TeloxideBot, query: CallbackQuery) -> ResponseResult<()> {
+    trace!(?query, "Got callback query");
+
+    let data = query.data.as_deref().unwrap_or_default();
+
+    if let Some((id, choice)) = format_choice::parse_callback_data(data) {
+        let claim = claim_pending_choice(id, query.from.id);
+        let Some(pending) = answer_claim(bot, &query, claim).await? else {
+            return Ok(());
+        };
+
+        info!(
+            ?choice,
+            "Adding download request with chosen format to queue"
+        );
+
+        let mut status_message = pending.status_message;
+
+        status_message
+            .update_message(&format!(
+                "Picked {choice}. Waiting for spot in line...",
+                choice = choice.label()
+            ))
+            .await?;
+
+        TaskQueue::push(TaskRequest::chosen_download_request(
+            pending.message,
+            choice,
+            status_message,
+        ));
+    } else if let Some((id, confirmed)) = url_list::parse_callback_data(data) {
+        let claim = claim_pending_batch(id, query.from.id);
+        let Some(pending) = answer_claim(bot, &query, claim).await? else {
+            return Ok(());
+        };
+
+        let mut status_message = pending.status_message;
+
+        if !confirmed {
+            status_message
+                .update_message("Not downloading the links from the list")
+                .await?;
+            return Ok(());
+        }
+
+        info!(
+            count = pending.urls.len(),
+            "Adding batch download request to queue"
+        );
+
+        status_message
+            .update_message("Links queued. Waiting for spot in line...")
+            .await?;
+
+        TaskQueue::push(TaskRequest::batch_download_request(
+            pending.message,
+            pending.urls,
+            status_message,
+        ));
+    } else {
+        bot.answer_callback_query(query.id.clone()).await?;
+    }
+
+    Ok(())
+}
+
+/// Answers the button press and returns the pending request if it's there
+/// and the user that pressed the button is the one that made it
+async fn answer_claim<T>(
+    bot: &TeloxideBot,
+    query: &CallbackQuery,
+    claim: ClaimResult<T>,
+) -> ResponseResult<Option<T>> {
+    let (pending, text) = match claim {
+        ClaimResult::Claimed(x) => (Some(x), None),
+        ClaimResult::NotRequester => (
+            None,
+            Some("Only the one who sent the request can answer this"),
+        ),
+        ClaimResult::Missing => (None, Some("This has expired. Send the request again.")),
+    };
+
+    let mut answer = bot.answer_callback_query(query.id.clone());
+    if let Some(text) = text {
+        answer = answer.text(text);
+    }
+    answer.await?;
+
+    Ok(pending)
+}
+
 #[allow(clippy::too_many_lines)]
 async fn handle_command(msg: Message, command: BotCommand) -> ResponseResult<()> {
     info!(?command, "Handling command");
@@ -345,7 +561,7 @@ async fn handle_command(msg: Message, command: BotCommand) -> ResponseResult<()>
                 .await?;
         }
         BotCommand::ListActions => {
-            let actions_text = AVAILABLE_ACTIONS
+            let actions_text = available_actions()
                 .iter()
                 .map(|x| {
                     format!(
@@ -384,7 +600,7 @@ async fn handle_command(msg: Message, command: BotCommand) -> ResponseResult<()>
                 .update_message("Message queued. Waiting for spot in line...")
                 .await?;
 
-            TaskQueue::push(Task::fix_request(msg, fixers, status_message));
+            TaskQueue::push(TaskRequest::fix_request(msg, fixers, status_message));
         }
         BotCommand::Act(action, options) => {
             info!(?action, ?options, "Adding action request to queue");
@@ -395,15 +611,169 @@ async fn handle_command(msg: Message, command: BotCommand) -> ResponseResult<()>
                 .update_message("Message queued. Waiting for spot in line...")
                 .await?;
 
-            TaskQueue::push(Task::action_request(msg, action, options, status_message));
+            TaskQueue::push(TaskRequest::action_request(
+                msg,
+                action,
+                options,
+                status_message,
+            ));
+        }
+        BotCommand::Download(format_choice) => {
+            info!(
+                ?format_choice,
+                "Adding interactive download request to queue"
+            );
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(match format_choice {
+                Some(format_choice) => {
+                    TaskRequest::chosen_download_request(msg, format_choice, status_message)
+                }
+                None => TaskRequest::interactive_download_request(msg, status_message),
+            });
+        }
+        BotCommand::Subs(lang) => {
+            info!(?lang, "Adding subtitles request to queue");
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(TaskRequest::subtitles_request(msg, lang, status_message));
+        }
+        BotCommand::Info(_) => {
+            info!("Adding info request to queue");
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(TaskRequest::info_request(msg, status_message));
+        }
+        BotCommand::Screenshot(_) => {
+            info!("Adding screenshot request to queue");
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(TaskRequest::chosen_download_request(
+                msg,
+                FormatChoice::ScreenshotOnly,
+                status_message,
+            ));
+        }
+        BotCommand::Archive(_) => {
+            info!("Adding archive request to queue");
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(TaskRequest::archive_request(msg, status_message));
+        }
+        BotCommand::Batch(_) => {
+            if url_list_document(&msg).is_none() {
+                TelegramBot::instance()
+                    .send_message(
+                        msg.chat.id,
+                        "Send a .txt file with one link per line with this command, or reply to \
+                         one with it.",
+                    )
+                    .reply_parameters(ReplyParameters::new(msg.id).allow_sending_without_reply())
+                    .await?;
+
+                return Ok(());
+            }
+
+            info!("Adding URL list request to queue");
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(TaskRequest::url_list_request(msg, status_message));
+        }
+        BotCommand::Deliver(target_name) => {
+            let targets = msg
+                .from
+                .as_ref()
+                .map(|user| {
+                    Config::global()
+                        .telegram_bot()
+                        .delivery_targets_for(user.id.0)
+                        .cloned()
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default();
+
+            let Some(target) = targets.iter().find(|x| x.name == target_name).cloned() else {
+                let text = if targets.is_empty() {
+                    "There's nowhere set up for you to deliver to.".to_string()
+                } else {
+                    format!(
+                        "Pick where to deliver to, eg. /deliver {example}\n\nAvailable: {names}",
+                        example = targets[0].name,
+                        names = targets
+                            .iter()
+                            .map(|x| x.name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", "),
+                    )
+                };
+
+                TelegramBot::instance()
+                    .send_message(msg.chat.id, text)
+                    .reply_parameters(ReplyParameters::new(msg.id).allow_sending_without_reply())
+                    .await?;
+
+                return Ok(());
+            };
+
+            info!(target = %target.name, "Adding deliver request to queue");
+
+            let mut status_message = StatusMessage::from_message(&msg);
+
+            status_message
+                .update_message("Message queued. Waiting for spot in line...")
+                .await?;
+
+            TaskQueue::push(TaskRequest::deliver_request(msg, target, status_message));
         }
     }
 
     Ok(())
 }
 
-async fn handle_message(msg: Message) -> ResponseResult<()> {
-    info!("Adding download request to queue");
+fn handle_message(msg: Message) {
+    // The parts of an album are queued together once they've all come in
+    album::collect_album(msg, |parts| async move {
+        if let Err(e) = queue_post(parts).await {
+            warn!(?e, "Failed to queue post");
+        }
+    });
+}
+
+async fn queue_post(mut parts: Vec<Message>) -> ResponseResult<()> {
+    if parts.is_empty() {
+        return Ok(());
+    }
+    let msg = parts.remove(0);
 
     let mut status_message = StatusMessage::from_message(&msg);
 
@@ -411,7 +781,13 @@ async fn handle_message(msg: Message) -> ResponseResult<()> {
         .update_message("Message queued. Waiting for spot in line...")
         .await?;
 
-    TaskQueue::push(Task::download_request(msg, status_message));
+    info!(parts = parts.len() + 1, "Adding download request to queue");
+
+    TaskQueue::push(TaskRequest::post_download_request(
+        msg,
+        parts,
+        status_message,
+    ));
 
     Ok(())
 }
@@ -432,6 +808,8 @@ fn parse_option_string(s: &str) -> Option<(String, serde_json::Value)> {
         match v.as_str() {
             "" | "true" | "TRUE" => (key, true.into()),
             "false" | "FALSE" => (key, false.into()),
+            // Whole numbers stay integers so they can be used for counts
+            _ if v.parse::<i64>().is_ok() => (key, v.parse::<i64>().unwrap_or_default().into()),
             _ if v.parse::<f64>().is_ok() => (key, v.parse::<f64>().unwrap_or_default().into()),
             _ => (key, v.into()),
         }
@@ -1,10 +1,19 @@
 pub(crate) mod bot;
 pub(crate) mod queue;
 
+use std::time::Duration;
+
+use app_actions::{
+    actions::init_available_actions,
+    startup::{self, Capabilities},
+};
 use app_config::Config;
 use app_tasks::TaskRunner;
 use queue::TaskQueueProcessor;
-use tracing::{debug, error};
+use tracing::{debug, error, info};
+
+/// How often the tools the actions depend on are checked for again
+const AVAILABLE_ACTIONS_REFRESH_INTERVAL: Duration = Duration::from_mins(15);
 
 #[tokio::main]
 async fn main() {
@@ -27,6 +36,16 @@ async fn main() {
 
     debug!(config = ?*Config::global(), "Running with config");
 
+    // Done before the bot starts so the first command doesn't have to wait for the checks
+    init_available_actions(AVAILABLE_ACTIONS_REFRESH_INTERVAL).await;
+
+    let capabilities = Capabilities::detect("sent to the chat", "in memory").await;
+    info!("Starting with capabilities:\n{capabilities}");
+
+    startup::self_test_if_enabled()
+        .await
+        .expect("Self-test failed");
+
     tokio::task::spawn(TaskQueueProcessor::run());
     tokio::task::spawn(TaskRunner::run());
 
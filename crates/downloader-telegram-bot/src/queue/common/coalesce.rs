@@ -0,0 +1,139 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use app_actions::{download_archive::archive_key, format_choice::FormatChoice};
+use app_config::Config;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use teloxide::types::Message;
+use tracing::{debug, trace};
+
+use super::{
+    file::{FileId, SentMedia},
+    urls::urls_in_message,
+};
+
+/// How long the files uploaded for some links are sent again
+/// to anyone else asking for the same links
+const REUSE_WINDOW: Duration = Duration::from_mins(10);
+
+/// The tasks are handled one at a time, so by the time a request for the same links
+/// gets picked up, the first one is already done and its uploads can be sent instead
+static RECENT_UPLOADS: Lazy<Mutex<HashMap<CoalesceKey, RecentUpload>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone)]
+pub struct RecentUpload {
+    uploaded_at: Instant,
+    pub media_groups: Arc<Vec<Vec<SentMedia>>>,
+    /// SHA-256 of the files, `None` if they weren't hashed
+    pub sha256s: Option<Arc<Vec<String>>>,
+}
+
+/// Requests with the same key end up with the same files
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CoalesceKey {
+    /// Normalized, sorted and deduplicated
+    urls: Vec<String>,
+    format_choice: Option<FormatChoice>,
+    /// The owner gets some things others don't, eg. age-restricted content
+    from_owner: bool,
+    /// The only content types the chat allows, sorted. Empty if it doesn't have its own.
+    allowed_content_types: Vec<String>,
+}
+impl CoalesceKey {
+    /// The key for a message that only has links in it.
+    ///
+    /// Messages with files aren't coalesced since the files are different every time.
+    pub fn for_message(
+        msg: &Message,
+        format_choice: Option<FormatChoice>,
+        from_owner: bool,
+    ) -> Option<Self> {
+        if FileId::from_message(msg).is_some() {
+            return None;
+        }
+
+        // Same links shared from different places still count as the same
+        let mut urls = urls_in_message(msg)
+            .iter()
+            .map(archive_key)
+            .collect::<Vec<_>>();
+        if urls.is_empty() {
+            return None;
+        }
+        urls.sort();
+        urls.dedup();
+
+        // A chat that only allows images shouldn't get the videos uploaded to another one
+        let mut allowed_content_types = Config::global()
+            .telegram_bot()
+            .content_types_for(msg.chat.id.0)
+            .unwrap_or_default()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+        allowed_content_types.sort();
+        allowed_content_types.dedup();
+
+        Some(Self {
+            urls,
+            format_choice,
+            from_owner,
+            allowed_content_types,
+        })
+    }
+
+    /// The key as a string, eg. for persisting it
+    pub fn store_key(&self) -> String {
+        format!(
+            "{urls}|{format}|{owner}|{content_types}",
+            urls = self.urls.join(" "),
+            format = self
+                .format_choice
+                .map(|x| x.to_string())
+                .unwrap_or_default(),
+            owner = if self.from_owner { "owner" } else { "" },
+            content_types = self.allowed_content_types.join(","),
+        )
+    }
+}
+
+/// What was recently uploaded for the same links, if there is anything
+pub fn recent_upload(key: &CoalesceKey) -> Option<RecentUpload> {
+    let upload = {
+        let mut uploads = RECENT_UPLOADS.lock();
+
+        uploads.retain(|_, x| x.uploaded_at.elapsed() < REUSE_WINDOW);
+
+        uploads.get(key).cloned()
+    };
+    trace!(?key, found = upload.is_some(), "Looked for recent upload");
+
+    upload
+}
+
+/// Remembers the uploaded media groups so they can be sent to others asking for the same links
+pub fn add_recent_upload(
+    key: CoalesceKey,
+    media_groups: Vec<Vec<SentMedia>>,
+    sha256s: Option<Vec<String>>,
+) {
+    if media_groups.iter().all(Vec::is_empty) {
+        return;
+    }
+
+    debug!(?key, "Remembering upload for coalescing");
+
+    RECENT_UPLOADS.lock().insert(
+        key,
+        RecentUpload {
+            uploaded_at: Instant::now(),
+            media_groups: Arc::new(media_groups),
+            sha256s: sha256s.map(Arc::new),
+        },
+    );
+}
@@ -5,13 +5,18 @@ use std::{
     sync::Arc,
 };
 
-use app_actions::fixers::{handlers::file_extensions::FileExtension, FixRequest, Fixer};
+use app_actions::{
+    blocklist::enforce_blocklist,
+    content_policy::ContentType,
+    fixers::{handlers::file_extensions::FileExtension, FixRequest, Fixer},
+};
 use app_helpers::{
     file_type::{infer_file_type, mime},
     id::time_thread_id,
 };
 use futures::{stream::FuturesUnordered, StreamExt};
 use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
 use teloxide::{
     net::Download,
     prelude::*,
@@ -32,6 +37,9 @@ pub const MAX_PAYLOAD_SIZE_BYTES: u64 = {
     50 * mb
 };
 
+/// The largest file that is sent, with some room left for the rest of the upload
+pub const MAX_FILE_SIZE_BYTES: u64 = MAX_PAYLOAD_SIZE_BYTES / 10 * 8;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct FileId(String);
 
@@ -82,6 +90,17 @@ impl FileId {
 
         trace!("Finished syncing file");
 
+        // Uploads are checked the same as downloads, the file is deleted if it's blocked
+        let source = format!("telegram:{}", f.meta.unique_id);
+        match enforce_blocklist(&download_file_path, &source, None).await {
+            Ok(None) => {}
+            Ok(Some(blocked)) => return Err(blocked.to_string()),
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&download_file_path).await;
+                return Err(format!("Failed to check file against blocklist: {e}"));
+            }
+        }
+
         trace!("Setting proper file extension");
 
         let final_file_path = FileExtension
@@ -94,6 +113,32 @@ impl FileId {
 
         Ok(final_file_path)
     }
+
+    /// Gets the contents of the file without saving it anywhere
+    #[tracing::instrument]
+    pub async fn download_to_memory(&self, max_size_bytes: u64) -> Result<Vec<u8>, String> {
+        let f = TelegramBot::instance()
+            .get_file(self.0.as_str())
+            .await
+            .map_err(|e| format!("Error while getting file: {e:?}"))?;
+
+        trace!("Got file: {:?}", f);
+
+        if u64::from(f.meta.size) > max_size_bytes {
+            return Err(format!(
+                "File is too large, it can be at most {max_size_bytes} bytes"
+            ));
+        }
+
+        let mut contents = vec![];
+
+        TelegramBot::pure_instance()
+            .download_file(&f.path, &mut contents)
+            .await
+            .map_err(|e| format!("Error while downloading file: {e:?}"))?;
+
+        Ok(contents)
+    }
 }
 
 impl std::fmt::Display for FileId {
@@ -165,11 +210,113 @@ pub fn file_id_from_message(msg: &Message) -> Option<FileId> {
     .map(FileId)
 }
 
+/// A file Telegram already has, so it can be sent again by its ID
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SentMedia {
+    pub kind: SentMediaKind,
+    pub file_id: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SentMediaKind {
+    Photo,
+    Video,
+    Audio,
+    Document,
+}
+
+impl SentMediaKind {
+    /// What kind of content was sent, `None` for documents since they can be anything
+    pub const fn content_type(self) -> Option<ContentType> {
+        match self {
+            Self::Photo => Some(ContentType::Image),
+            Self::Video => Some(ContentType::Video),
+            Self::Audio => Some(ContentType::Audio),
+            Self::Document => None,
+        }
+    }
+}
+
+impl SentMedia {
+    pub fn from_message(msg: &Message) -> Option<Self> {
+        let MessageKind::Common(msg_data) = &msg.kind else {
+            return None;
+        };
+
+        let (kind, file_id) = match &msg_data.media_kind {
+            MediaKind::Photo(x) => (
+                SentMediaKind::Photo,
+                &x.photo
+                    .iter()
+                    .max_by_key(|x| u64::from(x.width) * u64::from(x.height))?
+                    .file
+                    .id,
+            ),
+            MediaKind::Video(x) => (SentMediaKind::Video, &x.video.file.id),
+            MediaKind::Audio(x) => (SentMediaKind::Audio, &x.audio.file.id),
+            MediaKind::Document(x) => (SentMediaKind::Document, &x.document.file.id),
+            _ => return None,
+        };
+
+        Some(Self {
+            kind,
+            file_id: file_id.clone(),
+        })
+    }
+
+    pub fn to_input_media(&self) -> InputMedia {
+        let input_file = InputFile::file_id(self.file_id.clone());
+
+        match self.kind {
+            SentMediaKind::Photo => InputMedia::Photo(InputMediaPhoto::new(input_file)),
+            SentMediaKind::Video => InputMedia::Video(InputMediaVideo::new(input_file)),
+            SentMediaKind::Audio => InputMedia::Audio(InputMediaAudio::new(input_file)),
+            SentMediaKind::Document => InputMedia::Document(InputMediaDocument::new(input_file)),
+        }
+    }
+}
+
+/// A file that was sent as a reply
+#[derive(Debug, Clone)]
+pub struct UploadedFile {
+    pub media: SentMedia,
+    /// `None` if the file ID cache isn't used
+    pub sha256: Option<String>,
+}
+
+/// Groups the already sent media the same way [`files_to_input_media_groups`] groups files
+pub fn group_sent_media(media: Vec<SentMedia>) -> Vec<Vec<SentMedia>> {
+    let mut groups: Vec<(SentMediaKind, Vec<SentMedia>)> = vec![];
+
+    for x in media {
+        // Audio and documents can't be mixed with anything else in a group
+        let group_kind = match x.kind {
+            SentMediaKind::Photo | SentMediaKind::Video => SentMediaKind::Video,
+            kind => kind,
+        };
+
+        match groups
+            .iter_mut()
+            .find(|(kind, group)| *kind == group_kind && group.len() < 10)
+        {
+            Some((_, group)) => group.push(x),
+            None => groups.push((group_kind, vec![x])),
+        }
+    }
+
+    groups.into_iter().map(|(_, group)| group).collect()
+}
+
+/// The files in the media groups they're sent in
+pub type InputMediaGroups = Vec<Vec<(PathBuf, InputMedia)>>;
+
 #[tracing::instrument(skip_all)]
 pub async fn files_to_input_media_groups<TFiles, TFile>(
     files: TFiles,
     max_size: u64,
-) -> (Vec<Vec<InputMedia>>, Vec<(PathBuf, String)>)
+) -> (InputMediaGroups, Vec<(PathBuf, String)>)
 where
     TFiles: IntoIterator<Item = TFile> + Send + std::fmt::Debug,
     TFile: AsRef<Path> + Into<PathBuf> + Clone,
@@ -347,7 +494,7 @@ where
         res.extend(
             chunks
                 .into_iter()
-                .map(|x| x.into_iter().map(|x| x.media).collect()),
+                .map(|x| x.into_iter().map(|x| (x.file_info.path, x.media)).collect()),
         );
     }
     trace!(?res, "Got file groupings");
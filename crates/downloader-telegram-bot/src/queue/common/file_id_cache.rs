@@ -0,0 +1,273 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use app_actions::blocklist::sha256_file;
+use app_config::Config;
+use app_helpers::id::time_thread_id;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tokio::fs;
+use tracing::{debug, trace, warn};
+
+use super::{coalesce::CoalesceKey, file::SentMedia};
+
+static STORE: Lazy<Mutex<FileIdStore>> = Lazy::new(|| Mutex::new(load_store()));
+
+/// Keeps the saves from different tasks from overwriting each other out of order
+static SAVE_LOCK: Lazy<tokio::sync::Mutex<()>> = Lazy::new(|| tokio::sync::Mutex::new(()));
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileIdStore {
+    /// SHA-256 of the file contents to the file Telegram has
+    #[serde(default)]
+    files: HashMap<String, CachedFile>,
+    /// Request key to the files it resulted in
+    #[serde(default)]
+    requests: HashMap<String, CachedRequest>,
+}
+impl FileIdStore {
+    /// Drops everything that is too old to be sent again
+    fn prune(&mut self) {
+        self.files.retain(|_, x| is_fresh(x.remembered_at));
+        self.requests.retain(|_, x| is_fresh(x.remembered_at));
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedFile {
+    #[serde(flatten)]
+    media: SentMedia,
+    /// Unix timestamp in seconds
+    remembered_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedRequest {
+    /// SHA-256 of the files, in the order they were sent
+    sha256s: Vec<String>,
+    /// Unix timestamp in seconds
+    remembered_at: u64,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or_default()
+}
+
+/// Links can change what they point to and files can get added to the blocklist,
+/// so nothing is sent from the cache forever
+fn is_fresh(remembered_at: u64) -> bool {
+    now_secs().saturating_sub(remembered_at)
+        < Config::global()
+            .telegram_bot()
+            .file_id_cache_ttl()
+            .as_secs()
+}
+
+fn store_path() -> Option<&'static PathBuf> {
+    Config::global().telegram_bot().file_id_cache.as_ref()
+}
+
+/// Hash of the file to look it up by, `None` if neither the cache nor the blocklist is used
+pub async fn file_sha256(path: &Path) -> Option<String> {
+    if store_path().is_none() && Config::global().blocklist.blocklist_file.is_none() {
+        return None;
+    }
+
+    let path = path.to_path_buf();
+    let res = tokio::task::spawn_blocking(move || sha256_file(&path))
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|x| x.map_err(|e| e.to_string()));
+
+    match res {
+        Ok(x) => Some(x),
+        Err(e) => {
+            warn!(?e, "Failed to hash file for the file ID cache");
+            None
+        }
+    }
+}
+
+/// The file Telegram already has with the same contents
+pub fn cached_file(sha256: &str) -> Option<SentMedia> {
+    store_path()?;
+
+    STORE
+        .lock()
+        .files
+        .get(sha256)
+        .filter(|x| is_fresh(x.remembered_at))
+        .map(|x| x.media.clone())
+}
+
+/// The files a request with the same key resulted in along with their SHA-256,
+/// if Telegram still has all of them
+pub fn cached_request(key: &CoalesceKey) -> Option<Vec<(String, SentMedia)>> {
+    store_path()?;
+
+    let store = STORE.lock();
+    let request = store
+        .requests
+        .get(&key.store_key())
+        .filter(|x| is_fresh(x.remembered_at))?;
+
+    let media = request
+        .sha256s
+        .iter()
+        .map(|x| {
+            store
+                .files
+                .get(x)
+                .filter(|x| is_fresh(x.remembered_at))
+                .map(|file| (x.clone(), file.media.clone()))
+        })
+        .collect::<Option<Vec<_>>>();
+    drop(store);
+
+    trace!(?key, found = media.is_some(), "Looked for cached request");
+
+    media.filter(|x| !x.is_empty())
+}
+
+/// Remembers the files Telegram now has
+pub async fn remember_files<I>(files: I)
+where
+    I: IntoIterator<Item = (String, SentMedia)> + Send,
+{
+    if store_path().is_none() {
+        return;
+    }
+
+    let remembered_at = now_secs();
+    STORE
+        .lock()
+        .files
+        .extend(files.into_iter().map(|(sha256, media)| {
+            (
+                sha256,
+                CachedFile {
+                    media,
+                    remembered_at,
+                },
+            )
+        }));
+
+    save().await;
+}
+
+/// Remembers which files the request resulted in,
+/// so they can be sent without downloading anything the next time
+pub async fn remember_request(key: &CoalesceKey, hashes: Vec<String>) {
+    if store_path().is_none() || hashes.is_empty() {
+        return;
+    }
+
+    debug!(?key, ?hashes, "Remembering request results");
+
+    STORE.lock().requests.insert(
+        key.store_key(),
+        CachedRequest {
+            sha256s: hashes,
+            remembered_at: now_secs(),
+        },
+    );
+
+    save().await;
+}
+
+/// Forgets the request, eg. when Telegram doesn't accept its files anymore
+pub async fn forget_request(key: &CoalesceKey) {
+    if store_path().is_none() {
+        return;
+    }
+
+    let request = STORE.lock().requests.remove(&key.store_key());
+
+    if let Some(request) = request {
+        {
+            let mut store = STORE.lock();
+            for hash in request.sha256s {
+                store.files.remove(&hash);
+            }
+        }
+
+        save().await;
+    }
+}
+
+fn load_store() -> FileIdStore {
+    let Some(path) = store_path() else {
+        return FileIdStore::default();
+    };
+
+    let contents = match std::fs::read(path) {
+        Ok(x) => x,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return FileIdStore::default(),
+        Err(e) => {
+            warn!(?e, ?path, "Failed to read file ID cache");
+            return FileIdStore::default();
+        }
+    };
+
+    match serde_json::from_slice::<FileIdStore>(&contents) {
+        Ok(mut store) => {
+            store.prune();
+
+            debug!(
+                files = store.files.len(),
+                requests = store.requests.len(),
+                "Loaded file ID cache"
+            );
+
+            store
+        }
+        Err(e) => {
+            warn!(?e, ?path, "Invalid file ID cache, starting over");
+            FileIdStore::default()
+        }
+    }
+}
+
+/// Written next to the file and moved into place so a crash never leaves half of it behind
+async fn save() {
+    let Some(path) = store_path() else {
+        return;
+    };
+
+    let _lock = SAVE_LOCK.lock().await;
+
+    let contents = {
+        let mut store = STORE.lock();
+        store.prune();
+        serde_json::to_vec(&*store)
+    };
+    let contents = match contents {
+        Ok(x) => x,
+        Err(e) => {
+            warn!(?e, "Failed to serialize file ID cache");
+            return;
+        }
+    };
+
+    let temp = path.with_extension(format!("{}.tmp", time_thread_id()));
+
+    let res = match fs::write(&temp, contents).await {
+        Ok(()) => fs::rename(&temp, path).await,
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = res {
+        warn!(?e, ?path, "Failed to save file ID cache");
+        let _ = fs::remove_file(&temp).await;
+    }
+}
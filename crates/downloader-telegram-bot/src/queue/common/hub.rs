@@ -0,0 +1,328 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use app_actions::{downloaders::ProgressEstimate, format_choice::FormatChoice};
+use app_config::Config;
+use app_helpers::id::time_thread_id;
+use futures::StreamExt;
+use reqwest::{header, RequestBuilder};
+use serde::{de::DeserializeOwned, Deserialize};
+use tokio::{fs::File, io::AsyncWriteExt};
+use tracing::{debug, trace};
+use url::Url;
+
+/// How often the hub is asked whether it's done fixing the files
+const RESULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Requests the hub isn't done with by then are given up on
+const MAX_RESULT_WAIT: Duration = Duration::from_mins(30);
+
+/// Talks to the downloader-hub instance the downloads are handed off to
+#[derive(Debug, Clone)]
+pub struct HubClient {
+    base_url: Url,
+    client_key: String,
+    client: reqwest::Client,
+}
+
+impl HubClient {
+    /// The client for the configured hub, if there is one
+    pub fn from_config() -> Option<Self> {
+        let (url, client_key) = Config::global().telegram_bot().hub()?;
+
+        let mut base_url = Url::parse(url).ok()?;
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Some(Self {
+            base_url,
+            client_key: client_key.to_string(),
+            client: reqwest::Client::new(),
+        })
+    }
+
+    /// Asks the hub to download the URL and returns the ID of the request
+    #[tracing::instrument(skip(self))]
+    pub async fn create_request(
+        &self,
+        url: &Url,
+        format_choice: Option<FormatChoice>,
+    ) -> Result<String, String> {
+        let mut payload = serde_json::json!({ "url": url.as_str() });
+        if let Some(choice) = format_choice {
+            payload["format"] = choice.into();
+        }
+
+        let requests = self
+            .send::<Vec<HubRequest>>(
+                self.client
+                    .post(self.url("v1/download/requests")?)
+                    .json(&payload),
+            )
+            .await?;
+
+        debug!(?requests, "Created hub request");
+
+        requests
+            .into_iter()
+            .next()
+            .map(|x| x.request_uid)
+            .ok_or_else(|| "The hub didn't create the request".to_string())
+    }
+
+    /// Calls `on_progress` with the download progress until the hub is done downloading
+    #[tracing::instrument(skip(self, on_progress))]
+    pub async fn follow_progress<F>(&self, uid: &str, mut on_progress: F) -> Result<(), String>
+    where
+        F: FnMut(ProgressEstimate) + Send,
+    {
+        let res = self
+            .authorized(
+                self.client
+                    .get(self.url(&format!("v1/download/requests/{uid}/events"))?),
+            )
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| format!("Failed to follow the hub request: {e}"))?;
+
+        let mut stream = res.bytes_stream();
+        let mut buffer = vec![];
+        let mut event = None;
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to follow the hub request: {e}"))?;
+            buffer.extend_from_slice(&chunk);
+
+            while let Some(end) = buffer.iter().position(|x| *x == b'\n') {
+                let line = buffer.drain(..=end).collect::<Vec<_>>();
+                let line = String::from_utf8_lossy(&line);
+                let line = line.trim_end();
+
+                if line.is_empty() {
+                    event = None;
+                } else if let Some(name) = line.strip_prefix("event:") {
+                    event = Some(name.trim().to_string());
+                } else if let Some(data) = line.strip_prefix("data:") {
+                    trace!(?event, ?data, "Got hub event");
+
+                    match event.as_deref() {
+                        Some("progress") => {
+                            if let Ok(progress) = serde_json::from_str(data.trim()) {
+                                on_progress(progress);
+                            }
+                        }
+                        Some("done") => return Ok(()),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Waits until the hub is done with the request and downloads the results into the directory.
+    ///
+    /// Returns the downloaded files and the errors of the results that failed.
+    #[tracing::instrument(skip(self))]
+    pub async fn fetch_results(
+        &self,
+        uid: &str,
+        download_dir: &Path,
+    ) -> Result<(Vec<PathBuf>, Vec<String>), String> {
+        let started = Instant::now();
+        let info = loop {
+            let info = self
+                .send::<HubRequestInfo>(
+                    self.client
+                        .get(self.url(&format!("v1/download/requests/{uid}"))?),
+                )
+                .await?;
+
+            if !info.is_processing() {
+                break info;
+            }
+
+            if started.elapsed() > MAX_RESULT_WAIT {
+                return Err("The hub took too long to process the request".to_string());
+            }
+
+            tokio::time::sleep(RESULT_POLL_INTERVAL).await;
+        };
+
+        debug!(?info, "Hub is done with the request");
+
+        let mut paths = vec![];
+        let mut errors = vec![];
+
+        if info.results.is_empty() && info.request.status != HubStatus::Success {
+            errors.push(format!(
+                "The hub couldn't download it ({status:?})",
+                status = info.request.status
+            ));
+        }
+
+        if info.request.status == HubStatus::Partial {
+            let downloaded = info
+                .results
+                .iter()
+                .filter(|x| x.status == HubStatus::Success)
+                .count();
+
+            errors.push(format!(
+                "The hub only got {downloaded} of {total} files",
+                total = info.results.len(),
+            ));
+        }
+
+        for result in info.results {
+            match (result.status, result.download_url) {
+                (HubStatus::Success, Some(url)) => {
+                    match self.download_result(&url, download_dir).await {
+                        Ok(path) => paths.push(path),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                (status, _) => {
+                    errors.push(format!("The hub couldn't process a file ({status:?})"));
+                }
+            }
+        }
+
+        Ok((paths, errors))
+    }
+
+    /// Downloads the file from the signed URL the hub gave out
+    async fn download_result(&self, url: &str, download_dir: &Path) -> Result<PathBuf, String> {
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| format!("Failed to get the file from the hub: {e}"))?;
+
+        // The hub sends the name as `inline; filename="<name>"`
+        let file_name = res
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .and_then(|x| x.to_str().ok())
+            .and_then(|x| x.split_once("filename="))
+            .map(|(_, name)| name.trim_matches('"').to_string())
+            .and_then(|x| Path::new(&x).file_name().map(ToOwned::to_owned))
+            .map_or_else(|| format!("{}.bin", time_thread_id()).into(), PathBuf::from);
+
+        let file_path = download_dir.join(file_name);
+        trace!(?file_path, "Downloading file from hub");
+
+        let mut file = File::create(&file_path)
+            .await
+            .map_err(|e| format!("Error while creating file: {e:?}"))?;
+
+        let mut stream = res.bytes_stream();
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| format!("Failed to get the file from the hub: {e}"))?;
+
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Error while writing file: {e:?}"))?;
+        }
+
+        file.sync_all()
+            .await
+            .map_err(|e| format!("Error while syncing file: {e:?}"))?;
+
+        debug!(path = ?file_path, "Downloaded file from hub");
+
+        Ok(file_path)
+    }
+
+    async fn send<T>(&self, request: RequestBuilder) -> Result<T, String>
+    where
+        T: DeserializeOwned,
+    {
+        let res = self
+            .authorized(request)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach the hub: {e}"))?
+            .json::<HubResponse<T>>()
+            .await
+            .map_err(|e| format!("Invalid response from the hub: {e}"))?;
+
+        match res.body {
+            HubResponseBody::Success(x) => Ok(x),
+            HubResponseBody::Error(e) => Err(format!("The hub returned an error: {e}")),
+            HubResponseBody::Empty => Err("The hub returned an empty response".to_string()),
+        }
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header(
+            header::AUTHORIZATION,
+            format!("Client-Key {}", self.client_key),
+        )
+    }
+
+    fn url(&self, path: &str) -> Result<Url, String> {
+        self.base_url
+            .join(path)
+            .map_err(|e| format!("Invalid hub URL: {e}"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct HubResponse<T> {
+    body: HubResponseBody<T>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "camelCase")]
+enum HubResponseBody<T> {
+    Empty,
+    Success(T),
+    Error(String),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubRequest {
+    request_uid: String,
+    status: HubStatus,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubResult {
+    status: HubStatus,
+    download_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HubRequestInfo {
+    request: HubRequest,
+    results: Vec<HubResult>,
+}
+impl HubRequestInfo {
+    /// Still downloading, or the downloaded files are still being fixed
+    fn is_processing(&self) -> bool {
+        let is_processing = |x: HubStatus| matches!(x, HubStatus::Pending | HubStatus::Processing);
+
+        is_processing(self.request.status) || self.results.iter().any(|x| is_processing(x.status))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+enum HubStatus {
+    Blocked,
+    Failed,
+    /// Only some of the files were downloaded
+    Partial,
+    Pending,
+    Processing,
+    Success,
+}
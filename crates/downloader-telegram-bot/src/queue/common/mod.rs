@@ -1,2 +1,5 @@
+pub mod coalesce;
 pub mod file;
+pub mod file_id_cache;
+pub mod hub;
 pub mod urls;
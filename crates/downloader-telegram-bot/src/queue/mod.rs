@@ -2,18 +2,19 @@ mod common;
 mod processor;
 pub mod task;
 
-use deadqueue::unlimited::Queue;
+use app_queue::{RetryPolicy, TaskQueue as Queue};
 use once_cell::sync::Lazy;
 pub use processor::TaskQueueProcessor;
-pub use task::Task;
-use tracing::trace;
+pub use task::{Task, TaskRequest};
 
-static TASK_QUEUE: Lazy<Queue<Task>> = Lazy::new(Queue::new);
+const MAX_RETRIES: u32 = 5;
+
+static TASK_QUEUE: Lazy<Queue<TaskRequest>> =
+    Lazy::new(|| Queue::new(RetryPolicy::new(MAX_RETRIES)));
 
 pub struct TaskQueue;
 impl TaskQueue {
     pub fn push(task: Task) {
-        trace!(?task, "Pushing task to queue");
         TASK_QUEUE.push(task);
     }
 }
@@ -0,0 +1,103 @@
+use app_actions::post_archive::{archive_post, zip_post_archive};
+use app_helpers::{domain::check_domain_allowed, temp_dir::TempDir};
+use tracing::{debug, info, trace};
+
+use super::{download_request::downloader_options, Handler, HandlerError, HandlerReturn};
+use crate::queue::{
+    common::urls::urls_in_message,
+    task::{Task, TaskInfo},
+};
+
+#[derive(Clone, Debug)]
+pub struct ArchiveRequestHandler;
+
+#[async_trait::async_trait]
+impl Handler for ArchiveRequestHandler {
+    fn name(&self) -> &'static str {
+        "archive-request"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        matches!(task.info(), TaskInfo::ArchiveRequest { .. })
+    }
+
+    async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
+        trace!(?task, "Handling archive request");
+
+        task.update_status_message("Processing the request...")
+            .await;
+
+        let TaskInfo::ArchiveRequest { message: msg } = task.info() else {
+            return Err(HandlerError::Fatal("Invalid task info".to_string()));
+        };
+
+        trace!(?msg, "Got message from task");
+
+        task.add_span_metadata(msg);
+
+        info!(task_id = ?task.id(), "Handling archive request");
+
+        let mut urls = urls_in_message(msg);
+        if let Some(in_reply_to) = msg.reply_to_message() {
+            urls.extend(urls_in_message(in_reply_to));
+        }
+        urls.dedup();
+
+        if urls.is_empty() {
+            task.update_status_message(
+                "This needs to contain a link or be a reply to a message containing a link",
+            )
+            .await;
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        trace!(?urls, "Got urls from message");
+
+        let temp_download_dir =
+            TempDir::in_tmp_with_prefix(format!("downloader-hub.telegram-archive.{}.", task.id()))?;
+
+        task.update_status_message("Archiving posts...").await;
+
+        let options = downloader_options(msg, None);
+
+        let mut paths = vec![];
+        for url in urls {
+            if let Err(e) = check_domain_allowed(&url) {
+                task.send_additional_status_message(&format!("Refusing to archive {url}: {e}"))
+                    .await;
+                continue;
+            }
+
+            let res = match archive_post(&url, temp_download_dir.path(), options.clone()).await {
+                Ok(dir) => zip_post_archive(&dir).await,
+                Err(e) => Err(e),
+            };
+
+            match res {
+                Ok(path) => {
+                    debug!(?path, "Archived post");
+                    paths.push(path);
+                }
+                Err(e) => {
+                    task.send_additional_status_message(&format!(
+                        "Failed to archive {url}:\n\n{e}",
+                    ))
+                    .await;
+                }
+            }
+        }
+
+        if paths.is_empty() {
+            task.update_status_message("Nothing was archived").await;
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        task.update_status_message("Uploading archives...").await;
+
+        task.reply_with_files(paths)
+            .await
+            .map_err(HandlerError::Fatal)?;
+
+        Ok(HandlerReturn::default())
+    }
+}
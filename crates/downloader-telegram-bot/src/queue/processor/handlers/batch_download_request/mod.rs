@@ -0,0 +1,168 @@
+use std::path::Path;
+
+use app_actions::{download_file_with_options, downloaders::DownloaderOptions};
+use app_helpers::{domain::check_domain_allowed, temp_dir::TempDir};
+use teloxide::{types::Message, utils::html};
+use tracing::{debug, info, trace, warn};
+use url::Url;
+
+use super::{
+    download_request::{copy_files_to_save_dir, downloader_options, fix_files, is_from_owner},
+    Handler, HandlerError, HandlerReturn,
+};
+use crate::queue::task::{Task, TaskInfo};
+
+#[derive(Clone, Debug)]
+pub struct BatchDownloadRequestHandler;
+
+#[async_trait::async_trait]
+impl Handler for BatchDownloadRequestHandler {
+    fn name(&self) -> &'static str {
+        "batch-download-request"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        matches!(task.info(), TaskInfo::BatchDownloadRequest { .. })
+    }
+
+    async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
+        trace!(?task, "Handling batch download request");
+
+        let TaskInfo::BatchDownloadRequest { message: msg, urls } = task.info() else {
+            return Err(HandlerError::Fatal("Invalid task info".to_string()));
+        };
+
+        trace!(?msg, "Got message from task");
+
+        task.add_span_metadata(msg);
+
+        info!(task_id = ?task.id(), count = urls.len(), "Handling batch download request");
+
+        let temp_download_dir = TempDir::in_tmp_with_prefix(format!(
+            "downloader-hub.telegram-batch-download.{}.",
+            task.id()
+        ))?;
+        let options = downloader_options(msg, None);
+
+        // The links are done one by one so a single failure doesn't retry the whole batch
+        let mut failed = vec![];
+        let mut partial = vec![];
+        for (i, url) in urls.iter().enumerate() {
+            task.update_status_message(&format!(
+                "Downloading link {current} of {total}...\n{url}\n\nDone: {done}\nPartial: \
+                 {partial}\nFailed: {failed}",
+                current = i + 1,
+                total = urls.len(),
+                url = html::escape(url.as_str()),
+                done = i - failed.len() - partial.len(),
+                partial = partial.len(),
+                failed = failed.len(),
+            ))
+            .await;
+
+            let download_dir = temp_download_dir.path().join(i.to_string());
+            tokio::fs::create_dir_all(&download_dir).await?;
+
+            match download_url(task, msg, url, &download_dir, &options).await {
+                Ok(missing) if missing.is_empty() => {}
+                Ok(missing) => {
+                    warn!(
+                        ?missing,
+                        ?url,
+                        "Only downloaded some files of link from batch"
+                    );
+                    partial.push(format!(
+                        "- {url}: {missing}",
+                        url = html::escape(url.as_str()),
+                        missing = html::escape(&missing.join(", ")),
+                    ));
+                }
+                Err(e) => {
+                    warn!(?e, ?url, "Failed to download link from batch");
+                    failed.push(format!(
+                        "- {url}: {e}",
+                        url = html::escape(url.as_str()),
+                        e = html::escape(&e),
+                    ));
+                }
+            }
+
+            let _ = tokio::fs::remove_dir_all(&download_dir).await;
+        }
+
+        debug!(
+            failed = failed.len(),
+            partial = partial.len(),
+            "Downloaded batch"
+        );
+
+        task.update_status_message(&format!(
+            "Downloaded {done} of {total} links from the list.",
+            done = urls.len() - failed.len(),
+            total = urls.len(),
+        ))
+        .await;
+
+        if !partial.is_empty() {
+            task.send_additional_status_message(&format!(
+                "Only some files were downloaded from these links:\n\n{partial}",
+                partial = partial.join("\n"),
+            ))
+            .await;
+        }
+
+        if !failed.is_empty() {
+            task.send_additional_status_message(&format!(
+                "Failed to download some links:\n\n{failed}",
+                failed = failed.join("\n"),
+            ))
+            .await;
+        }
+
+        Ok(HandlerReturn::default().cleanup_status_message(false))
+    }
+}
+
+/// Downloads, fixes and uploads the media of a single link.
+///
+/// Returns why the files that weren't downloaded failed, if only some of them were.
+async fn download_url(
+    task: &Task,
+    msg: &Message,
+    url: &Url,
+    download_dir: &Path,
+    options: &DownloaderOptions,
+) -> Result<Vec<String>, String> {
+    check_domain_allowed(url).map_err(|e| e.to_string())?;
+
+    let results = download_file_with_options(url, download_dir, options.clone()).await;
+
+    let mut paths = vec![];
+    let mut errors = vec![];
+    for result in results {
+        match result {
+            Ok(x) => paths.push(x.path),
+            Err(e) => errors.push(e.user_message().unwrap_or_else(|| e.to_string())),
+        }
+    }
+
+    if paths.is_empty() {
+        return Err(if errors.is_empty() {
+            "Nothing was downloaded".to_string()
+        } else {
+            errors.join(", ")
+        });
+    }
+
+    let (fixed_paths, _) = fix_files(&paths).await.map_err(|e| e.to_string())?;
+
+    if is_from_owner(msg) {
+        copy_files_to_save_dir(fixed_paths.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+
+    task.reply_with_files(fixed_paths).await?;
+
+    Ok(errors)
+}
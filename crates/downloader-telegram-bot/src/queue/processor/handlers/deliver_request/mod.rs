@@ -0,0 +1,181 @@
+use std::{ffi::OsStr, path::Path};
+
+use app_config::{conditional::delivery_target::DeliveryTarget, Config};
+use app_errors::{AppError, ExternalToolError};
+use app_helpers::temp_dir::TempDir;
+use teloxide::utils::html;
+use tokio::process::Command;
+use tracing::{debug, info, trace, warn};
+
+use super::{
+    download_request::{download_files, fix_files},
+    Handler, HandlerError, HandlerReturn,
+};
+use crate::queue::task::{Task, TaskInfo};
+
+#[derive(Clone, Debug)]
+pub struct DeliverRequestHandler;
+
+#[async_trait::async_trait]
+impl Handler for DeliverRequestHandler {
+    fn name(&self) -> &'static str {
+        "deliver-request"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        matches!(task.info(), TaskInfo::DeliverRequest { .. })
+    }
+
+    async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
+        trace!(?task, "Handling deliver request");
+
+        task.update_status_message("Processing the request...")
+            .await;
+
+        let TaskInfo::DeliverRequest {
+            message: msg,
+            target,
+        } = task.info()
+        else {
+            return Err(HandlerError::Fatal("Invalid task info".to_string()));
+        };
+
+        trace!(?msg, "Got message from task");
+
+        task.add_span_metadata(msg);
+
+        info!(task_id = ?task.id(), target = %target.name, "Handling deliver request");
+
+        let rclone_path = Config::global()
+            .dependency_paths
+            .rclone_path()
+            .ok_or_else(|| {
+                AppError::from(ExternalToolError::unavailable(
+                    "rclone",
+                    "Please make sure it is installed and added to the PATH environment variable",
+                ))
+            })?;
+
+        let temp_download_dir =
+            TempDir::in_tmp_with_prefix(format!("downloader-hub.telegram-deliver.{}.", task.id()))?;
+
+        let mut messages = vec![msg];
+        messages.extend(msg.reply_to_message());
+
+        let downloaded = download_files(temp_download_dir.path(), task, &messages, None).await?;
+
+        trace!(?downloaded, "Downloaded files");
+
+        if downloaded.is_empty() {
+            task.update_status_message(
+                "This needs to contain a link or media or be a reply to a message containing them",
+            )
+            .await;
+
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        task.update_status_message("Fixing files...").await;
+
+        let (mut fixed_file_paths, msg_to_send) = fix_files(&downloaded.to_fix).await?;
+        fixed_file_paths.extend(downloaded.fixed);
+
+        if let Some(msg) = msg_to_send {
+            task.send_additional_status_message(&msg).await;
+        }
+
+        let mut delivered = vec![];
+        for (i, path) in fixed_file_paths.iter().enumerate() {
+            task.update_status_message(&format!(
+                "Uploading file {current} of {total} to {target}...",
+                current = i + 1,
+                total = fixed_file_paths.len(),
+                target = target.name,
+            ))
+            .await;
+
+            let line = match deliver_file(&rclone_path, target, path).await {
+                Ok(x) => x,
+                Err(e) => {
+                    warn!(?e, ?path, "Failed to deliver file");
+                    format!(
+                        "- Failed to upload {name}: {e}",
+                        name = html::escape(&file_name(path)),
+                        e = html::escape(&e.to_string()),
+                    )
+                }
+            };
+
+            delivered.push(line);
+        }
+
+        debug!(?delivered, "Delivered files");
+
+        task.update_status_message(&format!(
+            "Uploaded to {target}:\n\n{files}",
+            target = target.name,
+            files = delivered.join("\n"),
+        ))
+        .await;
+
+        Ok(HandlerReturn::default().cleanup_status_message(false))
+    }
+}
+
+/// Uploads the file to the target and returns the line that describes where it is.
+///
+/// Uses a shareable link if the remote supports them, the path on the remote otherwise.
+async fn deliver_file(
+    rclone_path: &Path,
+    target: &DeliveryTarget,
+    path: &Path,
+) -> Result<String, ExternalToolError> {
+    let name = file_name(path);
+    let remote_path = target.remote_path(&name);
+
+    debug!(?path, ?remote_path, "Uploading file with rclone");
+
+    run_rclone(
+        rclone_path,
+        &["copyto".as_ref(), path.as_os_str(), remote_path.as_ref()],
+    )
+    .await?;
+
+    let line = match run_rclone(rclone_path, &["link".as_ref(), remote_path.as_ref()]).await {
+        Ok(link) => format!(
+            r#"- <a href="{link}">{name}</a>"#,
+            link = html::escape(link.trim()),
+            name = html::escape(&name),
+        ),
+        Err(e) => {
+            debug!(?e, "Remote doesn't support links");
+            format!("- <code>{}</code>", html::escape(&remote_path))
+        }
+    };
+
+    Ok(line)
+}
+
+async fn run_rclone(rclone_path: &Path, args: &[&OsStr]) -> Result<String, ExternalToolError> {
+    let output = Command::new(rclone_path)
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| ExternalToolError::unavailable("rclone", e.to_string()))?;
+
+    if !output.status.success() {
+        return Err(ExternalToolError::failed(
+            "rclone",
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.to_string_lossy().to_string(),
+        |x| x.to_string_lossy().to_string(),
+    )
+}
@@ -0,0 +1,98 @@
+use parking_lot::Mutex;
+use teloxide::utils::html;
+
+/// Every file and link of a post with how far along it is,
+/// shown above the status when there's more than one of them
+#[derive(Debug, Default)]
+pub(super) struct Checklist {
+    items: Mutex<Vec<ChecklistItem>>,
+}
+
+#[derive(Debug)]
+struct ChecklistItem {
+    label: String,
+    state: ItemState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum ItemState {
+    Waiting,
+    Downloading,
+    Done,
+    /// Only some of the files were downloaded
+    Partial,
+    Failed,
+}
+impl ItemState {
+    pub const fn from_counts(downloaded: usize, failed: usize) -> Self {
+        match (downloaded, failed) {
+            (0, _) => Self::Failed,
+            (_, 0) => Self::Done,
+            _ => Self::Partial,
+        }
+    }
+
+    const fn icon(self) -> &'static str {
+        match self {
+            Self::Waiting => "▫️",
+            Self::Downloading => "⏳",
+            Self::Done => "✅",
+            Self::Partial => "⚠️",
+            Self::Failed => "❌",
+        }
+    }
+}
+
+impl Checklist {
+    pub fn new<I>(labels: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let items = labels
+            .into_iter()
+            .map(|label| ChecklistItem {
+                label,
+                state: ItemState::Waiting,
+            })
+            .collect();
+
+        Self {
+            items: Mutex::new(items),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.lock().len()
+    }
+
+    pub fn set(&self, label: &str, state: ItemState) {
+        if let Some(item) = self.items.lock().iter_mut().find(|x| x.label == label) {
+            item.state = state;
+        }
+    }
+
+    /// The status text with the checklist above it
+    pub fn status(&self, text: &str) -> String {
+        let list = {
+            let items = self.items.lock();
+
+            if items.len() < 2 {
+                return text.to_string();
+            }
+
+            items
+                .iter()
+                .map(|x| {
+                    format!(
+                        "{icon} {label}",
+                        icon = x.state.icon(),
+                        label = html::escape(&x.label)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        format!("{list}\n\n{text}")
+    }
+}
@@ -1,19 +1,58 @@
-use std::path::{Path, PathBuf};
+mod checklist;
 
-use app_actions::{download_file, fix_file};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use app_actions::{
+    age_restriction::{allows_age_restricted, ALLOW_AGE_RESTRICTED_OPTION},
+    blocklist::may_block_sha256,
+    content_policy::{allowed_content_types, ALLOWED_CONTENT_TYPES_OPTION},
+    download_file_with_progress,
+    downloaders::{
+        handlers::music::MusicDownloaderOptions, DownloadProgress, DownloaderOptions,
+        ProgressEstimate, ProgressEstimator, ProgressTracker,
+    },
+    extractors::{extract_info, ExtractInfoRequest},
+    fix_file,
+    fixers::{handlers::compress_to_size::COMPRESS_TO_SIZE_OPTION, FixRequest},
+    format_choice::{format_choices, FormatChoice, FORMAT_CHOICE_OPTION},
+};
 use app_config::Config;
-use app_helpers::temp_dir::TempDir;
-use futures::{stream::FuturesUnordered, StreamExt};
-use teloxide::types::Message;
-use tracing::{debug, info, trace};
+use app_helpers::{domain::check_domain_allowed, temp_dir::TempDir};
+use checklist::{Checklist, ItemState};
+use futures::{
+    future::{self, Either},
+    pin_mut,
+    stream::FuturesUnordered,
+    StreamExt,
+};
+use teloxide::{types::Message, utils::html};
+use tokio::sync::watch;
+use tracing::{debug, info, trace, warn};
 use url::Url;
 
 use super::{Handler, HandlerError, HandlerReturn};
-use crate::queue::{
-    common::{file::FileId, urls::urls_in_message},
-    task::{Task, TaskInfo},
+use crate::{
+    bot::helpers::format_choice::add_pending_choice,
+    queue::{
+        common::{
+            coalesce::{add_recent_upload, recent_upload, CoalesceKey},
+            file::{group_sent_media, FileId, SentMedia, MAX_FILE_SIZE_BYTES},
+            file_id_cache,
+            hub::HubClient,
+            urls::urls_in_message,
+        },
+        task::{Task, TaskInfo},
+    },
 };
 
+/// Telegram rate limits message edits, so the progress is shown at most this often
+const PROGRESS_UPDATE_INTERVAL: Duration = Duration::from_secs(3);
+
+const DOWNLOADING_FROM_URLS_STATUS: &str = "Downloading files from URLs...";
+
 #[derive(Debug)]
 pub struct DownloadRequestHandler;
 #[async_trait::async_trait]
@@ -26,13 +65,20 @@ impl Handler for DownloadRequestHandler {
         matches!(task.info(), TaskInfo::DownloadRequest { .. })
     }
 
+    #[allow(clippy::too_many_lines)]
     async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
         trace!(?task, "Handling download request");
 
         task.update_status_message("Processing the request...")
             .await;
 
-        let TaskInfo::DownloadRequest { message: msg } = task.info() else {
+        let TaskInfo::DownloadRequest {
+            message: msg,
+            post_parts,
+            choose_format,
+            format_choice,
+        } = task.info()
+        else {
             return Err(HandlerError::Fatal("Invalid task info".to_string()));
         };
 
@@ -42,17 +88,91 @@ impl Handler for DownloadRequestHandler {
 
         info!(task_id = ?task.id(), "Handling download request");
 
+        if *choose_format {
+            task.update_status_message("Looking for available formats...")
+                .await;
+
+            let choices = available_format_choices(msg).await;
+            debug!(?choices, "Got format choices");
+
+            // The download continues as usual if there's nothing to pick from
+            if choices.len() > 1 {
+                let keyboard = add_pending_choice(msg.clone(), task.status_message(), &choices);
+
+                match task
+                    .status_message()
+                    .update_message_with_keyboard("What should be downloaded?", keyboard)
+                    .await
+                {
+                    Ok(()) => return Ok(HandlerReturn::default().cleanup_status_message(false)),
+                    Err(e) => warn!(?e, "Failed to offer format choices"),
+                }
+            }
+        }
+
+        // The other parts of a post have files, so there's nothing to reuse
+        let coalesce_key = CoalesceKey::for_message(msg, *format_choice, is_from_owner(msg))
+            .filter(|_| post_parts.is_empty());
+        if let Some(key) = &coalesce_key {
+            if let Some(upload) = recent_upload(key).filter(|_| urls_allowed(msg)) {
+                let media = upload.media_groups.iter().flatten().collect::<Vec<_>>();
+                let sha256s = upload.sha256s.as_deref().map(Vec::as_slice);
+
+                if may_reuse_uploads(msg, *format_choice, &media, sha256s).await {
+                    debug!(
+                        ?key,
+                        "Sending the files recently uploaded for the same links"
+                    );
+
+                    match task.reply_with_sent_media(&upload.media_groups).await {
+                        Ok(()) => return Ok(HandlerReturn::default()),
+                        // Downloading it again still works
+                        Err(e) => warn!(?e, "Failed to send recently uploaded files"),
+                    }
+                }
+            } else if let Some(cached) =
+                file_id_cache::cached_request(key).filter(|_| urls_allowed(msg))
+            {
+                let (sha256s, media): (Vec<_>, Vec<_>) = cached.into_iter().unzip();
+
+                if may_reuse_uploads(
+                    msg,
+                    *format_choice,
+                    &media.iter().collect::<Vec<_>>(),
+                    Some(sha256s.as_slice()),
+                )
+                .await
+                {
+                    debug!(
+                        ?key,
+                        "Sending the files Telegram already has for the same links"
+                    );
+
+                    match task.reply_with_sent_media(&group_sent_media(media)).await {
+                        Ok(()) => return Ok(HandlerReturn::default()),
+                        Err(e) => {
+                            warn!(?e, "Failed to send cached files");
+                            file_id_cache::forget_request(key).await;
+                        }
+                    }
+                }
+            }
+        }
+
         let temp_download_dir = TempDir::in_tmp_with_prefix(format!(
             "downloader-hub.telegram-download.{}.",
             task.id()
         ))?;
 
-        debug!("Downloading files");
-        let paths_to_fix = download_files(temp_download_dir.path(), task, msg).await?;
+        let messages = std::iter::once(msg).chain(post_parts).collect::<Vec<_>>();
+
+        debug!(parts = messages.len(), "Downloading files");
+        let downloaded =
+            download_files(temp_download_dir.path(), task, &messages, *format_choice).await?;
         debug!("Downloaded files");
-        trace!(?paths_to_fix, "Downloaded files");
+        trace!(?downloaded, "Downloaded files");
 
-        if paths_to_fix.is_empty() {
+        if downloaded.is_empty() {
             task.update_status_message("No supported URL or file found in message")
                 .await;
 
@@ -61,9 +181,10 @@ impl Handler for DownloadRequestHandler {
 
         task.update_status_message("Fixing files...").await;
 
-        trace!(?paths_to_fix, "Fixing files");
+        trace!(paths_to_fix = ?downloaded.to_fix, "Fixing files");
         debug!("Fixing files");
-        let (fixed_file_paths, msg_to_send) = fix_files(&paths_to_fix).await?;
+        let (mut fixed_file_paths, msg_to_send) = fix_files(&downloaded.to_fix).await?;
+        fixed_file_paths.extend(downloaded.fixed);
 
         if let Some(msg) = msg_to_send {
             task.send_additional_status_message(&msg).await;
@@ -71,21 +192,43 @@ impl Handler for DownloadRequestHandler {
         debug!("Fixed files");
         trace!(?fixed_file_paths, "Fixed files");
 
-        if let Some(owner_id) = Config::global().telegram_bot().owner_id {
-            if msg.from.as_ref().is_some_and(|user| user.id.0 == owner_id) {
-                task.update_status_message("Copying files to download directory...")
-                    .await;
+        if is_from_owner(msg) {
+            task.update_status_message("Copying files to download directory...")
+                .await;
 
-                debug!("Copying files to download directory");
-                copy_files_to_save_dir(fixed_file_paths.clone()).await?;
-                debug!("Copied files to download directory");
-            }
+            debug!("Copying files to download directory");
+            copy_files_to_save_dir(fixed_file_paths.clone()).await?;
+            debug!("Copied files to download directory");
         }
 
-        task.reply_with_files(fixed_file_paths)
+        let file_count = fixed_file_paths.len();
+        let uploaded = task
+            .reply_with_files_and_keep_ids(fixed_file_paths)
             .await
             .map_err(HandlerError::Fatal)?;
 
+        // Only what was sent in full is sent to others, so nothing goes missing for them
+        let uploaded_all = uploaded.iter().map(Vec::len).sum::<usize>() == file_count;
+        if let Some(key) = coalesce_key.filter(|_| uploaded_all) {
+            let hashes = uploaded
+                .iter()
+                .flatten()
+                .map(|x| x.sha256.clone())
+                .collect::<Option<Vec<_>>>();
+            if let Some(hashes) = &hashes {
+                file_id_cache::remember_request(&key, hashes.clone()).await;
+            }
+
+            add_recent_upload(
+                key,
+                uploaded
+                    .into_iter()
+                    .map(|x| x.into_iter().map(|x| x.media).collect())
+                    .collect(),
+                hashes,
+            );
+        }
+
         trace!("Deleting status message");
         let _ = task.status_message().delete_message().await;
         trace!("Status message deleted");
@@ -94,8 +237,95 @@ impl Handler for DownloadRequestHandler {
     }
 }
 
+/// Whether the links in the message may be downloaded at all,
+/// so nothing gets sent for them from the caches if they may not be anymore
+fn urls_allowed(msg: &Message) -> bool {
+    urls_in_message(msg)
+        .iter()
+        .all(|x| check_domain_allowed(x).is_ok())
+}
+
+/// Whether files sent before may be sent for this message without downloading them again.
+///
+/// The chat's content types and the blocklist could have changed since,
+/// so they're checked the same way a download would be.
+async fn may_reuse_uploads(
+    msg: &Message,
+    format_choice: Option<FormatChoice>,
+    media: &[&SentMedia],
+    sha256s: Option<&[String]>,
+) -> bool {
+    if let Some(allowed) = allowed_content_types(&downloader_options(msg, format_choice)) {
+        let all_allowed = media
+            .iter()
+            .all(|x| x.kind.content_type().is_some_and(|x| allowed.contains(&x)));
+
+        if !all_allowed {
+            debug!(
+                ?allowed,
+                "Not all previously sent files are allowed in the chat"
+            );
+            return false;
+        }
+    }
+
+    let blocked = match sha256s {
+        Some(x) => may_block_sha256(x).await,
+        None if Config::global().blocklist.blocklist_file.is_some() => Ok(true),
+        None => Ok(false),
+    };
+
+    match blocked {
+        Ok(false) => true,
+        Ok(true) => {
+            debug!("Previously sent files might be on the blocklist");
+            false
+        }
+        Err(e) => {
+            warn!(
+                ?e,
+                "Failed to check previously sent files against the blocklist"
+            );
+            false
+        }
+    }
+}
+
+/// What the user can pick from for the media linked in the message
+async fn available_format_choices(msg: &Message) -> Vec<FormatChoice> {
+    let requests = urls_in_message(msg)
+        .into_iter()
+        .filter(|x| check_domain_allowed(x).is_ok())
+        .map(ExtractInfoRequest::from)
+        .collect::<Vec<_>>();
+
+    // Failures are reported when the download is tried
+    let infos = future::join_all(requests.iter().map(extract_info)).await;
+
+    let mut choices = vec![];
+    for info in infos.iter().filter_map(|x| x.as_ref().ok()) {
+        for choice in format_choices(info) {
+            if !choices.contains(&choice) {
+                choices.push(choice);
+            }
+        }
+    }
+
+    choices
+}
+
+pub(super) fn is_from_owner(msg: &Message) -> bool {
+    let Some(owner_id) = Config::global().telegram_bot().owner_id else {
+        return false;
+    };
+
+    msg.from.as_ref().is_some_and(|user| user.id.0 == owner_id)
+}
+
 #[tracing::instrument(skip_all)]
-async fn copy_files_to_save_dir(fixed_file_paths: Vec<PathBuf>) -> Result<(), HandlerError> {
+pub(super) async fn copy_files_to_save_dir(
+    fixed_file_paths: Vec<PathBuf>,
+) -> Result<(), HandlerError> {
     let download_dir = match Config::global().telegram_bot().owner_download_dir.as_ref() {
         Some(x) => x,
         None => return Ok(()),
@@ -120,7 +350,7 @@ async fn copy_files_to_save_dir(fixed_file_paths: Vec<PathBuf>) -> Result<(), Ha
 }
 
 #[tracing::instrument(skip_all)]
-async fn fix_files(
+pub(super) async fn fix_files(
     paths_to_fix: &[PathBuf],
 ) -> Result<(Vec<PathBuf>, Option<String>), HandlerError> {
     let mut fixed_file_paths = vec![];
@@ -130,13 +360,16 @@ async fn fix_files(
 
         if !path.exists() {
             return Err(HandlerError::Fatal(format!(
-                "Downloaded file not found: {:?}",
-                path
+                "Downloaded file not found: {}",
+                path.display()
             )));
         }
 
         trace!(?path, "Fixing file");
-        let res = fix_file(path).await;
+        let res = fix_file(
+            FixRequest::new(path).with_option(COMPRESS_TO_SIZE_OPTION, MAX_FILE_SIZE_BYTES),
+        )
+        .await;
         trace!(?res, "Fixed file");
 
         match res {
@@ -163,47 +396,150 @@ async fn fix_files(
     return Ok((fixed_file_paths, msg_text));
 }
 
+/// Files downloaded for a message
+#[derive(Debug, Default)]
+pub(super) struct DownloadedFiles {
+    /// Downloaded here, so they still have to be fixed
+    pub to_fix: Vec<PathBuf>,
+    /// Already fixed by the hub
+    pub fixed: Vec<PathBuf>,
+}
+impl DownloadedFiles {
+    pub const fn is_empty(&self) -> bool {
+        self.to_fix.is_empty() && self.fixed.is_empty()
+    }
+}
+
+/// Downloads the files and links of all the messages of a post
 #[tracing::instrument(skip_all)]
-async fn download_files(
+pub(super) async fn download_files(
     download_dir: &Path,
     task: &Task,
-    msg: &Message,
-) -> Result<Vec<PathBuf>, HandlerError> {
-    let file_id = FileId::from_message(msg);
-    let file_urls = urls_in_message(msg);
+    messages: &[&Message],
+    format_choice: Option<FormatChoice>,
+) -> Result<DownloadedFiles, HandlerError> {
+    let Some(first_msg) = messages.first() else {
+        return Ok(DownloadedFiles::default());
+    };
+
+    let file_ids = messages
+        .iter()
+        .filter_map(|x| FileId::from_message(x))
+        .collect::<Vec<_>>();
+
+    // Forwarded posts can have the same link in the text and in the caption
+    let mut file_urls = Vec::<Url>::new();
+    for url in messages.iter().flat_map(|x| urls_in_message(x)) {
+        if !file_urls.contains(&url) {
+            file_urls.push(url);
+        }
+    }
 
-    if file_id.is_none() && file_urls.is_empty() {
-        return Ok(vec![]);
+    let mut refused = vec![];
+    file_urls.retain(|url| match check_domain_allowed(url) {
+        Ok(()) => true,
+        Err(e) => {
+            refused.push(format!("Refusing to download {url}: {e}"));
+            false
+        }
+    });
+
+    for text in refused {
+        task.send_additional_status_message(&text).await;
     }
 
-    trace!(?file_id, ?file_urls, "Found message parts to process");
+    let mut downloaded = DownloadedFiles::default();
 
-    let mut paths_to_fix = vec![];
+    if file_ids.is_empty() && file_urls.is_empty() {
+        return Ok(downloaded);
+    }
 
-    if let Some(file_id) = file_id {
+    trace!(?file_ids, ?file_urls, "Found message parts to process");
+
+    let file_labels = (1..=file_ids.len())
+        .map(|i| {
+            if file_ids.len() == 1 {
+                "File from Telegram".to_string()
+            } else {
+                format!("File {i} from Telegram")
+            }
+        })
+        .collect::<Vec<_>>();
+    let checklist = Checklist::new(
+        file_labels
+            .iter()
+            .cloned()
+            .chain(file_urls.iter().map(ToString::to_string)),
+    );
+
+    for (file_id, label) in file_ids.iter().zip(&file_labels) {
         debug!(?file_id, "Downloading file from telegram");
-        task.update_status_message("Downloading file from Telegram...")
+        checklist.set(label, ItemState::Downloading);
+        task.update_status_message(&checklist.status("Downloading file from Telegram..."))
             .await;
 
         trace!(?file_id, "Downloading file from telegram");
-        let download_file_path = file_id
-            .download(download_dir)
-            .await
-            .map_err(HandlerError::Fatal)?;
-        trace!(?download_file_path, "Downloaded file from telegram");
+        match file_id.download(download_dir).await {
+            Ok(download_file_path) => {
+                trace!(?download_file_path, "Downloaded file from telegram");
+
+                checklist.set(label, ItemState::Done);
+                downloaded.to_fix.push(download_file_path);
+            }
+            // The rest of the post can still be downloaded
+            Err(e) if checklist.len() > 1 => {
+                warn!(?e, ?file_id, "Failed to download file from telegram");
 
-        paths_to_fix.push(download_file_path);
+                checklist.set(label, ItemState::Failed);
+                task.send_additional_status_message(&format!("Failed to download {label}: {e}"))
+                    .await;
+            }
+            Err(e) => return Err(HandlerError::Fatal(e)),
+        }
     }
 
-    if !file_urls.is_empty() {
+    if let Some(hub) = HubClient::from_config().filter(|_| !file_urls.is_empty()) {
+        debug!(?file_urls, "Handing the URLs off to the hub");
+
+        let (hub_file_paths, hub_errors) = download_files_with_hub(
+            &hub,
+            &file_urls,
+            download_dir,
+            task,
+            &checklist,
+            format_choice,
+        )
+        .await;
+
+        for error in hub_errors {
+            task.send_additional_status_message(&error).await;
+        }
+
+        trace!(?hub_file_paths, "Got files from hub");
+
+        downloaded.fixed.extend(hub_file_paths);
+    } else if !file_urls.is_empty() {
         debug!(?file_urls, "Downloading files from URLs");
-        task.update_status_message("Downloading files from URLs...")
+        for url in &file_urls {
+            checklist.set(url.as_str(), ItemState::Downloading);
+        }
+        task.update_status_message(&checklist.status(DOWNLOADING_FROM_URLS_STATUS))
             .await;
 
         trace!(?file_urls, "Downloading files from URLs");
 
+        let options = downloader_options(first_msg, format_choice);
+        let progress = ProgressTracker::new();
+        let download =
+            download_files_from_urls(&file_urls, download_dir, &options, &progress, &checklist);
+        let show_progress = show_download_progress(task, progress.subscribe(), &checklist);
+        pin_mut!(download, show_progress);
+
         let (downloaded_file_paths, download_errors) =
-            download_files_from_urls(&file_urls, download_dir).await;
+            match future::select(download, show_progress).await {
+                Either::Left((res, _)) => res,
+                Either::Right(((), download)) => download.await,
+            };
 
         for error in download_errors {
             task.send_additional_status_message(&error).await;
@@ -211,21 +547,188 @@ async fn download_files(
 
         trace!(?downloaded_file_paths, "Downloaded files from URLs");
 
-        paths_to_fix.extend(downloaded_file_paths);
+        downloaded.to_fix.extend(downloaded_file_paths);
     }
 
-    Ok(paths_to_fix)
+    Ok(downloaded)
+}
+
+/// Lets the hub download and fix the URLs and fetches the results.
+///
+/// The requests are all sent first so the hub can work on them at the same time.
+#[tracing::instrument(skip_all)]
+async fn download_files_with_hub(
+    hub: &HubClient,
+    file_urls: &[Url],
+    download_dir: &Path,
+    task: &Task,
+    checklist: &Checklist,
+    format_choice: Option<FormatChoice>,
+) -> (Vec<PathBuf>, Vec<String>) {
+    let mut paths = vec![];
+    let mut errors = vec![];
+
+    task.update_status_message(&checklist.status("Sending the links to the hub..."))
+        .await;
+
+    let mut requests = vec![];
+    for url in file_urls {
+        match hub.create_request(url, format_choice).await {
+            Ok(uid) => requests.push((url, uid)),
+            Err(e) => {
+                checklist.set(url.as_str(), ItemState::Failed);
+                errors.push(format!("Failed to download {url}: {e}"));
+            }
+        }
+    }
+
+    for (i, (url, uid)) in requests.iter().enumerate() {
+        checklist.set(url.as_str(), ItemState::Downloading);
+        let status = checklist.status(&format!(
+            "Downloading on the hub ({current}/{total})...\n{url}",
+            current = i + 1,
+            total = requests.len(),
+            url = html::escape(url.as_str()),
+        ));
+        task.update_status_message(&status).await;
+
+        let (progress_tx, progress_rx) = watch::channel(ProgressEstimate::default());
+        let follow = hub.follow_progress(uid, |x| {
+            progress_tx.send_replace(x);
+        });
+        let show_progress = show_hub_progress(task, &status, progress_rx);
+        pin_mut!(follow, show_progress);
+
+        let followed = match future::select(follow, show_progress).await {
+            Either::Left((res, _)) => res,
+            Either::Right(((), follow)) => follow.await,
+        };
+        // The results can still be fetched without the progress
+        if let Err(e) = followed {
+            warn!(?e, ?uid, "Failed to follow hub request");
+        }
+
+        task.update_status_message(&format!("{status}\n\nWaiting for the hub to finish..."))
+            .await;
+
+        match hub.fetch_results(uid, download_dir).await {
+            Ok((x, errs)) => {
+                checklist.set(url.as_str(), ItemState::from_counts(x.len(), errs.len()));
+                paths.extend(x);
+                errors.extend(
+                    errs.into_iter()
+                        .map(|e| format!("Failed to download {url}: {e}")),
+                );
+            }
+            Err(e) => {
+                checklist.set(url.as_str(), ItemState::Failed);
+                errors.push(format!("Failed to download {url}: {e}"));
+            }
+        }
+    }
+
+    (paths, errors)
+}
+
+async fn show_hub_progress(
+    task: &Task,
+    status: &str,
+    mut progress: watch::Receiver<ProgressEstimate>,
+) {
+    while progress.changed().await.is_ok() {
+        let estimate = *progress.borrow_and_update();
+
+        task.update_status_message(&format!("{status}\n\n{estimate}"))
+            .await;
+
+        tokio::time::sleep(PROGRESS_UPDATE_INTERVAL).await;
+    }
+}
+
+/// The options every download requested by the message gets
+pub(super) fn downloader_options(
+    msg: &Message,
+    format_choice: Option<FormatChoice>,
+) -> DownloaderOptions {
+    let mut options = DownloaderOptions::from(MusicDownloaderOptions::from_config());
+    options.insert(
+        ALLOW_AGE_RESTRICTED_OPTION.to_string(),
+        allows_age_restricted(is_from_owner(msg)).into(),
+    );
+    if let Some(choice) = format_choice {
+        options.insert(FORMAT_CHOICE_OPTION.to_string(), choice.into());
+    }
+    if let Some(allowed) = Config::global()
+        .telegram_bot()
+        .content_types_for(msg.chat.id.0)
+    {
+        options.insert(
+            ALLOWED_CONTENT_TYPES_OPTION.to_string(),
+            serde_json::to_value(allowed).unwrap_or_default(),
+        );
+    }
+
+    options
+}
+
+/// Keeps the status message updated with what was found,
+/// the download speed and the time remaining
+async fn show_download_progress(
+    task: &Task,
+    mut progress: watch::Receiver<DownloadProgress>,
+    checklist: &Checklist,
+) {
+    let mut estimator = ProgressEstimator::new();
+    let mut last_text = String::new();
+
+    while progress.changed().await.is_ok() {
+        let (estimate, found) = {
+            let progress = progress.borrow_and_update();
+
+            (estimator.update(&progress), progress.found().to_vec())
+        };
+
+        // So it's clear the right thing was matched before the download is done
+        let status = if found.is_empty() {
+            DOWNLOADING_FROM_URLS_STATUS.to_string()
+        } else {
+            found
+                .iter()
+                .map(|x| format!("{x} — downloading…", x = html::escape(&x.to_string())))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        let text = checklist.status(&format!("{status}\n\n{estimate}"));
+
+        if text != last_text {
+            task.update_status_message(&text).await;
+            last_text = text;
+        }
+
+        tokio::time::sleep(PROGRESS_UPDATE_INTERVAL).await;
+    }
 }
 
 #[tracing::instrument(skip_all, fields(download_dir))]
 async fn download_files_from_urls(
     file_urls: &[Url],
     download_dir: &Path,
+    options: &DownloaderOptions,
+    progress: &ProgressTracker,
+    checklist: &Checklist,
 ) -> (Vec<PathBuf>, Vec<String>) {
     let results = file_urls
         .iter()
         .map(|url| async move {
-            let res = download_file(url, download_dir).await;
+            let res =
+                download_file_with_progress(url, download_dir, options.clone(), Some(progress))
+                    .await;
+
+            let failed = res.iter().filter(|x| x.is_err()).count();
+            checklist.set(
+                url.as_str(),
+                ItemState::from_counts(res.len() - failed, failed),
+            );
 
             (url.to_string(), res)
         })
@@ -242,12 +745,24 @@ async fn download_files_from_urls(
             .collect::<Vec<_>>();
 
         if !errs.is_empty() {
+            let downloaded = url_results.len() - errs.len();
+            let summary = if downloaded > 0 {
+                format!(
+                    "Only downloaded {downloaded} of {total} files from URL: {url}",
+                    total = url_results.len(),
+                )
+            } else {
+                format!("Failed to download file from URL: {url}")
+            };
+
             let text = format!(
-                "Failed to download file from URL: {url}\n\nErrors:\n{errs}",
-                url = url,
+                "{summary}\n\nErrors:\n{errs}",
                 errs = errs
                     .iter()
-                    .map(|x| format!("- {err}", err = x))
+                    .map(|x| format!(
+                        "- {err}",
+                        err = x.user_message().unwrap_or_else(|| x.to_string())
+                    ))
                     .collect::<Vec<_>>()
                     .join("\n"),
             );
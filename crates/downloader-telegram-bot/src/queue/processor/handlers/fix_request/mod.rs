@@ -1,10 +1,12 @@
-use app_actions::fixers::{fix_file_with, FixRequest};
+use app_actions::fixers::{
+    fix_file_with, handlers::compress_to_size::COMPRESS_TO_SIZE_OPTION, FixRequest,
+};
 use app_helpers::temp_dir::TempDir;
 use tracing::{info, trace};
 
 use super::{Handler, HandlerError, HandlerReturn};
 use crate::queue::{
-    common::file::FileId,
+    common::file::{FileId, MAX_FILE_SIZE_BYTES},
     task::{Task, TaskInfo},
 };
 
@@ -71,7 +73,9 @@ impl Handler for FixRequestHandler {
 
         task.update_status_message("Fixing file...").await;
 
-        let fix_result = fix_file_with(fixers.clone(), FixRequest::new(path_to_fix)).await?;
+        let fix_request =
+            FixRequest::new(path_to_fix).with_option(COMPRESS_TO_SIZE_OPTION, MAX_FILE_SIZE_BYTES);
+        let fix_result = fix_file_with(fixers.clone(), fix_request).await?;
 
         task.update_status_message("Uploading fixed file...").await;
 
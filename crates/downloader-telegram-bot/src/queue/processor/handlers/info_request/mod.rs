@@ -0,0 +1,137 @@
+use std::{fmt::Write, time::Duration};
+
+use app_actions::{downloaders::FoundMedia, extract_only, extractors::ExtractionSummary};
+use app_helpers::domain::check_domain_allowed;
+use teloxide::utils::html;
+use tracing::{debug, info, trace};
+
+use super::{download_request::downloader_options, Handler, HandlerError, HandlerReturn};
+use crate::queue::{
+    common::urls::urls_in_message,
+    task::{Task, TaskInfo},
+};
+
+#[derive(Clone, Debug)]
+pub struct InfoRequestHandler;
+
+#[async_trait::async_trait]
+impl Handler for InfoRequestHandler {
+    fn name(&self) -> &'static str {
+        "info-request"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        matches!(task.info(), TaskInfo::InfoRequest { .. })
+    }
+
+    async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
+        trace!(?task, "Handling info request");
+
+        task.update_status_message("Processing the request...")
+            .await;
+
+        let TaskInfo::InfoRequest { message: msg } = task.info() else {
+            return Err(HandlerError::Fatal("Invalid task info".to_string()));
+        };
+
+        trace!(?msg, "Got message from task");
+
+        task.add_span_metadata(msg);
+
+        info!(task_id = ?task.id(), "Handling info request");
+
+        let mut urls = urls_in_message(msg);
+        if let Some(in_reply_to) = msg.reply_to_message() {
+            urls.extend(urls_in_message(in_reply_to));
+        }
+        urls.dedup();
+
+        if urls.is_empty() {
+            task.update_status_message(
+                "This needs to contain a link or be a reply to a message containing a link",
+            )
+            .await;
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        trace!(?urls, "Got urls from message");
+
+        task.update_status_message("Looking up the links...").await;
+
+        let options = downloader_options(msg, None);
+
+        for url in urls {
+            let text = match check_domain_allowed(&url) {
+                Ok(()) => match extract_only(&url, &options).await {
+                    Ok(summary) => summary_text(&summary),
+                    Err(e) => {
+                        debug!(?e, ?url, "Failed to extract info");
+                        format!(
+                            "Failed to get info for {url}:\n\n{e}",
+                            url = html::escape(url.as_str()),
+                            e = html::escape(&e.to_string()),
+                        )
+                    }
+                },
+                Err(e) => format!(
+                    "Refusing to look up {url}: {e}",
+                    url = html::escape(url.as_str()),
+                    e = html::escape(&e.to_string()),
+                ),
+            };
+
+            task.send_additional_status_message(&text).await;
+        }
+
+        Ok(HandlerReturn::default())
+    }
+}
+
+fn summary_text(summary: &ExtractionSummary) -> String {
+    let found = FoundMedia {
+        source_url: Some(summary.url.clone()),
+        title: summary.title.clone(),
+        duration: summary.duration_seconds.map(Duration::from_secs),
+        estimated_size: summary.estimated_size,
+    };
+
+    let mut lines = vec![
+        format!("<b>{}</b>", html::escape(&found.to_string())),
+        format!("Link: {}", html::escape(&summary.url)),
+        format!(
+            "Extractor: <code>{}</code>",
+            html::escape(summary.extractor.as_deref().unwrap_or("unknown"))
+        ),
+    ];
+
+    if summary.age_restricted {
+        lines.push("Age-restricted".to_string());
+    }
+
+    if !summary.formats.is_empty() {
+        lines.push(format!(
+            "Formats: {}",
+            summary
+                .formats
+                .iter()
+                .map(|x| format!("<code>{}</code>", html::escape(x)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(format!("Media ({}):", summary.media.len()));
+    lines.extend(summary.media.iter().map(|x| {
+        let mut line = format!("- {}", html::escape(&x.url));
+        if let Some(downloader) = &x.downloader {
+            let _ = write!(line, " (<code>{}</code>)", html::escape(downloader));
+        }
+        if x.screenshot {
+            line.push_str(" [screenshot]");
+        }
+        line
+    }));
+
+    lines.join("\n")
+}
@@ -1,6 +1,12 @@
 mod action_request;
+mod archive_request;
+mod batch_download_request;
+mod deliver_request;
 mod download_request;
 mod fix_request;
+mod info_request;
+mod subtitles_request;
+mod url_list_request;
 
 use crate::queue::task::Task;
 
@@ -8,6 +14,12 @@ pub static HANDLERS: &[&dyn Handler] = &[
     &download_request::DownloadRequestHandler,
     &fix_request::FixRequestHandler,
     &action_request::ActionRequestHandler,
+    &subtitles_request::SubtitlesRequestHandler,
+    &url_list_request::UrlListRequestHandler,
+    &batch_download_request::BatchDownloadRequestHandler,
+    &deliver_request::DeliverRequestHandler,
+    &info_request::InfoRequestHandler,
+    &archive_request::ArchiveRequestHandler,
 ];
 
 #[async_trait::async_trait]
@@ -49,17 +61,32 @@ pub enum HandlerError {
     FixFailed(#[from] app_actions::fixers::FixerError),
     #[error("Failed to run action: `{0}`")]
     ActionFailed(#[from] app_actions::actions::ActionError),
+    #[error(transparent)]
+    App(#[from] app_errors::AppError),
 }
 impl HandlerError {
     pub const fn is_fatal(&self) -> bool {
-        matches!(self, Self::Fatal(_))
+        match self {
+            Self::Fatal(_) => true,
+            Self::App(x) => !x.is_retryable(),
+            _ => false,
+        }
     }
 
-    pub const fn should_send_as_response(&self) -> bool {
+    pub fn should_send_as_response(&self) -> bool {
         match self {
             Self::ActionFailed(x) if x.should_send_as_response() => true,
             Self::FixFailed(x) if x.should_send_as_response() => true,
+            Self::App(x) if x.user_message().is_some() => true,
             _ => false,
         }
     }
+
+    /// What the user gets told when the error is sent as a response
+    pub fn response_text(&self) -> String {
+        match self {
+            Self::App(x) => x.user_message().unwrap_or_else(|| x.to_string()),
+            _ => self.to_string(),
+        }
+    }
 }
@@ -0,0 +1,102 @@
+use app_actions::actions::{
+    handlers::download_subtitles::DownloadSubtitles, Action, ActionRequest, ActionResultData,
+};
+use app_helpers::temp_dir::TempDir;
+use tracing::{debug, info, trace};
+
+use super::{Handler, HandlerError, HandlerReturn};
+use crate::queue::{
+    common::urls::urls_in_message,
+    task::{Task, TaskInfo},
+};
+
+#[derive(Clone, Debug)]
+pub struct SubtitlesRequestHandler;
+
+#[async_trait::async_trait]
+impl Handler for SubtitlesRequestHandler {
+    fn name(&self) -> &'static str {
+        "subtitles-request"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        matches!(task.info(), TaskInfo::SubtitlesRequest { .. })
+    }
+
+    async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
+        trace!(?task, "Handling subtitles request");
+
+        task.update_status_message("Processing the request...")
+            .await;
+
+        let TaskInfo::SubtitlesRequest { message: msg, lang } = task.info() else {
+            return Err(HandlerError::Fatal("Invalid task info".to_string()));
+        };
+
+        trace!(?msg, "Got message from task");
+
+        task.add_span_metadata(msg);
+
+        info!(task_id = ?task.id(), "Handling subtitles request");
+
+        let mut urls = urls_in_message(msg);
+        if let Some(in_reply_to) = msg.reply_to_message() {
+            urls.extend(urls_in_message(in_reply_to));
+        }
+        urls.dedup();
+
+        if urls.is_empty() {
+            task.update_status_message(
+                "This needs to contain a link or be a reply to a message containing a link",
+            )
+            .await;
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        trace!(?urls, "Got urls from message");
+
+        let temp_download_dir = TempDir::in_tmp_with_prefix(format!(
+            "downloader-hub.telegram-subtitles.{}.",
+            task.id()
+        ))?;
+
+        task.update_status_message("Downloading subtitles...").await;
+
+        let mut paths = vec![];
+        for url in urls {
+            let dir = temp_download_dir.path().to_path_buf();
+            let req = ActionRequest::new(dir.clone(), dir)
+                .with_option("url", url.as_str())
+                .with_option("lang", lang.as_str());
+
+            debug!(?req, "Running subtitles action");
+
+            match DownloadSubtitles.run(&req).await {
+                Ok(res) => {
+                    if let ActionResultData::Paths(x) = res.data {
+                        paths.extend(x);
+                    }
+                }
+                Err(e) => {
+                    task.send_additional_status_message(&format!(
+                        "Failed to get subtitles for {url}:\n\n{e}",
+                    ))
+                    .await;
+                }
+            }
+        }
+
+        if paths.is_empty() {
+            task.update_status_message("No subtitles found").await;
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        task.update_status_message("Uploading subtitles...").await;
+
+        task.reply_with_files(paths)
+            .await
+            .map_err(HandlerError::Fatal)?;
+
+        Ok(HandlerReturn::default())
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt::Write;
+
+use tracing::{debug, info, trace};
+
+use super::{Handler, HandlerError, HandlerReturn};
+use crate::{
+    bot::helpers::url_list::{add_pending_batch, parse_url_list, url_list_document},
+    queue::{
+        common::file::FileId,
+        task::{Task, TaskInfo},
+    },
+};
+
+/// Lists bigger than this are most likely not lists of links
+const MAX_URL_LIST_SIZE_BYTES: u64 = 1024 * 1024;
+
+/// Most links that are downloaded from a single list
+const MAX_URLS: usize = 100;
+
+#[derive(Clone, Debug)]
+pub struct UrlListRequestHandler;
+
+#[async_trait::async_trait]
+impl Handler for UrlListRequestHandler {
+    fn name(&self) -> &'static str {
+        "url-list-request"
+    }
+
+    fn can_handle(&self, task: &Task) -> bool {
+        matches!(task.info(), TaskInfo::UrlListRequest { .. })
+    }
+
+    async fn handle(&self, task: &Task) -> Result<HandlerReturn, HandlerError> {
+        trace!(?task, "Handling URL list request");
+
+        task.update_status_message("Reading the list of links...")
+            .await;
+
+        let TaskInfo::UrlListRequest { message: msg } = task.info() else {
+            return Err(HandlerError::Fatal("Invalid task info".to_string()));
+        };
+
+        trace!(?msg, "Got message from task");
+
+        task.add_span_metadata(msg);
+
+        info!(task_id = ?task.id(), "Handling URL list request");
+
+        let document = url_list_document(msg)
+            .ok_or_else(|| HandlerError::Fatal("Message has no list of links".to_string()))?;
+
+        let contents = FileId::from(document.file.id.clone())
+            .download_to_memory(MAX_URL_LIST_SIZE_BYTES)
+            .await
+            .map_err(HandlerError::Fatal)?;
+
+        let mut urls = parse_url_list(&String::from_utf8_lossy(&contents));
+
+        debug!(count = urls.len(), "Got links from list");
+
+        if urls.is_empty() {
+            task.update_status_message("No links found in the file")
+                .await;
+            return Ok(HandlerReturn::default().cleanup_status_message(false));
+        }
+
+        let mut text = format!(
+            "Found {count} link{s} in the file. Download {them}?",
+            count = urls.len(),
+            s = if urls.len() == 1 { "" } else { "s" },
+            them = if urls.len() == 1 { "it" } else { "them all" },
+        );
+        if urls.len() > MAX_URLS {
+            let _ = write!(text, "\n\nOnly the first {MAX_URLS} will be downloaded.");
+            urls.truncate(MAX_URLS);
+        }
+
+        let keyboard = add_pending_batch(msg.clone(), urls, task.status_message());
+
+        task.status_message()
+            .update_message_with_keyboard(&text, keyboard)
+            .await
+            .map_err(|e| HandlerError::Fatal(e.to_string()))?;
+
+        Ok(HandlerReturn::default().cleanup_status_message(false))
+    }
+}
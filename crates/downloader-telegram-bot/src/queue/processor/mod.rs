@@ -1,14 +1,14 @@
 mod handlers;
 
 use app_config::Config;
+use app_errors::{AppError, UserInputError};
+use app_helpers::temp_dir::with_task_size_limit;
 use handlers::HandlerError;
 use tracing::{debug, error, field, info, info_span, warn, Instrument, Span};
 
 use super::task::Task;
 use crate::queue::TASK_QUEUE;
 
-const MAX_RETRIES: u32 = 5;
-
 pub struct TaskQueueProcessor;
 impl TaskQueueProcessor {
     pub async fn run() {
@@ -87,13 +87,13 @@ async fn handle_task(task: &Task) {
 
     Span::current().record("handler", field::debug(handler.name()));
 
-    let res = handler.handle(task).await;
+    let res = with_task_size_limit(handler.handle(task))
+        .await
+        .unwrap_or_else(|e| Err(AppError::from(UserInputError::TooLarge(e.to_string())).into()));
 
     let err = match res {
         Ok(returned) => {
-            if let Ok(took) = task.time_since_added().to_std() {
-                info!("Task completed after {:?}", took);
-            }
+            TASK_QUEUE.completed(task);
 
             if returned.cleanup_status_message {
                 let _ = task.status_message().delete_message().await;
@@ -107,13 +107,14 @@ async fn handle_task(task: &Task) {
 
     if err.should_send_as_response() {
         debug!(?err, "Got error that should be sent as response");
-        task.update_status_message(&err.to_string()).await;
+        task.update_status_message(&err.response_text()).await;
+        TASK_QUEUE.failed(task);
 
         return;
     }
 
     warn!(?err, "Got error processing task");
-    if let Err(e) = should_retry(task, err) {
+    if let Err(e) = retry(task, err) {
         error!(?e, "Task will not be retried");
 
         let _ = task
@@ -124,23 +125,16 @@ async fn handle_task(task: &Task) {
                 e
             ))
             .await;
-
-        return;
     }
-
-    TASK_QUEUE.push(task.retried());
 }
 
-fn should_retry(task: &Task, err: HandlerError) -> Result<(), HandlerError> {
-    if task.retries() >= MAX_RETRIES {
-        return Err(HandlerError::Fatal(
-            "Too many retries, giving up".to_string(),
-        ));
-    }
-
+fn retry(task: &Task, err: HandlerError) -> Result<(), HandlerError> {
     if err.is_fatal() {
+        TASK_QUEUE.failed(task);
         return Err(err);
     }
 
-    Ok(())
+    TASK_QUEUE
+        .retry(task)
+        .map_err(|e| HandlerError::Fatal(e.to_string()))
 }
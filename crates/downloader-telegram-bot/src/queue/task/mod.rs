@@ -3,16 +3,22 @@ use std::path::PathBuf;
 use app_actions::{
     actions::{handlers::ActionEntry, ActionOptions},
     fixers::handlers::FixerInstance,
+    format_choice::FormatChoice,
 };
+use app_config::conditional::delivery_target::DeliveryTarget;
 use teloxide::{
     prelude::*,
-    types::{Message, ReplyParameters},
+    types::{InputMedia, Message, ReplyParameters},
 };
 use tracing::{debug, field, trace, warn, Span};
+use url::Url;
 
 use crate::{
     bot::{helpers::status_message::StatusMessage, TelegramBot},
-    queue::common::file::{files_to_input_media_groups, MAX_PAYLOAD_SIZE_BYTES},
+    queue::common::{
+        file::{files_to_input_media_groups, SentMedia, UploadedFile, MAX_FILE_SIZE_BYTES},
+        file_id_cache::{cached_file, file_sha256, remember_files},
+    },
 };
 
 #[derive(Clone, Debug)]
@@ -20,6 +26,12 @@ use crate::{
 pub enum TaskInfo {
     DownloadRequest {
         message: Message,
+        /// The rest of the post the message is a part of (eg. the other photos of an album),
+        /// downloaded together with it
+        post_parts: Vec<Message>,
+        /// Let the user pick the format first if there's more than one
+        choose_format: bool,
+        format_choice: Option<FormatChoice>,
     },
     FixRequest {
         message: Message,
@@ -30,27 +42,91 @@ pub enum TaskInfo {
         action: ActionEntry,
         options: ActionOptions,
     },
+    SubtitlesRequest {
+        message: Message,
+        lang: String,
+    },
+    /// A text file with links that should be confirmed before they're downloaded
+    UrlListRequest {
+        message: Message,
+    },
+    BatchDownloadRequest {
+        message: Message,
+        urls: Vec<Url>,
+    },
+    /// Download the media and upload it to cloud storage instead of Telegram
+    DeliverRequest {
+        message: Message,
+        target: DeliveryTarget,
+    },
+    /// Only show what was found for the links, without downloading anything
+    InfoRequest {
+        message: Message,
+    },
+    /// Bundle the whole post (media, screenshot, text and metadata) into a zip
+    ArchiveRequest {
+        message: Message,
+    },
 }
 
+pub type Task = app_queue::Task<TaskRequest>;
+
 #[derive(Clone, Debug)]
-pub struct Task {
-    id: String,
+pub struct TaskRequest {
     info: TaskInfo,
     status_message: StatusMessage,
-    retries: u32,
-    added: chrono::DateTime<chrono::Utc>,
-    last_run: Option<chrono::DateTime<chrono::Utc>>,
 }
-impl Task {
-    pub fn download_request(message: Message, status_message: StatusMessage) -> Self {
-        Self::new(TaskInfo::DownloadRequest { message }, status_message)
+impl TaskRequest {
+    /// A message downloaded together with the rest of its post, eg. the other parts of an album
+    pub fn post_download_request(
+        message: Message,
+        post_parts: Vec<Message>,
+        status_message: StatusMessage,
+    ) -> Task {
+        Self::new(
+            TaskInfo::DownloadRequest {
+                message,
+                post_parts,
+                choose_format: false,
+                format_choice: None,
+            },
+            status_message,
+        )
+    }
+
+    pub fn interactive_download_request(message: Message, status_message: StatusMessage) -> Task {
+        Self::new(
+            TaskInfo::DownloadRequest {
+                message,
+                post_parts: vec![],
+                choose_format: true,
+                format_choice: None,
+            },
+            status_message,
+        )
+    }
+
+    pub fn chosen_download_request(
+        message: Message,
+        format_choice: FormatChoice,
+        status_message: StatusMessage,
+    ) -> Task {
+        Self::new(
+            TaskInfo::DownloadRequest {
+                message,
+                post_parts: vec![],
+                choose_format: false,
+                format_choice: Some(format_choice),
+            },
+            status_message,
+        )
     }
 
     pub fn fix_request(
         message: Message,
         fixers: Vec<FixerInstance>,
         status_message: StatusMessage,
-    ) -> Self {
+    ) -> Task {
         Self::new(TaskInfo::FixRequest { message, fixers }, status_message)
     }
 
@@ -59,7 +135,7 @@ impl Task {
         action: ActionEntry,
         options: ActionOptions,
         status_message: StatusMessage,
-    ) -> Self {
+    ) -> Task {
         Self::new(
             TaskInfo::ActionRequest {
                 message,
@@ -69,31 +145,119 @@ impl Task {
             status_message,
         )
     }
+
+    pub fn subtitles_request(
+        message: Message,
+        lang: String,
+        status_message: StatusMessage,
+    ) -> Task {
+        Self::new(TaskInfo::SubtitlesRequest { message, lang }, status_message)
+    }
+
+    pub fn info_request(message: Message, status_message: StatusMessage) -> Task {
+        Self::new(TaskInfo::InfoRequest { message }, status_message)
+    }
+
+    pub fn archive_request(message: Message, status_message: StatusMessage) -> Task {
+        Self::new(TaskInfo::ArchiveRequest { message }, status_message)
+    }
+
+    pub fn url_list_request(message: Message, status_message: StatusMessage) -> Task {
+        Self::new(TaskInfo::UrlListRequest { message }, status_message)
+    }
+
+    pub fn batch_download_request(
+        message: Message,
+        urls: Vec<Url>,
+        status_message: StatusMessage,
+    ) -> Task {
+        Self::new(
+            TaskInfo::BatchDownloadRequest { message, urls },
+            status_message,
+        )
+    }
+
+    pub fn deliver_request(
+        message: Message,
+        target: DeliveryTarget,
+        status_message: StatusMessage,
+    ) -> Task {
+        Self::new(TaskInfo::DeliverRequest { message, target }, status_message)
+    }
 }
 
-impl Task {
+impl TaskRequest {
     #[tracing::instrument(skip_all)]
     pub async fn reply_with_files(&self, paths: Vec<PathBuf>) -> Result<(), String> {
+        self.reply_with_files_and_keep_ids(paths).await.map(|_| ())
+    }
+
+    /// Same as [`Self::reply_with_files`], but returns what was sent,
+    /// so it can be sent again without uploading anything.
+    ///
+    /// Files Telegram already has are sent by their ID instead of being uploaded.
+    #[tracing::instrument(skip_all)]
+    pub async fn reply_with_files_and_keep_ids(
+        &self,
+        paths: Vec<PathBuf>,
+    ) -> Result<Vec<Vec<UploadedFile>>, String> {
         trace!("Chunking files by size");
         let (media_groups, failed_files) =
-            files_to_input_media_groups(paths, MAX_PAYLOAD_SIZE_BYTES / 10 * 8).await;
+            files_to_input_media_groups(paths, MAX_FILE_SIZE_BYTES).await;
         trace!(?media_groups, ?failed_files, "Chunked files by size");
 
         debug!("Uploading files to Telegram");
+        let mut uploaded = vec![];
         for media_group in media_groups {
-            trace!(?media_group, "Uploading media group");
-
-            TelegramBot::instance()
-                .send_media_group(self.status_message().chat_id(), media_group)
-                .reply_parameters(
-                    ReplyParameters::new(self.status_message().msg_replying_to_id())
-                        .allow_sending_without_reply(),
-                )
-                .send()
-                .await
-                .map_err(|x| x.to_string())?;
-
-            trace!("Uploaded media group");
+            let mut hashes = vec![];
+            let mut to_upload = vec![];
+            let mut to_send = vec![];
+            let mut uses_cached = false;
+            for (path, media) in media_group {
+                let sha256 = file_sha256(&path).await;
+                let cached = sha256.as_deref().and_then(cached_file);
+
+                uses_cached |= cached.is_some();
+                to_send.push(cached.map_or_else(|| media.clone(), |x| x.to_input_media()));
+                to_upload.push(media);
+                hashes.push(sha256);
+            }
+
+            let sent = if uses_cached {
+                match self.send_media_group(to_send).await {
+                    Ok(x) => x,
+                    // The file IDs might not be valid anymore
+                    Err(e) => {
+                        warn!(
+                            ?e,
+                            "Failed to send media group, uploading the files instead"
+                        );
+                        self.send_media_group(to_upload).await?
+                    }
+                }
+            } else {
+                self.send_media_group(to_upload).await?
+            };
+
+            let group = sent
+                .iter()
+                .zip(hashes)
+                .filter_map(|(msg, sha256)| {
+                    Some(UploadedFile {
+                        media: SentMedia::from_message(msg)?,
+                        sha256,
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            remember_files(
+                group
+                    .iter()
+                    .filter_map(|x| Some((x.sha256.clone()?, x.media.clone()))),
+            )
+            .await;
+
+            uploaded.push(group);
         }
         debug!("Uploaded files to Telegram");
 
@@ -126,9 +290,40 @@ impl Task {
             trace!("Failed files message sent");
         }
 
+        Ok(uploaded)
+    }
+
+    /// Sends files Telegram already has as replies
+    pub async fn reply_with_sent_media(
+        &self,
+        media_groups: &[Vec<SentMedia>],
+    ) -> Result<(), String> {
+        for media_group in media_groups {
+            self.send_media_group(media_group.iter().map(SentMedia::to_input_media).collect())
+                .await?;
+        }
+
         Ok(())
     }
 
+    async fn send_media_group(&self, media_group: Vec<InputMedia>) -> Result<Vec<Message>, String> {
+        trace!(?media_group, "Uploading media group");
+
+        let sent = TelegramBot::instance()
+            .send_media_group(self.status_message().chat_id(), media_group)
+            .reply_parameters(
+                ReplyParameters::new(self.status_message().msg_replying_to_id())
+                    .allow_sending_without_reply(),
+            )
+            .send()
+            .await
+            .map_err(|x| x.to_string())?;
+
+        trace!("Uploaded media group");
+
+        Ok(sent)
+    }
+
     #[allow(clippy::unused_self)]
     pub fn add_span_metadata(&self, msg: &Message) {
         if let Some(user) = msg.from.as_ref() {
@@ -142,7 +337,7 @@ impl Task {
     }
 }
 
-impl Task {
+impl TaskRequest {
     pub async fn update_status_message(&self, text: &str) {
         try_and_warn(
             self.status_message().update_message(text),
@@ -160,23 +355,12 @@ impl Task {
     }
 }
 
-impl Task {
-    pub fn new(info: TaskInfo, status_message: StatusMessage) -> Self {
-        let mut id = ulid::Ulid::new().to_string();
-        id.make_ascii_lowercase();
-
-        Self {
-            id,
+impl TaskRequest {
+    pub fn new(info: TaskInfo, status_message: StatusMessage) -> Task {
+        Task::new(Self {
             info,
             status_message,
-            retries: 0,
-            added: chrono::Utc::now(),
-            last_run: None,
-        }
-    }
-
-    pub const fn id(&self) -> &String {
-        &self.id
+        })
     }
 
     pub const fn info(&self) -> &TaskInfo {
@@ -186,24 +370,6 @@ impl Task {
     pub fn status_message(&self) -> StatusMessage {
         self.status_message.clone()
     }
-
-    pub fn with_inc_retries(mut self) -> Self {
-        self.retries += 1;
-        self.last_run = Some(chrono::Utc::now());
-        self
-    }
-
-    pub fn retried(&self) -> Self {
-        self.clone().with_inc_retries()
-    }
-
-    pub const fn retries(&self) -> u32 {
-        self.retries
-    }
-
-    pub fn time_since_added(&self) -> chrono::Duration {
-        chrono::Utc::now().signed_duration_since(self.added)
-    }
 }
 
 async fn try_and_warn<F, T, E, S>(f: F, error_msg: S) -> Option<T>